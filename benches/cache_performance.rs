@@ -118,6 +118,7 @@ fn hybrid_cache_benchmarks(c: &mut Criterion) {
                     promotion_threshold: 0.1,
                     demotion_threshold: Duration::from_secs(300),
                     maintenance_interval: Duration::from_secs(60),
+                    ..Default::default()
                 };
 
                 let cache = HybridCache::new(config).unwrap();
@@ -147,6 +148,7 @@ fn hybrid_cache_benchmarks(c: &mut Criterion) {
                     promotion_threshold: 10.0, // High threshold to prevent promotion
                     demotion_threshold: Duration::from_secs(300),
                     maintenance_interval: Duration::from_secs(60),
+                    ..Default::default()
                 };
 
                 let cache = HybridCache::new(config).unwrap();
@@ -197,6 +199,30 @@ fn concurrent_access_benchmarks(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("memory_cache_hot_key_sampled", |b| {
+        let cache =
+            std::sync::Arc::new(LruMemoryCache::new(10 * 1024 * 1024).with_promotion_sampling(16));
+        let key = "hot_key".to_string();
+        rt.block_on(cache.set(&key, Bytes::from(vec![0u8; 1024])))
+            .unwrap();
+
+        b.iter(|| {
+            rt.block_on(async {
+                let mut handles = Vec::new();
+                for _ in 0..10 {
+                    let cache_clone = cache.clone();
+                    let key = key.clone();
+                    handles.push(tokio::spawn(async move {
+                        black_box(cache_clone.get(&key).await);
+                    }));
+                }
+                for handle in handles {
+                    handle.await.unwrap();
+                }
+            })
+        })
+    });
+
     group.finish();
 }
 
@@ -206,10 +232,11 @@ fn metrics_overhead_benchmarks(c: &mut Criterion) {
     let mut group = c.benchmark_group("metrics_overhead");
 
     group.bench_function("with_metrics", |b| {
+        // One collector for all iterations, as in a long-running service
+        let metrics = MetricsCollector::new(MetricsConfig::default());
         b.iter(|| {
             rt.block_on(async {
                 let cache = LruMemoryCache::new(10 * 1024 * 1024);
-                let metrics = MetricsCollector::new(MetricsConfig::default());
                 let key = "test_key".to_string();
                 let value = Bytes::from(vec![0u8; 1024]);
 
@@ -0,0 +1,36 @@
+//! Generates the gRPC client and server of the `grpc` feature
+//!
+//! The messages are defined by hand in `src/grpc.rs`, mirroring
+//! `proto/zarrs_cache.proto`, so building needs no `protoc`.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    generate_grpc();
+}
+
+#[cfg(feature = "grpc")]
+fn generate_grpc() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route_name: &str, message: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::proto::{}Request", message))
+            .output_type(format!("crate::grpc::proto::{}Response", message))
+            .codec_path("tonic::codec::ProstCodec")
+            .build()
+    };
+    let service = Service::builder()
+        .name("Cache")
+        .package("zarrs_cache")
+        .method(method("get", "Get", "Get"))
+        .method(method("set", "Set", "Set"))
+        .method(method("remove", "Remove", "Remove"))
+        .method(method("clear", "Clear", "Clear"))
+        .method(method("stats", "Stats", "Stats"))
+        .method(method("warm", "Warm", "Warm"))
+        .build();
+    Builder::new().compile(&[service]);
+}
@@ -1,8 +1,11 @@
 use bytes::Bytes;
 use std::collections::HashMap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::Duration;
 use tempfile::TempDir;
-use zarrs_cache::{Cache, HybridCache, HybridCacheConfig, MetricsCollector, MetricsConfig};
+use zarrs_cache::{
+    Cache, CacheTier, HybridCache, HybridCacheConfig, MetricsCollector, MetricsConfig,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -20,10 +23,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         promotion_threshold: 0.5,            // 0.5 accesses per second for promotion
         demotion_threshold: Duration::from_secs(120), // 2 minutes inactivity for demotion
         maintenance_interval: Duration::from_secs(30), // Run maintenance every 30 seconds
+        ..Default::default()
     };
 
     // Create hybrid cache
-    let cache = HybridCache::new(cache_config)?;
+    let cache = Arc::new(HybridCache::new(cache_config)?);
     println!("✓ Created hybrid cache with intelligent memory/disk tiering");
 
     // Configure advanced metrics collection
@@ -32,9 +36,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         snapshot_interval: Duration::from_secs(5),
         track_access_patterns: true,
         track_efficiency: true,
+        max_tracked_keys: 1000,
     };
 
-    let metrics = MetricsCollector::new(metrics_config);
+    let metrics =
+        Arc::new(MetricsCollector::new(metrics_config).with_cache(cache.clone(), CacheTier::Disk));
+    let _snapshotter = metrics.spawn_snapshotter();
     println!("✓ Initialized advanced metrics collector");
 
     println!("✓ Hybrid cache configured with intelligent tiering and metrics collection");
@@ -85,7 +92,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     for key in &hot_keys {
         for _ in 0..3 {
             let start_time = std::time::Instant::now();
-            let result = cache.get(&key.to_string()).await;
+            let result = cache.get(key.as_ref()).await;
             let elapsed = start_time.elapsed();
 
             metrics
@@ -128,7 +135,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     for key in &spatial_keys {
         let start_time = std::time::Instant::now();
-        let result = cache.get(&key.to_string()).await;
+        let result = cache.get(key.as_ref()).await;
         let elapsed = start_time.elapsed();
 
         metrics
@@ -142,21 +149,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 5. Generate performance snapshot
     println!("\n5. Capturing performance metrics...");
 
-    let cache_stats = cache.stats();
-    let performance_snapshot = zarrs_cache::PerformanceSnapshot {
-        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
-        hits: cache_stats.hits,
-        misses: cache_stats.misses,
-        hit_rate: cache_stats.hits as f64 / (cache_stats.hits + cache_stats.misses).max(1) as f64,
-        total_size_bytes: cache_stats.size_bytes,
-        entry_count: cache_stats.entry_count,
-        operations_per_second: 50.0, // Estimated based on our operations
-        average_response_time_ms: 2.5,
-        memory_usage_bytes: cache_stats.size_bytes / 2, // Estimate
-        disk_usage_bytes: cache_stats.size_bytes / 2,   // Estimate
-    };
-
-    metrics.record_snapshot(performance_snapshot).await;
+    metrics.capture_snapshot().await;
 
     // Record some promotion effectiveness
     metrics.record_promotion(true).await; // Effective promotion
@@ -116,6 +116,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             neighbor_chunks: 2,
             max_queue_size: 10,
         }),
+        listing_ttl: Some(Duration::from_secs(10)),
     };
 
     // Create disk cache with all features
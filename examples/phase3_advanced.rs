@@ -5,7 +5,6 @@ use std::time::Duration;
 use tempfile::TempDir;
 use zarrs_cache::{
     Cache, CacheWarmer, HybridCache, HybridCacheConfig, NeighborWarming, PredictiveWarming,
-    WarmingStrategy,
 };
 
 // Mock storage backend with simulated data
@@ -70,6 +69,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         promotion_threshold: 0.5, // Promote after 0.5 accesses per second
         demotion_threshold: Duration::from_secs(5),
         maintenance_interval: Duration::from_millis(500),
+        ..Default::default()
     };
 
     let hybrid_cache = Arc::new(HybridCache::new(hybrid_config)?);
@@ -88,7 +88,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load initial data
     for key in &test_keys {
         if let Some(data) = storage.get(key).await {
-            hybrid_cache.set(&key.to_string(), data).await?;
+            hybrid_cache.set(key, data).await?;
         }
     }
 
@@ -147,16 +147,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         promotion_threshold: 1.0,
         demotion_threshold: Duration::from_secs(30),
         maintenance_interval: Duration::from_secs(10),
+        ..Default::default()
     })?);
 
     // Set up cache warmer with multiple strategies
-    let predictive_strategy = WarmingStrategy::Predictive(
-        PredictiveWarming::new(5, 0.1), // Warm up to 5 keys with 0.1+ frequency
-    );
+    let predictive_strategy = PredictiveWarming::new(5, 0.1); // Warm up to 5 keys with 0.1+ frequency
 
-    let neighbor_strategy = WarmingStrategy::Neighbor(
-        NeighborWarming::new(1, 8), // Warm 1-distance neighbors, up to 8 keys
-    );
+    let neighbor_strategy = NeighborWarming::new(1, 8); // Warm 1-distance neighbors, up to 8 keys
 
     let warmer = CacheWarmer::new(Arc::clone(&warming_cache))
         .add_strategy(predictive_strategy)
@@ -168,7 +165,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🎯 Initial access pattern:");
     for key in &initial_keys {
         if let Some(data) = storage.get(key).await {
-            warming_cache.set(&key.to_string(), data).await?;
+            warming_cache.set(key, data).await?;
             warmer.record_access(key).await;
         }
         println!("   Accessed: {}", key);
@@ -0,0 +1,373 @@
+//! HTTP admin API for live caches
+//!
+//! [`AdminApi`] exposes statistics, tier occupancy, hot keys and analytics
+//! reports of named caches as JSON, along with operations to clear them,
+//! invalidate keys by prefix and trigger warming. Serve it on its own
+//! address, or mount [`AdminApi::router`] into an existing axum application:
+//!
+//! ```rust,no_run
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use std::sync::Arc;
+//! use zarrs_cache::admin::{AdminApi, AdminCache};
+//! use zarrs_cache::{HybridCache, HybridCacheConfig};
+//!
+//! let cache = Arc::new(HybridCache::new(HybridCacheConfig::default())?);
+//! AdminApi::new()
+//!     .with_cache("climate", AdminCache::hybrid(cache))
+//!     .serve("127.0.0.1:9090".parse().unwrap())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! | Route | |
+//! |---|---|
+//! | `GET /caches` | names of the caches |
+//! | `GET /caches/{name}/stats` | hits, misses, sizes and removals |
+//! | `GET /caches/{name}/tiers` | occupancy of each tier of a hybrid cache |
+//! | `GET /caches/{name}/hot-keys?limit=20` | most frequently accessed keys |
+//! | `GET /caches/{name}/report?range=1h` | analytics report of the attached metrics |
+//! | `POST /caches/{name}/clear` | remove every entry |
+//! | `POST /caches/{name}/invalidate?prefix=temp/` | remove the keys starting with `prefix` |
+//! | `POST /caches/{name}/warm` | run the attached warming |
+
+use crate::cache::hybrid::{HybridCache, HybridTierStats};
+use crate::cache::{Cache, CacheStats};
+use crate::error::CacheError;
+use crate::metrics::{CacheAnalyticsReport, MetricsCollector};
+use crate::units;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hot keys listed when the request sets no limit
+const DEFAULT_HOT_KEYS: usize = 20;
+
+/// Time range of reports when the request sets none
+const DEFAULT_REPORT_RANGE: Duration = Duration::from_secs(60 * 60);
+
+type WarmFn = Arc<dyn Fn() -> BoxFuture<'static, Result<usize, CacheError>> + Send + Sync>;
+
+/// A cache managed through the admin API, with what else it can report on
+#[derive(Clone)]
+pub struct AdminCache {
+    cache: Arc<dyn Cache>,
+    hybrid: Option<Arc<HybridCache>>,
+    metrics: Option<Arc<MetricsCollector>>,
+    warm: Option<WarmFn>,
+}
+
+impl AdminCache {
+    /// Any cache; its tiers and hot keys are not reported
+    pub fn new(cache: Arc<dyn Cache>) -> Self {
+        Self {
+            cache,
+            hybrid: None,
+            metrics: None,
+            warm: None,
+        }
+    }
+
+    /// A hybrid cache, reporting the occupancy of its tiers and its hot keys
+    pub fn hybrid(cache: Arc<HybridCache>) -> Self {
+        Self {
+            hybrid: Some(cache.clone()),
+            ..Self::new(cache)
+        }
+    }
+
+    /// Serve analytics reports, and hot keys of caches other than hybrid
+    /// ones, from `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Run `warm` on `POST /caches/{name}/warm`, typically
+    /// [`CacheWarmer::warm`](crate::warming::CacheWarmer::warm) with a
+    /// loader for the origin store
+    pub fn with_warming<F, Fut>(mut self, warm: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<usize, CacheError>> + Send + 'static,
+    {
+        self.warm = Some(Arc::new(move || Box::pin(warm())));
+        self
+    }
+}
+
+/// HTTP+JSON admin API for named caches
+#[derive(Clone, Default)]
+pub struct AdminApi {
+    caches: BTreeMap<String, AdminCache>,
+}
+
+impl AdminApi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Manage `cache` as `name`
+    pub fn with_cache(mut self, name: impl Into<String>, cache: AdminCache) -> Self {
+        self.caches.insert(name.into(), cache);
+        self
+    }
+
+    /// Routes of the API, to serve or nest into another router
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/caches", get(list_caches))
+            .route("/caches/:name/stats", get(stats))
+            .route("/caches/:name/tiers", get(tiers))
+            .route("/caches/:name/hot-keys", get(hot_keys))
+            .route("/caches/:name/report", get(report))
+            .route("/caches/:name/clear", post(clear))
+            .route("/caches/:name/invalidate", post(invalidate))
+            .route("/caches/:name/warm", post(warm))
+            .with_state(Arc::new(self))
+    }
+
+    /// Serve the API on `address` until the task is cancelled
+    pub async fn serve(self, address: SocketAddr) -> Result<(), CacheError> {
+        let listener = tokio::net::TcpListener::bind(address).await?;
+        tracing::info!("Serving admin API on {}", address);
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+
+    fn cache(&self, name: &str) -> Result<&AdminCache, AdminError> {
+        self.caches
+            .get(name)
+            .ok_or_else(|| AdminError::not_found(format!("no cache named {:?}", name)))
+    }
+}
+
+/// Error response of the API: a status and a JSON `{"error": ...}` body
+struct AdminError {
+    status: StatusCode,
+    message: String,
+}
+
+impl AdminError {
+    fn not_found(message: String) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            message,
+        }
+    }
+}
+
+impl From<CacheError> for AdminError {
+    fn from(error: CacheError) -> Self {
+        let status = match error.root() {
+            CacheError::InvalidKey(_) | CacheError::InvalidConfig(_) => StatusCode::BAD_REQUEST,
+            CacheError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+            _ if error.is_transient() => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self {
+            status,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        #[derive(Serialize)]
+        struct Body {
+            error: String,
+        }
+        let body = Json(Body {
+            error: self.message,
+        });
+        (self.status, body).into_response()
+    }
+}
+
+type AdminResult<T> = Result<Json<T>, AdminError>;
+
+/// Statistics of a cache, as reported by `GET /caches/{name}/stats`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    #[serde(flatten)]
+    pub stats: CacheStats,
+    pub hit_rate: f64,
+    /// Maximum size in bytes, if the cache is bounded
+    pub capacity: Option<usize>,
+}
+
+/// A frequently accessed key, as listed by `GET /caches/{name}/hot-keys`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HotKey {
+    pub key: String,
+    pub accesses: u64,
+    /// Recent accesses per second
+    pub frequency: f64,
+}
+
+/// Result of `POST /caches/{name}/invalidate`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvalidateResponse {
+    /// Entries removed, counted once per tier they were removed from
+    pub removed: usize,
+}
+
+/// Result of `POST /caches/{name}/warm`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarmResponse {
+    pub warmed: usize,
+}
+
+#[derive(Deserialize)]
+struct HotKeysQuery {
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct ReportQuery {
+    range: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct InvalidateQuery {
+    prefix: String,
+}
+
+async fn list_caches(State(api): State<Arc<AdminApi>>) -> Json<Vec<String>> {
+    Json(api.caches.keys().cloned().collect())
+}
+
+/// Statistics of `cache`, gathered off the async workers as some caches
+/// wait on their locks for them
+async fn cache_stats(cache: Arc<dyn Cache>) -> Result<CacheStats, AdminError> {
+    tokio::task::spawn_blocking(move || cache.stats())
+        .await
+        .map_err(|e| CacheError::Io(std::io::Error::other(e)).into())
+}
+
+async fn stats(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+) -> AdminResult<StatsResponse> {
+    let cache = api.cache(&name)?.cache.clone();
+    let capacity = cache.capacity();
+    let stats = cache_stats(cache).await?;
+    Ok(Json(StatsResponse {
+        hit_rate: stats.hit_rate(),
+        stats,
+        capacity,
+    }))
+}
+
+async fn tiers(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+) -> AdminResult<HybridTierStats> {
+    let Some(hybrid) = api.cache(&name)?.hybrid.clone() else {
+        return Err(AdminError::not_found(format!("{:?} has no tiers", name)));
+    };
+    let stats = tokio::task::spawn_blocking(move || hybrid.tier_stats())
+        .await
+        .map_err(|e| CacheError::Io(std::io::Error::other(e)))?;
+    Ok(Json(stats))
+}
+
+async fn hot_keys(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+    Query(query): Query<HotKeysQuery>,
+) -> AdminResult<Vec<HotKey>> {
+    let cache = api.cache(&name)?;
+    let limit = query.limit.unwrap_or(DEFAULT_HOT_KEYS);
+    let mut keys: Vec<HotKey> = if let Some(hybrid) = &cache.hybrid {
+        hybrid
+            .access_stats()
+            .await
+            .into_iter()
+            .map(|(key, (accesses, frequency))| HotKey {
+                key,
+                accesses,
+                frequency,
+            })
+            .collect()
+    } else if let Some(metrics) = &cache.metrics {
+        metrics
+            .access_statistics()
+            .await
+            .into_iter()
+            .map(|(key, (accesses, frequency))| HotKey {
+                key,
+                accesses,
+                frequency,
+            })
+            .collect()
+    } else {
+        return Err(AdminError::not_found(format!(
+            "{:?} does not track key accesses",
+            name
+        )));
+    };
+    keys.sort_by(|a, b| b.accesses.cmp(&a.accesses).then_with(|| a.key.cmp(&b.key)));
+    keys.truncate(limit);
+    Ok(Json(keys))
+}
+
+async fn report(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+    Query(query): Query<ReportQuery>,
+) -> AdminResult<CacheAnalyticsReport> {
+    let Some(metrics) = api.cache(&name)?.metrics.clone() else {
+        return Err(AdminError::not_found(format!("{:?} has no metrics", name)));
+    };
+    let range = match query.range {
+        Some(range) => units::parse_duration(&range)?,
+        None => DEFAULT_REPORT_RANGE,
+    };
+    Ok(Json(metrics.generate_report(range).await))
+}
+
+async fn clear(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, AdminError> {
+    api.cache(&name)?.cache.clear().await?;
+    tracing::info!("Cleared cache {} through the admin API", name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn invalidate(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+    Query(query): Query<InvalidateQuery>,
+) -> AdminResult<InvalidateResponse> {
+    let removed = api.cache(&name)?.cache.remove_prefix(&query.prefix).await?;
+    tracing::info!(
+        "Invalidated {} entries of cache {} under {:?} through the admin API",
+        removed,
+        name,
+        query.prefix
+    );
+    Ok(Json(InvalidateResponse { removed }))
+}
+
+async fn warm(
+    State(api): State<Arc<AdminApi>>,
+    Path(name): Path<String>,
+) -> AdminResult<WarmResponse> {
+    let Some(warm) = api.cache(&name)?.warm.clone() else {
+        return Err(AdminError::not_found(format!("{:?} has no warming", name)));
+    };
+    let warmed = warm().await?;
+    Ok(Json(WarmResponse { warmed }))
+}
@@ -0,0 +1,125 @@
+//! Threshold alerts on cache metrics
+//!
+//! Rules are attached to a [`MetricsCollector`] with
+//! [`MetricsCollector::with_alert_rule`] and evaluated every time it captures
+//! a snapshot. Transitions are published as [`AlertEvent`]s to subscribers
+//! of [`MetricsCollector::subscribe_alerts`] and to callbacks registered with
+//! [`MetricsCollector::with_alert_callback`].
+//!
+//! [`MetricsCollector`]: crate::metrics::MetricsCollector
+//! [`MetricsCollector::with_alert_rule`]: crate::metrics::MetricsCollector::with_alert_rule
+//! [`MetricsCollector::subscribe_alerts`]: crate::metrics::MetricsCollector::subscribe_alerts
+//! [`MetricsCollector::with_alert_callback`]: crate::metrics::MetricsCollector::with_alert_callback
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Metric condition an [`AlertRule`] watches
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Hit rate of the operations recorded over the last minute below the
+    /// given share; not evaluated while no operations are recorded
+    HitRateBelow(f64),
+    /// Share of the attached disk tiers' capacity in use above the given
+    /// share; not evaluated without a bounded disk tier
+    DiskUtilizationAbove(f64),
+    /// Share of the operations recorded over the last minute that failed
+    /// above the given share
+    ErrorRateAbove(f64),
+}
+
+impl AlertCondition {
+    /// Whether the observed `value` violates the condition
+    fn violated_by(&self, value: f64) -> bool {
+        match *self {
+            AlertCondition::HitRateBelow(threshold) => value < threshold,
+            AlertCondition::DiskUtilizationAbove(threshold)
+            | AlertCondition::ErrorRateAbove(threshold) => value > threshold,
+        }
+    }
+}
+
+/// A named condition that fires once it has held for `for_duration`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub condition: AlertCondition,
+    /// How long the condition must hold before the alert fires; zero fires
+    /// on the first violating evaluation
+    pub for_duration: Duration,
+}
+
+impl AlertRule {
+    pub fn new(name: impl Into<String>, condition: AlertCondition) -> Self {
+        Self {
+            name: name.into(),
+            condition,
+            for_duration: Duration::ZERO,
+        }
+    }
+
+    /// Fire only once the condition has held for `duration`
+    pub fn for_duration(mut self, duration: Duration) -> Self {
+        self.for_duration = duration;
+        self
+    }
+}
+
+/// Whether an alert started or stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// Transition of an [`AlertRule`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// Name of the rule
+    pub rule: String,
+    pub state: AlertState,
+    pub condition: AlertCondition,
+    /// Observed value of the watched metric
+    pub value: f64,
+    /// Unix time of the evaluation, in seconds
+    pub timestamp: u64,
+}
+
+/// A rule and how long its condition has held
+#[derive(Debug)]
+pub(crate) struct RuleState {
+    pub(crate) rule: AlertRule,
+    violated_since: Option<Instant>,
+    firing: bool,
+}
+
+impl RuleState {
+    pub(crate) fn new(rule: AlertRule) -> Self {
+        Self {
+            rule,
+            violated_since: None,
+            firing: false,
+        }
+    }
+
+    /// Update with the metric's current `value`, if it could be observed,
+    /// returning the state the alert moved to
+    ///
+    /// An unobservable metric keeps the alert as it is.
+    pub(crate) fn observe(&mut self, value: Option<f64>, now: Instant) -> Option<AlertState> {
+        let value = value?;
+        if !self.rule.condition.violated_by(value) {
+            self.violated_since = None;
+            return std::mem::take(&mut self.firing).then_some(AlertState::Resolved);
+        }
+
+        let since = *self.violated_since.get_or_insert(now);
+        if !self.firing && now.duration_since(since) >= self.rule.for_duration {
+            self.firing = true;
+            return Some(AlertState::Firing);
+        }
+        None
+    }
+}
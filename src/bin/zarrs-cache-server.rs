@@ -0,0 +1,47 @@
+//! Standalone cache server
+//!
+//! Serves a hybrid cache over the gRPC API in `proto/zarrs_cache.proto`:
+//!
+//! ```text
+//! zarrs-cache-server [CONFIG_FILE]
+//! ```
+//!
+//! The cache is configured from `CONFIG_FILE`, or the defaults, with
+//! `ZARRS_CACHE_*` environment overrides. The server listens on
+//! `ZARRS_CACHE_LISTEN`, `127.0.0.1:50051` unless set, and flushes the cache
+//! when interrupted.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use zarrs_cache::grpc::CacheService;
+use zarrs_cache::{HybridCache, HybridCacheConfig};
+
+const LISTEN_VAR: &str = "ZARRS_CACHE_LISTEN";
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:50051";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let config = match std::env::args_os().nth(1) {
+        Some(path) => HybridCacheConfig::from_file(path)?,
+        None => HybridCacheConfig::from_env()?,
+    };
+    let address: SocketAddr = std::env::var(LISTEN_VAR)
+        .unwrap_or_else(|_| DEFAULT_LISTEN.to_string())
+        .parse()?;
+    let cache = Arc::new(HybridCache::new(config)?);
+
+    tracing::info!("Serving cache on {}", address);
+    tonic::transport::Server::builder()
+        .add_service(CacheService::new(cache.clone()).into_server())
+        .serve_with_shutdown(address, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    tracing::info!("Shutting down");
+    cache.close().await?;
+    Ok(())
+}
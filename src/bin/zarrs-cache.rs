@@ -0,0 +1,287 @@
+//! Command line tool for disk cache directories
+//!
+//! ```text
+//! zarrs-cache [--layout LAYOUT] <CACHE_DIR> <COMMAND>
+//! ```
+//!
+//! Lists, verifies, clears and invalidates the entries of a disk cache, warms
+//! it from a manifest of keys, and compacts packed caches. Run it while no
+//! other process has the cache open, as neither would see the other's
+//! changes to the index.
+
+use bytes::Bytes;
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use zarrs_cache::{
+    Cache, CacheWarmer, DiskCache, DiskCacheConfig, DiskLayout, ManifestWarming, PackedDiskCache,
+    PackedDiskCacheConfig, WarmingBudget,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "zarrs-cache",
+    version,
+    about = "Inspect and maintain zarrs disk caches"
+)]
+struct Cli {
+    /// Cache directory
+    cache_dir: PathBuf,
+
+    /// Layout the cache was created with: flat, fan_out or hierarchy
+    ///
+    /// Opening a cache with another layout than it was written with moves
+    /// its files into the given one.
+    #[arg(long, default_value = "fan_out", value_parser = parse_layout)]
+    layout: DiskLayout,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List entries with their sizes and ages
+    Ls {
+        /// Only list keys starting with this prefix
+        #[arg(long, default_value = "")]
+        prefix: String,
+    },
+    /// Remove every entry
+    Clear,
+    /// Remove the entries whose keys start with a prefix
+    Invalidate { prefix: String },
+    /// Check every entry file and discard the corrupt ones
+    Verify,
+    /// Rewrite the mostly dead segment files of a packed cache
+    Compact {
+        /// Fraction of dead space, between 0 and 1, from which a segment
+        /// is rewritten
+        #[arg(long, default_value_t = 0.5)]
+        threshold: f64,
+    },
+    /// Load the keys of a manifest from a local copy of the store
+    Warm {
+        /// File of keys to warm, one per line
+        ///
+        /// Lines with `*` or `?` are glob patterns matched against the keys
+        /// of the source; blank lines and lines starting with `#` are
+        /// skipped.
+        #[arg(long)]
+        manifest: PathBuf,
+        /// Directory holding the value of each key at the key's path
+        #[arg(long)]
+        source: PathBuf,
+        /// Most bytes to load, such as `512MiB`
+        #[arg(long, value_parser = parse_size)]
+        max_bytes: Option<u64>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let cli = Cli::parse();
+    if !cli.cache_dir.is_dir() {
+        return Err(format!("{} is not a directory", cli.cache_dir.display()).into());
+    }
+
+    if let Command::Compact { threshold } = cli.command {
+        let config = PackedDiskCacheConfig::builder()
+            .cache_dir(&cli.cache_dir)
+            .compaction_threshold(threshold)
+            .build()?;
+        let cache = PackedDiskCache::with_config(config)?;
+        let segments = cache.segment_count();
+        let reclaimed = cache.compact().await?;
+        println!(
+            "Reclaimed {} from {} segments",
+            format_size(reclaimed),
+            segments
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let config = DiskCacheConfig::builder()
+        .cache_dir(&cli.cache_dir)
+        .layout(cli.layout)
+        .build()?;
+    let cache = Arc::new(DiskCache::with_config(config)?);
+
+    let code = match cli.command {
+        Command::Ls { prefix } => {
+            list(&cache, &prefix).await;
+            ExitCode::SUCCESS
+        }
+        Command::Clear => {
+            let entries = cache.entries().await.len();
+            cache.clear().await?;
+            println!("Removed {} entries", entries);
+            ExitCode::SUCCESS
+        }
+        Command::Invalidate { prefix } => {
+            let removed = cache.remove_prefix(&prefix).await?;
+            println!("Removed {} entries", removed);
+            ExitCode::SUCCESS
+        }
+        Command::Verify => {
+            let report = cache.verify().await;
+            for key in &report.corrupt {
+                println!("corrupt    {}", key);
+            }
+            for key in &report.unreadable {
+                println!("unreadable {}", key);
+            }
+            let discarded = report.corrupt.len() + report.unreadable.len();
+            println!(
+                "Checked {} entries, discarded {}",
+                report.checked, discarded
+            );
+            if discarded == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Command::Warm {
+            manifest,
+            source,
+            max_bytes,
+        } => {
+            let manifest = read_manifest(&manifest, &source)?;
+            let keys = manifest.keys().len();
+            let warmer = CacheWarmer::new(cache.clone())
+                .with_budget(WarmingBudget {
+                    max_bytes: max_bytes.map(|bytes| bytes as usize),
+                    ..Default::default()
+                })
+                .add_strategy(manifest);
+            let warmed = warmer
+                .warm(|key| {
+                    let path = source.join(&key);
+                    async move { tokio::fs::read(path).await.ok().map(Bytes::from) }
+                })
+                .await?;
+            println!("Warmed {} of {} keys", warmed, keys);
+            ExitCode::SUCCESS
+        }
+        Command::Compact { .. } => unreachable!("handled above"),
+    };
+
+    cache.persist_index().await?;
+    Ok(code)
+}
+
+async fn list(cache: &DiskCache, prefix: &str) {
+    let entries: Vec<_> = cache
+        .entries()
+        .await
+        .into_iter()
+        .filter(|entry| entry.key.starts_with(prefix))
+        .collect();
+
+    println!(
+        "{:>10}  {:>8}  {:>8}  {:>8}  KEY",
+        "SIZE", "AGE", "IDLE", "ACCESSES"
+    );
+    for entry in &entries {
+        println!(
+            "{:>10}  {:>8}  {:>8}  {:>8}  {}{}",
+            format_size(entry.size as u64),
+            format_age(entry.age),
+            format_age(entry.idle),
+            entry.access_count,
+            entry.key,
+            if entry.expired { " (expired)" } else { "" }
+        );
+    }
+    let total: u64 = entries.iter().map(|entry| entry.size as u64).sum();
+    println!("{} entries, {}", entries.len(), format_size(total));
+}
+
+/// The keys of the manifest at `path`, with its patterns matched against
+/// the files under `source`
+fn read_manifest(path: &Path, source: &Path) -> Result<ManifestWarming, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let is_pattern = |line: &str| line.contains(['*', '?']);
+    let source_keys = if lines.iter().any(|line| is_pattern(line)) {
+        list_keys(source)?
+    } else {
+        Vec::new()
+    };
+
+    let mut manifest = ManifestWarming::new();
+    for line in lines {
+        if is_pattern(line) {
+            manifest = manifest.with_glob(line, source_keys.iter().cloned());
+        } else {
+            manifest = manifest.with_keys([line]);
+        }
+    }
+    Ok(manifest)
+}
+
+/// Paths of the files under `dir`, relative to it and joined with `/`
+fn list_keys(dir: &Path) -> std::io::Result<Vec<String>> {
+    let mut keys = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let key = format!("{}{}", prefix, name);
+            if entry.file_type()?.is_dir() {
+                pending.push((entry.path(), format!("{}/", key)));
+            } else {
+                keys.push(key);
+            }
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+fn parse_layout(layout: &str) -> Result<DiskLayout, String> {
+    serde_json::from_value(serde_json::Value::String(layout.to_string())).map_err(|_| {
+        format!(
+            "unknown layout {:?}, expected flat, fan_out or hierarchy",
+            layout
+        )
+    })
+}
+
+fn parse_size(size: &str) -> Result<u64, String> {
+    zarrs_cache::units::parse_size(size).map_err(|e| e.to_string())
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86_399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
@@ -0,0 +1,277 @@
+use crate::cache::memory::LruMemoryCache;
+use crate::cache::{Cache, CacheStats, RemovalCause, RemovalCounts, StoreKey};
+use crate::error::CacheError;
+use crate::sketch::FrequencySketch;
+use bytes::Bytes;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Decides whether new entries may enter a cache tier
+pub trait AdmissionPolicy: Send + Sync + 'static {
+    /// Record an access to `key`
+    fn record_access(&self, key: &str);
+
+    /// Whether `candidate` should be admitted to the guarded cache
+    ///
+    /// `victim` is the key the guarded cache would evict to make room for
+    /// it, or `None` if it has room or cannot name one.
+    fn admit(&self, candidate: &str, victim: Option<&str>) -> bool;
+
+    /// Forget all recorded history
+    fn clear(&self) {}
+}
+
+/// Admission policy that accepts every entry
+#[derive(Debug, Default)]
+pub struct AlwaysAdmit;
+
+impl AdmissionPolicy for AlwaysAdmit {
+    fn record_access(&self, _key: &str) {}
+
+    fn admit(&self, _candidate: &str, _victim: Option<&str>) -> bool {
+        true
+    }
+}
+
+/// TinyLFU admission: only admit keys accessed more often than the entries
+/// they would push out
+///
+/// Access frequencies are tracked in a [`FrequencySketch`], so memory use is
+/// constant regardless of how many distinct keys pass through. A candidate is
+/// admitted when its estimated frequency exceeds that of the guarded cache's
+/// eviction victim; while the cache has room, or cannot name a victim, it
+/// must instead have been seen `min_frequency` times. One-touch scan chunks
+/// lose either way and are kept out of the guarded tier.
+///
+/// This is the filter of W-TinyLFU; [`AdmissionCache::w_tiny_lfu`] puts it
+/// behind an LRU window that holds new entries while they build up
+/// frequency.
+///
+/// # Default Values
+/// - `min_frequency`: 2 (key must have been requested before)
+#[derive(Debug)]
+pub struct TinyLfu {
+    sketch: Mutex<FrequencySketch>,
+    min_frequency: u8,
+}
+
+impl TinyLfu {
+    /// Create a filter sized for roughly `expected_keys` distinct keys
+    pub fn new(expected_keys: usize) -> Self {
+        Self::with_min_frequency(expected_keys, 2)
+    }
+
+    pub fn with_min_frequency(expected_keys: usize, min_frequency: u8) -> Self {
+        Self {
+            sketch: Mutex::new(FrequencySketch::new(expected_keys)),
+            min_frequency,
+        }
+    }
+
+    /// Estimated recent access count for `key`
+    pub fn frequency(&self, key: &str) -> u8 {
+        self.sketch().estimate(key)
+    }
+
+    fn sketch(&self) -> MutexGuard<'_, FrequencySketch> {
+        self.sketch.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl AdmissionPolicy for TinyLfu {
+    fn record_access(&self, key: &str) {
+        self.sketch().increment(key);
+    }
+
+    fn admit(&self, candidate: &str, victim: Option<&str>) -> bool {
+        let sketch = self.sketch();
+        let frequency = sketch.estimate(candidate);
+        match victim {
+            Some(victim) => frequency > sketch.estimate(victim),
+            None => frequency >= self.min_frequency,
+        }
+    }
+
+    fn clear(&self) {
+        self.sketch().clear();
+    }
+}
+
+/// Cache wrapper that filters inserts through an [`AdmissionPolicy`]
+///
+/// Each candidate is weighed against the entry the inner cache would evict
+/// for it. With a window configured this behaves like W-TinyLFU: rejected
+/// entries land in a small LRU window instead of being dropped, and are moved
+/// into the main cache once they prove popular enough to pass the filter.
+pub struct AdmissionCache<C: Cache, P: AdmissionPolicy> {
+    inner: C,
+    policy: P,
+    window: Option<LruMemoryCache>,
+}
+
+impl<C: Cache, P: AdmissionPolicy> AdmissionCache<C, P> {
+    pub fn new(inner: C, policy: P) -> Self {
+        Self {
+            inner,
+            policy,
+            window: None,
+        }
+    }
+
+    /// Keep rejected entries in an LRU window of `window_size_bytes`
+    pub fn with_window(mut self, window_size_bytes: usize) -> Self {
+        self.window = Some(LruMemoryCache::new(window_size_bytes));
+        self
+    }
+
+    /// Whether `key` may enter the inner cache with a `value_len`-byte value
+    async fn admit(&self, key: &str, value_len: usize) -> bool {
+        let victim = self.inner.eviction_candidate(key, value_len).await;
+        self.policy.admit(key, victim.as_deref())
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+}
+
+impl<C: Cache> AdmissionCache<C, TinyLfu> {
+    /// W-TinyLFU: `inner` behind a [`TinyLfu`] filter sized for about
+    /// `expected_keys` distinct keys and an LRU window of
+    /// `window_size_bytes`
+    pub fn w_tiny_lfu(inner: C, expected_keys: usize, window_size_bytes: usize) -> Self {
+        Self::new(inner, TinyLfu::new(expected_keys)).with_window(window_size_bytes)
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache, P: AdmissionPolicy> Cache for AdmissionCache<C, P> {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        self.policy.record_access(key);
+
+        if let Some(data) = self.inner.get(key).await {
+            return Some(data);
+        }
+
+        let window = self.window.as_ref()?;
+        let data = window.get(key).await?;
+
+        // Window entry became popular: move it to the main cache
+        if self.admit(key, data.len()).await && self.inner.set(key, data.clone()).await.is_ok() {
+            if let Err(e) = window.remove(key).await {
+                tracing::warn!("Failed to remove admitted key {} from window: {:?}", key, e);
+            }
+        }
+
+        Some(data)
+    }
+
+    /// Stale reads neither count as accesses nor admit window entries
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        if let Some(data) = self.inner.get_stale(key).await {
+            return Some(data);
+        }
+        self.window.as_ref()?.get_stale(key).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let admitted = self.admit(key, value.len()).await;
+        self.policy.record_access(key);
+
+        if admitted {
+            if let Some(window) = &self.window {
+                window.remove(key).await?;
+            }
+            return self.inner.set(key, value).await;
+        }
+
+        // Never leave a stale value behind for a rejected update
+        self.inner.remove(key).await?;
+
+        match &self.window {
+            Some(window) => window.set(key, value).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        if let Some(window) = &self.window {
+            window.remove_with_cause(key, cause).await?;
+        }
+        self.inner.remove_with_cause(key, cause).await
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut removed = 0;
+        if let Some(window) = &self.window {
+            removed += window.remove_prefix(prefix).await?;
+        }
+        Ok(removed + self.inner.remove_prefix(prefix).await?)
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        if let Some(window) = &self.window {
+            window.clear().await?;
+        }
+        self.policy.clear();
+        self.inner.clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.inner.purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    async fn eviction_candidate(&self, key: &str, value_len: usize) -> Option<StoreKey> {
+        self.inner.eviction_candidate(key, value_len).await
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        self.inner.io_operations()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.inner.resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        if let Some(window) = &self.window {
+            window.set_ttl(ttl).await?;
+        }
+        self.inner.set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size() + self.window.as_ref().map_or(0, |w| w.size())
+    }
+
+    fn stats(&self) -> CacheStats {
+        let mut stats = self.inner.stats();
+        if let Some(window) = &self.window {
+            let window_stats = window.stats();
+            // A main-cache miss that hits the window is still a hit overall
+            stats.hits += window_stats.hits;
+            stats.misses = stats.misses.saturating_sub(window_stats.hits);
+            stats.size_bytes += window_stats.size_bytes;
+            stats.entry_count += window_stats.entry_count;
+            stats.accounted_bytes += window_stats.accounted_bytes;
+            // Entries leaving the window for the main cache are not gone
+            stats.removals += RemovalCounts {
+                removed: 0,
+                ..window_stats.removals
+            };
+        }
+        stats
+    }
+}
@@ -0,0 +1,403 @@
+use crate::cache::{
+    entry_footprint, AtomicTtl, Cache, CacheStats, RemovalCause, RemovalTracker, SizeTracker,
+    StoreKey,
+};
+use crate::compression::CompressionStats;
+use crate::error::CacheError;
+use bytes::Bytes;
+use lru::LruCache;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// In-memory cache using the Adaptive Replacement Cache (ARC) policy
+///
+/// ARC splits resident entries into a recency list (T1, seen once) and a
+/// frequency list (T2, seen at least twice), and remembers recently evicted
+/// keys from each in ghost lists (B1, B2). A later insert of a ghost key shifts
+/// the T1 target size towards whichever list would have kept it, so the cache
+/// self-tunes between scan-heavy and hot-spot workloads. Sizes are tracked in
+/// accounted bytes (value plus per-entry overhead) rather than entry counts.
+pub struct ArcMemoryCache {
+    inner: Arc<RwLock<ArcState>>,
+    max_size_bytes: AtomicUsize,
+    sizes: Arc<SizeTracker>,
+    removals: RemovalTracker,
+    stats: Arc<CacheStatsInner>,
+    ttl: AtomicTtl,
+}
+
+struct CacheEntry {
+    data: Bytes,
+    /// Write time, moved by the TTL jitter
+    timestamp: Instant,
+    footprint: usize,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct ArcState {
+    /// Resident entries seen once recently
+    t1: LruCache<StoreKey, CacheEntry>,
+    /// Resident entries seen at least twice recently
+    t2: LruCache<StoreKey, CacheEntry>,
+    /// Ghost keys (with their sizes) recently evicted from T1
+    b1: LruCache<StoreKey, usize>,
+    /// Ghost keys (with their sizes) recently evicted from T2
+    b2: LruCache<StoreKey, usize>,
+    t1_bytes: usize,
+    t2_bytes: usize,
+    b1_bytes: usize,
+    b2_bytes: usize,
+    /// Adaptive target size of T1 in bytes
+    p: usize,
+}
+
+impl ArcState {
+    fn new() -> Self {
+        Self {
+            t1: LruCache::unbounded(),
+            t2: LruCache::unbounded(),
+            b1: LruCache::unbounded(),
+            b2: LruCache::unbounded(),
+            t1_bytes: 0,
+            t2_bytes: 0,
+            b1_bytes: 0,
+            b2_bytes: 0,
+            p: 0,
+        }
+    }
+
+    fn remove_resident(&mut self, key: &str) -> Option<CacheEntry> {
+        if let Some(entry) = self.t1.pop(key) {
+            self.t1_bytes -= entry.footprint;
+            return Some(entry);
+        }
+        if let Some(entry) = self.t2.pop(key) {
+            self.t2_bytes -= entry.footprint;
+            return Some(entry);
+        }
+        None
+    }
+
+    /// Evict one resident entry into its ghost list (ARC `REPLACE`)
+    fn replace(&mut self, hit_in_b2: bool) -> Option<CacheEntry> {
+        let evict_t1 = self.t1_bytes > 0
+            && (self.t1_bytes > self.p
+                || (hit_in_b2 && self.t1_bytes == self.p)
+                || self.t2.is_empty());
+
+        if evict_t1 {
+            let (key, entry) = self.t1.pop_lru()?;
+            self.t1_bytes -= entry.footprint;
+            self.b1.put(key, entry.footprint);
+            self.b1_bytes += entry.footprint;
+            Some(entry)
+        } else {
+            let (key, entry) = self.t2.pop_lru()?;
+            self.t2_bytes -= entry.footprint;
+            self.b2.put(key, entry.footprint);
+            self.b2_bytes += entry.footprint;
+            Some(entry)
+        }
+    }
+
+    /// Keep each ghost list within the cache capacity
+    fn trim_ghosts(&mut self, capacity: usize) {
+        while self.b1_bytes > capacity {
+            match self.b1.pop_lru() {
+                Some((_, size)) => self.b1_bytes -= size,
+                None => break,
+            }
+        }
+        while self.b2_bytes > capacity {
+            match self.b2.pop_lru() {
+                Some((_, size)) => self.b2_bytes -= size,
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl ArcMemoryCache {
+    pub fn new(max_size_bytes: usize) -> Self {
+        Self::with_ttl(max_size_bytes, None)
+    }
+
+    pub fn with_ttl(max_size_bytes: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(ArcState::new())),
+            max_size_bytes: AtomicUsize::new(max_size_bytes),
+            sizes: Arc::new(SizeTracker::default()),
+            removals: RemovalTracker::default(),
+            stats: Arc::new(CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+            ttl: AtomicTtl::new(ttl),
+        }
+    }
+
+    /// Move each entry's expiry by a random amount of up to `jitter` times
+    /// the TTL, earlier or later
+    ///
+    /// Entries written together, as by a warming run, then expire over a
+    /// window instead of all at once. `jitter` is clamped to 0..=1; 0 (the
+    /// default) expires every entry exactly one TTL after it was written.
+    pub fn with_ttl_jitter(mut self, jitter: f64) -> Self {
+        self.ttl = AtomicTtl::new(self.ttl.get()).with_jitter(jitter);
+        self
+    }
+
+    /// Current adaptive target size of the recency list in bytes
+    pub fn recency_target(&self) -> usize {
+        futures::executor::block_on(self.inner.read()).p
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        if let Some(ttl) = self.ttl.get() {
+            entry.timestamp.elapsed() > ttl
+        } else {
+            false
+        }
+    }
+
+    fn make_room(
+        &self,
+        state: &mut ArcState,
+        incoming_size: usize,
+        hit_in_b2: bool,
+    ) -> Result<(), CacheError> {
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
+        while state.t1_bytes + state.t2_bytes + incoming_size > max_size_bytes {
+            match state.replace(hit_in_b2) {
+                Some(evicted) => {
+                    self.sizes.sub(evicted.data.len(), evicted.footprint);
+                    self.removals.record(RemovalCause::Evicted, 1);
+                }
+                None => return Err(CacheError::CacheFull),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for ArcMemoryCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let mut state = self.inner.write().await;
+
+        let entry = if let Some(entry) = state.t1.pop(key) {
+            // Second access: move from recency to frequency list
+            state.t1_bytes -= entry.footprint;
+            entry
+        } else if let Some(entry) = state.t2.pop(key) {
+            state.t2_bytes -= entry.footprint;
+            entry
+        } else {
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        if self.is_expired(&entry) {
+            self.sizes.sub(entry.data.len(), entry.footprint);
+            self.removals.record(RemovalCause::Expired, 1);
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let data = entry.data.clone();
+        state.t2_bytes += entry.footprint;
+        state.t2.put(key.to_string(), entry);
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        Some(data)
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        let data = {
+            let state = self.inner.read().await;
+            state
+                .t1
+                .peek(key)
+                .or_else(|| state.t2.peek(key))
+                .map(|entry| entry.data.clone())
+        };
+        let counter = if data.is_some() {
+            &self.stats.hits
+        } else {
+            &self.stats.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        data
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let value_size = value.len();
+        let footprint = entry_footprint(key, value_size);
+        let mut state = self.inner.write().await;
+        let capacity = self.max_size_bytes.load(Ordering::Relaxed);
+        if footprint > capacity {
+            return Err(CacheError::EntryTooLarge {
+                size: footprint,
+                max: capacity,
+            });
+        }
+
+        let entry = CacheEntry {
+            data: value,
+            timestamp: self.ttl.expiry_base(Instant::now()),
+            footprint,
+        };
+
+        if let Some(old) = state.remove_resident(key) {
+            // Resident update counts as a repeat access
+            self.sizes.sub(old.data.len(), old.footprint);
+            self.make_room(&mut state, footprint, false)?;
+            state.t2_bytes += footprint;
+            state.t2.put(key.to_string(), entry);
+        } else if let Some(ghost_size) = state.b1.pop(key) {
+            // Ghost hit in B1: recency list was too small
+            state.b1_bytes -= ghost_size;
+            let delta = if state.b1_bytes >= state.b2_bytes || state.b1_bytes == 0 {
+                footprint
+            } else {
+                footprint.saturating_mul(state.b2_bytes / state.b1_bytes.max(1))
+            };
+            state.p = (state.p + delta).min(capacity);
+            self.make_room(&mut state, footprint, false)?;
+            state.t2_bytes += footprint;
+            state.t2.put(key.to_string(), entry);
+        } else if let Some(ghost_size) = state.b2.pop(key) {
+            // Ghost hit in B2: frequency list was too small
+            state.b2_bytes -= ghost_size;
+            let delta = if state.b2_bytes >= state.b1_bytes || state.b2_bytes == 0 {
+                footprint
+            } else {
+                footprint.saturating_mul(state.b1_bytes / state.b2_bytes.max(1))
+            };
+            state.p = state.p.saturating_sub(delta);
+            self.make_room(&mut state, footprint, true)?;
+            state.t2_bytes += footprint;
+            state.t2.put(key.to_string(), entry);
+        } else {
+            // Brand new key goes to the recency list
+            self.make_room(&mut state, footprint, false)?;
+            state.t1_bytes += footprint;
+            state.t1.put(key.to_string(), entry);
+        }
+
+        state.trim_ghosts(capacity);
+        self.sizes.add(value_size, footprint);
+
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+
+        if let Some(entry) = state.remove_resident(key) {
+            self.sizes.sub(entry.data.len(), entry.footprint);
+            self.removals.record(cause, 1);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut state = self.inner.write().await;
+        let keys: Vec<StoreKey> = state
+            .t1
+            .iter()
+            .chain(state.t2.iter())
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            if let Some(entry) = state.remove_resident(key) {
+                self.sizes.sub(entry.data.len(), entry.footprint);
+            }
+        }
+        self.removals
+            .record(RemovalCause::Removed, keys.len() as u64);
+        Ok(keys.len())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+        state.clear();
+        self.sizes.reset();
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        let Some(ttl) = self.ttl.get() else {
+            return Ok(0);
+        };
+
+        let mut state = self.inner.write().await;
+        let expired_keys: Vec<StoreKey> = state
+            .t1
+            .iter()
+            .chain(state.t2.iter())
+            .filter(|(_, entry)| entry.timestamp.elapsed() > ttl)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in &expired_keys {
+            if let Some(entry) = state.remove_resident(key) {
+                self.sizes.sub(entry.data.len(), entry.footprint);
+            }
+        }
+        self.removals
+            .record(RemovalCause::Expired, expired_keys.len() as u64);
+
+        Ok(expired_keys.len())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.max_size_bytes.load(Ordering::Relaxed))
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+        self.max_size_bytes.store(max_size_bytes, Ordering::Relaxed);
+        state.p = state.p.min(max_size_bytes);
+        self.make_room(&mut state, 0, false)?;
+        state.trim_ghosts(max_size_bytes);
+        Ok(())
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.ttl.set(ttl);
+        self.purge_expired().await?;
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.sizes.logical()
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes: self.sizes.logical(),
+            entry_count: self.sizes.entries(),
+            accounted_bytes: self.sizes.accounted(),
+            removals: self.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
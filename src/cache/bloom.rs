@@ -0,0 +1,88 @@
+//! Bloom filter of cached keys, answering most misses without an index lookup
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// False positive rate the filter is sized for at its capacity
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// Bloom filter over keys, safe to query and extend concurrently
+///
+/// Keys cannot be removed; the owner rebuilds the filter from its index
+/// once [`KeyFilter::is_saturated`] reports that enough keys were added to
+/// push the false positive rate past its target.
+pub(crate) struct KeyFilter {
+    bits: Vec<AtomicU64>,
+    hashes: u32,
+    capacity: usize,
+    inserted: AtomicUsize,
+}
+
+impl KeyFilter {
+    /// Empty filter sized for `capacity` keys
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let ln2 = std::f64::consts::LN_2;
+        let bit_count = (-(capacity as f64) * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil();
+        let words = (bit_count as usize).div_ceil(64).max(1);
+        let hashes = ((words * 64) as f64 / capacity as f64 * ln2).round() as u32;
+
+        Self {
+            bits: (0..words).map(|_| AtomicU64::new(0)).collect(),
+            hashes: hashes.clamp(1, 16),
+            capacity,
+            inserted: AtomicUsize::new(0),
+        }
+    }
+
+    /// Filter holding the `count` keys in `keys`, sized for at least
+    /// `capacity` keys and twice as many as it starts with
+    pub(crate) fn from_keys<'a>(
+        capacity: usize,
+        count: usize,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        let filter = Self::with_capacity(capacity.max(count.saturating_mul(2)));
+        for key in keys {
+            filter.insert(key);
+        }
+        filter
+    }
+
+    /// Bit positions for `key`, by double hashing
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let first = hasher.finish();
+        // Odd, so the probe sequence does not repeat early
+        let second = first.rotate_left(32).wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+
+        let bit_count = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64)
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % bit_count) as usize)
+    }
+
+    pub(crate) fn insert(&self, key: &str) {
+        for position in self.positions(key) {
+            self.bits[position / 64].fetch_or(1 << (position % 64), Ordering::Relaxed);
+        }
+        self.inserted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// `false` if `key` was never inserted; `true` if it probably was
+    pub(crate) fn might_contain(&self, key: &str) -> bool {
+        self.positions(key).all(|position| {
+            self.bits[position / 64].load(Ordering::Relaxed) & (1 << (position % 64)) != 0
+        })
+    }
+
+    /// Whether more keys were inserted than the filter was sized for
+    pub(crate) fn is_saturated(&self) -> bool {
+        self.inserted.load(Ordering::Relaxed) > self.capacity
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
@@ -0,0 +1,349 @@
+use crate::cache::{
+    entry_footprint, AtomicTtl, Cache, CacheStats, RemovalCause, RemovalTracker, SizeTracker,
+    StoreKey,
+};
+use crate::compression::CompressionStats;
+use crate::error::CacheError;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// In-memory cache using CLOCK (second-chance) eviction
+///
+/// Hits only set a per-entry reference bit under a shared read lock instead of
+/// reordering an LRU list under a write lock, so concurrent readers do not
+/// serialize. Eviction sweeps a clock hand over the slots, clearing reference
+/// bits and evicting the first entry that was not referenced since the last
+/// sweep. This approximates LRU at a fraction of the per-access cost.
+pub struct ClockMemoryCache {
+    inner: Arc<RwLock<ClockState>>,
+    max_size_bytes: AtomicUsize,
+    sizes: Arc<SizeTracker>,
+    removals: RemovalTracker,
+    stats: Arc<CacheStatsInner>,
+    ttl: AtomicTtl,
+}
+
+struct CacheEntry {
+    key: StoreKey,
+    data: Bytes,
+    /// Write time, moved by the TTL jitter
+    timestamp: Instant,
+    footprint: usize,
+    referenced: AtomicBool,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct ClockState {
+    slots: Vec<Option<CacheEntry>>,
+    index: HashMap<StoreKey, usize>,
+    free_slots: Vec<usize>,
+    hand: usize,
+}
+
+impl ClockState {
+    fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            index: HashMap::new(),
+            free_slots: Vec::new(),
+            hand: 0,
+        }
+    }
+
+    fn remove(&mut self, key: &str) -> Option<CacheEntry> {
+        let slot = self.index.remove(key)?;
+        let entry = self.slots[slot].take();
+        self.free_slots.push(slot);
+        entry
+    }
+
+    fn insert(&mut self, entry: CacheEntry) {
+        let key = entry.key.clone();
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(entry);
+                slot
+            }
+            None => {
+                self.slots.push(Some(entry));
+                self.slots.len() - 1
+            }
+        };
+        self.index.insert(key, slot);
+    }
+
+    /// Advance the clock hand until an unreferenced entry is found and evict it
+    fn evict_one(&mut self) -> Option<CacheEntry> {
+        if self.index.is_empty() {
+            return None;
+        }
+
+        // Two full sweeps are always enough: the first clears every bit
+        for _ in 0..self.slots.len() * 2 {
+            let slot = self.hand;
+            self.hand = (self.hand + 1) % self.slots.len();
+
+            let Some(entry) = &self.slots[slot] else {
+                continue;
+            };
+
+            if entry.referenced.swap(false, Ordering::Relaxed) {
+                continue; // Second chance
+            }
+
+            let key = entry.key.clone();
+            return self.remove(&key);
+        }
+
+        None
+    }
+}
+
+impl ClockMemoryCache {
+    pub fn new(max_size_bytes: usize) -> Self {
+        Self::with_ttl(max_size_bytes, None)
+    }
+
+    pub fn with_ttl(max_size_bytes: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(ClockState::new())),
+            max_size_bytes: AtomicUsize::new(max_size_bytes),
+            sizes: Arc::new(SizeTracker::default()),
+            removals: RemovalTracker::default(),
+            stats: Arc::new(CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+            ttl: AtomicTtl::new(ttl),
+        }
+    }
+
+    /// Move each entry's expiry by a random amount of up to `jitter` times
+    /// the TTL, earlier or later
+    ///
+    /// Entries written together, as by a warming run, then expire over a
+    /// window instead of all at once. `jitter` is clamped to 0..=1; 0 (the
+    /// default) expires every entry exactly one TTL after it was written.
+    pub fn with_ttl_jitter(mut self, jitter: f64) -> Self {
+        self.ttl = AtomicTtl::new(self.ttl.get()).with_jitter(jitter);
+        self
+    }
+
+    fn evict_if_needed(
+        &self,
+        state: &mut ClockState,
+        incoming_footprint: usize,
+    ) -> Result<(), CacheError> {
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
+        while self.sizes.accounted() + incoming_footprint > max_size_bytes {
+            match state.evict_one() {
+                Some(entry) => {
+                    self.sizes.sub(entry.data.len(), entry.footprint);
+                    self.removals.record(RemovalCause::Evicted, 1);
+                }
+                None => return Err(CacheError::CacheFull),
+            }
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        if let Some(ttl) = self.ttl.get() {
+            entry.timestamp.elapsed() > ttl
+        } else {
+            false
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for ClockMemoryCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        {
+            let state = self.inner.read().await;
+            let Some(&slot) = state.index.get(key) else {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+
+            if let Some(entry) = &state.slots[slot] {
+                if !self.is_expired(entry) {
+                    entry.referenced.store(true, Ordering::Relaxed);
+                    self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(entry.data.clone());
+                }
+            }
+        }
+
+        // Expired: upgrade to a write lock to drop the entry
+        let mut state = self.inner.write().await;
+        if let Some(&slot) = state.index.get(key) {
+            let expired = state.slots[slot]
+                .as_ref()
+                .is_some_and(|entry| self.is_expired(entry));
+            if expired {
+                if let Some(entry) = state.remove(key) {
+                    self.sizes.sub(entry.data.len(), entry.footprint);
+                    self.removals.record(RemovalCause::Expired, 1);
+                }
+            }
+        }
+        self.stats.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        let data = {
+            let state = self.inner.read().await;
+            state
+                .index
+                .get(key)
+                .and_then(|&slot| state.slots[slot].as_ref())
+                .map(|entry| entry.data.clone())
+        };
+        let counter = if data.is_some() {
+            &self.stats.hits
+        } else {
+            &self.stats.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        data
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let value_size = value.len();
+        let footprint = entry_footprint(key, value_size);
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
+        if footprint > max_size_bytes {
+            // Checked before touching the entry it would replace, which
+            // stays cached
+            return Err(CacheError::EntryTooLarge {
+                size: footprint,
+                max: max_size_bytes,
+            });
+        }
+        let mut state = self.inner.write().await;
+
+        if let Some(old) = state.remove(key) {
+            self.sizes.sub(old.data.len(), old.footprint);
+        }
+
+        self.evict_if_needed(&mut state, footprint)?;
+
+        state.insert(CacheEntry {
+            key: key.to_string(),
+            data: value,
+            timestamp: self.ttl.expiry_base(Instant::now()),
+            footprint,
+            referenced: AtomicBool::new(false),
+        });
+        self.sizes.add(value_size, footprint);
+
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+
+        if let Some(entry) = state.remove(key) {
+            self.sizes.sub(entry.data.len(), entry.footprint);
+            self.removals.record(cause, 1);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut state = self.inner.write().await;
+        let keys: Vec<StoreKey> = state
+            .index
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in &keys {
+            if let Some(entry) = state.remove(key) {
+                self.sizes.sub(entry.data.len(), entry.footprint);
+            }
+        }
+        self.removals
+            .record(RemovalCause::Removed, keys.len() as u64);
+        Ok(keys.len())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+        *state = ClockState::new();
+        self.sizes.reset();
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        let Some(ttl) = self.ttl.get() else {
+            return Ok(0);
+        };
+
+        let mut state = self.inner.write().await;
+        let expired_keys: Vec<StoreKey> = state
+            .slots
+            .iter()
+            .flatten()
+            .filter(|entry| entry.timestamp.elapsed() > ttl)
+            .map(|entry| entry.key.clone())
+            .collect();
+
+        for key in &expired_keys {
+            if let Some(entry) = state.remove(key) {
+                self.sizes.sub(entry.data.len(), entry.footprint);
+            }
+        }
+        self.removals
+            .record(RemovalCause::Expired, expired_keys.len() as u64);
+
+        Ok(expired_keys.len())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.max_size_bytes.load(Ordering::Relaxed))
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        let mut state = self.inner.write().await;
+        self.max_size_bytes.store(max_size_bytes, Ordering::Relaxed);
+        self.evict_if_needed(&mut state, 0)
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.ttl.set(ttl);
+        self.purge_expired().await?;
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.sizes.logical()
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes: self.sizes.logical(),
+            entry_count: self.sizes.entries(),
+            accounted_bytes: self.sizes.accounted(),
+            removals: self.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
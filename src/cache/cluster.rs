@@ -0,0 +1,814 @@
+use crate::cache::{stable_hash, Cache, CacheStats, RemovalCause};
+use crate::error::{CacheError, ErrorContext, Tier};
+use crate::events::CacheEventKind;
+use crate::units::{HumanDuration, SetterError};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Configuration for a cluster cache
+///
+/// # Default Values
+/// - `virtual_nodes`: 128
+/// - `request_timeout`: 500 milliseconds
+/// - `retry_after`: 10 seconds
+#[derive(Debug, Clone)]
+pub struct ClusterConfig {
+    /// Points each node takes on the hash ring; more spread keys more evenly
+    pub virtual_nodes: usize,
+    /// Longest to wait for a peer before treating the request as failed
+    pub request_timeout: Duration,
+    /// Time a peer that timed out or failed is skipped before it is tried
+    /// again
+    pub retry_after: Duration,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            virtual_nodes: 128,
+            request_timeout: Duration::from_millis(500),
+            retry_after: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ClusterConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> ClusterConfigBuilder {
+        ClusterConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.virtual_nodes == 0 {
+            return invalid("virtual_nodes must be greater than zero");
+        }
+        if self.request_timeout.is_zero() {
+            return invalid("request_timeout must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`ClusterConfig`], starting from its defaults
+///
+/// [`build`](ClusterConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterConfigBuilder {
+    config: ClusterConfig,
+    invalid: SetterError,
+}
+
+impl ClusterConfigBuilder {
+    pub fn virtual_nodes(mut self, nodes: usize) -> Self {
+        self.config.virtual_nodes = nodes;
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(timeout.to_duration()) {
+            self.config.request_timeout = duration;
+        }
+        self
+    }
+
+    pub fn retry_after(mut self, delay: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(delay.to_duration()) {
+            self.config.retry_after = duration;
+        }
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<ClusterConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Consistent-hash ring assigning keys to nodes
+///
+/// Each node takes `virtual_nodes` points on the ring, and a key belongs to
+/// the node of the first point at or after its hash, so adding or removing
+/// a node only moves that node's keys. Workers building rings from the same
+/// node ids and virtual node count agree on every key's owner.
+#[derive(Debug, Clone, Default)]
+pub struct HashRing {
+    nodes: Vec<String>,
+    /// Hash and index into `nodes` of every point, sorted by hash
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    pub fn new<I, N>(nodes: I, virtual_nodes: usize) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        let mut nodes: Vec<String> = nodes.into_iter().map(Into::into).collect();
+        nodes.sort();
+        nodes.dedup();
+
+        let mut points: Vec<(u64, usize)> = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, node)| {
+                (0..virtual_nodes)
+                    .map(move |point| (stable_hash(&format!("{}#{}", node, point)), index))
+            })
+            .collect();
+        points.sort_unstable();
+        Self { nodes, points }
+    }
+
+    /// Node owning `key`, or `None` for an empty ring
+    pub fn owner(&self, key: &str) -> Option<&str> {
+        let hash = stable_hash(key);
+        let point = self.points.partition_point(|&(point, _)| point < hash);
+        let (_, index) = self.points.get(point).or_else(|| self.points.first())?;
+        Some(&self.nodes[*index])
+    }
+
+    /// Up to `count` distinct nodes for `key`, its owner first, then the
+    /// next nodes clockwise
+    ///
+    /// These are where `count` replicas of a key go; removing a node only
+    /// moves its replicas to the next node along.
+    pub fn owners(&self, key: &str, count: usize) -> Vec<&str> {
+        let count = count.min(self.nodes.len());
+        let hash = stable_hash(key);
+        let start = self.points.partition_point(|&(point, _)| point < hash);
+        let mut owners: Vec<&str> = Vec::with_capacity(count);
+        for &(_, index) in self.points[start..].iter().chain(&self.points[..start]) {
+            if owners.len() == count {
+                break;
+            }
+            let node = self.nodes[index].as_str();
+            if !owners.contains(&node) {
+                owners.push(node);
+            }
+        }
+        owners
+    }
+
+    /// Nodes on the ring, sorted
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+}
+
+/// Peers and the ring over them and this node
+struct Members {
+    peers: HashMap<String, Arc<dyn Cache>>,
+    ring: HashRing,
+}
+
+/// Where a key's requests go
+enum Route {
+    Local,
+    Peer(String, Arc<dyn Cache>),
+}
+
+/// Cache spread over the workers of a cluster
+///
+/// Every key belongs to one node, chosen by a [`HashRing`] over this node
+/// and its peers, and is read from and written to that node only. Each
+/// worker keeps its share of the keys in a `local` cache and serves it to
+/// the others, for example through a `grpc::CacheService` reached with
+/// `ClusterCache::with_grpc_peer` (`grpc` feature). Used as the remote tier of
+/// a [`HybridCache`](crate::HybridCache), a worker asks the owning peer for
+/// a chunk before going to the origin store, so the memory and disk of the
+/// whole cluster act as one cache for a cooperative job.
+///
+/// Peers that time out or fail with a retryable error are skipped for the
+/// configured `retry_after`: their keys miss and writes to them fail, rather
+/// than every request waiting for them. Removals by prefix reach every
+/// node, while [`Cache::clear`] and the other maintenance operations only
+/// affect the local share, so one worker restarting clean does not empty
+/// the others. Hits and misses count this worker's requests.
+pub struct ClusterCache {
+    node_id: String,
+    config: ClusterConfig,
+    local: Arc<dyn Cache>,
+    members: RwLock<Members>,
+    /// Peers skipped until the given time
+    unavailable: Mutex<HashMap<String, Instant>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    peer_failures: AtomicU64,
+}
+
+impl ClusterCache {
+    /// A cluster of just this node, named `node_id`, which keeps its share
+    /// of the keys in `local`
+    pub fn new(node_id: impl Into<String>, local: Arc<dyn Cache>) -> Self {
+        Self::build(node_id.into(), local, ClusterConfig::default())
+    }
+
+    pub fn with_config(
+        node_id: impl Into<String>,
+        local: Arc<dyn Cache>,
+        config: ClusterConfig,
+    ) -> Result<Self, CacheError> {
+        config.validate()?;
+        Ok(Self::build(node_id.into(), local, config))
+    }
+
+    fn build(node_id: String, local: Arc<dyn Cache>, config: ClusterConfig) -> Self {
+        let ring = HashRing::new([node_id.clone()], config.virtual_nodes);
+        Self {
+            node_id,
+            config,
+            local,
+            members: RwLock::new(Members {
+                peers: HashMap::new(),
+                ring,
+            }),
+            unavailable: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            peer_failures: AtomicU64::new(0),
+        }
+    }
+
+    /// Add the peer `node_id`, reached through `cache`
+    pub fn with_peer(self, node_id: impl Into<String>, cache: Arc<dyn Cache>) -> Self {
+        self.add_peer(node_id, cache);
+        self
+    }
+
+    /// Add the peer `node_id`, served by a
+    /// [`CacheService`](crate::grpc::CacheService) at `endpoint`, e.g.
+    /// `http://10.0.0.2:50051`
+    ///
+    /// The connection is made on first use, so peers may start in any
+    /// order.
+    #[cfg(feature = "grpc")]
+    pub fn with_grpc_peer(
+        self,
+        node_id: impl Into<String>,
+        endpoint: impl Into<String>,
+    ) -> Result<Self, CacheError> {
+        let endpoint = endpoint.into();
+        let channel = tonic::transport::Endpoint::from_shared(endpoint.clone())
+            .map_err(|e| {
+                CacheError::InvalidConfig(format!("invalid peer endpoint {:?}: {}", endpoint, e))
+            })?
+            .timeout(self.config.request_timeout)
+            .connect_lazy();
+        let peer = Arc::new(crate::grpc::RemoteCache::with_channel(channel));
+        Ok(self.with_peer(node_id, peer))
+    }
+
+    /// Add or replace the peer `node_id`, moving its keys to it
+    ///
+    /// Adding this node's own id is ignored.
+    pub fn add_peer(&self, node_id: impl Into<String>, cache: Arc<dyn Cache>) {
+        let node_id = node_id.into();
+        if node_id == self.node_id {
+            return;
+        }
+        self.unavailable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&node_id);
+        let mut members = self.members.write().unwrap_or_else(|e| e.into_inner());
+        members.peers.insert(node_id, cache);
+        members.ring = self.ring(members.peers.keys());
+    }
+
+    /// Remove the peer `node_id`, moving its keys to the remaining nodes,
+    /// returning whether it was a peer
+    pub fn remove_peer(&self, node_id: &str) -> bool {
+        let mut members = self.members.write().unwrap_or_else(|e| e.into_inner());
+        if members.peers.remove(node_id).is_none() {
+            return false;
+        }
+        members.ring = self.ring(members.peers.keys());
+        true
+    }
+
+    fn ring<'a>(&'a self, peers: impl Iterator<Item = &'a String>) -> HashRing {
+        HashRing::new(peers.chain([&self.node_id]), self.config.virtual_nodes)
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// Ids of the peers, sorted
+    pub fn peers(&self) -> Vec<String> {
+        let mut peers: Vec<String> = self
+            .members
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .peers
+            .keys()
+            .cloned()
+            .collect();
+        peers.sort();
+        peers
+    }
+
+    /// Node owning `key`
+    pub fn owner(&self, key: &str) -> String {
+        let members = self.members.read().unwrap_or_else(|e| e.into_inner());
+        members.ring.owner(key).unwrap_or(&self.node_id).to_string()
+    }
+
+    /// The local share of the keys
+    pub fn local(&self) -> &Arc<dyn Cache> {
+        &self.local
+    }
+
+    /// Number of peer requests that timed out or failed with a retryable
+    /// error
+    pub fn peer_failures(&self) -> u64 {
+        self.peer_failures.load(Ordering::Relaxed)
+    }
+
+    fn route(&self, key: &str) -> Route {
+        let members = self.members.read().unwrap_or_else(|e| e.into_inner());
+        match members.ring.owner(key) {
+            Some(owner) if owner != self.node_id => match members.peers.get(owner) {
+                Some(peer) => Route::Peer(owner.to_string(), peer.clone()),
+                None => Route::Local,
+            },
+            _ => Route::Local,
+        }
+    }
+
+    /// Whether `peer` is being skipped after a failure
+    fn is_unavailable(&self, peer: &str) -> bool {
+        self.unavailable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(peer)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    /// Run `request` against `peer`, skipping it for a while if it times
+    /// out or fails with a retryable error
+    async fn call_peer<T>(
+        &self,
+        peer: &str,
+        request: impl Future<Output = Result<T, CacheError>>,
+    ) -> Result<T, CacheError> {
+        if self.is_unavailable(peer) {
+            return Err(CacheError::backend(format!("peer {} is unavailable", peer)));
+        }
+
+        let result = match tokio::time::timeout(self.config.request_timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(CacheError::Timeout(format!(
+                "peer {} did not answer within {:?}",
+                peer, self.config.request_timeout
+            ))),
+        };
+        let mut unavailable = self.unavailable.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Err(e) if e.is_retryable() => {
+                self.peer_failures.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("Skipping cache peer {} after failure: {}", peer, e);
+                unavailable.insert(peer.to_string(), Instant::now() + self.config.retry_after);
+            }
+            _ => {
+                unavailable.remove(peer);
+            }
+        }
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for ClusterCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let value = match self.route(key) {
+            Route::Local => self.local.get(key).await,
+            Route::Peer(id, peer) => {
+                match self.call_peer(&id, async { Ok(peer.get(key).await) }).await {
+                    Ok(value) => value,
+                    Err(e) => {
+                        tracing::debug!("{}", e.context(CacheEventKind::Get, Tier::Remote, key));
+                        None
+                    }
+                }
+            }
+        };
+        match value {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        match self.route(key) {
+            Route::Local => self.local.set(key, value).await,
+            Route::Peer(id, peer) => self.call_peer(&id, peer.set(key, value)).await.context(
+                CacheEventKind::Set,
+                Tier::Remote,
+                key,
+            ),
+        }
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        match self.route(key) {
+            Route::Local => self.local.remove_with_cause(key, cause).await,
+            Route::Peer(id, peer) => self
+                .call_peer(&id, peer.remove_with_cause(key, cause))
+                .await
+                .context(CacheEventKind::Remove, Tier::Remote, key),
+        }
+    }
+
+    /// Removes the matching keys of every node, even if some fail
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let peers: Vec<(String, Arc<dyn Cache>)> = self
+            .members
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .peers
+            .iter()
+            .map(|(id, peer)| (id.clone(), peer.clone()))
+            .collect();
+
+        let mut removed = self.local.remove_prefix(prefix).await?;
+        let mut failure = None;
+        for (id, peer) in peers {
+            match self.call_peer(&id, peer.remove_prefix(prefix)).await {
+                Ok(count) => removed += count,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to invalidate {:?} on cache peer {}: {}",
+                        prefix,
+                        id,
+                        e
+                    );
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(removed),
+        }
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        self.local.clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.local.purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.local.capacity()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.local.resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.local.set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        self.local.size()
+    }
+
+    /// Hits and misses of this worker's requests, and the sizes and
+    /// removals of the local share
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..self.local.stats()
+        }
+    }
+}
+
+/// Configuration for a sharded cache
+///
+/// # Default Values
+/// - `virtual_nodes`: 128
+/// - `replication`: 1 (each key is held by one shard)
+#[derive(Debug, Clone)]
+pub struct ShardedCacheConfig {
+    /// Points each shard takes on the hash ring; more spread keys more
+    /// evenly
+    pub virtual_nodes: usize,
+    /// Shards each key is written to; reads fall over to the next replica
+    /// on a miss
+    pub replication: usize,
+}
+
+impl Default for ShardedCacheConfig {
+    fn default() -> Self {
+        Self {
+            virtual_nodes: 128,
+            replication: 1,
+        }
+    }
+}
+
+impl ShardedCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> ShardedCacheConfigBuilder {
+        ShardedCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.virtual_nodes == 0 {
+            return invalid("virtual_nodes must be greater than zero");
+        }
+        if self.replication == 0 {
+            return invalid("replication must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`ShardedCacheConfig`], starting from its defaults
+///
+/// [`build`](ShardedCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct ShardedCacheConfigBuilder {
+    config: ShardedCacheConfig,
+}
+
+impl ShardedCacheConfigBuilder {
+    pub fn virtual_nodes(mut self, nodes: usize) -> Self {
+        self.config.virtual_nodes = nodes;
+        self
+    }
+
+    pub fn replication(mut self, replicas: usize) -> Self {
+        self.config.replication = replicas;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<ShardedCacheConfig, CacheError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Shards and the ring over them
+#[derive(Default)]
+struct Shards {
+    caches: HashMap<String, Arc<dyn Cache>>,
+    ring: HashRing,
+}
+
+/// Cache spread over several caches by consistent hashing
+///
+/// Each key is routed by a [`HashRing`] to `replication` of the shards,
+/// which may be local caches, such as one per disk, or remote peers such as
+/// a `grpc::RemoteCache` per cache server (`grpc` feature). Writes go
+/// to every replica, and reads try them in ring order until one hits, so a
+/// key survives the loss of all but one of its shards. Adding or removing a
+/// shard only moves the keys it gains or held, which miss until written
+/// again.
+///
+/// A write succeeds if any replica took it; replicas that failed drop their
+/// copy so they cannot serve it stale. Removals, by key or prefix, reach
+/// every replica and fail if any fails. Sizes, capacities and removals
+/// count every replica; hits and misses count requests to the sharded
+/// cache.
+pub struct ShardedCache {
+    config: ShardedCacheConfig,
+    shards: RwLock<Shards>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for ShardedCache {
+    fn default() -> Self {
+        Self::build(ShardedCacheConfig::default())
+    }
+}
+
+impl ShardedCache {
+    /// A sharded cache without shards yet, with one replica per key
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: ShardedCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+        Ok(Self::build(config))
+    }
+
+    fn build(config: ShardedCacheConfig) -> Self {
+        Self {
+            config,
+            shards: RwLock::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Add the shard `shard_id`, held by `cache`
+    pub fn with_shard(self, shard_id: impl Into<String>, cache: Arc<dyn Cache>) -> Self {
+        self.add_shard(shard_id, cache);
+        self
+    }
+
+    /// Add or replace the shard `shard_id`, moving its keys to it
+    pub fn add_shard(&self, shard_id: impl Into<String>, cache: Arc<dyn Cache>) {
+        let mut shards = self.shards.write().unwrap_or_else(|e| e.into_inner());
+        shards.caches.insert(shard_id.into(), cache);
+        shards.ring = HashRing::new(shards.caches.keys().cloned(), self.config.virtual_nodes);
+    }
+
+    /// Remove the shard `shard_id`, moving its keys to the remaining
+    /// shards, returning whether it was a shard
+    pub fn remove_shard(&self, shard_id: &str) -> bool {
+        let mut shards = self.shards.write().unwrap_or_else(|e| e.into_inner());
+        if shards.caches.remove(shard_id).is_none() {
+            return false;
+        }
+        shards.ring = HashRing::new(shards.caches.keys().cloned(), self.config.virtual_nodes);
+        true
+    }
+
+    /// Ids of the shards, sorted
+    pub fn shard_ids(&self) -> Vec<String> {
+        let shards = self.shards.read().unwrap_or_else(|e| e.into_inner());
+        shards.ring.nodes().to_vec()
+    }
+
+    /// Shards holding `key`, in the order reads try them
+    pub fn owners(&self, key: &str) -> Vec<String> {
+        let shards = self.shards.read().unwrap_or_else(|e| e.into_inner());
+        shards
+            .ring
+            .owners(key, self.config.replication)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn config(&self) -> &ShardedCacheConfig {
+        &self.config
+    }
+
+    /// Replicas of `key`, in ring order
+    fn replicas(&self, key: &str) -> Vec<(String, Arc<dyn Cache>)> {
+        let shards = self.shards.read().unwrap_or_else(|e| e.into_inner());
+        shards
+            .ring
+            .owners(key, self.config.replication)
+            .into_iter()
+            .map(|id| (id.to_string(), shards.caches[id].clone()))
+            .collect()
+    }
+
+    fn all_shards(&self) -> Vec<Arc<dyn Cache>> {
+        let shards = self.shards.read().unwrap_or_else(|e| e.into_inner());
+        shards.caches.values().cloned().collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for ShardedCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        for (_, shard) in self.replicas(key) {
+            if let Some(value) = shard.get(key).await {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Some(value);
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let mut stored = false;
+        let mut failure = None;
+        for (id, shard) in self.replicas(key) {
+            match shard.set(key, value.clone()).await {
+                Ok(()) => stored = true,
+                Err(e) => {
+                    tracing::warn!("Failed to set {} on cache shard {}: {}", key, id, e);
+                    if let Err(e) = shard.remove(key).await {
+                        tracing::warn!("Failed to drop {} from cache shard {}: {}", key, id, e);
+                    }
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        match (stored, failure) {
+            (false, Some(e)) => Err(e),
+            (false, None) => Err(CacheError::Unavailable(
+                "sharded cache has no shards".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut failure = None;
+        for (id, shard) in self.replicas(key) {
+            if let Err(e) = shard.remove_with_cause(key, cause).await {
+                tracing::warn!("Failed to remove {} from cache shard {}: {}", key, id, e);
+                failure.get_or_insert(e);
+            }
+        }
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Removes the matching keys of every shard, even if some fail
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut removed = 0;
+        let mut failure = None;
+        for shard in self.all_shards() {
+            match shard.remove_prefix(prefix).await {
+                Ok(count) => removed += count,
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        failure.map_or(Ok(removed), Err)
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        for shard in self.all_shards() {
+            shard.clear().await?;
+        }
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        let mut purged = 0;
+        for shard in self.all_shards() {
+            purged += shard.purge_expired().await?;
+        }
+        Ok(purged)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.all_shards().iter().map(|shard| shard.capacity()).sum()
+    }
+
+    /// Splits `max_size_bytes` evenly between the shards
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        let shards = self.all_shards();
+        let shard_size = max_size_bytes / shards.len().max(1);
+        for shard in shards {
+            shard.resize(shard_size).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        for shard in self.all_shards() {
+            shard.set_ttl(ttl).await?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.all_shards().iter().map(|shard| shard.size()).sum()
+    }
+
+    fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        for shard in self.all_shards() {
+            let shard_stats = shard.stats();
+            stats.size_bytes += shard_stats.size_bytes;
+            stats.entry_count += shard_stats.entry_count;
+            stats.accounted_bytes += shard_stats.accounted_bytes;
+            stats.removals += shard_stats.removals;
+        }
+        stats
+    }
+}
@@ -0,0 +1,118 @@
+//! Unbuffered (`O_DIRECT`) entry file I/O for the disk cache
+
+use std::alloc::{self, Layout};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+/// Alignment of buffers, offsets and lengths; covers every common logical
+/// block size
+const ALIGN: usize = 4096;
+
+fn round_up(len: usize) -> usize {
+    len.div_ceil(ALIGN) * ALIGN
+}
+
+/// Zero-initialized heap buffer aligned for direct I/O
+pub(crate) struct AlignedBuf {
+    ptr: *mut u8,
+    capacity: usize,
+    len: usize,
+}
+
+// SAFETY: the buffer is uniquely owned heap memory
+unsafe impl Send for AlignedBuf {}
+unsafe impl Sync for AlignedBuf {}
+
+impl AlignedBuf {
+    /// Buffer holding `len` bytes, with capacity rounded up to the alignment
+    fn zeroed(len: usize) -> Self {
+        let capacity = round_up(len.max(1));
+        let layout = Layout::from_size_align(capacity, ALIGN).expect("valid layout");
+        // SAFETY: `layout` has a non-zero size
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        Self { ptr, capacity, len }
+    }
+
+    /// The whole aligned capacity
+    fn padded_mut(&mut self) -> &mut [u8] {
+        // SAFETY: `ptr` points to `capacity` initialized bytes we own
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.capacity) }
+    }
+}
+
+impl AsRef<[u8]> for AlignedBuf {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: `len <= capacity` initialized bytes
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, ALIGN).expect("valid layout");
+        // SAFETY: allocated in `zeroed` with the same layout
+        unsafe { alloc::dealloc(self.ptr, layout) };
+    }
+}
+
+fn open_direct(path: &Path, write: bool) -> io::Result<File> {
+    OpenOptions::new()
+        .read(!write)
+        .write(write)
+        .custom_flags(libc::O_DIRECT)
+        .open(path)
+}
+
+/// Whether an error means the filesystem does not support direct I/O
+pub(crate) fn is_unsupported(error: &io::Error) -> bool {
+    error.raw_os_error() == Some(libc::EINVAL)
+}
+
+/// Overwrite the existing file at `path` with `parts`, bypassing the page cache
+///
+/// The data is written padded to the alignment, then the file is truncated
+/// to its real length.
+pub(crate) fn write_file(path: &Path, parts: &[&[u8]], sync: bool) -> io::Result<()> {
+    let len = parts.iter().map(|part| part.len()).sum();
+    let mut buf = AlignedBuf::zeroed(len);
+    let mut offset = 0;
+    for part in parts {
+        buf.padded_mut()[offset..offset + part.len()].copy_from_slice(part);
+        offset += part.len();
+    }
+
+    let mut file = open_direct(path, true)?;
+    file.write_all(buf.padded_mut())?;
+    file.set_len(len as u64)?;
+    if sync {
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Read the whole file at `path`, bypassing the page cache
+pub(crate) fn read_file(path: &Path) -> io::Result<AlignedBuf> {
+    let mut file = open_direct(path, false)?;
+    let len = file.metadata()?.len() as usize;
+    let mut buf = AlignedBuf::zeroed(len);
+
+    // Reads stay aligned: every read but the last returns whole blocks
+    let mut filled = 0;
+    while filled < len {
+        let read = file.read(&mut buf.padded_mut()[filled..])?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    if filled < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(buf)
+}
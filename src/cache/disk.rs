@@ -1,21 +1,400 @@
-use crate::cache::{Cache, CacheStats, StoreKey};
+use crate::cache::bloom::KeyFilter;
+#[cfg(target_os = "linux")]
+use crate::cache::direct_io;
+#[cfg(unix)]
+use crate::cache::handles::FileHandlePool;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+use crate::cache::uring::{UringIo, UringOp};
+use crate::cache::{AtomicTtl, Cache, CacheStats, RemovalCause, RemovalTracker, StoreKey};
+use crate::compression::CompressionStats;
 use crate::error::CacheError;
+use crate::units::{ByteSize, HumanDuration, SetterError};
 use bytes::Bytes;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
+/// Name of the serialized index inside the cache directory
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// Name of the index write-ahead log inside the cache directory
+pub const JOURNAL_FILE_NAME: &str = "index.wal";
+
+/// Name of the persisted statistics inside the cache directory
+pub const STATE_FILE_NAME: &str = "state.json";
+
+/// Files the cache keeps at the top of its directory
+const RESERVED_FILE_NAMES: [&str; 3] = [INDEX_FILE_NAME, JOURNAL_FILE_NAME, STATE_FILE_NAME];
+
+/// Current on-disk index format
+const INDEX_VERSION: u32 = 1;
+
+/// How cache files are arranged inside the cache directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskLayout {
+    /// Every entry directly in the cache directory
+    Flat,
+    /// Entries spread over two levels of subdirectories (`ab/cd/<hash>.cache`)
+    ///
+    /// Keeps directories small enough for ext4 and NFS with millions of entries.
+    #[default]
+    FanOut,
+    /// Every entry at its own key, used as a relative path, holding just the
+    /// value (`temperature/c/1/2/3` at `<cache_dir>/temperature/c/1/2/3`)
+    ///
+    /// The cache directory then doubles as a partial local mirror of the
+    /// zarr store that other tools can open directly. Entry files carry no
+    /// header, so corruption is detected by size only, not by checksum.
+    /// Keys that are not plain relative paths, that end in `.tmp`, or that
+    /// clash with the index files are rejected with
+    /// [`CacheError::InvalidKey`], and a key cannot be stored while another
+    /// key uses it as a directory. Any other file in the directory counts
+    /// as an entry, to be adopted on a rescan or removed by garbage
+    /// collection.
+    Hierarchy,
+}
+
+impl DiskLayout {
+    /// Whether entry files start with a header holding the key and checksum
+    fn has_entry_headers(self) -> bool {
+        self != DiskLayout::Hierarchy
+    }
+}
+
+/// How the disk cache performs batched file I/O
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskIoBackend {
+    /// Blocking `std::fs` calls, one file at a time
+    #[default]
+    Std,
+    /// Linux io_uring with up to `queue_depth` operations in flight
+    ///
+    /// Used by [`DiskCache::set_many`] and [`DiskCache::get_many`]. Requires
+    /// the `io-uring` feature on Linux; otherwise, or if the kernel refuses to
+    /// set up a ring, the cache falls back to `Std`.
+    IoUring { queue_depth: u32 },
+}
+
+/// Longest access history kept per entry, bounding `LruK`'s K
+const MAX_ACCESS_HISTORY: usize = 8;
+
+/// Reads buffered before their access times are applied to the index
+const ACCESS_BATCH_SIZE: usize = 64;
+
+/// Outdated eviction queue entries tolerated beyond one per indexed entry
+/// before the queue is rebuilt from the index
+const EVICTION_QUEUE_SLACK: usize = 1024;
+
+/// Index entries by eviction rank, lowest first
+type EvictionQueue = BinaryHeap<Reverse<((u64, Instant), StoreKey)>>;
+
+/// Which entries the disk cache evicts first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiskEvictionPolicy {
+    /// Least recently accessed entry first
+    #[default]
+    Lru,
+    /// LRU-K: entry whose K-th most recent access is oldest first
+    ///
+    /// Entries accessed fewer than K times go before all others, oldest
+    /// access first, so a one-off scan cannot push out the working set.
+    /// K is capped at 8; 2 is the usual choice.
+    LruK { k: usize },
+    /// Least frequently accessed entry first, ties broken by recency
+    Lfu,
+}
+
+/// Configuration for the disk cache
+///
+/// # Default Values
+/// - `cache_dir`: System temp directory + "zarrs_disk_cache"
+/// - `max_size_bytes`: None (unbounded)
+/// - `ttl`: None (no expiration)
+/// - `ttl_jitter`: 0.0 (every entry expires exactly one TTL after its write)
+/// - `layout`: Fan-out
+/// - `sync_writes`: false
+/// - `journal`: true
+/// - `mmap_threshold_bytes`: None (always read into memory)
+/// - `io_backend`: Std
+/// - `direct_io`: false
+/// - `eviction_policy`: LRU
+/// - `eviction_batch_fraction`: 0.0 (evict only what the incoming entry needs)
+/// - `key_filter_capacity`: None (no key filter)
+/// - `file_handle_pool_size`: None (open entry files on every read)
+#[derive(Debug, Clone)]
+pub struct DiskCacheConfig {
+    /// Directory holding the cache files and index
+    pub cache_dir: PathBuf,
+    /// Maximum total size of cached files in bytes
+    pub max_size_bytes: Option<u64>,
+    /// TTL for cache entries
+    ///
+    /// Expired entries are dropped when read, and otherwise by
+    /// [`Cache::purge_expired`], e.g. from [`DiskCache::spawn_expiry_sweeper`].
+    pub ttl: Option<Duration>,
+    /// Fraction of the TTL, between 0 and 1, by which each entry's expiry
+    /// is moved earlier or later at random when it is written
+    ///
+    /// Spreads the expiry of entries written together, such as by a warming
+    /// run, so they do not all miss at the same instant. Entries loaded from
+    /// the index on startup draw a new offset.
+    pub ttl_jitter: f64,
+    /// File layout; entries in another layout are migrated on startup
+    pub layout: DiskLayout,
+    /// fsync entry files and their directory before a write returns
+    ///
+    /// Writes are atomic either way; this additionally makes them survive
+    /// power loss, at a large cost in write latency.
+    pub sync_writes: bool,
+    /// Log index mutations to a write-ahead log
+    ///
+    /// The index is then recovered exactly after a crash from the last
+    /// snapshot plus the log, without scanning the cache directory. Without a
+    /// journal, restarts rescan every entry file to find entries written
+    /// after the last snapshot.
+    pub journal: bool,
+    /// Serve hits on entry files of at least this many bytes from a memory
+    /// map instead of reading them into a buffer
+    ///
+    /// The returned `Bytes` borrow the mapping without copying, which cuts
+    /// latency for repeated reads of large chunks. Small files are cheaper
+    /// to read than to map. Entry files are only ever replaced by rename, so
+    /// a mapping stays valid after the entry is overwritten or evicted. On
+    /// Windows a mapped file cannot be replaced until every `Bytes` borrowing
+    /// it is dropped.
+    pub mmap_threshold_bytes: Option<u64>,
+    /// I/O path for batched reads and writes
+    pub io_backend: DiskIoBackend,
+    /// Read and write entry files with `O_DIRECT`, bypassing the OS page cache
+    ///
+    /// Keeps large streaming writes, such as demotions from a memory tier,
+    /// from evicting hot pages the rest of the process relies on. Buffers
+    /// are aligned and padded internally. Direct I/O takes precedence over
+    /// memory-mapped reads and the io_uring backend. Only supported on
+    /// Linux; elsewhere, or on filesystems rejecting `O_DIRECT`, the cache
+    /// falls back to buffered I/O with a warning.
+    pub direct_io: bool,
+    /// Order in which entries are evicted
+    pub eviction_policy: DiskEvictionPolicy,
+    /// Fraction of `max_size_bytes` to free in one go once eviction starts
+    ///
+    /// A burst of writes to a full cache then triggers one eviction pass
+    /// instead of one per write.
+    pub eviction_batch_fraction: f64,
+    /// Keep a bloom filter of cached keys, sized for this many entries, in
+    /// front of the index
+    ///
+    /// Lookups of keys that were never cached, typical for a hybrid cache
+    /// missing in both tiers, are then answered without taking the index
+    /// lock. The filter takes about 1.2 bytes per entry for a 1% false
+    /// positive rate, and is rebuilt larger as the cache outgrows it.
+    pub key_filter_capacity: Option<usize>,
+    /// Keep up to this many entry files open for reuse by later reads
+    ///
+    /// Saves an open and close per hit on entries read over and over without
+    /// being promoted to a memory tier. Each pooled handle holds a file
+    /// descriptor, counted against the process limit. Only supported on
+    /// Unix; ignored elsewhere.
+    pub file_handle_pool_size: Option<usize>,
+}
+
+impl Default for DiskCacheConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: std::env::temp_dir().join("zarrs_disk_cache"),
+            max_size_bytes: None,
+            ttl: None,
+            ttl_jitter: 0.0,
+            layout: DiskLayout::FanOut,
+            sync_writes: false,
+            journal: true,
+            mmap_threshold_bytes: None,
+            io_backend: DiskIoBackend::Std,
+            direct_io: false,
+            eviction_policy: DiskEvictionPolicy::Lru,
+            eviction_batch_fraction: 0.0,
+            key_filter_capacity: None,
+            file_handle_pool_size: None,
+        }
+    }
+}
+
+impl DiskCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> DiskCacheConfigBuilder {
+        DiskCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.max_size_bytes == Some(0) {
+            return invalid("max_size_bytes must be greater than zero");
+        }
+        if self.ttl == Some(Duration::ZERO) {
+            return invalid("ttl must be greater than zero");
+        }
+        if !(0.0..=1.0).contains(&self.ttl_jitter) {
+            return invalid("ttl_jitter must be between 0 and 1");
+        }
+        if let DiskIoBackend::IoUring { queue_depth: 0 } = self.io_backend {
+            return invalid("io_uring queue_depth must be greater than zero");
+        }
+        if let DiskEvictionPolicy::LruK { k: 0 } = self.eviction_policy {
+            return invalid("LRU-K eviction needs k of at least 1");
+        }
+        if !(0.0..=1.0).contains(&self.eviction_batch_fraction) {
+            return invalid("eviction_batch_fraction must be between 0 and 1");
+        }
+        if self.key_filter_capacity == Some(0) {
+            return invalid("key_filter_capacity must be greater than zero");
+        }
+        if self.file_handle_pool_size == Some(0) {
+            return invalid("file_handle_pool_size must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`DiskCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](DiskCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct DiskCacheConfigBuilder {
+    config: DiskCacheConfig,
+    invalid: SetterError,
+}
+
+impl DiskCacheConfigBuilder {
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.cache_dir = dir.into();
+        self
+    }
+
+    pub fn max_size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.max_size_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn ttl_jitter(mut self, jitter: f64) -> Self {
+        self.config.ttl_jitter = jitter;
+        self
+    }
+
+    pub fn layout(mut self, layout: DiskLayout) -> Self {
+        self.config.layout = layout;
+        self
+    }
+
+    pub fn sync_writes(mut self, sync: bool) -> Self {
+        self.config.sync_writes = sync;
+        self
+    }
+
+    pub fn journal(mut self, enabled: bool) -> Self {
+        self.config.journal = enabled;
+        self
+    }
+
+    pub fn mmap_threshold_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.mmap_threshold_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn io_backend(mut self, backend: DiskIoBackend) -> Self {
+        self.config.io_backend = backend;
+        self
+    }
+
+    pub fn direct_io(mut self, enabled: bool) -> Self {
+        self.config.direct_io = enabled;
+        self
+    }
+
+    pub fn eviction_policy(mut self, policy: DiskEvictionPolicy) -> Self {
+        self.config.eviction_policy = policy;
+        self
+    }
+
+    pub fn eviction_batch_fraction(mut self, fraction: f64) -> Self {
+        self.config.eviction_batch_fraction = fraction;
+        self
+    }
+
+    pub fn key_filter_capacity(mut self, capacity: usize) -> Self {
+        self.config.key_filter_capacity = Some(capacity);
+        self
+    }
+
+    pub fn file_handle_pool_size(mut self, handles: usize) -> Self {
+        self.config.file_handle_pool_size = Some(handles);
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<DiskCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
 pub struct DiskCache {
     cache_dir: PathBuf,
-    max_size_bytes: Option<u64>,
+    /// Maximum size in bytes; `u64::MAX` when unbounded
+    max_size_bytes: AtomicU64,
     current_size: Arc<AtomicUsize>,
     stats: Arc<CacheStatsInner>,
-    ttl: Option<Duration>,
+    ttl: AtomicTtl,
+    layout: DiskLayout,
+    sync_writes: bool,
+    mmap_threshold_bytes: Option<u64>,
+    io_backend: DiskIoBackend,
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    uring: Option<UringIo>,
+    /// Cleared if the filesystem turns out not to support direct I/O
+    direct_io: AtomicBool,
+    eviction_policy: DiskEvictionPolicy,
+    eviction_batch_fraction: f64,
     index: Arc<RwLock<HashMap<StoreKey, CacheMetadata>>>,
+    /// Accesses by readers not yet applied to the index, so hits only need
+    /// the index read lock
+    pending_accesses: Mutex<Vec<(StoreKey, Instant)>>,
+    /// Entries by creation time, soonest to expire first; only kept with a
+    /// TTL. Entries removed or rewritten since being queued are skipped when
+    /// they come due.
+    expiry_queue: Mutex<BinaryHeap<Reverse<(Instant, StoreKey)>>>,
+    /// Entries by eviction rank, queued again whenever their rank changes;
+    /// entries removed or re-ranked since being queued are skipped
+    eviction_queue: Mutex<EvictionQueue>,
+    /// Superset of the indexed keys; updated under the index write lock
+    key_filter: Option<std::sync::RwLock<KeyFilter>>,
+    #[cfg(unix)]
+    handles: Option<FileHandlePool>,
+    /// Open write-ahead log; appended to while holding the index write lock
+    journal: Option<Mutex<BufWriter<fs::File>>>,
 }
 
 #[derive(Clone)]
@@ -23,12 +402,342 @@ struct CacheMetadata {
     file_path: PathBuf,
     size: usize,
     created_at: Instant,
+    /// Instant from which the TTL runs: `created_at` moved by the TTL jitter
+    expires_from: Instant,
     last_accessed: Instant,
+    /// Accesses including the write that created the entry
+    access_count: u64,
+    /// Most recent accesses, oldest first, as many as the policy needs
+    access_history: VecDeque<Instant>,
+}
+
+impl CacheMetadata {
+    fn new(file_path: PathBuf, size: usize, created_at: Instant, expires_from: Instant) -> Self {
+        Self {
+            file_path,
+            size,
+            created_at,
+            expires_from,
+            last_accessed: created_at,
+            access_count: 1,
+            access_history: VecDeque::from([created_at]),
+        }
+    }
+
+    fn record_access(&mut self, at: Instant, history_len: usize) {
+        self.last_accessed = at;
+        self.access_count += 1;
+        self.access_history.push_back(at);
+        while self.access_history.len() > history_len {
+            self.access_history.pop_front();
+        }
+    }
+
+    /// Whether both describe the same write of an entry
+    fn same_entry(&self, other: &CacheMetadata) -> bool {
+        self.file_path == other.file_path && self.created_at == other.created_at
+    }
 }
 
 struct CacheStatsInner {
     hits: AtomicU64,
     misses: AtomicU64,
+    /// Entries discarded because their file failed verification
+    corrupted: AtomicU64,
+    /// Misses answered by the key filter
+    filtered: AtomicU64,
+    /// Entry file reads, writes and deletions
+    io_operations: AtomicU64,
+    removals: RemovalTracker,
+}
+
+/// Outcome of a [`DiskCache::collect_garbage`] pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskGcReport {
+    /// Entry files deleted because the index does not reference them
+    pub orphaned_files: usize,
+    /// Bytes freed by deleting orphaned entry files
+    pub orphaned_bytes: u64,
+    /// Temp files of interrupted writes deleted
+    pub stale_temp_files: usize,
+    /// Index entries dropped because their file was missing
+    pub dangling_entries: usize,
+}
+
+/// An entry of a [`DiskCache`], as listed by [`DiskCache::entries`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskEntryInfo {
+    pub key: StoreKey,
+    /// Value size in bytes
+    pub size: usize,
+    /// Time since the entry was written
+    pub age: Duration,
+    /// Time since the entry was last accessed
+    pub idle: Duration,
+    /// Accesses including the write that created the entry
+    pub access_count: u64,
+    /// Whether the entry outlived the TTL and awaits removal
+    pub expired: bool,
+    /// File holding the entry
+    pub file_path: PathBuf,
+}
+
+/// Outcome of a [`DiskCache::verify`] pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskVerifyReport {
+    /// Entries whose file was read
+    pub checked: usize,
+    /// Entries discarded because their file failed its checksum or size check
+    pub corrupt: Vec<StoreKey>,
+    /// Entries discarded because their file could not be read
+    pub unreadable: Vec<StoreKey>,
+}
+
+/// Counters carried over between instances, written alongside the index
+#[derive(Serialize, Deserialize)]
+struct PersistedState {
+    version: u32,
+    hits: u64,
+    misses: u64,
+    corrupted: u64,
+}
+
+/// Serialized form of the index, written by [`DiskCache::persist_index`]
+#[derive(Serialize, Deserialize)]
+struct PersistedIndex {
+    version: u32,
+    entries: Vec<PersistedEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry {
+    key: StoreKey,
+    /// Path relative to the cache directory, using `/` separators
+    relative_path: String,
+    size: usize,
+    /// Creation time in milliseconds since the Unix epoch
+    created_at_ms: u64,
+    /// Last access time in milliseconds since the Unix epoch
+    #[serde(default)]
+    last_accessed_ms: Option<u64>,
+    #[serde(default)]
+    access_count: u64,
+    /// Recent access times in milliseconds since the Unix epoch, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    access_history_ms: Vec<u64>,
+}
+
+/// One index mutation in the write-ahead log, stored as a JSON line
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalRecord {
+    Insert(PersistedEntry),
+    Remove { key: StoreKey },
+    Access { key: StoreKey, at_ms: u64 },
+    Clear,
+}
+
+fn instant_to_unix_ms(instant: Instant) -> u64 {
+    let wall = SystemTime::now()
+        .checked_sub(instant.elapsed())
+        .unwrap_or(UNIX_EPOCH);
+    wall.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Magic bytes opening every entry file
+const ENTRY_MAGIC: &[u8; 4] = b"ZCE\0";
+
+/// Current entry file format
+const ENTRY_FORMAT_VERSION: u8 = 1;
+
+/// Fixed header part: magic, version, key length, value checksum
+const ENTRY_PREFIX_LEN: usize = 4 + 1 + 4 + 4;
+
+/// Longest key accepted when reading a header back from an untrusted file
+const MAX_KEY_LEN: usize = 64 * 1024;
+
+/// Decoded entry file header
+struct EntryHeader<'a> {
+    key: &'a str,
+    /// CRC32C of the value bytes
+    checksum: u32,
+    len: usize,
+}
+
+/// Length of the entry header for `key`
+fn entry_header_len(key: &str) -> usize {
+    ENTRY_PREFIX_LEN + key.len()
+}
+
+fn encode_entry_header(key: &str, value: &[u8]) -> Vec<u8> {
+    entry_header_with_checksum(key, crc32c::crc32c(value))
+}
+
+fn entry_header_with_checksum(key: &str, checksum: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(entry_header_len(key));
+    header.extend_from_slice(ENTRY_MAGIC);
+    header.push(ENTRY_FORMAT_VERSION);
+    header.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    header.extend_from_slice(&checksum.to_le_bytes());
+    header.extend_from_slice(key.as_bytes());
+    header
+}
+
+/// Writer tracking the length and CRC32C of the value bytes written through
+/// it
+struct ChecksumWriter<W: Write> {
+    inner: W,
+    checksum: u32,
+    len: usize,
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.checksum = crc32c::crc32c_append(self.checksum, &buf[..written]);
+        self.len += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Parse the fixed header part, returning the key length and checksum
+fn decode_entry_prefix(data: &[u8]) -> Option<(usize, u32)> {
+    let rest = data.strip_prefix(ENTRY_MAGIC)?;
+    let (&version, rest) = rest.split_first()?;
+    if version != ENTRY_FORMAT_VERSION {
+        return None;
+    }
+    let key_len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    let checksum = u32::from_le_bytes(rest.get(4..8)?.try_into().ok()?);
+    (key_len <= MAX_KEY_LEN).then_some((key_len, checksum))
+}
+
+fn decode_entry_header(data: &[u8]) -> Option<EntryHeader<'_>> {
+    let (key_len, checksum) = decode_entry_prefix(data)?;
+    let key_bytes = data.get(ENTRY_PREFIX_LEN..ENTRY_PREFIX_LEN + key_len)?;
+    let key = std::str::from_utf8(key_bytes).ok()?;
+    Some(EntryHeader {
+        key,
+        checksum,
+        len: ENTRY_PREFIX_LEN + key_len,
+    })
+}
+
+/// Read just the header of an entry file
+fn read_entry_key(path: &Path) -> std::io::Result<Option<StoreKey>> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path)?;
+    let mut header = vec![0u8; ENTRY_PREFIX_LEN];
+    if file.read_exact(&mut header).is_err() {
+        return Ok(None);
+    }
+    let Some((key_len, _)) = decode_entry_prefix(&header) else {
+        return Ok(None);
+    };
+    header.resize(ENTRY_PREFIX_LEN + key_len, 0);
+    if file.read_exact(&mut header[ENTRY_PREFIX_LEN..]).is_err() {
+        return Ok(None);
+    }
+    Ok(decode_entry_header(&header).map(|header| header.key.to_string()))
+}
+
+/// Why an entry file could not be served
+enum EntryReadError {
+    Io(std::io::Error),
+    /// Header missing, for another key, or checksum mismatch
+    Corrupt(String),
+}
+
+/// Read a whole entry file
+///
+/// Files of at least `mmap_threshold` bytes are memory-mapped rather than
+/// copied into a buffer.
+fn read_entry(path: &Path, mmap_threshold: Option<u64>) -> std::io::Result<Bytes> {
+    read_open_entry(&fs::File::open(path)?, mmap_threshold)
+}
+
+/// [`read_entry`] on an already open file, without moving its cursor
+fn read_open_entry(file: &fs::File, mmap_threshold: Option<u64>) -> std::io::Result<Bytes> {
+    let len = file.metadata()?.len();
+
+    match mmap_threshold {
+        #[cfg(not(target_arch = "wasm32"))]
+        Some(threshold) if len >= threshold && len > 0 => {
+            // SAFETY: entry files are written to a temp file and renamed into
+            // place, never modified or truncated afterwards, so the mapped
+            // contents cannot change underneath the returned `Bytes`
+            let map = unsafe { memmap2::Mmap::map(file) }?;
+            Ok(Bytes::from_owner(map))
+        }
+        #[cfg(unix)]
+        _ => {
+            use std::os::unix::fs::FileExt;
+
+            // Positional, so pooled handles can be read concurrently
+            let mut buf = vec![0u8; len as usize];
+            file.read_exact_at(&mut buf, 0)?;
+            Ok(Bytes::from(buf))
+        }
+        #[cfg(not(unix))]
+        _ => {
+            use std::io::Read;
+
+            let mut buf = Vec::with_capacity(len as usize);
+            (&*file).read_to_end(&mut buf)?;
+            Ok(Bytes::from(buf))
+        }
+    }
+}
+
+/// Strip and check the header of a whole entry file
+fn verify_entry(data: Bytes, key: &str) -> Result<Bytes, EntryReadError> {
+    let header = decode_entry_header(&data)
+        .ok_or_else(|| EntryReadError::Corrupt("missing entry header".to_string()))?;
+    if header.key != key {
+        return Err(EntryReadError::Corrupt(format!(
+            "file belongs to key {:?}",
+            header.key
+        )));
+    }
+
+    let (checksum, header_len) = (header.checksum, header.len);
+    let value = data.slice(header_len..);
+    if crc32c::crc32c(&value) != checksum {
+        return Err(EntryReadError::Corrupt("checksum mismatch".to_string()));
+    }
+    Ok(value)
+}
+
+/// Make a rename within `dir` durable
+fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Time since `path` was last modified; zero if unknown
+fn file_age(path: &Path) -> Duration {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .unwrap_or_default()
+}
+
+fn unix_ms_to_instant(ms: u64) -> Instant {
+    let age = SystemTime::now()
+        .duration_since(UNIX_EPOCH + Duration::from_millis(ms))
+        .unwrap_or_default();
+    Instant::now().checked_sub(age).unwrap_or_else(Instant::now)
 }
 
 impl DiskCache {
@@ -41,79 +750,1118 @@ impl DiskCache {
         max_size_bytes: Option<u64>,
         ttl: Option<Duration>,
     ) -> Result<Self, CacheError> {
-        // Create cache directory if it doesn't exist
-        fs::create_dir_all(&cache_dir)?;
-
-        let cache = Self {
+        Self::with_config(DiskCacheConfig {
             cache_dir,
             max_size_bytes,
+            ttl,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: DiskCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+
+        // Create cache directory if it doesn't exist
+        fs::create_dir_all(&config.cache_dir)?;
+
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let uring = match config.io_backend {
+            DiskIoBackend::IoUring { queue_depth } => match UringIo::new(queue_depth) {
+                Ok(uring) => Some(uring),
+                Err(e) => {
+                    tracing::warn!("Failed to set up io_uring, using std I/O: {}", e);
+                    None
+                }
+            },
+            DiskIoBackend::Std => None,
+        };
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        let io_backend = if uring.is_some() {
+            config.io_backend
+        } else {
+            DiskIoBackend::Std
+        };
+        #[cfg(not(all(target_os = "linux", feature = "io-uring")))]
+        let io_backend = {
+            if config.io_backend != DiskIoBackend::Std {
+                tracing::warn!(
+                    "io_uring disk backend requires the `io-uring` feature on Linux; using std I/O"
+                );
+            }
+            DiskIoBackend::Std
+        };
+
+        if config.file_handle_pool_size.is_some() && !cfg!(unix) {
+            tracing::warn!("File handle pooling is only supported on Unix; ignoring it");
+        }
+        if config.direct_io && !cfg!(target_os = "linux") {
+            tracing::warn!("Direct I/O is only supported on Linux; using buffered I/O");
+        }
+
+        let mut cache = Self {
+            cache_dir: config.cache_dir,
+            max_size_bytes: AtomicU64::new(config.max_size_bytes.unwrap_or(u64::MAX)),
             current_size: Arc::new(AtomicUsize::new(0)),
             stats: Arc::new(CacheStatsInner {
                 hits: AtomicU64::new(0),
                 misses: AtomicU64::new(0),
+                corrupted: AtomicU64::new(0),
+                filtered: AtomicU64::new(0),
+                io_operations: AtomicU64::new(0),
+                removals: RemovalTracker::default(),
             }),
-            ttl,
+            ttl: AtomicTtl::new(config.ttl).with_jitter(config.ttl_jitter),
+            layout: config.layout,
+            sync_writes: config.sync_writes,
+            mmap_threshold_bytes: config.mmap_threshold_bytes,
+            io_backend,
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            uring,
+            direct_io: AtomicBool::new(config.direct_io && cfg!(target_os = "linux")),
+            eviction_policy: config.eviction_policy,
+            eviction_batch_fraction: config.eviction_batch_fraction,
             index: Arc::new(RwLock::new(HashMap::new())),
+            pending_accesses: Mutex::new(Vec::new()),
+            expiry_queue: Mutex::new(BinaryHeap::new()),
+            eviction_queue: Mutex::new(BinaryHeap::new()),
+            key_filter: config
+                .key_filter_capacity
+                .map(|capacity| std::sync::RwLock::new(KeyFilter::with_capacity(capacity))),
+            #[cfg(unix)]
+            handles: config.file_handle_pool_size.map(FileHandlePool::new),
+            journal: None,
+        };
+
+        // Reuse entries and counters persisted by a previous instance
+        cache.load_state();
+        cache.initialize_from_disk(config.journal)?;
+
+        Ok(cache)
+    }
+
+    pub fn layout(&self) -> DiskLayout {
+        self.layout
+    }
+
+    /// I/O backend in use, after any fallback from the configured one
+    pub fn io_backend(&self) -> DiskIoBackend {
+        self.io_backend
+    }
+
+    /// Whether entry files bypass the page cache, after any fallback to
+    /// buffered I/O
+    pub fn direct_io(&self) -> bool {
+        self.direct_io.load(Ordering::Relaxed)
+    }
+
+    /// Stop using direct I/O if `error` shows the filesystem rejects it
+    #[cfg(target_os = "linux")]
+    fn direct_io_failed(&self, error: &std::io::Error) -> bool {
+        if !direct_io::is_unsupported(error) {
+            return false;
+        }
+        if self.direct_io.swap(false, Ordering::Relaxed) {
+            tracing::warn!(
+                "Direct I/O not supported in {:?}, using buffered I/O: {}",
+                self.cache_dir,
+                error
+            );
+        }
+        true
+    }
+
+    /// Read the entry file described by `metadata`, through the page cache
+    /// or around it, and check it holds the value of `key`
+    fn read_entry_file(
+        &self,
+        key: &str,
+        metadata: &CacheMetadata,
+    ) -> Result<Bytes, EntryReadError> {
+        let (path, size) = (&metadata.file_path, metadata.size);
+        self.count_io(1);
+
+        #[cfg(target_os = "linux")]
+        if self.direct_io() {
+            match direct_io::read_file(path) {
+                Ok(buf) => return self.decode_entry(Bytes::from_owner(buf), key, size),
+                Err(e) if self.direct_io_failed(&e) => {}
+                Err(e) => return Err(EntryReadError::Io(e)),
+            }
+        }
+
+        #[cfg(unix)]
+        if let Some(handles) = &self.handles {
+            let data = handles
+                .open(path, metadata.created_at)
+                .and_then(|file| read_open_entry(&file, self.mmap_threshold_bytes))
+                .map_err(EntryReadError::Io)?;
+            return self.decode_entry(data, key, size);
+        }
+
+        let data = read_entry(path, self.mmap_threshold_bytes).map_err(EntryReadError::Io)?;
+        self.decode_entry(data, key, size)
+    }
+
+    /// Close any pooled handle on the entry file at `path`
+    fn forget_handle(&self, path: &Path) {
+        #[cfg(unix)]
+        if let Some(handles) = &self.handles {
+            handles.forget(path);
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+    }
+
+    /// Delete the file of an entry just removed from the index
+    fn remove_entry_file(&self, path: &Path) -> std::io::Result<()> {
+        self.forget_handle(path);
+        self.count_io(1);
+        fs::remove_file(path)
+    }
+
+    /// Extract the value of `key` from the contents of its entry file
+    fn decode_entry(&self, data: Bytes, key: &str, size: usize) -> Result<Bytes, EntryReadError> {
+        if self.layout.has_entry_headers() {
+            return verify_entry(data, key);
+        }
+        if data.len() != size {
+            return Err(EntryReadError::Corrupt(format!(
+                "expected {} bytes, found {}",
+                size,
+                data.len()
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Header to write before the value of `key` under the current layout
+    fn entry_header(&self, key: &str, value: &[u8]) -> Vec<u8> {
+        if self.layout.has_entry_headers() {
+            encode_entry_header(key, value)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Length of [`DiskCache::entry_header`] for `key`
+    fn entry_header_len(&self, key: &str) -> usize {
+        if self.layout.has_entry_headers() {
+            entry_header_len(key)
+        } else {
+            0
+        }
+    }
+
+    /// Number of entries discarded because their file was corrupt
+    pub fn corrupted_entries(&self) -> u64 {
+        self.stats.corrupted.load(Ordering::Relaxed)
+    }
+
+    /// Number of misses answered by the key filter without an index lookup
+    pub fn key_filter_rejections(&self) -> u64 {
+        self.stats.filtered.load(Ordering::Relaxed)
+    }
+
+    fn count_io(&self, operations: usize) {
+        self.stats
+            .io_operations
+            .fetch_add(operations as u64, Ordering::Relaxed);
+    }
+
+    /// Whether `key` may be cached; always true without a key filter
+    fn may_contain(&self, key: &str) -> bool {
+        self.key_filter.as_ref().is_none_or(|filter| {
+            filter
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .might_contain(key)
+        })
+    }
+
+    /// Add `key` to the key filter, rebuilding it from `index` once full
+    ///
+    /// Must be called while holding the index write lock, before `key` is
+    /// inserted into the index.
+    fn add_to_key_filter(&self, key: &str, index: &HashMap<StoreKey, CacheMetadata>) {
+        let Some(filter) = &self.key_filter else {
+            return;
+        };
+
+        let capacity = {
+            let filter = filter.read().unwrap_or_else(|e| e.into_inner());
+            filter.insert(key);
+            if !filter.is_saturated() {
+                return;
+            }
+            filter.capacity()
+        };
+        let rebuilt = KeyFilter::from_keys(
+            capacity,
+            index.len() + 1,
+            index.keys().map(String::as_str).chain(std::iter::once(key)),
+        );
+        *filter.write().unwrap_or_else(|e| e.into_inner()) = rebuilt;
+    }
+
+    /// Rebuild the index from the persisted snapshot and write-ahead log
+    ///
+    /// Entries whose files disappeared or no longer match are dropped. When
+    /// the snapshot and log may be missing entries (journaling disabled, or a
+    /// directory from a version without a journal), entry files the index does
+    /// not know about are adopted using the key in their header and files
+    /// without a valid header are removed, so no dangling files accumulate.
+    fn initialize_from_disk(&mut self, journal: bool) -> Result<(), CacheError> {
+        let index_exists = self.cache_dir.join(INDEX_FILE_NAME).exists();
+        let mut persisted: HashMap<StoreKey, PersistedEntry> = self
+            .load_persisted_index()
+            .into_iter()
+            .map(|entry| (entry.key.clone(), entry))
+            .collect();
+
+        let journal_path = self.cache_dir.join(JOURNAL_FILE_NAME);
+        let journal_exists = journal_path.exists();
+        let replayed = if journal_exists {
+            Self::replay_journal(&journal_path, &mut persisted)?
+        } else {
+            0
+        };
+
+        let mut index = HashMap::new();
+        let mut total_size = 0;
+
+        let mut migrated = 0;
+        for entry in persisted.into_values() {
+            let mut file_path = self.cache_dir.join(&entry.relative_path);
+            // Entries written under another layout may have a header or not
+            let headered = match fs::metadata(&file_path) {
+                Ok(file_metadata) if file_metadata.is_file() => {
+                    let len = file_metadata.len() as usize;
+                    if len == entry_header_len(&entry.key) + entry.size {
+                        true
+                    } else if len == entry.size {
+                        false
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+            let Ok(expected_path) = self.key_to_path(&entry.key) else {
+                tracing::warn!(
+                    "Dropping cache entry {:?} that the {:?} layout cannot store",
+                    entry.key,
+                    self.layout
+                );
+                continue;
+            };
+
+            // Move entries written under another layout into place
+            if headered != self.layout.has_entry_headers() {
+                match self.convert_entry_file(&file_path, &expected_path, &entry.key, headered) {
+                    Ok(()) => {
+                        file_path = expected_path;
+                        migrated += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to migrate cache file {:?}: {:?}", file_path, e);
+                        continue;
+                    }
+                }
+            } else if file_path != expected_path {
+                match Self::move_file(&file_path, &expected_path) {
+                    Ok(()) => {
+                        file_path = expected_path;
+                        migrated += 1;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to migrate cache file {:?}: {}", file_path, e);
+                        continue;
+                    }
+                }
+            }
+
+            let created_at = unix_ms_to_instant(entry.created_at_ms);
+            let last_accessed = entry
+                .last_accessed_ms
+                .map_or(created_at, unix_ms_to_instant);
+            let mut access_history: VecDeque<Instant> = entry
+                .access_history_ms
+                .iter()
+                .map(|&ms| unix_ms_to_instant(ms))
+                .collect();
+            if access_history.is_empty() {
+                access_history.push_back(last_accessed);
+            }
+            while access_history.len() > self.access_history_len() {
+                access_history.pop_front();
+            }
+            total_size += entry.size;
+            index.insert(
+                entry.key,
+                CacheMetadata {
+                    file_path,
+                    size: entry.size,
+                    created_at,
+                    expires_from: self.ttl.expiry_base(created_at),
+                    last_accessed,
+                    access_count: entry.access_count.max(1),
+                    access_history,
+                },
+            );
+        }
+
+        // Snapshot plus log already describe every entry
+        let complete = journal && (index_exists || journal_exists);
+
+        let mut orphans = 0;
+        let mut adopted = 0;
+        if !complete {
+            let known: HashSet<PathBuf> = index.values().map(|m| m.file_path.clone()).collect();
+            let (files, _) = self.entry_files(&self.cache_dir, Duration::ZERO)?;
+            for path in files {
+                if known.contains(&path) {
+                    continue;
+                }
+                match self.adopt_file(&path) {
+                    Some((key, metadata)) if !index.contains_key(&key) => {
+                        total_size += metadata.size;
+                        index.insert(key, metadata);
+                        adopted += 1;
+                    }
+                    _ => match fs::remove_file(&path) {
+                        Ok(()) => orphans += 1,
+                        Err(e) => {
+                            tracing::warn!("Failed to remove orphaned cache file {:?}: {}", path, e)
+                        }
+                    },
+                }
+            }
+        }
+
+        tracing::info!(
+            "Loaded {} disk cache entries ({} bytes) from {:?}: replayed {} journal records, migrated {}, adopted {}, removed {} orphaned files",
+            index.len(),
+            total_size,
+            self.cache_dir,
+            replayed,
+            migrated,
+            adopted,
+            orphans
+        );
+
+        // Fold the log into a fresh snapshot before appending to it again
+        if journal || journal_exists {
+            self.write_index(&index)?;
+        }
+        if journal {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&journal_path)?;
+            file.set_len(0)?;
+            self.journal = Some(Mutex::new(BufWriter::new(file)));
+        } else if journal_exists {
+            fs::remove_file(&journal_path)?;
+        }
+
+        if self.ttl.get().is_some() {
+            let queue = self
+                .expiry_queue
+                .get_mut()
+                .unwrap_or_else(|e| e.into_inner());
+            queue.extend(
+                index
+                    .iter()
+                    .map(|(key, metadata)| Reverse((metadata.expires_from, key.clone()))),
+            );
+        }
+        let ranked = self.rank_entries(&index);
+        *self
+            .eviction_queue
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner()) = ranked;
+
+        if let Some(filter) = &mut self.key_filter {
+            let filter = filter.get_mut().unwrap_or_else(|e| e.into_inner());
+            *filter = KeyFilter::from_keys(
+                filter.capacity(),
+                index.len(),
+                index.keys().map(String::as_str),
+            );
+        }
+
+        self.current_size.store(total_size, Ordering::Relaxed);
+        self.index = Arc::new(RwLock::new(index));
+
+        Ok(())
+    }
+
+    /// Apply the records of a write-ahead log to `entries`
+    ///
+    /// Replay stops at the first record that does not parse, which is a
+    /// record torn by a crash mid-append. Returns the number of records
+    /// applied.
+    fn replay_journal(
+        path: &Path,
+        entries: &mut HashMap<StoreKey, PersistedEntry>,
+    ) -> Result<usize, CacheError> {
+        let reader = std::io::BufReader::new(fs::File::open(path)?);
+        let mut replayed = 0;
+
+        for line in reader.split(b'\n') {
+            let line = line?;
+            let record = match serde_json::from_slice::<JournalRecord>(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    tracing::warn!("Ignoring torn disk cache journal tail: {}", e);
+                    break;
+                }
+            };
+
+            match record {
+                JournalRecord::Insert(entry) => {
+                    entries.insert(entry.key.clone(), entry);
+                }
+                JournalRecord::Remove { key } => {
+                    entries.remove(&key);
+                }
+                JournalRecord::Access { key, at_ms } => {
+                    if let Some(entry) = entries.get_mut(&key) {
+                        entry.last_accessed_ms = Some(at_ms);
+                        entry.access_count += 1;
+                        entry.access_history_ms.push(at_ms);
+                        if entry.access_history_ms.len() > MAX_ACCESS_HISTORY {
+                            entry.access_history_ms.remove(0);
+                        }
+                    }
+                }
+                JournalRecord::Clear => entries.clear(),
+            }
+            replayed += 1;
+        }
+
+        Ok(replayed)
+    }
+
+    /// Append an index mutation to the write-ahead log, if enabled
+    ///
+    /// Must be called while holding the index write lock so the log order
+    /// matches the order mutations were applied. Access-time records are
+    /// buffered; with `durable` the log is flushed, and with `sync_writes`
+    /// also fsynced, before returning.
+    fn log(&self, record: &JournalRecord, durable: bool) -> Result<(), CacheError> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
         };
 
-        // Initialize by scanning existing files
-        cache.initialize_from_disk()?;
+        let mut line =
+            serde_json::to_vec(record).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        line.push(b'\n');
+
+        let mut writer = journal.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(&line)?;
+        if durable {
+            writer.flush()?;
+            if self.sync_writes {
+                writer.get_ref().sync_data()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Log the removal of `key`, warning instead of failing
+    fn log_removal(&self, key: &str) {
+        let record = JournalRecord::Remove {
+            key: key.to_string(),
+        };
+        if let Err(e) = self.log(&record, true) {
+            tracing::warn!("Failed to journal removal of {}: {:?}", key, e);
+        }
+    }
+
+    /// Recover an unindexed entry file from its header, or from its path
+    /// under the hierarchy layout
+    fn adopt_file(&self, path: &Path) -> Option<(StoreKey, CacheMetadata)> {
+        let key = if self.layout.has_entry_headers() {
+            read_entry_key(path).ok()??
+        } else {
+            self.relative_path(path)?
+        };
+        if self.key_to_path(&key).ok()? != path {
+            return None;
+        }
+
+        let file_metadata = fs::metadata(path).ok()?;
+        let size = (file_metadata.len() as usize).checked_sub(self.entry_header_len(&key))?;
+        let age = file_metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .unwrap_or_default();
+        let created_at = Instant::now().checked_sub(age).unwrap_or_else(Instant::now);
+
+        Some((
+            key,
+            CacheMetadata::new(
+                path.to_path_buf(),
+                size,
+                created_at,
+                self.ttl.expiry_base(created_at),
+            ),
+        ))
+    }
+
+    /// All entry files under `dir`, recursively
+    ///
+    /// Temp files at least `temp_min_age` old, left behind by writes
+    /// interrupted by a crash, are deleted on the way; their count is
+    /// returned alongside the entry files.
+    fn entry_files(
+        &self,
+        dir: &Path,
+        temp_min_age: Duration,
+    ) -> Result<(Vec<PathBuf>, usize), CacheError> {
+        let mut files = Vec::new();
+        let mut removed_temp_files = 0;
+        for dir_entry in fs::read_dir(dir)?.flatten() {
+            let path = dir_entry.path();
+            if dir_entry.file_type().is_ok_and(|t| t.is_dir()) {
+                let (nested, removed) = self.entry_files(&path, temp_min_age)?;
+                files.extend(nested);
+                removed_temp_files += removed;
+            } else if path.extension().is_some_and(|ext| ext == "tmp") {
+                if file_age(&path) >= temp_min_age {
+                    match fs::remove_file(&path) {
+                        Ok(()) => removed_temp_files += 1,
+                        Err(e) => {
+                            tracing::warn!("Failed to remove stale temp file {:?}: {}", path, e)
+                        }
+                    }
+                }
+            } else if self.is_entry_file(&path) {
+                files.push(path);
+            }
+        }
+        Ok((files, removed_temp_files))
+    }
+
+    /// Whether a non-temp file in the cache directory holds an entry
+    fn is_entry_file(&self, path: &Path) -> bool {
+        match self.layout {
+            DiskLayout::Flat | DiskLayout::FanOut => {
+                path.extension().is_some_and(|ext| ext == "cache")
+            }
+            DiskLayout::Hierarchy => {
+                path.parent() != Some(self.cache_dir.as_path())
+                    || !path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| RESERVED_FILE_NAMES.contains(&name))
+            }
+        }
+    }
+
+    /// Path of `path` relative to the cache directory, `/`-separated
+    fn relative_path(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.cache_dir).ok()?;
+        let components = relative
+            .components()
+            .map(|c| c.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(components.join("/"))
+    }
+
+    /// Write an entry file atomically
+    ///
+    /// The data goes to a temp file in the target directory which is renamed
+    /// into place, so readers and restarts never see a partially written
+    /// entry. With `sync_writes` the file and directory are fsynced as well.
+    fn write_entry_file(&self, path: &Path, key: &str, value: &[u8]) -> Result<(), CacheError> {
+        self.count_io(1);
+        let dir = path.parent().unwrap_or(&self.cache_dir);
+        fs::create_dir_all(dir)?;
+
+        let mut tmp = tempfile::Builder::new()
+            .prefix(".entry-")
+            .suffix(".tmp")
+            .tempfile_in(dir)?;
+        let header = self.entry_header(key, value);
+        if !self.write_entry_direct(tmp.path(), &header, value)? {
+            tmp.write_all(&header)?;
+            tmp.write_all(value)?;
+            if self.sync_writes {
+                tmp.as_file().sync_all()?;
+            }
+        }
+        tmp.persist(path).map_err(|e| e.error)?;
+
+        if self.sync_writes {
+            sync_dir(dir)?;
+        }
+        Ok(())
+    }
+
+    /// [`DiskCache::write_entry_file`] with the value produced by `write`,
+    /// returning its length
+    ///
+    /// The header's checksum is only known once the value is written, so
+    /// room is left for the header and it is filled in last.
+    fn write_entry_file_streamed(
+        &self,
+        path: &Path,
+        key: &str,
+        write: impl FnOnce(&mut dyn Write) -> Result<(), CacheError>,
+    ) -> Result<usize, CacheError> {
+        use std::io::{Seek, SeekFrom};
+
+        self.count_io(1);
+        let dir = path.parent().unwrap_or(&self.cache_dir);
+        fs::create_dir_all(dir)?;
+
+        let mut tmp = tempfile::Builder::new()
+            .prefix(".entry-")
+            .suffix(".tmp")
+            .tempfile_in(dir)?;
+        let header_len = self.entry_header_len(key);
+        tmp.write_all(&vec![0; header_len])?;
+        let mut writer = ChecksumWriter {
+            inner: BufWriter::new(tmp.as_file_mut()),
+            checksum: 0,
+            len: 0,
+        };
+        write(&mut writer)?;
+        writer.flush()?;
+        let (checksum, len) = (writer.checksum, writer.len);
+        drop(writer);
+
+        if header_len > 0 {
+            tmp.seek(SeekFrom::Start(0))?;
+            tmp.write_all(&entry_header_with_checksum(key, checksum))?;
+        }
+        if self.sync_writes {
+            tmp.as_file().sync_all()?;
+        }
+        tmp.persist(path).map_err(|e| e.error)?;
+
+        if self.sync_writes {
+            sync_dir(dir)?;
+        }
+        Ok(len)
+    }
+
+    /// Fill the empty temp file at `path` with direct I/O if enabled,
+    /// returning whether it was written
+    fn write_entry_direct(
+        &self,
+        path: &Path,
+        header: &[u8],
+        value: &[u8],
+    ) -> Result<bool, CacheError> {
+        #[cfg(target_os = "linux")]
+        if self.direct_io() {
+            match direct_io::write_file(path, &[header, value], self.sync_writes) {
+                Ok(()) => return Ok(true),
+                Err(e) if self.direct_io_failed(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = (path, header, value);
+
+        Ok(false)
+    }
+
+    /// Rewrite the entry file of `key` at `from` to `to` in the current
+    /// layout's format, adding or stripping the header
+    fn convert_entry_file(
+        &self,
+        from: &Path,
+        to: &Path,
+        key: &str,
+        headered: bool,
+    ) -> Result<(), CacheError> {
+        let data = Bytes::from(fs::read(from)?);
+        let value = if headered {
+            verify_entry(data, key).map_err(|e| match e {
+                EntryReadError::Io(e) => CacheError::Io(e),
+                EntryReadError::Corrupt(reason) => CacheError::Corruption(reason),
+            })?
+        } else {
+            data
+        };
+
+        self.write_entry_file(to, key, &value)?;
+        if from != to {
+            fs::remove_file(from)?;
+        }
+        Ok(())
+    }
+
+    fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(from, to)
+    }
+
+    fn load_persisted_index(&self) -> Vec<PersistedEntry> {
+        let index_path = self.cache_dir.join(INDEX_FILE_NAME);
+        let contents = match fs::read(&index_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                tracing::warn!("Failed to read disk cache index {:?}: {}", index_path, e);
+                return Vec::new();
+            }
+        };
+
+        match serde_json::from_slice::<PersistedIndex>(&contents) {
+            Ok(persisted) if persisted.version == INDEX_VERSION => persisted.entries,
+            Ok(persisted) => {
+                tracing::warn!(
+                    "Ignoring disk cache index with unsupported version {}",
+                    persisted.version
+                );
+                Vec::new()
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring corrupt disk cache index {:?}: {}", index_path, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Snapshot form of an index entry
+    fn persisted_entry(&self, key: &str, metadata: &CacheMetadata) -> Option<PersistedEntry> {
+        Some(PersistedEntry {
+            key: key.to_string(),
+            relative_path: self.relative_path(&metadata.file_path)?,
+            size: metadata.size,
+            created_at_ms: instant_to_unix_ms(metadata.created_at),
+            last_accessed_ms: Some(instant_to_unix_ms(metadata.last_accessed)),
+            access_count: metadata.access_count,
+            access_history_ms: metadata
+                .access_history
+                .iter()
+                .map(|&at| instant_to_unix_ms(at))
+                .collect(),
+        })
+    }
+
+    /// Write a snapshot of `index` and truncate the write-ahead log
+    ///
+    /// Callers must keep the index from changing until this returns, so no
+    /// logged mutation is lost between the snapshot and the truncation.
+    fn write_index(&self, index: &HashMap<StoreKey, CacheMetadata>) -> Result<(), CacheError> {
+        let entries = index
+            .iter()
+            .filter_map(|(key, metadata)| self.persisted_entry(key, metadata))
+            .collect();
+        let persisted = PersistedIndex {
+            version: INDEX_VERSION,
+            entries,
+        };
+        let contents =
+            serde_json::to_vec(&persisted).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        self.write_file_atomically(INDEX_FILE_NAME, &contents)?;
+        self.write_state()?;
+
+        // Everything logged so far is in the snapshot; a crash before the
+        // truncation only replays records that are already applied
+        if let Some(journal) = &self.journal {
+            let mut writer = journal.lock().unwrap_or_else(|e| e.into_inner());
+            writer.flush()?;
+            writer.get_ref().set_len(0)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace a file in the cache directory
+    ///
+    /// Write-then-rename so a crash never leaves a truncated file behind.
+    fn write_file_atomically(&self, name: &str, contents: &[u8]) -> Result<(), CacheError> {
+        let path = self.cache_dir.join(name);
+        let tmp_path = self.cache_dir.join(format!("{}.tmp", name));
+        {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(contents)?;
+            if self.sync_writes {
+                file.sync_all()?;
+            }
+        }
+        fs::rename(&tmp_path, &path)?;
+        if self.sync_writes {
+            sync_dir(&self.cache_dir)?;
+        }
+        Ok(())
+    }
+
+    fn write_state(&self) -> Result<(), CacheError> {
+        let state = PersistedState {
+            version: INDEX_VERSION,
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            corrupted: self.stats.corrupted.load(Ordering::Relaxed),
+        };
+        let contents =
+            serde_json::to_vec(&state).map_err(|e| CacheError::Serialization(e.to_string()))?;
+        self.write_file_atomically(STATE_FILE_NAME, &contents)
+    }
+
+    /// Restore the counters of a previous instance
+    fn load_state(&self) {
+        let state_path = self.cache_dir.join(STATE_FILE_NAME);
+        let contents = match fs::read(&state_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => {
+                tracing::warn!("Failed to read disk cache state {:?}: {}", state_path, e);
+                return;
+            }
+        };
+
+        match serde_json::from_slice::<PersistedState>(&contents) {
+            Ok(state) if state.version == INDEX_VERSION => {
+                self.stats.hits.store(state.hits, Ordering::Relaxed);
+                self.stats.misses.store(state.misses, Ordering::Relaxed);
+                self.stats
+                    .corrupted
+                    .store(state.corrupted, Ordering::Relaxed);
+            }
+            Ok(state) => tracing::warn!(
+                "Ignoring disk cache state with unsupported version {}",
+                state.version
+            ),
+            Err(e) => tracing::warn!("Ignoring corrupt disk cache state {:?}: {}", state_path, e),
+        }
+    }
+
+    /// Save the index so the next `DiskCache` on this directory can reuse its files
+    ///
+    /// Hit, miss and corruption counters and per-entry access times are saved
+    /// too, so eviction order and long-term statistics survive restarts.
+    ///
+    /// Also runs automatically when the cache is dropped. With journaling the
+    /// snapshot also truncates the write-ahead log, so long-running processes
+    /// should call this periodically (see [`DiskCache::spawn_persister`]) to
+    /// bound the log and restart time.
+    pub async fn persist_index(&self) -> Result<(), CacheError> {
+        let mut index = self.index.write().await;
+        self.apply_pending_accesses(&mut index);
+        self.write_index(&index)
+    }
+
+    /// Spawn a background task that calls [`DiskCache::persist_index`]
+    /// every `interval`
+    ///
+    /// The task holds only a weak reference and exits once the cache is
+    /// dropped.
+    pub fn spawn_persister(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
+
+            loop {
+                ticker.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                if let Err(e) = cache.persist_index().await {
+                    tracing::warn!("Failed to persist disk cache index: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// Reconcile the cache directory with the index
+    ///
+    /// Deletes entry files the index does not reference (left behind by
+    /// crashes or bugs, and invisible to `size()` and eviction) and leftover
+    /// temp files, and drops index entries whose file disappeared. Files
+    /// modified less than `min_age` ago are left alone, since a concurrent
+    /// write may not have reached the index yet.
+    pub async fn collect_garbage(&self, min_age: Duration) -> Result<DiskGcReport, CacheError> {
+        let mut report = DiskGcReport::default();
+
+        // Walk the directory without holding the lock
+        let (files, stale_temp_files) = self.entry_files(&self.cache_dir, min_age)?;
+        report.stale_temp_files = stale_temp_files;
+        let on_disk: HashSet<&PathBuf> = files.iter().collect();
+
+        let mut index = self.index.write().await;
+
+        let indexed: HashSet<&PathBuf> = index.values().map(|m| &m.file_path).collect();
+        for path in &files {
+            if indexed.contains(path) {
+                continue;
+            }
+            let Ok(file_metadata) = fs::metadata(path) else {
+                continue;
+            };
+            if file_age(path) < min_age {
+                continue;
+            }
+            match fs::remove_file(path) {
+                Ok(()) => {
+                    report.orphaned_files += 1;
+                    report.orphaned_bytes += file_metadata.len();
+                }
+                Err(e) => tracing::warn!("Failed to remove orphaned cache file {:?}: {}", path, e),
+            }
+        }
+
+        // Entries written after the walk are not in `on_disk` but still exist
+        let dangling: Vec<StoreKey> = index
+            .iter()
+            .filter(|(_, metadata)| {
+                !on_disk.contains(&metadata.file_path) && !metadata.file_path.exists()
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &dangling {
+            if let Some(metadata) = index.remove(key) {
+                self.log_removal(key);
+                self.current_size
+                    .fetch_sub(metadata.size, Ordering::Relaxed);
+            }
+        }
+        report.dangling_entries = dangling.len();
 
-        Ok(cache)
+        if report != DiskGcReport::default() {
+            tracing::info!("Disk cache garbage collection: {:?}", report);
+        }
+
+        Ok(report)
     }
 
-    fn initialize_from_disk(&self) -> Result<(), CacheError> {
-        // Clean slate approach: remove all existing cache files on startup
-        // This prevents dangling files and ensures consistent state
-        // Data source of truth remains in S3, cache is truly temporary
-        if self.cache_dir.exists() {
-            if let Err(e) = fs::remove_dir_all(&self.cache_dir) {
-                tracing::warn!("Failed to clean cache directory on startup: {}", e);
-                // Continue anyway - create_dir_all will handle it
-            }
-        }
+    /// Spawn a background task that runs [`DiskCache::collect_garbage`]
+    /// every `interval`
+    ///
+    /// The task holds only a weak reference and exits once the cache is
+    /// dropped.
+    pub fn spawn_garbage_collector(
+        self: &Arc<Self>,
+        interval: Duration,
+        min_age: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::downgrade(self);
 
-        // Recreate clean cache directory
-        fs::create_dir_all(&self.cache_dir)?;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
 
-        tracing::info!("Initialized clean disk cache at: {:?}", self.cache_dir);
-        Ok(())
+            loop {
+                ticker.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                if let Err(e) = cache.collect_garbage(min_age).await {
+                    tracing::warn!("Disk cache garbage collection failed: {:?}", e);
+                }
+            }
+        })
+    }
+
+    /// Entry files are named after a BLAKE3 hash of the key, so distinct keys
+    /// never share a file; the key itself is kept in the entry header.
+    /// Under the hierarchy layout the key is the path, and keys that cannot
+    /// be stored are rejected.
+    fn key_to_path(&self, key: &str) -> Result<PathBuf, CacheError> {
+        let hash = blake3::hash(key.as_bytes()).to_hex();
+        let file_name = format!("{}.cache", hash);
+        match self.layout {
+            DiskLayout::Flat => Ok(self.cache_dir.join(file_name)),
+            DiskLayout::FanOut => Ok(self
+                .cache_dir
+                .join(&hash[0..2])
+                .join(&hash[2..4])
+                .join(file_name)),
+            DiskLayout::Hierarchy => self.hierarchy_path(key),
+        }
     }
 
-    fn key_to_path(&self, key: &StoreKey) -> PathBuf {
-        // Convert key to safe filename
-        let safe_key = key.replace(['/', '\\'], "_");
-        self.cache_dir.join(format!("{}.cache", safe_key))
+    fn hierarchy_path(&self, key: &str) -> Result<PathBuf, CacheError> {
+        let invalid = |reason: &str| CacheError::InvalidKey(format!("{:?} {}", key, reason));
+        if RESERVED_FILE_NAMES.contains(&key) {
+            return Err(invalid("clashes with a disk cache index file"));
+        }
+        if key.ends_with(".tmp") {
+            return Err(invalid("clashes with disk cache temp files"));
+        }
+
+        let mut path = self.cache_dir.clone();
+        for segment in key.split('/') {
+            // Exactly one plain file name: no `.`, `..`, roots or separators
+            let mut components = Path::new(segment).components();
+            match (components.next(), components.next()) {
+                (Some(std::path::Component::Normal(name)), None) if name == segment => {
+                    path.push(segment)
+                }
+                _ => return Err(invalid("is not a relative path")),
+            }
+        }
+        Ok(path)
     }
 
     fn is_expired(&self, metadata: &CacheMetadata) -> bool {
-        if let Some(ttl) = self.ttl {
-            metadata.created_at.elapsed() > ttl
+        if let Some(ttl) = self.ttl.get() {
+            metadata.expires_from.elapsed() > ttl
         } else {
             false
         }
     }
 
-    async fn cleanup_expired(&self) -> Result<(), CacheError> {
-        if self.ttl.is_none() {
-            return Ok(());
+    /// Queue a freshly written entry for expiry
+    fn schedule_expiry(&self, key: &str, expires_from: Instant) {
+        if self.ttl.get().is_none() {
+            return;
         }
+        self.expiry_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Reverse((expires_from, key.to_string())));
+    }
 
-        let mut index = self.index.write().await;
-        let mut expired_keys = Vec::new();
+    /// Remove expired entries, taking only those due from the expiry queue
+    /// instead of scanning the index
+    async fn remove_expired(&self) -> Result<usize, CacheError> {
+        let Some(ttl) = self.ttl.get() else {
+            return Ok(0);
+        };
 
-        // Collect expired keys
-        for (key, metadata) in index.iter() {
-            if self.is_expired(metadata) {
-                expired_keys.push(key.clone());
+        let mut due = Vec::new();
+        {
+            let mut queue = self.expiry_queue.lock().unwrap_or_else(|e| e.into_inner());
+            while let Some(Reverse((expires_from, _))) = queue.peek() {
+                if expires_from.elapsed() <= ttl {
+                    break;
+                }
+                if let Some(Reverse(entry)) = queue.pop() {
+                    due.push(entry);
+                }
             }
         }
+        if due.is_empty() {
+            return Ok(0);
+        }
 
-        // Remove expired entries
-        for key in expired_keys {
+        let mut index = self.index.write().await;
+        let mut removed = 0;
+        for (expires_from, key) in due {
+            // Skip entries removed or rewritten since they were queued
+            if index
+                .get(&key)
+                .is_none_or(|metadata| metadata.expires_from != expires_from)
+            {
+                continue;
+            }
             if let Some(metadata) = index.remove(&key) {
+                self.log_removal(&key);
                 // Remove file
-                if let Err(e) = fs::remove_file(&metadata.file_path) {
+                if let Err(e) = self.remove_entry_file(&metadata.file_path) {
                     tracing::warn!(
                         "Failed to remove expired cache file {:?}: {}",
                         metadata.file_path,
@@ -122,145 +1870,836 @@ impl DiskCache {
                 }
                 self.current_size
                     .fetch_sub(metadata.size, Ordering::Relaxed);
+                removed += 1;
             }
         }
+        self.stats
+            .removals
+            .record(RemovalCause::Expired, removed as u64);
 
-        Ok(())
+        Ok(removed)
     }
 
-    async fn evict_if_needed(&self, incoming_size: usize) -> Result<(), CacheError> {
-        let Some(max_size) = self.max_size_bytes else {
-            return Ok(());
+    /// Spawn a background task that removes expired entries every `interval`
+    ///
+    /// Reads never scan for expired entries themselves, so without this
+    /// task expired entries that are not read again keep their disk space
+    /// until they are evicted or [`Cache::purge_expired`] is called. The
+    /// task holds only a weak reference and exits once the cache is dropped.
+    pub fn spawn_expiry_sweeper(
+        self: &Arc<Self>,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
+
+            loop {
+                ticker.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                match cache.remove_expired().await {
+                    Ok(0) => {}
+                    Ok(removed) => {
+                        tracing::debug!("Removed {} expired disk cache entries", removed)
+                    }
+                    Err(e) => tracing::warn!("Disk cache expiry sweep failed: {:?}", e),
+                }
+            }
+        })
+    }
+
+    /// Serve `key`, using the raw file contents in `prefetched` if a batch
+    /// read already fetched them
+    ///
+    /// The index is only read-locked to find the entry; the file is read
+    /// without holding the lock, and the access is buffered for
+    /// [`DiskCache::apply_pending_accesses`]. Expired entries are dropped
+    /// unless `stale`, in which case they are served and left in place.
+    async fn lookup(
+        &self,
+        key: &str,
+        prefetched: Option<std::io::Result<Bytes>>,
+        stale: bool,
+    ) -> Option<Bytes> {
+        if !self.may_contain(key) {
+            self.stats.filtered.fetch_add(1, Ordering::Relaxed);
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let Some(metadata) = self.index.read().await.get(key).cloned() else {
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+
+        if !stale && self.is_expired(&metadata) {
+            if self.discard_entry(key, &metadata, "expired").await {
+                self.stats.removals.record(RemovalCause::Expired, 1);
+            }
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        // Read file
+        let read = match prefetched {
+            Some(Ok(data)) => self.decode_entry(data, key, metadata.size),
+            Some(Err(e)) => Err(EntryReadError::Io(e)),
+            None => self.read_entry_file(key, &metadata),
         };
+        match read {
+            Ok(data) => {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                if self.buffer_access(key) {
+                    let mut index = self.index.write().await;
+                    self.apply_pending_accesses(&mut index);
+                }
+                Some(data)
+            }
+            Err(e) => {
+                // A concurrent write may have replaced the entry since the
+                // lookup, in which case its file is left alone
+                match e {
+                    EntryReadError::Io(e) => {
+                        tracing::warn!("Failed to read cache file {:?}: {}", metadata.file_path, e);
+                        self.discard_entry(key, &metadata, "unreadable").await;
+                    }
+                    EntryReadError::Corrupt(reason) => {
+                        if self.discard_entry(key, &metadata, "corrupt").await {
+                            tracing::warn!(
+                                "Discarded corrupt cache file {:?}: {}",
+                                metadata.file_path,
+                                reason
+                            );
+                            self.stats.corrupted.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
 
+    /// Remove `key` and its file if the index still holds the entry `seen`,
+    /// returning whether it did
+    async fn discard_entry(&self, key: &str, seen: &CacheMetadata, reason: &str) -> bool {
         let mut index = self.index.write().await;
+        if !index
+            .get(key)
+            .is_some_and(|current| current.same_entry(seen))
+        {
+            return false;
+        }
 
-        while self.current_size.load(Ordering::Relaxed) + incoming_size > max_size as usize {
-            // Find least recently accessed item
-            let lru_key = index
-                .iter()
-                .min_by_key(|(_, metadata)| metadata.last_accessed)
-                .map(|(key, _)| key.clone());
+        index.remove(key);
+        self.log_removal(key);
+        if let Err(e) = self.remove_entry_file(&seen.file_path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(
+                    "Failed to remove {} cache file {:?}: {}",
+                    reason,
+                    seen.file_path,
+                    e
+                );
+            }
+        }
+        self.current_size.fetch_sub(seen.size, Ordering::Relaxed);
+        true
+    }
+
+    /// Note a hit on `key`, returning whether the buffer is due to be applied
+    fn buffer_access(&self, key: &str) -> bool {
+        let mut pending = self
+            .pending_accesses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pending.push((key.to_string(), Instant::now()));
+        pending.len() >= ACCESS_BATCH_SIZE
+    }
+
+    /// Apply buffered accesses to the index and journal them
+    ///
+    /// Must be called while holding the index write lock, and before
+    /// anything that depends on access order, such as eviction.
+    fn apply_pending_accesses(&self, index: &mut HashMap<StoreKey, CacheMetadata>) {
+        let pending = std::mem::take(
+            &mut *self
+                .pending_accesses
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        );
+        let history_len = self.access_history_len();
+        let mut queue = self.eviction_queue();
+        for (key, at) in pending {
+            let Some(metadata) = index.get_mut(&key) else {
+                continue;
+            };
+            metadata.record_access(at, history_len);
+            queue.push(Reverse((self.eviction_rank(metadata), key.clone())));
+            let access = JournalRecord::Access {
+                key,
+                at_ms: instant_to_unix_ms(at),
+            };
+            if let Err(e) = self.log(&access, false) {
+                tracing::warn!("Failed to journal cache access: {:?}", e);
+            }
+        }
+        self.compact_eviction_queue(&mut queue, index);
+    }
+
+    /// Add a freshly written entry file to the index
+    async fn record_entry(
+        &self,
+        key: &str,
+        file_path: PathBuf,
+        value_size: usize,
+    ) -> Result<(), CacheError> {
+        let created_at = Instant::now();
+        let expires_from = self.ttl.expiry_base(created_at);
+        let metadata = CacheMetadata::new(file_path, value_size, created_at, expires_from);
+
+        // Update index
+        let mut index = self.index.write().await;
+
+        // Remove old entry if it exists
+        if let Some(old_metadata) = index.remove(key) {
+            self.current_size
+                .fetch_sub(old_metadata.size, Ordering::Relaxed);
+            // Old file was overwritten
+            self.forget_handle(&old_metadata.file_path);
+        }
+
+        if let Some(entry) = self.persisted_entry(key, &metadata) {
+            self.log(&JournalRecord::Insert(entry), true)?;
+        }
+        self.add_to_key_filter(key, &index);
+        let rank = self.eviction_rank(&metadata);
+        index.insert(key.to_string(), metadata);
+        self.current_size.fetch_add(value_size, Ordering::Relaxed);
+        self.schedule_expiry(key, expires_from);
+        let mut queue = self.eviction_queue();
+        queue.push(Reverse((rank, key.to_string())));
+        self.compact_eviction_queue(&mut queue, &index);
+
+        Ok(())
+    }
+
+    /// Write several entries at once
+    ///
+    /// With the io_uring backend all file writes of the batch are submitted
+    /// together. Returns one result per entry, in order.
+    pub async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        let paths: Vec<Result<PathBuf, CacheError>> = entries
+            .iter()
+            .map(|(key, _)| self.key_to_path(key))
+            .collect();
+        let total_size = entries
+            .iter()
+            .zip(&paths)
+            .filter(|(_, path)| path.is_ok())
+            .map(|((_, value), _)| value.len())
+            .sum();
+        if self.evict_if_needed(total_size).await.is_err() {
+            // The batch does not fit as a whole; let entries evict each other
+            let mut results = Vec::with_capacity(entries.len());
+            for (key, value) in entries {
+                results.push(self.set(&key, value).await);
+            }
+            return results;
+        }
+
+        let mut results: Vec<Option<Result<(), CacheError>>> = Vec::with_capacity(entries.len());
+        let mut jobs: Vec<(PathBuf, &StoreKey, &Bytes)> = Vec::new();
+        for ((key, value), path) in entries.iter().zip(paths) {
+            match path {
+                Ok(path) => {
+                    jobs.push((path, key, value));
+                    results.push(None);
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+        let written = self.write_entry_files(&jobs);
+
+        let mut recorded = Vec::with_capacity(jobs.len());
+        for ((file_path, key, value), result) in jobs.into_iter().zip(written) {
+            recorded.push(match result {
+                Ok(()) => self.record_entry(key, file_path, value.len()).await,
+                Err(e) => Err(e),
+            });
+        }
+        let mut recorded = recorded.into_iter();
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| recorded.next().expect("one result per job")))
+            .collect()
+    }
+
+    /// Store the value of `key` as written by `write`, streaming it into the
+    /// entry file instead of taking it as one buffer
+    ///
+    /// Suits values produced piecewise, such as compressor output, that are
+    /// too large to be worth holding in memory whole. `write` runs on the
+    /// blocking thread pool. `size_hint` is the expected value size, for
+    /// making room beforehand; the entry is accounted at the size actually
+    /// written. Streamed writes bypass direct I/O.
+    pub async fn set_streaming<F>(
+        self: &Arc<Self>,
+        key: &str,
+        size_hint: usize,
+        write: F,
+    ) -> Result<(), CacheError>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<(), CacheError> + Send + 'static,
+    {
+        let file_path = self.key_to_path(key)?;
+        self.evict_if_needed(size_hint).await?;
+
+        let cache = self.clone();
+        let (path, entry_key) = (file_path.clone(), key.to_string());
+        let value_size = tokio::task::spawn_blocking(move || {
+            cache.write_entry_file_streamed(&path, &entry_key, write)
+        })
+        .await
+        .map_err(|e| CacheError::Io(std::io::Error::other(e)))??;
+
+        self.record_entry(key, file_path, value_size).await
+    }
+
+    /// Look up several entries at once
+    ///
+    /// With the io_uring backend the entry files are read in one batch.
+    /// Returns one result per key, in order.
+    pub async fn get_many(&self, keys: &[StoreKey]) -> Vec<Option<Bytes>> {
+        let prefetched = self.read_entry_files(keys);
+
+        let mut results = Vec::with_capacity(keys.len());
+        for (key, prefetched) in keys.iter().zip(prefetched) {
+            results.push(self.lookup(key, prefetched, false).await);
+        }
+        results
+    }
+
+    /// Whether an unexpired entry for `key` is indexed
+    ///
+    /// Neither reads the entry file nor counts as a hit or miss.
+    pub async fn contains_key(&self, key: &str) -> bool {
+        self.may_contain(key)
+            && self
+                .index
+                .read()
+                .await
+                .get(key)
+                .is_some_and(|metadata| !self.is_expired(metadata))
+    }
 
-            if let Some(key) = lru_key {
-                if let Some(metadata) = index.remove(&key) {
-                    // Remove file
-                    if let Err(e) = fs::remove_file(&metadata.file_path) {
+    /// Up to `limit` unexpired keys with their value sizes, those the
+    /// eviction policy would keep longest first
+    ///
+    /// Access counts and times survive restarts with the index, so this
+    /// ranks entries by their use before the cache was reopened.
+    pub async fn hottest_keys(&self, limit: usize) -> Vec<(StoreKey, usize)> {
+        let mut index = self.index.write().await;
+        self.apply_pending_accesses(&mut index);
+
+        let mut ranked: Vec<((u64, Instant), StoreKey, usize)> = index
+            .iter()
+            .filter(|(_, metadata)| !self.is_expired(metadata))
+            .map(|(key, metadata)| (self.eviction_rank(metadata), key.clone(), metadata.size))
+            .collect();
+        drop(index);
+        ranked.sort_unstable_by_key(|(rank, _, _)| std::cmp::Reverse(*rank));
+
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(_, key, size)| (key, size))
+            .collect()
+    }
+
+    /// Keys of unexpired entries not accessed for at least `idle`
+    pub async fn idle_keys(&self, idle: Duration) -> Vec<StoreKey> {
+        let mut index = self.index.write().await;
+        self.apply_pending_accesses(&mut index);
+        index
+            .iter()
+            .filter(|(_, metadata)| {
+                metadata.last_accessed.elapsed() >= idle && !self.is_expired(metadata)
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Every entry, expired or not, sorted by key
+    pub async fn entries(&self) -> Vec<DiskEntryInfo> {
+        let mut index = self.index.write().await;
+        self.apply_pending_accesses(&mut index);
+        let mut entries: Vec<DiskEntryInfo> = index
+            .iter()
+            .map(|(key, metadata)| DiskEntryInfo {
+                key: key.clone(),
+                size: metadata.size,
+                age: metadata.created_at.elapsed(),
+                idle: metadata.last_accessed.elapsed(),
+                access_count: metadata.access_count,
+                expired: self.is_expired(metadata),
+                file_path: metadata.file_path.clone(),
+            })
+            .collect();
+        drop(index);
+        entries.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+        entries
+    }
+
+    /// Read every entry file and discard the entries that fail
+    /// verification, as a read of them would
+    ///
+    /// Entries written or removed while the pass runs are left alone, and
+    /// the reported keys are sorted.
+    /// Neither counts as a hit or miss; discarded corrupt entries count
+    /// towards [`DiskCache::corrupted_entries`].
+    pub async fn verify(&self) -> DiskVerifyReport {
+        let entries: Vec<(StoreKey, CacheMetadata)> = self
+            .index
+            .read()
+            .await
+            .iter()
+            .map(|(key, metadata)| (key.clone(), metadata.clone()))
+            .collect();
+
+        let mut report = DiskVerifyReport::default();
+        for (key, metadata) in entries {
+            report.checked += 1;
+            match self.read_entry_file(&key, &metadata) {
+                Ok(_) => {}
+                Err(EntryReadError::Io(e)) => {
+                    tracing::warn!("Failed to read cache file {:?}: {}", metadata.file_path, e);
+                    if self.discard_entry(&key, &metadata, "unreadable").await {
+                        report.unreadable.push(key);
+                    }
+                }
+                Err(EntryReadError::Corrupt(reason)) => {
+                    if self.discard_entry(&key, &metadata, "corrupt").await {
                         tracing::warn!(
-                            "Failed to remove cache file {:?}: {}",
+                            "Discarded corrupt cache file {:?}: {}",
                             metadata.file_path,
-                            e
+                            reason
                         );
+                        self.stats.corrupted.fetch_add(1, Ordering::Relaxed);
+                        report.corrupt.push(key);
                     }
-                    self.current_size
-                        .fetch_sub(metadata.size, Ordering::Relaxed);
+                }
+            }
+        }
+        report.corrupt.sort_unstable();
+        report.unreadable.sort_unstable();
+        report
+    }
+
+    /// Record accesses served elsewhere, such as by a memory tier in front of
+    /// this cache, so eviction and [`DiskCache::hottest_keys`] see them
+    ///
+    /// Keys not in the cache, and accesses no newer than the last one
+    /// recorded for their entry, are ignored.
+    pub async fn record_accesses(&self, accesses: Vec<(StoreKey, Instant)>) {
+        let mut index = self.index.write().await;
+        self.apply_pending_accesses(&mut index);
+        self.pending_accesses
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(accesses.into_iter().filter(|(key, at)| {
+                index
+                    .get(key)
+                    .is_some_and(|metadata| *at > metadata.last_accessed)
+            }));
+        self.apply_pending_accesses(&mut index);
+    }
+
+    fn write_entry_files(
+        &self,
+        jobs: &[(PathBuf, &StoreKey, &Bytes)],
+    ) -> Vec<Result<(), CacheError>> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = self.uring.as_ref().filter(|_| !self.direct_io()) {
+            self.count_io(jobs.len());
+            return self.write_entry_files_uring(uring, jobs);
+        }
+
+        jobs.iter()
+            .map(|(path, key, value)| self.write_entry_file(path, key, value))
+            .collect()
+    }
+
+    /// Raw contents of the entry files for `keys`, or `None` for files to be
+    /// read on lookup instead
+    fn read_entry_files(&self, keys: &[StoreKey]) -> Vec<Option<std::io::Result<Bytes>>> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(uring) = self.uring.as_ref().filter(|_| !self.direct_io()) {
+            let read = self.read_entry_files_uring(uring, keys);
+            // Files left to the lookup are counted when it reads them
+            self.count_io(read.iter().filter(|read| read.is_some()).count());
+            return read;
+        }
+
+        keys.iter().map(|_| None).collect()
+    }
+
+    /// Batched form of [`DiskCache::write_entry_file`]
+    ///
+    /// Temp files are created up front; the data writes and fsyncs of the
+    /// whole batch then go through the ring before each file is renamed into
+    /// place.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    fn write_entry_files_uring(
+        &self,
+        uring: &UringIo,
+        jobs: &[(PathBuf, &StoreKey, &Bytes)],
+    ) -> Vec<Result<(), CacheError>> {
+        use std::os::fd::AsRawFd;
+
+        let prepared: Vec<Result<(tempfile::NamedTempFile, Vec<u8>), CacheError>> = jobs
+            .iter()
+            .map(|(path, key, value)| {
+                let dir = path.parent().unwrap_or(&self.cache_dir);
+                fs::create_dir_all(dir)?;
+                let tmp = tempfile::Builder::new()
+                    .prefix(".entry-")
+                    .suffix(".tmp")
+                    .tempfile_in(dir)?;
+                Ok((tmp, self.entry_header(key, value)))
+            })
+            .collect();
+
+        let mut failures: Vec<Option<std::io::Error>> = jobs.iter().map(|_| None).collect();
+        let run = |ops: Vec<(usize, UringOp)>, failures: &mut [Option<std::io::Error>]| {
+            let (owners, ops): (Vec<usize>, Vec<UringOp>) = ops.into_iter().unzip();
+            // SAFETY: the temp files, headers and values outlive the call
+            let results = unsafe { uring.run(&ops) };
+            for (job, result) in owners.into_iter().zip(results) {
+                if let Err(e) = result {
+                    failures[job].get_or_insert(e);
+                }
+            }
+        };
+
+        let mut writes = Vec::new();
+        for (job, prepared) in prepared.iter().enumerate() {
+            let Ok((tmp, header)) = prepared else {
+                continue;
+            };
+            let fd = tmp.as_file().as_raw_fd();
+            let value = jobs[job].2;
+            writes.push((
+                job,
+                UringOp::Write {
+                    fd,
+                    buf: header.as_ptr(),
+                    len: header.len(),
+                    offset: 0,
+                },
+            ));
+            if !value.is_empty() {
+                writes.push((
+                    job,
+                    UringOp::Write {
+                        fd,
+                        buf: value.as_ptr(),
+                        len: value.len(),
+                        offset: header.len() as u64,
+                    },
+                ));
+            }
+        }
+        run(writes, &mut failures);
+
+        if self.sync_writes {
+            let syncs = prepared
+                .iter()
+                .enumerate()
+                .filter(|(job, _)| failures[*job].is_none())
+                .filter_map(|(job, prepared)| {
+                    let (tmp, _) = prepared.as_ref().ok()?;
+                    let fd = tmp.as_file().as_raw_fd();
+                    Some((job, UringOp::Fsync { fd }))
+                })
+                .collect();
+            run(syncs, &mut failures);
+        }
+
+        let mut synced_dirs = HashSet::new();
+        prepared
+            .into_iter()
+            .zip(failures)
+            .zip(jobs)
+            .map(|((prepared, failure), (path, _, _))| {
+                let (tmp, _) = prepared?;
+                if let Some(e) = failure {
+                    return Err(e.into());
+                }
+                tmp.persist(path).map_err(|e| e.error)?;
+
+                let dir = path.parent().unwrap_or(&self.cache_dir);
+                if self.sync_writes && synced_dirs.insert(dir.to_path_buf()) {
+                    sync_dir(dir)?;
+                }
+                Ok(())
+            })
+            .collect()
+    }
+
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    fn read_entry_files_uring(
+        &self,
+        uring: &UringIo,
+        keys: &[StoreKey],
+    ) -> Vec<Option<std::io::Result<Bytes>>> {
+        use std::os::fd::AsRawFd;
+
+        /// Open file and its read buffer; `None` if left to the lookup
+        type Opened = Option<std::io::Result<(fs::File, Vec<u8>)>>;
+
+        let mut opened: Vec<Opened> = keys
+            .iter()
+            .map(|key| {
+                // Keys the layout cannot store are never indexed
+                let path = self.key_to_path(key).ok()?;
+                let file = match fs::File::open(path) {
+                    Ok(file) => file,
+                    Err(e) => return Some(Err(e)),
+                };
+                let len = match file.metadata() {
+                    Ok(metadata) => metadata.len(),
+                    Err(e) => return Some(Err(e)),
+                };
+                // Large files are mapped by the regular read path instead
+                if self
+                    .mmap_threshold_bytes
+                    .is_some_and(|threshold| len >= threshold)
+                {
+                    return None;
+                }
+                Some(Ok((file, vec![0u8; len as usize])))
+            })
+            .collect();
+
+        let (owners, ops): (Vec<usize>, Vec<UringOp>) = opened
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, opened)| match opened {
+                Some(Ok((file, buf))) if !buf.is_empty() => Some((
+                    index,
+                    UringOp::Read {
+                        fd: file.as_raw_fd(),
+                        buf: buf.as_mut_ptr(),
+                        len: buf.len(),
+                        offset: 0,
+                    },
+                )),
+                _ => None,
+            })
+            .unzip();
+        // SAFETY: the files and buffers outlive the call and are not
+        // otherwise accessed until it returns
+        let results = unsafe { uring.run(&ops) };
+
+        let mut failures: Vec<Option<std::io::Error>> = keys.iter().map(|_| None).collect();
+        for (index, result) in owners.into_iter().zip(results) {
+            if let Err(e) = result {
+                failures[index] = Some(e);
+            }
+        }
+
+        opened
+            .into_iter()
+            .zip(failures)
+            .map(|(opened, failure)| {
+                let opened = opened?;
+                Some(match (opened, failure) {
+                    (Err(e), _) | (Ok(_), Some(e)) => Err(e),
+                    (Ok((_, buf)), None) => Ok(Bytes::from(buf)),
+                })
+            })
+            .collect()
+    }
+
+    /// Accesses each entry remembers for the eviction policy
+    fn access_history_len(&self) -> usize {
+        match self.eviction_policy {
+            DiskEvictionPolicy::LruK { k } => k.clamp(1, MAX_ACCESS_HISTORY),
+            DiskEvictionPolicy::Lru | DiskEvictionPolicy::Lfu => 1,
+        }
+    }
+
+    /// Sort key of an entry under the eviction policy; lowest goes first
+    fn eviction_rank(&self, metadata: &CacheMetadata) -> (u64, Instant) {
+        match self.eviction_policy {
+            DiskEvictionPolicy::Lru => (0, metadata.last_accessed),
+            DiskEvictionPolicy::LruK { .. } => {
+                let k = self.access_history_len();
+                if metadata.access_history.len() >= k {
+                    (
+                        1,
+                        metadata.access_history[metadata.access_history.len() - k],
+                    )
                 } else {
-                    break; // No more items to evict
+                    (0, metadata.last_accessed)
                 }
-            } else {
-                return Err(CacheError::CacheFull);
             }
+            DiskEvictionPolicy::Lfu => (metadata.access_count, metadata.last_accessed),
         }
+    }
 
-        Ok(())
+    fn eviction_queue(&self) -> MutexGuard<'_, EvictionQueue> {
+        self.eviction_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
     }
-}
 
-#[async_trait::async_trait]
-impl Cache for DiskCache {
-    async fn get(&self, key: &StoreKey) -> Option<Bytes> {
-        // Clean up expired entries periodically
-        if let Err(e) = self.cleanup_expired().await {
-            tracing::warn!("Failed to cleanup expired entries: {:?}", e);
+    /// Eviction queue of every index entry at its current rank
+    fn rank_entries(&self, index: &HashMap<StoreKey, CacheMetadata>) -> EvictionQueue {
+        index
+            .iter()
+            .map(|(key, metadata)| Reverse((self.eviction_rank(metadata), key.clone())))
+            .collect()
+    }
+
+    /// Rebuild `queue` from the index once outdated entries pile up
+    fn compact_eviction_queue(
+        &self,
+        queue: &mut EvictionQueue,
+        index: &HashMap<StoreKey, CacheMetadata>,
+    ) {
+        if queue.len() > 2 * index.len() + EVICTION_QUEUE_SLACK {
+            *queue = self.rank_entries(index);
+        }
+    }
+
+    /// Maximum size in bytes, if bounded
+    fn max_size(&self) -> Option<u64> {
+        match self.max_size_bytes.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            max_size => Some(max_size),
+        }
+    }
+
+    async fn evict_if_needed(&self, incoming_size: usize) -> Result<(), CacheError> {
+        let Some(max_size) = self.max_size() else {
+            return Ok(());
+        };
+        let max_size = max_size as usize;
+        if incoming_size > max_size {
+            return Err(CacheError::EntryTooLarge {
+                size: incoming_size,
+                max: max_size,
+            });
         }
 
         let mut index = self.index.write().await;
+        if self.current_size.load(Ordering::Relaxed) + incoming_size <= max_size {
+            return Ok(());
+        }
+        self.apply_pending_accesses(&mut index);
+
+        // Once eviction starts, make room for more than this one entry
+        let batch = (max_size as f64 * self.eviction_batch_fraction.clamp(0.0, 1.0)) as usize;
+        let target = max_size.saturating_sub(batch).max(incoming_size);
 
-        if let Some(metadata) = index.get(key).cloned() {
-            // Check if expired
-            if self.is_expired(&metadata) {
-                // Remove expired entry
-                index.remove(key);
-                if let Err(e) = fs::remove_file(&metadata.file_path) {
+        let mut queue = self.eviction_queue();
+        let mut rebuilt = false;
+        let mut evicted = 0;
+        while self.current_size.load(Ordering::Relaxed) + incoming_size > target {
+            let Some(Reverse((rank, key))) = queue.pop() else {
+                // Every entry is queued, so this only helps if one was missed
+                if rebuilt {
+                    break;
+                }
+                *queue = self.rank_entries(&index);
+                rebuilt = true;
+                continue;
+            };
+            // Removed or re-ranked since being queued
+            if index
+                .get(&key)
+                .is_none_or(|metadata| self.eviction_rank(metadata) != rank)
+            {
+                continue;
+            }
+            if let Some(metadata) = index.remove(&key) {
+                self.log_removal(&key);
+                // Remove file
+                if let Err(e) = self.remove_entry_file(&metadata.file_path) {
                     tracing::warn!(
-                        "Failed to remove expired cache file {:?}: {}",
+                        "Failed to remove cache file {:?}: {}",
                         metadata.file_path,
                         e
                     );
                 }
                 self.current_size
                     .fetch_sub(metadata.size, Ordering::Relaxed);
-                self.stats.misses.fetch_add(1, Ordering::Relaxed);
-                return None;
+                evicted += 1;
             }
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Evicted, evicted as u64);
+        tracing::debug!("Evicted {} disk cache entries", evicted);
 
-            // Update last accessed time
-            let mut updated_metadata = metadata.clone();
-            updated_metadata.last_accessed = Instant::now();
-            index.insert(key.clone(), updated_metadata);
-
-            // Read file
-            match fs::read(&metadata.file_path) {
-                Ok(data) => {
-                    self.stats.hits.fetch_add(1, Ordering::Relaxed);
-                    Some(Bytes::from(data))
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to read cache file {:?}: {}", metadata.file_path, e);
-                    // Remove invalid entry
-                    index.remove(key);
-                    self.current_size
-                        .fetch_sub(metadata.size, Ordering::Relaxed);
-                    self.stats.misses.fetch_add(1, Ordering::Relaxed);
-                    None
-                }
-            }
-        } else {
-            self.stats.misses.fetch_add(1, Ordering::Relaxed);
-            None
+        if self.current_size.load(Ordering::Relaxed) + incoming_size > max_size {
+            return Err(CacheError::CacheFull);
         }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for DiskCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, None, false).await
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, None, true).await
     }
 
-    async fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), CacheError> {
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
         let value_size = value.len();
 
+        let file_path = self.key_to_path(key)?;
+
         // Check if we need to evict
         self.evict_if_needed(value_size).await?;
 
-        let file_path = self.key_to_path(key);
-
-        // Write to disk
-        fs::write(&file_path, &value)?;
-
-        let now = Instant::now();
-        let metadata = CacheMetadata {
-            file_path,
-            size: value_size,
-            created_at: now,
-            last_accessed: now,
-        };
+        self.write_entry_file(&file_path, key, &value)?;
 
-        // Update index
-        let mut index = self.index.write().await;
+        self.record_entry(key, file_path, value_size).await?;
 
-        // Remove old entry if it exists
-        if let Some(old_metadata) = index.remove(key) {
-            self.current_size
-                .fetch_sub(old_metadata.size, Ordering::Relaxed);
-            // Old file will be overwritten
-        }
+        Ok(())
+    }
 
-        index.insert(key.clone(), metadata);
-        self.current_size.fetch_add(value_size, Ordering::Relaxed);
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        DiskCache::set_many(self, entries).await
+    }
 
-        Ok(())
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
     }
 
-    async fn remove(&self, key: &StoreKey) -> Result<(), CacheError> {
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
         let mut index = self.index.write().await;
 
         if let Some(metadata) = index.remove(key) {
-            if let Err(e) = fs::remove_file(&metadata.file_path) {
+            self.stats.removals.record(cause, 1);
+            self.log(
+                &JournalRecord::Remove {
+                    key: key.to_string(),
+                },
+                true,
+            )?;
+            if let Err(e) = self.remove_entry_file(&metadata.file_path) {
                 tracing::warn!(
                     "Failed to remove cache file {:?}: {}",
                     metadata.file_path,
@@ -274,12 +2713,28 @@ impl Cache for DiskCache {
         Ok(())
     }
 
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let keys: Vec<StoreKey> = self
+            .index
+            .read()
+            .await
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in &keys {
+            self.remove_with_cause(key, RemovalCause::Removed).await?;
+        }
+        Ok(keys.len())
+    }
+
     async fn clear(&self) -> Result<(), CacheError> {
         let mut index = self.index.write().await;
+        self.log(&JournalRecord::Clear, true)?;
 
         // Remove all files
         for (_, metadata) in index.drain() {
-            if let Err(e) = fs::remove_file(&metadata.file_path) {
+            if let Err(e) = self.remove_entry_file(&metadata.file_path) {
                 tracing::warn!(
                     "Failed to remove cache file {:?}: {}",
                     metadata.file_path,
@@ -289,10 +2744,68 @@ impl Cache for DiskCache {
         }
 
         self.current_size.store(0, Ordering::Relaxed);
+        if let Some(filter) = &self.key_filter {
+            let mut filter = filter.write().unwrap_or_else(|e| e.into_inner());
+            *filter = KeyFilter::with_capacity(filter.capacity());
+        }
+        self.expiry_queue
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.eviction_queue().clear();
+
+        Ok(())
+    }
 
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.remove_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.max_size().map(|max_size| max_size as usize)
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        if max_size_bytes == 0 {
+            return Err(CacheError::InvalidConfig(
+                "max_size_bytes must be greater than zero".to_string(),
+            ));
+        }
+        self.max_size_bytes
+            .store(max_size_bytes as u64, Ordering::Relaxed);
+        self.evict_if_needed(0).await
+    }
+
+    /// Entries already cached are queued for expiry when a TTL is first set,
+    /// which takes one pass over the index.
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        // Writes scheduled after this see the new TTL; those before are
+        // in the index read below
+        let previous = self.ttl.set(ttl);
+        if ttl.is_none() {
+            self.expiry_queue
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+        } else if previous.is_none() {
+            let index = self.index.read().await;
+            self.expiry_queue
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .extend(
+                    index
+                        .iter()
+                        .map(|(key, metadata)| Reverse((metadata.expires_from, key.clone()))),
+                );
+        }
+        self.remove_expired().await?;
         Ok(())
     }
 
+    fn io_operations(&self) -> Option<u64> {
+        Some(self.stats.io_operations.load(Ordering::Relaxed))
+    }
+
     fn size(&self) -> usize {
         self.current_size.load(Ordering::Relaxed)
     }
@@ -305,6 +2818,23 @@ impl Cache for DiskCache {
             misses: self.stats.misses.load(Ordering::Relaxed),
             size_bytes: self.current_size.load(Ordering::Relaxed),
             entry_count: index_guard.len(),
+            // Disk budgets count file bytes only
+            accounted_bytes: self.current_size.load(Ordering::Relaxed),
+            removals: self.stats.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
+
+impl Drop for DiskCache {
+    fn drop(&mut self) {
+        let Ok(mut index) = self.index.try_write() else {
+            tracing::warn!("Disk cache index busy on drop; not persisted");
+            return;
+        };
+        self.apply_pending_accesses(&mut index);
+        if let Err(e) = self.write_index(&index) {
+            tracing::warn!("Failed to persist disk cache index: {:?}", e);
         }
     }
 }
@@ -0,0 +1,478 @@
+use crate::cache::{Cache, CacheStats, RemovalCause, RemovalTracker, StoreKey};
+use crate::compression::CompressionStats;
+use crate::error::{BoxError, CacheError};
+use crate::units::{ByteSize, HumanDuration, SetterError};
+use bytes::Bytes;
+use redb::{Database, Durability, ReadableTable, TableDefinition};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Chunk values by key
+const VALUES: TableDefinition<&str, &[u8]> = TableDefinition::new("values");
+
+/// Per-entry metadata by key: value size, creation time in unix milliseconds
+const METADATA: TableDefinition<&str, (u64, u64)> = TableDefinition::new("metadata");
+
+/// Configuration for the embedded key-value disk cache
+///
+/// # Default Values
+/// - `path`: System temp directory + "zarrs_cache.redb"
+/// - `max_size_bytes`: None (unbounded)
+/// - `ttl`: None (no expiration)
+/// - `sync_writes`: true
+#[derive(Debug, Clone)]
+pub struct EmbeddedDiskCacheConfig {
+    /// Database file
+    pub path: PathBuf,
+    /// Maximum total size of cached values in bytes
+    pub max_size_bytes: Option<u64>,
+    /// TTL for cache entries
+    pub ttl: Option<Duration>,
+    /// Commit every write durably before it returns
+    ///
+    /// Commits are atomic and crash consistent either way; when disabled the
+    /// most recent writes may be lost on power failure.
+    pub sync_writes: bool,
+}
+
+impl Default for EmbeddedDiskCacheConfig {
+    fn default() -> Self {
+        Self {
+            path: std::env::temp_dir().join("zarrs_cache.redb"),
+            max_size_bytes: None,
+            ttl: None,
+            sync_writes: true,
+        }
+    }
+}
+
+impl EmbeddedDiskCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> EmbeddedDiskCacheConfigBuilder {
+        EmbeddedDiskCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        if self.max_size_bytes == Some(0) {
+            return Err(CacheError::InvalidConfig(
+                "max_size_bytes must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`EmbeddedDiskCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](EmbeddedDiskCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedDiskCacheConfigBuilder {
+    config: EmbeddedDiskCacheConfig,
+    invalid: SetterError,
+}
+
+impl EmbeddedDiskCacheConfigBuilder {
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.path = path.into();
+        self
+    }
+
+    pub fn max_size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.max_size_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn sync_writes(mut self, sync: bool) -> Self {
+        self.config.sync_writes = sync;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<EmbeddedDiskCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Disk cache backed by an embedded [redb](https://docs.rs/redb) database
+///
+/// Values and their metadata are written in a single transaction, so the
+/// on-disk state is always consistent after a crash and there is no separate
+/// index to rebuild or repair. Small entries share database pages instead of
+/// each taking a file and an inode.
+///
+/// The recency order used for eviction is kept in memory and seeded from
+/// entry creation times on startup.
+pub struct EmbeddedDiskCache {
+    config: EmbeddedDiskCacheConfig,
+    db: Database,
+    index: Arc<RwLock<EmbeddedIndex>>,
+    stats: Arc<CacheStatsInner>,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    removals: RemovalTracker,
+}
+
+struct EmbeddedEntry {
+    size: usize,
+    created_at_ms: u64,
+    last_accessed: Instant,
+}
+
+#[derive(Default)]
+struct EmbeddedIndex {
+    entries: HashMap<StoreKey, EmbeddedEntry>,
+    total_bytes: usize,
+}
+
+impl EmbeddedIndex {
+    fn forget(&mut self, key: &str) -> Option<EmbeddedEntry> {
+        let entry = self.entries.remove(key)?;
+        self.total_bytes -= entry.size;
+        Some(entry)
+    }
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn backend_error(error: impl Into<BoxError>) -> CacheError {
+    CacheError::backend(error)
+}
+
+impl EmbeddedDiskCache {
+    pub fn new(path: PathBuf, max_size_bytes: Option<u64>) -> Result<Self, CacheError> {
+        Self::with_config(EmbeddedDiskCacheConfig {
+            path,
+            max_size_bytes,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: EmbeddedDiskCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+        if let Some(parent) = config.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let db = Database::create(&config.path).map_err(backend_error)?;
+        let index = Self::load_index(&db)?;
+
+        Ok(Self {
+            config,
+            db,
+            index: Arc::new(RwLock::new(index)),
+            stats: Arc::new(CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                removals: RemovalTracker::default(),
+            }),
+        })
+    }
+
+    pub fn config(&self) -> &EmbeddedDiskCacheConfig {
+        &self.config
+    }
+
+    /// Read entry metadata from the database, creating the tables if needed
+    fn load_index(db: &Database) -> Result<EmbeddedIndex, CacheError> {
+        let txn = db.begin_write().map_err(backend_error)?;
+        let mut index = EmbeddedIndex::default();
+        {
+            txn.open_table(VALUES).map_err(backend_error)?;
+            let metadata = txn.open_table(METADATA).map_err(backend_error)?;
+
+            // Oldest entries first, so they are the first eviction candidates
+            let mut loaded = Vec::new();
+            for row in metadata.iter().map_err(backend_error)? {
+                let (key, value) = row.map_err(backend_error)?;
+                let (size, created_at_ms) = value.value();
+                loaded.push((key.value().to_string(), size as usize, created_at_ms));
+            }
+            loaded.sort_by_key(|(_, _, created_at_ms)| *created_at_ms);
+
+            let now = Instant::now();
+            let count = loaded.len();
+            for (position, (key, size, created_at_ms)) in loaded.into_iter().enumerate() {
+                let age = Duration::from_nanos((count - position) as u64);
+                index.total_bytes += size;
+                index.entries.insert(
+                    key,
+                    EmbeddedEntry {
+                        size,
+                        created_at_ms,
+                        last_accessed: now.checked_sub(age).unwrap_or(now),
+                    },
+                );
+            }
+        }
+        txn.commit().map_err(backend_error)?;
+
+        Ok(index)
+    }
+
+    fn is_expired(&self, entry: &EmbeddedEntry) -> bool {
+        match self.config.ttl {
+            Some(ttl) => now_unix_ms().saturating_sub(entry.created_at_ms) > ttl.as_millis() as u64,
+            None => false,
+        }
+    }
+
+    fn begin_write(&self) -> Result<redb::WriteTransaction, CacheError> {
+        let mut txn = self.db.begin_write().map_err(backend_error)?;
+        if !self.config.sync_writes {
+            txn.set_durability(Durability::Eventual);
+        }
+        Ok(txn)
+    }
+
+    /// Delete `keys` from the database in one transaction
+    fn delete_keys(&self, keys: &[impl AsRef<str>]) -> Result<(), CacheError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let txn = self.begin_write()?;
+        {
+            let mut values = txn.open_table(VALUES).map_err(backend_error)?;
+            let mut metadata = txn.open_table(METADATA).map_err(backend_error)?;
+            for key in keys {
+                values.remove(key.as_ref()).map_err(backend_error)?;
+                metadata.remove(key.as_ref()).map_err(backend_error)?;
+            }
+        }
+        txn.commit().map_err(backend_error)
+    }
+
+    /// Pick least recently used keys to make room for `incoming` bytes
+    /// stored under `key`, whose current value is released by the write
+    fn eviction_victims(
+        &self,
+        index: &EmbeddedIndex,
+        key: &str,
+        incoming: usize,
+    ) -> Result<Vec<StoreKey>, CacheError> {
+        let Some(max_size) = self.config.max_size_bytes else {
+            return Ok(Vec::new());
+        };
+        let max_size = max_size as usize;
+        if incoming > max_size {
+            return Err(CacheError::EntryTooLarge {
+                size: incoming,
+                max: max_size,
+            });
+        }
+
+        let replaced = index.entries.get(key).map_or(0, |entry| entry.size);
+        let mut total = index.total_bytes - replaced;
+        if total + incoming <= max_size {
+            return Ok(Vec::new());
+        }
+
+        let mut by_recency: Vec<(&StoreKey, &EmbeddedEntry)> = index
+            .entries
+            .iter()
+            .filter(|(candidate, _)| candidate.as_str() != key)
+            .collect();
+        by_recency.sort_by_key(|(_, entry)| entry.last_accessed);
+
+        let mut victims = Vec::new();
+        for (victim, entry) in by_recency {
+            if total + incoming <= max_size {
+                break;
+            }
+            total -= entry.size;
+            victims.push(victim.clone());
+        }
+
+        Ok(victims)
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for EmbeddedDiskCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        {
+            let mut index = self.index.write().await;
+            let Some(entry) = index.entries.get_mut(key) else {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+            entry.last_accessed = Instant::now();
+
+            if self.is_expired(entry) {
+                index.forget(key);
+                self.stats.removals.record(RemovalCause::Expired, 1);
+                drop(index);
+                if let Err(e) = self.delete_keys(&[key]) {
+                    tracing::warn!("Failed to delete expired entry {}: {:?}", key, e);
+                }
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        }
+
+        let read = || -> Result<Option<Bytes>, CacheError> {
+            let txn = self.db.begin_read().map_err(backend_error)?;
+            let values = txn.open_table(VALUES).map_err(backend_error)?;
+            let value = values.get(key).map_err(backend_error)?;
+            Ok(value.map(|value| Bytes::copy_from_slice(value.value())))
+        };
+
+        match read() {
+            Ok(Some(data)) => {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                Some(data)
+            }
+            Ok(None) => {
+                // Removed concurrently
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read cache entry {}: {:?}", key, e);
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let mut index = self.index.write().await;
+
+        let victims = self.eviction_victims(&index, key, value.len())?;
+
+        let created_at_ms = now_unix_ms();
+        let txn = self.begin_write()?;
+        {
+            let mut values = txn.open_table(VALUES).map_err(backend_error)?;
+            let mut metadata = txn.open_table(METADATA).map_err(backend_error)?;
+            for victim in &victims {
+                values.remove(victim.as_str()).map_err(backend_error)?;
+                metadata.remove(victim.as_str()).map_err(backend_error)?;
+            }
+            values.insert(key, value.as_ref()).map_err(backend_error)?;
+            metadata
+                .insert(key, (value.len() as u64, created_at_ms))
+                .map_err(backend_error)?;
+        }
+        txn.commit().map_err(backend_error)?;
+
+        for victim in &victims {
+            index.forget(victim);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Evicted, victims.len() as u64);
+        index.forget(key);
+        index.total_bytes += value.len();
+        index.entries.insert(
+            key.to_string(),
+            EmbeddedEntry {
+                size: value.len(),
+                created_at_ms,
+                last_accessed: Instant::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut index = self.index.write().await;
+
+        if index.entries.contains_key(key) {
+            self.delete_keys(&[key])?;
+            index.forget(key);
+            self.stats.removals.record(cause, 1);
+        }
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut index = self.index.write().await;
+
+        let txn = self.begin_write()?;
+        {
+            let mut values = txn.open_table(VALUES).map_err(backend_error)?;
+            let mut metadata = txn.open_table(METADATA).map_err(backend_error)?;
+            values.retain(|_, _| false).map_err(backend_error)?;
+            metadata.retain(|_, _| false).map_err(backend_error)?;
+        }
+        txn.commit().map_err(backend_error)?;
+
+        *index = EmbeddedIndex::default();
+
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        if self.config.ttl.is_none() {
+            return Ok(0);
+        }
+
+        let mut index = self.index.write().await;
+        let expired: Vec<StoreKey> = index
+            .entries
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        self.delete_keys(&expired)?;
+        for key in &expired {
+            index.forget(key);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Expired, expired.len() as u64);
+
+        Ok(expired.len())
+    }
+
+    fn size(&self) -> usize {
+        futures::executor::block_on(self.index.read()).total_bytes
+    }
+
+    fn stats(&self) -> CacheStats {
+        let index = futures::executor::block_on(self.index.read());
+
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes: index.total_bytes,
+            entry_count: index.entries.len(),
+            accounted_bytes: index.total_bytes,
+            removals: self.stats.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
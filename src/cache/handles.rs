@@ -0,0 +1,66 @@
+//! Pool of open entry files for the disk cache
+
+use lru::LruCache;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// An open entry file and the write of the entry it was opened for
+struct PooledFile {
+    written_at: Instant,
+    file: Arc<fs::File>,
+}
+
+/// LRU-bounded set of open entry files
+///
+/// Entry files are replaced by rename, so a handle keeps reading the file
+/// it was opened on; handles are tagged with the write they belong to and
+/// reopened once the entry is rewritten.
+pub(crate) struct FileHandlePool {
+    files: Mutex<LruCache<PathBuf, PooledFile>>,
+}
+
+impl FileHandlePool {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            files: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Open file for the entry at `path` written at `written_at`, reusing a
+    /// pooled handle when there is one
+    pub(crate) fn open(&self, path: &Path, written_at: Instant) -> std::io::Result<Arc<fs::File>> {
+        {
+            let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+            match files.get(path) {
+                Some(pooled) if pooled.written_at == written_at => return Ok(pooled.file.clone()),
+                Some(_) => {
+                    files.pop(path);
+                }
+                None => {}
+            }
+        }
+
+        // Open without holding the pool lock
+        let file = Arc::new(fs::File::open(path)?);
+        self.files.lock().unwrap_or_else(|e| e.into_inner()).put(
+            path.to_path_buf(),
+            PooledFile {
+                written_at,
+                file: file.clone(),
+            },
+        );
+        Ok(file)
+    }
+
+    /// Close the handle for `path`, so a deleted file releases its space
+    pub(crate) fn forget(&self, path: &Path) {
+        self.files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop(path);
+    }
+}
@@ -1,20 +1,62 @@
-use crate::cache::disk::DiskCache;
-use crate::cache::memory::LruMemoryCache;
-use crate::cache::{Cache, CacheStats};
-use crate::error::CacheError;
+use crate::cache::admission::{AdmissionCache, AdmissionPolicy};
+use crate::cache::disk::{DiskCache, DiskCacheConfig, DiskIoBackend, DiskLayout};
+use crate::cache::tracker::AccessTracker;
+use crate::cache::{entry_footprint, Cache, CacheStats, MemoryPolicy, RemovalCause};
+use crate::compression::{
+    decode_entry_offloaded, encode_entry_offloaded, encode_entry_to, Compression,
+    CompressionAlgorithm, CompressionTracker, DEFAULT_OFFLOAD_BYTES,
+};
+use crate::config;
+use crate::error::{CacheError, ErrorContext, Tier};
+use crate::events::{CacheEvent, CacheEventKind, CacheEventOutcome, EventLog};
+use crate::metrics::{MetricsCollector, RollingCounter};
+use crate::pressure::{PressureLevel, PressureSignal};
+use crate::units::{self, ByteSize, HumanDuration, SetterError};
 use bytes::Bytes;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Factor the adaptive promotion threshold moves by per ghost hit
+const GHOST_ADAPT_FACTOR: f64 = 1.1;
+
+/// How far the adaptive promotion threshold may move from the configured one,
+/// as a factor either way
+const GHOST_ADAPT_RANGE: f64 = 100.0;
+
+/// Tiers an entry is stored in, chosen by its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    /// Written to disk, and kept in memory while hot
+    Both,
+    /// Too small to be worth a disk write
+    MemoryOnly,
+    /// Too large for the memory tier
+    DiskOnly,
+}
+
 /// Access frequency tracking for promotion/demotion decisions
 #[derive(Debug, Clone)]
 struct AccessInfo {
     count: u64,
     last_access: Instant,
     promoted_at: Option<Instant>,
+    /// Route of the last value seen for the key
+    route: Route,
+    /// Whether the memory tier was given a copy
+    in_memory: bool,
+    /// Whether the disk tier was given the current value, so demoting it
+    /// needs no write
+    on_disk: bool,
+    /// Promoted, and not yet hit in memory since
+    promotion_pending: bool,
 }
 
 impl AccessInfo {
@@ -23,6 +65,10 @@ impl AccessInfo {
             count: 1,
             last_access: Instant::now(),
             promoted_at: None,
+            route: Route::Both,
+            in_memory: false,
+            on_disk: false,
+            promotion_pending: false,
         }
     }
 
@@ -33,6 +79,8 @@ impl AccessInfo {
 
     fn mark_promoted(&mut self) {
         self.promoted_at = Some(Instant::now());
+        self.in_memory = true;
+        self.promotion_pending = true;
     }
 
     /// Calculate access frequency (accesses per second)
@@ -54,6 +102,209 @@ impl AccessInfo {
     }
 }
 
+/// Promotion and demotion thresholds in effect
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
+    promotion: f64,
+    demotion: Duration,
+    /// Promotion threshold set by the configuration or
+    /// [`HybridCache::set_promotion_threshold`], around which ghost lists
+    /// adapt it
+    base_promotion: f64,
+}
+
+/// Counters seen by the previous threshold tuning round
+#[derive(Debug, Default)]
+struct TuningState {
+    memory_hits: u64,
+    memory_misses: u64,
+    promotions_executed: u64,
+    promotions_effective: u64,
+}
+
+/// Keys recently pushed out of each tier, whose hits tune the promotion
+/// threshold
+///
+/// Keys are remembered once demoted, or once no longer tracked while still
+/// placed in a tier; a key only counts as a ghost hit if the tier turns out
+/// to have dropped it when it is next read.
+struct GhostLists {
+    memory: LruCache<String, ()>,
+    disk: LruCache<String, ()>,
+}
+
+impl GhostLists {
+    fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            memory: LruCache::new(capacity),
+            disk: LruCache::new(capacity),
+        }
+    }
+
+    /// `threshold` moved by one step scaled ARC-style by the ratio of the
+    /// ghost list sizes, within [`GHOST_ADAPT_RANGE`] of `configured`
+    fn adapt(&self, memory_hit: bool, threshold: f64, configured: f64) -> f64 {
+        let (hit, other) = if memory_hit {
+            (self.memory.len(), self.disk.len())
+        } else {
+            (self.disk.len(), self.memory.len())
+        };
+        let ratio = (other as f64 / hit.max(1) as f64).clamp(1.0, 8.0);
+        let factor = GHOST_ADAPT_FACTOR.powf(ratio);
+
+        let threshold = if memory_hit {
+            // Memory dropped an entry still in use: promote more eagerly
+            threshold / factor
+        } else {
+            // The working set outgrows the disk tier: promotions just churn memory
+            threshold * factor
+        };
+        threshold.clamp(
+            configured / GHOST_ADAPT_RANGE,
+            configured * GHOST_ADAPT_RANGE,
+        )
+    }
+}
+
+/// Bounds and pace for feedback-driven tuning of the hybrid thresholds
+///
+/// Each maintenance round with enough memory tier lookups since the last
+/// one, the thresholds move one step: promoting less and demoting sooner
+/// while promotion accuracy is below `min_promotion_accuracy`, or promoting
+/// more and demoting later while the memory tier hit rate over the last
+/// `hit_rate_window` is below `target_memory_hit_rate`. Promotion accuracy
+/// is read from the [`MetricsCollector`] attached with
+/// [`HybridCache::with_metrics`]; without one only the hit rate is used.
+///
+/// # Default Values
+/// - `promotion_threshold_range`: 0.01 to 10 accesses per second
+/// - `demotion_threshold_range`: 30 seconds to 1 hour
+/// - `target_memory_hit_rate`: 0.8
+/// - `min_promotion_accuracy`: 0.7
+/// - `adjustment_factor`: 1.2
+/// - `min_samples`: 100 memory tier lookups
+/// - `hit_rate_window`: 60 seconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThresholdTuning {
+    /// Lowest and highest promotion threshold to tune within
+    pub promotion_threshold_range: (f64, f64),
+    /// Shortest and longest demotion threshold to tune within
+    pub demotion_threshold_range: (Duration, Duration),
+    /// Memory tier hit rate below which promotion is made easier
+    pub target_memory_hit_rate: f64,
+    /// Share of promotions hit in memory below which promotion is made harder
+    pub min_promotion_accuracy: f64,
+    /// Factor the thresholds move by per step
+    pub adjustment_factor: f64,
+    /// Memory tier lookups needed before a round adjusts anything
+    pub min_samples: u64,
+    /// Recent period whose memory tier hit rate is compared against
+    /// `target_memory_hit_rate`, up to 15 minutes
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub hit_rate_window: Duration,
+}
+
+impl Default for ThresholdTuning {
+    fn default() -> Self {
+        Self {
+            promotion_threshold_range: (0.01, 10.0),
+            demotion_threshold_range: (Duration::from_secs(30), Duration::from_secs(3600)),
+            target_memory_hit_rate: 0.8,
+            min_promotion_accuracy: 0.7,
+            adjustment_factor: 1.2,
+            min_samples: 100,
+            hit_rate_window: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How costly it is to fetch a key again from its origin store
+///
+/// Entries from costly origins are promoted to memory at a proportionally
+/// lower access frequency, and left idle proportionally longer before being
+/// demoted, so a chunk restored from tape outlives a local one read as often.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyClass {
+    /// Local or network filesystem
+    #[default]
+    Local,
+    /// Object store such as S3, GCS or Azure Blob Storage
+    ObjectStore,
+    /// Archival storage such as tape or S3 Glacier
+    Archive,
+}
+
+impl LatencyClass {
+    /// Weight of the class relative to [`LatencyClass::Local`]
+    pub fn refetch_cost(self) -> f64 {
+        match self {
+            LatencyClass::Local => 1.0,
+            LatencyClass::ObjectStore => 4.0,
+            LatencyClass::Archive => 16.0,
+        }
+    }
+}
+
+/// Thresholds for a remote tier below disk
+///
+/// # Default Values
+/// - `promotion_threshold`: 0.0 (every remote hit is copied to disk)
+/// - `demotion_threshold`: 3600 seconds (1 hour)
+/// - `write_through`: false
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteTierConfig {
+    /// Minimum access frequency for a remote hit to be copied to the disk
+    /// tier (accesses per second)
+    pub promotion_threshold: f64,
+    /// Time a disk entry must go unread before it is moved to the remote tier
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub demotion_threshold: Duration,
+    /// Write every new entry to the remote tier as well, so other workers
+    /// see it at once rather than after demotion
+    pub write_through: bool,
+}
+
+impl Default for RemoteTierConfig {
+    fn default() -> Self {
+        Self {
+            promotion_threshold: 0.0,
+            demotion_threshold: Duration::from_secs(3600),
+            write_through: false,
+        }
+    }
+}
+
+impl RemoteTierConfig {
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        if !(self.promotion_threshold.is_finite() && self.promotion_threshold >= 0.0) {
+            return Err(CacheError::InvalidConfig(
+                "remote promotion_threshold must be a non-negative number".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A remote tier and its thresholds
+struct RemoteTier {
+    cache: Arc<dyn Cache>,
+    config: RemoteTierConfig,
+}
+
+/// Statistics of each tier of a [`HybridCache`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HybridTierStats {
+    pub memory: CacheStats,
+    pub disk: CacheStats,
+    /// `None` without a remote tier
+    pub remote: Option<CacheStats>,
+}
+
 /// Configuration for hybrid cache behavior
 ///
 /// # Default Values
@@ -61,25 +312,145 @@ impl AccessInfo {
 /// - `disk_size`: 1GB
 /// - `disk_dir`: System temp directory + "zarrs_hybrid_cache"
 /// - `ttl`: None (no expiration)
+/// - `memory_ttl`: None (uses `ttl`)
+/// - `disk_ttl`: None (uses `ttl`)
+/// - `ttl_jitter`: 0.0 (every entry expires exactly one TTL after its write)
 /// - `promotion_threshold`: 0.1 accesses per second
 /// - `demotion_threshold`: 300 seconds (5 minutes)
 /// - `maintenance_interval`: 60 seconds (1 minute)
-#[derive(Debug, Clone)]
+/// - `memory_policy`: LRU
+/// - `disk_layout`: Fan-out
+/// - `disk_io_backend`: Std
+/// - `disk_key_filter_capacity`: 100,000 entries
+/// - `memory_max_entry_bytes`: None (any size may be kept in memory)
+/// - `memory_only_max_bytes`: None (every entry is written to disk)
+/// - `ghost_list_capacity`: None (fixed promotion threshold)
+/// - `threshold_tuning`: None (fixed thresholds)
+/// - `warm_start_bytes`: None (memory tier starts empty)
+/// - `max_promotions_per_cycle`: None (no entry limit)
+/// - `max_promotion_bytes_per_cycle`: None (no byte limit)
+/// - `access_tracker_capacity`: 100,000 keys
+/// - `disk_compression`: None (disk entries stored as is)
+/// - `compression_offload_bytes`: 256 KiB
+/// - `compression_streaming_bytes`: 16 MiB
+/// - `latency_classes`: empty (every key is [`LatencyClass::Local`])
+///
+/// Settings missing from a deserialized configuration take these defaults.
+/// Build one with [`HybridCacheConfig::builder`] to have it validated, or
+/// call [`HybridCacheConfig::validate`]; [`HybridCache::new`] rejects invalid
+/// configurations either way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HybridCacheConfig {
     /// Memory cache size in bytes
+    #[serde(deserialize_with = "units::deserialize_size")]
     pub memory_size: usize,
     /// Disk cache size in bytes
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
     pub disk_size: Option<u64>,
     /// Disk cache directory
     pub disk_dir: PathBuf,
     /// TTL for cache entries
+    #[serde(deserialize_with = "units::deserialize_optional_duration")]
     pub ttl: Option<Duration>,
+    /// TTL for the memory tier, overriding `ttl`, e.g. minutes to bound
+    /// staleness of hot copies
+    #[serde(deserialize_with = "units::deserialize_optional_duration")]
+    pub memory_ttl: Option<Duration>,
+    /// TTL for the disk tier, overriding `ttl`, e.g. days for durable reuse
+    #[serde(deserialize_with = "units::deserialize_optional_duration")]
+    pub disk_ttl: Option<Duration>,
+    /// Fraction of its tier's TTL, between 0 and 1, by which each entry's
+    /// expiry is moved earlier or later at random when it is written
+    ///
+    /// Entries populated together, such as by a warming run, then expire
+    /// over a window instead of causing a storm of misses at one instant.
+    pub ttl_jitter: f64,
     /// Minimum access frequency to promote to memory (accesses per second)
     pub promotion_threshold: f64,
     /// Time of inactivity before considering demotion
+    #[serde(deserialize_with = "units::deserialize_duration")]
     pub demotion_threshold: Duration,
     /// How often to run maintenance tasks
+    #[serde(deserialize_with = "units::deserialize_duration")]
     pub maintenance_interval: Duration,
+    /// Eviction policy for the memory tier
+    pub memory_policy: MemoryPolicy,
+    /// File layout of the disk tier
+    pub disk_layout: DiskLayout,
+    /// I/O backend for the batched promotions and demotions of the disk tier
+    pub disk_io_backend: DiskIoBackend,
+    /// Size of the disk tier's key filter, which answers misses for keys
+    /// never cached without touching the disk index; `None` disables it
+    pub disk_key_filter_capacity: Option<usize>,
+    /// Entries larger than this go straight to disk and are never promoted,
+    /// so a single huge chunk cannot flush the whole memory tier
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
+    pub memory_max_entry_bytes: Option<usize>,
+    /// Entries of at most this many bytes, such as zarr metadata documents,
+    /// are kept in memory only and never written to disk
+    ///
+    /// Such entries are lost on restart or once evicted from memory, are
+    /// never demoted, and are not included in `stats().entry_count`. Under
+    /// critical memory pressure they are written to disk instead.
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
+    pub memory_only_max_bytes: Option<usize>,
+    /// Number of keys recently pushed out of each tier to remember
+    ///
+    /// When set, `promotion_threshold` is only the starting point: reading a
+    /// key the memory tier dropped lowers the threshold, and reading a key the
+    /// disk tier dropped raises it, ARC-style. `None` keeps it fixed.
+    pub ghost_list_capacity: Option<usize>,
+    /// Feedback-driven tuning of `promotion_threshold` and
+    /// `demotion_threshold`, which then only set the starting point
+    pub threshold_tuning: Option<ThresholdTuning>,
+    /// Bytes of the hottest disk entries to load into the memory tier when
+    /// the cache is created, capped at `memory_size`
+    ///
+    /// Entries are ranked by the access statistics persisted with the disk
+    /// index, so a restarted service starts with a warm memory tier.
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
+    pub warm_start_bytes: Option<usize>,
+    /// Most entries a single maintenance run promotes to memory
+    ///
+    /// The hottest candidates go first; the rest stay on disk and are
+    /// reconsidered next run, so a burst of reads cannot stampede the
+    /// memory tier.
+    pub max_promotions_per_cycle: Option<usize>,
+    /// Most value bytes a single maintenance run promotes to memory
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
+    pub max_promotion_bytes_per_cycle: Option<usize>,
+    /// Number of keys whose access statistics are tracked for promotion
+    /// and demotion
+    ///
+    /// The least recently accessed keys are forgotten beyond this, so
+    /// tracking memory stays bounded however many distinct keys are read.
+    pub access_tracker_capacity: usize,
+    /// Compression of entries stored by the disk tier
+    ///
+    /// The memory tier keeps values uncompressed, so memory hits pay no
+    /// decompression cost; entries are compressed when written or demoted to
+    /// disk and decompressed when read back or promoted. Disk sizes and
+    /// statistics count compressed bytes.
+    pub disk_compression: Option<CompressionAlgorithm>,
+    /// Size from which disk entries are compressed and decompressed on
+    /// tokio's blocking thread pool instead of the calling task
+    ///
+    /// Smaller entries are handled inline, as handing them off costs more
+    /// than compressing them.
+    #[serde(deserialize_with = "units::deserialize_size")]
+    pub compression_offload_bytes: usize,
+    /// Size from which disk entries are compressed straight into their
+    /// entry file, so the compressed copy is never held in memory whole
+    #[serde(deserialize_with = "units::deserialize_size")]
+    pub compression_streaming_bytes: usize,
+    /// Latency class of the origin store of the keys under each prefix
+    ///
+    /// The longest matching prefix applies; keys matching none are
+    /// [`LatencyClass::Local`]. Promotion and demotion thresholds are
+    /// weighted by the [refetch cost](LatencyClass::refetch_cost) of the
+    /// class.
+    pub latency_classes: BTreeMap<String, LatencyClass>,
 }
 
 impl Default for HybridCacheConfig {
@@ -89,46 +460,449 @@ impl Default for HybridCacheConfig {
             disk_size: Some(1024 * 1024 * 1024), // 1GB
             disk_dir: std::env::temp_dir().join("zarrs_hybrid_cache"),
             ttl: None,
+            memory_ttl: None,
+            disk_ttl: None,
+            ttl_jitter: 0.0,
             promotion_threshold: 0.1, // 0.1 accesses per second
             demotion_threshold: Duration::from_secs(300), // 5 minutes
             maintenance_interval: Duration::from_secs(60), // 1 minute
+            memory_policy: MemoryPolicy::Lru,
+            disk_layout: DiskLayout::FanOut,
+            disk_io_backend: DiskIoBackend::Std,
+            disk_key_filter_capacity: Some(100_000),
+            memory_max_entry_bytes: None,
+            memory_only_max_bytes: None,
+            ghost_list_capacity: None,
+            threshold_tuning: None,
+            warm_start_bytes: None,
+            max_promotions_per_cycle: None,
+            max_promotion_bytes_per_cycle: None,
+            access_tracker_capacity: 100_000,
+            disk_compression: None,
+            compression_offload_bytes: DEFAULT_OFFLOAD_BYTES,
+            compression_streaming_bytes: 16 * 1024 * 1024,
+            latency_classes: BTreeMap::new(),
+        }
+    }
+}
+
+impl HybridCacheConfig {
+    /// Load and validate the configuration from a file, as
+    /// [`CacheConfig::from_file`](crate::CacheConfig::from_file) does, with
+    /// `ZARRS_CACHE_<SETTING>` overrides such as `ZARRS_CACHE_MEMORY_SIZE=512MB`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let config = config::read_file::<Self>(path.as_ref())?.override_from(std::env::vars())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Default configuration with `ZARRS_CACHE_<SETTING>` overrides,
+    /// validated
+    pub fn from_env() -> Result<Self, CacheError> {
+        let config = Self::default().override_from(std::env::vars())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Apply the overrides among `vars`, as
+    /// [`CacheConfig::override_from`](crate::CacheConfig::override_from) does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        config::apply_overrides(&self, config::ENV_PREFIX, vars)
+    }
+
+    /// Builder starting from the default configuration
+    pub fn builder() -> HybridCacheConfigBuilder {
+        HybridCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable and consistent with each other
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.memory_size == 0 {
+            return invalid("memory_size must be greater than zero");
+        }
+        match self.disk_size {
+            Some(0) => return invalid("disk_size must be greater than zero"),
+            Some(disk) if self.memory_size as u64 > disk => {
+                return invalid("memory_size must not exceed disk_size")
+            }
+            _ => {}
+        }
+        if !(0.0..=1.0).contains(&self.ttl_jitter) {
+            return invalid("ttl_jitter must be between 0 and 1");
+        }
+        if !(self.promotion_threshold.is_finite() && self.promotion_threshold >= 0.0) {
+            return invalid("promotion_threshold must be a non-negative number");
+        }
+        if let (Some(memory_only), Some(max_entry)) =
+            (self.memory_only_max_bytes, self.memory_max_entry_bytes)
+        {
+            if memory_only >= max_entry {
+                return invalid("memory_only_max_bytes must be below memory_max_entry_bytes");
+            }
+        }
+        if self.ghost_list_capacity == Some(0) {
+            return invalid("ghost_list_capacity must be greater than zero");
+        }
+        if self.access_tracker_capacity == 0 {
+            return invalid("access_tracker_capacity must be greater than zero");
+        }
+        if let DiskIoBackend::IoUring { queue_depth: 0 } = self.disk_io_backend {
+            return invalid("io_uring queue_depth must be greater than zero");
+        }
+        if let Some(compression) = self.disk_compression {
+            if self.disk_layout == DiskLayout::Hierarchy {
+                return invalid("disk_compression cannot be used with the hierarchy disk layout");
+            }
+            compression.validate()?;
+        }
+
+        if let Some(tuning) = &self.threshold_tuning {
+            let (min_promotion, max_promotion) = tuning.promotion_threshold_range;
+            if !(min_promotion >= 0.0 && min_promotion <= max_promotion) {
+                return invalid("promotion_threshold_range must be an ordered, non-negative range");
+            }
+            let (min_demotion, max_demotion) = tuning.demotion_threshold_range;
+            if min_demotion > max_demotion {
+                return invalid("demotion_threshold_range must be an ordered range");
+            }
+            if !(0.0..=1.0).contains(&tuning.target_memory_hit_rate) {
+                return invalid("target_memory_hit_rate must be between 0 and 1");
+            }
+            if !(0.0..=1.0).contains(&tuning.min_promotion_accuracy) {
+                return invalid("min_promotion_accuracy must be between 0 and 1");
+            }
+            if !(tuning.adjustment_factor > 1.0 && tuning.adjustment_factor.is_finite()) {
+                return invalid("adjustment_factor must be greater than 1");
+            }
+            if !(Duration::from_secs(1)..=Duration::from_secs(15 * 60))
+                .contains(&tuning.hit_rate_window)
+            {
+                return invalid("hit_rate_window must be between 1 second and 15 minutes");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder for [`HybridCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](HybridCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct HybridCacheConfigBuilder {
+    config: HybridCacheConfig,
+    invalid: SetterError,
+}
+
+impl HybridCacheConfigBuilder {
+    pub fn memory_size(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.memory_size = bytes;
+        }
+        self
+    }
+
+    /// Disk tier size in bytes; `None` leaves the disk tier unbounded
+    pub fn disk_size(mut self, bytes: Option<u64>) -> Self {
+        self.config.disk_size = bytes;
+        self
+    }
+
+    pub fn disk_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.disk_dir = dir.into();
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn memory_ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.memory_ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn disk_ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.disk_ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn ttl_jitter(mut self, jitter: f64) -> Self {
+        self.config.ttl_jitter = jitter;
+        self
+    }
+
+    pub fn promotion_threshold(mut self, accesses_per_second: f64) -> Self {
+        self.config.promotion_threshold = accesses_per_second;
+        self
+    }
+
+    pub fn demotion_threshold(mut self, idle: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(idle.to_duration()) {
+            self.config.demotion_threshold = duration;
+        }
+        self
+    }
+
+    pub fn maintenance_interval(mut self, interval: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(interval.to_duration()) {
+            self.config.maintenance_interval = duration;
+        }
+        self
+    }
+
+    pub fn memory_policy(mut self, policy: MemoryPolicy) -> Self {
+        self.config.memory_policy = policy;
+        self
+    }
+
+    pub fn disk_layout(mut self, layout: DiskLayout) -> Self {
+        self.config.disk_layout = layout;
+        self
+    }
+
+    pub fn disk_io_backend(mut self, backend: DiskIoBackend) -> Self {
+        self.config.disk_io_backend = backend;
+        self
+    }
+
+    /// Key filter size of the disk tier; `None` disables the filter
+    pub fn disk_key_filter_capacity(mut self, capacity: Option<usize>) -> Self {
+        self.config.disk_key_filter_capacity = capacity;
+        self
+    }
+
+    pub fn memory_max_entry_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.memory_max_entry_bytes = Some(bytes);
         }
+        self
+    }
+
+    pub fn memory_only_max_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.memory_only_max_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ghost_list_capacity(mut self, capacity: usize) -> Self {
+        self.config.ghost_list_capacity = Some(capacity);
+        self
+    }
+
+    pub fn threshold_tuning(mut self, tuning: ThresholdTuning) -> Self {
+        self.config.threshold_tuning = Some(tuning);
+        self
+    }
+
+    pub fn warm_start_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.warm_start_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn max_promotions_per_cycle(mut self, entries: usize) -> Self {
+        self.config.max_promotions_per_cycle = Some(entries);
+        self
+    }
+
+    pub fn max_promotion_bytes_per_cycle(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.max_promotion_bytes_per_cycle = Some(bytes);
+        }
+        self
+    }
+
+    pub fn access_tracker_capacity(mut self, keys: usize) -> Self {
+        self.config.access_tracker_capacity = keys;
+        self
+    }
+
+    pub fn disk_compression(mut self, compression: CompressionAlgorithm) -> Self {
+        self.config.disk_compression = Some(compression);
+        self
+    }
+
+    pub fn compression_offload_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.compression_offload_bytes = bytes;
+        }
+        self
+    }
+
+    pub fn compression_streaming_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.compression_streaming_bytes = bytes;
+        }
+        self
+    }
+
+    /// Tag the origin store of keys under `prefix` with `class`
+    pub fn latency_class(mut self, prefix: impl Into<String>, class: LatencyClass) -> Self {
+        self.config.latency_classes.insert(prefix.into(), class);
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<HybridCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
 /// Hybrid cache that combines memory and disk storage with intelligent promotion/demotion
 pub struct HybridCache {
-    memory_cache: Arc<LruMemoryCache>,
+    memory_cache: Arc<dyn Cache>,
     disk_cache: Arc<DiskCache>,
-    access_tracker: Arc<RwLock<HashMap<String, AccessInfo>>>,
+    access_tracker: AccessTracker<AccessInfo>,
     config: HybridCacheConfig,
+    /// Memory tier size before any shrinking under memory pressure
+    memory_budget: AtomicUsize,
     last_maintenance: Arc<RwLock<Instant>>,
+    pressure: Option<PressureSignal>,
+    ghosts: Option<std::sync::Mutex<GhostLists>>,
+    thresholds: std::sync::Mutex<Thresholds>,
+    tuning: std::sync::Mutex<TuningState>,
+    metrics: Option<Arc<MetricsCollector>>,
+    /// Reads answered by the memory tier, excluding maintenance probes
+    memory_hits: AtomicU64,
+    /// Reads the memory tier could not answer, excluding maintenance probes
+    memory_misses: AtomicU64,
+    /// Recent memory tier reads, excluding maintenance probes
+    memory_lookups: std::sync::Mutex<RollingCounter>,
+    /// Held while maintenance runs, so a flush can wait for it
+    maintenance: tokio::sync::Mutex<()>,
+    /// Set by [`HybridCache::close`] to stop maintenance
+    closed: AtomicBool,
+    remote: Option<RemoteTier>,
+    disk_compression: Option<Arc<dyn Compression>>,
+    compression_tracker: Arc<CompressionTracker>,
+    events: Option<Arc<EventLog>>,
 }
 
 impl HybridCache {
     /// Create a new hybrid cache with the given configuration
     pub fn new(config: HybridCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+
         // Create memory cache
-        let memory_cache = if let Some(ttl) = config.ttl {
-            LruMemoryCache::with_ttl(config.memory_size, Some(ttl))
-        } else {
-            LruMemoryCache::new(config.memory_size)
-        };
+        let memory_cache = config.memory_policy.build_with_ttl_jitter(
+            config.memory_size,
+            config.memory_ttl.or(config.ttl),
+            config.ttl_jitter,
+        );
 
         // Create disk cache
-        let disk_cache = if let Some(ttl) = config.ttl {
-            DiskCache::with_ttl(config.disk_dir.clone(), config.disk_size, Some(ttl))?
-        } else {
-            DiskCache::new(config.disk_dir.clone(), config.disk_size)?
-        };
+        let disk_cache = DiskCache::with_config(DiskCacheConfig {
+            cache_dir: config.disk_dir.clone(),
+            max_size_bytes: config.disk_size,
+            ttl: config.disk_ttl.or(config.ttl),
+            ttl_jitter: config.ttl_jitter,
+            layout: config.disk_layout,
+            io_backend: config.disk_io_backend,
+            key_filter_capacity: config.disk_key_filter_capacity,
+            ..Default::default()
+        })?;
+
+        let ghosts = config
+            .ghost_list_capacity
+            .map(|capacity| std::sync::Mutex::new(GhostLists::new(capacity)));
+        let disk_compression = config
+            .disk_compression
+            .map(|algorithm| Arc::from(algorithm.build()));
+        let thresholds = std::sync::Mutex::new(Thresholds {
+            promotion: config.promotion_threshold,
+            demotion: config.demotion_threshold,
+            base_promotion: config.promotion_threshold,
+        });
 
-        Ok(Self {
-            memory_cache: Arc::new(memory_cache),
+        let cache = Self {
+            memory_cache,
             disk_cache: Arc::new(disk_cache),
-            access_tracker: Arc::new(RwLock::new(HashMap::new())),
+            access_tracker: AccessTracker::new(config.access_tracker_capacity),
+            memory_budget: AtomicUsize::new(config.memory_size),
             config,
             last_maintenance: Arc::new(RwLock::new(Instant::now())),
-        })
+            pressure: None,
+            ghosts,
+            thresholds,
+            tuning: std::sync::Mutex::new(TuningState::default()),
+            metrics: None,
+            memory_hits: AtomicU64::new(0),
+            memory_misses: AtomicU64::new(0),
+            memory_lookups: std::sync::Mutex::new(RollingCounter::new()),
+            maintenance: tokio::sync::Mutex::new(()),
+            closed: AtomicBool::new(false),
+            remote: None,
+            disk_compression,
+            compression_tracker: Arc::default(),
+            events: None,
+        };
+
+        if let Some(budget) = cache.config.warm_start_bytes {
+            let loaded = futures::executor::block_on(cache.warm_start(budget))?;
+            tracing::info!("Warm-started memory tier with {} disk entries", loaded);
+        }
+
+        Ok(cache)
+    }
+
+    /// Load the hottest disk entries into the memory tier, up to `budget`
+    /// bytes, returning how many were loaded
+    async fn warm_start(&self, budget: usize) -> Result<usize, CacheError> {
+        let budget = budget.min(self.memory_budget.load(Ordering::Relaxed));
+
+        let mut keys = Vec::new();
+        let mut selected = 0;
+        for (key, size) in self.disk_cache.hottest_keys(usize::MAX).await {
+            if self.route(size) != Route::Both {
+                continue;
+            }
+            let footprint = entry_footprint(&key, size);
+            if selected + footprint > budget {
+                break;
+            }
+            selected += footprint;
+            keys.push(key);
+        }
+
+        let values = self.disk_cache.get_many(&keys).await;
+        let mut loaded = 0;
+        // Coldest first, so the hottest entries are the last to be evicted
+        for (key, value) in keys.into_iter().zip(values).rev() {
+            // Decoded on this thread: warm start runs under `block_on`, where
+            // the blocking pool is missing outside a runtime and waiting for
+            // it would park a worker inside one
+            let Some(value) = self
+                .decode_from_disk_offloading(&key, value, usize::MAX)
+                .await
+            else {
+                continue;
+            };
+            self.memory_cache.set(&key, value).await?;
+            let mut access_info = AccessInfo::new();
+            access_info.in_memory = true;
+            access_info.on_disk = true;
+            self.access_tracker.insert(&key, access_info);
+            loaded += 1;
+        }
+        Ok(loaded)
     }
 
     /// Create a hybrid cache with default configuration
@@ -140,8 +914,86 @@ impl HybridCache {
         Self::new(config)
     }
 
+    /// Guard the memory tier with an admission policy
+    ///
+    /// Entries rejected by the policy are still written to the disk tier, so a
+    /// one-touch scan cannot push the working set out of memory.
+    pub fn with_admission_policy<P: AdmissionPolicy>(mut self, policy: P) -> Self {
+        self.memory_cache = Arc::new(AdmissionCache::new(self.memory_cache, policy));
+        self
+    }
+
+    /// Adapt the memory tier to host memory pressure
+    ///
+    /// While pressure is elevated the memory tier is shrunk and promotions are
+    /// paused; under critical pressure new writes skip memory entirely. Full
+    /// capacity is restored once the monitor reports normal pressure again.
+    pub fn with_memory_pressure(mut self, signal: PressureSignal) -> Self {
+        self.pressure = Some(signal);
+        self
+    }
+
+    /// Add a third tier below disk, such as a cache shared by a cluster of
+    /// workers
+    ///
+    /// Disk misses fall through to the remote tier, and remote hits are
+    /// copied to disk once frequent enough. Disk entries left unread for the
+    /// remote demotion threshold are moved down to it by maintenance. Writes
+    /// go to the remote tier too with `write_through`, or otherwise drop any
+    /// remote copy so it cannot be served stale; removals always reach it.
+    /// [`Cache::clear`], sizes and stats only cover the local tiers.
+    pub fn with_remote_tier(mut self, remote: Arc<dyn Cache>, config: RemoteTierConfig) -> Self {
+        self.remote = Some(RemoteTier {
+            cache: remote,
+            config,
+        });
+        self
+    }
+
+    /// Report promotion outcomes to a metrics collector
+    ///
+    /// A promotion counts as effective once the promoted entry is hit in
+    /// memory, and as ineffective if it leaves memory or stops being tracked
+    /// first. With [`HybridCacheConfig::threshold_tuning`] set, the collected
+    /// promotion accuracy also steers the thresholds.
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Log reads, writes, removals, promotions and demotions to `events`
+    pub fn with_event_log(mut self, events: Arc<EventLog>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Resize the memory tier for the current pressure level and return it
+    async fn apply_memory_pressure(&self) -> PressureLevel {
+        let Some(signal) = &self.pressure else {
+            return PressureLevel::Normal;
+        };
+
+        let level = signal.level();
+        let target = signal.scaled_capacity(self.memory_budget.load(Ordering::Relaxed));
+        if self.memory_cache.capacity() != Some(target) {
+            match self.memory_cache.resize(target).await {
+                Ok(()) => tracing::debug!(
+                    "Resized memory tier to {} bytes under {:?} pressure",
+                    target,
+                    level
+                ),
+                Err(e) => tracing::warn!("Failed to resize memory tier: {:?}", e),
+            }
+        }
+
+        level
+    }
+
     /// Check if maintenance should run and execute if needed
     async fn maybe_run_maintenance(&self) -> Result<(), CacheError> {
+        if self.closed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
         let mut last_maintenance = self.last_maintenance.write().await;
         if last_maintenance.elapsed() >= self.config.maintenance_interval {
             *last_maintenance = Instant::now();
@@ -151,124 +1003,786 @@ impl HybridCache {
         Ok(())
     }
 
-    /// Run maintenance tasks: promote hot items, demote cold items
+    /// Run maintenance tasks: purge expired entries, promote hot items, demote cold items
     async fn run_maintenance(&self) -> Result<(), CacheError> {
-        let mut access_tracker = self.access_tracker.write().await;
-        let mut promotions = Vec::new();
+        let _maintenance = self.maintenance.lock().await;
+        let purged = self.memory_cache.purge_expired().await?;
+        if purged > 0 {
+            tracing::debug!("Purged {} expired entries from memory", purged);
+        }
+        let purged = self.disk_cache.purge_expired().await?;
+        if purged > 0 {
+            tracing::debug!("Purged {} expired entries from disk", purged);
+        }
+
+        let promotions_paused = self.apply_memory_pressure().await != PressureLevel::Normal;
+        self.tune_thresholds().await;
+        let promotion_threshold = self.promotion_threshold();
+        let demotion_threshold = self.demotion_threshold();
+
+        let mut promotion_candidates = Vec::new();
         let mut demotions = Vec::new();
+        let mut clean_demotions = Vec::new();
+        let mut wasted_promotions = 0;
 
-        // Analyze access patterns
-        for (key, access_info) in access_tracker.iter() {
-            if access_info.frequency() >= self.config.promotion_threshold {
+        // Analyze access patterns, weighted by the cost of refetching each key
+        for (key, access_info) in self.access_tracker.snapshot() {
+            let refetch_cost = self.refetch_cost(&key);
+            let frequency = access_info.frequency() * refetch_cost;
+            if frequency >= promotion_threshold {
+                if promotions_paused || access_info.route == Route::DiskOnly {
+                    continue;
+                }
                 // Check if item is in disk cache but not in memory
-                if self.memory_cache.get(key).await.is_none() {
-                    if let Some(data) = self.disk_cache.get(key).await {
-                        promotions.push((key.clone(), data));
-                    }
+                if self.memory_cache.get(&key).await.is_none() {
+                    promotion_candidates.push((frequency, key.to_string()));
                 }
-            } else if access_info.should_demote(self.config.demotion_threshold) {
-                // Check if item is in memory cache
-                if let Some(data) = self.memory_cache.get(key).await {
-                    demotions.push((key.clone(), data));
+            } else if access_info.should_demote(demotion_threshold.mul_f64(refetch_cost))
+                && access_info.route != Route::MemoryOnly
+            {
+                if access_info.in_memory
+                    && access_info.on_disk
+                    && self.disk_cache.contains_key(&key).await
+                {
+                    // Disk already holds this value; only memory needs to let go
+                    clean_demotions.push(key.to_string());
+                } else if let Some(data) = self.memory_cache.get(&key).await {
+                    // Check if item is in memory cache
+                    demotions.push((key.to_string(), data));
                 }
             }
         }
 
-        // Execute promotions
-        for (key, data) in promotions {
-            if let Err(e) = self.memory_cache.set(&key, data).await {
-                tracing::warn!("Failed to promote key {}: {:?}", key, e);
+        // Execute promotions within this cycle's budget, hottest first,
+        // reading the disk copies and inserting into memory in one batch each
+        promotion_candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        if let Some(limit) = self.config.max_promotions_per_cycle {
+            promotion_candidates.truncate(limit);
+        }
+        let promotion_keys: Vec<String> = promotion_candidates
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect();
+        let promoted_data = self.disk_cache.get_many(&promotion_keys).await;
+        let byte_budget = self.config.max_promotion_bytes_per_cycle;
+        let mut promoted_bytes = 0;
+        let mut promotions = Vec::new();
+        for (key, data) in promotion_keys.into_iter().zip(promoted_data) {
+            let Some(data) = self.decode_from_disk(&key, data).await else {
+                continue;
+            };
+            if byte_budget.is_some_and(|budget| promoted_bytes + data.len() > budget) {
+                continue;
+            }
+            promoted_bytes += data.len();
+            promotions.push((key, data));
+        }
+        let promoted_keys: Vec<String> = promotions.iter().map(|(key, _)| key.clone()).collect();
+        let results = self.memory_cache.set_many(promotions).await;
+        for (key, result) in promoted_keys.into_iter().zip(results) {
+            if let Err(e) = result {
+                tracing::warn!("{}", e.context(CacheEventKind::Promote, Tier::Memory, &key));
             } else {
-                if let Some(access_info) = access_tracker.get_mut(&key) {
-                    access_info.mark_promoted();
-                }
+                self.access_tracker
+                    .update(&key, |access_info| access_info.mark_promoted());
+                self.log(|| CacheEvent::new(CacheEventKind::Promote, &key, CacheEventOutcome::Ok));
                 tracing::debug!("Promoted key to memory: {}", key);
             }
         }
 
-        // Execute demotions
+        // Execute demotions, writing those disk lacks in one batch, except
+        // values large enough to be streamed
+        let mut encoded = Vec::with_capacity(demotions.len());
+        let mut streamed = Vec::new();
         for (key, data) in demotions {
-            if let Err(e) = self.disk_cache.set(&key, data).await {
-                tracing::warn!("Failed to demote key {}: {:?}", key, e);
+            if self.streams_to_disk(data.len()) {
+                streamed.push((key, data));
+                continue;
+            }
+            match self.encode_for_disk(data).await {
+                Ok(data) => encoded.push((key, data)),
+                Err(e) => {
+                    tracing::warn!("{}", e.context(CacheEventKind::Demote, Tier::Disk, &key))
+                }
+            }
+        }
+        let mut demoted_keys: Vec<String> = encoded.iter().map(|(key, _)| key.clone()).collect();
+        let mut results = self.disk_cache.set_many(encoded).await;
+        for (key, data) in streamed {
+            results.push(self.write_to_disk(&key, data).await);
+            demoted_keys.push(key);
+        }
+        results.extend(clean_demotions.iter().map(|_| Ok(())));
+        for (key, result) in demoted_keys.into_iter().chain(clean_demotions).zip(results) {
+            if let Err(e) = result {
+                tracing::warn!("{}", e.context(CacheEventKind::Demote, Tier::Disk, &key));
             } else {
-                if let Err(e) = self.memory_cache.remove(&key).await {
-                    tracing::warn!("Failed to remove demoted key from memory: {:?}", e);
+                if let Err(e) = self
+                    .memory_cache
+                    .remove_with_cause(&key, RemovalCause::Demoted)
+                    .await
+                {
+                    tracing::warn!("{}", e.context(CacheEventKind::Remove, Tier::Memory, &key));
                 }
+                let promotion_pending = self.access_tracker.update(&key, |access_info| {
+                    access_info.in_memory = false;
+                    access_info.on_disk = true;
+                    std::mem::take(&mut access_info.promotion_pending)
+                });
+                if promotion_pending == Some(true) {
+                    wasted_promotions += 1;
+                }
+                self.remember_ghost(&key, true, false);
+                self.log(|| CacheEvent::new(CacheEventKind::Demote, &key, CacheEventOutcome::Ok));
                 tracing::debug!("Demoted key to disk: {}", key);
             }
         }
 
         // Clean up old access tracking entries
-        access_tracker.retain(|_, access_info| {
-            !access_info.should_demote(self.config.demotion_threshold * 2)
+        let stale = self.access_tracker.remove_where(|key, access_info| {
+            access_info.should_demote(demotion_threshold.mul_f64(2.0 * self.refetch_cost(key)))
         });
+        for (key, access_info) in stale {
+            // Still placed in its tiers; remember it in case they drop it
+            self.remember_ghost(&key, access_info.in_memory, access_info.on_disk);
+            if access_info.promotion_pending {
+                wasted_promotions += 1;
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            for _ in 0..wasted_promotions {
+                metrics.record_promotion(false).await;
+            }
+        }
+
+        self.demote_to_remote().await;
+
+        // Bound what a crash can lose from the persistent disk tier
+        self.disk_cache.persist_index().await?;
 
         Ok(())
     }
 
-    /// Update access tracking for a key
-    async fn track_access(&self, key: &String) {
-        let mut access_tracker = self.access_tracker.write().await;
-        match access_tracker.get_mut(key) {
-            Some(access_info) => access_info.update_access(),
-            None => {
-                access_tracker.insert(key.to_string(), AccessInfo::new());
+    /// Move disk entries idle past the remote demotion threshold down to the
+    /// remote tier
+    async fn demote_to_remote(&self) {
+        let Some(remote) = &self.remote else {
+            return;
+        };
+        let mut idle = Vec::new();
+        for class in self.latency_classes_in_use() {
+            let threshold = remote
+                .config
+                .demotion_threshold
+                .mul_f64(class.refetch_cost());
+            idle.extend(
+                self.disk_cache
+                    .idle_keys(threshold)
+                    .await
+                    .into_iter()
+                    .filter(|key| self.latency_class(key) == class),
+            );
+        }
+        if idle.is_empty() {
+            return;
+        }
+
+        // Entries served from memory may look idle on disk; keep them local
+        let keys: Vec<String> = idle
+            .into_iter()
+            .filter(|key| self.access_tracker.read(key, |info| info.in_memory) != Some(true))
+            .collect();
+        let values = self.disk_cache.get_many(&keys).await;
+        for (key, value) in keys.into_iter().zip(values) {
+            let Some(value) = self.decode_from_disk(&key, value).await else {
+                continue;
+            };
+            if !remote.config.write_through {
+                if let Err(e) = remote.cache.set(&key, value).await {
+                    tracing::warn!("{}", e.context(CacheEventKind::Demote, Tier::Remote, &key));
+                    continue;
+                }
+            }
+            if let Err(e) = self
+                .disk_cache
+                .remove_with_cause(&key, RemovalCause::Demoted)
+                .await
+            {
+                tracing::warn!("{}", e.context(CacheEventKind::Remove, Tier::Disk, &key));
+                continue;
             }
+            self.update_access_info(&key, |info| info.on_disk = false);
+            self.log(|| CacheEvent::new(CacheEventKind::Demote, &key, CacheEventOutcome::Ok));
+            tracing::debug!("Demoted key to remote tier: {}", key);
         }
     }
 
-    /// Get cache configuration
-    pub fn config(&self) -> &HybridCacheConfig {
-        &self.config
+    /// Serve `key` from the remote tier, copying it to a local tier if it is
+    /// accessed often enough
+    async fn get_remote(&self, key: &str) -> Option<Bytes> {
+        let remote = self.remote.as_ref()?;
+        let data = remote.cache.get(key).await?;
+
+        let frequency = self
+            .access_tracker
+            .read(key, |info| info.frequency())
+            .unwrap_or(0.0)
+            * self.refetch_cost(key);
+        if frequency < remote.config.promotion_threshold {
+            return Some(data);
+        }
+
+        let route = self.route(data.len());
+        let promoted = if route == Route::MemoryOnly {
+            self.memory_cache.set(key, data.clone()).await
+        } else {
+            self.write_to_disk(key, data.clone()).await
+        };
+        match promoted {
+            Ok(()) => self.update_access_info(key, |info| {
+                info.route = route;
+                info.in_memory = route == Route::MemoryOnly;
+                info.on_disk = route != Route::MemoryOnly;
+            }),
+            Err(e) => {
+                let tier = if route == Route::MemoryOnly {
+                    Tier::Memory
+                } else {
+                    Tier::Disk
+                };
+                tracing::warn!("{}", e.context(CacheEventKind::Promote, tier, key))
+            }
+        }
+        Some(data)
     }
 
-    /// Get access statistics for debugging
-    pub async fn access_stats(&self) -> HashMap<String, (u64, f64)> {
-        let access_tracker = self.access_tracker.read().await;
-        access_tracker
-            .iter()
-            .map(|(key, info)| (key.clone(), (info.count, info.frequency())))
-            .collect()
+    /// Write `value` through to the remote tier, or drop its remote copy
+    async fn sync_remote(&self, key: &str, value: Bytes) {
+        let Some(remote) = &self.remote else {
+            return;
+        };
+        let (operation, result) = if remote.config.write_through {
+            (CacheEventKind::Set, remote.cache.set(key, value).await)
+        } else {
+            (CacheEventKind::Remove, remote.cache.remove(key).await)
+        };
+        if let Err(e) = result {
+            tracing::warn!("{}", e.context(operation, Tier::Remote, key));
+        }
     }
-}
 
-#[async_trait::async_trait]
-impl Cache for HybridCache {
-    async fn get(&self, key: &String) -> Option<Bytes> {
+    /// Store `value` in the local tiers
+    async fn set_local(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
         // Track access
-        self.track_access(key).await;
+        self.track_access(key);
+
+        let pressure = self.apply_memory_pressure().await;
+        let mut route = self.route(value.len());
+        if route == Route::MemoryOnly && pressure == PressureLevel::Critical {
+            route = Route::Both;
+        }
+        // The tiers are given fresh copies, tracked from here on
+        self.forget_ghost(key);
+        self.update_access_info(key, |info| {
+            info.route = route;
+            info.in_memory = false;
+            info.on_disk = false;
+        });
+
+        match route {
+            Route::MemoryOnly => {
+                self.memory_cache.set(key, value).await.context(
+                    CacheEventKind::Set,
+                    Tier::Memory,
+                    key,
+                )?;
+                self.update_access_info(key, |info| info.in_memory = true);
+                // Never serve an older copy from disk
+                return self.disk_cache.remove(key).await.context(
+                    CacheEventKind::Remove,
+                    Tier::Disk,
+                    key,
+                );
+            }
+            Route::DiskOnly => {
+                self.write_to_disk(key, value).await.context(
+                    CacheEventKind::Set,
+                    Tier::Disk,
+                    key,
+                )?;
+                self.update_access_info(key, |info| info.on_disk = true);
+                return self.memory_cache.remove(key).await.context(
+                    CacheEventKind::Remove,
+                    Tier::Memory,
+                    key,
+                );
+            }
+            Route::Both => {}
+        }
+
+        // Always store in disk cache for persistence
+        self.write_to_disk(key, value.clone()).await.context(
+            CacheEventKind::Set,
+            Tier::Disk,
+            key,
+        )?;
+
+        // Store in memory cache if it fits or if frequently accessed
+        let promotion_threshold = self.promotion_threshold();
+        let refetch_cost = self.refetch_cost(key);
+        let should_cache_in_memory = self
+            .access_tracker
+            .read(key, |info| {
+                info.frequency() * refetch_cost >= promotion_threshold
+            })
+            .unwrap_or(true); // Default to caching new items in memory
+
+        let mut in_memory = false;
+        if pressure == PressureLevel::Critical {
+            // Skip memory under critical pressure, but never serve a stale copy
+            self.memory_cache.remove(key).await.context(
+                CacheEventKind::Remove,
+                Tier::Memory,
+                key,
+            )?;
+        } else if should_cache_in_memory {
+            match self.memory_cache.set(key, value).await {
+                Ok(()) => in_memory = true,
+                Err(e) => {
+                    tracing::debug!("Could not cache in memory (likely size limit): {:?}", e)
+                }
+            }
+        }
+        self.update_access_info(key, |info| {
+            info.in_memory = in_memory;
+            info.on_disk = true;
+        });
+
+        Ok(())
+    }
+
+    /// Wait for running maintenance, including its demotions, then persist
+    /// access statistics and the disk index
+    ///
+    /// Accesses served by the memory tier are recorded in the disk index, so
+    /// they survive a restart and inform eviction and warm-starts. Memory-only
+    /// entries are not persisted.
+    pub async fn flush(&self) -> Result<(), CacheError> {
+        let _maintenance = self.maintenance.lock().await;
+
+        let accesses = self
+            .access_tracker
+            .snapshot()
+            .into_iter()
+            .filter(|(_, access_info)| access_info.on_disk)
+            .map(|(key, access_info)| (key.to_string(), access_info.last_access))
+            .collect();
+        self.disk_cache.record_accesses(accesses).await;
+        self.disk_cache.persist_index().await
+    }
+
+    /// Stop maintenance and [`flush`](HybridCache::flush), for a clean shutdown
+    ///
+    /// The cache keeps serving reads and writes afterwards, but no longer
+    /// promotes, demotes or purges expired entries.
+    pub async fn close(&self) -> Result<(), CacheError> {
+        self.closed.store(true, Ordering::Relaxed);
+        self.flush().await
+    }
+
+    /// Tiers for a value of `len` bytes
+    fn route(&self, len: usize) -> Route {
+        if self
+            .config
+            .memory_only_max_bytes
+            .is_some_and(|max| len <= max)
+        {
+            Route::MemoryOnly
+        } else if self
+            .config
+            .memory_max_entry_bytes
+            .is_some_and(|max| len > max)
+        {
+            Route::DiskOnly
+        } else {
+            Route::Both
+        }
+    }
+
+    /// Whether a value of `len` bytes is compressed straight into its disk
+    /// entry file
+    fn streams_to_disk(&self, len: usize) -> bool {
+        self.disk_compression.is_some() && len >= self.config.compression_streaming_bytes
+    }
+
+    /// Store `value` on the disk tier, compressed if disk compression is on
+    async fn write_to_disk(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        match &self.disk_compression {
+            Some(compression) if self.streams_to_disk(value.len()) => {
+                let (compression, tracker) =
+                    (compression.clone(), self.compression_tracker.clone());
+                self.disk_cache
+                    .set_streaming(key, value.len(), move |writer| {
+                        encode_entry_to(&*compression, &value, &tracker, writer)
+                    })
+                    .await
+            }
+            _ => {
+                let stored = self.encode_for_disk(value).await?;
+                self.disk_cache.set(key, stored).await
+            }
+        }
+    }
+
+    /// Value as stored by the disk tier, compressed if disk compression is on
+    async fn encode_for_disk(&self, value: Bytes) -> Result<Bytes, CacheError> {
+        match &self.disk_compression {
+            Some(compression) => {
+                encode_entry_offloaded(
+                    compression.clone(),
+                    value,
+                    None,
+                    self.compression_tracker.clone(),
+                    self.config.compression_offload_bytes,
+                )
+                .await
+            }
+            None => Ok(value),
+        }
+    }
+
+    /// Value read from the disk tier, decompressed if disk compression is on
+    ///
+    /// Entries that fail to decompress, such as those written before
+    /// compression was enabled, are dropped from disk and read as misses.
+    async fn decode_from_disk(&self, key: &str, stored: Option<Bytes>) -> Option<Bytes> {
+        self.decode_from_disk_offloading(key, stored, self.config.compression_offload_bytes)
+            .await
+    }
+
+    /// [`Self::decode_from_disk`], offloading values of at least
+    /// `offload_bytes` bytes to the blocking thread pool
+    async fn decode_from_disk_offloading(
+        &self,
+        key: &str,
+        stored: Option<Bytes>,
+        offload_bytes: usize,
+    ) -> Option<Bytes> {
+        let stored = stored?;
+        let Some(compression) = &self.disk_compression else {
+            return Some(stored);
+        };
+        match decode_entry_offloaded(
+            compression.clone(),
+            stored,
+            self.compression_tracker.clone(),
+            offload_bytes,
+        )
+        .await
+        {
+            Ok(value) => Some(value),
+            Err(e) => {
+                tracing::warn!("Dropping undecodable disk entry {}: {:?}", key, e);
+                if let Err(e) = self.disk_cache.remove(key).await {
+                    tracing::warn!("Failed to remove undecodable key {}: {:?}", key, e);
+                }
+                None
+            }
+        }
+    }
+
+    /// Log `event` if an event log is attached
+    fn log(&self, event: impl FnOnce() -> CacheEvent) {
+        if let Some(events) = &self.events {
+            events.record(event());
+        }
+    }
+
+    /// Update the tracked access info of `key`, if it is tracked
+    fn update_access_info(&self, key: &str, update: impl FnOnce(&mut AccessInfo)) {
+        self.access_tracker.update(key, update);
+    }
+
+    /// Latency class of the origin store of `key`, per
+    /// [`HybridCacheConfig::latency_classes`]
+    pub fn latency_class(&self, key: &str) -> LatencyClass {
+        self.config
+            .latency_classes
+            .range::<str, _>((Bound::Unbounded, Bound::Included(key)))
+            .rev()
+            .find(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .map(|(_, class)| *class)
+            .unwrap_or_default()
+    }
+
+    fn refetch_cost(&self, key: &str) -> f64 {
+        self.latency_class(key).refetch_cost()
+    }
+
+    /// Latency classes keys may have: those configured, and the default
+    fn latency_classes_in_use(&self) -> Vec<LatencyClass> {
+        let mut classes = vec![LatencyClass::default()];
+        for class in self.config.latency_classes.values() {
+            if !classes.contains(class) {
+                classes.push(*class);
+            }
+        }
+        classes
+    }
+
+    /// Current minimum access frequency to promote to memory
+    ///
+    /// This is `promotion_threshold` from the configuration, unless ghost
+    /// lists or threshold tuning have adapted it.
+    pub fn promotion_threshold(&self) -> f64 {
+        self.thresholds
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .promotion
+    }
+
+    /// Current time of inactivity before demotion
+    ///
+    /// This is `demotion_threshold` from the configuration, unless threshold
+    /// tuning has adapted it.
+    pub fn demotion_threshold(&self) -> Duration {
+        self.thresholds
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .demotion
+    }
+
+    /// Nudge the thresholds by the memory tier hit rate and promotion
+    /// accuracy seen since the previous round, per [`ThresholdTuning`]
+    async fn tune_thresholds(&self) {
+        let Some(tuning) = &self.config.threshold_tuning else {
+            return;
+        };
+        let memory_hits = self.memory_hits.load(Ordering::Relaxed);
+        let memory_misses = self.memory_misses.load(Ordering::Relaxed);
+        let promotions = match &self.metrics {
+            Some(metrics) => Some(metrics.promotion_stats().await),
+            None => None,
+        };
+
+        let (hit_rate, accuracy) = {
+            let mut state = self.tuning.lock().unwrap_or_else(|e| e.into_inner());
+            let lookups = memory_hits + memory_misses - state.memory_hits - state.memory_misses;
+            if lookups < tuning.min_samples.max(1) {
+                return;
+            }
+            state.memory_hits = memory_hits;
+            state.memory_misses = memory_misses;
+
+            let accuracy = promotions.and_then(|promotions| {
+                let executed = promotions
+                    .promotions_executed
+                    .saturating_sub(state.promotions_executed);
+                let effective = promotions
+                    .promotions_effective
+                    .saturating_sub(state.promotions_effective);
+                state.promotions_executed = promotions.promotions_executed;
+                state.promotions_effective = promotions.promotions_effective;
+                (executed > 0).then(|| effective as f64 / executed as f64)
+            });
+            let hit_rate = self
+                .memory_lookups
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .window(tuning.hit_rate_window)
+                .hit_rate;
+            (hit_rate, accuracy)
+        };
+
+        let mut thresholds = self.thresholds.lock().unwrap_or_else(|e| e.into_inner());
+        let step = tuning.adjustment_factor;
+        if accuracy.is_some_and(|accuracy| accuracy < tuning.min_promotion_accuracy) {
+            // Promoted entries leave memory unused: promote less, demote sooner
+            thresholds.promotion *= step;
+            thresholds.demotion = thresholds.demotion.div_f64(step);
+        } else if hit_rate < tuning.target_memory_hit_rate {
+            // Memory misses too often: promote more, demote later
+            thresholds.promotion /= step;
+            thresholds.demotion = thresholds.demotion.mul_f64(step);
+        } else {
+            return;
+        }
+
+        let (min_promotion, max_promotion) = tuning.promotion_threshold_range;
+        let (min_demotion, max_demotion) = tuning.demotion_threshold_range;
+        thresholds.promotion = thresholds.promotion.clamp(min_promotion, max_promotion);
+        thresholds.demotion = thresholds.demotion.clamp(min_demotion, max_demotion);
+        tracing::debug!(
+            "Tuned thresholds to promote at {} accesses/s and demote after {:?} \
+             (memory hit rate {:.2}, promotion accuracy {:?})",
+            thresholds.promotion,
+            thresholds.demotion,
+            hit_rate,
+            accuracy
+        );
+    }
+
+    fn record_memory_lookup(&self, was_hit: bool) {
+        self.memory_lookups
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .record(was_hit);
+    }
+
+    /// Report the outcome of a pending promotion of `key`, if there is one
+    async fn settle_promotion(&self, key: &str, effective: bool) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let pending = self
+            .access_tracker
+            .update(key, |info| std::mem::take(&mut info.promotion_pending));
+        if pending == Some(true) {
+            metrics.record_promotion(effective).await;
+        }
+    }
+
+    /// Remember that `key` left the tracker or a tier while placed in the
+    /// given tiers
+    fn remember_ghost(&self, key: &str, memory: bool, disk: bool) {
+        let Some(ghosts) = &self.ghosts else {
+            return;
+        };
+        let mut ghosts = ghosts.lock().unwrap_or_else(|e| e.into_inner());
+        if memory {
+            ghosts.memory.put(key.to_string(), ());
+        }
+        if disk {
+            ghosts.disk.put(key.to_string(), ());
+        }
+    }
+
+    /// Forget `key` from the ghost lists
+    fn forget_ghost(&self, key: &str) {
+        if let Some(ghosts) = &self.ghosts {
+            let mut ghosts = ghosts.lock().unwrap_or_else(|e| e.into_inner());
+            ghosts.memory.pop(key);
+            ghosts.disk.pop(key);
+        }
+    }
+
+    /// Adapt the promotion threshold if a tier missing `key` had been given
+    /// it, either per the tracker (`placed`) or the ghost list
+    fn ghost_hit(&self, key: &str, memory: bool, placed: bool) {
+        let Some(ghosts) = &self.ghosts else {
+            return;
+        };
+        let mut ghosts = ghosts.lock().unwrap_or_else(|e| e.into_inner());
+        let ghost = if memory {
+            ghosts.memory.pop(key)
+        } else {
+            ghosts.disk.pop(key)
+        };
+        if placed || ghost.is_some() {
+            let mut thresholds = self.thresholds.lock().unwrap_or_else(|e| e.into_inner());
+            thresholds.promotion =
+                ghosts.adapt(memory, thresholds.promotion, thresholds.base_promotion);
+            tracing::debug!(
+                "Ghost hit in {} tier for {}, promotion threshold now {}",
+                if memory { "memory" } else { "disk" },
+                key,
+                thresholds.promotion
+            );
+        }
+    }
+
+    /// Update access tracking for a key
+    fn track_access(&self, key: &str) {
+        let evicted = self
+            .access_tracker
+            .access(key, AccessInfo::new, |access_info| {
+                access_info.update_access()
+            });
+        if let Some((evicted, access_info)) = evicted {
+            // Still placed in its tiers; remember it in case they drop it
+            self.remember_ghost(&evicted, access_info.in_memory, access_info.on_disk);
+        }
+    }
+
+    /// Read `key` from the first tier holding it, promoting it if warranted
+    async fn lookup(&self, key: &str) -> Option<Bytes> {
+        // Track access
+        self.track_access(key);
+        let pressure = self.apply_memory_pressure().await;
 
         // Try memory cache first (fastest)
         if let Some(data) = self.memory_cache.get(key).await {
+            self.memory_hits.fetch_add(1, Ordering::Relaxed);
+            self.record_memory_lookup(true);
+            self.settle_promotion(key, true).await;
             return Some(data);
         }
+        self.memory_misses.fetch_add(1, Ordering::Relaxed);
+        self.record_memory_lookup(false);
+
+        let (route, in_memory, on_disk) = self
+            .access_tracker
+            .read(key, |info| (info.route, info.in_memory, info.on_disk))
+            .unwrap_or((Route::Both, false, false));
 
         // Try disk cache
-        if let Some(data) = self.disk_cache.get(key).await {
+        let from_disk = match self.disk_cache.get(key).await {
+            Some(stored) => self.decode_from_disk(key, Some(stored)).await,
+            None => None,
+        };
+        if let Some(data) = from_disk {
+            self.ghost_hit(key, true, in_memory);
+            self.settle_promotion(key, false).await;
+            let route = self.route(data.len());
+            self.update_access_info(key, |info| {
+                info.route = route;
+                info.in_memory = false;
+                info.on_disk = true;
+            });
+            if route == Route::DiskOnly {
+                return Some(data);
+            }
+
             // Consider promoting frequently accessed items
-            let should_promote = {
-                let access_tracker = self.access_tracker.read().await;
-                access_tracker
-                    .get(key)
-                    .map(|info| info.frequency() >= self.config.promotion_threshold)
-                    .unwrap_or(false)
-            };
+            let promotion_threshold = self.promotion_threshold();
+            let refetch_cost = self.refetch_cost(key);
+            let should_promote = self
+                .access_tracker
+                .read(key, |info| {
+                    info.frequency() * refetch_cost >= promotion_threshold
+                })
+                .unwrap_or(false);
 
-            if should_promote {
+            if should_promote && pressure == PressureLevel::Normal {
                 // Promote to memory cache
                 if let Err(e) = self.memory_cache.set(key, data.clone()).await {
-                    tracing::warn!("Failed to promote key {}: {:?}", key, e);
+                    tracing::warn!("{}", e.context(CacheEventKind::Promote, Tier::Memory, key));
                 } else {
-                    let mut access_tracker = self.access_tracker.write().await;
-                    if let Some(access_info) = access_tracker.get_mut(key) {
-                        access_info.mark_promoted();
-                    }
+                    self.update_access_info(key, |access_info| access_info.mark_promoted());
+                    self.log(|| {
+                        CacheEvent::new(CacheEventKind::Promote, key, CacheEventOutcome::Ok)
+                            .with_size(data.len())
+                    });
                 }
             }
 
             return Some(data);
         }
 
+        if route != Route::MemoryOnly {
+            self.ghost_hit(key, false, on_disk);
+        }
+        self.settle_promotion(key, false).await;
+        self.update_access_info(key, |info| {
+            info.in_memory = false;
+            info.on_disk = false;
+        });
+
+        if let Some(data) = self.get_remote(key).await {
+            return Some(data);
+        }
+
         // Run maintenance if needed
         if let Err(e) = self.maybe_run_maintenance().await {
             tracing::warn!("Maintenance failed: {:?}", e);
@@ -277,42 +1791,183 @@ impl Cache for HybridCache {
         None
     }
 
-    async fn set(&self, key: &String, value: Bytes) -> Result<(), CacheError> {
-        // Track access
-        self.track_access(key).await;
+    /// Remove `key` from every tier and forget its access statistics
+    async fn remove_from_tiers(&self, key: &str) -> Result<(), CacheError> {
+        // Remove from both caches
+        let memory_result =
+            self.memory_cache
+                .remove(key)
+                .await
+                .context(CacheEventKind::Remove, Tier::Memory, key);
+        let disk_result =
+            self.disk_cache
+                .remove(key)
+                .await
+                .context(CacheEventKind::Remove, Tier::Disk, key);
+        // A shared remote copy must not resurface the key
+        let remote_result =
+            match &self.remote {
+                Some(remote) => remote.cache.remove(key).await.context(
+                    CacheEventKind::Remove,
+                    Tier::Remote,
+                    key,
+                ),
+                None => Ok(()),
+            };
 
-        // Always store in disk cache for persistence
-        self.disk_cache.set(key, value.clone()).await?;
+        // Remove from access tracking
+        self.access_tracker.remove(key);
+        self.forget_ghost(key);
 
-        // Store in memory cache if it fits or if frequently accessed
-        let should_cache_in_memory = {
-            let access_tracker = self.access_tracker.read().await;
-            access_tracker
-                .get(key)
-                .map(|info| info.frequency() >= self.config.promotion_threshold)
-                .unwrap_or(true) // Default to caching new items in memory
-        };
+        // Return first error if any
+        memory_result.and(disk_result).and(remote_result)
+    }
 
-        if should_cache_in_memory {
-            if let Err(e) = self.memory_cache.set(key, value).await {
-                tracing::debug!("Could not cache in memory (likely size limit): {:?}", e);
-            }
-        }
+    /// Configuration the cache was created with
+    ///
+    /// Settings changed since through [`Cache::resize`],
+    /// [`Cache::set_ttl`] or the `set_*` methods are not reflected here.
+    pub fn config(&self) -> &HybridCacheConfig {
+        &self.config
+    }
+
+    /// Change the maximum size of the disk tier, evicting entries if it
+    /// shrinks
+    pub async fn resize_disk(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.disk_cache.resize(max_size_bytes).await
+    }
+
+    /// Change the time-to-live of memory tier entries
+    pub async fn set_memory_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.memory_cache.set_ttl(ttl).await
+    }
+
+    /// Change the time-to-live of disk tier entries
+    pub async fn set_disk_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.disk_cache.set_ttl(ttl).await
+    }
 
+    /// Change the minimum access frequency to promote to memory
+    ///
+    /// Ghost lists and threshold tuning go on adapting the threshold from
+    /// this value.
+    pub fn set_promotion_threshold(&self, accesses_per_second: f64) -> Result<(), CacheError> {
+        if !(accesses_per_second.is_finite() && accesses_per_second >= 0.0) {
+            return Err(CacheError::InvalidConfig(
+                "promotion_threshold must be a non-negative number".to_string(),
+            ));
+        }
+        let mut thresholds = self.thresholds.lock().unwrap_or_else(|e| e.into_inner());
+        thresholds.promotion = accesses_per_second;
+        thresholds.base_promotion = accesses_per_second;
         Ok(())
     }
 
-    async fn remove(&self, key: &String) -> Result<(), CacheError> {
-        // Remove from both caches
-        let memory_result = self.memory_cache.remove(key).await;
-        let disk_result = self.disk_cache.remove(key).await;
+    /// Change the time of inactivity before demotion
+    pub fn set_demotion_threshold(&self, idle: Duration) {
+        self.thresholds
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .demotion = idle;
+    }
 
-        // Remove from access tracking
-        let mut access_tracker = self.access_tracker.write().await;
-        access_tracker.remove(key);
+    /// Statistics of each tier, including why entries left it
+    pub fn tier_stats(&self) -> HybridTierStats {
+        HybridTierStats {
+            memory: self.memory_cache.stats(),
+            disk: self.disk_cache.stats(),
+            remote: self.remote.as_ref().map(|remote| remote.cache.stats()),
+        }
+    }
 
-        // Return first error if any
-        memory_result.and(disk_result)
+    /// Get access statistics for debugging
+    pub async fn access_stats(&self) -> HashMap<String, (u64, f64)> {
+        self.access_tracker
+            .snapshot()
+            .into_iter()
+            .map(|(key, info)| (key.to_string(), (info.count, info.frequency())))
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for HybridCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let started = Instant::now();
+        let value = self.lookup(key).await;
+        self.log(|| {
+            let event = match &value {
+                Some(value) => CacheEvent::new(CacheEventKind::Get, key, CacheEventOutcome::Hit)
+                    .with_size(value.len()),
+                None => CacheEvent::new(CacheEventKind::Get, key, CacheEventOutcome::Miss),
+            };
+            event.with_latency(started.elapsed())
+        });
+        value
+    }
+
+    /// Check the memory tier, then the disk tier, without promoting or
+    /// tracking the access
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        if let Some(value) = self.memory_cache.get_stale(key).await {
+            return Some(value);
+        }
+        let stored = self.disk_cache.get_stale(key).await;
+        self.decode_from_disk(key, stored).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let started = Instant::now();
+        let len = value.len();
+        let result = self.set_local(key, value.clone()).await;
+        if result.is_ok() {
+            self.sync_remote(key, value).await;
+        }
+        self.log(|| {
+            CacheEvent::new(CacheEventKind::Set, key, CacheEventOutcome::of(&result))
+                .with_size(len)
+                .with_latency(started.elapsed())
+        });
+        result
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        let started = Instant::now();
+        let result = self.remove_from_tiers(key).await;
+        self.log(|| {
+            CacheEvent::new(CacheEventKind::Remove, key, CacheEventOutcome::of(&result))
+                .with_latency(started.elapsed())
+        });
+        result
+    }
+
+    /// Remove the keys starting with `prefix` from every tier, counting an
+    /// entry once per tier it was removed from
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let memory_result = self.memory_cache.remove_prefix(prefix).await;
+        let disk_result = self.disk_cache.remove_prefix(prefix).await;
+        let remote_result = match &self.remote {
+            Some(remote) => remote.cache.remove_prefix(prefix).await,
+            None => Ok(0),
+        };
+
+        self.access_tracker.remove_prefix(prefix);
+        if let Some(ghosts) = &self.ghosts {
+            let mut ghosts = ghosts.lock().unwrap_or_else(|e| e.into_inner());
+            let GhostLists { memory, disk } = &mut *ghosts;
+            for list in [memory, disk] {
+                let keys: Vec<String> = list
+                    .iter()
+                    .filter(|(key, _)| key.starts_with(prefix))
+                    .map(|(key, _)| key.clone())
+                    .collect();
+                for key in keys {
+                    list.pop(&key);
+                }
+            }
+        }
+
+        Ok(memory_result? + disk_result? + remote_result?)
     }
 
     async fn clear(&self) -> Result<(), CacheError> {
@@ -320,12 +1975,48 @@ impl Cache for HybridCache {
         let disk_result = self.disk_cache.clear().await;
 
         // Clear access tracking
-        let mut access_tracker = self.access_tracker.write().await;
-        access_tracker.clear();
+        self.access_tracker.clear();
+        if let Some(ghosts) = &self.ghosts {
+            let mut ghosts = ghosts.lock().unwrap_or_else(|e| e.into_inner());
+            ghosts.memory.clear();
+            ghosts.disk.clear();
+        }
 
         memory_result.and(disk_result)
     }
 
+    /// Size of the memory tier before any shrinking under memory pressure
+    fn capacity(&self) -> Option<usize> {
+        Some(self.memory_budget.load(Ordering::Relaxed))
+    }
+
+    /// Change the size of the memory tier; the disk tier is resized with
+    /// [`HybridCache::resize_disk`]
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        if max_size_bytes == 0 {
+            return Err(CacheError::InvalidConfig(
+                "memory_size must be greater than zero".to_string(),
+            ));
+        }
+        self.memory_budget.store(max_size_bytes, Ordering::Relaxed);
+        let target = match &self.pressure {
+            Some(signal) => signal.scaled_capacity(max_size_bytes),
+            None => max_size_bytes,
+        };
+        self.memory_cache.resize(target).await
+    }
+
+    /// Change the time-to-live of both tiers
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        let memory_result = self.memory_cache.set_ttl(ttl).await;
+        let disk_result = self.disk_cache.set_ttl(ttl).await;
+        memory_result.and(disk_result)
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        self.disk_cache.io_operations()
+    }
+
     fn size(&self) -> usize {
         self.memory_cache.size() + self.disk_cache.size()
     }
@@ -335,12 +2026,16 @@ impl Cache for HybridCache {
         let disk_stats = self.disk_cache.stats();
 
         // For hybrid cache, we need to avoid double-counting entries that exist in both tiers
-        // We'll use disk_stats as the authoritative count since all entries go to disk
+        // We'll use disk_stats as the authoritative count since all entries go to disk,
+        // except memory-only entries routed by size
         CacheStats {
             hits: memory_stats.hits + disk_stats.hits,
             misses: memory_stats.misses + disk_stats.misses,
             size_bytes: memory_stats.size_bytes + disk_stats.size_bytes,
             entry_count: disk_stats.entry_count, // Use disk as authoritative count
+            accounted_bytes: memory_stats.accounted_bytes + disk_stats.accounted_bytes,
+            removals: memory_stats.removals + disk_stats.removals,
+            compression: self.compression_tracker.stats(),
         }
     }
 }
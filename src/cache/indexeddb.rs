@@ -0,0 +1,692 @@
+use crate::cache::{Cache, CacheStats, RemovalCause, RemovalTracker, SizeTracker, StoreKey};
+use crate::compression::CompressionStats;
+use crate::error::CacheError;
+use crate::units::{ByteSize, HumanDuration, SetterError};
+use bytes::Bytes;
+use js_sys::{Array, Function, Promise, Uint8Array};
+use lru::LruCache;
+use send_wrapper::SendWrapper;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    DomException, IdbDatabase, IdbFactory, IdbRequest, IdbTransaction, IdbTransactionMode,
+};
+
+/// Object store of chunk values by key
+const VALUES: &str = "values";
+
+/// Object store of per-entry metadata by key: `[value size, creation time
+/// in unix milliseconds]`
+const METADATA: &str = "metadata";
+
+/// Version of the object stores above
+const SCHEMA_VERSION: u32 = 1;
+
+/// Configuration for the IndexedDB browser cache
+///
+/// # Default Values
+/// - `database`: "zarrs-cache"
+/// - `memory_size`: 64 MiB
+/// - `max_size_bytes`: None (bounded only by the browser's storage quota)
+/// - `ttl`: None (no expiration)
+#[derive(Debug, Clone)]
+pub struct IndexedDbCacheConfig {
+    /// Name of the IndexedDB database, shared by every page of the origin
+    pub database: String,
+    /// Bytes of recently used values also kept in memory; 0 disables the
+    /// memory tier
+    pub memory_size: usize,
+    /// Maximum total size of values stored in IndexedDB in bytes
+    pub max_size_bytes: Option<u64>,
+    /// TTL for cache entries
+    pub ttl: Option<Duration>,
+}
+
+impl Default for IndexedDbCacheConfig {
+    fn default() -> Self {
+        Self {
+            database: "zarrs-cache".to_string(),
+            memory_size: 64 * 1024 * 1024,
+            max_size_bytes: None,
+            ttl: None,
+        }
+    }
+}
+
+impl IndexedDbCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> IndexedDbCacheConfigBuilder {
+        IndexedDbCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        if self.database.is_empty() {
+            return Err(CacheError::InvalidConfig(
+                "database must not be empty".to_string(),
+            ));
+        }
+        if self.max_size_bytes == Some(0) {
+            return Err(CacheError::InvalidConfig(
+                "max_size_bytes must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`IndexedDbCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](IndexedDbCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedDbCacheConfigBuilder {
+    config: IndexedDbCacheConfig,
+    invalid: SetterError,
+}
+
+impl IndexedDbCacheConfigBuilder {
+    pub fn database(mut self, name: impl Into<String>) -> Self {
+        self.config.database = name.into();
+        self
+    }
+
+    pub fn memory_size(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.memory_size = bytes as usize;
+        }
+        self
+    }
+
+    pub fn max_size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.max_size_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<IndexedDbCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Browser cache persisting values in IndexedDB, with the most recently
+/// used ones also held in memory
+///
+/// The persistent tier of browser builds with the `wasm` feature, in place
+/// of [`DiskCache`](crate::DiskCache): entries survive page reloads and are
+/// shared by every page of the origin that opens the same database. Values
+/// and their metadata are written in one IndexedDB transaction, and a write
+/// returns once that transaction has committed.
+///
+/// The recency order used for eviction is kept in memory and seeded from
+/// entry creation times when the cache is opened. Only usable on the main
+/// thread or the worker that opened it; other threads panic on access.
+pub struct IndexedDbCache {
+    config: IndexedDbCacheConfig,
+    db: SendWrapper<IdbDatabase>,
+    index: tokio::sync::Mutex<LruCache<StoreKey, IndexedDbEntry>>,
+    memory: Mutex<MemoryTier>,
+    sizes: SizeTracker,
+    stats: CacheStatsInner,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    removals: RemovalTracker,
+}
+
+struct IndexedDbEntry {
+    size: usize,
+    created_at_ms: u64,
+}
+
+/// Copies of recently used values, dropped first when over budget
+struct MemoryTier {
+    values: LruCache<StoreKey, Bytes>,
+    size: usize,
+    max_size: usize,
+}
+
+impl MemoryTier {
+    fn insert(&mut self, key: &str, value: Bytes) {
+        self.remove(key);
+        if value.len() > self.max_size {
+            return;
+        }
+        while self.size + value.len() > self.max_size {
+            match self.values.pop_lru() {
+                Some((_, evicted)) => self.size -= evicted.len(),
+                None => break,
+            }
+        }
+        self.size += value.len();
+        self.values.put(key.to_string(), value);
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(value) = self.values.pop(key) {
+            self.size -= value.len();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.size = 0;
+    }
+}
+
+fn now_unix_ms() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+fn js_error(error: JsValue) -> CacheError {
+    let message = match error.dyn_ref::<DomException>() {
+        Some(exception) => format!("{}: {}", exception.name(), exception.message()),
+        None => format!("{error:?}"),
+    };
+    CacheError::backend(message)
+}
+
+/// A pending promise with the functions that settle it
+fn deferred() -> (Promise, Function, Function) {
+    let mut settle = None;
+    let promise = Promise::new(&mut |resolve, reject| settle = Some((resolve, reject)));
+    let (resolve, reject) = settle.expect("promise executor runs synchronously");
+    (promise, resolve, reject)
+}
+
+/// Result of `request` once it succeeds
+async fn request_result(request: &IdbRequest) -> Result<JsValue, CacheError> {
+    let (promise, resolve, reject) = deferred();
+    let on_success = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let result = request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        })
+    };
+    let on_error = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let error = request
+                .error()
+                .ok()
+                .flatten()
+                .map_or(JsValue::UNDEFINED, JsValue::from);
+            let _ = reject.call1(&JsValue::UNDEFINED, &error);
+        })
+    };
+    request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+    request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    let result = JsFuture::from(promise).await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    result.map_err(js_error)
+}
+
+/// Wait for `transaction` to commit
+async fn committed(transaction: &IdbTransaction) -> Result<(), CacheError> {
+    let (promise, resolve, reject) = deferred();
+    let on_complete = Closure::<dyn FnMut()>::new(move || {
+        let _ = resolve.call0(&JsValue::UNDEFINED);
+    });
+    let on_failure = {
+        let transaction = transaction.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let error = transaction
+                .error()
+                .map_or(JsValue::UNDEFINED, JsValue::from);
+            let _ = reject.call1(&JsValue::UNDEFINED, &error);
+        })
+    };
+    transaction.set_oncomplete(Some(on_complete.as_ref().unchecked_ref()));
+    transaction.set_onerror(Some(on_failure.as_ref().unchecked_ref()));
+    transaction.set_onabort(Some(on_failure.as_ref().unchecked_ref()));
+
+    let result = JsFuture::from(promise).await;
+    transaction.set_oncomplete(None);
+    transaction.set_onerror(None);
+    transaction.set_onabort(None);
+    result.map(|_| ()).map_err(js_error)
+}
+
+/// Open the database named `name`, creating its object stores if needed
+async fn open_database(name: &str) -> Result<IdbDatabase, CacheError> {
+    let factory: IdbFactory = js_sys::Reflect::get(&js_sys::global(), &"indexedDB".into())
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(|_| CacheError::backend("IndexedDB is not available"))?;
+
+    let request = factory
+        .open_with_u32(name, SCHEMA_VERSION)
+        .map_err(js_error)?;
+    let on_upgrade = {
+        let request = request.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let Ok(result) = request.result() else {
+                return;
+            };
+            let db: IdbDatabase = result.unchecked_into();
+            for store in [VALUES, METADATA] {
+                if let Err(e) = db.create_object_store(store) {
+                    tracing::warn!("Failed to create object store {}: {:?}", store, e);
+                }
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+
+    let result = request_result(&request).await;
+    request.set_onupgradeneeded(None);
+    Ok(result?.unchecked_into())
+}
+
+impl IndexedDbCache {
+    /// Open the cache in the database named `database`
+    pub async fn open(
+        database: impl Into<String>,
+        max_size_bytes: Option<u64>,
+    ) -> Result<Self, CacheError> {
+        Self::with_config(IndexedDbCacheConfig {
+            database: database.into(),
+            max_size_bytes,
+            ..Default::default()
+        })
+        .await
+    }
+
+    pub async fn with_config(config: IndexedDbCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+        SendWrapper::new(Self::open_inner(config)).await
+    }
+
+    async fn open_inner(config: IndexedDbCacheConfig) -> Result<Self, CacheError> {
+        let db = open_database(&config.database).await?;
+        let sizes = SizeTracker::default();
+        let index = Self::load_index(&db, &sizes).await?;
+
+        Ok(Self {
+            memory: Mutex::new(MemoryTier {
+                values: LruCache::unbounded(),
+                size: 0,
+                max_size: config.memory_size,
+            }),
+            config,
+            db: SendWrapper::new(db),
+            index: tokio::sync::Mutex::new(index),
+            sizes,
+            stats: CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                removals: RemovalTracker::default(),
+            },
+        })
+    }
+
+    pub fn config(&self) -> &IndexedDbCacheConfig {
+        &self.config
+    }
+
+    /// Read entry metadata from the database
+    async fn load_index(
+        db: &IdbDatabase,
+        sizes: &SizeTracker,
+    ) -> Result<LruCache<StoreKey, IndexedDbEntry>, CacheError> {
+        let transaction = db
+            .transaction_with_str_and_mode(METADATA, IdbTransactionMode::Readonly)
+            .map_err(js_error)?;
+        let metadata = transaction.object_store(METADATA).map_err(js_error)?;
+        let keys = metadata.get_all_keys().map_err(js_error)?;
+        let values = metadata.get_all().map_err(js_error)?;
+        let keys: Array = request_result(&keys).await?.unchecked_into();
+        let values: Array = request_result(&values).await?.unchecked_into();
+
+        // Oldest entries first, so they are the first eviction candidates
+        let mut loaded: Vec<(StoreKey, IndexedDbEntry)> = keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| {
+                let value: Array = value.dyn_into().ok()?;
+                Some((
+                    key.as_string()?,
+                    IndexedDbEntry {
+                        size: value.get(0).as_f64()? as usize,
+                        created_at_ms: value.get(1).as_f64()? as u64,
+                    },
+                ))
+            })
+            .collect();
+        loaded.sort_by_key(|(_, entry)| entry.created_at_ms);
+
+        let mut index = LruCache::unbounded();
+        for (key, entry) in loaded {
+            sizes.add(entry.size, entry.size);
+            index.put(key, entry);
+        }
+        Ok(index)
+    }
+
+    fn is_expired(&self, entry: &IndexedDbEntry) -> bool {
+        match self.config.ttl {
+            Some(ttl) => now_unix_ms().saturating_sub(entry.created_at_ms) > ttl.as_millis() as u64,
+            None => false,
+        }
+    }
+
+    fn memory(&self) -> std::sync::MutexGuard<'_, MemoryTier> {
+        self.memory.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Forget `key`, returning whether it was cached
+    fn forget(&self, index: &mut LruCache<StoreKey, IndexedDbEntry>, key: &str) -> bool {
+        self.memory().remove(key);
+        match index.pop(key) {
+            Some(entry) => {
+                self.sizes.sub(entry.size, entry.size);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Delete `keys` from the database in one transaction
+    async fn delete_keys(&self, keys: &[impl AsRef<str>]) -> Result<(), CacheError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let transaction = self.write_transaction()?;
+        let values = transaction.object_store(VALUES).map_err(js_error)?;
+        let metadata = transaction.object_store(METADATA).map_err(js_error)?;
+        for key in keys {
+            let key = JsValue::from_str(key.as_ref());
+            values.delete(&key).map_err(js_error)?;
+            metadata.delete(&key).map_err(js_error)?;
+        }
+        committed(&transaction).await
+    }
+
+    fn write_transaction(&self) -> Result<IdbTransaction, CacheError> {
+        let stores = Array::of2(&VALUES.into(), &METADATA.into());
+        self.db
+            .transaction_with_str_sequence_and_mode(&stores, IdbTransactionMode::Readwrite)
+            .map_err(js_error)
+    }
+
+    /// Pick least recently used keys to make room for `incoming` bytes
+    /// stored under `key`, whose current value is released by the write
+    fn eviction_victims(
+        &self,
+        index: &LruCache<StoreKey, IndexedDbEntry>,
+        key: &str,
+        incoming: usize,
+    ) -> Result<Vec<StoreKey>, CacheError> {
+        let Some(max_size) = self.config.max_size_bytes else {
+            return Ok(Vec::new());
+        };
+        let max_size = max_size as usize;
+        if incoming > max_size {
+            return Err(CacheError::EntryTooLarge {
+                size: incoming,
+                max: max_size,
+            });
+        }
+
+        let replaced = index.peek(key).map_or(0, |entry| entry.size);
+        let mut total = self.sizes.logical() - replaced;
+        let mut victims = Vec::new();
+        for (victim, entry) in index.iter().rev() {
+            if total + incoming <= max_size {
+                break;
+            }
+            if victim != key {
+                total -= entry.size;
+                victims.push(victim.clone());
+            }
+        }
+
+        Ok(victims)
+    }
+
+    async fn get_inner(&self, key: &str) -> Option<Bytes> {
+        {
+            let mut index = self.index.lock().await;
+            let Some(entry) = index.get(key) else {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+
+            if self.is_expired(entry) {
+                self.forget(&mut index, key);
+                self.stats.removals.record(RemovalCause::Expired, 1);
+                drop(index);
+                if let Err(e) = self.delete_keys(&[key]).await {
+                    tracing::warn!("Failed to delete expired entry {}: {:?}", key, e);
+                }
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+        }
+
+        if let Some(data) = self.memory().values.get(key).cloned() {
+            self.stats.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(data);
+        }
+
+        let read = async {
+            let transaction = self.db.transaction_with_str(VALUES).map_err(js_error)?;
+            let values = transaction.object_store(VALUES).map_err(js_error)?;
+            let request = values.get(&JsValue::from_str(key)).map_err(js_error)?;
+            let value = request_result(&request).await?;
+            Ok::<_, CacheError>(
+                value
+                    .dyn_into::<Uint8Array>()
+                    .ok()
+                    .map(|value| Bytes::from(value.to_vec())),
+            )
+        };
+
+        match read.await {
+            Ok(Some(data)) => {
+                self.memory().insert(key, data.clone());
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                Some(data)
+            }
+            Ok(None) => {
+                // Removed concurrently, possibly by another page
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read cache entry {}: {:?}", key, e);
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    async fn set_inner(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let mut index = self.index.lock().await;
+
+        let victims = self.eviction_victims(&index, key, value.len())?;
+
+        let created_at_ms = now_unix_ms();
+        let transaction = self.write_transaction()?;
+        let values = transaction.object_store(VALUES).map_err(js_error)?;
+        let metadata = transaction.object_store(METADATA).map_err(js_error)?;
+        for victim in &victims {
+            let victim = JsValue::from_str(victim);
+            values.delete(&victim).map_err(js_error)?;
+            metadata.delete(&victim).map_err(js_error)?;
+        }
+        let js_key = JsValue::from_str(key);
+        values
+            .put_with_key(&Uint8Array::from(value.as_ref()), &js_key)
+            .map_err(js_error)?;
+        let entry = Array::of2(&(value.len() as f64).into(), &(created_at_ms as f64).into());
+        metadata.put_with_key(&entry, &js_key).map_err(js_error)?;
+        committed(&transaction).await?;
+
+        for victim in &victims {
+            self.forget(&mut index, victim);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Evicted, victims.len() as u64);
+        self.forget(&mut index, key);
+        self.sizes.add(value.len(), value.len());
+        index.put(
+            key.to_string(),
+            IndexedDbEntry {
+                size: value.len(),
+                created_at_ms,
+            },
+        );
+        self.memory().insert(key, value);
+
+        Ok(())
+    }
+
+    async fn remove_inner(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut index = self.index.lock().await;
+
+        if index.contains(key) {
+            self.delete_keys(&[key]).await?;
+            self.forget(&mut index, key);
+            self.stats.removals.record(cause, 1);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_prefix_inner(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut index = self.index.lock().await;
+        let keys: Vec<StoreKey> = index
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        self.delete_keys(&keys).await?;
+        for key in &keys {
+            self.forget(&mut index, key);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Removed, keys.len() as u64);
+
+        Ok(keys.len())
+    }
+
+    async fn clear_inner(&self) -> Result<(), CacheError> {
+        let mut index = self.index.lock().await;
+
+        let transaction = self.write_transaction()?;
+        for store in [VALUES, METADATA] {
+            transaction
+                .object_store(store)
+                .and_then(|store| store.clear())
+                .map_err(js_error)?;
+        }
+        committed(&transaction).await?;
+
+        index.clear();
+        self.memory().clear();
+        self.sizes.reset();
+
+        Ok(())
+    }
+
+    async fn purge_expired_inner(&self) -> Result<usize, CacheError> {
+        if self.config.ttl.is_none() {
+            return Ok(0);
+        }
+
+        let mut index = self.index.lock().await;
+        let expired: Vec<StoreKey> = index
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        self.delete_keys(&expired).await?;
+        for key in &expired {
+            self.forget(&mut index, key);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Expired, expired.len() as u64);
+
+        Ok(expired.len())
+    }
+}
+
+// IndexedDB futures hold JavaScript values and are not `Send`. The cache is
+// only ever used from the thread that opened it, which `SendWrapper` checks.
+#[async_trait::async_trait]
+impl Cache for IndexedDbCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        SendWrapper::new(self.get_inner(key)).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        SendWrapper::new(self.set_inner(key, value)).await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        SendWrapper::new(self.remove_inner(key, cause)).await
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        SendWrapper::new(self.remove_prefix_inner(prefix)).await
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        SendWrapper::new(self.clear_inner()).await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        SendWrapper::new(self.purge_expired_inner()).await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.config.max_size_bytes.map(|max| max as usize)
+    }
+
+    fn size(&self) -> usize {
+        self.sizes.logical()
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes: self.sizes.logical(),
+            entry_count: self.sizes.entries(),
+            accounted_bytes: self.sizes.accounted(),
+            removals: self.stats.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
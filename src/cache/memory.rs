@@ -1,4 +1,8 @@
-use crate::cache::{Cache, CacheStats, StoreKey};
+use crate::cache::{
+    entry_footprint, AtomicTtl, Cache, CacheStats, RemovalCause, RemovalTracker, SizeTracker,
+    StoreKey,
+};
+use crate::compression::CompressionStats;
 use crate::error::CacheError;
 use bytes::Bytes;
 use lru::LruCache;
@@ -9,20 +13,28 @@ use tokio::sync::RwLock;
 
 pub struct LruMemoryCache {
     inner: Arc<RwLock<LruCache<StoreKey, CacheEntry>>>,
-    max_size_bytes: usize,
-    current_size: Arc<AtomicUsize>,
+    max_size_bytes: AtomicUsize,
+    sizes: Arc<SizeTracker>,
+    removals: Arc<RemovalTracker>,
     stats: Arc<CacheStatsInner>,
-    ttl: Option<Duration>,
+    ttl: Arc<AtomicTtl>,
+    /// Move an entry to the MRU position on only one in this many hits
+    promotion_interval: u64,
 }
 
 struct CacheEntry {
     data: Bytes,
+    /// Write time, moved by the TTL jitter
     timestamp: std::time::Instant,
+    /// Accounted size including key and bookkeeping overhead
+    footprint: usize,
 }
 
 struct CacheStatsInner {
     hits: AtomicU64,
     misses: AtomicU64,
+    /// Lookup counter used to pick which hits update the LRU order
+    sampled_hits: AtomicU64,
 }
 
 impl LruMemoryCache {
@@ -33,55 +45,138 @@ impl LruMemoryCache {
     pub fn with_ttl(max_size_bytes: usize, ttl: Option<Duration>) -> Self {
         Self {
             inner: Arc::new(RwLock::new(LruCache::unbounded())),
-            max_size_bytes,
-            current_size: Arc::new(AtomicUsize::new(0)),
+            max_size_bytes: AtomicUsize::new(max_size_bytes),
+            sizes: Arc::new(SizeTracker::default()),
+            removals: Arc::new(RemovalTracker::default()),
             stats: Arc::new(CacheStatsInner {
                 hits: AtomicU64::new(0),
                 misses: AtomicU64::new(0),
+                sampled_hits: AtomicU64::new(0),
             }),
-            ttl,
+            ttl: Arc::new(AtomicTtl::new(ttl)),
+            promotion_interval: 1,
         }
     }
 
+    /// Only update the LRU order on one in every `every_n_hits` hits
+    ///
+    /// Other hits are served under a shared read lock without touching the
+    /// recency list, so hot keys no longer serialize readers on the write
+    /// lock. Popular entries are still promoted often enough to stay near the
+    /// MRU end; the eviction order becomes an approximation of true LRU.
+    /// A value of 1 (the default) promotes on every hit.
+    pub fn with_promotion_sampling(mut self, every_n_hits: u64) -> Self {
+        self.promotion_interval = every_n_hits.max(1);
+        self
+    }
+
+    /// Move each entry's expiry by a random amount of up to `jitter` times
+    /// the TTL, earlier or later
+    ///
+    /// Entries written together, as by a warming run, then expire over a
+    /// window instead of all at once. `jitter` is clamped to 0..=1; 0 (the
+    /// default) expires every entry exactly one TTL after it was written.
+    pub fn with_ttl_jitter(mut self, jitter: f64) -> Self {
+        self.ttl = Arc::new(AtomicTtl::new(self.ttl.get()).with_jitter(jitter));
+        self
+    }
+
+    /// Whether this hit should skip the LRU update
+    fn skip_promotion(&self) -> bool {
+        self.promotion_interval > 1
+            && !self
+                .stats
+                .sampled_hits
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.promotion_interval)
+    }
+
     fn is_expired(&self, entry: &CacheEntry) -> bool {
-        if let Some(ttl) = self.ttl {
+        if let Some(ttl) = self.ttl.get() {
             entry.timestamp.elapsed() > ttl
         } else {
             false
         }
     }
 
-    async fn cleanup_expired(&self) {
-        if self.ttl.is_none() {
-            return;
-        }
+    /// Spawn a background task that purges expired entries every `interval`
+    ///
+    /// The task holds only a weak reference and exits once the cache is
+    /// dropped. While no TTL is set it sweeps nothing.
+    pub fn spawn_expiry_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let inner = Arc::downgrade(&self.inner);
+        let sizes = self.sizes.clone();
+        let removals = self.removals.clone();
+        let ttl = self.ttl.clone();
 
-        let mut cache = self.inner.write().await;
-        let mut expired_keys = Vec::new();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
 
-        // Collect expired keys
-        for (key, entry) in cache.iter() {
-            if self.is_expired(entry) {
-                expired_keys.push(key.clone());
+            loop {
+                ticker.tick().await;
+                let Some(inner) = inner.upgrade() else {
+                    break;
+                };
+                let Some(ttl) = ttl.get() else {
+                    continue;
+                };
+                let purged = purge_expired_entries(&inner, &sizes, &removals, ttl).await;
+                if purged > 0 {
+                    tracing::debug!("Expiry sweeper purged {} entries", purged);
+                }
             }
+        })
+    }
+
+    /// Put `value` under `key` in the locked cache, evicting to make room
+    fn insert(
+        &self,
+        cache: &mut LruCache<StoreKey, CacheEntry>,
+        key: &str,
+        value: Bytes,
+    ) -> Result<(), CacheError> {
+        let value_size = value.len();
+        let footprint = entry_footprint(key, value_size);
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
+        if footprint > max_size_bytes {
+            // Checked before touching the entry it would replace, which
+            // stays cached
+            return Err(CacheError::EntryTooLarge {
+                size: footprint,
+                max: max_size_bytes,
+            });
         }
 
-        // Remove expired entries
-        for key in expired_keys {
-            if let Some(entry) = cache.pop(&key) {
-                self.current_size
-                    .fetch_sub(entry.data.len(), Ordering::Relaxed);
-            }
+        // Replacing an entry releases its old size first
+        if let Some(old) = cache.pop(key) {
+            self.sizes.sub(old.data.len(), old.footprint);
         }
-    }
 
-    async fn evict_if_needed(&self, incoming_size: usize) -> Result<(), CacheError> {
-        let mut cache = self.inner.write().await;
+        self.evict_if_needed(cache, footprint)?;
 
-        while self.current_size.load(Ordering::Relaxed) + incoming_size > self.max_size_bytes {
+        let entry = CacheEntry {
+            data: value,
+            timestamp: self.ttl.expiry_base(Instant::now()),
+            footprint,
+        };
+
+        cache.put(key.to_string(), entry);
+        self.sizes.add(value_size, footprint);
+
+        Ok(())
+    }
+
+    fn evict_if_needed(
+        &self,
+        cache: &mut LruCache<StoreKey, CacheEntry>,
+        incoming_footprint: usize,
+    ) -> Result<(), CacheError> {
+        let max_size_bytes = self.max_size_bytes.load(Ordering::Relaxed);
+        while self.sizes.accounted() + incoming_footprint > max_size_bytes {
             if let Some((_, entry)) = cache.pop_lru() {
-                self.current_size
-                    .fetch_sub(entry.data.len(), Ordering::Relaxed);
+                self.sizes.sub(entry.data.len(), entry.footprint);
+                self.removals.record(RemovalCause::Evicted, 1);
             } else {
                 return Err(CacheError::CacheFull);
             }
@@ -91,11 +186,50 @@ impl LruMemoryCache {
     }
 }
 
+async fn purge_expired_entries(
+    inner: &RwLock<LruCache<StoreKey, CacheEntry>>,
+    sizes: &SizeTracker,
+    removals: &RemovalTracker,
+    ttl: Duration,
+) -> usize {
+    let mut cache = inner.write().await;
+
+    // Collect expired keys
+    let expired_keys: Vec<StoreKey> = cache
+        .iter()
+        .filter(|(_, entry)| entry.timestamp.elapsed() > ttl)
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    // Remove expired entries
+    for key in &expired_keys {
+        if let Some(entry) = cache.pop(key) {
+            sizes.sub(entry.data.len(), entry.footprint);
+        }
+    }
+    removals.record(RemovalCause::Expired, expired_keys.len() as u64);
+
+    expired_keys.len()
+}
+
 #[async_trait::async_trait]
 impl Cache for LruMemoryCache {
-    async fn get(&self, key: &StoreKey) -> Option<Bytes> {
-        // Clean up expired entries periodically
-        self.cleanup_expired().await;
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        if self.skip_promotion() {
+            let cache = self.inner.read().await;
+            match cache.peek(key) {
+                Some(entry) if !self.is_expired(entry) => {
+                    self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(entry.data.clone());
+                }
+                None => {
+                    self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                    return None;
+                }
+                // Expired: fall through to drop it under the write lock
+                Some(_) => {}
+            }
+        }
 
         let mut cache = self.inner.write().await;
 
@@ -104,8 +238,9 @@ impl Cache for LruMemoryCache {
             if self.is_expired(entry) {
                 // Remove expired entry
                 if let Some(expired_entry) = cache.pop(key) {
-                    self.current_size
-                        .fetch_sub(expired_entry.data.len(), Ordering::Relaxed);
+                    self.sizes
+                        .sub(expired_entry.data.len(), expired_entry.footprint);
+                    self.removals.record(RemovalCause::Expired, 1);
                 }
                 self.stats.misses.fetch_add(1, Ordering::Relaxed);
                 None
@@ -119,53 +254,131 @@ impl Cache for LruMemoryCache {
         }
     }
 
-    async fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), CacheError> {
-        let value_size = value.len();
-
-        self.evict_if_needed(value_size).await?;
-
-        let entry = CacheEntry {
-            data: value,
-            timestamp: Instant::now(),
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        let data = self
+            .inner
+            .read()
+            .await
+            .peek(key)
+            .map(|entry| entry.data.clone());
+        let counter = if data.is_some() {
+            &self.stats.hits
+        } else {
+            &self.stats.misses
         };
+        counter.fetch_add(1, Ordering::Relaxed);
+        data
+    }
 
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
         let mut cache = self.inner.write().await;
-        cache.put(key.clone(), entry);
-        self.current_size.fetch_add(value_size, Ordering::Relaxed);
+        self.insert(&mut cache, key, value)
+    }
 
-        Ok(())
+    /// Insert the whole batch under a single write lock
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        let mut cache = self.inner.write().await;
+        entries
+            .into_iter()
+            .map(|(key, value)| self.insert(&mut cache, &key, value))
+            .collect()
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
     }
 
-    async fn remove(&self, key: &StoreKey) -> Result<(), CacheError> {
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
         let mut cache = self.inner.write().await;
 
         if let Some(entry) = cache.pop(key) {
-            self.current_size
-                .fetch_sub(entry.data.len(), Ordering::Relaxed);
+            self.sizes.sub(entry.data.len(), entry.footprint);
+            self.removals.record(cause, 1);
         }
 
         Ok(())
     }
 
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut cache = self.inner.write().await;
+        let keys: Vec<StoreKey> = cache
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            if let Some(entry) = cache.pop(key) {
+                self.sizes.sub(entry.data.len(), entry.footprint);
+            }
+        }
+        self.removals
+            .record(RemovalCause::Removed, keys.len() as u64);
+        Ok(keys.len())
+    }
+
     async fn clear(&self) -> Result<(), CacheError> {
         let mut cache = self.inner.write().await;
         cache.clear();
-        self.current_size.store(0, Ordering::Relaxed);
+        self.sizes.reset();
+        Ok(())
+    }
+
+    /// Reads only check the entry they touch, so expired entries that are
+    /// never read again are reclaimed here or by eviction pressure.
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        match self.ttl.get() {
+            Some(ttl) => {
+                Ok(purge_expired_entries(&self.inner, &self.sizes, &self.removals, ttl).await)
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.max_size_bytes.load(Ordering::Relaxed))
+    }
+
+    async fn eviction_candidate(&self, key: &str, value_len: usize) -> Option<StoreKey> {
+        let cache = self.inner.read().await;
+        let replaced = cache.peek(key).map_or(0, |entry| entry.footprint);
+        let needed =
+            self.sizes.accounted().saturating_sub(replaced) + entry_footprint(key, value_len);
+        if needed <= self.max_size_bytes.load(Ordering::Relaxed) {
+            return None;
+        }
+        cache
+            .iter()
+            .rev()
+            .map(|(candidate, _)| candidate)
+            .find(|candidate| candidate.as_str() != key)
+            .cloned()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        let mut cache = self.inner.write().await;
+        self.max_size_bytes.store(max_size_bytes, Ordering::Relaxed);
+        self.evict_if_needed(&mut cache, 0)
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.ttl.set(ttl);
+        self.purge_expired().await?;
         Ok(())
     }
 
     fn size(&self) -> usize {
-        self.current_size.load(Ordering::Relaxed)
+        self.sizes.logical()
     }
 
     fn stats(&self) -> CacheStats {
-        let cache_guard = futures::executor::block_on(self.inner.read());
-
         CacheStats {
             hits: self.stats.hits.load(Ordering::Relaxed),
             misses: self.stats.misses.load(Ordering::Relaxed),
-            size_bytes: self.current_size.load(Ordering::Relaxed),
-            entry_count: cache_guard.len(),
+            size_bytes: self.sizes.logical(),
+            entry_count: self.sizes.entries(),
+            accounted_bytes: self.sizes.accounted(),
+            removals: self.removals.counts(),
+            compression: CompressionStats::default(),
         }
     }
 }
@@ -1,23 +1,322 @@
+use crate::compression::CompressionStats;
 use crate::error::CacheError;
 use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 pub type StoreKey = String;
 
+/// Approximate bookkeeping cost of one in-memory entry beyond its key and value bytes
+///
+/// Covers the key `String` header, the `Bytes` handle and its shared header,
+/// the insertion timestamp, and the index node and hash bucket.
+pub const ENTRY_OVERHEAD_BYTES: usize = 128;
+
+/// Approximate memory footprint of an in-memory entry
+pub fn entry_footprint(key: &str, value_len: usize) -> usize {
+    key.len() + value_len + ENTRY_OVERHEAD_BYTES
+}
+
+/// Hash of `key` that is the same in every process and on every node
+pub(crate) fn stable_hash(key: &str) -> u64 {
+    let hash = blake3::hash(key.as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Entry count and logical and accounted byte counters shared by the
+/// memory caches
+///
+/// Every [`add`](SizeTracker::add) is one entry stored and every
+/// [`sub`](SizeTracker::sub) one entry dropped, so stats can be read
+/// without taking the cache's lock.
+#[derive(Debug, Default)]
+pub(crate) struct SizeTracker {
+    entries: AtomicUsize,
+    logical: AtomicUsize,
+    accounted: AtomicUsize,
+}
+
+impl SizeTracker {
+    pub(crate) fn add(&self, logical: usize, accounted: usize) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        self.logical.fetch_add(logical, Ordering::Relaxed);
+        self.accounted.fetch_add(accounted, Ordering::Relaxed);
+    }
+
+    pub(crate) fn sub(&self, logical: usize, accounted: usize) {
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.logical.fetch_sub(logical, Ordering::Relaxed);
+        self.accounted.fetch_sub(accounted, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reset(&self) {
+        self.entries.store(0, Ordering::Relaxed);
+        self.logical.store(0, Ordering::Relaxed);
+        self.accounted.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn entries(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn logical(&self) -> usize {
+        self.logical.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn accounted(&self) -> usize {
+        self.accounted.load(Ordering::Relaxed)
+    }
+}
+
+/// Optional time-to-live that can be replaced while the cache is in use
+///
+/// Carries the TTL jitter of its cache: entries record the instant returned
+/// by [`AtomicTtl::expiry_base`] and expire once `ttl` has elapsed since it.
+#[derive(Debug)]
+pub(crate) struct AtomicTtl {
+    nanos: AtomicU64,
+    /// Fraction of the TTL, between 0 and 1, by which each entry's expiry
+    /// is moved earlier or later
+    jitter: f64,
+}
+
+impl AtomicTtl {
+    /// Stored in place of a TTL when there is none
+    const NONE: u64 = u64::MAX;
+
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            nanos: AtomicU64::new(Self::encode(ttl)),
+            jitter: 0.0,
+        }
+    }
+
+    /// Spread expiries by up to `jitter` times the TTL either way
+    pub(crate) fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub(crate) fn get(&self) -> Option<Duration> {
+        match self.nanos.load(Ordering::Relaxed) {
+            Self::NONE => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Replace the TTL, returning the previous one
+    pub(crate) fn set(&self, ttl: Option<Duration>) -> Option<Duration> {
+        match self.nanos.swap(Self::encode(ttl), Ordering::Relaxed) {
+            Self::NONE => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Instant from which the TTL of an entry written at `at` runs
+    ///
+    /// `at` moved by a random offset of up to the jitter times the current
+    /// TTL, so entries written together do not all expire together.
+    pub(crate) fn expiry_base(&self, at: Instant) -> Instant {
+        let Some(ttl) = self.get().filter(|_| self.jitter > 0.0) else {
+            return at;
+        };
+        // Uniform in [-1, 1]
+        let unit = RandomState::new().hash_one(at) as f64 / u64::MAX as f64 * 2.0 - 1.0;
+        let offset = ttl.mul_f64(self.jitter * unit.abs());
+        let base = if unit < 0.0 {
+            at.checked_sub(offset)
+        } else {
+            at.checked_add(offset)
+        };
+        base.unwrap_or(at)
+    }
+
+    fn encode(ttl: Option<Duration>) -> u64 {
+        ttl.map_or(Self::NONE, |ttl| {
+            u64::try_from(ttl.as_nanos()).map_or(Self::NONE - 1, |nanos| nanos.min(Self::NONE - 1))
+        })
+    }
+}
+
+/// Why an entry left a cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovalCause {
+    /// Pushed out to make room
+    Evicted,
+    /// Outlived its time-to-live
+    Expired,
+    /// Removed by a caller
+    Removed,
+    /// Moved down to a slower tier
+    Demoted,
+}
+
+/// Entries that left a cache, by cause
+///
+/// Entries dropped by [`Cache::clear`] are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovalCounts {
+    pub evicted: u64,
+    pub expired: u64,
+    pub removed: u64,
+    pub demoted: u64,
+}
+
+impl RemovalCounts {
+    pub fn total(&self) -> u64 {
+        self.evicted + self.expired + self.removed + self.demoted
+    }
+}
+
+impl std::ops::AddAssign for RemovalCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.evicted += other.evicted;
+        self.expired += other.expired;
+        self.removed += other.removed;
+        self.demoted += other.demoted;
+    }
+}
+
+impl std::ops::Add for RemovalCounts {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+/// Removal counters shared by the caches
+#[derive(Debug, Default)]
+pub(crate) struct RemovalTracker {
+    evicted: AtomicU64,
+    expired: AtomicU64,
+    removed: AtomicU64,
+    demoted: AtomicU64,
+}
+
+impl RemovalTracker {
+    pub(crate) fn record(&self, cause: RemovalCause, entries: u64) {
+        let counter = match cause {
+            RemovalCause::Evicted => &self.evicted,
+            RemovalCause::Expired => &self.expired,
+            RemovalCause::Removed => &self.removed,
+            RemovalCause::Demoted => &self.demoted,
+        };
+        counter.fetch_add(entries, Ordering::Relaxed);
+    }
+
+    pub(crate) fn counts(&self) -> RemovalCounts {
+        RemovalCounts {
+            evicted: self.evicted.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            removed: self.removed.load(Ordering::Relaxed),
+            demoted: self.demoted.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Core caching trait for zarr data storage
+///
+/// Keys are borrowed, so callers holding a `&str` do not allocate a
+/// `String` per call.
 #[async_trait::async_trait]
 pub trait Cache: Send + Sync + 'static {
     /// Get data from cache by key
-    async fn get(&self, key: &StoreKey) -> Option<Bytes>;
+    async fn get(&self, key: &str) -> Option<Bytes>;
+
+    /// Get data from cache by key even if it has expired, leaving it cached
+    ///
+    /// Lets a read-through layer serve a stale copy while the origin store
+    /// is unavailable. The default is [`Cache::get`], for caches that drop
+    /// expired entries as soon as they are found.
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        self.get(key).await
+    }
 
     /// Store data in cache with key
-    async fn set(&self, key: &StoreKey, value: Bytes) -> Result<(), CacheError>;
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError>;
+
+    /// Store several entries, returning one result per entry in order
+    ///
+    /// The default stores them one at a time; caches that can take their
+    /// lock once for the whole batch override it.
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        let mut results = Vec::with_capacity(entries.len());
+        for (key, value) in entries {
+            results.push(self.set(&key, value).await);
+        }
+        results
+    }
 
     /// Remove data from cache
-    async fn remove(&self, key: &StoreKey) -> Result<(), CacheError>;
+    async fn remove(&self, key: &str) -> Result<(), CacheError>;
+
+    /// Remove data from cache, counting it as leaving for `cause`
+    ///
+    /// The default removes it like [`Cache::remove`]; caches that count
+    /// removals override it.
+    async fn remove_with_cause(&self, key: &str, _cause: RemovalCause) -> Result<(), CacheError> {
+        self.remove(key).await
+    }
+
+    /// Remove every entry whose key starts with `prefix`, such as all
+    /// chunks of an array, returning how many were removed
+    async fn remove_prefix(&self, _prefix: &str) -> Result<usize, CacheError> {
+        Err(CacheError::Unsupported("remove_prefix".to_string()))
+    }
 
     /// Clear all cached data
     async fn clear(&self) -> Result<(), CacheError>;
 
+    /// Drop expired entries, returning how many were removed
+    ///
+    /// Caches without TTL support keep the default no-op.
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        Ok(0)
+    }
+
+    /// Maximum size in bytes, if the cache is bounded
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// Key the cache would evict first to make room for a `value_len`-byte
+    /// value under `key`
+    ///
+    /// `None` when the value fits without evicting anything, or for caches
+    /// that do not keep a single eviction order.
+    async fn eviction_candidate(&self, _key: &str, _value_len: usize) -> Option<StoreKey> {
+        None
+    }
+
+    /// Entry file reads, writes and deletions performed so far, for caches
+    /// that keep entries in files
+    fn io_operations(&self) -> Option<u64> {
+        None
+    }
+
+    /// Change the maximum size, evicting entries if the cache shrinks
+    async fn resize(&self, _max_size_bytes: usize) -> Result<(), CacheError> {
+        Err(CacheError::Unsupported("resize".to_string()))
+    }
+
+    /// Change the time-to-live of entries, or remove it with `None`
+    ///
+    /// The new TTL applies to entries already cached, counted from when they
+    /// were written; entries it has expired are purged at once.
+    async fn set_ttl(&self, _ttl: Option<Duration>) -> Result<(), CacheError> {
+        Err(CacheError::Unsupported("set_ttl".to_string()))
+    }
+
     /// Get current cache size in bytes
     fn size(&self) -> usize;
 
@@ -25,12 +324,87 @@ pub trait Cache: Send + Sync + 'static {
     fn stats(&self) -> CacheStats;
 }
 
-#[derive(Debug, Clone)]
+#[async_trait::async_trait]
+impl<C: Cache + ?Sized> Cache for std::sync::Arc<C> {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        (**self).get(key).await
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        (**self).get_stale(key).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        (**self).set(key, value).await
+    }
+
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        (**self).set_many(entries).await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        (**self).remove(key).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        (**self).remove_with_cause(key, cause).await
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        (**self).remove_prefix(prefix).await
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        (**self).clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        (**self).purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        (**self).capacity()
+    }
+
+    async fn eviction_candidate(&self, key: &str, value_len: usize) -> Option<StoreKey> {
+        (**self).eviction_candidate(key, value_len).await
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        (**self).io_operations()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        (**self).resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        (**self).set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        (**self).size()
+    }
+
+    fn stats(&self) -> CacheStats {
+        (**self).stats()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CacheStats {
     pub hits: u64,
     pub misses: u64,
+    /// Logical size: sum of cached value lengths
     pub size_bytes: usize,
     pub entry_count: usize,
+    /// Accounted size: value bytes plus approximate per-entry overhead
+    /// (key bytes and bookkeeping). Memory budgets are enforced against this.
+    pub accounted_bytes: usize,
+    /// Entries that left the cache, by cause
+    pub removals: RemovalCounts,
+    /// Work of compressing values, empty unless the cache compresses them
+    pub compression: CompressionStats,
 }
 
 impl CacheStats {
@@ -45,6 +419,74 @@ impl CacheStats {
     }
 }
 
+/// Eviction policy for in-memory caches
+///
+/// # Default Value
+/// - `Lru`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryPolicy {
+    /// Least-recently-used eviction ([`memory::LruMemoryCache`])
+    #[default]
+    Lru,
+    /// Adaptive replacement cache ([`arc::ArcMemoryCache`])
+    Arc,
+    /// CLOCK second-chance eviction ([`clock::ClockMemoryCache`])
+    Clock,
+}
+
+impl MemoryPolicy {
+    /// Build an in-memory cache using this policy
+    pub fn build(
+        self,
+        max_size_bytes: usize,
+        ttl: Option<std::time::Duration>,
+    ) -> std::sync::Arc<dyn Cache> {
+        self.build_with_ttl_jitter(max_size_bytes, ttl, 0.0)
+    }
+
+    /// Build an in-memory cache using this policy whose entries expire up to
+    /// `jitter` times the TTL earlier or later
+    pub fn build_with_ttl_jitter(
+        self,
+        max_size_bytes: usize,
+        ttl: Option<std::time::Duration>,
+        jitter: f64,
+    ) -> std::sync::Arc<dyn Cache> {
+        match self {
+            MemoryPolicy::Lru => std::sync::Arc::new(
+                memory::LruMemoryCache::with_ttl(max_size_bytes, ttl).with_ttl_jitter(jitter),
+            ),
+            MemoryPolicy::Arc => std::sync::Arc::new(
+                arc::ArcMemoryCache::with_ttl(max_size_bytes, ttl).with_ttl_jitter(jitter),
+            ),
+            MemoryPolicy::Clock => std::sync::Arc::new(
+                clock::ClockMemoryCache::with_ttl(max_size_bytes, ttl).with_ttl_jitter(jitter),
+            ),
+        }
+    }
+}
+
+pub mod admission;
+pub mod arc;
+mod bloom;
+pub mod clock;
+pub mod cluster;
+#[cfg(target_os = "linux")]
+mod direct_io;
 pub mod disk;
+#[cfg(feature = "redb")]
+pub mod embedded;
+#[cfg(unix)]
+mod handles;
 pub mod hybrid;
+#[cfg(feature = "wasm")]
+pub mod indexeddb;
 pub mod memory;
+pub mod packed;
+pub mod sharded;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shared;
+mod tracker;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
@@ -0,0 +1,865 @@
+use crate::cache::{Cache, CacheStats, RemovalCause, RemovalTracker, StoreKey};
+use crate::compression::CompressionStats;
+use crate::error::CacheError;
+use crate::units::{ByteSize, HumanDuration, SetterError};
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+
+/// Magic bytes opening every record in a segment
+const RECORD_MAGIC: &[u8; 4] = b"ZCP\0";
+
+/// Current record format
+const RECORD_FORMAT_VERSION: u8 = 1;
+
+/// Fixed record header: magic, version, kind, key length, value length,
+/// value checksum, creation time
+const RECORD_HEADER_LEN: usize = 4 + 1 + 1 + 4 + 4 + 4 + 8;
+
+const RECORD_PUT: u8 = 0;
+const RECORD_TOMBSTONE: u8 = 1;
+
+/// Longest key accepted when replaying a segment
+const MAX_KEY_LEN: usize = 64 * 1024;
+
+/// Configuration for the packed disk cache
+///
+/// # Default Values
+/// - `cache_dir`: System temp directory + "zarrs_packed_cache"
+/// - `max_size_bytes`: None (unbounded)
+/// - `ttl`: None (no expiration)
+/// - `segment_size_bytes`: 64MB
+/// - `compaction_threshold`: 0.5 (rewrite segments that are half dead)
+#[derive(Debug, Clone)]
+pub struct PackedDiskCacheConfig {
+    /// Directory holding the segment files
+    pub cache_dir: PathBuf,
+    /// Maximum total size of live values in bytes
+    pub max_size_bytes: Option<u64>,
+    /// TTL for cache entries
+    pub ttl: Option<Duration>,
+    /// Size at which the active segment is sealed and a new one started
+    pub segment_size_bytes: u64,
+    /// Fraction of dead bytes at which a sealed segment is compacted
+    pub compaction_threshold: f64,
+}
+
+impl Default for PackedDiskCacheConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: std::env::temp_dir().join("zarrs_packed_cache"),
+            max_size_bytes: None,
+            ttl: None,
+            segment_size_bytes: 64 * 1024 * 1024, // 64MB
+            compaction_threshold: 0.5,
+        }
+    }
+}
+
+impl PackedDiskCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> PackedDiskCacheConfigBuilder {
+        PackedDiskCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.max_size_bytes == Some(0) {
+            return invalid("max_size_bytes must be greater than zero");
+        }
+        if self.segment_size_bytes == 0 {
+            return invalid("segment_size_bytes must be greater than zero");
+        }
+        if !(0.0..=1.0).contains(&self.compaction_threshold) {
+            return invalid("compaction_threshold must be between 0 and 1");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`PackedDiskCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](PackedDiskCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct PackedDiskCacheConfigBuilder {
+    config: PackedDiskCacheConfig,
+    invalid: SetterError,
+}
+
+impl PackedDiskCacheConfigBuilder {
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.cache_dir = dir.into();
+        self
+    }
+
+    pub fn max_size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.max_size_bytes = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn segment_size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.segment_size_bytes = bytes;
+        }
+        self
+    }
+
+    pub fn compaction_threshold(mut self, dead_fraction: f64) -> Self {
+        self.config.compaction_threshold = dead_fraction;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<PackedDiskCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Disk cache that appends entries into large segment files
+///
+/// Millions of small chunk files waste inodes and pay an open/close per
+/// access. Here every write is appended to the active segment and located
+/// through an in-memory offset index. Overwritten, removed and evicted
+/// entries leave dead space behind, which [`PackedDiskCache::compact`]
+/// reclaims by copying the live records of mostly-dead segments forward.
+///
+/// The index is rebuilt on startup by replaying the segments in order.
+/// Removals, evictions and discarded corrupt entries are recorded as
+/// tombstones, so no older version of a key reappears after a restart.
+pub struct PackedDiskCache {
+    config: PackedDiskCacheConfig,
+    state: Arc<RwLock<PackState>>,
+    stats: Arc<CacheStatsInner>,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    corrupted: AtomicU64,
+    removals: RemovalTracker,
+}
+
+#[derive(Clone)]
+struct PackedEntry {
+    segment: u32,
+    /// Offset of the value bytes within the segment
+    offset: u64,
+    len: usize,
+    checksum: u32,
+    created_at_ms: u64,
+    last_accessed: Instant,
+}
+
+impl PackedEntry {
+    /// Bytes the record occupies in its segment
+    fn record_len(&self, key: &str) -> u64 {
+        (RECORD_HEADER_LEN + key.len() + self.len) as u64
+    }
+}
+
+struct Segment {
+    file: Arc<File>,
+    total_bytes: u64,
+    dead_bytes: u64,
+}
+
+impl Segment {
+    fn dead_ratio(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            self.dead_bytes as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+struct PackState {
+    index: HashMap<StoreKey, PackedEntry>,
+    /// Segment holding the latest tombstone of each removed key
+    tombstones: HashMap<StoreKey, u32>,
+    segments: BTreeMap<u32, Segment>,
+    active: u32,
+    live_bytes: usize,
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn segment_path(dir: &Path, id: u32) -> PathBuf {
+    dir.join(format!("segment-{:08}.pack", id))
+}
+
+fn parse_segment_id(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_prefix("segment-")?
+        .strip_suffix(".pack")?
+        .parse()
+        .ok()
+}
+
+fn encode_record(kind: u8, key: &str, value: &[u8], created_at_ms: u64) -> Vec<u8> {
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + key.len() + value.len());
+    record.extend_from_slice(RECORD_MAGIC);
+    record.push(RECORD_FORMAT_VERSION);
+    record.push(kind);
+    record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    record.extend_from_slice(&crc32c::crc32c(value).to_le_bytes());
+    record.extend_from_slice(&created_at_ms.to_le_bytes());
+    record.extend_from_slice(key.as_bytes());
+    record.extend_from_slice(value);
+    record
+}
+
+struct RecordHeader {
+    kind: u8,
+    key_len: usize,
+    value_len: usize,
+    checksum: u32,
+    created_at_ms: u64,
+}
+
+fn decode_record_header(header: &[u8; RECORD_HEADER_LEN]) -> Option<RecordHeader> {
+    if !header.starts_with(RECORD_MAGIC) || header[4] != RECORD_FORMAT_VERSION {
+        return None;
+    }
+    let kind = header[5];
+    let key_len = u32::from_le_bytes(header[6..10].try_into().ok()?) as usize;
+    if kind > RECORD_TOMBSTONE || key_len > MAX_KEY_LEN {
+        return None;
+    }
+    Some(RecordHeader {
+        kind,
+        key_len,
+        value_len: u32::from_le_bytes(header[10..14].try_into().ok()?) as usize,
+        checksum: u32::from_le_bytes(header[14..18].try_into().ok()?),
+        created_at_ms: u64::from_le_bytes(header[18..26].try_into().ok()?),
+    })
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_at(mut file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::io::{Seek, SeekFrom};
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)
+}
+
+fn open_segment(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)
+}
+
+impl PackedDiskCache {
+    pub fn new(cache_dir: PathBuf, max_size_bytes: Option<u64>) -> Result<Self, CacheError> {
+        Self::with_config(PackedDiskCacheConfig {
+            cache_dir,
+            max_size_bytes,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: PackedDiskCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+        fs::create_dir_all(&config.cache_dir)?;
+        let state = Self::replay(&config.cache_dir)?;
+
+        Ok(Self {
+            config,
+            state: Arc::new(RwLock::new(state)),
+            stats: Arc::new(CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                corrupted: AtomicU64::new(0),
+                removals: RemovalTracker::default(),
+            }),
+        })
+    }
+
+    pub fn config(&self) -> &PackedDiskCacheConfig {
+        &self.config
+    }
+
+    /// Number of segment files currently on disk
+    pub fn segment_count(&self) -> usize {
+        futures::executor::block_on(self.state.read())
+            .segments
+            .len()
+    }
+
+    /// Number of entries discarded because their record was corrupt
+    pub fn corrupted_entries(&self) -> u64 {
+        self.stats.corrupted.load(Ordering::Relaxed)
+    }
+
+    /// Rebuild the index by replaying every segment in order
+    ///
+    /// A torn record at the end of a segment (crash mid-append) ends the replay
+    /// of that segment; the garbage is counted as dead space. Writes always go
+    /// to a fresh segment after startup.
+    fn replay(dir: &Path) -> Result<PackState, CacheError> {
+        let mut ids: Vec<u32> = fs::read_dir(dir)?
+            .flatten()
+            .filter_map(|entry| parse_segment_id(&entry.path()))
+            .collect();
+        ids.sort_unstable();
+
+        let mut index: HashMap<StoreKey, PackedEntry> = HashMap::new();
+        let mut tombstones = HashMap::new();
+        let mut segments = BTreeMap::new();
+        let now = Instant::now();
+
+        for &id in &ids {
+            let path = segment_path(dir, id);
+            let total_bytes = fs::metadata(&path)?.len();
+            if total_bytes == 0 {
+                // Active segment of a previous run that never got a write
+                fs::remove_file(&path)?;
+                continue;
+            }
+            let file = open_segment(&path)?;
+            let mut reader = std::io::BufReader::new(&file);
+            let mut offset = 0u64;
+            segments.insert(
+                id,
+                Segment {
+                    file: Arc::new(file.try_clone()?),
+                    total_bytes,
+                    dead_bytes: 0,
+                },
+            );
+
+            loop {
+                let mut header = [0u8; RECORD_HEADER_LEN];
+                if reader.read_exact(&mut header).is_err() {
+                    break;
+                }
+                let Some(record) = decode_record_header(&header) else {
+                    break;
+                };
+                let mut key = vec![0u8; record.key_len];
+                if reader.read_exact(&mut key).is_err() {
+                    break;
+                }
+                let Ok(key) = String::from_utf8(key) else {
+                    break;
+                };
+                // Values are verified lazily on read
+                if std::io::copy(
+                    &mut (&mut reader).take(record.value_len as u64),
+                    &mut std::io::sink(),
+                )? < record.value_len as u64
+                {
+                    break;
+                }
+
+                let record_len = (RECORD_HEADER_LEN + record.key_len + record.value_len) as u64;
+                let value_offset = offset + (RECORD_HEADER_LEN + record.key_len) as u64;
+                offset += record_len;
+
+                if let Some(old) = index.remove(&key) {
+                    let old_len = old.record_len(&key);
+                    if let Some(segment) = segments.get_mut(&old.segment) {
+                        segment.dead_bytes += old_len;
+                    }
+                }
+
+                let segment = segments.get_mut(&id).expect("segment registered above");
+                if record.kind == RECORD_TOMBSTONE {
+                    segment.dead_bytes += record_len;
+                    tombstones.insert(key, id);
+                    continue;
+                }
+                tombstones.remove(&key);
+
+                index.insert(
+                    key,
+                    PackedEntry {
+                        segment: id,
+                        offset: value_offset,
+                        len: record.value_len,
+                        checksum: record.checksum,
+                        created_at_ms: record.created_at_ms,
+                        last_accessed: now,
+                    },
+                );
+            }
+
+            // Anything past the last complete record is unusable
+            let segment = segments.get_mut(&id).expect("segment registered above");
+            segment.dead_bytes += total_bytes - offset;
+        }
+
+        let active = ids.last().map_or(0, |id| id + 1);
+        let file = open_segment(&segment_path(dir, active))?;
+        segments.insert(
+            active,
+            Segment {
+                file: Arc::new(file),
+                total_bytes: 0,
+                dead_bytes: 0,
+            },
+        );
+
+        let live_bytes = index.values().map(|entry| entry.len).sum();
+        tracing::info!(
+            "Replayed {} packed cache entries ({} bytes) from {} segments in {:?}",
+            index.len(),
+            live_bytes,
+            ids.len(),
+            dir
+        );
+
+        Ok(PackState {
+            index,
+            tombstones,
+            segments,
+            active,
+            live_bytes,
+        })
+    }
+
+    fn is_expired(&self, entry: &PackedEntry) -> bool {
+        match self.config.ttl {
+            Some(ttl) => now_unix_ms().saturating_sub(entry.created_at_ms) > ttl.as_millis() as u64,
+            None => false,
+        }
+    }
+
+    /// Start a new active segment, leaving the current one sealed
+    fn seal_active(&self, state: &mut PackState) -> Result<(), CacheError> {
+        let id = state.active + 1;
+        let file = open_segment(&segment_path(&self.config.cache_dir, id))?;
+        state.segments.insert(
+            id,
+            Segment {
+                file: Arc::new(file),
+                total_bytes: 0,
+                dead_bytes: 0,
+            },
+        );
+        state.active = id;
+        Ok(())
+    }
+
+    /// Append a record to the active segment, sealing it first if full
+    ///
+    /// A partially written record is cut off again so later offsets stay
+    /// right; if that fails too, the segment is sealed with the torn record
+    /// counted as dead space.
+    fn append(&self, state: &mut PackState, record: &[u8]) -> Result<(u32, u64), CacheError> {
+        let active = &state.segments[&state.active];
+        if active.total_bytes > 0
+            && active.total_bytes + record.len() as u64 > self.config.segment_size_bytes
+        {
+            self.seal_active(state)?;
+        }
+
+        let segment = state
+            .segments
+            .get_mut(&state.active)
+            .expect("active segment exists");
+        let offset = segment.total_bytes;
+        if let Err(e) = (&*segment.file).write_all(record) {
+            if segment.file.set_len(offset).is_err() {
+                let written = segment.file.metadata().map_or(offset, |m| m.len());
+                segment.dead_bytes += written.saturating_sub(offset);
+                segment.total_bytes = written.max(offset);
+                self.seal_active(state)?;
+            }
+            return Err(e.into());
+        }
+        segment.total_bytes += record.len() as u64;
+
+        Ok((state.active, offset))
+    }
+
+    /// Record that `key` no longer has a value, so replay skips its older
+    /// records
+    fn append_tombstone(&self, state: &mut PackState, key: &str) -> Result<(), CacheError> {
+        let tombstone = encode_record(RECORD_TOMBSTONE, key, &[], now_unix_ms());
+        let (segment, _) = self.append(state, &tombstone)?;
+        // A tombstone only matters until compaction drops the record it hides
+        if let Some(segment_info) = state.segments.get_mut(&segment) {
+            segment_info.dead_bytes += tombstone.len() as u64;
+        }
+        state.tombstones.insert(key.to_string(), segment);
+        Ok(())
+    }
+
+    /// Drop `key` from the index and log a tombstone for it
+    fn discard(&self, state: &mut PackState, key: &str) -> Result<Option<PackedEntry>, CacheError> {
+        let Some(entry) = Self::forget(state, key) else {
+            return Ok(None);
+        };
+        self.append_tombstone(state, key)?;
+        Ok(Some(entry))
+    }
+
+    /// Drop `key` from the index and count its record as dead space
+    fn forget(state: &mut PackState, key: &str) -> Option<PackedEntry> {
+        let entry = state.index.remove(key)?;
+        state.live_bytes -= entry.len;
+        if let Some(segment) = state.segments.get_mut(&entry.segment) {
+            segment.dead_bytes += entry.record_len(key);
+        }
+        Some(entry)
+    }
+
+    fn evict_if_needed(&self, state: &mut PackState, incoming: usize) -> Result<(), CacheError> {
+        let Some(max_size) = self.config.max_size_bytes else {
+            return Ok(());
+        };
+        let max_size = max_size as usize;
+        if incoming > max_size {
+            return Err(CacheError::EntryTooLarge {
+                size: incoming,
+                max: max_size,
+            });
+        }
+
+        while state.live_bytes + incoming > max_size {
+            let lru_key = state
+                .index
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_accessed)
+                .map(|(key, _)| key.clone());
+
+            match lru_key {
+                Some(key) => {
+                    self.discard(state, &key)?;
+                    self.stats.removals.record(RemovalCause::Evicted, 1);
+                }
+                None => return Err(CacheError::CacheFull),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite sealed segments whose dead space exceeds the compaction threshold
+    ///
+    /// Live records are verified against their checksums and appended to the
+    /// active segment, and the old segment file is deleted. Records that fail
+    /// verification are discarded rather than copied forward. Returns the
+    /// number of bytes reclaimed.
+    pub async fn compact(&self) -> Result<u64, CacheError> {
+        let mut state = self.state.write().await;
+        let candidates: Vec<u32> = state
+            .segments
+            .iter()
+            .filter(|(&id, segment)| {
+                id != state.active && segment.dead_ratio() >= self.config.compaction_threshold
+            })
+            .map(|(&id, _)| id)
+            .collect();
+
+        let mut reclaimed = 0;
+        for id in candidates {
+            let live: Vec<(StoreKey, PackedEntry)> = state
+                .index
+                .iter()
+                .filter(|(_, entry)| entry.segment == id)
+                .map(|(key, entry)| (key.clone(), entry.clone()))
+                .collect();
+            let file = state.segments[&id].file.clone();
+
+            for (key, entry) in live {
+                let mut value = vec![0u8; entry.len];
+                let verified = read_at(&file, &mut value, entry.offset)
+                    .map(|_| crc32c::crc32c(&value) == entry.checksum);
+                if !matches!(verified, Ok(true)) {
+                    tracing::warn!("Discarding corrupt packed cache entry {}", key);
+                    self.stats.corrupted.fetch_add(1, Ordering::Relaxed);
+                    self.discard(&mut state, &key)?;
+                    continue;
+                }
+                let record = encode_record(RECORD_PUT, &key, &value, entry.created_at_ms);
+                let (segment, offset) = self.append(&mut state, &record)?;
+                let moved = state.index.get_mut(&key).expect("live entry");
+                moved.segment = segment;
+                moved.offset = offset + (RECORD_HEADER_LEN + key.len()) as u64;
+            }
+
+            // Tombstones must outlive every older record they shadow
+            let older_segments_exist = state
+                .segments
+                .keys()
+                .next()
+                .is_some_and(|&first| first < id);
+            let tombstones: Vec<StoreKey> = state
+                .tombstones
+                .iter()
+                .filter(|(_, &segment)| segment == id)
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in tombstones {
+                if older_segments_exist {
+                    self.append_tombstone(&mut state, &key)?;
+                } else {
+                    state.tombstones.remove(&key);
+                }
+            }
+
+            if let Some(segment) = state.segments.remove(&id) {
+                reclaimed += segment.dead_bytes;
+            }
+            if let Err(e) = fs::remove_file(segment_path(&self.config.cache_dir, id)) {
+                tracing::warn!("Failed to remove compacted segment {}: {}", id, e);
+            }
+            tracing::debug!("Compacted packed cache segment {}", id);
+        }
+
+        Ok(reclaimed)
+    }
+
+    /// Spawn a background task that compacts segments every `interval`
+    ///
+    /// The task holds only a weak reference and exits once the cache is
+    /// dropped.
+    pub fn spawn_compactor(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
+
+            loop {
+                ticker.tick().await;
+                let Some(cache) = cache.upgrade() else {
+                    break;
+                };
+                match cache.compact().await {
+                    Ok(0) => {}
+                    Ok(reclaimed) => tracing::debug!("Compaction reclaimed {} bytes", reclaimed),
+                    Err(e) => tracing::warn!("Packed cache compaction failed: {:?}", e),
+                }
+            }
+        })
+    }
+
+    /// Serve `key`, dropping it if expired unless `stale`
+    async fn lookup(&self, key: &str, stale: bool) -> Option<Bytes> {
+        let (entry, file) = {
+            let mut state = self.state.write().await;
+            let Some(entry) = state.index.get_mut(key) else {
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            };
+            entry.last_accessed = Instant::now();
+            let entry = entry.clone();
+
+            if !stale && self.is_expired(&entry) {
+                Self::forget(&mut state, key);
+                self.stats.removals.record(RemovalCause::Expired, 1);
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                return None;
+            }
+
+            let file = state.segments[&entry.segment].file.clone();
+            (entry, file)
+        };
+
+        // Read outside the lock; the handle stays valid even if compaction
+        // deletes the segment meanwhile
+        let mut value = vec![0u8; entry.len];
+        let verified = read_at(&file, &mut value, entry.offset)
+            .map(|_| crc32c::crc32c(&value) == entry.checksum);
+
+        match verified {
+            Ok(true) => {
+                self.stats.hits.fetch_add(1, Ordering::Relaxed);
+                Some(Bytes::from(value))
+            }
+            Ok(false) | Err(_) => {
+                tracing::warn!("Discarding corrupt packed cache entry {}", key);
+                self.stats.corrupted.fetch_add(1, Ordering::Relaxed);
+                let mut state = self.state.write().await;
+                if state
+                    .index
+                    .get(key)
+                    .is_some_and(|current| current.segment == entry.segment)
+                {
+                    if let Err(e) = self.discard(&mut state, key) {
+                        tracing::warn!("Failed to log removal of {}: {:?}", key, e);
+                    }
+                }
+                self.stats.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for PackedDiskCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, false).await
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, true).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let mut state = self.state.write().await;
+
+        Self::forget(&mut state, key);
+        state.tombstones.remove(key);
+        self.evict_if_needed(&mut state, value.len())?;
+
+        let created_at_ms = now_unix_ms();
+        let record = encode_record(RECORD_PUT, key, &value, created_at_ms);
+        let (segment, offset) = self.append(&mut state, &record)?;
+
+        state.index.insert(
+            key.to_string(),
+            PackedEntry {
+                segment,
+                offset: offset + (RECORD_HEADER_LEN + key.len()) as u64,
+                len: value.len(),
+                checksum: crc32c::crc32c(&value),
+                created_at_ms,
+                last_accessed: Instant::now(),
+            },
+        );
+        state.live_bytes += value.len();
+
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let mut state = self.state.write().await;
+
+        if self.discard(&mut state, key)?.is_some() {
+            self.stats.removals.record(cause, 1);
+        }
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let mut state = self.state.write().await;
+
+        for id in state.segments.keys() {
+            if let Err(e) = fs::remove_file(segment_path(&self.config.cache_dir, *id)) {
+                tracing::warn!("Failed to remove segment {}: {}", id, e);
+            }
+        }
+
+        let active = state.active + 1;
+        let file = open_segment(&segment_path(&self.config.cache_dir, active))?;
+        state.index.clear();
+        state.tombstones.clear();
+        state.segments.clear();
+        state.segments.insert(
+            active,
+            Segment {
+                file: Arc::new(file),
+                total_bytes: 0,
+                dead_bytes: 0,
+            },
+        );
+        state.active = active;
+        state.live_bytes = 0;
+
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        if self.config.ttl.is_none() {
+            return Ok(0);
+        }
+
+        let mut state = self.state.write().await;
+        let expired: Vec<StoreKey> = state
+            .index
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in &expired {
+            Self::forget(&mut state, key);
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Expired, expired.len() as u64);
+
+        Ok(expired.len())
+    }
+
+    fn size(&self) -> usize {
+        futures::executor::block_on(self.state.read()).live_bytes
+    }
+
+    fn stats(&self) -> CacheStats {
+        let state = futures::executor::block_on(self.state.read());
+
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes: state.live_bytes,
+            entry_count: state.index.len(),
+            // Segment bytes on disk, including dead space awaiting compaction
+            accounted_bytes: state
+                .segments
+                .values()
+                .map(|segment| segment.total_bytes as usize)
+                .sum(),
+            removals: self.stats.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::cache::{stable_hash, Cache, CacheStats, MemoryPolicy, RemovalCause};
+use crate::error::CacheError;
+use bytes::{Bytes, BytesMut};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Next affinity slot handed to a thread that touches a sharded cache
+static NEXT_AFFINITY_SLOT: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static AFFINITY_SLOT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Stable per-thread slot, assigned round-robin on first use
+fn affinity_slot() -> usize {
+    AFFINITY_SLOT.with(|slot| match slot.get() {
+        Some(index) => index,
+        None => {
+            let index = NEXT_AFFINITY_SLOT.fetch_add(1, Ordering::Relaxed);
+            slot.set(Some(index));
+            index
+        }
+    })
+}
+
+/// Version stripes invalidating local copies; keys sharing a stripe
+/// invalidate each other's copies
+const VERSION_STRIPES: usize = 1024;
+
+/// Share of each shard's budget kept for copies of keys homed elsewhere
+const COPY_SHARE: usize = 4;
+
+/// Length of the version tag in front of every local copy
+const TAG_LEN: usize = 8;
+
+/// Memory cache split into per-worker affinity shards
+///
+/// Every key has one home shard, chosen by its hash, which holds the only
+/// authoritative copy: writes and removals go there alone, so concurrent
+/// writers of a key cannot leave diverging copies behind. Each worker
+/// thread also owns a shard of local copies, filled on reads of keys homed
+/// elsewhere, so hot lookups stay on locks and cache lines owned by one
+/// core. Copies carry the version of their key at the time they were read
+/// and are ignored once a write moves the version on, so readers never see
+/// stale data.
+///
+/// A lookup touches at most the local copy and the home shard; a write
+/// touches the home shard only. A quarter of each shard's budget goes to
+/// local copies. Works best when each worker touches a mostly disjoint set
+/// of chunks, as in tokio runtimes with one task per array region.
+pub struct ShardedMemoryCache {
+    homes: Vec<Arc<dyn Cache>>,
+    copies: Vec<Arc<dyn Cache>>,
+    /// Per-stripe write versions of the keys
+    versions: Box<[AtomicU64]>,
+    /// Bumped by bulk removals, which move every key's version on
+    epoch: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    cross_shard_hits: AtomicU64,
+}
+
+impl ShardedMemoryCache {
+    /// One shard per available CPU, splitting `max_size_bytes` evenly
+    pub fn per_core(max_size_bytes: usize, policy: MemoryPolicy) -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::new(cores, max_size_bytes, policy, None)
+    }
+
+    pub fn new(
+        shard_count: usize,
+        max_size_bytes: usize,
+        policy: MemoryPolicy,
+        ttl: Option<Duration>,
+    ) -> Self {
+        let shard_count = shard_count.max(1);
+        let (home_size, copy_size) = Self::split(max_size_bytes, shard_count);
+
+        Self {
+            homes: (0..shard_count)
+                .map(|_| policy.build(home_size, ttl))
+                .collect(),
+            copies: (0..shard_count)
+                .map(|_| policy.build(copy_size, ttl))
+                .collect(),
+            versions: (0..VERSION_STRIPES).map(|_| AtomicU64::new(0)).collect(),
+            epoch: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            cross_shard_hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Budget of each home shard and each shard of local copies
+    fn split(max_size_bytes: usize, shard_count: usize) -> (usize, usize) {
+        let shard_size = max_size_bytes / shard_count;
+        // With one shard every key is local and needs no copy
+        let copy_size = if shard_count > 1 {
+            shard_size / COPY_SHARE
+        } else {
+            0
+        };
+        (shard_size - copy_size, copy_size)
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.homes.len()
+    }
+
+    /// Hits served by the home shard of a key homed on another worker's
+    /// shard, rather than by the local shard
+    pub fn cross_shard_hits(&self) -> u64 {
+        self.cross_shard_hits.load(Ordering::Relaxed)
+    }
+
+    /// Index of the shard owned by the calling thread
+    pub fn local_shard(&self) -> usize {
+        affinity_slot() % self.homes.len()
+    }
+
+    /// Index of the shard holding `key`
+    pub fn home_shard(&self, key: &str) -> usize {
+        self.locate(key).0
+    }
+
+    /// Home shard and version stripe of `key`
+    fn locate(&self, key: &str) -> (usize, usize) {
+        let hash = stable_hash(key);
+        let home = (hash % self.homes.len() as u64) as usize;
+        let stripe = (hash >> 32) as usize % VERSION_STRIPES;
+        (home, stripe)
+    }
+
+    /// Current version of the keys in `stripe`, moved on by every write to
+    /// one of them and by every bulk removal
+    fn version(&self, stripe: usize) -> u64 {
+        // Both counters only grow, so their sum does too
+        self.epoch.load(Ordering::Acquire) + self.versions[stripe].load(Ordering::Acquire)
+    }
+
+    /// Copy of `key` in the local shard, if it was read at `version`
+    async fn local_copy(&self, local: usize, key: &str, version: u64) -> Option<Bytes> {
+        let tagged = self.copies[local].get(key).await?;
+        let tag = u64::from_le_bytes(tagged.get(..TAG_LEN)?.try_into().ok()?);
+        (tag == version).then(|| tagged.slice(TAG_LEN..))
+    }
+
+    /// Keep a copy of `value`, read at `version`, in the local shard
+    async fn store_copy(&self, local: usize, key: &str, version: u64, value: &Bytes) {
+        let mut tagged = BytesMut::with_capacity(TAG_LEN + value.len());
+        tagged.extend_from_slice(&version.to_le_bytes());
+        tagged.extend_from_slice(value);
+        // Best effort: a copy that does not fit is simply not kept
+        let _ = self.copies[local].set(key, tagged.freeze()).await;
+    }
+
+    fn all_shards(&self) -> impl Iterator<Item = &Arc<dyn Cache>> {
+        self.homes.iter().chain(&self.copies)
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for ShardedMemoryCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let (home, stripe) = self.locate(key);
+        let local = self.local_shard();
+        if home == local {
+            let value = self.homes[home].get(key).await;
+            let counter = if value.is_some() {
+                &self.hits
+            } else {
+                &self.misses
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+            return value;
+        }
+
+        let version = self.version(stripe);
+        if let Some(value) = self.local_copy(local, key, version).await {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(value);
+        }
+
+        let Some(value) = self.homes[home].get(key).await else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        self.cross_shard_hits.fetch_add(1, Ordering::Relaxed);
+        // A write since the version was read may have been missed
+        if self.version(stripe) == version {
+            self.store_copy(local, key, version, &value).await;
+        }
+        Some(value)
+    }
+
+    /// Read from the home shard, which holds the only authoritative copy
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        let (home, _) = self.locate(key);
+        let value = self.homes[home].get_stale(key).await;
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let (home, stripe) = self.locate(key);
+        let result = self.homes[home].set(key, value).await;
+        self.versions[stripe].fetch_add(1, Ordering::Release);
+        result
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let (home, stripe) = self.locate(key);
+        let result = self.homes[home].remove_with_cause(key, cause).await;
+        self.versions[stripe].fetch_add(1, Ordering::Release);
+        result
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let mut removed = 0;
+        for shard in &self.homes {
+            removed += shard.remove_prefix(prefix).await?;
+        }
+        self.epoch.fetch_add(1, Ordering::Release);
+        for shard in &self.copies {
+            shard.remove_prefix(prefix).await?;
+        }
+        Ok(removed)
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        for shard in &self.homes {
+            shard.clear().await?;
+        }
+        self.epoch.fetch_add(1, Ordering::Release);
+        for shard in &self.copies {
+            shard.clear().await?;
+        }
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        let mut purged = 0;
+        for shard in &self.homes {
+            purged += shard.purge_expired().await?;
+        }
+        for shard in &self.copies {
+            shard.purge_expired().await?;
+        }
+        Ok(purged)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.all_shards().map(|shard| shard.capacity()).sum()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        let (home_size, copy_size) = Self::split(max_size_bytes, self.homes.len());
+        for shard in &self.homes {
+            shard.resize(home_size).await?;
+        }
+        for shard in &self.copies {
+            shard.resize(copy_size).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        for shard in self.all_shards() {
+            shard.set_ttl(ttl).await?;
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.all_shards().map(|shard| shard.size()).sum()
+    }
+
+    fn stats(&self) -> CacheStats {
+        // Shard-level hit/miss counters include copy probes, so report
+        // lookups as seen by callers instead
+        let mut stats = CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+
+        // Copies take memory but are neither entries of their own nor
+        // removals of cached data
+        for shard in &self.homes {
+            let shard_stats = shard.stats();
+            stats.entry_count += shard_stats.entry_count;
+            stats.removals += shard_stats.removals;
+        }
+        for shard in self.all_shards() {
+            let shard_stats = shard.stats();
+            stats.size_bytes += shard_stats.size_bytes;
+            stats.accounted_bytes += shard_stats.accounted_bytes;
+        }
+
+        stats
+    }
+}
@@ -0,0 +1,830 @@
+use crate::cache::{stable_hash, Cache, CacheStats, RemovalCause, RemovalTracker};
+use crate::compression::CompressionStats;
+use crate::error::CacheError;
+use crate::units::{ByteSize, HumanDuration, SetterError};
+use bytes::Bytes;
+use memmap2::{MmapOptions, MmapRaw};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Marks a file as a shared cache segment
+const SEGMENT_MAGIC: u64 = u64::from_le_bytes(*b"ZCSHM\0\0\0");
+
+/// Current segment format
+const SEGMENT_VERSION: u64 = 1;
+
+/// Segment header: magic, version, slot count, data size, write cursor and
+/// TTL, padded to a cache line
+const HEADER_LEN: usize = 64;
+
+/// Offsets of the header words
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 8;
+const SLOT_COUNT_OFFSET: usize = 16;
+const DATA_SIZE_OFFSET: usize = 24;
+const CURSOR_OFFSET: usize = 32;
+const TTL_OFFSET: usize = 40;
+
+/// Stored in place of a TTL when there is none
+const NO_TTL: u64 = u64::MAX;
+
+/// Index slot: sequence, key hash, record position, key and value lengths,
+/// creation time
+const SLOT_LEN: usize = 5 * 8;
+
+/// Slots a key may occupy, starting at its home slot
+const PROBE_LEN: usize = 16;
+
+/// CRC32C of key and value closing every record
+const CHECKSUM_LEN: usize = 4;
+
+/// Times a reader spins on a slot being written before yielding
+const SPIN_LIMIT: usize = 1000;
+
+/// Longest a slot may stay busy before it is skipped, as its writer may
+/// have died; long enough for a preempted writer to be scheduled again
+const BUSY_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Attempts to claim an index slot for a write before giving up
+const PUBLISH_ATTEMPTS: usize = 8;
+
+/// Configuration for the shared-memory cache
+///
+/// # Default Values
+/// - `path`: "/dev/shm/zarrs-cache" on Linux, system temp directory +
+///   "zarrs-cache.shm" elsewhere
+/// - `size_bytes`: 256MB
+/// - `max_entries`: 65536
+/// - `ttl`: None (no expiration)
+#[derive(Debug, Clone)]
+pub struct SharedMemoryCacheConfig {
+    /// Segment file every process of the cache maps; put it on a RAM-backed
+    /// filesystem such as `/dev/shm`
+    pub path: PathBuf,
+    /// Bytes of keys and values the segment holds
+    pub size_bytes: u64,
+    /// Number of index slots, and so the most entries the segment holds
+    pub max_entries: usize,
+    /// TTL for cache entries
+    pub ttl: Option<Duration>,
+}
+
+impl Default for SharedMemoryCacheConfig {
+    fn default() -> Self {
+        let path = if cfg!(target_os = "linux") {
+            PathBuf::from("/dev/shm/zarrs-cache")
+        } else {
+            std::env::temp_dir().join("zarrs-cache.shm")
+        };
+        Self {
+            path,
+            size_bytes: 256 * 1024 * 1024, // 256MB
+            max_entries: 65536,
+            ttl: None,
+        }
+    }
+}
+
+impl SharedMemoryCacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> SharedMemoryCacheConfigBuilder {
+        SharedMemoryCacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.size_bytes == 0 {
+            return invalid("size_bytes must be greater than zero");
+        }
+        if self.max_entries == 0 {
+            return invalid("max_entries must be greater than zero");
+        }
+        if segment_len(self.max_entries, self.size_bytes).is_none() {
+            return invalid("size_bytes and max_entries must fit in the address space");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`SharedMemoryCacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](SharedMemoryCacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct SharedMemoryCacheConfigBuilder {
+    config: SharedMemoryCacheConfig,
+    invalid: SetterError,
+}
+
+impl SharedMemoryCacheConfigBuilder {
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.path = path.into();
+        self
+    }
+
+    pub fn size_bytes(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.size_bytes = bytes;
+        }
+        self
+    }
+
+    pub fn max_entries(mut self, entries: usize) -> Self {
+        self.config.max_entries = entries;
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<SharedMemoryCacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// In-memory cache shared by the processes of one node
+///
+/// Worker processes, such as one Python interpreter per core, that each keep
+/// their own memory cache hold the same chunks many times over. This cache
+/// lives in a segment file mapped into every process that opens it, so they
+/// share one copy; on a RAM-backed filesystem like `/dev/shm` the segment
+/// never touches disk. The first process to open the path creates the
+/// segment with its configuration; later ones attach to it and use its size,
+/// entry count and TTL, whatever their own configuration says.
+///
+/// Values are appended to a ring buffer and located through a hash index of
+/// `max_entries` slots. Neither takes a lock: writers reserve ring space
+/// with an atomic counter and publish entries through per-slot sequence
+/// numbers, and readers check a value's checksum and that the ring has not
+/// wrapped over it since. Once the ring is full, new values overwrite the
+/// oldest, so eviction is first-in first-out; an entry is also evicted when
+/// every slot its key may use is taken.
+///
+/// Hits, misses and removals are counted per process; sizes and entry
+/// counts describe the whole segment. A process that dies while publishing
+/// an entry leaves its slot unusable, and slow to skip, until the segment is
+/// recreated. The segment outlives the processes using it;
+/// [`SharedMemoryCache::unlink`] removes it.
+pub struct SharedMemoryCache {
+    config: SharedMemoryCacheConfig,
+    map: MmapRaw,
+    slot_count: usize,
+    data_size: u64,
+    stats: CacheStatsInner,
+}
+
+struct CacheStatsInner {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    corrupted: AtomicU64,
+    removals: RemovalTracker,
+}
+
+/// Consistent copy of an index slot
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    /// Key hash; zero for an empty slot
+    hash: u64,
+    /// Ring position of the record, counted from the segment's creation
+    pos: u64,
+    key_len: u32,
+    value_len: u32,
+    created_at_ms: u64,
+}
+
+impl Slot {
+    fn is_empty(&self) -> bool {
+        self.hash == 0
+    }
+
+    fn record_len(&self) -> u64 {
+        self.key_len as u64 + self.value_len as u64 + CHECKSUM_LEN as u64
+    }
+}
+
+/// Total length of a segment, if it fits in the address space
+fn segment_len(slot_count: usize, data_size: u64) -> Option<u64> {
+    let slots = slot_count.checked_mul(SLOT_LEN)?.checked_add(HEADER_LEN)?;
+    let len = (slots as u64).checked_add(data_size)?;
+    usize::try_from(len).ok()?;
+    Some(len)
+}
+
+/// Hash of `key`, the same in every process and never zero
+fn key_hash(key: &str) -> u64 {
+    stable_hash(key) | 1
+}
+
+fn encode_ttl(ttl: Option<Duration>) -> u64 {
+    ttl.map_or(NO_TTL, |ttl| (ttl.as_millis() as u64).min(NO_TTL - 1))
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The 8-byte word at `offset` of `map`
+fn word(map: &MmapRaw, offset: usize) -> &AtomicU64 {
+    debug_assert!(offset.is_multiple_of(8) && offset + 8 <= map.len());
+    // SAFETY: the mapping is page-aligned and outlives the reference, and
+    // callers only pass 8-byte aligned offsets inside it. Every process
+    // accesses these words atomically.
+    unsafe { &*(map.as_ptr().add(offset) as *const AtomicU64) }
+}
+
+impl SharedMemoryCache {
+    /// Open the segment at `path`, creating it with `size_bytes` of space if
+    /// there is none
+    pub fn new(path: PathBuf, size_bytes: u64) -> Result<Self, CacheError> {
+        Self::with_config(SharedMemoryCacheConfig {
+            path,
+            size_bytes,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: SharedMemoryCacheConfig) -> Result<Self, CacheError> {
+        config.validate()?;
+        let map = match OpenOptions::new().read(true).write(true).open(&config.path) {
+            Ok(file) => Self::attach(&config.path, &file)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::create(&config)?,
+            Err(e) => return Err(e.into()),
+        };
+
+        let slot_count = word(&map, SLOT_COUNT_OFFSET).load(Ordering::Relaxed) as usize;
+        let data_size = word(&map, DATA_SIZE_OFFSET).load(Ordering::Relaxed);
+        if slot_count != config.max_entries || data_size != config.size_bytes {
+            tracing::info!(
+                "Attached to shared cache {:?} of {} bytes and {} entries",
+                config.path,
+                data_size,
+                slot_count
+            );
+        }
+
+        Ok(Self {
+            config,
+            map,
+            slot_count,
+            data_size,
+            stats: CacheStatsInner {
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                corrupted: AtomicU64::new(0),
+                removals: RemovalTracker::default(),
+            },
+        })
+    }
+
+    /// Create and initialize a segment under a temporary name, then link it
+    /// into place so no process sees it half-initialized
+    ///
+    /// If another process links its segment first, that one is used.
+    fn create(config: &SharedMemoryCacheConfig) -> Result<MmapRaw, CacheError> {
+        if let Some(parent) = config.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temp_name = config.path.as_os_str().to_owned();
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp_path = PathBuf::from(temp_name);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)?;
+        let init = || -> Result<MmapRaw, CacheError> {
+            // Validated by the configuration
+            let len = segment_len(config.max_entries, config.size_bytes).unwrap_or(u64::MAX);
+            file.set_len(len)?;
+            let map = MmapOptions::new().map_raw(&file)?;
+            word(&map, VERSION_OFFSET).store(SEGMENT_VERSION, Ordering::Relaxed);
+            word(&map, SLOT_COUNT_OFFSET).store(config.max_entries as u64, Ordering::Relaxed);
+            word(&map, DATA_SIZE_OFFSET).store(config.size_bytes, Ordering::Relaxed);
+            word(&map, CURSOR_OFFSET).store(0, Ordering::Relaxed);
+            word(&map, TTL_OFFSET).store(encode_ttl(config.ttl), Ordering::Relaxed);
+            word(&map, MAGIC_OFFSET).store(SEGMENT_MAGIC, Ordering::Release);
+            Ok(map)
+        };
+        let linked = init().and_then(|map| {
+            fs::hard_link(&temp_path, &config.path)?;
+            Ok(map)
+        });
+        let _ = fs::remove_file(&temp_path);
+
+        match linked {
+            Ok(map) => Ok(map),
+            Err(CacheError::Io(e)) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(&config.path)?;
+                Self::attach(&config.path, &file)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Map the existing segment `file`, checking its header
+    fn attach(path: &Path, file: &File) -> Result<MmapRaw, CacheError> {
+        let not_a_segment =
+            || CacheError::Corruption(format!("{:?} is not a shared cache segment", path));
+        let len = file.metadata()?.len();
+        if len < HEADER_LEN as u64 {
+            return Err(not_a_segment());
+        }
+
+        let map = MmapOptions::new().map_raw(file)?;
+        if word(&map, MAGIC_OFFSET).load(Ordering::Acquire) != SEGMENT_MAGIC {
+            return Err(not_a_segment());
+        }
+        let version = word(&map, VERSION_OFFSET).load(Ordering::Relaxed);
+        if version != SEGMENT_VERSION {
+            return Err(CacheError::Corruption(format!(
+                "shared cache segment {:?} has unsupported version {}",
+                path, version
+            )));
+        }
+        let slot_count = word(&map, SLOT_COUNT_OFFSET).load(Ordering::Relaxed);
+        let data_size = word(&map, DATA_SIZE_OFFSET).load(Ordering::Relaxed);
+        let expected = usize::try_from(slot_count)
+            .ok()
+            .and_then(|slot_count| segment_len(slot_count, data_size));
+        if slot_count == 0 || data_size == 0 || expected != Some(len) {
+            return Err(not_a_segment());
+        }
+        Ok(map)
+    }
+
+    pub fn config(&self) -> &SharedMemoryCacheConfig {
+        &self.config
+    }
+
+    /// Most entries the segment holds
+    pub fn max_entries(&self) -> usize {
+        self.slot_count
+    }
+
+    /// Number of entries discarded because their value failed its checksum
+    pub fn corrupted_entries(&self) -> u64 {
+        self.stats.corrupted.load(Ordering::Relaxed)
+    }
+
+    /// Remove the segment file
+    ///
+    /// Processes with the cache open keep using the segment, and its memory
+    /// is freed once they all close it. Opening the path afterwards creates
+    /// a new, empty segment.
+    pub fn unlink(&self) -> Result<(), CacheError> {
+        fs::remove_file(&self.config.path)?;
+        Ok(())
+    }
+
+    fn cursor(&self) -> &AtomicU64 {
+        word(&self.map, CURSOR_OFFSET)
+    }
+
+    /// TTL of the segment's entries
+    fn ttl(&self) -> Option<Duration> {
+        match word(&self.map, TTL_OFFSET).load(Ordering::Relaxed) {
+            NO_TTL => None,
+            millis => Some(Duration::from_millis(millis)),
+        }
+    }
+
+    /// Word `field` of index slot `index`
+    fn slot_word(&self, index: usize, field: usize) -> &AtomicU64 {
+        word(&self.map, HEADER_LEN + index * SLOT_LEN + field * 8)
+    }
+
+    /// Sequence number and contents of slot `index`, or `None` if it stays
+    /// busy being written
+    fn load_slot(&self, index: usize) -> Option<(u64, Slot)> {
+        let seq_word = self.slot_word(index, 0);
+        let mut spins = 0;
+        let mut busy_since = None;
+        loop {
+            let seq = seq_word.load(Ordering::Acquire);
+            if seq % 2 == 1 {
+                if spins < SPIN_LIMIT {
+                    spins += 1;
+                    std::hint::spin_loop();
+                } else if busy_since.get_or_insert_with(Instant::now).elapsed() < BUSY_TIMEOUT {
+                    std::thread::yield_now();
+                } else {
+                    return None;
+                }
+                continue;
+            }
+
+            let lens = self.slot_word(index, 3).load(Ordering::Relaxed);
+            let slot = Slot {
+                hash: self.slot_word(index, 1).load(Ordering::Relaxed),
+                pos: self.slot_word(index, 2).load(Ordering::Relaxed),
+                key_len: (lens >> 32) as u32,
+                value_len: lens as u32,
+                created_at_ms: self.slot_word(index, 4).load(Ordering::Relaxed),
+            };
+            fence(Ordering::Acquire);
+            if seq_word.load(Ordering::Relaxed) == seq {
+                return Some((seq, slot));
+            }
+        }
+    }
+
+    /// Replace slot `index` with `slot` if it is still at sequence `seq`,
+    /// returning whether it was
+    fn store_slot(&self, index: usize, seq: u64, slot: &Slot) -> bool {
+        let seq_word = self.slot_word(index, 0);
+        if seq_word
+            .compare_exchange(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        fence(Ordering::Release);
+        self.slot_word(index, 1).store(slot.hash, Ordering::Relaxed);
+        self.slot_word(index, 2).store(slot.pos, Ordering::Relaxed);
+        self.slot_word(index, 3).store(
+            ((slot.key_len as u64) << 32) | slot.value_len as u64,
+            Ordering::Relaxed,
+        );
+        self.slot_word(index, 4)
+            .store(slot.created_at_ms, Ordering::Relaxed);
+        seq_word.store(seq + 2, Ordering::Release);
+        true
+    }
+
+    /// Indices of the slots `hash` may occupy
+    fn probe(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let home = (hash % self.slot_count as u64) as usize;
+        let slot_count = self.slot_count;
+        (0..PROBE_LEN.min(slot_count)).map(move |i| (home + i) % slot_count)
+    }
+
+    /// Whether the ring has not wrapped over the record of `slot`
+    fn is_intact(&self, slot: &Slot) -> bool {
+        self.cursor().load(Ordering::Acquire) <= slot.pos + self.data_size
+    }
+
+    /// Whether `slot` holds an entry that is neither overwritten nor expired
+    fn is_live(&self, slot: &Slot) -> bool {
+        !slot.is_empty() && self.is_intact(slot) && !self.is_expired(slot)
+    }
+
+    fn is_expired(&self, slot: &Slot) -> bool {
+        match self.ttl() {
+            Some(ttl) => now_unix_ms().saturating_sub(slot.created_at_ms) > ttl.as_millis() as u64,
+            None => false,
+        }
+    }
+
+    fn data(&self) -> *mut u8 {
+        // SAFETY: the data ring starts inside the mapping, after the index
+        unsafe {
+            self.map
+                .as_mut_ptr()
+                .add(HEADER_LEN + self.slot_count * SLOT_LEN)
+        }
+    }
+
+    /// Copy `bytes` into the ring at position `pos`, wrapping at its end
+    fn write_ring(&self, pos: u64, bytes: &[u8]) {
+        let start = (pos % self.data_size) as usize;
+        let first = bytes.len().min(self.data_size as usize - start);
+        // SAFETY: both copies stay inside the ring, and the range was
+        // reserved for this writer by advancing the cursor past it. Readers
+        // of records the ring wraps over detect it and discard their copy.
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), self.data().add(start), first);
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr().add(first),
+                self.data(),
+                bytes.len() - first,
+            );
+        }
+    }
+
+    /// Copy `buf.len()` bytes out of the ring from position `pos`
+    ///
+    /// The copy is only valid if the record is still intact afterwards.
+    fn read_ring(&self, pos: u64, buf: &mut [u8]) {
+        let start = (pos % self.data_size) as usize;
+        let first = buf.len().min(self.data_size as usize - start);
+        // SAFETY: both copies stay inside the ring; see `write_ring` for
+        // concurrent writers
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.data().add(start), buf.as_mut_ptr(), first);
+            std::ptr::copy_nonoverlapping(
+                self.data(),
+                buf.as_mut_ptr().add(first),
+                buf.len() - first,
+            );
+        }
+    }
+
+    /// Key of the record of `slot`, if it is still intact
+    fn read_key(&self, slot: &Slot) -> Option<String> {
+        let mut key = vec![0u8; slot.key_len as usize];
+        self.read_ring(slot.pos, &mut key);
+        if !self.is_intact(slot) {
+            return None;
+        }
+        String::from_utf8(key).ok()
+    }
+
+    /// Whether `slot` holds an intact record of `key`
+    fn holds_key(&self, slot: &Slot, key: &str, hash: u64) -> bool {
+        slot.hash == hash
+            && slot.key_len as usize == key.len()
+            && self.read_key(slot).as_deref() == Some(key)
+    }
+
+    /// Value of the record of `slot` if it is intact and holds `key`
+    fn read_value(&self, slot: &Slot, key: &str) -> Option<Bytes> {
+        let key_len = slot.key_len as usize;
+        let value_end = key_len + slot.value_len as usize;
+        let mut record = vec![0u8; slot.record_len() as usize];
+        self.read_ring(slot.pos, &mut record);
+        if !self.is_intact(slot) {
+            return None;
+        }
+
+        let checksum = u32::from_le_bytes(
+            record[value_end..]
+                .try_into()
+                .expect("record ends in a checksum"),
+        );
+        if crc32c::crc32c(&record[..value_end]) != checksum {
+            self.stats.corrupted.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!("Corrupt record in shared cache {:?}", self.config.path);
+            return None;
+        }
+        if &record[..key_len] != key.as_bytes() {
+            return None;
+        }
+        Some(Bytes::from(record).slice(key_len..value_end))
+    }
+
+    /// Empty slot `index` if it is still at sequence `seq`
+    fn clear_slot(&self, index: usize, seq: u64) -> bool {
+        self.store_slot(index, seq, &Slot::default())
+    }
+
+    /// Point an index slot for `key` at `slot`, preferring the slot already
+    /// holding the key, then a free one, then evicting the oldest entry
+    ///
+    /// Of concurrent writes of a key, the one that reserved its ring space
+    /// last is kept.
+    fn publish(&self, key: &str, slot: &Slot) -> Result<(), CacheError> {
+        for _ in 0..PUBLISH_ATTEMPTS {
+            let mut same_key = Vec::new();
+            let mut free = None;
+            let mut oldest: Option<(usize, u64, Slot)> = None;
+            for index in self.probe(slot.hash) {
+                let Some((seq, current)) = self.load_slot(index) else {
+                    continue;
+                };
+                if current.is_empty() || !self.is_intact(&current) {
+                    free.get_or_insert((index, seq, current));
+                } else if self.holds_key(&current, key, slot.hash) {
+                    same_key.push((index, seq, current.pos));
+                } else if oldest.is_none_or(|(_, _, old)| current.pos < old.pos) {
+                    oldest = Some((index, seq, current));
+                }
+            }
+
+            // A later write of the key was published first and wins
+            if same_key.iter().any(|&(_, _, pos)| pos > slot.pos) {
+                return Ok(());
+            }
+
+            let (index, seq, cause) = if let Some(&(index, seq, _)) = same_key.first() {
+                (index, seq, None)
+            } else if let Some((index, seq, current)) = free {
+                let expired = !current.is_empty() && self.is_expired(&current);
+                (index, seq, expired.then_some(RemovalCause::Expired))
+            } else if let Some((index, seq, current)) = oldest {
+                let cause = if self.is_expired(&current) {
+                    RemovalCause::Expired
+                } else {
+                    RemovalCause::Evicted
+                };
+                (index, seq, Some(cause))
+            } else {
+                continue;
+            };
+
+            if !self.store_slot(index, seq, slot) {
+                continue;
+            }
+            if let Some(cause) = cause {
+                self.stats.removals.record(cause, 1);
+            }
+            // Drop older copies written concurrently into other slots
+            for &(other, seq, _) in same_key.iter().skip(1) {
+                self.clear_slot(other, seq);
+            }
+            return Ok(());
+        }
+        Err(CacheError::CacheFull)
+    }
+
+    /// Empty every slot whose entry matches `remove`, returning how many
+    /// live entries were removed
+    fn clear_slots(
+        &self,
+        indices: impl Iterator<Item = usize>,
+        remove: impl Fn(&Slot) -> bool,
+    ) -> usize {
+        let mut removed = 0;
+        for index in indices {
+            while let Some((seq, slot)) = self.load_slot(index) {
+                if slot.is_empty() || !remove(&slot) {
+                    break;
+                }
+                if self.clear_slot(index, seq) {
+                    if self.is_live(&slot) {
+                        removed += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        removed
+    }
+
+    /// Entry count and value bytes of the live entries
+    fn occupancy(&self) -> (usize, usize) {
+        (0..self.slot_count)
+            .filter_map(|index| self.load_slot(index))
+            .filter(|(_, slot)| self.is_live(slot))
+            .fold((0, 0), |(entries, bytes), (_, slot)| {
+                (entries + 1, bytes + slot.value_len as usize)
+            })
+    }
+
+    /// Newest intact value of `key`, skipping expired ones unless `stale`
+    fn lookup(&self, key: &str, stale: bool) -> Option<Bytes> {
+        let hash = key_hash(key);
+        let mut candidates: Vec<Slot> = self
+            .probe(hash)
+            .filter_map(|index| self.load_slot(index))
+            .map(|(_, slot)| slot)
+            .filter(|slot| slot.hash == hash && slot.key_len as usize == key.len())
+            .collect();
+        // Concurrent writes of a key may briefly leave several copies; the
+        // newest wins
+        candidates.sort_unstable_by_key(|slot| std::cmp::Reverse(slot.pos));
+
+        let value = candidates
+            .iter()
+            .filter(|slot| stale || !self.is_expired(slot))
+            .find_map(|slot| self.read_value(slot, key));
+        match value {
+            Some(_) => self.stats.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.stats.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        value
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for SharedMemoryCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, false)
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        self.lookup(key, true)
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let record_len = key.len() + value.len() + CHECKSUM_LEN;
+        let max = self.data_size.min(u32::MAX as u64) as usize;
+        if record_len > max {
+            return Err(CacheError::EntryTooLarge {
+                size: record_len,
+                max,
+            });
+        }
+
+        let pos = self.cursor().fetch_add(record_len as u64, Ordering::AcqRel);
+        let checksum = crc32c::crc32c_append(crc32c::crc32c(key.as_bytes()), &value);
+        self.write_ring(pos, key.as_bytes());
+        self.write_ring(pos + key.len() as u64, &value);
+        self.write_ring(
+            pos + (key.len() + value.len()) as u64,
+            &checksum.to_le_bytes(),
+        );
+
+        let slot = Slot {
+            hash: key_hash(key),
+            pos,
+            key_len: key.len() as u32,
+            value_len: value.len() as u32,
+            created_at_ms: now_unix_ms(),
+        };
+        self.publish(key, &slot)
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let hash = key_hash(key);
+        let removed = self.clear_slots(self.probe(hash), |slot| {
+            // Records the ring wrapped over can no longer be told apart
+            slot.hash == hash && (!self.is_intact(slot) || self.holds_key(slot, key, hash))
+        });
+        self.stats.removals.record(cause, removed as u64);
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        let removed = self.clear_slots(0..self.slot_count, |slot| {
+            self.read_key(slot)
+                .is_some_and(|key| key.starts_with(prefix))
+        });
+        self.stats
+            .removals
+            .record(RemovalCause::Removed, removed as u64);
+        Ok(removed)
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        let removed = self.clear_slots(0..self.slot_count, |_| true);
+        self.stats
+            .removals
+            .record(RemovalCause::Removed, removed as u64);
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        if self.ttl().is_none() {
+            return Ok(0);
+        }
+        let mut purged = 0;
+        for index in 0..self.slot_count {
+            let Some((seq, slot)) = self.load_slot(index) else {
+                continue;
+            };
+            if !slot.is_empty()
+                && self.is_intact(&slot)
+                && self.is_expired(&slot)
+                && self.clear_slot(index, seq)
+            {
+                purged += 1;
+            }
+        }
+        self.stats
+            .removals
+            .record(RemovalCause::Expired, purged as u64);
+        Ok(purged)
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.data_size as usize)
+    }
+
+    /// Applies to every process using the segment
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        word(&self.map, TTL_OFFSET).store(encode_ttl(ttl), Ordering::Relaxed);
+        self.purge_expired().await?;
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.occupancy().1
+    }
+
+    fn stats(&self) -> CacheStats {
+        let (entry_count, size_bytes) = self.occupancy();
+        CacheStats {
+            hits: self.stats.hits.load(Ordering::Relaxed),
+            misses: self.stats.misses.load(Ordering::Relaxed),
+            size_bytes,
+            entry_count,
+            // Ring bytes in use, including overwritten and removed records
+            accounted_bytes: self.cursor().load(Ordering::Relaxed).min(self.data_size) as usize,
+            removals: self.stats.removals.counts(),
+            compression: CompressionStats::default(),
+        }
+    }
+}
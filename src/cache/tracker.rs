@@ -0,0 +1,137 @@
+//! Bounded, sharded per-key access statistics for the hybrid cache
+
+use lru::LruCache;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, MutexGuard};
+
+/// Number of independently locked shards
+const SHARD_COUNT: usize = 16;
+
+/// Per-key state for at most a fixed number of keys
+///
+/// Keys are spread over independently locked shards, so accesses to
+/// different keys rarely contend. Each shard drops its least recently
+/// accessed key once full, so memory use stays constant however many
+/// distinct keys are seen. Locks are held for a single call only, never
+/// across an await.
+pub(crate) struct AccessTracker<V> {
+    shards: Vec<Mutex<LruCache<String, V>>>,
+}
+
+impl<V: Clone> AccessTracker<V> {
+    /// Tracker holding about `capacity` keys
+    pub(crate) fn new(capacity: usize) -> Self {
+        let per_shard =
+            NonZeroUsize::new(capacity.div_ceil(SHARD_COUNT)).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            shards: (0..SHARD_COUNT)
+                .map(|_| Mutex::new(LruCache::new(per_shard)))
+                .collect(),
+        }
+    }
+
+    fn shard(&self, key: &str) -> MutexGuard<'_, LruCache<String, V>> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = hasher.finish() as usize % self.shards.len();
+        self.shards[index].lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record an access to `key`, applying `update` to its state or starting
+    /// it with `new`
+    ///
+    /// Returns the entry pushed out to make room, if any.
+    pub(crate) fn access(
+        &self,
+        key: &str,
+        new: impl FnOnce() -> V,
+        update: impl FnOnce(&mut V),
+    ) -> Option<(String, V)> {
+        let mut shard = self.shard(key);
+        if let Some(value) = shard.get_mut(key) {
+            update(value);
+            return None;
+        }
+        shard.push(key.to_string(), new())
+    }
+
+    /// Start tracking `key` with `value`, replacing any state it had
+    ///
+    /// Returns the entry pushed out to make room, if any.
+    pub(crate) fn insert(&self, key: &str, value: V) -> Option<(String, V)> {
+        let mut shard = self.shard(key);
+        shard
+            .push(key.to_string(), value)
+            .filter(|(evicted, _)| evicted != key)
+    }
+
+    /// Read the state of `key`, if it is tracked
+    pub(crate) fn read<R>(&self, key: &str, read: impl FnOnce(&V) -> R) -> Option<R> {
+        self.shard(key).peek(key).map(read)
+    }
+
+    /// Change the state of `key`, if it is tracked, without counting an access
+    pub(crate) fn update<R>(&self, key: &str, update: impl FnOnce(&mut V) -> R) -> Option<R> {
+        self.shard(key).peek_mut(key).map(update)
+    }
+
+    pub(crate) fn remove(&self, key: &str) -> Option<V> {
+        self.shard(key).pop(key)
+    }
+
+    /// Forget the keys starting with `prefix`
+    pub(crate) fn remove_prefix(&self, prefix: &str) {
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            let keys: Vec<String> = shard
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in keys {
+                shard.pop(&key);
+            }
+        }
+    }
+
+    /// Stop tracking every entry `stale` returns `true` for, returning them
+    pub(crate) fn remove_where(&self, mut stale: impl FnMut(&str, &V) -> bool) -> Vec<(String, V)> {
+        let mut removed = Vec::new();
+        for shard in &self.shards {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            let keys: Vec<String> = shard
+                .iter()
+                .filter(|(key, value)| stale(key, value))
+                .map(|(key, _)| key.clone())
+                .collect();
+            for key in keys {
+                if let Some(value) = shard.pop(&key) {
+                    removed.push((key, value));
+                }
+            }
+        }
+        removed
+    }
+
+    /// Copy of every tracked entry, locking one shard at a time
+    pub(crate) fn snapshot(&self) -> Vec<(String, V)> {
+        let mut entries = Vec::new();
+        for shard in &self.shards {
+            let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            entries.extend(
+                shard
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone())),
+            );
+        }
+        entries
+    }
+
+    pub(crate) fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+}
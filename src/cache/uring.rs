@@ -0,0 +1,179 @@
+//! Batched file I/O over io_uring for the disk cache
+
+use io_uring::{opcode, types, IoUring};
+use std::io;
+use std::os::fd::RawFd;
+use std::sync::Mutex;
+
+/// One queued operation against an open file
+pub(crate) enum UringOp {
+    /// Write all of `buf` at `offset`
+    Write {
+        fd: RawFd,
+        buf: *const u8,
+        len: usize,
+        offset: u64,
+    },
+    /// Fill all of `buf` from `offset`
+    Read {
+        fd: RawFd,
+        buf: *mut u8,
+        len: usize,
+        offset: u64,
+    },
+    /// Flush the file to stable storage
+    Fsync { fd: RawFd },
+}
+
+// SAFETY: the pointers are only dereferenced by the kernel while `run` blocks,
+// and callers keep the buffers alive and unaliased for that call
+unsafe impl Send for UringOp {}
+
+impl UringOp {
+    fn len(&self) -> usize {
+        match self {
+            UringOp::Write { len, .. } | UringOp::Read { len, .. } => *len,
+            UringOp::Fsync { .. } => 0,
+        }
+    }
+
+    /// Submission entry for the part of the operation after `done` bytes
+    fn entry(&self, done: usize, user_data: u64) -> io_uring::squeue::Entry {
+        // A single read or write moves at most this much; longer buffers
+        // complete over several submissions
+        const MAX_CHUNK: usize = 1 << 30;
+
+        match *self {
+            UringOp::Write {
+                fd,
+                buf,
+                len,
+                offset,
+            } => {
+                let chunk = (len - done).min(MAX_CHUNK) as u32;
+                // SAFETY: `done < len`, so the pointer stays inside the buffer
+                opcode::Write::new(types::Fd(fd), unsafe { buf.add(done) }, chunk)
+                    .offset(offset + done as u64)
+                    .build()
+            }
+            UringOp::Read {
+                fd,
+                buf,
+                len,
+                offset,
+            } => {
+                let chunk = (len - done).min(MAX_CHUNK) as u32;
+                // SAFETY: as above
+                opcode::Read::new(types::Fd(fd), unsafe { buf.add(done) }, chunk)
+                    .offset(offset + done as u64)
+                    .build()
+            }
+            UringOp::Fsync { fd } => opcode::Fsync::new(types::Fd(fd)).build(),
+        }
+        .user_data(user_data)
+    }
+}
+
+/// A shared submission ring driving batches of blocking file operations
+pub(crate) struct UringIo {
+    ring: Mutex<IoUring>,
+    queue_depth: usize,
+}
+
+impl UringIo {
+    pub(crate) fn new(queue_depth: u32) -> io::Result<Self> {
+        let queue_depth = queue_depth.max(1);
+        Ok(Self {
+            ring: Mutex::new(IoUring::new(queue_depth)?),
+            queue_depth: queue_depth as usize,
+        })
+    }
+
+    /// Run every operation to completion, returning one result per operation
+    ///
+    /// Up to the queue depth of operations are in flight at once; short
+    /// reads and writes are resubmitted for the remainder. A read hitting end
+    /// of file fails with `UnexpectedEof`.
+    ///
+    /// # Safety
+    ///
+    /// Every buffer referenced by `ops` must stay valid, and read buffers
+    /// unaliased, until this returns.
+    pub(crate) unsafe fn run(&self, ops: &[UringOp]) -> Vec<io::Result<()>> {
+        let mut results: Vec<Option<io::Result<()>>> = ops.iter().map(|_| None).collect();
+        let mut done = vec![0usize; ops.len()];
+        let mut pending: Vec<usize> = (0..ops.len()).rev().collect();
+        let mut in_flight = 0;
+
+        let mut ring = self.ring.lock().unwrap_or_else(|e| e.into_inner());
+
+        while !pending.is_empty() || in_flight > 0 {
+            {
+                let mut submission = ring.submission();
+                while in_flight < self.queue_depth {
+                    let Some(index) = pending.pop() else {
+                        break;
+                    };
+                    let entry = ops[index].entry(done[index], index as u64);
+                    // SAFETY: guaranteed by the caller
+                    if unsafe { submission.push(&entry) }.is_err() {
+                        pending.push(index);
+                        break;
+                    }
+                    in_flight += 1;
+                }
+            }
+
+            if let Err(e) = ring.submit_and_wait(1) {
+                match e.kind() {
+                    // Completions may be waiting to be reaped; retry after that
+                    io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::ResourceBusy => {}
+                    _ => {
+                        // Submitted operations may still write into caller
+                        // buffers, so neither returning nor unwinding is sound
+                        tracing::error!("io_uring submission failed: {}", e);
+                        std::process::abort();
+                    }
+                }
+            }
+
+            let completed: Vec<(usize, i32)> = ring
+                .completion()
+                .map(|cqe| (cqe.user_data() as usize, cqe.result()))
+                .collect();
+            for (index, result) in completed {
+                in_flight -= 1;
+                let op = &ops[index];
+                if result < 0 {
+                    let error = io::Error::from_raw_os_error(-result);
+                    if error.kind() == io::ErrorKind::Interrupted {
+                        pending.push(index);
+                    } else {
+                        results[index] = Some(Err(error));
+                    }
+                    continue;
+                }
+
+                done[index] += result as usize;
+                if done[index] >= op.len() {
+                    results[index] = Some(Ok(()));
+                } else if result == 0 {
+                    let kind = match op {
+                        UringOp::Read { .. } => io::ErrorKind::UnexpectedEof,
+                        _ => io::ErrorKind::WriteZero,
+                    };
+                    results[index] = Some(Err(kind.into()));
+                } else {
+                    pending.push(index);
+                }
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every operation completes"))
+            .collect()
+    }
+}
@@ -0,0 +1,1065 @@
+//! Compression of cached values
+
+use crate::cache::{Cache, CacheStats, RemovalCause};
+use crate::error::CacheError;
+use bytes::Bytes;
+use flate2::read::{DeflateDecoder, DeflateEncoder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// First bytes of every entry written by [`CompressedCache`]
+const ENTRY_MAGIC: [u8; 2] = *b"zc";
+
+/// Magic, algorithm id and original length as a little-endian u64
+const ENTRY_HEADER_LEN: usize = 11;
+
+/// Algorithm ids of the entry header
+const ID_UNCOMPRESSED: u8 = 0;
+const ID_DEFLATE: u8 = 1;
+const ID_ZSTD: u8 = 2;
+const ID_SNAPPY: u8 = 3;
+/// A codec other than the built-in ones, only readable by the same codec
+const ID_CUSTOM: u8 = 0xff;
+
+/// Leading bytes trial-compressed before a larger value is compressed whole
+const COMPRESSION_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Default size from which values are compressed and decompressed on the
+/// blocking thread pool
+pub(crate) const DEFAULT_OFFLOAD_BYTES: usize = 256 * 1024;
+
+/// Codec applied to values before they are stored
+pub trait Compression: Send + Sync + 'static {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError>;
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError>;
+
+    /// Compress `data` into `writer`, producing the same format as
+    /// [`compress`](Compression::compress)
+    ///
+    /// Codecs able to emit output piecewise override this so the compressed
+    /// value is never held whole; the default compresses into a buffer first.
+    fn compress_to(&self, data: &[u8], writer: &mut dyn Write) -> Result<(), CacheError> {
+        writer.write_all(&self.compress(data)?)?;
+        Ok(())
+    }
+
+    /// Built-in algorithm this codec implements, recorded with each entry so
+    /// entries stay readable after switching to another built-in codec
+    ///
+    /// Custom codecs keep the default of `None`, and their entries are only
+    /// read back by a cache using the same codec.
+    fn algorithm(&self) -> Option<CompressionAlgorithm> {
+        None
+    }
+}
+
+/// Built-in compression, selectable by configuration
+///
+/// # Default Value
+/// - `Deflate` at level 6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgorithm {
+    /// [`DeflateCompression`] at `level` 0 to 9
+    Deflate { level: u32 },
+    /// [`ZstdCompression`] at `level` -7 to 22
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+    /// [`SnappyCompression`]
+    #[cfg(feature = "snappy")]
+    Snappy,
+}
+
+impl Default for CompressionAlgorithm {
+    fn default() -> Self {
+        CompressionAlgorithm::Deflate { level: 6 }
+    }
+}
+
+impl CompressionAlgorithm {
+    /// Build the codec for this algorithm
+    pub fn build(self) -> Box<dyn Compression> {
+        match self {
+            CompressionAlgorithm::Deflate { level } => Box::new(DeflateCompression::new(level)),
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd { level } => Box::new(ZstdCompression::new(level)),
+            #[cfg(feature = "snappy")]
+            CompressionAlgorithm::Snappy => Box::new(SnappyCompression),
+        }
+    }
+
+    /// Id recorded in the header of entries compressed with this algorithm
+    fn id(self) -> u8 {
+        match self {
+            CompressionAlgorithm::Deflate { .. } => ID_DEFLATE,
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd { .. } => ID_ZSTD,
+            #[cfg(feature = "snappy")]
+            CompressionAlgorithm::Snappy => ID_SNAPPY,
+        }
+    }
+
+    /// Algorithm able to decompress entries with header id `id`, if it is
+    /// built in and its feature is enabled
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            ID_DEFLATE => Some(CompressionAlgorithm::default()),
+            #[cfg(feature = "zstd")]
+            ID_ZSTD => Some(CompressionAlgorithm::Zstd { level: 3 }),
+            #[cfg(feature = "snappy")]
+            ID_SNAPPY => Some(CompressionAlgorithm::Snappy),
+            _ => None,
+        }
+    }
+
+    /// Check that the settings are in range
+    pub fn validate(&self) -> Result<(), CacheError> {
+        match *self {
+            CompressionAlgorithm::Deflate { level } if level > 9 => Err(CacheError::InvalidConfig(
+                "deflate level must be between 0 and 9".to_string(),
+            )),
+            CompressionAlgorithm::Deflate { .. } => Ok(()),
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd { level } if !ZSTD_LEVELS.contains(&level) => Err(
+                CacheError::InvalidConfig("zstd level must be between -7 and 22".to_string()),
+            ),
+            #[cfg(feature = "zstd")]
+            CompressionAlgorithm::Zstd { .. } => Ok(()),
+            #[cfg(feature = "snappy")]
+            CompressionAlgorithm::Snappy => Ok(()),
+        }
+    }
+}
+
+/// Raw deflate compression
+///
+/// # Default Value
+/// - `level`: 6
+#[derive(Debug, Clone, Copy)]
+pub struct DeflateCompression {
+    level: u32,
+}
+
+impl DeflateCompression {
+    /// Compression level from 0 (store only) to 9 (smallest output)
+    pub fn new(level: u32) -> Self {
+        Self {
+            level: level.min(9),
+        }
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+}
+
+impl Default for DeflateCompression {
+    fn default() -> Self {
+        Self::new(6)
+    }
+}
+
+impl Compression for DeflateCompression {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        let mut compressed = Vec::with_capacity(data.len() / 2);
+        DeflateEncoder::new(data, flate2::Compression::new(self.level))
+            .read_to_end(&mut compressed)
+            .map_err(|e| CacheError::Compression(e.to_string()))?;
+        Ok(compressed)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        let mut decompressed = Vec::with_capacity(data.len() * 2);
+        DeflateDecoder::new(data)
+            .read_to_end(&mut decompressed)
+            .map_err(|e| CacheError::Compression(e.to_string()))?;
+        Ok(decompressed)
+    }
+
+    fn compress_to(&self, data: &[u8], writer: &mut dyn Write) -> Result<(), CacheError> {
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(writer, flate2::Compression::new(self.level));
+        encoder.write_all(data)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    fn algorithm(&self) -> Option<CompressionAlgorithm> {
+        Some(CompressionAlgorithm::Deflate { level: self.level })
+    }
+}
+
+/// Levels accepted by [`ZstdCompression`]; negative levels trade ratio for
+/// speed
+#[cfg(feature = "zstd")]
+const ZSTD_LEVELS: std::ops::RangeInclusive<i32> = -7..=22;
+
+/// Zstandard compression
+///
+/// Usually both smaller and faster than deflate, notably on floating-point
+/// chunk data. Requires the `zstd` feature.
+///
+/// # Default Value
+/// - `level`: 3
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct ZstdCompression {
+    level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdCompression {
+    /// Compression level from -7 (fastest) to 22 (smallest output)
+    pub fn new(level: i32) -> Self {
+        Self {
+            level: level.clamp(*ZSTD_LEVELS.start(), *ZSTD_LEVELS.end()),
+        }
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Default for ZstdCompression {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Compression for ZstdCompression {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        zstd::bulk::compress(data, self.level).map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        zstd::stream::decode_all(data).map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn compress_to(&self, data: &[u8], writer: &mut dyn Write) -> Result<(), CacheError> {
+        zstd::stream::copy_encode(data, writer, self.level)
+            .map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn algorithm(&self) -> Option<CompressionAlgorithm> {
+        Some(CompressionAlgorithm::Zstd { level: self.level })
+    }
+}
+
+/// Zstandard compression with a dictionary shared by all values
+///
+/// Small, similar values such as the chunks of one array compress far better
+/// against a dictionary trained on a sample of them. Entries can only be
+/// decompressed with the same dictionary, so keep
+/// [`dictionary`](ZstdDictionaryCompression::dictionary) to read them after
+/// a restart. Requires the `zstd` feature.
+#[cfg(feature = "zstd")]
+pub struct ZstdDictionaryCompression {
+    level: i32,
+    dictionary: Vec<u8>,
+    encoder: zstd::dict::EncoderDictionary<'static>,
+    decoder: zstd::dict::DecoderDictionary<'static>,
+}
+
+#[cfg(feature = "zstd")]
+impl ZstdDictionaryCompression {
+    /// Compress against `dictionary` at `level`, from -7 to 22
+    pub fn new(dictionary: Vec<u8>, level: i32) -> Self {
+        let level = level.clamp(*ZSTD_LEVELS.start(), *ZSTD_LEVELS.end());
+        Self {
+            level,
+            encoder: zstd::dict::EncoderDictionary::copy(&dictionary, level),
+            decoder: zstd::dict::DecoderDictionary::copy(&dictionary),
+            dictionary,
+        }
+    }
+
+    /// Train a dictionary of at most `max_size` bytes on `samples`
+    ///
+    /// A few hundred samples and a dictionary of around a hundredth of their
+    /// total size usually work well.
+    pub fn train<S: AsRef<[u8]>>(
+        samples: &[S],
+        max_size: usize,
+        level: i32,
+    ) -> Result<Self, CacheError> {
+        if samples.is_empty() {
+            return Err(CacheError::Compression(
+                "no samples to train a dictionary on".to_string(),
+            ));
+        }
+        let dictionary = zstd::dict::from_samples(samples, max_size)
+            .map_err(|e| CacheError::Compression(e.to_string()))?;
+        Ok(Self::new(dictionary, level))
+    }
+
+    pub fn level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn dictionary(&self) -> &[u8] {
+        &self.dictionary
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl std::fmt::Debug for ZstdDictionaryCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ZstdDictionaryCompression")
+            .field("level", &self.level)
+            .field("dictionary_len", &self.dictionary.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Compression for ZstdDictionaryCompression {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        zstd::bulk::Compressor::with_prepared_dictionary(&self.encoder)
+            .and_then(|mut compressor| compressor.compress(data))
+            .map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        let mut decompressed = Vec::with_capacity(data.len() * 2);
+        zstd::stream::read::Decoder::with_prepared_dictionary(data, &self.decoder)
+            .and_then(|mut decoder| decoder.read_to_end(&mut decompressed))
+            .map_err(|e| CacheError::Compression(e.to_string()))?;
+        Ok(decompressed)
+    }
+
+    fn compress_to(&self, data: &[u8], writer: &mut dyn Write) -> Result<(), CacheError> {
+        let mut encoder =
+            zstd::stream::write::Encoder::with_prepared_dictionary(writer, &self.encoder)
+                .map_err(|e| CacheError::Compression(e.to_string()))?;
+        encoder.write_all(data)?;
+        encoder.finish()?;
+        Ok(())
+    }
+}
+
+/// Snappy compression, in the raw (unframed) format
+///
+/// Favours speed over ratio, and matches the chunk codec of many Hadoop and
+/// Arrow pipelines. The raw format is produced in one piece, so streamed
+/// writes still buffer the compressed value. Requires the `snappy` feature.
+#[cfg(feature = "snappy")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SnappyCompression;
+
+#[cfg(feature = "snappy")]
+impl Compression for SnappyCompression {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        snap::raw::Encoder::new()
+            .compress_vec(data)
+            .map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|e| CacheError::Compression(e.to_string()))
+    }
+
+    fn algorithm(&self) -> Option<CompressionAlgorithm> {
+        Some(CompressionAlgorithm::Snappy)
+    }
+}
+
+/// Reordering of numeric chunk bytes before compression
+///
+/// The bytes of neighbouring floats and integers differ mostly in their low
+/// bits; grouping like bytes, or like bits, together gives the entropy coder
+/// long runs to work with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shuffle {
+    /// First bytes of all elements, then second bytes, and so on
+    Byte,
+    /// Lowest bits of all elements, then next bits, and so on, like Blosc's
+    /// bitshuffle; usually the better choice for floating-point data
+    Bit,
+}
+
+/// Codec applying a [`Shuffle`] to values before compressing them
+///
+/// `element_size` is the size of the array's data type, such as 4 for
+/// float32 or 8 for float64, so arrays of different data types want codecs
+/// of their own. Bytes past the last whole element, or for bit shuffling the
+/// last whole group of 8 elements, are stored unshuffled.
+///
+/// Entries are recorded as custom codec entries, so only a cache with the
+/// same shuffle settings reads them back.
+#[derive(Debug, Clone)]
+pub struct ShuffleCompression<Z: Compression> {
+    inner: Z,
+    shuffle: Shuffle,
+    element_size: usize,
+}
+
+impl<Z: Compression> ShuffleCompression<Z> {
+    pub fn new(inner: Z, shuffle: Shuffle, element_size: usize) -> Self {
+        Self {
+            inner,
+            shuffle,
+            element_size: element_size.max(1),
+        }
+    }
+
+    pub fn shuffle(&self) -> Shuffle {
+        self.shuffle
+    }
+
+    pub fn element_size(&self) -> usize {
+        self.element_size
+    }
+}
+
+impl<Z: Compression> Compression for ShuffleCompression<Z> {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        let shuffled = match self.shuffle {
+            Shuffle::Byte => byte_shuffle(data, self.element_size),
+            Shuffle::Bit => bit_shuffle(data, self.element_size),
+        };
+        self.inner.compress(&shuffled)
+    }
+
+    fn compress_to(&self, data: &[u8], writer: &mut dyn Write) -> Result<(), CacheError> {
+        let shuffled = match self.shuffle {
+            Shuffle::Byte => byte_shuffle(data, self.element_size),
+            Shuffle::Bit => bit_shuffle(data, self.element_size),
+        };
+        self.inner.compress_to(&shuffled, writer)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        let shuffled = self.inner.decompress(data)?;
+        Ok(match self.shuffle {
+            Shuffle::Byte => byte_unshuffle(&shuffled, self.element_size),
+            Shuffle::Bit => bit_unshuffle(&shuffled, self.element_size),
+        })
+    }
+}
+
+/// Byte `k` of element `i` moves to `k * elements + i`
+fn byte_shuffle(data: &[u8], element_size: usize) -> Vec<u8> {
+    let elements = data.len() / element_size;
+    let body = elements * element_size;
+    let mut shuffled = vec![0; data.len()];
+    for (i, element) in data[..body].chunks_exact(element_size).enumerate() {
+        for (k, &byte) in element.iter().enumerate() {
+            shuffled[k * elements + i] = byte;
+        }
+    }
+    shuffled[body..].copy_from_slice(&data[body..]);
+    shuffled
+}
+
+fn byte_unshuffle(shuffled: &[u8], element_size: usize) -> Vec<u8> {
+    let elements = shuffled.len() / element_size;
+    let body = elements * element_size;
+    let mut data = vec![0; shuffled.len()];
+    for (i, element) in data[..body].chunks_exact_mut(element_size).enumerate() {
+        for (k, byte) in element.iter_mut().enumerate() {
+            *byte = shuffled[k * elements + i];
+        }
+    }
+    data[body..].copy_from_slice(&shuffled[body..]);
+    data
+}
+
+/// Byte shuffle, then split each byte stream into its 8 bit planes
+///
+/// Bit `b` of byte `j` in a stream of byte-shuffled elements moves to bit
+/// `j % 8` of byte `b * elements / 8 + j / 8` of that stream.
+fn bit_shuffle(data: &[u8], element_size: usize) -> Vec<u8> {
+    let elements = data.len() / element_size / 8 * 8;
+    let body = elements * element_size;
+    let streams = byte_shuffle(&data[..body], element_size);
+    let mut shuffled = vec![0; data.len()];
+    let plane_len = elements / 8;
+    for (stream, planes) in streams
+        .chunks_exact(elements.max(1))
+        .zip(shuffled.chunks_exact_mut(elements.max(1)))
+    {
+        for (group, bytes) in stream.chunks_exact(8).enumerate() {
+            for bit in 0..8 {
+                planes[bit * plane_len + group] = bytes
+                    .iter()
+                    .enumerate()
+                    .fold(0, |plane, (j, byte)| plane | ((byte >> bit) & 1) << j);
+            }
+        }
+    }
+    shuffled[body..].copy_from_slice(&data[body..]);
+    shuffled
+}
+
+fn bit_unshuffle(shuffled: &[u8], element_size: usize) -> Vec<u8> {
+    let elements = shuffled.len() / element_size / 8 * 8;
+    let body = elements * element_size;
+    let mut streams = vec![0; body];
+    let plane_len = elements / 8;
+    for (planes, stream) in shuffled[..body]
+        .chunks_exact(elements.max(1))
+        .zip(streams.chunks_exact_mut(elements.max(1)))
+    {
+        for (group, bytes) in stream.chunks_exact_mut(8).enumerate() {
+            for bit in 0..8 {
+                let plane = planes[bit * plane_len + group];
+                for (j, byte) in bytes.iter_mut().enumerate() {
+                    *byte |= ((plane >> j) & 1) << bit;
+                }
+            }
+        }
+    }
+    let mut data = byte_unshuffle(&streams, element_size);
+    data.extend_from_slice(&shuffled[body..]);
+    data
+}
+
+/// Compression work done with one algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlgorithmStats {
+    /// Values written
+    pub values: u64,
+    /// Size of the written values
+    pub logical_bytes: u64,
+    /// Size of the written values as stored, headers included
+    pub stored_bytes: u64,
+    /// Time spent compressing the written values, or for values stored
+    /// uncompressed, on trial compressions that did not pay off
+    pub compression_time: Duration,
+    /// Values read back
+    pub decompressions: u64,
+    pub decompression_time: Duration,
+}
+
+impl AlgorithmStats {
+    /// Logical to stored size ratio of the written values, 1.0 before any
+    /// write
+    pub fn ratio(&self) -> f64 {
+        if self.stored_bytes == 0 {
+            1.0
+        } else {
+            self.logical_bytes as f64 / self.stored_bytes as f64
+        }
+    }
+}
+
+impl std::ops::AddAssign for AlgorithmStats {
+    fn add_assign(&mut self, other: Self) {
+        self.values += other.values;
+        self.logical_bytes += other.logical_bytes;
+        self.stored_bytes += other.stored_bytes;
+        self.compression_time += other.compression_time;
+        self.decompressions += other.decompressions;
+        self.decompression_time += other.decompression_time;
+    }
+}
+
+/// Compression work done by a cache, per algorithm
+///
+/// Algorithms are named `deflate`, `zstd`, `snappy` or `custom`, with `none`
+/// for values stored uncompressed. Empty for caches that do not compress.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompressionStats {
+    pub algorithms: BTreeMap<String, AlgorithmStats>,
+}
+
+impl CompressionStats {
+    pub fn is_empty(&self) -> bool {
+        self.algorithms.is_empty()
+    }
+
+    /// Work summed over all algorithms
+    pub fn total(&self) -> AlgorithmStats {
+        let mut total = AlgorithmStats::default();
+        for stats in self.algorithms.values() {
+            total += *stats;
+        }
+        total
+    }
+}
+
+impl std::ops::AddAssign for CompressionStats {
+    fn add_assign(&mut self, other: Self) {
+        for (algorithm, stats) in other.algorithms {
+            *self.algorithms.entry(algorithm).or_default() += stats;
+        }
+    }
+}
+
+/// Header algorithm ids in [`CompressionTracker`] slot order, with their names
+const TRACKED_ALGORITHMS: [(u8, &str); 5] = [
+    (ID_UNCOMPRESSED, "none"),
+    (ID_DEFLATE, "deflate"),
+    (ID_ZSTD, "zstd"),
+    (ID_SNAPPY, "snappy"),
+    (ID_CUSTOM, "custom"),
+];
+
+/// Counters behind [`CompressionStats`], one set per tracked algorithm
+#[derive(Debug, Default)]
+pub(crate) struct CompressionTracker {
+    slots: [AlgorithmCounters; TRACKED_ALGORITHMS.len()],
+}
+
+#[derive(Debug, Default)]
+struct AlgorithmCounters {
+    values: AtomicU64,
+    logical_bytes: AtomicU64,
+    stored_bytes: AtomicU64,
+    compression_nanos: AtomicU64,
+    decompressions: AtomicU64,
+    decompression_nanos: AtomicU64,
+}
+
+impl CompressionTracker {
+    fn slot(&self, id: u8) -> &AlgorithmCounters {
+        let index = TRACKED_ALGORITHMS
+            .iter()
+            .position(|&(tracked, _)| tracked == id)
+            .unwrap_or(TRACKED_ALGORITHMS.len() - 1);
+        &self.slots[index]
+    }
+
+    fn record_write(&self, id: u8, logical_bytes: usize, stored_bytes: usize, elapsed: Duration) {
+        let slot = self.slot(id);
+        slot.values.fetch_add(1, Ordering::Relaxed);
+        slot.logical_bytes
+            .fetch_add(logical_bytes as u64, Ordering::Relaxed);
+        slot.stored_bytes
+            .fetch_add(stored_bytes as u64, Ordering::Relaxed);
+        slot.compression_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn record_read(&self, id: u8, elapsed: Duration) {
+        let slot = self.slot(id);
+        slot.decompressions.fetch_add(1, Ordering::Relaxed);
+        slot.decompression_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Values written uncompressed
+    pub(crate) fn uncompressed_writes(&self) -> u64 {
+        self.slot(ID_UNCOMPRESSED).values.load(Ordering::Relaxed)
+    }
+
+    /// Work recorded so far, leaving out unused algorithms
+    pub(crate) fn stats(&self) -> CompressionStats {
+        let algorithms = TRACKED_ALGORITHMS
+            .iter()
+            .zip(&self.slots)
+            .map(|(&(_, name), slot)| {
+                let stats = AlgorithmStats {
+                    values: slot.values.load(Ordering::Relaxed),
+                    logical_bytes: slot.logical_bytes.load(Ordering::Relaxed),
+                    stored_bytes: slot.stored_bytes.load(Ordering::Relaxed),
+                    compression_time: Duration::from_nanos(
+                        slot.compression_nanos.load(Ordering::Relaxed),
+                    ),
+                    decompressions: slot.decompressions.load(Ordering::Relaxed),
+                    decompression_time: Duration::from_nanos(
+                        slot.decompression_nanos.load(Ordering::Relaxed),
+                    ),
+                };
+                (name.to_string(), stats)
+            })
+            .filter(|(_, stats)| stats.values > 0 || stats.decompressions > 0)
+            .collect();
+        CompressionStats { algorithms }
+    }
+}
+
+/// Value framed with a header recording how its payload is stored
+///
+/// The payload is `value` compressed with `compression`, or `value` itself
+/// when `min_ratio` is given and compression does not shrink it by that ratio.
+pub(crate) fn encode_entry(
+    compression: &dyn Compression,
+    value: &[u8],
+    min_ratio: Option<f64>,
+    tracker: &CompressionTracker,
+) -> Result<Bytes, CacheError> {
+    let started = Instant::now();
+    let compresses_well = |original: usize, compressed: usize| {
+        min_ratio.is_none_or(|ratio| compressed as f64 * ratio <= original as f64)
+    };
+    let sample_compresses_well =
+        min_ratio.is_none() || value.len() <= COMPRESSION_SAMPLE_BYTES || {
+            let sample = compression.compress(&value[..COMPRESSION_SAMPLE_BYTES])?;
+            compresses_well(COMPRESSION_SAMPLE_BYTES, sample.len())
+        };
+    let compressed = if sample_compresses_well {
+        Some(compression.compress(value)?)
+            .filter(|compressed| compresses_well(value.len(), compressed.len()))
+    } else {
+        None
+    };
+    let elapsed = started.elapsed();
+
+    let id = match &compressed {
+        Some(_) => compression
+            .algorithm()
+            .map_or(ID_CUSTOM, CompressionAlgorithm::id),
+        None => ID_UNCOMPRESSED,
+    };
+    let payload = compressed.as_deref().unwrap_or(value);
+    let mut stored = Vec::with_capacity(ENTRY_HEADER_LEN + payload.len());
+    stored.extend_from_slice(&ENTRY_MAGIC);
+    stored.push(id);
+    stored.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    stored.extend_from_slice(payload);
+    tracker.record_write(id, value.len(), stored.len(), elapsed);
+    Ok(Bytes::from(stored))
+}
+
+/// [`encode_entry`] without a minimum ratio, compressing `value` straight
+/// into `writer`
+pub(crate) fn encode_entry_to(
+    compression: &dyn Compression,
+    value: &[u8],
+    tracker: &CompressionTracker,
+    writer: &mut dyn Write,
+) -> Result<(), CacheError> {
+    let started = Instant::now();
+    let id = compression
+        .algorithm()
+        .map_or(ID_CUSTOM, CompressionAlgorithm::id);
+    let mut writer = CountingWriter {
+        inner: writer,
+        len: 0,
+    };
+    writer.write_all(&ENTRY_MAGIC)?;
+    writer.write_all(&[id])?;
+    writer.write_all(&(value.len() as u64).to_le_bytes())?;
+    compression.compress_to(value, &mut writer)?;
+    tracker.record_write(id, value.len(), writer.len, started.elapsed());
+    Ok(())
+}
+
+/// Writer counting the bytes written through it
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    len: usize,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.len += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Size of the value held by `stored`, per its header if it has one
+fn decoded_len(stored: &[u8]) -> usize {
+    match stored.get(3..ENTRY_HEADER_LEN) {
+        Some(len) if stored[..2] == ENTRY_MAGIC => {
+            u64::from_le_bytes(len.try_into().unwrap()) as usize
+        }
+        _ => stored.len(),
+    }
+}
+
+/// [`encode_entry`] run on the blocking thread pool if `value` has at least
+/// `offload_bytes` bytes, so compressing it does not stall the executor
+pub(crate) async fn encode_entry_offloaded(
+    compression: Arc<dyn Compression>,
+    value: Bytes,
+    min_ratio: Option<f64>,
+    tracker: Arc<CompressionTracker>,
+    offload_bytes: usize,
+) -> Result<Bytes, CacheError> {
+    if value.len() < offload_bytes {
+        return encode_entry(&*compression, &value, min_ratio, &tracker);
+    }
+    tokio::task::spawn_blocking(move || encode_entry(&*compression, &value, min_ratio, &tracker))
+        .await
+        .map_err(|e| CacheError::Compression(e.to_string()))?
+}
+
+/// [`decode_entry`] run on the blocking thread pool if the value has at
+/// least `offload_bytes` bytes
+pub(crate) async fn decode_entry_offloaded(
+    compression: Arc<dyn Compression>,
+    stored: Bytes,
+    tracker: Arc<CompressionTracker>,
+    offload_bytes: usize,
+) -> Result<Bytes, CacheError> {
+    if decoded_len(&stored) < offload_bytes {
+        return decode_entry(&*compression, stored, &tracker);
+    }
+    tokio::task::spawn_blocking(move || decode_entry(&*compression, stored, &tracker))
+        .await
+        .map_err(|e| CacheError::Compression(e.to_string()))?
+}
+
+/// Original value of an entry written by [`encode_entry`]
+///
+/// Payloads compressed with another built-in algorithm than `compression` are
+/// decompressed with that algorithm.
+pub(crate) fn decode_entry(
+    compression: &dyn Compression,
+    stored: Bytes,
+    tracker: &CompressionTracker,
+) -> Result<Bytes, CacheError> {
+    if stored.len() < ENTRY_HEADER_LEN || stored[..2] != ENTRY_MAGIC {
+        return Err(CacheError::Corruption(
+            "entry has no compression header".to_string(),
+        ));
+    }
+    let id = stored[2];
+    let original_len = u64::from_le_bytes(stored[3..ENTRY_HEADER_LEN].try_into().unwrap());
+    let payload = stored.slice(ENTRY_HEADER_LEN..);
+
+    let started = Instant::now();
+    let own_id = compression
+        .algorithm()
+        .map_or(ID_CUSTOM, CompressionAlgorithm::id);
+    let value = if id == ID_UNCOMPRESSED {
+        payload
+    } else if id == own_id {
+        Bytes::from(compression.decompress(&payload)?)
+    } else if let Some(algorithm) = CompressionAlgorithm::from_id(id) {
+        Bytes::from(algorithm.build().decompress(&payload)?)
+    } else {
+        return Err(CacheError::Compression(format!(
+            "entry is compressed with unavailable algorithm {}",
+            id
+        )));
+    };
+
+    if value.len() as u64 != original_len {
+        return Err(CacheError::Corruption(format!(
+            "entry decoded to {} bytes, header records {}",
+            value.len(),
+            original_len
+        )));
+    }
+    tracker.record_read(id, started.elapsed());
+    Ok(value)
+}
+
+/// Cache wrapper storing every value compressed
+///
+/// Sizes and statistics are those of the inner cache, so they count
+/// compressed bytes, with the work of compression added to
+/// [`CacheStats::compression`]. Each entry starts with a header naming its algorithm
+/// and original length, so entries compressed with another built-in codec
+/// remain readable. Entries that fail to decode are dropped and reported as
+/// misses.
+///
+/// With [`with_min_ratio`](CompressedCache::with_min_ratio), values that do
+/// not compress well, such as chunks already compressed by their zarr codec,
+/// are stored as is instead.
+///
+/// Values of 256 KiB or more are compressed and decompressed on tokio's
+/// blocking thread pool, see
+/// [`with_offload_bytes`](CompressedCache::with_offload_bytes).
+pub struct CompressedCache<C: Cache> {
+    inner: C,
+    /// Codec of new writes; entries keep naming the codec they were written with
+    compression: RwLock<Arc<dyn Compression>>,
+    /// Smallest original-to-compressed size ratio worth storing compressed;
+    /// `None` compresses every value
+    min_ratio: Option<f64>,
+    /// Size from which values are (de)compressed on the blocking thread pool
+    offload_bytes: usize,
+    tracker: Arc<CompressionTracker>,
+}
+
+impl<C: Cache> CompressedCache<C> {
+    pub fn new<Z: Compression>(inner: C, compression: Z) -> Self {
+        Self {
+            inner,
+            compression: RwLock::new(Arc::new(compression)),
+            min_ratio: None,
+            offload_bytes: DEFAULT_OFFLOAD_BYTES,
+            tracker: Arc::default(),
+        }
+    }
+
+    /// Store values uncompressed unless compression shrinks them by at least
+    /// `ratio`, such as 1.1 for a tenth
+    ///
+    /// Values over 64 KiB are only compressed whole if their first 64 KiB
+    /// reach the ratio.
+    pub fn with_min_ratio(mut self, ratio: f64) -> Self {
+        self.min_ratio = Some(ratio);
+        self
+    }
+
+    /// Compress and decompress values of at least `bytes` bytes on the
+    /// blocking thread pool rather than on the calling task
+    ///
+    /// Smaller values are handled inline, as handing them off costs more
+    /// than compressing them. `usize::MAX` keeps all work inline.
+    pub fn with_offload_bytes(mut self, bytes: usize) -> Self {
+        self.offload_bytes = bytes;
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Compress subsequent writes with `compression`
+    ///
+    /// Entries already written with a built-in codec stay readable; those
+    /// written with a custom or dictionary codec read as misses.
+    pub fn set_compression<Z: Compression>(&self, compression: Z) {
+        *self.compression.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(compression);
+    }
+
+    /// Train a zstd dictionary on the values cached under `keys` and compress
+    /// subsequent writes with it
+    ///
+    /// Keys that are not cached are skipped. The level is that of the current
+    /// codec if it is zstd, otherwise 3. Returns the dictionary, to be passed
+    /// to [`ZstdDictionaryCompression::new`] so entries stay readable after a
+    /// restart.
+    #[cfg(feature = "zstd")]
+    pub async fn train_zstd_dictionary(
+        &self,
+        keys: &[crate::cache::StoreKey],
+        max_size: usize,
+    ) -> Result<Vec<u8>, CacheError> {
+        let mut samples = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self.get(key).await {
+                samples.push(value);
+            }
+        }
+        let level = match self.compression().algorithm() {
+            Some(CompressionAlgorithm::Zstd { level }) => level,
+            _ => ZstdCompression::default().level(),
+        };
+        let compression = ZstdDictionaryCompression::train(&samples, max_size, level)?;
+        let dictionary = compression.dictionary().to_vec();
+        self.set_compression(compression);
+        Ok(dictionary)
+    }
+
+    fn compression(&self) -> Arc<dyn Compression> {
+        self.compression
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Writes stored uncompressed because they did not compress well enough
+    pub fn uncompressed_writes(&self) -> u64 {
+        self.tracker.uncompressed_writes()
+    }
+
+    async fn encode(&self, value: Bytes) -> Result<Bytes, CacheError> {
+        encode_entry_offloaded(
+            self.compression(),
+            value,
+            self.min_ratio,
+            self.tracker.clone(),
+            self.offload_bytes,
+        )
+        .await
+    }
+
+    /// Original value of the entry `stored` under `key`, dropping the entry
+    /// if it cannot be decoded
+    async fn decode(&self, key: &str, stored: Bytes) -> Option<Bytes> {
+        match decode_entry_offloaded(
+            self.compression(),
+            stored,
+            self.tracker.clone(),
+            self.offload_bytes,
+        )
+        .await
+        {
+            Ok(data) => Some(data),
+            Err(e) => {
+                tracing::warn!("Dropping undecodable entry {}: {:?}", key, e);
+                if let Err(e) = self.inner.remove(key).await {
+                    tracing::warn!("Failed to remove undecodable entry {}: {:?}", key, e);
+                }
+                None
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache> Cache for CompressedCache<C> {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let stored = self.inner.get(key).await?;
+        self.decode(key, stored).await
+    }
+
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        let stored = self.inner.get_stale(key).await?;
+        self.decode(key, stored).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let stored = self.encode(value).await?;
+        self.inner.set(key, stored).await
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.inner.remove(key).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        self.inner.remove_with_cause(key, cause).await
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        self.inner.remove_prefix(prefix).await
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        self.inner.clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.inner.purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        self.inner.io_operations()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.inner.resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.inner.set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn stats(&self) -> CacheStats {
+        let mut stats = self.inner.stats();
+        stats.compression += self.tracker.stats();
+        stats
+    }
+}
@@ -1,7 +1,35 @@
+use crate::error::CacheError;
+use crate::units::{self, ByteSize, HumanDuration, SetterError};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+/// Prefix of the environment variables overriding [`CacheConfig`] and
+/// [`HybridCacheConfig`](crate::HybridCacheConfig) settings
+pub const ENV_PREFIX: &str = "ZARRS_CACHE_";
+
+/// Prefix of the environment variables overriding [`PrefetchConfig`]
+/// settings
+pub const PREFETCH_ENV_PREFIX: &str = "ZARRS_CACHE_PREFETCH_";
+
+/// Prefix of the environment variables overriding
+/// [`MetricsConfig`](crate::MetricsConfig) settings
+pub const METRICS_ENV_PREFIX: &str = "ZARRS_CACHE_METRICS_";
+
+/// Prefix of the environment variables overriding
+/// [`RateLimitConfig`](crate::upstream::RateLimitConfig) settings
+pub const RATE_LIMIT_ENV_PREFIX: &str = "ZARRS_CACHE_RATE_LIMIT_";
+
+/// Prefix of the environment variables overriding
+/// [`RetryConfig`](crate::upstream::RetryConfig) settings
+pub const RETRY_ENV_PREFIX: &str = "ZARRS_CACHE_RETRY_";
+
+/// Prefix of the environment variables overriding
+/// [`CircuitBreakerConfig`](crate::upstream::CircuitBreakerConfig) settings
+pub const CIRCUIT_BREAKER_ENV_PREFIX: &str = "ZARRS_CACHE_CIRCUIT_BREAKER_";
+
 /// General cache configuration
 ///
 /// # Default Values
@@ -10,22 +38,34 @@ use std::time::Duration;
 /// - `max_disk_size`: None (unlimited)
 /// - `ttl`: None (no expiration)
 /// - `prefetch_config`: None (no prefetching)
+/// - `listing_ttl`: 30 seconds
+///
+/// Settings missing from a deserialized configuration take these defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CacheConfig {
     /// Maximum memory cache size in bytes
+    #[serde(deserialize_with = "units::deserialize_size")]
     pub max_memory_size: usize,
 
     /// Optional disk cache directory
     pub disk_cache_dir: Option<PathBuf>,
 
     /// Maximum disk cache size in bytes
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
     pub max_disk_size: Option<u64>,
 
     /// Time-to-live for cached entries
+    #[serde(deserialize_with = "units::deserialize_optional_duration")]
     pub ttl: Option<Duration>,
 
     /// Prefetch strategy configuration
     pub prefetch_config: Option<PrefetchConfig>,
+
+    /// How long store listings are cached, `None` to list the store every
+    /// time
+    #[serde(deserialize_with = "units::deserialize_optional_duration")]
+    pub listing_ttl: Option<Duration>,
 }
 
 /// Configuration for prefetch strategies
@@ -33,7 +73,10 @@ pub struct CacheConfig {
 /// # Default Values
 /// - `neighbor_chunks`: 2
 /// - `max_queue_size`: 10
+///
+/// Settings missing from a deserialized configuration take these defaults.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PrefetchConfig {
     /// Number of neighboring chunks to prefetch
     pub neighbor_chunks: usize,
@@ -59,6 +102,261 @@ impl Default for CacheConfig {
             max_disk_size: None,
             ttl: None,
             prefetch_config: None,
+            listing_ttl: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> CacheConfigBuilder {
+        CacheConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable and consistent with each other
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.max_memory_size == 0 {
+            return invalid("max_memory_size must be greater than zero");
+        }
+        match self.max_disk_size {
+            Some(0) => return invalid("max_disk_size must be greater than zero"),
+            Some(disk) if self.max_memory_size as u64 > disk => {
+                return invalid("max_memory_size must not exceed max_disk_size")
+            }
+            _ => {}
+        }
+        if self.ttl == Some(Duration::ZERO) {
+            return invalid("ttl must be greater than zero");
+        }
+        if self.listing_ttl == Some(Duration::ZERO) {
+            return invalid("listing_ttl must be greater than zero");
+        }
+        if let Some(prefetch) = &self.prefetch_config {
+            prefetch.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Load the configuration from a JSON, TOML (`toml` feature) or YAML
+    /// (`yaml` feature) file, chosen by extension, then apply environment
+    /// overrides as [`CacheConfig::from_env`] does
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with environment overrides
+    ///
+    /// `ZARRS_CACHE_<SETTING>` sets the setting of that name, such as
+    /// `ZARRS_CACHE_MAX_MEMORY_SIZE=512MB`, and `ZARRS_CACHE_PREFETCH_<SETTING>`
+    /// one of `prefetch_config` if present.
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among the `(name, value)` variables `vars`
+    ///
+    /// Values are read as JSON, and otherwise as a string, so sizes and
+    /// durations may be written as `512MB` or `5m`.
+    /// Variables naming no setting are ignored.
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        let vars: Vec<(String, String)> = vars.into_iter().collect();
+        let mut config = apply_overrides(&self, ENV_PREFIX, vars.iter().cloned())?;
+        if let Some(prefetch) = config.prefetch_config {
+            config.prefetch_config = Some(prefetch.override_from(vars)?);
+        }
+        Ok(config)
+    }
+}
+
+impl PrefetchConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> PrefetchConfigBuilder {
+        PrefetchConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        if self.max_queue_size == 0 {
+            return Err(CacheError::InvalidConfig(
+                "max_queue_size must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Load the configuration from a file, as [`CacheConfig::from_file`]
+    /// does, with `ZARRS_CACHE_PREFETCH_<SETTING>` overrides
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with `ZARRS_CACHE_PREFETCH_<SETTING>` overrides
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among `vars`, as [`CacheConfig::override_from`]
+    /// does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        apply_overrides(&self, PREFETCH_ENV_PREFIX, vars)
+    }
+}
+
+/// Builder for [`CacheConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](CacheConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct CacheConfigBuilder {
+    config: CacheConfig,
+    invalid: SetterError,
+}
+
+impl CacheConfigBuilder {
+    pub fn max_memory_size(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_usize()) {
+            self.config.max_memory_size = bytes;
+        }
+        self
+    }
+
+    pub fn disk_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.disk_cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn max_disk_size(mut self, size: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(size.to_bytes()) {
+            self.config.max_disk_size = Some(bytes);
+        }
+        self
+    }
+
+    pub fn ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.ttl = Some(duration);
+        }
+        self
+    }
+
+    pub fn prefetch_config(mut self, prefetch: PrefetchConfig) -> Self {
+        self.config.prefetch_config = Some(prefetch);
+        self
+    }
+
+    pub fn listing_ttl(mut self, ttl: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(ttl.to_duration()) {
+            self.config.listing_ttl = Some(duration);
+        }
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<CacheConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Builder for [`PrefetchConfig`], starting from its defaults
+///
+/// [`build`](PrefetchConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchConfigBuilder {
+    config: PrefetchConfig,
+}
+
+impl PrefetchConfigBuilder {
+    pub fn neighbor_chunks(mut self, chunks: usize) -> Self {
+        self.config.neighbor_chunks = chunks;
+        self
+    }
+
+    pub fn max_queue_size(mut self, keys: usize) -> Self {
+        self.config.max_queue_size = keys;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<PrefetchConfig, CacheError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Parse the configuration file at `path` in the format of its extension
+pub(crate) fn read_file<T: DeserializeOwned>(path: &Path) -> Result<T, CacheError> {
+    let contents = std::fs::read_to_string(path)?;
+    let invalid =
+        |e: &dyn std::fmt::Display| CacheError::InvalidConfig(format!("{}: {}", path.display(), e));
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => serde_json::from_str(&contents).map_err(|e| invalid(&e)),
+        #[cfg(feature = "toml")]
+        Some("toml") => toml::from_str(&contents).map_err(|e| invalid(&e)),
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => serde_yaml::from_str(&contents).map_err(|e| invalid(&e)),
+        _ => Err(CacheError::Unsupported(format!(
+            "configuration file format of {}",
+            path.display()
+        ))),
+    }
+}
+
+/// `config` with each top-level setting named by a `<prefix><SETTING>`
+/// variable among `vars` replaced by the variable's value
+///
+/// A value is tried as JSON and then as a string, keeping the first the
+/// setting accepts.
+pub(crate) fn apply_overrides<T: Serialize + DeserializeOwned>(
+    config: &T,
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<T, CacheError> {
+    let serialization = |e: serde_json::Error| CacheError::Serialization(e.to_string());
+    let Value::Object(mut settings) = serde_json::to_value(config).map_err(serialization)? else {
+        return Err(CacheError::Serialization(
+            "configuration is not a map of settings".to_string(),
+        ));
+    };
+
+    for (name, raw) in vars {
+        let Some(setting) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        let setting = setting.to_ascii_lowercase();
+        if !settings.contains_key(&setting) {
+            continue;
+        }
+        let accepted = override_candidates(&raw).into_iter().any(|candidate| {
+            settings.insert(setting.clone(), candidate);
+            T::deserialize(&Value::Object(settings.clone())).is_ok()
+        });
+        if !accepted {
+            return Err(CacheError::InvalidConfig(format!(
+                "{}: unusable value {:?}",
+                name, raw
+            )));
         }
     }
+    serde_json::from_value(Value::Object(settings)).map_err(serialization)
+}
+
+/// Readings of an override value, JSON first
+fn override_candidates(raw: &str) -> Vec<Value> {
+    let raw = raw.trim();
+    let mut candidates = Vec::new();
+    if let Ok(value) = serde_json::from_str(raw) {
+        candidates.push(value);
+    }
+    candidates.push(Value::String(raw.to_string()));
+    candidates
 }
@@ -0,0 +1,281 @@
+//! Caching of decoded chunks
+//!
+//! The caches in front of a store keep chunks as they are stored, so a hit
+//! still pays for decoding them. For chunks compressed with zstd or blosc
+//! that is most of the time a read takes. A [`DecodedChunkCache`] is a
+//! second-level cache of the decoded bytes, keyed by the array, the chunk
+//! indices and the codec chain that decoded them, so repeated reads skip
+//! decoding as well as the store:
+//!
+//! ```rust
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use zarrs_cache::decoded::{DecodedChunkCache, DecodedChunkKey};
+//! use zarrs_cache::LruMemoryCache;
+//!
+//! let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024 * 1024));
+//! let key = DecodedChunkKey::new("/temperature", &[0, 4, 2], r#"[{"name":"zstd"}]"#);
+//! let chunk = decoded
+//!     .get_or_decode(&key, || async {
+//!         // Retrieve and decode the chunk, such as with `Array::retrieve_chunk`
+//!         Ok(vec![0u8; 4096])
+//!     })
+//!     .await?;
+//! assert_eq!(chunk.as_ptr() as usize % 8, 0);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Decoded bytes are copied into buffers aligned to 8 bytes, so they can be
+//! viewed as slices of any numeric element type without another copy.
+//! Memory caches hand these buffers back as they are; hits on caches that
+//! keep entries on disk are copied into a new one.
+//! Entries of one array share the key prefix of its path, and
+//! [`DecodedChunkCache::invalidate_array`] drops them all when the array is
+//! rewritten.
+
+use crate::cache::{stable_hash, Cache, CacheStats, StoreKey};
+use crate::error::CacheError;
+use bytes::Bytes;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Path segment separating an array's path from its decoded entries
+const DECODED_SEGMENT: &str = ".decoded";
+
+/// Identity of a decoded chunk: its array, chunk indices and codec chain
+///
+/// The codec chain is part of the key so an array whose codecs change, or
+/// two arrays at the same path in different stores, never share entries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DecodedChunkKey {
+    array: String,
+    chunk_indices: Vec<u64>,
+    codec_chain: u64,
+}
+
+impl DecodedChunkKey {
+    /// Key of the chunk at `chunk_indices` of the array at `array`, decoded
+    /// by the codec chain described by `codec_chain`
+    ///
+    /// `codec_chain` can be any text that changes with the codecs, such as
+    /// the JSON of the array's `codecs` metadata; only its hash is kept.
+    pub fn new(array: &str, chunk_indices: &[u64], codec_chain: &str) -> Self {
+        Self {
+            array: array.trim_end_matches('/').to_string(),
+            chunk_indices: chunk_indices.to_vec(),
+            codec_chain: stable_hash(codec_chain),
+        }
+    }
+
+    /// Key of the chunk at `chunk_indices` of a zarrs `array`
+    pub fn for_array<TStorage: ?Sized>(
+        array: &zarrs::array::Array<TStorage>,
+        chunk_indices: &[u64],
+    ) -> Self {
+        let codecs = serde_json::to_string(&array.codecs().create_metadatas()).unwrap_or_default();
+        Self::new(array.path().as_str(), chunk_indices, &codecs)
+    }
+
+    pub fn array(&self) -> &str {
+        &self.array
+    }
+
+    pub fn chunk_indices(&self) -> &[u64] {
+        &self.chunk_indices
+    }
+
+    /// Key of the entry in the underlying cache
+    ///
+    /// `<array>/.decoded/<codec chain hash>/<indices joined by '.'>`
+    pub fn store_key(&self) -> StoreKey {
+        let indices: Vec<String> = self.chunk_indices.iter().map(u64::to_string).collect();
+        format!(
+            "{}/{}/{:016x}/{}",
+            self.array,
+            DECODED_SEGMENT,
+            self.codec_chain,
+            indices.join(".")
+        )
+    }
+}
+
+/// Copy `data` into a buffer aligned to 8 bytes
+pub fn aligned_bytes(data: &[u8]) -> Bytes {
+    let mut words = vec![0u64; data.len().div_ceil(8)];
+    // SAFETY: the words span at least `data.len()` bytes, and any bytes
+    // make valid `u64`s
+    unsafe {
+        std::ptr::copy_nonoverlapping(data.as_ptr(), words.as_mut_ptr().cast::<u8>(), data.len());
+    }
+    Bytes::from_owner(AlignedBuffer {
+        words,
+        len: data.len(),
+    })
+}
+
+/// Bytes kept in `u64` words for their alignment
+struct AlignedBuffer {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: the words span at least `len` initialized bytes
+        unsafe { std::slice::from_raw_parts(self.words.as_ptr().cast::<u8>(), self.len) }
+    }
+}
+
+/// Decoding work done and saved by a [`DecodedChunkCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DecodedCacheStats {
+    /// Reads answered with cached decoded bytes
+    pub hits: u64,
+    /// Chunks decoded on a miss
+    pub decodes: u64,
+    /// Decodes that failed
+    pub failed_decodes: u64,
+    /// Decoded bytes produced on misses
+    pub decoded_bytes: u64,
+    /// Time spent decoding on misses
+    pub decode_time: Duration,
+}
+
+impl DecodedCacheStats {
+    /// Average time one decode took, zero before the first
+    pub fn average_decode_time(&self) -> Duration {
+        if self.decodes == 0 {
+            Duration::ZERO
+        } else {
+            self.decode_time / self.decodes as u32
+        }
+    }
+
+    /// Estimated decoding time the hits saved, at the average decode time
+    pub fn estimated_time_saved(&self) -> Duration {
+        self.average_decode_time()
+            .saturating_mul(self.hits.min(u32::MAX as u64) as u32)
+    }
+}
+
+/// Second-level cache of decoded chunks in front of the codec chain
+///
+/// Any [`Cache`] holds the entries; give it its own budget, as decoded
+/// chunks are usually several times larger than the stored ones.
+pub struct DecodedChunkCache<C: Cache> {
+    cache: C,
+    hits: AtomicU64,
+    decodes: AtomicU64,
+    failed_decodes: AtomicU64,
+    decoded_bytes: AtomicU64,
+    decode_nanos: AtomicU64,
+}
+
+impl<C: Cache> DecodedChunkCache<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            cache,
+            hits: AtomicU64::new(0),
+            decodes: AtomicU64::new(0),
+            failed_decodes: AtomicU64::new(0),
+            decoded_bytes: AtomicU64::new(0),
+            decode_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Cached decoded bytes of `key`
+    pub async fn get(&self, key: &DecodedChunkKey) -> Option<Bytes> {
+        let data = self.cache.get(&key.store_key()).await?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        // Caches that keep entries outside memory return them unaligned
+        if data.as_ptr().align_offset(8) == 0 {
+            Some(data)
+        } else {
+            Some(aligned_bytes(&data))
+        }
+    }
+
+    /// Cache the decoded bytes of `key`, returning them in an aligned buffer
+    pub async fn insert(&self, key: &DecodedChunkKey, decoded: &[u8]) -> Result<Bytes, CacheError> {
+        let data = aligned_bytes(decoded);
+        self.cache.set(&key.store_key(), data.clone()).await?;
+        Ok(data)
+    }
+
+    /// Cached decoded bytes of `key`, decoding them with `decode` and
+    /// caching them on a miss
+    ///
+    /// Decoded bytes that fail to be cached are still returned.
+    pub async fn get_or_decode<F, Fut, D>(
+        &self,
+        key: &DecodedChunkKey,
+        decode: F,
+    ) -> Result<Bytes, CacheError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<D, CacheError>>,
+        D: AsRef<[u8]>,
+    {
+        if let Some(data) = self.get(key).await {
+            return Ok(data);
+        }
+
+        let started = Instant::now();
+        let decoded = decode().await;
+        self.decode_nanos
+            .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        let decoded = match decoded {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                self.failed_decodes.fetch_add(1, Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+        self.decodes.fetch_add(1, Ordering::Relaxed);
+        self.decoded_bytes
+            .fetch_add(decoded.as_ref().len() as u64, Ordering::Relaxed);
+
+        let data = aligned_bytes(decoded.as_ref());
+        if let Err(e) = self.cache.set(&key.store_key(), data.clone()).await {
+            tracing::warn!("Failed to cache decoded chunk {}: {:?}", key.store_key(), e);
+        }
+        Ok(data)
+    }
+
+    /// Drop the decoded chunk of `key`
+    pub async fn remove(&self, key: &DecodedChunkKey) -> Result<(), CacheError> {
+        self.cache.remove(&key.store_key()).await
+    }
+
+    /// Drop every decoded chunk of the array at `array`, whatever its codec
+    /// chain, returning how many were removed
+    pub async fn invalidate_array(&self, array: &str) -> Result<usize, CacheError> {
+        let prefix = format!("{}/{}/", array.trim_end_matches('/'), DECODED_SEGMENT);
+        self.cache.remove_prefix(&prefix).await
+    }
+
+    pub async fn clear(&self) -> Result<(), CacheError> {
+        self.cache.clear().await
+    }
+
+    /// Decoding work done and saved so far
+    pub fn stats(&self) -> DecodedCacheStats {
+        DecodedCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            decodes: self.decodes.load(Ordering::Relaxed),
+            failed_decodes: self.failed_decodes.load(Ordering::Relaxed),
+            decoded_bytes: self.decoded_bytes.load(Ordering::Relaxed),
+            decode_time: Duration::from_nanos(self.decode_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Statistics of the underlying cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+}
@@ -1,10 +1,43 @@
+use crate::cache::StoreKey;
+use crate::events::CacheEventKind;
+use std::fmt;
+use std::io::ErrorKind;
 use thiserror::Error;
 
+/// Source error of a [`CacheError::Backend`]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Tier of a tiered cache an operation failed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    Memory,
+    Disk,
+    Remote,
+}
+
+impl fmt::Display for Tier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Tier::Memory => "memory",
+            Tier::Disk => "disk",
+            Tier::Remote => "remote",
+        })
+    }
+}
+
+/// Errors of cache operations
+///
+/// [`CacheError::is_retryable`] and [`CacheError::is_transient`] tell
+/// read-through and write-back layers which failures are worth another
+/// attempt.
 #[derive(Error, Debug)]
 pub enum CacheError {
     #[error("Cache is full and cannot evict more entries")]
     CacheFull,
 
+    #[error("Entry of {size} bytes exceeds the cache capacity of {max} bytes")]
+    EntryTooLarge { size: usize, max: usize },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -13,4 +46,143 @@ pub enum CacheError {
 
     #[error("Invalid cache key: {0}")]
     InvalidKey(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Corrupt cache data: {0}")]
+    Corruption(String),
+
+    #[error("Storage backend error: {0}")]
+    Backend(#[source] BoxError),
+
+    #[error("Operation not supported: {0}")]
+    Unsupported(String),
+
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Compression error: {0}")]
+    Compression(String),
+
+    /// A load refused without trying the origin store, such as by an open
+    /// [`CircuitBreaker`](crate::upstream::CircuitBreaker)
+    #[error("Origin store unavailable: {0}")]
+    Unavailable(String),
+
+    /// `source` with the key, tier and operation it failed for
+    #[error("Failed to {operation} key {key:?} in the {tier} tier: {source}")]
+    Context {
+        operation: CacheEventKind,
+        tier: Tier,
+        key: StoreKey,
+        #[source]
+        source: Box<CacheError>,
+    },
+}
+
+impl CacheError {
+    /// A [`CacheError::Backend`] wrapping `source`, which may be an error or
+    /// a message
+    pub fn backend(source: impl Into<BoxError>) -> Self {
+        CacheError::Backend(source.into())
+    }
+
+    /// This error with the key, tier and operation it failed for
+    ///
+    /// Errors that already carry context keep it, as it was attached closer
+    /// to the failure.
+    pub fn context(self, operation: CacheEventKind, tier: Tier, key: &str) -> Self {
+        match self {
+            CacheError::Context { .. } => self,
+            source => CacheError::Context {
+                operation,
+                tier,
+                key: key.to_string(),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// The error without its context
+    pub fn root(&self) -> &CacheError {
+        match self {
+            CacheError::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Key the failed operation was for, if known
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            CacheError::Context { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Tier the operation failed in, if known
+    pub fn tier(&self) -> Option<Tier> {
+        match self {
+            CacheError::Context { tier, .. } => Some(*tier),
+            _ => None,
+        }
+    }
+
+    /// Operation that failed, if known
+    pub fn operation(&self) -> Option<CacheEventKind> {
+        match self {
+            CacheError::Context { operation, .. } => Some(*operation),
+            _ => None,
+        }
+    }
+
+    /// Whether the same operation may succeed if tried again unchanged
+    ///
+    /// Covers timeouts, interrupted or refused I/O and backend failures;
+    /// invalid input, corrupt data and unsupported operations fail the same
+    /// way every time.
+    pub fn is_retryable(&self) -> bool {
+        match self.root() {
+            CacheError::Timeout(_) | CacheError::Backend(_) => true,
+            CacheError::Io(e) => matches!(
+                e.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::TimedOut
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the failure reflects the cache's current state rather than
+    /// the operation, so it may clear up on its own
+    ///
+    /// Everything retryable is transient, and so are a full cache, which
+    /// frees up as entries expire or are removed, and an unavailable origin
+    /// store, which is tried again once it may have recovered.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self.root(),
+            CacheError::CacheFull | CacheError::Unavailable(_)
+        ) || self.is_retryable()
+    }
+}
+
+/// Attaching context to the error of a cache operation's result
+///
+/// The key is only copied when the operation failed, so successful
+/// operations don't pay for it.
+pub trait ErrorContext<T> {
+    /// The result, with the key, tier and operation attached to its error
+    fn context(self, operation: CacheEventKind, tier: Tier, key: &str) -> Result<T, CacheError>;
+}
+
+impl<T> ErrorContext<T> for Result<T, CacheError> {
+    fn context(self, operation: CacheEventKind, tier: Tier, key: &str) -> Result<T, CacheError> {
+        self.map_err(|error| error.context(operation, tier, key))
+    }
 }
@@ -0,0 +1,170 @@
+//! Structured log of cache operations
+//!
+//! An [`EventLog`] attached to a [`MetricsCache`] or a [`HybridCache`]
+//! receives a [`CacheEvent`] per operation, and one attached to a
+//! [`CircuitBreaker`] one per change of its state. The most recent events are kept
+//! in a bounded ring buffer for inspection, and every event is published to
+//! [`EventLog::subscribe`]rs as it happens.
+//!
+//! [`MetricsCache`]: crate::metrics::MetricsCache
+//! [`HybridCache`]: crate::cache::hybrid::HybridCache
+//! [`CircuitBreaker`]: crate::upstream::CircuitBreaker
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Kind of operation a [`CacheEvent`] describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheEventKind {
+    Get,
+    Set,
+    Remove,
+    /// Data displaced to make room for a write of the event's key
+    Evict,
+    /// Copied from disk into the memory tier
+    Promote,
+    /// Moved from memory to disk, or from disk to a remote tier
+    Demote,
+    /// Circuit breaker opened by a failed load of the event's key, or
+    /// reopened by a failed probe
+    BreakerOpen,
+    /// Load of the event's key let through an open circuit breaker to probe
+    /// the origin store
+    BreakerProbe,
+    /// Circuit breaker closed by a successful probe
+    BreakerClose,
+}
+
+impl std::fmt::Display for CacheEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CacheEventKind::Get => "get",
+            CacheEventKind::Set => "set",
+            CacheEventKind::Remove => "remove",
+            CacheEventKind::Evict => "evict",
+            CacheEventKind::Promote => "promote",
+            CacheEventKind::Demote => "demote",
+            CacheEventKind::BreakerOpen => "breaker_open",
+            CacheEventKind::BreakerProbe => "breaker_probe",
+            CacheEventKind::BreakerClose => "breaker_close",
+        })
+    }
+}
+
+/// How an operation ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheEventOutcome {
+    Hit,
+    Miss,
+    Ok,
+    Error,
+}
+
+impl CacheEventOutcome {
+    /// Outcome of a write or removal
+    pub(crate) fn of<T, E>(result: &Result<T, E>) -> Self {
+        if result.is_ok() {
+            Self::Ok
+        } else {
+            Self::Error
+        }
+    }
+}
+
+/// A single cache operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEvent {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    pub kind: CacheEventKind,
+    pub key: String,
+    /// Bytes read, written or moved, when known
+    pub size: Option<usize>,
+    /// Time the operation took, for operations callers wait on
+    pub latency: Option<Duration>,
+    pub outcome: CacheEventOutcome,
+}
+
+impl CacheEvent {
+    /// Event stamped with the current time
+    pub fn new(kind: CacheEventKind, key: &str, outcome: CacheEventOutcome) -> Self {
+        Self {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            kind,
+            key: key.to_string(),
+            size: None,
+            latency: None,
+            outcome,
+        }
+    }
+
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+}
+
+/// Bounded log of recent [`CacheEvent`]s with live subscribers
+#[derive(Debug)]
+pub struct EventLog {
+    capacity: usize,
+    recent: Mutex<VecDeque<CacheEvent>>,
+    sender: broadcast::Sender<CacheEvent>,
+}
+
+impl EventLog {
+    /// Keep the last `capacity` events, and let each subscriber fall up to
+    /// `capacity` events behind before it misses the oldest
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            recent: Mutex::new(VecDeque::with_capacity(capacity)),
+            sender: broadcast::channel(capacity).0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn record(&self, event: CacheEvent) {
+        {
+            let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+            if recent.len() == self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(event.clone());
+        }
+        // Without subscribers only the ring buffer keeps the event
+        let _ = self.sender.send(event);
+    }
+
+    /// Receive events recorded from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<CacheEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Buffered events, oldest first
+    pub fn recent(&self) -> Vec<CacheEvent> {
+        self.recent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
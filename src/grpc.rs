@@ -0,0 +1,417 @@
+//! Serving caches over gRPC
+//!
+//! [`CacheService`] exposes any [`Cache`] through the API defined in
+//! `proto/zarrs_cache.proto`, so sidecar deployments and clients in other
+//! languages share the same caching logic; the `zarrs-cache-server` binary
+//! (the `server` feature) runs one for a [`HybridCache`](crate::HybridCache).
+//! [`RemoteCache`] is the Rust client, itself a [`Cache`]:
+//!
+//! ```rust,no_run
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use bytes::Bytes;
+//! use zarrs_cache::{grpc::RemoteCache, Cache};
+//!
+//! let cache = RemoteCache::connect("http://127.0.0.1:50051").await?;
+//! cache.set(&"temp/0.0".to_string(), Bytes::from("chunk")).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::{Cache, CacheStats, StoreKey};
+use crate::error::CacheError;
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
+use tonic::{Code, Request, Response, Status};
+
+/// Messages of the gRPC API and its generated client and server
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetRequest {
+        #[prost(string, tag = "1")]
+        pub key: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetResponse {
+        /// Unset on a miss
+        #[prost(bytes = "bytes", optional, tag = "1")]
+        pub value: Option<bytes::Bytes>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SetRequest {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(bytes = "bytes", tag = "2")]
+        pub value: bytes::Bytes,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SetResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RemoveRequest {
+        #[prost(string, tag = "1")]
+        pub key: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct RemoveResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ClearRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ClearResponse {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StatsRequest {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StatsResponse {
+        #[prost(uint64, tag = "1")]
+        pub hits: u64,
+        #[prost(uint64, tag = "2")]
+        pub misses: u64,
+        #[prost(uint64, tag = "3")]
+        pub size_bytes: u64,
+        #[prost(uint64, tag = "4")]
+        pub entry_count: u64,
+        #[prost(uint64, tag = "5")]
+        pub accounted_bytes: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Entry {
+        #[prost(string, tag = "1")]
+        pub key: String,
+        #[prost(bytes = "bytes", tag = "2")]
+        pub value: bytes::Bytes,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WarmRequest {
+        #[prost(message, repeated, tag = "1")]
+        pub entries: Vec<Entry>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WarmResponse {
+        /// Number of entries stored
+        #[prost(uint64, tag = "1")]
+        pub warmed: u64,
+        /// Keys of the entries that could not be stored
+        #[prost(string, repeated, tag = "2")]
+        pub failed: Vec<String>,
+    }
+
+    include!(concat!(env!("OUT_DIR"), "/zarrs_cache.Cache.rs"));
+}
+
+/// Status metadata carrying the sizes of an [`CacheError::EntryTooLarge`]
+const ENTRY_SIZE_METADATA: &str = "entry-size";
+const MAX_SIZE_METADATA: &str = "max-size";
+
+/// Serves a cache over the gRPC API
+pub struct CacheService<C: Cache + ?Sized> {
+    cache: Arc<C>,
+}
+
+impl<C: Cache + ?Sized> CacheService<C> {
+    pub fn new(cache: Arc<C>) -> Self {
+        Self { cache }
+    }
+
+    /// The service, ready to add to a [`tonic::transport::Server`]
+    pub fn into_server(self) -> proto::cache_server::CacheServer<Self> {
+        proto::cache_server::CacheServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl<C: Cache + ?Sized> proto::cache_server::Cache for CacheService<C> {
+    async fn get(
+        &self,
+        request: Request<proto::GetRequest>,
+    ) -> Result<Response<proto::GetResponse>, Status> {
+        let value = self.cache.get(&request.into_inner().key).await;
+        Ok(Response::new(proto::GetResponse { value }))
+    }
+
+    async fn set(
+        &self,
+        request: Request<proto::SetRequest>,
+    ) -> Result<Response<proto::SetResponse>, Status> {
+        let request = request.into_inner();
+        self.cache
+            .set(&request.key, request.value)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(proto::SetResponse {}))
+    }
+
+    async fn remove(
+        &self,
+        request: Request<proto::RemoveRequest>,
+    ) -> Result<Response<proto::RemoveResponse>, Status> {
+        self.cache
+            .remove(&request.into_inner().key)
+            .await
+            .map_err(status)?;
+        Ok(Response::new(proto::RemoveResponse {}))
+    }
+
+    async fn clear(
+        &self,
+        _request: Request<proto::ClearRequest>,
+    ) -> Result<Response<proto::ClearResponse>, Status> {
+        self.cache.clear().await.map_err(status)?;
+        Ok(Response::new(proto::ClearResponse {}))
+    }
+
+    async fn stats(
+        &self,
+        _request: Request<proto::StatsRequest>,
+    ) -> Result<Response<proto::StatsResponse>, Status> {
+        // Stats of some caches wait on their locks
+        let cache = self.cache.clone();
+        let stats = tokio::task::spawn_blocking(move || cache.stats())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::StatsResponse {
+            hits: stats.hits,
+            misses: stats.misses,
+            size_bytes: stats.size_bytes as u64,
+            entry_count: stats.entry_count as u64,
+            accounted_bytes: stats.accounted_bytes as u64,
+        }))
+    }
+
+    async fn warm(
+        &self,
+        request: Request<proto::WarmRequest>,
+    ) -> Result<Response<proto::WarmResponse>, Status> {
+        let entries: Vec<(StoreKey, Bytes)> = request
+            .into_inner()
+            .entries
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect();
+        let keys: Vec<StoreKey> = entries.iter().map(|(key, _)| key.clone()).collect();
+        let results = self.cache.set_many(entries).await;
+
+        let mut response = proto::WarmResponse::default();
+        for (key, result) in keys.into_iter().zip(results) {
+            match result {
+                Ok(()) => response.warmed += 1,
+                Err(e) => {
+                    tracing::debug!("Failed to warm key {}: {}", key, e);
+                    response.failed.push(key);
+                }
+            }
+        }
+        Ok(Response::new(response))
+    }
+}
+
+/// gRPC status of a cache error
+fn status(error: CacheError) -> Status {
+    let message = error.to_string();
+    match error.root() {
+        CacheError::EntryTooLarge { size, max } => {
+            let mut status = Status::out_of_range(message);
+            let metadata = status.metadata_mut();
+            metadata.insert(ENTRY_SIZE_METADATA, MetadataValue::from(*size as u64));
+            metadata.insert(MAX_SIZE_METADATA, MetadataValue::from(*max as u64));
+            status
+        }
+        CacheError::InvalidKey(_) | CacheError::InvalidConfig(_) => {
+            Status::invalid_argument(message)
+        }
+        CacheError::CacheFull => Status::resource_exhausted(message),
+        CacheError::Timeout(_) => Status::deadline_exceeded(message),
+        CacheError::Corruption(_) => Status::data_loss(message),
+        CacheError::Unsupported(_) => Status::unimplemented(message),
+        CacheError::Unavailable(_) => Status::unavailable(message),
+        _ if error.is_retryable() => Status::unavailable(message),
+        _ => Status::internal(message),
+    }
+}
+
+/// Cache error of a gRPC status, the counterpart of [`status`]
+fn cache_error(status: Status) -> CacheError {
+    let metadata_size = |name: &str| {
+        status
+            .metadata()
+            .get(name)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+    };
+    match status.code() {
+        Code::OutOfRange => match (
+            metadata_size(ENTRY_SIZE_METADATA),
+            metadata_size(MAX_SIZE_METADATA),
+        ) {
+            (Some(size), Some(max)) => CacheError::EntryTooLarge { size, max },
+            _ => CacheError::InvalidKey(status.message().to_string()),
+        },
+        Code::InvalidArgument => CacheError::InvalidKey(status.message().to_string()),
+        Code::ResourceExhausted => CacheError::CacheFull,
+        Code::DeadlineExceeded => CacheError::Timeout(status.message().to_string()),
+        Code::DataLoss => CacheError::Corruption(status.message().to_string()),
+        Code::Unimplemented => CacheError::Unsupported(status.message().to_string()),
+        Code::Unavailable | Code::Unknown | Code::Aborted | Code::Cancelled => {
+            CacheError::backend(status)
+        }
+        _ => CacheError::Io(std::io::Error::other(status)),
+    }
+}
+
+/// A cache served by a [`CacheService`], reached over gRPC
+///
+/// Failed reads count as misses and are logged. [`Cache::stats`] reports
+/// the hits and misses of this client, and the sizes last fetched by
+/// [`RemoteCache::remote_stats`].
+pub struct RemoteCache {
+    client: proto::cache_client::CacheClient<Channel>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    remote: Mutex<CacheStats>,
+}
+
+impl RemoteCache {
+    /// Connect to the server at `endpoint`, e.g. `http://127.0.0.1:50051`
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self, CacheError> {
+        let client = proto::cache_client::CacheClient::connect(endpoint.into())
+            .await
+            .map_err(CacheError::backend)?;
+        Ok(Self::from_client(client))
+    }
+
+    /// A client on an existing channel, e.g. one configured with TLS or
+    /// timeouts
+    pub fn with_channel(channel: Channel) -> Self {
+        Self::from_client(proto::cache_client::CacheClient::new(channel))
+    }
+
+    fn from_client(client: proto::cache_client::CacheClient<Channel>) -> Self {
+        Self {
+            client,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            remote: Mutex::default(),
+        }
+    }
+
+    /// Statistics of the cache on the server
+    pub async fn remote_stats(&self) -> Result<CacheStats, CacheError> {
+        let response = self
+            .client
+            .clone()
+            .stats(proto::StatsRequest {})
+            .await
+            .map_err(cache_error)?
+            .into_inner();
+        let stats = CacheStats {
+            hits: response.hits,
+            misses: response.misses,
+            size_bytes: response.size_bytes as usize,
+            entry_count: response.entry_count as usize,
+            accounted_bytes: response.accounted_bytes as usize,
+            ..Default::default()
+        };
+        *self.remote.lock().unwrap_or_else(|e| e.into_inner()) = stats.clone();
+        Ok(stats)
+    }
+
+    /// Store a batch of entries in one request, returning the keys the
+    /// server failed to store
+    pub async fn warm(&self, entries: Vec<(StoreKey, Bytes)>) -> Result<Vec<StoreKey>, CacheError> {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| proto::Entry { key, value })
+            .collect();
+        let response = self
+            .client
+            .clone()
+            .warm(proto::WarmRequest { entries })
+            .await
+            .map_err(cache_error)?;
+        Ok(response.into_inner().failed)
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for RemoteCache {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let request = proto::GetRequest {
+            key: key.to_string(),
+        };
+        let value = match self.client.clone().get(request).await {
+            Ok(response) => response.into_inner().value,
+            Err(e) => {
+                tracing::warn!("Failed to get key {} from remote cache: {}", key, e);
+                None
+            }
+        };
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let request = proto::SetRequest {
+            key: key.to_string(),
+            value,
+        };
+        self.client
+            .clone()
+            .set(request)
+            .await
+            .map_err(cache_error)?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        let request = proto::RemoveRequest {
+            key: key.to_string(),
+        };
+        self.client
+            .clone()
+            .remove(request)
+            .await
+            .map_err(cache_error)?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        self.client
+            .clone()
+            .clear(proto::ClearRequest {})
+            .await
+            .map_err(cache_error)?;
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        self.remote
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .size_bytes
+    }
+
+    fn stats(&self) -> CacheStats {
+        let remote = self.remote.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..remote.clone()
+        }
+    }
+}
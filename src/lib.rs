@@ -48,16 +48,14 @@
 //!
 //! ### 🔥 Predictive Cache Warming
 //! ```rust
-//! use zarrs_cache::{CacheWarmer, WarmingStrategy, PredictiveWarming};
+//! use zarrs_cache::{CacheWarmer, PredictiveWarming};
 //! use std::sync::Arc;
 //!
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! # let cache = zarrs_cache::LruMemoryCache::new(1024);
 //! let warmer = CacheWarmer::new(Arc::new(cache))
-//!     .add_strategy(WarmingStrategy::Predictive(
-//!         PredictiveWarming::new(10, 0.8) // Warm 10 keys with 80% confidence
-//!     ));
+//!     .add_strategy(PredictiveWarming::new(10, 0.8)); // Warm 10 keys with 80% confidence
 //!
 //! // Automatically warm cache based on patterns
 //! let warmed_count = warmer.warm(|key| async move {
@@ -71,16 +69,24 @@
 //!
 //! ### 📊 Advanced Analytics & Monitoring
 //! ```rust
-//! use zarrs_cache::{MetricsCollector, MetricsConfig};
+//! use zarrs_cache::{CacheTier, LruMemoryCache, MetricsCollector, MetricsConfig};
+//! use std::sync::Arc;
 //! use std::time::Duration;
 //!
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//! let metrics = MetricsCollector::new(MetricsConfig {
-//!     track_access_patterns: true,
-//!     track_efficiency: true,
-//!     ..Default::default()
-//! });
+//! let cache = Arc::new(LruMemoryCache::new(64 * 1024 * 1024));
+//! let metrics = Arc::new(
+//!     MetricsCollector::new(MetricsConfig {
+//!         track_access_patterns: true,
+//!         track_efficiency: true,
+//!         ..Default::default()
+//!     })
+//!     .with_cache(cache.clone(), CacheTier::Memory),
+//! );
+//!
+//! // Capture a snapshot of the cache every `snapshot_interval`
+//! metrics.spawn_snapshotter();
 //!
 //! // Get comprehensive analytics
 //! let report = metrics.generate_report(Duration::from_secs(3600)).await;
@@ -130,31 +136,118 @@
 //! ## ✨ Core Features
 //!
 //! - 🚀 **LRU Memory Cache**: Lightning-fast in-memory caching with automatic eviction
+//! - 🧭 **ARC Memory Cache**: Self-tuning adaptive replacement for mixed scan + hot-spot workloads
+//! - ⏱️ **CLOCK Memory Cache**: Second-chance eviction with lock-free hit bookkeeping
+//! - 🧩 **Per-Core Shards**: Affinity-sharded memory cache for many-core NUMA nodes
+//! - 🚧 **TinyLFU Admission**: Keeps one-touch scan chunks from evicting the working set
+//! - 🌡️ **Memory Pressure**: Shrinks memory tiers when the host runs low on RAM
 //! - 💾 **Disk Cache**: Persistent storage with TTL support
-//! - 🔄 **Hybrid Tiering**: Intelligent promotion/demotion between memory and disk
+//! - 📦 **Packed Disk Cache**: Append-only segment files for millions of small chunks
+//! - 🧮 **Decoded Chunks**: Second-level cache of decoded chunks that skips codec decoding on hits
+//! - 🧱 **Shard Caching**: Shard indexes and inner chunks of sharded zarr v3 arrays cached separately
+//! - 📌 **Dataset Packs**: Small reference datasets downloaded once into a memory-mapped, read-only store
+//! - 🔄 **Hybrid Tiering**: Intelligent promotion/demotion between memory and disk, weighted by the latency class of the origin store
 //! - 🔥 **Cache Warming**: Predictive and neighbor-based preloading strategies
+//! - 🚦 **Upstream Rate Limiting**: Caps the requests and bytes per second loaded from the store
+//! - 🔁 **Load Retries**: Exponential backoff with jitter for transient store failures
+//! - 🛑 **Circuit Breaker**: Stops loading from a failing store, serving stale copies until it recovers
 //! - 📊 **Advanced Metrics**: Comprehensive performance monitoring and analytics
+//! - 🎞️ **Access Traces**: Record production accesses and replay them against any cache
+//! - 🧪 **Policy Simulator**: Projected hit rates of eviction policies and sizes from a trace
 //! - ⚡ **Async Support**: Full async/await support for non-blocking operations
 //! - 🔒 **Thread-Safe**: Safe for concurrent access across multiple threads
 
+#[cfg(feature = "admin")]
+pub mod admin;
+pub mod alerts;
 pub mod cache;
+pub mod compression;
 pub mod config;
+pub mod decoded;
 pub mod error;
+pub mod events;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod metrics;
 pub mod prefetch;
+pub mod pressure;
+pub mod registry;
+pub mod reload;
+pub mod shard;
+pub mod simulator;
+pub mod sketch;
 pub mod store;
+pub mod trace;
+pub mod units;
+pub mod upstream;
 pub mod warming;
 
 // Re-export commonly used types
-pub use cache::disk::DiskCache;
-pub use cache::hybrid::{HybridCache, HybridCacheConfig};
+pub use alerts::{AlertCondition, AlertEvent, AlertRule, AlertState};
+pub use cache::admission::{AdmissionCache, AdmissionPolicy, AlwaysAdmit, TinyLfu};
+pub use cache::arc::ArcMemoryCache;
+pub use cache::clock::ClockMemoryCache;
+pub use cache::cluster::{
+    ClusterCache, ClusterConfig, ClusterConfigBuilder, HashRing, ShardedCache, ShardedCacheConfig,
+    ShardedCacheConfigBuilder,
+};
+pub use cache::disk::{
+    DiskCache, DiskCacheConfig, DiskCacheConfigBuilder, DiskEntryInfo, DiskEvictionPolicy,
+    DiskGcReport, DiskIoBackend, DiskLayout, DiskVerifyReport,
+};
+#[cfg(feature = "redb")]
+pub use cache::embedded::{
+    EmbeddedDiskCache, EmbeddedDiskCacheConfig, EmbeddedDiskCacheConfigBuilder,
+};
+pub use cache::hybrid::{
+    HybridCache, HybridCacheConfig, HybridCacheConfigBuilder, HybridTierStats, LatencyClass,
+    RemoteTierConfig, ThresholdTuning,
+};
+#[cfg(feature = "wasm")]
+pub use cache::indexeddb::{IndexedDbCache, IndexedDbCacheConfig, IndexedDbCacheConfigBuilder};
 pub use cache::memory::LruMemoryCache;
-pub use cache::{Cache, CacheStats};
-pub use config::{CacheConfig, PrefetchConfig};
-pub use error::CacheError;
-pub use metrics::{CacheAnalyticsReport, MetricsCollector, MetricsConfig, PerformanceSnapshot};
+pub use cache::packed::{PackedDiskCache, PackedDiskCacheConfig, PackedDiskCacheConfigBuilder};
+pub use cache::sharded::ShardedMemoryCache;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::shared::{
+    SharedMemoryCache, SharedMemoryCacheConfig, SharedMemoryCacheConfigBuilder,
+};
+pub use cache::{Cache, CacheStats, MemoryPolicy, RemovalCause, RemovalCounts};
+#[cfg(feature = "snappy")]
+pub use compression::SnappyCompression;
+pub use compression::{
+    AlgorithmStats, CompressedCache, Compression, CompressionAlgorithm, CompressionStats,
+    DeflateCompression, Shuffle, ShuffleCompression,
+};
+#[cfg(feature = "zstd")]
+pub use compression::{ZstdCompression, ZstdDictionaryCompression};
+pub use config::{CacheConfig, CacheConfigBuilder, PrefetchConfig, PrefetchConfigBuilder};
+pub use decoded::{DecodedCacheStats, DecodedChunkCache, DecodedChunkKey};
+pub use error::{CacheError, ErrorContext, Tier};
+pub use events::{CacheEvent, CacheEventKind, CacheEventOutcome, EventLog};
+pub use metrics::{
+    CacheAnalyticsReport, CacheTier, EvictionStats, MetricsCache, MetricsCollector, MetricsConfig,
+    MetricsConfigBuilder, PerformanceSnapshot, RegressionThresholds, RemovalAnalysis, ReportDiff,
+    RollingStats, SizeSimulationPoint, WindowStats,
+};
 pub use prefetch::{NeighborChunkPrefetch, NoPrefetch, PrefetchStrategy, SequentialPrefetch};
+pub use pressure::{
+    MemoryPressureConfig, MemoryPressureConfigBuilder, MemoryPressureMonitor, MemoryUsage,
+    PressureLevel, PressureSignal,
+};
+pub use shard::{IndexLocation, ShardCache, ShardCacheStats, ShardIndex, ShardLayout};
+pub use simulator::{PolicySimulator, SimulatedPolicy, SimulationReport, SimulationResult};
 pub use store::cached::CachedStore;
+pub use store::listing::ListingStats;
+pub use store::pack::DatasetPack;
+pub use trace::{ReplayReport, TraceOp, TraceRecord, TraceReplayer, TraceWriter, TracedCache};
+pub use units::{ByteSize, HumanDuration};
+pub use upstream::{
+    BreakerState, CircuitBreaker, CircuitBreakerConfig, CircuitBreakerConfigBuilder,
+    CircuitBreakerStats, RateLimitConfig, RateLimitConfigBuilder, RateLimiter, RateLimiterStats,
+    RetryConfig, RetryConfigBuilder, RetryPolicy, RetryStats,
+};
 pub use warming::{
-    CacheWarmer, NeighborWarming, PredictiveWarming, TimeContext, WarmingContext, WarmingStrategy,
+    CacheWarmer, ManifestWarming, NeighborWarming, PeerWarming, PredictiveWarming, TimeContext,
+    WarmingBudget, WarmingContext, WarmingStrategy,
 };
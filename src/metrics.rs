@@ -1,8 +1,63 @@
+use crate::alerts::{AlertCondition, AlertEvent, AlertRule, AlertState, RuleState};
+use crate::cache::{entry_footprint, Cache, CacheStats, RemovalCause, RemovalCounts, StoreKey};
+use crate::compression::CompressionStats;
+use crate::config;
+use crate::error::CacheError;
+use crate::events::{CacheEvent, CacheEventKind, CacheEventOutcome, EventLog};
+use crate::sketch::{CountMinSketch, TopK};
+use crate::units::{self, HumanDuration, SetterError};
+use bytes::Bytes;
+use lru::LruCache;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock, RwLockReadGuard};
+
+/// Gini coefficient of key access counts from which accesses count as skewed
+const SKEWED_GINI: f64 = 0.4;
+
+/// Count hit rate above the byte hit rate from which large entries are
+/// reported as missing disproportionately
+const BYTE_HIT_RATE_GAP: f64 = 0.2;
+
+/// Missed keys a [`MetricsCache`] remembers while waiting for their fill
+const MAX_PENDING_FILLS: usize = 10_000;
+
+/// Longest window rolling statistics cover, in seconds
+const ROLLING_HORIZON_SECS: u64 = 15 * 60;
+
+/// Keys the shadow trace remembers, bounding the sizes it can simulate
+const SHADOW_TRACE_KEYS: usize = 10_000;
+
+/// Recent accesses the shadow trace replays
+const SHADOW_TRACE_ACCESSES: usize = 10_000;
+
+/// Memory sizes, relative to the attached memory tiers, that reports
+/// estimate the hit rate at
+const SIZE_SIMULATION_FACTORS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+/// Hit rate gain from doubling memory worth recommending
+const SIZE_SIMULATION_GAIN: f64 = 0.1;
+
+/// Compression ratio below which compressing is not worth its time
+const MIN_USEFUL_COMPRESSION_RATIO: f64 = 1.1;
+
+/// Alert events buffered for each subscriber before the oldest are dropped
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+/// Window of recent operations alert conditions are evaluated over
+const ALERT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Shards recorded counts are spread over, so recording threads rarely
+/// update the same cache line
+const RECORDER_SHARDS: usize = 16;
+
+/// Records a shard buffers before applying them to the access patterns
+const ACCESS_BUFFER_LEN: usize = 256;
 
 /// Advanced metrics collector for cache performance monitoring
 #[derive(Debug)]
@@ -15,6 +70,105 @@ pub struct MetricsCollector {
     efficiency_tracker: Arc<RwLock<EfficiencyTracker>>,
     /// Configuration for metrics collection
     config: MetricsConfig,
+    /// Caches snapshots are captured from
+    caches: Vec<AttachedCache>,
+    /// Operations since the last captured snapshot
+    snapshot_window: Arc<RwLock<SnapshotWindow>>,
+    /// Counts and access records not yet aggregated
+    shards: [RecorderShard; RECORDER_SHARDS],
+    /// Recorded operations over the last [`ROLLING_HORIZON_SECS`]
+    rolling: std::sync::Mutex<RollingCounter>,
+    /// Second of `rolling` the shards' pending counts were recorded in
+    flushed_second: AtomicU64,
+    /// Start of `rolling`'s seconds
+    started_at: Instant,
+    /// Alert rules evaluated with each snapshot
+    alert_rules: std::sync::Mutex<Vec<RuleState>>,
+    alert_sender: broadcast::Sender<AlertEvent>,
+    alert_callbacks: AlertCallbacks,
+    /// Process CPU time when the collector was created
+    cpu_time_at_start: Option<Duration>,
+}
+
+type AlertCallback = Box<dyn Fn(&AlertEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct AlertCallbacks(Vec<AlertCallback>);
+
+impl std::fmt::Debug for AlertCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AlertCallbacks({})", self.0.len())
+    }
+}
+
+/// Tier whose usage an attached cache's size is reported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheTier {
+    Memory,
+    Disk,
+}
+
+struct AttachedCache {
+    cache: Arc<dyn Cache>,
+    tier: CacheTier,
+}
+
+impl std::fmt::Debug for AttachedCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttachedCache")
+            .field("tier", &self.tier)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Activity since the last captured snapshot
+#[derive(Debug)]
+struct SnapshotWindow {
+    started_at: Instant,
+    /// Cache hits and misses when the window started
+    operations_at_start: u64,
+}
+
+/// Counters of one recorder shard, on a cache line of its own
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct RecorderShard {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    errors: AtomicU64,
+    /// Operations recorded with their response times since the last snapshot
+    timed_operations: AtomicU64,
+    response_nanos: AtomicU64,
+    /// Bytes served from cache
+    hit_bytes: AtomicU64,
+    /// Bytes fetched upstream after misses
+    miss_bytes: AtomicU64,
+    /// Records not yet applied to the access patterns, oldest first
+    pending: std::sync::Mutex<Vec<PendingRecord>>,
+}
+
+#[derive(Debug)]
+enum PendingRecord {
+    Access {
+        key: String,
+        was_hit: bool,
+        response_time: Duration,
+        at: Instant,
+    },
+    EntrySize {
+        key: String,
+        bytes: usize,
+    },
+}
+
+/// Shard of the calling thread
+fn recorder_shard() -> usize {
+    static NEXT_SHARD: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SHARD: usize = NEXT_SHARD.fetch_add(1, Ordering::Relaxed) % RECORDER_SHARDS;
+    }
+    SHARD.with(|shard| *shard)
 }
 
 /// Configuration for metrics collection
@@ -24,16 +178,24 @@ pub struct MetricsCollector {
 /// - `snapshot_interval`: 60 seconds
 /// - `track_access_patterns`: true
 /// - `track_efficiency`: true
+/// - `max_tracked_keys`: 1000
+///
+/// Settings missing from a deserialized configuration take these defaults.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MetricsConfig {
     /// Maximum number of performance snapshots to keep
     pub max_history_size: usize,
     /// Interval between automatic snapshots
+    #[serde(deserialize_with = "units::deserialize_duration")]
     pub snapshot_interval: Duration,
     /// Enable detailed access pattern tracking
     pub track_access_patterns: bool,
     /// Enable cache efficiency analysis
     pub track_efficiency: bool,
+    /// Most frequently accessed keys kept by name for access statistics;
+    /// other keys are only counted in a fixed-size sketch
+    pub max_tracked_keys: usize,
 }
 
 impl Default for MetricsConfig {
@@ -43,7 +205,97 @@ impl Default for MetricsConfig {
             snapshot_interval: Duration::from_secs(60),
             track_access_patterns: true,
             track_efficiency: true,
+            max_tracked_keys: 1000,
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> MetricsConfigBuilder {
+        MetricsConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.max_history_size == 0 {
+            return invalid("max_history_size must be greater than zero");
+        }
+        if self.snapshot_interval.is_zero() {
+            return invalid("snapshot_interval must be greater than zero");
+        }
+        if self.max_tracked_keys == 0 {
+            return invalid("max_tracked_keys must be greater than zero");
         }
+        Ok(())
+    }
+
+    /// Load the configuration from a file, as
+    /// [`CacheConfig::from_file`](crate::CacheConfig::from_file) does, with
+    /// `ZARRS_CACHE_METRICS_<SETTING>` overrides
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        config::read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with `ZARRS_CACHE_METRICS_<SETTING>` overrides
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among `vars`, as
+    /// [`CacheConfig::override_from`](crate::CacheConfig::override_from) does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        config::apply_overrides(&self, config::METRICS_ENV_PREFIX, vars)
+    }
+}
+
+/// Builder for [`MetricsConfig`], starting from its defaults
+///
+/// [`build`](MetricsConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsConfigBuilder {
+    config: MetricsConfig,
+    invalid: SetterError,
+}
+
+impl MetricsConfigBuilder {
+    pub fn max_history_size(mut self, snapshots: usize) -> Self {
+        self.config.max_history_size = snapshots;
+        self
+    }
+
+    pub fn snapshot_interval(mut self, interval: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(interval.to_duration()) {
+            self.config.snapshot_interval = duration;
+        }
+        self
+    }
+
+    pub fn track_access_patterns(mut self, enabled: bool) -> Self {
+        self.config.track_access_patterns = enabled;
+        self
+    }
+
+    pub fn track_efficiency(mut self, enabled: bool) -> Self {
+        self.config.track_efficiency = enabled;
+        self
+    }
+
+    pub fn max_tracked_keys(mut self, keys: usize) -> Self {
+        self.config.max_tracked_keys = keys;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<MetricsConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
     }
 }
 
@@ -60,26 +312,107 @@ pub struct PerformanceSnapshot {
     pub average_response_time_ms: f64,
     pub memory_usage_bytes: usize,
     pub disk_usage_bytes: usize,
+    /// Bytes served from cache so far
+    #[serde(default)]
+    pub hit_bytes: u64,
+    /// Bytes fetched upstream after misses so far
+    #[serde(default)]
+    pub miss_bytes: u64,
+    /// Share of bytes served from cache, 0.0 when none were recorded
+    #[serde(default)]
+    pub byte_hit_rate: f64,
 }
 
 /// Access pattern analysis data
 #[derive(Debug)]
 struct AccessPatternAnalyzer {
-    /// Key access frequencies
-    key_frequencies: HashMap<String, KeyAccessInfo>,
+    /// Access counts, by name for the most frequent keys
+    key_frequencies: TopK,
+    /// Hit counts of every key
+    key_hits: CountMinSketch,
     /// Temporal access patterns
     temporal_patterns: VecDeque<TemporalAccess>,
     /// Spatial locality analysis (for zarr chunks)
     spatial_locality: SpatialLocalityTracker,
+    /// Recency of keys for hit rate estimates at other sizes
+    shadow: ShadowTrace,
 }
 
-#[derive(Debug, Clone)]
-struct KeyAccessInfo {
-    total_accesses: u64,
-    last_access: Instant,
-    access_intervals: VecDeque<Duration>,
-    cache_hits: u64,
-    cache_misses: u64,
+/// Recently accessed keys in LRU order with their sizes
+///
+/// Each access is stored with its reuse distance: the bytes of the distinct
+/// keys accessed since the previous access of the same key, including its
+/// own. An LRU cache of any size at least that distance would have hit, so
+/// the distances give the hit rate of every size at once.
+struct ShadowTrace {
+    /// Entry sizes, most recently accessed first; 0 until a size is recorded
+    entries: LruCache<String, usize>,
+    /// Reuse distances of recent accesses, `None` for first accesses
+    distances: VecDeque<Option<usize>>,
+}
+
+impl std::fmt::Debug for ShadowTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShadowTrace")
+            .field("keys", &self.entries.len())
+            .field("accesses", &self.distances.len())
+            .finish()
+    }
+}
+
+impl ShadowTrace {
+    fn new() -> Self {
+        Self {
+            entries: LruCache::new(
+                std::num::NonZeroUsize::new(SHADOW_TRACE_KEYS).expect("non-zero capacity"),
+            ),
+            distances: VecDeque::new(),
+        }
+    }
+
+    fn access(&mut self, key: &str) {
+        let mut distance = 0;
+        let mut reused = None;
+        for (candidate, size) in self.entries.iter() {
+            distance += size;
+            if candidate == key {
+                reused = Some(distance);
+                break;
+            }
+        }
+        match reused {
+            Some(_) => {
+                self.entries.promote(key);
+            }
+            None => {
+                self.entries.put(key.to_string(), 0);
+            }
+        }
+
+        self.distances.push_back(reused);
+        if self.distances.len() > SHADOW_TRACE_ACCESSES {
+            self.distances.pop_front();
+        }
+    }
+
+    /// Account `key` like the memory caches do, per-entry overhead included
+    fn set_size(&mut self, key: &str, bytes: usize) {
+        self.entries
+            .put(key.to_string(), entry_footprint(key, bytes));
+    }
+
+    /// Share of recent accesses an LRU cache of `size_bytes` would have hit
+    fn hit_rate(&self, size_bytes: usize) -> f64 {
+        if self.distances.is_empty() {
+            return 0.0;
+        }
+        let hits = self
+            .distances
+            .iter()
+            .filter(|distance| distance.is_some_and(|distance| distance <= size_bytes))
+            .count();
+        hits as f64 / self.distances.len() as f64
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -93,8 +426,6 @@ struct TemporalAccess {
 
 #[derive(Debug)]
 struct SpatialLocalityTracker {
-    /// Track chunk coordinate access patterns
-    chunk_accesses: HashMap<ChunkCoordinate, u64>,
     /// Recent access sequence for locality analysis
     recent_sequence: VecDeque<ChunkCoordinate>,
 }
@@ -114,6 +445,10 @@ struct EfficiencyTracker {
     warming_stats: WarmingStats,
     /// Resource utilization
     resource_utilization: ResourceUtilization,
+    /// Whether bounded memory and disk tiers were measured
+    bounded_tiers: (bool, bool),
+    /// Data pushed out by writes
+    eviction_stats: EvictionStats,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -133,11 +468,24 @@ pub struct WarmingStats {
     pub warming_efficiency: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Data displaced from a cache to make room for writes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvictionStats {
+    /// Writes that displaced cached data
+    pub evictions: u64,
+    pub evicted_bytes: u64,
+}
+
+/// Resources used by the attached caches, as of the last snapshot
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceUtilization {
+    /// Share of the bounded memory tiers' capacity in use
     pub memory_utilization: f64,
+    /// Share of the bounded disk tiers' capacity in use
     pub disk_utilization: f64,
+    /// CPU time used by the process since the collector was created
     pub cpu_time_ms: u64,
+    /// Entry file reads, writes and deletions of the attached caches
     pub io_operations: u64,
 }
 
@@ -149,16 +497,193 @@ pub struct CacheAnalyticsReport {
     pub performance_summary: PerformanceSummary,
     pub access_patterns: AccessPatternSummary,
     pub efficiency_analysis: EfficiencyAnalysis,
+    /// Why entries left the attached caches
+    #[serde(default)]
+    pub removals: RemovalAnalysis,
+    /// Estimated hit rate at multiples of the attached memory tier size,
+    /// empty without a bounded memory tier or recorded accesses
+    #[serde(default)]
+    pub size_simulation: Vec<SizeSimulationPoint>,
+    /// Compression work of the attached caches, empty if none compresses
+    #[serde(default)]
+    pub compression: CompressionStats,
     pub recommendations: Vec<OptimizationRecommendation>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PerformanceSummary {
     pub average_hit_rate: f64,
+    /// Average byte hit rate of the snapshots that recorded bytes
+    #[serde(default)]
+    pub average_byte_hit_rate: f64,
     pub peak_hit_rate: f64,
     pub average_response_time_ms: f64,
     pub throughput_ops_per_second: f64,
     pub cache_size_trend: String, // "increasing", "decreasing", "stable"
+    /// Recorded operations over the last 1, 5 and 15 minutes
+    #[serde(default)]
+    pub recent: RollingStats,
+}
+
+/// Recorded operations over a recent period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowStats {
+    pub window: Duration,
+    pub hits: u64,
+    pub misses: u64,
+    /// 0.0 when no operations were recorded
+    pub hit_rate: f64,
+    /// Operations per second over the part of the window since recording
+    /// started
+    pub operations_per_second: f64,
+    /// Failed operations, recorded with [`MetricsCollector::record_error`]
+    #[serde(default)]
+    pub errors: u64,
+}
+
+impl WindowStats {
+    /// Share of hits, misses and errors that were errors, 0.0 without any
+    pub fn error_rate(&self) -> f64 {
+        let total = self.hits + self.misses + self.errors;
+        if total > 0 {
+            self.errors as f64 / total as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Recorded operations over the last 1, 5 and 15 minutes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RollingStats {
+    pub last_1m: WindowStats,
+    pub last_5m: WindowStats,
+    pub last_15m: WindowStats,
+}
+
+/// Hits, misses and errors per second over the last
+/// [`ROLLING_HORIZON_SECS`]
+#[derive(Debug)]
+pub(crate) struct RollingCounter {
+    started_at: Instant,
+    /// Oldest first
+    buckets: VecDeque<RollingBucket>,
+}
+
+#[derive(Debug)]
+struct RollingBucket {
+    /// Second since the counter started
+    second: u64,
+    hits: u64,
+    misses: u64,
+    errors: u64,
+}
+
+impl RollingCounter {
+    pub(crate) fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    fn starting_at(started_at: Instant) -> Self {
+        Self {
+            started_at,
+            buckets: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, was_hit: bool) {
+        self.record_at(self.started_at.elapsed().as_secs(), was_hit);
+    }
+
+    /// Add counts recorded in `second`, which must not precede earlier ones
+    fn add_at(&mut self, second: u64, hits: u64, misses: u64, errors: u64) {
+        if hits + misses + errors == 0 {
+            return;
+        }
+        let bucket = self.bucket_at(second);
+        bucket.hits += hits;
+        bucket.misses += misses;
+        bucket.errors += errors;
+    }
+
+    fn record_at(&mut self, second: u64, was_hit: bool) {
+        let bucket = self.bucket_at(second);
+        if was_hit {
+            bucket.hits += 1;
+        } else {
+            bucket.misses += 1;
+        }
+    }
+
+    /// Bucket of `second`, dropping those past the horizon
+    fn bucket_at(&mut self, second: u64) -> &mut RollingBucket {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|bucket| bucket.second + ROLLING_HORIZON_SECS <= second)
+        {
+            self.buckets.pop_front();
+        }
+        if self
+            .buckets
+            .back()
+            .is_none_or(|bucket| bucket.second != second)
+        {
+            self.buckets.push_back(RollingBucket {
+                second,
+                hits: 0,
+                misses: 0,
+                errors: 0,
+            });
+        }
+        self.buckets.back_mut().expect("bucket just ensured")
+    }
+
+    /// Operations over the last `window`, in whole seconds up to
+    /// [`ROLLING_HORIZON_SECS`]
+    pub(crate) fn window(&self, window: Duration) -> WindowStats {
+        self.window_at(self.started_at.elapsed().as_secs(), window)
+    }
+
+    fn window_at(&self, second: u64, window: Duration) -> WindowStats {
+        let window_secs = window.as_secs().clamp(1, ROLLING_HORIZON_SECS);
+        let (hits, misses, errors) = self
+            .buckets
+            .iter()
+            .rev()
+            .take_while(|bucket| bucket.second + window_secs > second)
+            .fold((0, 0, 0), |(hits, misses, errors), bucket| {
+                (
+                    hits + bucket.hits,
+                    misses + bucket.misses,
+                    errors + bucket.errors,
+                )
+            });
+        let operations = hits + misses;
+        // The current second counts, so a fresh counter covers one second
+        let covered = window_secs.min(second + 1);
+
+        WindowStats {
+            window: Duration::from_secs(window_secs),
+            hits,
+            misses,
+            hit_rate: if operations > 0 {
+                hits as f64 / operations as f64
+            } else {
+                0.0
+            },
+            operations_per_second: operations as f64 / covered as f64,
+            errors,
+        }
+    }
+
+    pub(crate) fn stats(&self) -> RollingStats {
+        RollingStats {
+            last_1m: self.window(Duration::from_secs(60)),
+            last_5m: self.window(Duration::from_secs(5 * 60)),
+            last_15m: self.window(Duration::from_secs(15 * 60)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -166,7 +691,11 @@ pub struct AccessPatternSummary {
     pub most_accessed_keys: Vec<(String, u64)>,
     pub temporal_hotspots: Vec<String>, // Time periods with high activity
     pub spatial_locality_score: f64,
-    pub access_distribution: String, // "uniform", "skewed", "clustered"
+    pub access_distribution: String, // "uniform", "skewed", "clustered", "unknown"
+    /// Gini coefficient of per-key access counts, from 0.0 (every key
+    /// accessed equally) towards 1.0 (all accesses to one key)
+    #[serde(default)]
+    pub access_skew: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -174,9 +703,77 @@ pub struct EfficiencyAnalysis {
     pub promotion_effectiveness: f64,
     pub warming_effectiveness: f64,
     pub resource_efficiency: f64,
+    #[serde(default)]
+    pub resource_utilization: ResourceUtilization,
     pub bottleneck_analysis: Vec<String>,
 }
 
+/// Hit rate an LRU memory tier of a given size would have had
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeSimulationPoint {
+    /// Size relative to the attached memory tiers
+    pub size_factor: f64,
+    pub memory_size_bytes: usize,
+    /// Share of recent recorded accesses that would have hit
+    pub estimated_hit_rate: f64,
+}
+
+/// Entries that left the attached caches, per tier and cause
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemovalAnalysis {
+    pub memory: RemovalCounts,
+    pub disk: RemovalCounts,
+}
+
+/// Limits beyond which [`CacheAnalyticsReport::diff_with`] flags a regression
+///
+/// # Default Values
+///
+/// - `max_hit_rate_drop`: 0.05
+/// - `max_byte_hit_rate_drop`: 0.05
+/// - `max_response_time_increase`: 0.2 (20%)
+/// - `max_locality_drop`: 0.1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RegressionThresholds {
+    /// Largest tolerated fall in average hit rate
+    pub max_hit_rate_drop: f64,
+    /// Largest tolerated fall in average byte hit rate
+    pub max_byte_hit_rate_drop: f64,
+    /// Largest tolerated rise in average response time, as a fraction of
+    /// the baseline's
+    pub max_response_time_increase: f64,
+    /// Largest tolerated fall in spatial locality score
+    pub max_locality_drop: f64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            max_hit_rate_drop: 0.05,
+            max_byte_hit_rate_drop: 0.05,
+            max_response_time_increase: 0.2,
+            max_locality_drop: 0.1,
+        }
+    }
+}
+
+/// Change from a baseline report to a newer one
+///
+/// Each delta is the newer value minus the baseline's.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    pub hit_rate_delta: f64,
+    pub byte_hit_rate_delta: f64,
+    pub response_time_delta_ms: f64,
+    pub throughput_delta: f64,
+    pub locality_delta: f64,
+    pub access_skew_delta: f64,
+    /// Whether any change exceeded its threshold
+    pub regression: bool,
+    /// One description per exceeded threshold
+    pub regressions: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OptimizationRecommendation {
     pub category: String,
@@ -188,22 +785,405 @@ pub struct OptimizationRecommendation {
 impl MetricsCollector {
     /// Create a new metrics collector
     pub fn new(config: MetricsConfig) -> Self {
+        let started_at = Instant::now();
         Self {
             performance_history: Arc::new(RwLock::new(VecDeque::new())),
-            access_patterns: Arc::new(RwLock::new(AccessPatternAnalyzer::new())),
+            access_patterns: Arc::new(RwLock::new(AccessPatternAnalyzer::new(
+                config.max_tracked_keys,
+            ))),
             efficiency_tracker: Arc::new(RwLock::new(EfficiencyTracker::new())),
             config,
+            caches: Vec::new(),
+            snapshot_window: Arc::new(RwLock::new(SnapshotWindow {
+                started_at: Instant::now(),
+                operations_at_start: 0,
+            })),
+            shards: std::array::from_fn(|_| RecorderShard::default()),
+            rolling: std::sync::Mutex::new(RollingCounter::starting_at(started_at)),
+            flushed_second: AtomicU64::new(0),
+            started_at,
+            alert_rules: std::sync::Mutex::new(Vec::new()),
+            alert_sender: broadcast::channel(ALERT_CHANNEL_CAPACITY).0,
+            alert_callbacks: AlertCallbacks::default(),
+            cpu_time_at_start: process_cpu_time(),
+        }
+    }
+
+    /// Capture snapshots from `cache`, reporting its size as `tier` usage
+    ///
+    /// Attach every tier of a deployment; their statistics are summed.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>, tier: CacheTier) -> Self {
+        self.caches.push(AttachedCache { cache, tier });
+        self
+    }
+
+    /// Evaluate `rule` with every captured snapshot
+    pub fn with_alert_rule(mut self, rule: AlertRule) -> Self {
+        self.alert_rules
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(RuleState::new(rule));
+        self
+    }
+
+    /// Call `callback` with every alert event
+    ///
+    /// Callbacks run on the task capturing the snapshot and should not block.
+    pub fn with_alert_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&AlertEvent) + Send + Sync + 'static,
+    {
+        self.alert_callbacks.0.push(Box::new(callback));
+        self
+    }
+
+    /// Receive alert events published from now on
+    ///
+    /// A subscriber lagging more than 64 events behind misses the oldest.
+    pub fn subscribe_alerts(&self) -> broadcast::Receiver<AlertEvent> {
+        self.alert_sender.subscribe()
+    }
+
+    /// Build a snapshot from the attached caches and record it
+    ///
+    /// Operations per second come from the change in hits and misses since
+    /// the previous snapshot, and the response time is the average of the
+    /// operations recorded in between. Alert rules are evaluated afterwards.
+    pub async fn capture_snapshot(&self) -> PerformanceSnapshot {
+        let mut hits = 0;
+        let mut misses = 0;
+        let mut entry_count = 0;
+        let mut memory_usage_bytes = 0;
+        let mut disk_usage_bytes = 0;
+        // Usage and capacity of the bounded memory and disk tiers
+        let mut bounded_memory = (0, 0);
+        let mut bounded_disk = (0, 0);
+        let mut io_operations = 0;
+        for attached in &self.caches {
+            let stats = attached.cache.stats();
+            hits += stats.hits;
+            misses += stats.misses;
+            entry_count += stats.entry_count;
+            io_operations += attached.cache.io_operations().unwrap_or(0);
+            match attached.tier {
+                CacheTier::Memory => {
+                    memory_usage_bytes += stats.size_bytes;
+                    // Memory budgets are enforced against accounted bytes
+                    if let Some(capacity) = attached.cache.capacity() {
+                        bounded_memory.0 += stats.accounted_bytes;
+                        bounded_memory.1 += capacity;
+                    }
+                }
+                CacheTier::Disk => {
+                    disk_usage_bytes += stats.size_bytes;
+                    if let Some(capacity) = attached.cache.capacity() {
+                        bounded_disk.0 += stats.size_bytes;
+                        bounded_disk.1 += capacity;
+                    }
+                }
+            }
+        }
+        let operations = hits + misses;
+
+        let (operations_per_second, average_response_time_ms) = {
+            let mut window = self.snapshot_window.write().await;
+            let elapsed = window.started_at.elapsed().as_secs_f64();
+            let operations_per_second = if elapsed > 0.0 {
+                operations.saturating_sub(window.operations_at_start) as f64 / elapsed
+            } else {
+                0.0
+            };
+            let (timed_operations, response_nanos) =
+                self.shards
+                    .iter()
+                    .fold((0, 0), |(operations, nanos), shard| {
+                        (
+                            operations + shard.timed_operations.swap(0, Ordering::Relaxed),
+                            nanos + shard.response_nanos.swap(0, Ordering::Relaxed),
+                        )
+                    });
+            let average_response_time_ms = if timed_operations > 0 {
+                response_nanos as f64 / 1e6 / timed_operations as f64
+            } else {
+                0.0
+            };
+            *window = SnapshotWindow {
+                started_at: Instant::now(),
+                operations_at_start: operations,
+            };
+            (operations_per_second, average_response_time_ms)
+        };
+        let (hit_bytes, miss_bytes) = self.shards.iter().fold((0, 0), |(hit, miss), shard| {
+            (
+                hit + shard.hit_bytes.load(Ordering::Relaxed),
+                miss + shard.miss_bytes.load(Ordering::Relaxed),
+            )
+        });
+
+        let snapshot = PerformanceSnapshot {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            hits,
+            misses,
+            hit_rate: if operations > 0 {
+                hits as f64 / operations as f64
+            } else {
+                0.0
+            },
+            total_size_bytes: memory_usage_bytes + disk_usage_bytes,
+            entry_count,
+            operations_per_second,
+            average_response_time_ms,
+            memory_usage_bytes,
+            disk_usage_bytes,
+            hit_bytes,
+            miss_bytes,
+            byte_hit_rate: if hit_bytes + miss_bytes > 0 {
+                hit_bytes as f64 / (hit_bytes + miss_bytes) as f64
+            } else {
+                0.0
+            },
+        };
+        self.record_snapshot(snapshot.clone()).await;
+
+        let utilization = |(used, capacity): (usize, usize)| {
+            (capacity > 0).then(|| used as f64 / capacity as f64)
+        };
+        let memory_utilization = utilization(bounded_memory);
+        let disk_utilization = utilization(bounded_disk);
+        if self.config.track_efficiency {
+            let cpu_time = match (self.cpu_time_at_start, process_cpu_time()) {
+                (Some(start), Some(now)) => now.saturating_sub(start),
+                _ => Duration::ZERO,
+            };
+            let mut efficiency = self.efficiency_tracker.write().await;
+            efficiency.resource_utilization = ResourceUtilization {
+                memory_utilization: memory_utilization.unwrap_or(0.0),
+                disk_utilization: disk_utilization.unwrap_or(0.0),
+                cpu_time_ms: cpu_time.as_millis() as u64,
+                io_operations,
+            };
+            efficiency.bounded_tiers = (memory_utilization.is_some(), disk_utilization.is_some());
+        }
+        self.evaluate_alerts(snapshot.timestamp, disk_utilization)
+            .await;
+        snapshot
+    }
+
+    /// Update the alert rules and publish their transitions
+    async fn evaluate_alerts(&self, timestamp: u64, disk_utilization: Option<f64>) {
+        let recent = self.window_stats(ALERT_WINDOW).await;
+        let now = Instant::now();
+
+        let events: Vec<AlertEvent> = {
+            let mut rules = self.alert_rules.lock().unwrap_or_else(|e| e.into_inner());
+            rules
+                .iter_mut()
+                .filter_map(|state| {
+                    let value = match state.rule.condition {
+                        AlertCondition::HitRateBelow(_) => {
+                            (recent.hits + recent.misses > 0).then_some(recent.hit_rate)
+                        }
+                        AlertCondition::DiskUtilizationAbove(_) => disk_utilization,
+                        AlertCondition::ErrorRateAbove(_) => Some(recent.error_rate()),
+                    };
+                    let transition = state.observe(value, now)?;
+                    Some(AlertEvent {
+                        rule: state.rule.name.clone(),
+                        state: transition,
+                        condition: state.rule.condition,
+                        value: value.unwrap_or_default(),
+                        timestamp,
+                    })
+                })
+                .collect()
+        };
+
+        for event in events {
+            match event.state {
+                AlertState::Firing => tracing::warn!(
+                    "Alert {} firing: {:?} at {:.3}",
+                    event.rule,
+                    event.condition,
+                    event.value
+                ),
+                AlertState::Resolved => tracing::info!("Alert {} resolved", event.rule),
+            }
+            for callback in &self.alert_callbacks.0 {
+                callback(&event);
+            }
+            // Without subscribers there is nobody to deliver to
+            let _ = self.alert_sender.send(event);
         }
     }
 
+    /// Spawn a background task that calls
+    /// [`MetricsCollector::capture_snapshot`] every
+    /// [`MetricsConfig::snapshot_interval`]
+    ///
+    /// The task holds only a weak reference and exits once the collector is
+    /// dropped.
+    pub fn spawn_snapshotter(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let collector = Arc::downgrade(self);
+        let interval = self.config.snapshot_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick completes immediately
+
+            loop {
+                ticker.tick().await;
+                let Some(collector) = collector.upgrade() else {
+                    break;
+                };
+                collector.capture_snapshot().await;
+            }
+        })
+    }
+
     /// Record a cache operation for metrics
+    ///
+    /// Counts go to atomics of the calling thread's shard, aggregated once a
+    /// second and when read. The access itself is buffered and applied to
+    /// the access patterns in batches.
     pub async fn record_operation(&self, key: &str, was_hit: bool, response_time: Duration) {
+        let shard = self.shard();
+        let counter = if was_hit { &shard.hits } else { &shard.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        shard.timed_operations.fetch_add(1, Ordering::Relaxed);
+        shard
+            .response_nanos
+            .fetch_add(response_time.as_nanos() as u64, Ordering::Relaxed);
+
         if self.config.track_access_patterns {
-            let mut patterns = self.access_patterns.write().await;
-            patterns.record_access(key, was_hit, response_time);
+            let record = PendingRecord::Access {
+                key: key.to_string(),
+                was_hit,
+                response_time,
+                at: Instant::now(),
+            };
+            self.buffer_record(shard, record).await;
+        }
+    }
+
+    /// Shard of the calling thread, once counts of earlier seconds are
+    /// aggregated
+    fn shard(&self) -> &RecorderShard {
+        let now = self.rolling_second();
+        if now > self.flushed_second.load(Ordering::Acquire) {
+            self.flush_counts(now);
+        }
+        &self.shards[recorder_shard()]
+    }
+
+    fn rolling_second(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+
+    /// Move the shards' pending counts into the rolling counter
+    ///
+    /// Pending counts were all recorded in `flushed_second`: recording in a
+    /// later second flushes before counting.
+    fn flush_counts(&self, now: u64) {
+        let mut rolling = self.rolling.lock().unwrap_or_else(|e| e.into_inner());
+        let second = self.flushed_second.load(Ordering::Acquire);
+        let (hits, misses, errors) =
+            self.shards
+                .iter()
+                .fold((0, 0, 0), |(hits, misses, errors), shard| {
+                    (
+                        hits + shard.hits.swap(0, Ordering::Relaxed),
+                        misses + shard.misses.swap(0, Ordering::Relaxed),
+                        errors + shard.errors.swap(0, Ordering::Relaxed),
+                    )
+                });
+        rolling.add_at(second, hits, misses, errors);
+        if now > second {
+            self.flushed_second.store(now, Ordering::Release);
         }
     }
 
+    /// Buffer `record` in `shard`, applying the shard's buffer to the access
+    /// patterns once it is full
+    async fn buffer_record(&self, shard: &RecorderShard, record: PendingRecord) {
+        let full = {
+            let mut pending = shard.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(record);
+            (pending.len() >= ACCESS_BUFFER_LEN).then(|| std::mem::take(&mut *pending))
+        };
+        if let Some(records) = full {
+            self.access_patterns.write().await.apply(records);
+        }
+    }
+
+    /// Access patterns with every buffered record applied
+    async fn access_patterns(&self) -> RwLockReadGuard<'_, AccessPatternAnalyzer> {
+        let mut patterns = self.access_patterns.write().await;
+        for shard in &self.shards {
+            let records =
+                std::mem::take(&mut *shard.pending.lock().unwrap_or_else(|e| e.into_inner()));
+            patterns.apply(records);
+        }
+        patterns.downgrade()
+    }
+
+    /// Rolling counter with every pending count aggregated
+    fn flushed_rolling(&self) -> std::sync::MutexGuard<'_, RollingCounter> {
+        self.flush_counts(self.rolling_second());
+        self.rolling.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Recorded operations over the last 1, 5 and 15 minutes
+    ///
+    /// Unlike snapshots, which carry the attached caches' lifetime counters,
+    /// these reflect current behavior only.
+    pub async fn rolling_stats(&self) -> RollingStats {
+        self.flushed_rolling().stats()
+    }
+
+    /// Recorded operations over the last `window`, up to 15 minutes
+    pub async fn window_stats(&self, window: Duration) -> WindowStats {
+        self.flushed_rolling().window(window)
+    }
+
+    /// Record the size of the value cached under `key`, for
+    /// [`MetricsCollector::hit_rate_curve`]
+    ///
+    /// Counts as a use of `key` in the simulated caches, like a write, but
+    /// not as an access.
+    pub async fn record_entry_size(&self, key: &str, bytes: usize) {
+        if self.config.track_access_patterns {
+            let record = PendingRecord::EntrySize {
+                key: key.to_string(),
+                bytes,
+            };
+            self.buffer_record(self.shard(), record).await;
+        }
+    }
+
+    /// Record a failed cache operation, for the error rate of
+    /// [`AlertCondition::ErrorRateAbove`]
+    pub async fn record_error(&self) {
+        self.shard().errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record `bytes` served from the cache, or fetched upstream after a miss
+    ///
+    /// Snapshots report the share of bytes served from cache next to the
+    /// request hit rate, which misrepresents bandwidth savings when entry
+    /// sizes vary.
+    pub fn record_bytes(&self, was_hit: bool, bytes: usize) {
+        let shard = &self.shards[recorder_shard()];
+        let counter = if was_hit {
+            &shard.hit_bytes
+        } else {
+            &shard.miss_bytes
+        };
+        counter.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
     /// Record a performance snapshot
     pub async fn record_snapshot(&self, snapshot: PerformanceSnapshot) {
         let mut history = self.performance_history.write().await;
@@ -227,6 +1207,34 @@ impl MetricsCollector {
         }
     }
 
+    /// Promotion counts and accuracy recorded so far
+    pub async fn promotion_stats(&self) -> PromotionStats {
+        self.efficiency_tracker.read().await.promotion_stats.clone()
+    }
+
+    /// Record a write that displaced `evicted_bytes` of cached data
+    pub async fn record_eviction(&self, evicted_bytes: usize) {
+        if self.config.track_efficiency {
+            let mut efficiency = self.efficiency_tracker.write().await;
+            efficiency.eviction_stats.evictions += 1;
+            efficiency.eviction_stats.evicted_bytes += evicted_bytes as u64;
+        }
+    }
+
+    /// Eviction counts recorded so far
+    pub async fn eviction_stats(&self) -> EvictionStats {
+        self.efficiency_tracker.read().await.eviction_stats.clone()
+    }
+
+    /// Resource usage measured by the last captured snapshot
+    pub async fn resource_utilization(&self) -> ResourceUtilization {
+        self.efficiency_tracker
+            .read()
+            .await
+            .resource_utilization
+            .clone()
+    }
+
     /// Record cache warming event
     pub async fn record_warming(&self, keys_warmed: u64, subsequent_hits: u64) {
         if self.config.track_efficiency {
@@ -245,16 +1253,23 @@ impl MetricsCollector {
     /// Generate comprehensive analytics report
     pub async fn generate_report(&self, time_range: Duration) -> CacheAnalyticsReport {
         let history = self.performance_history.read().await;
-        let patterns = self.access_patterns.read().await;
+        let patterns = self.access_patterns().await;
         let efficiency = self.efficiency_tracker.read().await;
 
-        let performance_summary = self.analyze_performance(&history, time_range);
+        let mut performance_summary = self.analyze_performance(&history, time_range);
+        performance_summary.recent = self.rolling_stats().await;
         let access_patterns_summary = patterns.analyze_patterns();
         let efficiency_analysis = efficiency.analyze_efficiency();
+        let removals = self.analyze_removals();
+        let size_simulation = self.simulate_sizes(&patterns.shadow);
+        let compression = self.analyze_compression();
         let recommendations = self.generate_recommendations(
             &performance_summary,
             &access_patterns_summary,
             &efficiency_analysis,
+            &removals,
+            &size_simulation,
+            &compression,
         );
 
         CacheAnalyticsReport {
@@ -266,20 +1281,127 @@ impl MetricsCollector {
             performance_summary,
             access_patterns: access_patterns_summary,
             efficiency_analysis,
+            removals,
+            size_simulation,
+            compression,
             recommendations,
         }
     }
 
+    /// Estimated hit rate at 0.5, 1, 2 and 4 times the attached
+    /// memory tier size
+    ///
+    /// Replays recently recorded accesses against an LRU cache of each size,
+    /// with entry sizes from [`MetricsCollector::record_entry_size`]. Keys
+    /// without a recorded size count as empty, and sizes holding more than
+    /// the last 10,000 keys are underestimated.
+    pub async fn hit_rate_curve(&self) -> Vec<SizeSimulationPoint> {
+        self.simulate_sizes(&self.access_patterns().await.shadow)
+    }
+
+    fn simulate_sizes(&self, shadow: &ShadowTrace) -> Vec<SizeSimulationPoint> {
+        let memory_size: usize = self
+            .caches
+            .iter()
+            .filter(|attached| attached.tier == CacheTier::Memory)
+            .filter_map(|attached| attached.cache.capacity())
+            .sum();
+        if memory_size == 0 || shadow.distances.is_empty() {
+            return Vec::new();
+        }
+
+        SIZE_SIMULATION_FACTORS
+            .iter()
+            .map(|&size_factor| {
+                let memory_size_bytes = (memory_size as f64 * size_factor) as usize;
+                SizeSimulationPoint {
+                    size_factor,
+                    memory_size_bytes,
+                    estimated_hit_rate: shadow.hit_rate(memory_size_bytes),
+                }
+            })
+            .collect()
+    }
+
+    /// Removal counts of the attached caches, summed per tier
+    fn analyze_removals(&self) -> RemovalAnalysis {
+        let mut removals = RemovalAnalysis::default();
+        for attached in &self.caches {
+            let counts = attached.cache.stats().removals;
+            match attached.tier {
+                CacheTier::Memory => removals.memory += counts,
+                CacheTier::Disk => removals.disk += counts,
+            }
+        }
+        removals
+    }
+
+    /// Compression work of the attached caches, summed per algorithm
+    fn analyze_compression(&self) -> CompressionStats {
+        let mut compression = CompressionStats::default();
+        for attached in &self.caches {
+            compression += attached.cache.stats().compression;
+        }
+        compression
+    }
+
+    /// Recorded snapshots, oldest first
+    pub async fn snapshot_history(&self) -> Vec<PerformanceSnapshot> {
+        self.performance_history
+            .read()
+            .await
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Recorded snapshots as CSV, one row per snapshot with a header row
+    pub async fn snapshots_to_csv(&self) -> String {
+        let mut csv = String::from(
+            "timestamp,hits,misses,hit_rate,total_size_bytes,entry_count,\
+             operations_per_second,average_response_time_ms,memory_usage_bytes,disk_usage_bytes,\
+             hit_bytes,miss_bytes,byte_hit_rate\n",
+        );
+        for s in self.performance_history.read().await.iter() {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                s.timestamp,
+                s.hits,
+                s.misses,
+                s.hit_rate,
+                s.total_size_bytes,
+                s.entry_count,
+                s.operations_per_second,
+                s.average_response_time_ms,
+                s.memory_usage_bytes,
+                s.disk_usage_bytes,
+                s.hit_bytes,
+                s.miss_bytes,
+                s.byte_hit_rate
+            );
+        }
+        csv
+    }
+
+    /// Write [`snapshots_to_csv`](MetricsCollector::snapshots_to_csv) to `path`
+    pub async fn snapshots_to_csv_file(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        std::fs::write(path, self.snapshots_to_csv().await)?;
+        Ok(())
+    }
+
     /// Get current performance metrics
     pub async fn current_metrics(&self) -> Option<PerformanceSnapshot> {
         let history = self.performance_history.read().await;
         history.back().cloned()
     }
 
-    /// Get access pattern statistics
+    /// Access count and hit rate of the most frequently accessed keys
+    ///
+    /// Up to [`MetricsConfig::max_tracked_keys`] keys are reported, with
+    /// counts estimated from a sketch that may overcount slightly.
     pub async fn access_statistics(&self) -> HashMap<String, (u64, f64)> {
-        let patterns = self.access_patterns.read().await;
-        patterns.get_access_statistics()
+        self.access_patterns().await.get_access_statistics()
     }
 
     fn analyze_performance(
@@ -290,10 +1412,12 @@ impl MetricsCollector {
         if history.is_empty() {
             return PerformanceSummary {
                 average_hit_rate: 0.0,
+                average_byte_hit_rate: 0.0,
                 peak_hit_rate: 0.0,
                 average_response_time_ms: 0.0,
                 throughput_ops_per_second: 0.0,
                 cache_size_trend: "unknown".to_string(),
+                recent: RollingStats::default(),
             };
         }
 
@@ -302,6 +1426,16 @@ impl MetricsCollector {
         let throughputs: Vec<f64> = history.iter().map(|s| s.operations_per_second).collect();
 
         let average_hit_rate = hit_rates.iter().sum::<f64>() / hit_rates.len() as f64;
+        let byte_hit_rates: Vec<f64> = history
+            .iter()
+            .filter(|s| s.hit_bytes + s.miss_bytes > 0)
+            .map(|s| s.byte_hit_rate)
+            .collect();
+        let average_byte_hit_rate = if byte_hit_rates.is_empty() {
+            0.0
+        } else {
+            byte_hit_rates.iter().sum::<f64>() / byte_hit_rates.len() as f64
+        };
         let peak_hit_rate = hit_rates.iter().fold(0.0f64, |a, &b| a.max(b));
         let average_response_time =
             response_times.iter().sum::<f64>() / response_times.len() as f64;
@@ -326,10 +1460,12 @@ impl MetricsCollector {
 
         PerformanceSummary {
             average_hit_rate,
+            average_byte_hit_rate,
             peak_hit_rate,
             average_response_time_ms: average_response_time,
             throughput_ops_per_second: average_throughput,
             cache_size_trend,
+            recent: RollingStats::default(),
         }
     }
 
@@ -338,6 +1474,9 @@ impl MetricsCollector {
         performance: &PerformanceSummary,
         access_patterns: &AccessPatternSummary,
         efficiency: &EfficiencyAnalysis,
+        removals: &RemovalAnalysis,
+        size_simulation: &[SizeSimulationPoint],
+        compression: &CompressionStats,
     ) -> Vec<OptimizationRecommendation> {
         let mut recommendations = Vec::new();
 
@@ -351,6 +1490,22 @@ impl MetricsCollector {
             });
         }
 
+        // Bandwidth recommendations; a zero byte hit rate means none recorded
+        if performance.average_byte_hit_rate > 0.0
+            && performance.average_hit_rate - performance.average_byte_hit_rate > BYTE_HIT_RATE_GAP
+        {
+            recommendations.push(OptimizationRecommendation {
+                category: "Bandwidth".to_string(),
+                priority: "medium".to_string(),
+                description: format!(
+                    "Only {:.0}% of bytes are served from cache against a {:.0}% hit rate, so large entries miss disproportionately. Make room for large chunks, for example in the disk tier, rather than many small entries.",
+                    performance.average_byte_hit_rate * 100.0,
+                    performance.average_hit_rate * 100.0
+                ),
+                expected_impact: "Upstream bandwidth savings closer to the hit rate".to_string(),
+            });
+        }
+
         // Response time recommendations
         if performance.average_response_time_ms > 10.0 {
             recommendations.push(OptimizationRecommendation {
@@ -371,6 +1526,102 @@ impl MetricsCollector {
             });
         }
 
+        // Sizing recommendations
+        match access_patterns.access_distribution.as_str() {
+            "uniform" => recommendations.push(OptimizationRecommendation {
+                category: "Sizing".to_string(),
+                priority: "medium".to_string(),
+                description: format!(
+                    "Accesses are spread evenly over keys (Gini {:.2}), so the hit rate grows only with the share of the working set that fits. Size the cache to the whole working set or rely on prefetching.",
+                    access_patterns.access_skew
+                ),
+                expected_impact: "Hit rate proportional to cached share of data".to_string(),
+            }),
+            "skewed" | "clustered" if performance.average_hit_rate < 0.8 => {
+                recommendations.push(OptimizationRecommendation {
+                    category: "Sizing".to_string(),
+                    priority: "high".to_string(),
+                    description: format!(
+                        "Accesses concentrate on few keys (Gini {:.2}) yet the hit rate is low. A memory tier large enough for the most accessed keys should absorb most reads.",
+                        access_patterns.access_skew
+                    ),
+                    expected_impact: "Large hit rate gain from a modest size increase".to_string(),
+                });
+            }
+            _ => {}
+        }
+
+        // Simulated sizing recommendations
+        let at_factor = |factor: f64| {
+            size_simulation
+                .iter()
+                .find(|point| point.size_factor == factor)
+        };
+        if let (Some(current), Some(doubled)) = (at_factor(1.0), at_factor(2.0)) {
+            if doubled.estimated_hit_rate - current.estimated_hit_rate >= SIZE_SIMULATION_GAIN {
+                recommendations.push(OptimizationRecommendation {
+                    category: "Sizing".to_string(),
+                    priority: "high".to_string(),
+                    description: format!(
+                        "Doubling the memory tier to {} bytes would have raised the hit rate from an estimated {:.0}% to {:.0}%.",
+                        doubled.memory_size_bytes,
+                        current.estimated_hit_rate * 100.0,
+                        doubled.estimated_hit_rate * 100.0
+                    ),
+                    expected_impact: "Hit rate gain shown by the size simulation".to_string(),
+                });
+            }
+        }
+
+        // Removal cause recommendations, only worth acting on when misses hurt
+        if performance.average_hit_rate < 0.8 {
+            for (tier, counts) in [("memory", &removals.memory), ("disk", &removals.disk)] {
+                if counts.expired > counts.evicted {
+                    recommendations.push(OptimizationRecommendation {
+                        category: "Expiry".to_string(),
+                        priority: "medium".to_string(),
+                        description: format!(
+                            "The {} tier expired {} entries but evicted only {}. Misses are likely caused by an overly aggressive TTL; consider lengthening it.",
+                            tier, counts.expired, counts.evicted
+                        ),
+                        expected_impact: "Fewer misses on data read again after its TTL".to_string(),
+                    });
+                } else if counts.evicted > counts.expired {
+                    recommendations.push(OptimizationRecommendation {
+                        category: "Sizing".to_string(),
+                        priority: "medium".to_string(),
+                        description: format!(
+                            "The {} tier evicted {} entries for capacity but expired only {}. Misses are likely caused by an undersized tier; consider enlarging it.",
+                            tier, counts.evicted, counts.expired
+                        ),
+                        expected_impact: "Fewer misses on recently evicted data".to_string(),
+                    });
+                }
+            }
+        }
+
+        // Compression recommendations
+        for (algorithm, stats) in &compression.algorithms {
+            if algorithm != "none"
+                && stats.values > 0
+                && stats.ratio() < MIN_USEFUL_COMPRESSION_RATIO
+            {
+                recommendations.push(OptimizationRecommendation {
+                    category: "Compression".to_string(),
+                    priority: "medium".to_string(),
+                    description: format!(
+                        "{} compression shrank {} values by only {:.2}x at a cost of {} ms. The data is likely already compressed; consider a minimum compression ratio or storing it uncompressed.",
+                        algorithm,
+                        stats.values,
+                        stats.ratio(),
+                        stats.compression_time.as_millis()
+                    ),
+                    expected_impact: "Lower write and read latency at little cost in space"
+                        .to_string(),
+                });
+            }
+        }
+
         // Efficiency recommendations
         if efficiency.warming_effectiveness < 0.6 {
             recommendations.push(OptimizationRecommendation {
@@ -387,49 +1638,609 @@ impl MetricsCollector {
     }
 }
 
+/// Cache wrapper feeding a [`MetricsCollector`] automatically
+///
+/// Every read is timed and recorded with
+/// [`MetricsCollector::record_operation`], and every write that shrinks the
+/// room left in the inner cache is recorded as an eviction of the bytes it
+/// displaced. Displacement is judged from the change in size around the
+/// write, so replacing a value counts its old bytes, and concurrent writes
+/// can blur the attribution. Bytes of hits, and of values written after a
+/// miss of the same key, are recorded with
+/// [`MetricsCollector::record_bytes`] as served from cache and fetched
+/// upstream respectively, and the sizes of hits and writes feed the size
+/// simulation of reports. Snapshots are captured by
+/// [`MetricsCache::spawn_snapshotter`].
+///
+/// With [`MetricsCache::with_event_log`], reads, writes, removals and the
+/// displacement caused by single writes are also logged as [`CacheEvent`]s.
+pub struct MetricsCache<C: Cache> {
+    inner: Arc<C>,
+    metrics: Arc<MetricsCollector>,
+    /// Missed keys whose next write is counted as fetched upstream
+    pending_fills: std::sync::Mutex<HashSet<String>>,
+    events: Option<Arc<EventLog>>,
+}
+
+impl<C: Cache> MetricsCache<C> {
+    /// Wrap `inner`, reporting its size as `tier` usage in snapshots
+    pub fn new(inner: C, config: MetricsConfig, tier: CacheTier) -> Self {
+        let inner = Arc::new(inner);
+        let metrics = MetricsCollector::new(config).with_cache(inner.clone(), tier);
+        Self {
+            inner,
+            metrics: Arc::new(metrics),
+            pending_fills: std::sync::Mutex::new(HashSet::new()),
+            events: None,
+        }
+    }
+
+    /// Log every operation to `events`
+    pub fn with_event_log(mut self, events: Arc<EventLog>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Collector the wrapper records into, for reports and snapshots
+    pub fn metrics(&self) -> &Arc<MetricsCollector> {
+        &self.metrics
+    }
+
+    /// Capture snapshots of the inner cache every
+    /// [`MetricsConfig::snapshot_interval`], see
+    /// [`MetricsCollector::spawn_snapshotter`]
+    pub fn spawn_snapshotter(&self) -> tokio::task::JoinHandle<()> {
+        self.metrics.spawn_snapshotter()
+    }
+
+    /// Record `len` bytes written to `key` as fetched upstream if it missed
+    fn record_fill(&self, key: &str, len: usize) {
+        let filled = self
+            .pending_fills
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        if filled {
+            self.metrics.record_bytes(false, len);
+        }
+    }
+
+    /// Record the bytes a write of `len` bytes displaced, logging them
+    /// against `key` for single writes
+    async fn record_write(&self, size_before: usize, len: usize, key: Option<&str>) {
+        let displaced = (size_before + len).saturating_sub(self.inner.size());
+        if displaced > 0 {
+            self.metrics.record_eviction(displaced).await;
+            if let Some(key) = key {
+                self.log(
+                    CacheEvent::new(CacheEventKind::Evict, key, CacheEventOutcome::Ok)
+                        .with_size(displaced),
+                );
+            }
+        }
+    }
+
+    fn log(&self, event: CacheEvent) {
+        if let Some(events) = &self.events {
+            events.record(event);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache> Cache for MetricsCache<C> {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let started = Instant::now();
+        let value = self.inner.get(key).await;
+        let latency = started.elapsed();
+        self.metrics
+            .record_operation(key, value.is_some(), latency)
+            .await;
+        if self.events.is_some() {
+            let event = match &value {
+                Some(value) => CacheEvent::new(CacheEventKind::Get, key, CacheEventOutcome::Hit)
+                    .with_size(value.len()),
+                None => CacheEvent::new(CacheEventKind::Get, key, CacheEventOutcome::Miss),
+            };
+            self.log(event.with_latency(latency));
+        }
+        match &value {
+            Some(value) => {
+                self.metrics.record_bytes(true, value.len());
+                self.metrics.record_entry_size(key, value.len()).await;
+            }
+            None => {
+                let mut pending = self.pending_fills.lock().unwrap_or_else(|e| e.into_inner());
+                if pending.len() < MAX_PENDING_FILLS {
+                    pending.insert(key.to_string());
+                }
+            }
+        }
+        value
+    }
+
+    /// Passed through without being recorded as an access
+    async fn get_stale(&self, key: &str) -> Option<Bytes> {
+        self.inner.get_stale(key).await
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let size_before = self.inner.size();
+        let len = value.len();
+        let started = Instant::now();
+        let result = self.inner.set(key, value).await;
+        self.log(
+            CacheEvent::new(CacheEventKind::Set, key, CacheEventOutcome::of(&result))
+                .with_size(len)
+                .with_latency(started.elapsed()),
+        );
+        if let Err(e) = result {
+            self.metrics.record_error().await;
+            return Err(e);
+        }
+        self.record_fill(key, len);
+        self.metrics.record_entry_size(key, len).await;
+        self.record_write(size_before, len, Some(key)).await;
+        Ok(())
+    }
+
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        let size_before = self.inner.size();
+        let len: usize = entries.iter().map(|(_, value)| value.len()).sum();
+        for (key, value) in &entries {
+            self.record_fill(key, value.len());
+            self.metrics.record_entry_size(key, value.len()).await;
+        }
+        let logged: Vec<(StoreKey, usize)> = match &self.events {
+            Some(_) => entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.len()))
+                .collect(),
+            None => Vec::new(),
+        };
+        let results = self.inner.set_many(entries).await;
+        for ((key, len), result) in logged.iter().zip(&results) {
+            self.log(
+                CacheEvent::new(CacheEventKind::Set, key, CacheEventOutcome::of(result))
+                    .with_size(*len),
+            );
+        }
+        for _ in results.iter().filter(|result| result.is_err()) {
+            self.metrics.record_error().await;
+        }
+        self.record_write(size_before, len, None).await;
+        results
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        let started = Instant::now();
+        let result = self.inner.remove_with_cause(key, cause).await;
+        self.log(
+            CacheEvent::new(CacheEventKind::Remove, key, CacheEventOutcome::of(&result))
+                .with_latency(started.elapsed()),
+        );
+        result
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        self.inner.remove_prefix(prefix).await
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        self.inner.clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.inner.purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        self.inner.io_operations()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.inner.resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.inner.set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.inner.stats()
+    }
+}
+
+impl CacheAnalyticsReport {
+    /// Write the report to `path` as pretty-printed JSON
+    pub fn to_json_file(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| CacheError::Serialization(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Read a report written by [`CacheAnalyticsReport::to_json_file`]
+    pub fn from_json_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let json = std::fs::read(path)?;
+        serde_json::from_slice(&json).map_err(|e| CacheError::Serialization(e.to_string()))
+    }
+
+    /// Compare this report against `baseline` with the default thresholds
+    pub fn diff(&self, baseline: &CacheAnalyticsReport) -> ReportDiff {
+        self.diff_with(baseline, &RegressionThresholds::default())
+    }
+
+    /// Compare this report against `baseline`, flagging a regression when
+    /// a change exceeds `thresholds`
+    pub fn diff_with(
+        &self,
+        baseline: &CacheAnalyticsReport,
+        thresholds: &RegressionThresholds,
+    ) -> ReportDiff {
+        let (new, old) = (&self.performance_summary, &baseline.performance_summary);
+        let hit_rate_delta = new.average_hit_rate - old.average_hit_rate;
+        let byte_hit_rate_delta = new.average_byte_hit_rate - old.average_byte_hit_rate;
+        let response_time_delta_ms = new.average_response_time_ms - old.average_response_time_ms;
+        let locality_delta = self.access_patterns.spatial_locality_score
+            - baseline.access_patterns.spatial_locality_score;
+
+        let mut regressions = Vec::new();
+        if -hit_rate_delta > thresholds.max_hit_rate_drop {
+            regressions.push(format!(
+                "Hit rate fell from {:.1}% to {:.1}%",
+                old.average_hit_rate * 100.0,
+                new.average_hit_rate * 100.0
+            ));
+        }
+        if -byte_hit_rate_delta > thresholds.max_byte_hit_rate_drop {
+            regressions.push(format!(
+                "Byte hit rate fell from {:.1}% to {:.1}%",
+                old.average_byte_hit_rate * 100.0,
+                new.average_byte_hit_rate * 100.0
+            ));
+        }
+        // Without a baseline latency there is nothing to be relative to
+        if old.average_response_time_ms > 0.0
+            && response_time_delta_ms / old.average_response_time_ms
+                > thresholds.max_response_time_increase
+        {
+            regressions.push(format!(
+                "Average response time rose from {:.2}ms to {:.2}ms",
+                old.average_response_time_ms, new.average_response_time_ms
+            ));
+        }
+        if -locality_delta > thresholds.max_locality_drop {
+            regressions.push(format!(
+                "Spatial locality score fell from {:.2} to {:.2}",
+                baseline.access_patterns.spatial_locality_score,
+                self.access_patterns.spatial_locality_score
+            ));
+        }
+
+        ReportDiff {
+            hit_rate_delta,
+            byte_hit_rate_delta,
+            response_time_delta_ms,
+            throughput_delta: new.throughput_ops_per_second - old.throughput_ops_per_second,
+            locality_delta,
+            access_skew_delta: self.access_patterns.access_skew
+                - baseline.access_patterns.access_skew,
+            regression: !regressions.is_empty(),
+            regressions,
+        }
+    }
+
+    /// Render the report as a self-contained HTML page
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let performance = &self.performance_summary;
+        let patterns = &self.access_patterns;
+        let efficiency = &self.efficiency_analysis;
+
+        html.push_str(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Cache Analytics Report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; color: #222; }\n\
+             table { border-collapse: collapse; margin-bottom: 1.5em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: left; }\n\
+             th { background: #f0f0f0; }\n\
+             .high { color: #b00020; } .medium { color: #b26a00; } .low { color: #2e7d32; }\n\
+             </style>\n</head>\n<body>\n<h1>Cache Analytics Report</h1>\n",
+        );
+        let _ = writeln!(
+            html,
+            "<p>Generated at {} (Unix time), covering {:.0} seconds.</p>",
+            self.generated_at,
+            self.time_range.as_secs_f64()
+        );
+
+        html.push_str("<h2>Performance</h2>\n<table>\n");
+        let rows = [
+            (
+                "Average hit rate",
+                format!("{:.1}%", performance.average_hit_rate * 100.0),
+            ),
+            (
+                "Average byte hit rate",
+                format!("{:.1}%", performance.average_byte_hit_rate * 100.0),
+            ),
+            (
+                "Peak hit rate",
+                format!("{:.1}%", performance.peak_hit_rate * 100.0),
+            ),
+            (
+                "Average response time",
+                format!("{:.2} ms", performance.average_response_time_ms),
+            ),
+            (
+                "Throughput",
+                format!("{:.1} ops/s", performance.throughput_ops_per_second),
+            ),
+            ("Cache size trend", performance.cache_size_trend.clone()),
+            (
+                "Hit rate over the last 1 / 5 / 15 minutes",
+                format!(
+                    "{:.1}% / {:.1}% / {:.1}%",
+                    performance.recent.last_1m.hit_rate * 100.0,
+                    performance.recent.last_5m.hit_rate * 100.0,
+                    performance.recent.last_15m.hit_rate * 100.0
+                ),
+            ),
+        ];
+        for (name, value) in rows {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                name,
+                escape_html(&value)
+            );
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Access Patterns</h2>\n<table>\n");
+        let _ = writeln!(
+            html,
+            "<tr><th>Spatial locality</th><td>{:.1}%</td></tr>",
+            patterns.spatial_locality_score * 100.0
+        );
+        let _ = writeln!(
+            html,
+            "<tr><th>Access distribution</th><td>{} (Gini {:.2})</td></tr>",
+            escape_html(&patterns.access_distribution),
+            patterns.access_skew
+        );
+        html.push_str("</table>\n");
+        if !patterns.most_accessed_keys.is_empty() {
+            html.push_str("<table>\n<tr><th>Key</th><th>Accesses</th></tr>\n");
+            for (key, count) in &patterns.most_accessed_keys {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td></tr>",
+                    escape_html(key),
+                    count
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("<h2>Efficiency</h2>\n<table>\n");
+        let rows = [
+            (
+                "Promotion effectiveness",
+                efficiency.promotion_effectiveness,
+            ),
+            ("Warming effectiveness", efficiency.warming_effectiveness),
+            ("Resource efficiency", efficiency.resource_efficiency),
+            (
+                "Memory utilization",
+                efficiency.resource_utilization.memory_utilization,
+            ),
+            (
+                "Disk utilization",
+                efficiency.resource_utilization.disk_utilization,
+            ),
+        ];
+        for (name, value) in rows {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{:.1}%</td></tr>",
+                name,
+                value * 100.0
+            );
+        }
+        let utilization = &efficiency.resource_utilization;
+        let _ = writeln!(
+            html,
+            "<tr><th>CPU time</th><td>{} ms</td></tr>",
+            utilization.cpu_time_ms
+        );
+        let _ = writeln!(
+            html,
+            "<tr><th>I/O operations</th><td>{}</td></tr>",
+            utilization.io_operations
+        );
+        html.push_str("</table>\n");
+        if !efficiency.bottleneck_analysis.is_empty() {
+            html.push_str("<ul>\n");
+            for bottleneck in &efficiency.bottleneck_analysis {
+                let _ = writeln!(html, "<li>{}</li>", escape_html(bottleneck));
+            }
+            html.push_str("</ul>\n");
+        }
+
+        if !self.size_simulation.is_empty() {
+            html.push_str(
+                "<h2>Hit Rate vs Memory Size</h2>\n<table>\n<tr><th>Size factor</th>\
+                 <th>Memory size</th><th>Estimated hit rate</th></tr>\n",
+            );
+            for point in &self.size_simulation {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}&times;</td><td>{} bytes</td><td>{:.1}%</td></tr>",
+                    point.size_factor,
+                    point.memory_size_bytes,
+                    point.estimated_hit_rate * 100.0
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        if !self.compression.is_empty() {
+            html.push_str(
+                "<h2>Compression</h2>\n<table>\n<tr><th>Algorithm</th><th>Values</th>\
+                 <th>Logical bytes</th><th>Stored bytes</th><th>Ratio</th>\
+                 <th>Compression time</th><th>Decompressions</th>\
+                 <th>Decompression time</th></tr>\n",
+            );
+            for (algorithm, stats) in &self.compression.algorithms {
+                let _ = writeln!(
+                    html,
+                    "<tr><th>{}</th><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td>\
+                     <td>{:.1} ms</td><td>{}</td><td>{:.1} ms</td></tr>",
+                    escape_html(algorithm),
+                    stats.values,
+                    stats.logical_bytes,
+                    stats.stored_bytes,
+                    stats.ratio(),
+                    stats.compression_time.as_secs_f64() * 1000.0,
+                    stats.decompressions,
+                    stats.decompression_time.as_secs_f64() * 1000.0
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str(
+            "<h2>Removals</h2>\n<table>\n<tr><th>Tier</th><th>Evicted</th>\
+             <th>Expired</th><th>Removed</th><th>Demoted</th></tr>\n",
+        );
+        for (tier, counts) in [
+            ("Memory", &self.removals.memory),
+            ("Disk", &self.removals.disk),
+        ] {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                tier, counts.evicted, counts.expired, counts.removed, counts.demoted
+            );
+        }
+        html.push_str("</table>\n");
+
+        html.push_str("<h2>Recommendations</h2>\n");
+        if self.recommendations.is_empty() {
+            html.push_str("<p>None.</p>\n");
+        } else {
+            html.push_str(
+                "<table>\n<tr><th>Priority</th><th>Category</th>\
+                 <th>Recommendation</th><th>Expected impact</th></tr>\n",
+            );
+            for rec in &self.recommendations {
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"{priority}\">{priority}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    escape_html(&rec.category),
+                    escape_html(&rec.description),
+                    escape_html(&rec.expected_impact),
+                    priority = escape_html(&rec.priority),
+                );
+            }
+            html.push_str("</table>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// Write [`to_html`](CacheAnalyticsReport::to_html) to `path`
+    pub fn to_html_file(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        std::fs::write(path, self.to_html())?;
+        Ok(())
+    }
+}
+
+/// Escape text for use in HTML element content and attribute values
+/// CPU time used by this process so far, where the platform reports it
+fn process_cpu_time() -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `time` is a valid timespec for the call to fill in
+        if unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut time) } == 0 {
+            return Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32));
+        }
+    }
+    None
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl AccessPatternAnalyzer {
-    fn new() -> Self {
+    fn new(max_tracked_keys: usize) -> Self {
         Self {
-            key_frequencies: HashMap::new(),
+            key_frequencies: TopK::new(max_tracked_keys),
+            key_hits: CountMinSketch::new(max_tracked_keys * 8),
             temporal_patterns: VecDeque::new(),
             spatial_locality: SpatialLocalityTracker::new(),
+            shadow: ShadowTrace::new(),
         }
     }
 
-    fn record_access(&mut self, key: &str, was_hit: bool, response_time: Duration) {
-        // Update key frequency info
-        let key_info = self
-            .key_frequencies
-            .entry(key.to_string())
-            .or_insert_with(|| KeyAccessInfo {
-                total_accesses: 0,
-                last_access: Instant::now(),
-                access_intervals: VecDeque::new(),
-                cache_hits: 0,
-                cache_misses: 0,
-            });
-
-        let now = Instant::now();
-        if key_info.total_accesses > 0 {
-            let interval = now.duration_since(key_info.last_access);
-            key_info.access_intervals.push_back(interval);
-            if key_info.access_intervals.len() > 100 {
-                key_info.access_intervals.pop_front();
+    fn apply(&mut self, records: Vec<PendingRecord>) {
+        for record in records {
+            match record {
+                PendingRecord::Access {
+                    key,
+                    was_hit,
+                    response_time,
+                    at,
+                } => self.record_access(&key, was_hit, response_time, at),
+                PendingRecord::EntrySize { key, bytes } => self.shadow.set_size(&key, bytes),
             }
         }
+    }
 
-        key_info.total_accesses += 1;
-        key_info.last_access = now;
-
+    fn record_access(&mut self, key: &str, was_hit: bool, response_time: Duration, at: Instant) {
+        self.key_frequencies.increment(key);
         if was_hit {
-            key_info.cache_hits += 1;
-        } else {
-            key_info.cache_misses += 1;
+            self.key_hits.add(key, 1);
         }
 
         // Record temporal pattern
         self.temporal_patterns.push_back(TemporalAccess {
-            timestamp: now,
+            timestamp: at,
             key: key.to_string(),
             was_hit,
             response_time,
@@ -442,37 +2253,68 @@ impl AccessPatternAnalyzer {
 
         // Update spatial locality if it's a chunk key
         self.spatial_locality.record_chunk_access(key);
+        self.shadow.access(key);
     }
 
     fn analyze_patterns(&self) -> AccessPatternSummary {
-        let mut most_accessed: Vec<(String, u64)> = self
-            .key_frequencies
-            .iter()
-            .map(|(k, v)| (k.clone(), v.total_accesses))
-            .collect();
-        most_accessed.sort_by(|a, b| b.1.cmp(&a.1));
-        most_accessed.truncate(10);
+        let most_accessed = self.key_frequencies.top(10);
 
         let spatial_locality_score = self.spatial_locality.calculate_locality_score();
+        let access_skew = self.access_skew();
+
+        // Skewed accesses whose hot keys are neighbouring chunks are clustered
+        let access_distribution = if self.key_frequencies.len() < 2 {
+            "unknown"
+        } else if access_skew < SKEWED_GINI {
+            "uniform"
+        } else if spatial_locality_score >= 0.5 {
+            "clustered"
+        } else {
+            "skewed"
+        };
 
         AccessPatternSummary {
             most_accessed_keys: most_accessed,
             temporal_hotspots: vec![], // Simplified for now
             spatial_locality_score,
-            access_distribution: "mixed".to_string(), // Simplified analysis
+            access_distribution: access_distribution.to_string(),
+            access_skew,
+        }
+    }
+
+    /// Gini coefficient of the access counts of the tracked keys
+    fn access_skew(&self) -> f64 {
+        let mut counts: Vec<u64> = self
+            .key_frequencies
+            .iter()
+            .map(|(_, count)| count)
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if counts.len() < 2 || total == 0 {
+            return 0.0;
         }
+        counts.sort_unstable();
+
+        let n = counts.len() as f64;
+        let weighted: f64 = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| (i + 1) as f64 * count as f64)
+            .sum();
+        (2.0 * weighted / (n * total as f64) - (n + 1.0) / n).max(0.0)
     }
 
     fn get_access_statistics(&self) -> HashMap<String, (u64, f64)> {
         self.key_frequencies
             .iter()
-            .map(|(key, info)| {
-                let hit_rate = if info.total_accesses > 0 {
-                    info.cache_hits as f64 / info.total_accesses as f64
+            .map(|(key, accesses)| {
+                // Both counts may be overestimated; the ratio is kept in range
+                let hit_rate = if accesses > 0 {
+                    (self.key_hits.estimate(key) as f64 / accesses as f64).min(1.0)
                 } else {
                     0.0
                 };
-                (key.clone(), (info.total_accesses, hit_rate))
+                (key.to_string(), (accesses, hit_rate))
             })
             .collect()
     }
@@ -481,14 +2323,12 @@ impl AccessPatternAnalyzer {
 impl SpatialLocalityTracker {
     fn new() -> Self {
         Self {
-            chunk_accesses: HashMap::new(),
             recent_sequence: VecDeque::new(),
         }
     }
 
     fn record_chunk_access(&mut self, key: &str) {
         if let Some(coord) = self.parse_chunk_coordinate(key) {
-            *self.chunk_accesses.entry(coord.clone()).or_insert(0) += 1;
             self.recent_sequence.push_back(coord);
 
             if self.recent_sequence.len() > 1000 {
@@ -580,12 +2420,9 @@ impl EfficiencyTracker {
                 warming_hit_rate: 0.0,
                 warming_efficiency: 0.0,
             },
-            resource_utilization: ResourceUtilization {
-                memory_utilization: 0.0,
-                disk_utilization: 0.0,
-                cpu_time_ms: 0,
-                io_operations: 0,
-            },
+            resource_utilization: ResourceUtilization::default(),
+            bounded_tiers: (false, false),
+            eviction_stats: EvictionStats::default(),
         }
     }
 
@@ -600,9 +2437,20 @@ impl EfficiencyTracker {
     fn analyze_efficiency(&self) -> EfficiencyAnalysis {
         let promotion_effectiveness = self.promotion_stats.promotion_accuracy;
         let warming_effectiveness = self.warming_stats.warming_hit_rate;
-        let resource_efficiency = (self.resource_utilization.memory_utilization
-            + self.resource_utilization.disk_utilization)
-            / 2.0;
+        // Average over the tiers that have a capacity to fill
+        let utilization = &self.resource_utilization;
+        let measured: Vec<f64> = [
+            (self.bounded_tiers.0, utilization.memory_utilization),
+            (self.bounded_tiers.1, utilization.disk_utilization),
+        ]
+        .into_iter()
+        .filter_map(|(bounded, value)| bounded.then_some(value))
+        .collect();
+        let resource_efficiency = if measured.is_empty() {
+            0.0
+        } else {
+            measured.iter().sum::<f64>() / measured.len() as f64
+        };
 
         let mut bottlenecks = Vec::new();
         if promotion_effectiveness < 0.7 {
@@ -619,7 +2467,37 @@ impl EfficiencyTracker {
             promotion_effectiveness,
             warming_effectiveness,
             resource_efficiency,
+            resource_utilization: self.resource_utilization.clone(),
             bottleneck_analysis: bottlenecks,
         }
     }
 }
+
+#[cfg(test)]
+mod rolling_counter_tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_counter_forgets_old_operations() {
+        let mut counter = RollingCounter::new();
+        counter.record_at(0, true);
+        counter.record_at(0, false);
+        counter.record_at(100, true);
+        counter.record_at(299, true);
+
+        let last_minute = counter.window_at(299, Duration::from_secs(60));
+        assert_eq!((last_minute.hits, last_minute.misses), (1, 0));
+        assert_eq!(last_minute.operations_per_second, 1.0 / 60.0);
+
+        let last_5m = counter.window_at(299, Duration::from_secs(300));
+        assert_eq!((last_5m.hits, last_5m.misses), (3, 1));
+        assert_eq!(last_5m.hit_rate, 0.75);
+
+        // Past the horizon buckets are dropped as new ones arrive
+        counter.record_at(ROLLING_HORIZON_SECS + 50, false);
+        assert_eq!(counter.buckets.len(), 3);
+        let last_15m = counter.window_at(ROLLING_HORIZON_SECS + 50, Duration::from_secs(3600));
+        assert_eq!(last_15m.window, Duration::from_secs(ROLLING_HORIZON_SECS));
+        assert_eq!((last_15m.hits, last_15m.misses), (2, 1));
+    }
+}
@@ -1,8 +1,10 @@
 use crate::cache::Cache;
 use crate::config::PrefetchConfig;
 use crate::error::CacheError;
+use crate::upstream::{self, RateLimiter};
 use bytes::Bytes;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::{RwLock, Semaphore};
 
@@ -263,22 +265,38 @@ pub trait PrefetchStrategy: Send + Sync + 'static {
 
 /// Neighboring chunk prefetching strategy
 pub struct NeighborChunkPrefetch {
-    neighbor_count: usize,
-    max_queue_size: usize,
+    neighbor_count: AtomicUsize,
+    max_queue_size: AtomicUsize,
     prefetch_queue: Arc<RwLock<VecDeque<String>>>,
     #[allow(dead_code)]
     semaphore: Arc<Semaphore>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl NeighborChunkPrefetch {
     pub fn new(config: &PrefetchConfig) -> Self {
         Self {
-            neighbor_count: config.neighbor_chunks,
-            max_queue_size: config.max_queue_size,
+            neighbor_count: AtomicUsize::new(config.neighbor_chunks),
+            max_queue_size: AtomicUsize::new(config.max_queue_size),
             prefetch_queue: Arc::new(RwLock::new(VecDeque::new())),
             semaphore: Arc::new(Semaphore::new(config.max_queue_size)),
+            rate_limiter: None,
         }
     }
+
+    /// Load prefetched keys only as fast as `limiter` allows
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Apply `config` to prefetches from now on
+    pub fn set_config(&self, config: &PrefetchConfig) {
+        self.neighbor_count
+            .store(config.neighbor_chunks, Ordering::Relaxed);
+        self.max_queue_size
+            .store(config.max_queue_size, Ordering::Relaxed);
+    }
 }
 
 #[async_trait::async_trait]
@@ -288,7 +306,8 @@ impl PrefetchStrategy for NeighborChunkPrefetch {
             return Vec::new();
         };
 
-        let neighbor_coords = generate_neighbor_coordinates(&coords, self.neighbor_count);
+        let neighbor_coords =
+            generate_neighbor_coordinates(&coords, self.neighbor_count.load(Ordering::Relaxed));
 
         neighbor_coords
             .into_iter()
@@ -307,11 +326,12 @@ impl PrefetchStrategy for NeighborChunkPrefetch {
         F: Fn(String) -> Fut + Send + Sync,
         Fut: std::future::Future<Output = Option<Bytes>> + Send,
     {
+        let max_queue_size = self.max_queue_size.load(Ordering::Relaxed);
         let mut queue = self.prefetch_queue.write().await;
 
         // Add keys to prefetch queue
         for key in keys {
-            if queue.len() >= self.max_queue_size {
+            if queue.len() >= max_queue_size {
                 queue.pop_front(); // Remove oldest if queue is full
             }
 
@@ -323,11 +343,13 @@ impl PrefetchStrategy for NeighborChunkPrefetch {
 
         // Process prefetch queue synchronously for now
         // In a real implementation, this would use a background worker
-        let keys_to_fetch: Vec<String> = queue.drain(..).take(self.max_queue_size).collect();
+        let keys_to_fetch: Vec<String> = queue.drain(..).take(max_queue_size).collect();
         drop(queue);
 
         for key in keys_to_fetch {
-            if let Some(data) = loader(key.clone()).await {
+            if let Some(data) =
+                upstream::limited_load(self.rate_limiter.as_ref(), &loader, key.clone()).await
+            {
                 if let Err(e) = cache.set(&key, data).await {
                     tracing::warn!("Failed to prefetch key {}: {:?}", key, e);
                 } else {
@@ -342,17 +364,33 @@ impl PrefetchStrategy for NeighborChunkPrefetch {
 
 /// Sequential prefetching strategy
 pub struct SequentialPrefetch {
-    lookahead: usize,
-    max_queue_size: usize,
+    lookahead: AtomicUsize,
+    max_queue_size: AtomicUsize,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl SequentialPrefetch {
     pub fn new(config: &PrefetchConfig) -> Self {
         Self {
-            lookahead: config.neighbor_chunks,
-            max_queue_size: config.max_queue_size,
+            lookahead: AtomicUsize::new(config.neighbor_chunks),
+            max_queue_size: AtomicUsize::new(config.max_queue_size),
+            rate_limiter: None,
         }
     }
+
+    /// Load prefetched keys only as fast as `limiter` allows
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Apply `config` to prefetches from now on
+    pub fn set_config(&self, config: &PrefetchConfig) {
+        self.lookahead
+            .store(config.neighbor_chunks, Ordering::Relaxed);
+        self.max_queue_size
+            .store(config.max_queue_size, Ordering::Relaxed);
+    }
 }
 
 #[async_trait::async_trait]
@@ -362,7 +400,7 @@ impl PrefetchStrategy for SequentialPrefetch {
             return Vec::new();
         };
 
-        generate_sequential_keys(&array_name, &coords, self.lookahead)
+        generate_sequential_keys(&array_name, &coords, self.lookahead.load(Ordering::Relaxed))
     }
 
     async fn prefetch<C, F, Fut>(
@@ -377,14 +415,17 @@ impl PrefetchStrategy for SequentialPrefetch {
         Fut: std::future::Future<Output = Option<Bytes>> + Send,
     {
         // Simple implementation: prefetch first N keys that aren't cached
+        let max_queue_size = self.max_queue_size.load(Ordering::Relaxed);
         let mut count = 0;
         for key in keys {
-            if count >= self.max_queue_size {
+            if count >= max_queue_size {
                 break;
             }
 
             if cache.get(&key).await.is_none() {
-                if let Some(data) = loader(key.clone()).await {
+                if let Some(data) =
+                    upstream::limited_load(self.rate_limiter.as_ref(), &loader, key.clone()).await
+                {
                     if let Err(e) = cache.set(&key, data).await {
                         tracing::warn!("Failed to prefetch key {}: {:?}", key, e);
                     } else {
@@ -0,0 +1,368 @@
+use crate::cache::Cache;
+use crate::error::CacheError;
+use crate::units::{HumanDuration, SetterError};
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Host memory pressure as seen by [`MemoryPressureMonitor`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PressureLevel {
+    /// Plenty of headroom; caches run at full capacity
+    #[default]
+    Normal,
+    /// Usage above the high watermark; shrink caches and pause promotions
+    Elevated,
+    /// Usage above the critical watermark; shrink aggressively
+    Critical,
+}
+
+/// A memory usage sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Bytes currently in use (process RSS)
+    pub used_bytes: u64,
+    /// Bytes available to the process (cgroup limit or physical memory)
+    pub limit_bytes: u64,
+}
+
+impl MemoryUsage {
+    /// Fraction of the limit in use (0.0 to 1.0+)
+    pub fn ratio(&self) -> f64 {
+        if self.limit_bytes == 0 {
+            0.0
+        } else {
+            self.used_bytes as f64 / self.limit_bytes as f64
+        }
+    }
+
+    /// Sample the current process on Linux
+    ///
+    /// Usage is the process RSS from `/proc/self/status`. The limit is the
+    /// cgroup v2 `memory.max`, falling back to cgroup v1
+    /// `memory.limit_in_bytes` and finally `MemTotal` from `/proc/meminfo`.
+    /// Returns `None` where this information is unavailable.
+    pub fn current() -> Option<Self> {
+        let used_bytes = read_kib_field("/proc/self/status", "VmRSS:")?;
+        let limit_bytes =
+            read_cgroup_limit().or_else(|| read_kib_field("/proc/meminfo", "MemTotal:"))?;
+
+        Some(Self {
+            used_bytes,
+            limit_bytes,
+        })
+    }
+}
+
+fn read_kib_field(path: &str, field: &str) -> Option<u64> {
+    let contents = fs::read_to_string(path).ok()?;
+    let line = contents.lines().find(|line| line.starts_with(field))?;
+    let kib: u64 = line[field.len()..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+fn read_cgroup_limit() -> Option<u64> {
+    const UNLIMITED_THRESHOLD: u64 = 1 << 60; // cgroup v1 reports "no limit" as a huge value
+
+    for path in [
+        "/sys/fs/cgroup/memory.max",
+        "/sys/fs/cgroup/memory/memory.limit_in_bytes",
+    ] {
+        if let Ok(contents) = fs::read_to_string(path) {
+            let value = contents.trim();
+            if value == "max" {
+                return None;
+            }
+            if let Ok(limit) = value.parse::<u64>() {
+                if limit < UNLIMITED_THRESHOLD {
+                    return Some(limit);
+                }
+                return None;
+            }
+        }
+    }
+
+    None
+}
+
+/// Configuration for memory pressure monitoring
+///
+/// # Default Values
+/// - `check_interval`: 5 seconds
+/// - `high_watermark`: 0.85 (85% of the limit)
+/// - `critical_watermark`: 0.95
+/// - `low_watermark`: 0.75 (pressure clears below this)
+/// - `elevated_capacity_factor`: 0.5 (halve cache capacity)
+/// - `critical_capacity_factor`: 0.1
+#[derive(Debug, Clone)]
+pub struct MemoryPressureConfig {
+    /// How often to sample memory usage
+    pub check_interval: Duration,
+    /// Usage ratio at which pressure becomes elevated
+    pub high_watermark: f64,
+    /// Usage ratio at which pressure becomes critical
+    pub critical_watermark: f64,
+    /// Usage ratio below which pressure returns to normal
+    pub low_watermark: f64,
+    /// Fraction of baseline capacity kept under elevated pressure
+    pub elevated_capacity_factor: f64,
+    /// Fraction of baseline capacity kept under critical pressure
+    pub critical_capacity_factor: f64,
+}
+
+impl Default for MemoryPressureConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(5),
+            high_watermark: 0.85,
+            critical_watermark: 0.95,
+            low_watermark: 0.75,
+            elevated_capacity_factor: 0.5,
+            critical_capacity_factor: 0.1,
+        }
+    }
+}
+
+impl MemoryPressureConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> MemoryPressureConfigBuilder {
+        MemoryPressureConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable and consistent with each other
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.check_interval.is_zero() {
+            return invalid("check_interval must be greater than zero");
+        }
+        if !(0.0 <= self.low_watermark
+            && self.low_watermark < self.high_watermark
+            && self.high_watermark <= self.critical_watermark
+            && self.critical_watermark <= 1.0)
+        {
+            return invalid("watermarks must satisfy 0 <= low < high <= critical <= 1");
+        }
+        if !(0.0 < self.critical_capacity_factor
+            && self.critical_capacity_factor <= self.elevated_capacity_factor
+            && self.elevated_capacity_factor <= 1.0)
+        {
+            return invalid("capacity factors must satisfy 0 < critical <= elevated <= 1");
+        }
+        Ok(())
+    }
+}
+
+/// Builder for [`MemoryPressureConfig`], starting from its defaults
+///
+/// [`build`](MemoryPressureConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPressureConfigBuilder {
+    config: MemoryPressureConfig,
+    invalid: SetterError,
+}
+
+impl MemoryPressureConfigBuilder {
+    pub fn check_interval(mut self, interval: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(interval.to_duration()) {
+            self.config.check_interval = duration;
+        }
+        self
+    }
+
+    /// Usage ratios at which pressure clears, becomes elevated and becomes
+    /// critical
+    pub fn watermarks(mut self, low: f64, high: f64, critical: f64) -> Self {
+        self.config.low_watermark = low;
+        self.config.high_watermark = high;
+        self.config.critical_watermark = critical;
+        self
+    }
+
+    pub fn elevated_capacity_factor(mut self, factor: f64) -> Self {
+        self.config.elevated_capacity_factor = factor;
+        self
+    }
+
+    pub fn critical_capacity_factor(mut self, factor: f64) -> Self {
+        self.config.critical_capacity_factor = factor;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<MemoryPressureConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+type UsageProbe = Box<dyn Fn() -> Option<MemoryUsage> + Send + Sync>;
+
+/// Watches process memory and publishes a [`PressureLevel`]
+///
+/// Caches subscribe through [`PressureSignal`]s: standalone caches via
+/// [`shrink_on_pressure`], hybrid caches via `HybridCache::with_memory_pressure`.
+pub struct MemoryPressureMonitor {
+    config: MemoryPressureConfig,
+    probe: UsageProbe,
+    sender: watch::Sender<PressureLevel>,
+}
+
+impl MemoryPressureMonitor {
+    /// Create a monitor that samples the current process
+    pub fn new(config: MemoryPressureConfig) -> Self {
+        Self::with_probe(config, MemoryUsage::current)
+    }
+
+    /// Create a monitor with a custom usage probe
+    pub fn with_probe<F>(config: MemoryPressureConfig, probe: F) -> Self
+    where
+        F: Fn() -> Option<MemoryUsage> + Send + Sync + 'static,
+    {
+        let (sender, _) = watch::channel(PressureLevel::Normal);
+        Self {
+            config,
+            probe: Box::new(probe),
+            sender,
+        }
+    }
+
+    pub fn config(&self) -> &MemoryPressureConfig {
+        &self.config
+    }
+
+    /// Current pressure level
+    pub fn level(&self) -> PressureLevel {
+        *self.sender.borrow()
+    }
+
+    /// Subscribe to pressure level changes
+    pub fn signal(&self) -> PressureSignal {
+        PressureSignal {
+            receiver: self.sender.subscribe(),
+            elevated_factor: self.config.elevated_capacity_factor,
+            critical_factor: self.config.critical_capacity_factor,
+        }
+    }
+
+    /// Sample memory once and publish the resulting level
+    pub fn check(&self) -> PressureLevel {
+        let current = self.level();
+        let Some(usage) = (self.probe)() else {
+            return current;
+        };
+
+        let ratio = usage.ratio();
+        let next = if ratio >= self.config.critical_watermark {
+            PressureLevel::Critical
+        } else if ratio >= self.config.high_watermark {
+            PressureLevel::Elevated
+        } else if ratio < self.config.low_watermark {
+            PressureLevel::Normal
+        } else {
+            // Between the watermarks: keep pressure until usage clearly drops
+            current.min(PressureLevel::Elevated)
+        };
+
+        if next != current {
+            tracing::info!(
+                "Memory pressure changed from {:?} to {:?} ({:.1}% of limit)",
+                current,
+                next,
+                ratio * 100.0
+            );
+            self.sender.send_replace(next);
+        }
+
+        next
+    }
+
+    /// Spawn a background task that samples memory every `check_interval`
+    ///
+    /// The task exits once the monitor is dropped.
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let monitor = Arc::downgrade(self);
+        let interval = self.config.check_interval;
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(monitor) = monitor.upgrade() else {
+                    break;
+                };
+                monitor.check();
+            }
+        })
+    }
+}
+
+/// Receiver side of a [`MemoryPressureMonitor`]
+#[derive(Debug, Clone)]
+pub struct PressureSignal {
+    receiver: watch::Receiver<PressureLevel>,
+    elevated_factor: f64,
+    critical_factor: f64,
+}
+
+impl PressureSignal {
+    /// Latest published pressure level
+    pub fn level(&self) -> PressureLevel {
+        *self.receiver.borrow()
+    }
+
+    /// Fraction of baseline capacity to keep at `level`
+    pub fn capacity_factor(&self, level: PressureLevel) -> f64 {
+        match level {
+            PressureLevel::Normal => 1.0,
+            PressureLevel::Elevated => self.elevated_factor,
+            PressureLevel::Critical => self.critical_factor,
+        }
+    }
+
+    /// Scale a baseline capacity for the current level
+    pub fn scaled_capacity(&self, baseline: usize) -> usize {
+        (baseline as f64 * self.capacity_factor(self.level())) as usize
+    }
+
+    /// Wait for the next level change; returns `false` once the monitor is gone
+    pub async fn changed(&mut self) -> bool {
+        self.receiver.changed().await.is_ok()
+    }
+}
+
+/// Resize `cache` whenever the pressure level changes
+///
+/// The capacity at the time of the call is the baseline restored once pressure
+/// subsides. The task exits when the monitor is dropped.
+pub fn shrink_on_pressure<C: Cache + ?Sized>(
+    cache: Arc<C>,
+    mut signal: PressureSignal,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(baseline) = cache.capacity() else {
+            tracing::warn!("Cache has no capacity limit; ignoring memory pressure");
+            return;
+        };
+
+        loop {
+            let target = signal.scaled_capacity(baseline);
+            if cache.capacity() != Some(target) {
+                match cache.resize(target).await {
+                    Ok(()) => tracing::debug!("Resized cache to {} bytes", target),
+                    Err(e) => tracing::warn!("Failed to resize cache under pressure: {:?}", e),
+                }
+            }
+
+            if !signal.changed().await {
+                break;
+            }
+        }
+    })
+}
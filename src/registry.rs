@@ -0,0 +1,111 @@
+//! Process-wide registry of named caches
+//!
+//! Components of one application that read the same data can share a cache
+//! instance, and with it a single memory and disk budget, by looking it up
+//! by name instead of each creating their own:
+//!
+//! ```rust,no_run
+//! use zarrs_cache::{registry, HybridCacheConfig};
+//!
+//! // Whichever component runs first creates the cache; later calls get it
+//! let cache = registry::get_or_create("climate", HybridCacheConfig::default()).unwrap();
+//! ```
+//!
+//! Registered caches live until they are [`remove`]d, even when no
+//! component holds them.
+
+use crate::cache::hybrid::{HybridCache, HybridCacheConfig};
+use crate::cache::Cache;
+use crate::error::CacheError;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+type Entry = Arc<dyn Any + Send + Sync>;
+
+fn caches() -> &'static Mutex<HashMap<String, Entry>> {
+    static CACHES: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    CACHES.get_or_init(Mutex::default)
+}
+
+/// The hybrid cache registered as `name`, created from `config` first if
+/// there is none
+///
+/// `config` is ignored when the cache already exists.
+pub fn get_or_create(
+    name: &str,
+    config: HybridCacheConfig,
+) -> Result<Arc<HybridCache>, CacheError> {
+    get_or_insert_with(name, || HybridCache::new(config))
+}
+
+/// The cache registered as `name`, created by `create` first if there is
+/// none
+///
+/// Fails with [`CacheError::InvalidConfig`] if `name` holds a cache of
+/// another type. The registry is locked while `create` runs, so it is
+/// called at most once per name.
+pub fn get_or_insert_with<C: Cache>(
+    name: &str,
+    create: impl FnOnce() -> Result<C, CacheError>,
+) -> Result<Arc<C>, CacheError> {
+    let mut caches = caches().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(entry) = caches.get(name) {
+        return downcast(name, entry.clone());
+    }
+    let cache = Arc::new(create()?);
+    caches.insert(name.to_string(), cache.clone());
+    Ok(cache)
+}
+
+/// Register `cache` as `name`, returning the cache it replaces
+pub fn insert<C: Cache>(name: &str, cache: Arc<C>) -> Option<Arc<dyn Any + Send + Sync>> {
+    caches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), cache)
+}
+
+/// The cache registered as `name`, if it is a `C`
+pub fn get<C: Cache>(name: &str) -> Option<Arc<C>> {
+    let entry = caches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .cloned()?;
+    entry.downcast().ok()
+}
+
+/// Unregister the cache registered as `name`
+///
+/// Components already holding the cache keep using it; the next
+/// [`get_or_create`] creates a new one.
+pub fn remove(name: &str) -> bool {
+    caches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name)
+        .is_some()
+}
+
+/// Names of the registered caches, sorted
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = caches()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    names.sort();
+    names
+}
+
+fn downcast<C: Cache>(name: &str, entry: Entry) -> Result<Arc<C>, CacheError> {
+    entry.downcast().map_err(|_| {
+        CacheError::InvalidConfig(format!(
+            "cache {:?} is registered with another type than {}",
+            name,
+            std::any::type_name::<C>()
+        ))
+    })
+}
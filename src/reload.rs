@@ -0,0 +1,267 @@
+//! Applying configuration changes to running caches
+//!
+//! [`apply`] brings a running cache or prefetch strategy in line with a
+//! changed configuration, as far as that is possible without recreating it,
+//! and reports the changed settings that only take effect on a restart. With
+//! the `hot-reload` feature, `ConfigWatcher` does this whenever a
+//! configuration file changes:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "hot-reload")]
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use std::sync::Arc;
+//! use zarrs_cache::{reload::ConfigWatcher, HybridCache, HybridCacheConfig};
+//!
+//! let config = HybridCacheConfig::from_file("cache.toml")?;
+//! let cache = Arc::new(HybridCache::new(config.clone())?);
+//! let _watcher = ConfigWatcher::spawn("cache.toml", cache.clone(), config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::cache::hybrid::{HybridCache, HybridCacheConfig};
+use crate::cache::Cache;
+use crate::config::PrefetchConfig;
+use crate::error::CacheError;
+use crate::prefetch::{NeighborChunkPrefetch, SequentialPrefetch};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Something whose configuration can partly change while it runs
+#[async_trait::async_trait]
+pub trait Reloadable: Send + Sync + 'static {
+    type Config: Serialize + DeserializeOwned + Clone + Send + Sync + 'static;
+
+    /// Settings [`Reloadable::reload`] applies; changes to any other
+    /// setting need a restart
+    const LIVE_SETTINGS: &'static [&'static str];
+
+    /// Load the configuration from `path`
+    fn load(path: &Path) -> Result<Self::Config, CacheError>;
+
+    /// Apply the `changed` live settings of `config`
+    async fn reload(&self, config: &Self::Config, changed: &[&str]) -> Result<(), CacheError>;
+}
+
+/// Apply the live settings changed from `old` to `new` to `target`,
+/// returning the names of the changed settings that need a restart
+pub async fn apply<R: Reloadable>(
+    target: &R,
+    old: &R::Config,
+    new: &R::Config,
+) -> Result<Vec<String>, CacheError> {
+    let changed = changed_settings(old, new)?;
+    let (live, restart): (Vec<&str>, Vec<&str>) = changed
+        .iter()
+        .map(String::as_str)
+        .partition(|setting| R::LIVE_SETTINGS.contains(setting));
+    if !live.is_empty() {
+        target.reload(new, &live).await?;
+        tracing::info!("Applied configuration changes to {}", live.join(", "));
+    }
+    if !restart.is_empty() {
+        tracing::warn!(
+            "Configuration changes to {} take effect after a restart",
+            restart.join(", ")
+        );
+    }
+    Ok(restart.into_iter().map(str::to_string).collect())
+}
+
+/// Names of the top-level settings that differ between `old` and `new`
+fn changed_settings<T: Serialize>(old: &T, new: &T) -> Result<Vec<String>, CacheError> {
+    let serialization = |e: serde_json::Error| CacheError::Serialization(e.to_string());
+    let old = serde_json::to_value(old).map_err(serialization)?;
+    let new = serde_json::to_value(new).map_err(serialization)?;
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return Err(CacheError::Serialization(
+            "configuration is not a map of settings".to_string(),
+        ));
+    };
+    Ok(new
+        .into_iter()
+        .filter(|(name, value)| old.get(name) != Some(value))
+        .map(|(name, _)| name)
+        .collect())
+}
+
+#[async_trait::async_trait]
+impl Reloadable for HybridCache {
+    type Config = HybridCacheConfig;
+
+    const LIVE_SETTINGS: &'static [&'static str] = &[
+        "memory_size",
+        "disk_size",
+        "ttl",
+        "memory_ttl",
+        "disk_ttl",
+        "promotion_threshold",
+        "demotion_threshold",
+    ];
+
+    fn load(path: &Path) -> Result<HybridCacheConfig, CacheError> {
+        HybridCacheConfig::from_file(path)
+    }
+
+    async fn reload(&self, config: &HybridCacheConfig, changed: &[&str]) -> Result<(), CacheError> {
+        let changed = |setting: &str| changed.contains(&setting);
+        if changed("memory_size") {
+            self.resize(config.memory_size).await?;
+        }
+        if changed("disk_size") {
+            let max_size = config.disk_size.map_or(usize::MAX, |size| size as usize);
+            self.resize_disk(max_size).await?;
+        }
+        if changed("ttl") || changed("memory_ttl") {
+            self.set_memory_ttl(config.memory_ttl.or(config.ttl))
+                .await?;
+        }
+        if changed("ttl") || changed("disk_ttl") {
+            self.set_disk_ttl(config.disk_ttl.or(config.ttl)).await?;
+        }
+        if changed("promotion_threshold") {
+            self.set_promotion_threshold(config.promotion_threshold)?;
+        }
+        if changed("demotion_threshold") {
+            self.set_demotion_threshold(config.demotion_threshold);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Reloadable for NeighborChunkPrefetch {
+    type Config = PrefetchConfig;
+
+    const LIVE_SETTINGS: &'static [&'static str] = &["neighbor_chunks", "max_queue_size"];
+
+    fn load(path: &Path) -> Result<PrefetchConfig, CacheError> {
+        PrefetchConfig::from_file(path)
+    }
+
+    async fn reload(&self, config: &PrefetchConfig, _changed: &[&str]) -> Result<(), CacheError> {
+        self.set_config(config);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Reloadable for SequentialPrefetch {
+    type Config = PrefetchConfig;
+
+    const LIVE_SETTINGS: &'static [&'static str] = &["neighbor_chunks", "max_queue_size"];
+
+    fn load(path: &Path) -> Result<PrefetchConfig, CacheError> {
+        PrefetchConfig::from_file(path)
+    }
+
+    async fn reload(&self, config: &PrefetchConfig, _changed: &[&str]) -> Result<(), CacheError> {
+        self.set_config(config);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hot-reload")]
+pub use watcher::ConfigWatcher;
+
+#[cfg(feature = "hot-reload")]
+mod watcher {
+    use super::{apply, Reloadable};
+    use crate::error::CacheError;
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    /// Wait after a change for further writes to the file before reloading
+    const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+    /// Reloads a configuration file into a running target whenever the
+    /// file changes
+    ///
+    /// The directory of the file is watched, so editors replacing the file
+    /// are noticed too. Files that fail to load or validate are logged and
+    /// skipped, keeping the running configuration. Watching stops when the
+    /// watcher is dropped.
+    pub struct ConfigWatcher {
+        _watcher: RecommendedWatcher,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl ConfigWatcher {
+        /// Watch `path` and apply its changes to `target`, which runs with
+        /// `current`
+        ///
+        /// Must be called within a Tokio runtime.
+        pub fn spawn<R: Reloadable>(
+            path: impl AsRef<Path>,
+            target: Arc<R>,
+            current: R::Config,
+        ) -> Result<Self, CacheError> {
+            let path = std::path::absolute(path.as_ref())?;
+            let dir = path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from("."));
+            let watcher_error = CacheError::backend;
+
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            let file = path.clone();
+            let mut watcher = notify::recommended_watcher(
+                move |event: notify::Result<notify::Event>| match event {
+                    Ok(event) if event.paths.iter().any(|changed| changed == &file) => {
+                        let _ = sender.send(());
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Configuration file watch failed: {}", e),
+                },
+            )
+            .map_err(watcher_error)?;
+            watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(watcher_error)?;
+
+            let task = tokio::spawn(async move {
+                let mut current = current;
+                while receiver.recv().await.is_some() {
+                    tokio::time::sleep(SETTLE_DELAY).await;
+                    while receiver.try_recv().is_ok() {}
+
+                    let config = match R::load(&path) {
+                        Ok(config) => config,
+                        Err(e) => {
+                            tracing::warn!(
+                                "Ignoring invalid configuration in {}: {}",
+                                path.display(),
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                    match apply(target.as_ref(), &current, &config).await {
+                        Ok(_) => current = config,
+                        Err(e) => tracing::warn!(
+                            "Failed to apply configuration from {}: {}",
+                            path.display(),
+                            e
+                        ),
+                    }
+                }
+            });
+
+            Ok(Self {
+                _watcher: watcher,
+                task,
+            })
+        }
+    }
+
+    impl Drop for ConfigWatcher {
+        fn drop(&mut self) {
+            self.task.abort();
+        }
+    }
+}
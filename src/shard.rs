@@ -0,0 +1,459 @@
+//! Caching of shard indexes and inner chunks of sharded zarr v3 arrays
+//!
+//! With the `sharding_indexed` codec, a stored object is a shard holding
+//! many inner chunks and an index of where each one lies. Readers fetch the
+//! index and then the byte range of each inner chunk they need, so caching
+//! whole objects either misses every time or keeps entire shards for a few
+//! chunks. A [`ShardCache`] caches the parsed index of each shard and each
+//! inner chunk on its own, so reading neighboring inner chunks fetches
+//! neither the shard nor its index again:
+//!
+//! ```rust
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use zarrs_cache::shard::{ShardCache, ShardLayout};
+//! use zarrs_cache::LruMemoryCache;
+//! use zarrs_storage::byte_range::ByteRange;
+//!
+//! let shards = ShardCache::new(LruMemoryCache::new(256 * 1024 * 1024));
+//! let layout = ShardLayout::new(vec![8, 8]);
+//! let chunk = shards
+//!     .get_inner_chunk("temperature/c/0/0", &layout, &[2, 3], |key, range: ByteRange| async move {
+//!         // A ranged GET of `range` of the shard object `key`
+//!         None
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Inner chunks are cached as stored, still encoded by the inner codecs;
+//! put a [`DecodedChunkCache`] behind the shard cache to skip decoding too.
+//!
+//! [`DecodedChunkCache`]: crate::decoded::DecodedChunkCache
+
+use crate::cache::{Cache, StoreKey};
+use crate::error::CacheError;
+use bytes::Bytes;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use zarrs_storage::byte_range::ByteRange;
+
+/// Bytes of one index entry: the offset and length of an inner chunk
+const ENTRY_BYTES: usize = 16;
+
+/// Bytes of the `crc32c` checksum that follows the index entries
+const CHECKSUM_BYTES: usize = 4;
+
+/// Offset and length of an inner chunk that is not stored
+const EMPTY: u64 = u64::MAX;
+
+/// Where a shard keeps its index
+///
+/// # Default Value
+/// - `End`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexLocation {
+    Start,
+    #[default]
+    End,
+}
+
+/// Layout of the shards of one array: the grid of inner chunks in a shard
+/// and how its index is stored
+///
+/// Indexes are read as the default index codecs store them: little-endian
+/// `bytes`, optionally followed by `crc32c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardLayout {
+    /// Inner chunks along each dimension of a shard
+    pub chunks_per_shard: Vec<u64>,
+    pub index_location: IndexLocation,
+    /// Whether the index ends with a `crc32c` checksum
+    pub index_checksum: bool,
+}
+
+impl ShardLayout {
+    /// Layout with `chunks_per_shard` inner chunks along each dimension,
+    /// and a checksummed index at the end of the shard
+    pub fn new(chunks_per_shard: Vec<u64>) -> Self {
+        Self {
+            chunks_per_shard,
+            index_location: IndexLocation::End,
+            index_checksum: true,
+        }
+    }
+
+    pub fn with_index_location(mut self, location: IndexLocation) -> Self {
+        self.index_location = location;
+        self
+    }
+
+    pub fn with_index_checksum(mut self, checksum: bool) -> Self {
+        self.index_checksum = checksum;
+        self
+    }
+
+    /// Layout of the shards of a zarrs `array`
+    ///
+    /// Fails with [`CacheError::InvalidConfig`] if the array is not sharded,
+    /// and with [`CacheError::Unsupported`] if its index codecs are not
+    /// `bytes` and `crc32c`.
+    pub fn for_array<TStorage: ?Sized>(
+        array: &zarrs::array::Array<TStorage>,
+    ) -> Result<Self, CacheError> {
+        let codecs = serde_json::to_value(array.codecs().create_metadatas())
+            .map_err(|e| CacheError::Serialization(e.to_string()))?;
+        let sharding = codecs
+            .as_array()
+            .and_then(|codecs| {
+                codecs
+                    .iter()
+                    .find(|codec| codec["name"] == "sharding_indexed")
+            })
+            .map(|codec| &codec["configuration"])
+            .ok_or_else(|| {
+                CacheError::InvalidConfig(format!("array {} is not sharded", array.path().as_str()))
+            })?;
+
+        let inner_shape = dimensions(&sharding["chunk_shape"])?;
+        let shard_shape: Vec<u64> = array
+            .chunk_shape(&vec![0; array.dimensionality()])
+            .map_err(CacheError::backend)?
+            .iter()
+            .map(|d| d.get())
+            .collect();
+        if shard_shape.len() != inner_shape.len() {
+            return Err(CacheError::InvalidConfig(format!(
+                "inner chunk shape {:?} does not match shard shape {:?}",
+                inner_shape, shard_shape
+            )));
+        }
+        let chunks_per_shard = shard_shape
+            .iter()
+            .zip(&inner_shape)
+            .map(|(shard, inner)| shard.div_ceil((*inner).max(1)))
+            .collect();
+
+        let index_location = match sharding["index_location"].as_str() {
+            None | Some("end") => IndexLocation::End,
+            Some("start") => IndexLocation::Start,
+            Some(other) => {
+                return Err(CacheError::Unsupported(format!(
+                    "shard index location {:?}",
+                    other
+                )))
+            }
+        };
+        let index_codecs: Vec<&str> = sharding["index_codecs"]
+            .as_array()
+            .map(|codecs| {
+                codecs
+                    .iter()
+                    .map(|codec| codec["name"].as_str().unwrap_or_default())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let little_endian = sharding["index_codecs"][0]["configuration"]["endian"]
+            .as_str()
+            .is_none_or(|endian| endian == "little");
+        let index_checksum = match index_codecs.as_slice() {
+            ["bytes"] if little_endian => false,
+            ["bytes", "crc32c"] if little_endian => true,
+            _ => {
+                return Err(CacheError::Unsupported(format!(
+                    "shard index codecs {:?}",
+                    index_codecs
+                )))
+            }
+        };
+
+        Ok(Self {
+            chunks_per_shard,
+            index_location,
+            index_checksum,
+        })
+    }
+
+    /// Inner chunks in a shard
+    pub fn inner_chunks(&self) -> usize {
+        self.chunks_per_shard.iter().product::<u64>() as usize
+    }
+
+    /// Bytes of the stored index
+    pub fn index_size(&self) -> u64 {
+        let checksum = if self.index_checksum {
+            CHECKSUM_BYTES
+        } else {
+            0
+        };
+        (self.inner_chunks() * ENTRY_BYTES + checksum) as u64
+    }
+
+    /// Byte range of the index in a shard
+    pub fn index_range(&self) -> ByteRange {
+        match self.index_location {
+            IndexLocation::Start => ByteRange::FromStart(0, Some(self.index_size())),
+            IndexLocation::End => ByteRange::Suffix(self.index_size()),
+        }
+    }
+
+    /// Position in the index of the inner chunk at `inner_indices` within
+    /// the shard, in row-major order
+    pub fn linear_index(&self, inner_indices: &[u64]) -> Result<usize, CacheError> {
+        if inner_indices.len() != self.chunks_per_shard.len()
+            || inner_indices
+                .iter()
+                .zip(&self.chunks_per_shard)
+                .any(|(index, count)| index >= count)
+        {
+            return Err(CacheError::InvalidKey(format!(
+                "inner chunk {:?} is outside a shard of {:?} chunks",
+                inner_indices, self.chunks_per_shard
+            )));
+        }
+        let linear = inner_indices
+            .iter()
+            .zip(&self.chunks_per_shard)
+            .fold(0, |linear, (index, count)| linear * count + index);
+        Ok(linear as usize)
+    }
+}
+
+fn dimensions(value: &Value) -> Result<Vec<u64>, CacheError> {
+    value
+        .as_array()
+        .and_then(|dims| dims.iter().map(Value::as_u64).collect())
+        .ok_or_else(|| CacheError::InvalidConfig(format!("invalid inner chunk shape {}", value)))
+}
+
+/// Parsed index of a shard: the byte range of each inner chunk, if stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardIndex {
+    entries: Vec<Option<(u64, u64)>>,
+}
+
+impl ShardIndex {
+    /// Parse the stored index `bytes` of a shard laid out as `layout`
+    ///
+    /// Fails with [`CacheError::Corruption`] if the index has the wrong
+    /// size or its checksum does not match.
+    pub fn decode(bytes: &[u8], layout: &ShardLayout) -> Result<Self, CacheError> {
+        if bytes.len() as u64 != layout.index_size() {
+            return Err(CacheError::Corruption(format!(
+                "shard index of {} bytes, expected {}",
+                bytes.len(),
+                layout.index_size()
+            )));
+        }
+        let entries_len = layout.inner_chunks() * ENTRY_BYTES;
+        let (entries, checksum) = bytes.split_at(entries_len);
+        if layout.index_checksum {
+            let stored = u32::from_le_bytes(checksum.try_into().expect("4-byte checksum"));
+            if crc32c::crc32c(entries) != stored {
+                return Err(CacheError::Corruption(
+                    "shard index checksum mismatch".to_string(),
+                ));
+            }
+        }
+
+        let entries = entries
+            .chunks_exact(ENTRY_BYTES)
+            .map(|entry| {
+                let offset = u64::from_le_bytes(entry[..8].try_into().expect("8-byte offset"));
+                let length = u64::from_le_bytes(entry[8..].try_into().expect("8-byte length"));
+                (offset != EMPTY || length != EMPTY).then_some((offset, length))
+            })
+            .collect();
+        Ok(Self { entries })
+    }
+
+    /// The index in its stored form, without a checksum
+    fn encode(&self) -> Bytes {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_BYTES);
+        for entry in &self.entries {
+            let (offset, length) = entry.unwrap_or((EMPTY, EMPTY));
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            bytes.extend_from_slice(&length.to_le_bytes());
+        }
+        Bytes::from(bytes)
+    }
+
+    /// Byte range of the inner chunk at position `index`, if it is stored
+    pub fn chunk_range(&self, index: usize) -> Option<ByteRange> {
+        let (offset, length) = (*self.entries.get(index)?)?;
+        Some(ByteRange::FromStart(offset, Some(length)))
+    }
+
+    /// Inner chunks the index covers, stored or not
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inner chunks stored in the shard
+    pub fn stored_chunks(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+}
+
+/// Hits and loads of a [`ShardCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ShardCacheStats {
+    /// Shard indexes found in the cache
+    pub index_hits: u64,
+    /// Shard indexes fetched from the store
+    pub index_loads: u64,
+    /// Inner chunks found in the cache
+    pub chunk_hits: u64,
+    /// Inner chunks fetched from the store
+    pub chunk_loads: u64,
+    /// Bytes fetched from the store, indexes included
+    pub bytes_loaded: u64,
+}
+
+/// Cache of the indexes and inner chunks of shards
+///
+/// Entries are kept in any [`Cache`] under the shard's key followed by
+/// `#index` for its index and `#<position>` for an inner chunk.
+pub struct ShardCache<C: Cache> {
+    cache: C,
+    index_hits: AtomicU64,
+    index_loads: AtomicU64,
+    chunk_hits: AtomicU64,
+    chunk_loads: AtomicU64,
+    bytes_loaded: AtomicU64,
+}
+
+impl<C: Cache> ShardCache<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            cache,
+            index_hits: AtomicU64::new(0),
+            index_loads: AtomicU64::new(0),
+            chunk_hits: AtomicU64::new(0),
+            chunk_loads: AtomicU64::new(0),
+            bytes_loaded: AtomicU64::new(0),
+        }
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+
+    /// Index of the shard `shard_key`, fetching its byte range with
+    /// `loader` on a miss
+    ///
+    /// `Ok(None)` if the shard does not exist. Indexes are cached parsed,
+    /// without their checksum.
+    pub async fn get_index<F, Fut>(
+        &self,
+        shard_key: &str,
+        layout: &ShardLayout,
+        loader: F,
+    ) -> Result<Option<ShardIndex>, CacheError>
+    where
+        F: FnOnce(StoreKey, ByteRange) -> Fut,
+        Fut: Future<Output = Option<Bytes>>,
+    {
+        let key = index_key(shard_key);
+        let cached_layout = layout.clone().with_index_checksum(false);
+        if let Some(cached) = self.cache.get(&key).await {
+            match ShardIndex::decode(&cached, &cached_layout) {
+                Ok(index) => {
+                    self.index_hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Some(index));
+                }
+                // Cached for a shard of another layout
+                Err(_) => self.cache.remove(&key).await?,
+            }
+        }
+
+        let Some(bytes) = loader(shard_key.to_string(), layout.index_range()).await else {
+            return Ok(None);
+        };
+        self.index_loads.fetch_add(1, Ordering::Relaxed);
+        self.bytes_loaded
+            .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        let index = ShardIndex::decode(&bytes, layout)?;
+        if let Err(e) = self.cache.set(&key, index.encode()).await {
+            tracing::warn!("Failed to cache index of shard {}: {:?}", shard_key, e);
+        }
+        Ok(Some(index))
+    }
+
+    /// Inner chunk at `inner_indices` of the shard `shard_key`, still
+    /// encoded, fetching the index and the chunk's byte range with `loader`
+    /// on a miss
+    ///
+    /// `Ok(None)` if the shard does not exist or does not store the chunk,
+    /// in which case it holds the fill value.
+    pub async fn get_inner_chunk<F, Fut>(
+        &self,
+        shard_key: &str,
+        layout: &ShardLayout,
+        inner_indices: &[u64],
+        loader: F,
+    ) -> Result<Option<Bytes>, CacheError>
+    where
+        F: Fn(StoreKey, ByteRange) -> Fut,
+        Fut: Future<Output = Option<Bytes>>,
+    {
+        let position = layout.linear_index(inner_indices)?;
+        let key = chunk_key(shard_key, position);
+        if let Some(chunk) = self.cache.get(&key).await {
+            self.chunk_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(chunk));
+        }
+
+        let Some(index) = self.get_index(shard_key, layout, &loader).await? else {
+            return Ok(None);
+        };
+        let Some(range) = index.chunk_range(position) else {
+            return Ok(None);
+        };
+        let Some(chunk) = loader(shard_key.to_string(), range).await else {
+            return Ok(None);
+        };
+        self.chunk_loads.fetch_add(1, Ordering::Relaxed);
+        self.bytes_loaded
+            .fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        if let Err(e) = self.cache.set(&key, chunk.clone()).await {
+            tracing::warn!("Failed to cache inner chunk {}: {:?}", key, e);
+        }
+        Ok(Some(chunk))
+    }
+
+    /// Drop the cached index and inner chunks of the shard `shard_key`,
+    /// such as after it is rewritten, returning how many were removed
+    pub async fn invalidate_shard(&self, shard_key: &str) -> Result<usize, CacheError> {
+        self.cache.remove_prefix(&format!("{}#", shard_key)).await
+    }
+
+    pub async fn clear(&self) -> Result<(), CacheError> {
+        self.cache.clear().await
+    }
+
+    /// Hits and loads so far
+    pub fn stats(&self) -> ShardCacheStats {
+        ShardCacheStats {
+            index_hits: self.index_hits.load(Ordering::Relaxed),
+            index_loads: self.index_loads.load(Ordering::Relaxed),
+            chunk_hits: self.chunk_hits.load(Ordering::Relaxed),
+            chunk_loads: self.chunk_loads.load(Ordering::Relaxed),
+            bytes_loaded: self.bytes_loaded.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn index_key(shard_key: &str) -> StoreKey {
+    format!("{}#index", shard_key)
+}
+
+fn chunk_key(shard_key: &str, position: usize) -> StoreKey {
+    format!("{}#{}", shard_key, position)
+}
@@ -0,0 +1,374 @@
+//! Offline eviction-policy simulation
+//!
+//! A [`PolicySimulator`] replays a recorded access trace (see
+//! [`crate::trace`]) against in-memory caches of several eviction policies
+//! and sizes, reporting the hit rate each would have had. Nothing is read
+//! from or written to a store, so a policy and size can be chosen before
+//! committing cluster resources:
+//!
+//! ```rust,no_run
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use zarrs_cache::simulator::PolicySimulator;
+//! use zarrs_cache::trace::read_trace;
+//!
+//! let report = PolicySimulator::new(read_trace("production.trace")?)
+//!     .with_sizes([256 << 20, 1 << 30])
+//!     .run()
+//!     .await;
+//! for result in &report.results {
+//!     println!(
+//!         "{:>8} {:>12} {:.1}%",
+//!         result.policy,
+//!         result.size_bytes,
+//!         result.hit_rate * 100.0
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! LRU, ARC and CLOCK run the crate's own memory caches, and TinyLFU the
+//! [`AdmissionCache`] in front of an LRU cache with a 1% window, so results
+//! reflect their actual behavior. LFU, which no memory cache implements,
+//! is modeled by the simulator. Values are zeroed buffers shared between
+//! entries, so simulating large caches takes little memory.
+
+use crate::cache::admission::AdmissionCache;
+use crate::cache::arc::ArcMemoryCache;
+use crate::cache::clock::ClockMemoryCache;
+use crate::cache::memory::LruMemoryCache;
+use crate::cache::{entry_footprint, Cache, CacheStats, StoreKey};
+use crate::error::CacheError;
+use crate::trace::{TraceOp, TraceRecord, TraceReplayer};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Fractions of the trace's working set simulated when no sizes are given
+const DEFAULT_SIZE_FACTORS: [f64; 5] = [0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// Share of a TinyLFU cache given to its admission window
+const TINY_LFU_WINDOW: f64 = 0.01;
+
+/// Eviction policy a [`PolicySimulator`] can simulate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SimulatedPolicy {
+    /// Least recently used ([`LruMemoryCache`])
+    Lru,
+    /// Least frequently used, ties broken by recency
+    Lfu,
+    /// Adaptive replacement ([`ArcMemoryCache`])
+    Arc,
+    /// CLOCK second chance ([`ClockMemoryCache`])
+    Clock,
+    /// W-TinyLFU: LRU behind a [`TinyLfu`](crate::TinyLfu) admission filter and window
+    TinyLfu,
+}
+
+impl SimulatedPolicy {
+    pub const ALL: [SimulatedPolicy; 5] = [
+        SimulatedPolicy::Lru,
+        SimulatedPolicy::Lfu,
+        SimulatedPolicy::Arc,
+        SimulatedPolicy::Clock,
+        SimulatedPolicy::TinyLfu,
+    ];
+
+    /// An empty cache of this policy holding up to `size_bytes`
+    fn build(self, size_bytes: usize, expected_keys: usize) -> Box<dyn Cache> {
+        match self {
+            SimulatedPolicy::Lru => Box::new(LruMemoryCache::new(size_bytes)),
+            SimulatedPolicy::Lfu => Box::new(LfuModel::new(size_bytes)),
+            SimulatedPolicy::Arc => Box::new(ArcMemoryCache::new(size_bytes)),
+            SimulatedPolicy::Clock => Box::new(ClockMemoryCache::new(size_bytes)),
+            SimulatedPolicy::TinyLfu => {
+                let window = (size_bytes as f64 * TINY_LFU_WINDOW) as usize;
+                Box::new(AdmissionCache::w_tiny_lfu(
+                    LruMemoryCache::new(size_bytes - window),
+                    expected_keys.max(1),
+                    window,
+                ))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SimulatedPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SimulatedPolicy::Lru => "lru",
+            SimulatedPolicy::Lfu => "lfu",
+            SimulatedPolicy::Arc => "arc",
+            SimulatedPolicy::Clock => "clock",
+            SimulatedPolicy::TinyLfu => "tiny_lfu",
+        })
+    }
+}
+
+/// Projected outcome of one policy at one size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationResult {
+    pub policy: SimulatedPolicy,
+    pub size_bytes: usize,
+    pub gets: u64,
+    pub hits: u64,
+    /// Share of gets that would have hit
+    pub hit_rate: f64,
+    /// Bytes hits would have served
+    pub hit_bytes: u64,
+}
+
+/// Projected hit rates of every simulated policy and size
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    /// Share of gets that hit when the trace was recorded
+    pub recorded_hit_rate: f64,
+    /// Accounted bytes of every key of the trace at its largest size
+    pub working_set_bytes: usize,
+    /// Results ordered by size, then by policy
+    pub results: Vec<SimulationResult>,
+}
+
+impl SimulationReport {
+    /// Result of the policy with the highest hit rate at `size_bytes`
+    pub fn best_at(&self, size_bytes: usize) -> Option<&SimulationResult> {
+        self.results
+            .iter()
+            .filter(|result| result.size_bytes == size_bytes)
+            .max_by(|a, b| a.hit_rate.total_cmp(&b.hit_rate))
+    }
+}
+
+/// Replays a trace against eviction policies and cache sizes
+pub struct PolicySimulator {
+    replayer: TraceReplayer,
+    policies: Vec<SimulatedPolicy>,
+    sizes: Vec<usize>,
+}
+
+impl PolicySimulator {
+    /// Simulate every policy at fractions of the trace's working set, from
+    /// 5% to all of it
+    pub fn new(records: Vec<TraceRecord>) -> Self {
+        Self {
+            replayer: TraceReplayer::new(records),
+            policies: SimulatedPolicy::ALL.to_vec(),
+            sizes: Vec::new(),
+        }
+    }
+
+    pub fn with_policies(mut self, policies: impl IntoIterator<Item = SimulatedPolicy>) -> Self {
+        self.policies = policies.into_iter().collect();
+        self
+    }
+
+    /// Cache sizes to simulate, in accounted bytes as memory caches count
+    /// them
+    pub fn with_sizes(mut self, sizes: impl IntoIterator<Item = usize>) -> Self {
+        self.sizes = sizes.into_iter().collect();
+        self
+    }
+
+    /// Accounted bytes of every key of the trace at its largest size
+    pub fn working_set_bytes(&self) -> usize {
+        self.largest_sizes()
+            .iter()
+            .map(|(key, &size)| entry_footprint(key, size as usize))
+            .sum()
+    }
+
+    /// Replay the trace against each policy at each size
+    ///
+    /// Each combination replays the whole trace from an empty cache, with
+    /// reads that hit when recorded writing their value back on a miss.
+    pub async fn run(&self) -> SimulationReport {
+        let keys = self.largest_sizes().len();
+        let working_set_bytes = self.working_set_bytes();
+        let mut sizes = if self.sizes.is_empty() {
+            DEFAULT_SIZE_FACTORS
+                .iter()
+                .map(|factor| (working_set_bytes as f64 * factor) as usize)
+                .collect()
+        } else {
+            self.sizes.clone()
+        };
+        sizes.sort_unstable();
+        sizes.dedup();
+
+        let mut results = Vec::with_capacity(sizes.len() * self.policies.len());
+        let mut recorded_hit_rate = 0.0;
+        for &size_bytes in &sizes {
+            for &policy in &self.policies {
+                let cache = policy.build(size_bytes, keys);
+                let replay = self.replayer.replay(cache.as_ref()).await;
+                recorded_hit_rate = replay.recorded_hit_rate();
+                results.push(SimulationResult {
+                    policy,
+                    size_bytes,
+                    gets: replay.gets,
+                    hits: replay.hits,
+                    hit_rate: replay.hit_rate(),
+                    hit_bytes: replay.hit_bytes,
+                });
+            }
+        }
+
+        SimulationReport {
+            recorded_hit_rate,
+            working_set_bytes,
+            results,
+        }
+    }
+
+    /// Largest recorded size of each key
+    fn largest_sizes(&self) -> HashMap<&str, u64> {
+        let mut sizes: HashMap<&str, u64> = HashMap::new();
+        for record in self.replayer.records() {
+            if record.op != TraceOp::Remove {
+                let size = sizes.entry(record.key.as_str()).or_default();
+                *size = (*size).max(record.size);
+            }
+        }
+        sizes
+    }
+}
+
+/// Byte-bounded LFU cache evicting the least frequently read entry, the
+/// least recently used among equals
+struct LfuModel {
+    max_size_bytes: usize,
+    state: Mutex<LfuState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Default)]
+struct LfuState {
+    entries: HashMap<StoreKey, LfuEntry>,
+    /// (accesses, last access, key) of every entry, next victim first
+    order: BTreeSet<(u64, u64, StoreKey)>,
+    clock: u64,
+    size: usize,
+    accounted: usize,
+}
+
+struct LfuEntry {
+    value: Bytes,
+    accesses: u64,
+    last_access: u64,
+    footprint: usize,
+}
+
+impl LfuModel {
+    fn new(max_size_bytes: usize) -> Self {
+        Self {
+            max_size_bytes,
+            state: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LfuState {
+    fn remove(&mut self, key: &str) -> Option<LfuEntry> {
+        let entry = self.entries.remove(key)?;
+        self.order
+            .remove(&(entry.accesses, entry.last_access, key.to_string()));
+        self.size -= entry.value.len();
+        self.accounted -= entry.footprint;
+        Some(entry)
+    }
+}
+
+#[async_trait::async_trait]
+impl Cache for LfuModel {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        let Some(entry) = state.entries.get_mut(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        let previous = (entry.accesses, entry.last_access, key.to_string());
+        entry.accesses += 1;
+        entry.last_access = clock;
+        let current = (entry.accesses, entry.last_access, key.to_string());
+        let value = entry.value.clone();
+        state.order.remove(&previous);
+        state.order.insert(current);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(value)
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let footprint = entry_footprint(key, value.len());
+        if footprint > self.max_size_bytes {
+            return Err(CacheError::EntryTooLarge {
+                size: footprint,
+                max: self.max_size_bytes,
+            });
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        // A rewrite keeps the count of the entry it replaces
+        let accesses = state.remove(key).map_or(1, |entry| entry.accesses);
+        while state.accounted + footprint > self.max_size_bytes {
+            let Some((_, _, victim)) = state.order.first().cloned() else {
+                break;
+            };
+            state.remove(&victim);
+        }
+        let last_access = state.clock;
+        state.size += value.len();
+        state.accounted += footprint;
+        state.order.insert((accesses, last_access, key.to_string()));
+        state.entries.insert(
+            key.to_string(),
+            LfuEntry {
+                value,
+                accesses,
+                last_access,
+                footprint,
+            },
+        );
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = LfuState::default();
+        Ok(())
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.max_size_bytes)
+    }
+
+    fn size(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).size
+    }
+
+    fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            size_bytes: state.size,
+            entry_count: state.entries.len(),
+            accounted_bytes: state.accounted,
+            ..Default::default()
+        }
+    }
+}
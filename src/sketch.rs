@@ -0,0 +1,346 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Number of hash rows in the count-min sketch
+const DEPTH: usize = 4;
+
+/// Maximum value of a single counter (4-bit counters as in TinyLFU)
+const MAX_COUNT: u8 = 15;
+
+/// Row seeds used to derive independent indexes from one key hash
+const SEEDS: [u64; DEPTH] = [
+    0xc3a5_c85c_97cb_3127,
+    0xb492_b66f_be98_f273,
+    0x9ae1_6a3b_2f90_404f,
+    0xcbf2_9ce4_8422_2325,
+];
+
+/// Approximate frequency counter with constant memory
+///
+/// A count-min sketch with small saturating counters. After `sample_size`
+/// increments every counter is halved, so estimates favour recent popularity
+/// over all-time totals (the TinyLFU "reset" operation).
+#[derive(Debug, Clone)]
+pub struct FrequencySketch {
+    table: Vec<u8>,
+    width_mask: usize,
+    additions: usize,
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    /// Create a sketch sized for roughly `expected_keys` distinct keys
+    pub fn new(expected_keys: usize) -> Self {
+        let width = expected_keys.max(16).next_power_of_two();
+        Self {
+            table: vec![0; width * DEPTH],
+            width_mask: width - 1,
+            additions: 0,
+            sample_size: width * 10,
+        }
+    }
+
+    /// Record one occurrence of `key`
+    pub fn increment(&mut self, key: &str) {
+        let hash = hash_key(key);
+        let mut incremented = false;
+        for row in 0..DEPTH {
+            let idx = index(hash, row, self.width_mask);
+            if self.table[idx] < MAX_COUNT {
+                self.table[idx] += 1;
+                incremented = true;
+            }
+        }
+
+        if incremented {
+            self.additions += 1;
+            if self.additions >= self.sample_size {
+                self.reset();
+            }
+        }
+    }
+
+    /// Estimated number of recent occurrences of `key`
+    pub fn estimate(&self, key: &str) -> u8 {
+        let hash = hash_key(key);
+        (0..DEPTH)
+            .map(|row| self.table[index(hash, row, self.width_mask)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Halve all counters to age out old popularity
+    pub fn reset(&mut self) {
+        for counter in self.table.iter_mut() {
+            *counter >>= 1;
+        }
+        self.additions /= 2;
+    }
+
+    /// Reset every counter to zero
+    pub fn clear(&mut self) {
+        self.table.iter_mut().for_each(|counter| *counter = 0);
+        self.additions = 0;
+    }
+}
+
+fn hash_key(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Position in a `DEPTH`-row table of `width_mask + 1` columns
+fn index(hash: u64, row: usize, width_mask: usize) -> usize {
+    let mixed = (hash ^ SEEDS[row]).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    let slot = (mixed >> 32) as usize & width_mask;
+    row * (width_mask + 1) + slot
+}
+
+/// Approximate all-time occurrence counter with constant memory
+///
+/// A count-min sketch with full-width counters and no aging. Estimates
+/// never undercount; with conservative updates they overcount by a small
+/// fraction of the total count.
+#[derive(Debug, Clone)]
+pub struct CountMinSketch {
+    table: Vec<u64>,
+    width_mask: usize,
+}
+
+impl CountMinSketch {
+    /// Create a sketch sized for roughly `expected_keys` distinct keys
+    pub fn new(expected_keys: usize) -> Self {
+        let width = expected_keys.max(16).next_power_of_two();
+        Self {
+            table: vec![0; width * DEPTH],
+            width_mask: width - 1,
+        }
+    }
+
+    /// Record `count` occurrences of `key` and return its new estimate
+    pub fn add(&mut self, key: &str, count: u64) -> u64 {
+        let hash = hash_key(key);
+        let slots: [usize; DEPTH] = std::array::from_fn(|row| index(hash, row, self.width_mask));
+        let estimate = slots
+            .iter()
+            .map(|&slot| self.table[slot])
+            .min()
+            .unwrap_or(0)
+            + count;
+        // Conservative update: only raise counters below the new estimate
+        for slot in slots {
+            self.table[slot] = self.table[slot].max(estimate);
+        }
+        estimate
+    }
+
+    /// Estimated number of occurrences of `key`
+    pub fn estimate(&self, key: &str) -> u64 {
+        let hash = hash_key(key);
+        (0..DEPTH)
+            .map(|row| self.table[index(hash, row, self.width_mask)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Reset every counter to zero
+    pub fn clear(&mut self) {
+        self.table.iter_mut().for_each(|counter| *counter = 0);
+    }
+}
+
+/// The most frequent keys of a stream, in constant memory
+///
+/// Every key is counted in a [`CountMinSketch`]; the `capacity` keys with
+/// the highest estimates are also kept by name, so the heaviest hitters can
+/// be listed without storing every key seen.
+#[derive(Debug, Clone)]
+pub struct TopK {
+    sketch: CountMinSketch,
+    capacity: usize,
+    counts: HashMap<String, u64>,
+    /// `counts` ordered by count, lowest first
+    ranked: BTreeSet<(u64, String)>,
+    total: u64,
+}
+
+impl TopK {
+    /// Track the `capacity` most frequent keys
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            // Wide enough that tail keys rarely inflate the tracked counts
+            sketch: CountMinSketch::new(capacity * 8),
+            capacity,
+            counts: HashMap::with_capacity(capacity),
+            ranked: BTreeSet::new(),
+            total: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Record one occurrence of `key` and return its estimated count
+    pub fn increment(&mut self, key: &str) -> u64 {
+        self.total += 1;
+        let estimate = self.sketch.add(key, 1);
+        if let Some(count) = self.counts.get_mut(key) {
+            self.ranked.remove(&(*count, key.to_string()));
+            *count = estimate;
+            self.ranked.insert((estimate, key.to_string()));
+            return estimate;
+        }
+
+        if self.counts.len() >= self.capacity {
+            match self.ranked.first() {
+                Some((lowest, _)) if *lowest < estimate => {
+                    if let Some((_, evicted)) = self.ranked.pop_first() {
+                        self.counts.remove(&evicted);
+                    }
+                }
+                _ => return estimate,
+            }
+        }
+        self.counts.insert(key.to_string(), estimate);
+        self.ranked.insert((estimate, key.to_string()));
+        estimate
+    }
+
+    /// Estimated count of `key`, whether or not it is tracked
+    pub fn estimate(&self, key: &str) -> u64 {
+        self.sketch.estimate(key)
+    }
+
+    /// Estimated count of `key` if it is among the tracked keys
+    pub fn get(&self, key: &str) -> Option<u64> {
+        self.counts.get(key).copied()
+    }
+
+    /// Up to `n` tracked keys with their counts, most frequent first
+    pub fn top(&self, n: usize) -> Vec<(String, u64)> {
+        self.ranked
+            .iter()
+            .rev()
+            .take(n)
+            .map(|(count, key)| (key.clone(), *count))
+            .collect()
+    }
+
+    /// Tracked keys with their counts, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counts
+            .iter()
+            .map(|(key, count)| (key.as_str(), *count))
+    }
+
+    /// Number of tracked keys
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Occurrences recorded, of tracked and untracked keys alike
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn clear(&mut self) {
+        self.sketch.clear();
+        self.counts.clear();
+        self.ranked.clear();
+        self.total = 0;
+    }
+}
+
+#[cfg(test)]
+mod frequency_sketch_tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_track_increments() {
+        let mut sketch = FrequencySketch::new(128);
+        assert_eq!(sketch.estimate("array/0.0"), 0);
+
+        for _ in 0..3 {
+            sketch.increment("array/0.0");
+        }
+        sketch.increment("array/0.1");
+
+        assert!(sketch.estimate("array/0.0") >= 3);
+        assert!(sketch.estimate("array/0.1") >= 1);
+        assert!(sketch.estimate("array/0.0") > sketch.estimate("array/9.9"));
+    }
+
+    #[test]
+    fn test_counters_saturate() {
+        let mut sketch = FrequencySketch::new(16);
+        for _ in 0..100 {
+            sketch.increment("hot");
+        }
+        assert!(sketch.estimate("hot") <= MAX_COUNT);
+    }
+
+    #[test]
+    fn test_reset_halves_counts() {
+        let mut sketch = FrequencySketch::new(16);
+        for _ in 0..8 {
+            sketch.increment("key");
+        }
+        let before = sketch.estimate("key");
+        sketch.reset();
+        assert_eq!(sketch.estimate("key"), before / 2);
+
+        sketch.clear();
+        assert_eq!(sketch.estimate("key"), 0);
+    }
+}
+
+#[cfg(test)]
+mod top_k_tests {
+    use super::*;
+
+    #[test]
+    fn test_count_min_never_undercounts() {
+        let mut sketch = CountMinSketch::new(16);
+        for i in 0..1000 {
+            sketch.add(&format!("key{}", i % 100), 1);
+        }
+        for i in 0..100 {
+            assert!(sketch.estimate(&format!("key{}", i)) >= 10);
+        }
+        assert_eq!(sketch.add("key0", 5), sketch.estimate("key0"));
+    }
+
+    #[test]
+    fn test_top_k_keeps_heavy_hitters_in_constant_memory() {
+        let mut top = TopK::new(3);
+        for round in 0..100 {
+            top.increment("hot");
+            if round % 2 == 0 {
+                top.increment("warm");
+            }
+            // A stream of keys seen once each
+            top.increment(&format!("cold{}", round));
+        }
+
+        assert_eq!(top.len(), 3);
+        assert_eq!(top.total(), 250);
+        let ranked = top.top(2);
+        assert_eq!(ranked[0], ("hot".to_string(), 100));
+        assert_eq!(ranked[1].0, "warm");
+        assert!(ranked[1].1 >= 50);
+        assert!(top.get("cold0").is_none());
+        assert!(top.estimate("cold0") >= 1);
+
+        top.clear();
+        assert!(top.is_empty());
+        assert_eq!(top.estimate("hot"), 0);
+    }
+}
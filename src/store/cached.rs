@@ -1,9 +1,36 @@
 use crate::cache::Cache;
 use crate::config::CacheConfig;
+use crate::error::CacheError;
+use crate::store::listing::{Listing, ListingCache, ListingKey, ListingStats};
+use crate::upstream::{self, BreakerState, CircuitBreaker, RateLimiter};
 use bytes::Bytes;
+use std::future::Future;
 use std::sync::Arc;
+use tokio::sync::RwLock;
+use zarrs_storage::byte_range::{ByteRange, InvalidByteRangeError};
+use zarrs_storage::{
+    AsyncBytes, AsyncListableStorageTraits, AsyncReadableStorageTraits, AsyncWritableStorageTraits,
+    ListableStorageTraits, MaybeAsyncBytes, StorageError, StoreKey, StoreKeyOffsetValue, StoreKeys,
+    StoreKeysPrefixes, StorePrefix,
+};
 
 /// A generic caching wrapper that can work with any storage backend
+///
+/// Over a zarrs async store, `CachedStore` is itself an
+/// [`AsyncReadableStorageTraits`] store, so async zarrs arrays can be opened
+/// on it directly. Whole values read through it are cached; ranged reads
+/// are sliced from a cached value, or passed to the store. Over a listable
+/// store, sync or async, listings are cached for
+/// [`CacheConfig::listing_ttl`]. Over an async writable store, writes go
+/// to the store and then update or invalidate the cached entries and
+/// listings they touch.
+///
+/// Only async writes invalidate: `CachedStore` does not implement the sync
+/// [`WritableStorageTraits`](zarrs_storage::WritableStorageTraits), and
+/// writes made to the wrapped store directly, sync or async, are not seen.
+/// Entries and listings they replace stay cached until they expire or are
+/// dropped with [`remove_cached`](Self::remove_cached) and
+/// [`invalidate_listings`](Self::invalidate_listings).
 pub struct CachedStore<S, C>
 where
     S: Send + Sync + 'static,
@@ -12,6 +39,12 @@ where
     inner: Arc<S>,
     cache: Arc<C>,
     config: CacheConfig,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    circuit_breaker: Option<Arc<CircuitBreaker>>,
+    listings: ListingCache,
+    /// Count of writes through the store; reads that raced a write do not
+    /// cache what they read
+    writes: RwLock<u64>,
 }
 
 impl<S, C> CachedStore<S, C>
@@ -23,10 +56,43 @@ where
         Self {
             inner: Arc::new(store),
             cache: Arc::new(cache),
+            listings: ListingCache::new(config.listing_ttl),
             config,
+            rate_limiter: None,
+            circuit_breaker: None,
+            writes: RwLock::new(0),
         }
     }
 
+    /// Let [`get_or_load`](Self::get_or_load) load only as fast as
+    /// `limiter` allows
+    ///
+    /// Share the limiter with the prefetch strategies and the cache warmer
+    /// to cap all loads from the store together.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    pub fn rate_limiter(&self) -> Option<&Arc<RateLimiter>> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Let [`get_or_load`](Self::get_or_load) answer from cached copies,
+    /// expired ones included, while `breaker` is open and its
+    /// configuration allows stale reads
+    ///
+    /// The loaders passed to `get_or_load` should load through the same
+    /// breaker, as made by [`CircuitBreaker::wrap`].
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&Arc<CircuitBreaker>> {
+        self.circuit_breaker.as_ref()
+    }
+
     pub fn cache_stats(&self) -> crate::cache::CacheStats {
         self.cache.stats()
     }
@@ -66,7 +132,7 @@ where
         }
 
         // Check cache first
-        if let Some(cached_data) = self.cache.get(&key.to_string()).await {
+        if let Some(cached_data) = self.cache.get(key).await {
             tracing::debug!("Cache HIT for key: {}", key);
             return Some(cached_data);
         }
@@ -82,18 +148,359 @@ where
         value: Bytes,
     ) -> Result<(), crate::error::CacheError> {
         if self.should_cache_key(key) {
-            self.cache.set(&key.to_string(), value).await?;
+            self.cache.set(key, value).await?;
         }
         Ok(())
     }
 
+    /// Get data with caching, loading it with `loader` and caching it on a
+    /// miss
+    ///
+    /// A value that fails to be cached is still returned.
+    pub async fn get_or_load<F, Fut>(&self, key: &str, loader: F) -> Option<Bytes>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Option<Bytes>>,
+    {
+        if let Some(data) = self.get_stale_while_open(key).await {
+            return Some(data);
+        }
+        if let Some(data) = self.get_cached(key).await {
+            return Some(data);
+        }
+
+        let data =
+            upstream::limited_load(self.rate_limiter.as_ref(), &loader, key.to_string()).await?;
+        if let Err(e) = self.set_cached(key, data.clone()).await {
+            tracing::warn!("Failed to cache loaded key {}: {:?}", key, e);
+        }
+        Some(data)
+    }
+
+    /// Cached copy of `key`, expired or not, while the circuit breaker
+    /// keeps the origin store out of reach
+    async fn get_stale_while_open(&self, key: &str) -> Option<Bytes> {
+        let breaker = self.circuit_breaker.as_ref()?;
+        if !breaker.config().serve_stale
+            || breaker.state() == BreakerState::Closed
+            || !self.should_cache_key(key)
+        {
+            return None;
+        }
+        let data = self.cache.get_stale(key).await?;
+        tracing::debug!(
+            "Serving cached key {} while the origin store is unavailable",
+            key
+        );
+        breaker.record_stale_read();
+        Some(data)
+    }
+
     /// Remove data from cache
     pub async fn remove_cached(&self, key: &str) -> Result<(), crate::error::CacheError> {
-        self.cache.remove(&key.to_string()).await
+        self.cache.remove(key).await
     }
 
     /// Clear all cached data
     pub async fn clear_cache(&self) -> Result<(), crate::error::CacheError> {
+        self.listings.clear();
         self.cache.clear().await
     }
+
+    /// Forget cached listings, so the next ones come from the store
+    pub fn invalidate_listings(&self) {
+        self.listings.clear();
+    }
+
+    pub fn listing_stats(&self) -> ListingStats {
+        self.listings.stats()
+    }
+
+    /// Listing of `key`, cached or listed by `list`
+    fn list_through<T>(
+        &self,
+        key: ListingKey,
+        list: impl FnOnce() -> Result<T, StorageError>,
+    ) -> Result<T, StorageError>
+    where
+        T: Clone + Into<Listing> + TryFrom<Listing>,
+    {
+        if let Some(listing) = self.listings.get(&key).and_then(|l| l.try_into().ok()) {
+            return Ok(listing);
+        }
+        let listing = list()?;
+        self.listings.insert(key, listing.clone().into());
+        Ok(listing)
+    }
+
+    /// Listing of `key`, cached or listed by the future `list`
+    async fn list_through_async<T, Fut>(
+        &self,
+        key: ListingKey,
+        list: Fut,
+    ) -> Result<T, StorageError>
+    where
+        T: Clone + Into<Listing> + TryFrom<Listing>,
+        Fut: Future<Output = Result<T, StorageError>>,
+    {
+        if let Some(listing) = self.listings.get(&key).and_then(|l| l.try_into().ok()) {
+            return Ok(listing);
+        }
+        let listing = list.await?;
+        self.listings.insert(key, listing.clone().into());
+        Ok(listing)
+    }
+
+    /// Replace the cached copy of a key written to the store with `value`,
+    /// or drop it when `value` is `None`
+    ///
+    /// Runs with reads kept from caching, so none caches what the store
+    /// held before the write.
+    async fn update_written(
+        &self,
+        key: &StoreKey,
+        value: Option<Bytes>,
+    ) -> Result<(), StorageError> {
+        let mut writes = self.writes.write().await;
+        *writes += 1;
+        self.listings.invalidate_key(key.as_str());
+        let updated = match value {
+            Some(value) if self.should_cache_key(key.as_str()) => {
+                match self.cache.set(key.as_str(), value).await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!("Failed to cache written key {}: {:?}", key, e);
+                        false
+                    }
+                }
+            }
+            _ => false,
+        };
+        if !updated {
+            self.cache
+                .remove(key.as_str())
+                .await
+                .map_err(|e| invalidation_error(key.as_str(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Drop the cached copies of keys under a prefix erased from the store
+    async fn update_erased_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        let mut writes = self.writes.write().await;
+        *writes += 1;
+        self.listings.invalidate_prefix(prefix.as_str());
+        match self.cache.remove_prefix(prefix.as_str()).await {
+            Ok(_) => Ok(()),
+            // Caches without prefix removal are cleared instead
+            Err(CacheError::Unsupported(_)) => self.cache.clear().await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| invalidation_error(prefix.as_str(), e))
+    }
+
+    /// Fetch a value from the store through the rate limiter and the
+    /// circuit breaker, charging its bytes to the limiter
+    async fn fetch<Fut>(&self, key: &str, fetch: Fut) -> Result<MaybeAsyncBytes, StorageError>
+    where
+        Fut: Future<Output = Result<MaybeAsyncBytes, StorageError>>,
+    {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let data = match &self.circuit_breaker {
+            Some(breaker) => breaker
+                .load(
+                    |_| async { fetch.await.map_err(CacheError::backend) },
+                    key.to_string(),
+                )
+                .await
+                .map_err(|e| StorageError::Other(e.to_string()))?,
+            None => fetch.await?,
+        };
+        if let (Some(limiter), Some(data)) = (&self.rate_limiter, &data) {
+            limiter.record_bytes(data.len());
+        }
+        Ok(data)
+    }
+}
+
+/// Error of a write that reached the store but left a stale cached copy
+fn invalidation_error(key: &str, error: CacheError) -> StorageError {
+    StorageError::Other(format!(
+        "{} was written, but its cached copy could not be invalidated: {}",
+        key, error
+    ))
+}
+
+/// `byte_ranges` of a cached value, sharing its buffer
+pub(crate) fn slice_byte_ranges(
+    data: &Bytes,
+    byte_ranges: &[ByteRange],
+) -> Result<Vec<AsyncBytes>, StorageError> {
+    let size = data.len() as u64;
+    byte_ranges
+        .iter()
+        .map(|range| {
+            if range.start(size) > size || range.end(size) > size {
+                return Err(InvalidByteRangeError::new(*range, size).into());
+            }
+            Ok(data.slice(range.to_range_usize(size)))
+        })
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl<S, C> AsyncReadableStorageTraits for CachedStore<S, C>
+where
+    S: AsyncReadableStorageTraits + 'static,
+    C: Cache,
+{
+    async fn get(&self, key: &StoreKey) -> Result<MaybeAsyncBytes, StorageError> {
+        if let Some(data) = self.get_stale_while_open(key.as_str()).await {
+            return Ok(Some(data));
+        }
+        if let Some(data) = self.get_cached(key.as_str()).await {
+            return Ok(Some(data));
+        }
+
+        let generation = *self.writes.read().await;
+        let data = self.fetch(key.as_str(), self.inner.get(key)).await?;
+        if let Some(data) = &data {
+            // A write since the fetch may have replaced what was read
+            let writes = self.writes.read().await;
+            if *writes == generation {
+                if let Err(e) = self.set_cached(key.as_str(), data.clone()).await {
+                    tracing::warn!("Failed to cache loaded key {}: {:?}", key, e);
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    async fn get_partial_values_key(
+        &self,
+        key: &StoreKey,
+        byte_ranges: &[ByteRange],
+    ) -> Result<Option<Vec<AsyncBytes>>, StorageError> {
+        if let Some(data) = self.get_stale_while_open(key.as_str()).await {
+            return slice_byte_ranges(&data, byte_ranges).map(Some);
+        }
+        if let Some(data) = self.get_cached(key.as_str()).await {
+            return slice_byte_ranges(&data, byte_ranges).map(Some);
+        }
+
+        // Ranges are not cached, as they cannot answer reads of other ranges
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+        let values = self.inner.get_partial_values_key(key, byte_ranges).await?;
+        if let (Some(limiter), Some(values)) = (&self.rate_limiter, &values) {
+            limiter.record_bytes(values.iter().map(Bytes::len).sum());
+        }
+        Ok(values)
+    }
+
+    async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
+        if let Some(data) = self.get_cached(key.as_str()).await {
+            return Ok(Some(data.len() as u64));
+        }
+        self.inner.size_key(key).await
+    }
+}
+
+impl<S, C> ListableStorageTraits for CachedStore<S, C>
+where
+    S: ListableStorageTraits + 'static,
+    C: Cache,
+{
+    fn list(&self) -> Result<StoreKeys, StorageError> {
+        self.list_through(ListingKey::All, || self.inner.list())
+    }
+
+    fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        self.list_through(ListingKey::Prefix(prefix.clone()), || {
+            self.inner.list_prefix(prefix)
+        })
+    }
+
+    fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        self.list_through(ListingKey::Dir(prefix.clone()), || {
+            self.inner.list_dir(prefix)
+        })
+    }
+
+    fn size_prefix(&self, prefix: &StorePrefix) -> Result<u64, StorageError> {
+        self.list_through(ListingKey::SizePrefix(prefix.clone()), || {
+            self.inner.size_prefix(prefix)
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, C> AsyncListableStorageTraits for CachedStore<S, C>
+where
+    S: AsyncListableStorageTraits + 'static,
+    C: Cache,
+{
+    async fn list(&self) -> Result<StoreKeys, StorageError> {
+        self.list_through_async(ListingKey::All, self.inner.list())
+            .await
+    }
+
+    async fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        self.list_through_async(
+            ListingKey::Prefix(prefix.clone()),
+            self.inner.list_prefix(prefix),
+        )
+        .await
+    }
+
+    async fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        self.list_through_async(ListingKey::Dir(prefix.clone()), self.inner.list_dir(prefix))
+            .await
+    }
+
+    async fn size_prefix(&self, prefix: &StorePrefix) -> Result<u64, StorageError> {
+        self.list_through_async(
+            ListingKey::SizePrefix(prefix.clone()),
+            self.inner.size_prefix(prefix),
+        )
+        .await
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, C> AsyncWritableStorageTraits for CachedStore<S, C>
+where
+    S: AsyncWritableStorageTraits + 'static,
+    C: Cache,
+{
+    async fn set(&self, key: &StoreKey, value: AsyncBytes) -> Result<(), StorageError> {
+        self.inner.set(key, value.clone()).await?;
+        self.update_written(key, Some(value)).await
+    }
+
+    async fn set_partial_values(
+        &self,
+        key_offset_values: &[StoreKeyOffsetValue],
+    ) -> Result<(), StorageError> {
+        let result = self.inner.set_partial_values(key_offset_values).await;
+        // Partly written keys are dropped even if the write failed midway
+        for key_offset_value in key_offset_values {
+            self.update_written(key_offset_value.key(), None).await?;
+        }
+        result
+    }
+
+    async fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        self.inner.erase(key).await?;
+        self.update_written(key, None).await
+    }
+
+    async fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        let result = self.inner.erase_prefix(prefix).await;
+        self.update_erased_prefix(prefix).await?;
+        result
+    }
 }
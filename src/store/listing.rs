@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zarrs_storage::{StoreKeys, StoreKeysPrefixes, StorePrefix};
+
+/// Listing requests of the store, by method and prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ListingKey {
+    All,
+    Prefix(StorePrefix),
+    Dir(StorePrefix),
+    SizePrefix(StorePrefix),
+}
+
+impl ListingKey {
+    fn prefix(&self) -> &str {
+        match self {
+            ListingKey::All => "",
+            ListingKey::Prefix(prefix)
+            | ListingKey::Dir(prefix)
+            | ListingKey::SizePrefix(prefix) => prefix.as_str(),
+        }
+    }
+
+    /// Whether the listing may include `key`
+    fn covers(&self, key: &str) -> bool {
+        key.starts_with(self.prefix())
+    }
+}
+
+/// Result of a listing request
+#[derive(Debug, Clone)]
+pub(crate) enum Listing {
+    Keys(StoreKeys),
+    KeysPrefixes(StoreKeysPrefixes),
+    Size(u64),
+}
+
+impl From<StoreKeys> for Listing {
+    fn from(keys: StoreKeys) -> Self {
+        Listing::Keys(keys)
+    }
+}
+
+impl From<StoreKeysPrefixes> for Listing {
+    fn from(keys_prefixes: StoreKeysPrefixes) -> Self {
+        Listing::KeysPrefixes(keys_prefixes)
+    }
+}
+
+impl From<u64> for Listing {
+    fn from(size: u64) -> Self {
+        Listing::Size(size)
+    }
+}
+
+impl TryFrom<Listing> for StoreKeys {
+    type Error = Listing;
+
+    fn try_from(listing: Listing) -> Result<Self, Listing> {
+        match listing {
+            Listing::Keys(keys) => Ok(keys),
+            other => Err(other),
+        }
+    }
+}
+
+impl TryFrom<Listing> for StoreKeysPrefixes {
+    type Error = Listing;
+
+    fn try_from(listing: Listing) -> Result<Self, Listing> {
+        match listing {
+            Listing::KeysPrefixes(keys_prefixes) => Ok(keys_prefixes),
+            other => Err(other),
+        }
+    }
+}
+
+impl TryFrom<Listing> for u64 {
+    type Error = Listing;
+
+    fn try_from(listing: Listing) -> Result<Self, Listing> {
+        match listing {
+            Listing::Size(size) => Ok(size),
+            other => Err(other),
+        }
+    }
+}
+
+/// Listing requests answered from and passed on by a
+/// [`CachedStore`](crate::CachedStore)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingStats {
+    /// Listings answered from the cache
+    pub hits: u64,
+    /// Listings passed on to the store
+    pub misses: u64,
+    /// Listings cached now, expired ones included until they are replaced
+    pub cached: usize,
+}
+
+/// Short-lived cache of store listings
+///
+/// Listings of large hierarchies on object stores are slow and repeated
+/// by discovery tooling, but go stale as soon as keys are written, so they
+/// are kept for a short TTL only.
+#[derive(Debug)]
+pub(crate) struct ListingCache {
+    ttl: Option<Duration>,
+    entries: Mutex<HashMap<ListingKey, (Instant, Listing)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ListingCache {
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cached result of `key`, if it is younger than the TTL
+    pub(crate) fn get(&self, key: &ListingKey) -> Option<Listing> {
+        let fresh = self.ttl.and_then(|ttl| {
+            let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            entries
+                .get(key)
+                .filter(|(listed, _)| listed.elapsed() < ttl)
+                .map(|(_, listing)| listing.clone())
+        });
+        let counter = if fresh.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        fresh
+    }
+
+    /// Cache the result of `key`, dropping expired listings
+    pub(crate) fn insert(&self, key: ListingKey, listing: Listing) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (listed, _)| listed.elapsed() < ttl);
+        entries.insert(key, (Instant::now(), listing));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Drop the listings that may include `key`
+    pub(crate) fn invalidate_key(&self, key: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|listing_key, _| !listing_key.covers(key));
+    }
+
+    /// Drop the listings that may include keys under `prefix`
+    pub(crate) fn invalidate_prefix(&self, prefix: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|listing_key, _| {
+                !listing_key.covers(prefix) && !listing_key.prefix().starts_with(prefix)
+            });
+    }
+
+    pub(crate) fn stats(&self) -> ListingStats {
+        ListingStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            cached: self.entries.lock().unwrap_or_else(|e| e.into_inner()).len(),
+        }
+    }
+}
@@ -1 +1,3 @@
 pub mod cached;
+pub mod listing;
+pub mod pack;
@@ -0,0 +1,424 @@
+use crate::error::CacheError;
+use crate::store::cached::slice_byte_ranges;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use zarrs_storage::byte_range::ByteRange;
+use zarrs_storage::{
+    AsyncBytes, AsyncListableStorageTraits, AsyncReadableStorageTraits, ListableStorageTraits,
+    MaybeAsyncBytes, MaybeBytes, ReadableStorageTraits, StorageError, StoreKey, StoreKeys,
+    StoreKeysPrefixes, StorePrefix,
+};
+
+/// Whole contents of `file`, memory-mapped
+#[cfg(not(target_arch = "wasm32"))]
+fn map_file(file: File) -> std::io::Result<Bytes> {
+    // SAFETY: pack files are written to a temp file and renamed into
+    // place, never modified or truncated afterwards, so the mapped
+    // contents cannot change underneath the returned `Bytes`
+    Ok(Bytes::from_owner(unsafe { memmap2::Mmap::map(&file) }?))
+}
+
+/// Whole contents of `file`, read into memory where it cannot be mapped
+#[cfg(target_arch = "wasm32")]
+fn map_file(mut file: File) -> std::io::Result<Bytes> {
+    use std::io::Read;
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    Ok(Bytes::from(buf))
+}
+
+/// Magic bytes opening a pack file
+const PACK_MAGIC: &[u8; 4] = b"ZCDP";
+
+/// Current pack format
+const PACK_FORMAT_VERSION: u8 = 1;
+
+/// Fixed header: magic, version, reserved, entry count, index offset and
+/// index length
+const PACK_HEADER_LEN: usize = 4 + 1 + 3 + 8 + 8 + 8;
+
+/// Values start at multiples of this, so decoded views of them are aligned
+const VALUE_ALIGNMENT: u64 = 8;
+
+/// Values fetched at once while downloading a dataset
+const DOWNLOAD_CONCURRENCY: usize = 16;
+
+/// Location of a value in the pack file
+#[derive(Debug, Clone, Copy)]
+struct PackEntry {
+    offset: usize,
+    len: usize,
+}
+
+/// Read-only, memory-mapped copy of a whole zarr dataset
+///
+/// Small reference datasets read by every job, such as land masks and
+/// climatologies, are downloaded once into a pack file with
+/// [`download`](DatasetPack::download), and every later read is a slice of
+/// the map, with no further traffic to the origin store. The pack is itself
+/// a readable and listable zarrs store, sync and async, so arrays are opened
+/// on it directly.
+///
+/// Pack files hold the values at their store keys, checksummed; every value
+/// is verified when the pack is opened.
+#[derive(Debug)]
+pub struct DatasetPack {
+    path: PathBuf,
+    data: Bytes,
+    entries: BTreeMap<String, PackEntry>,
+}
+
+impl DatasetPack {
+    /// Open the pack file at `path`, verifying its index and values
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let data = if file.metadata()?.len() == 0 {
+            Bytes::new()
+        } else {
+            map_file(file)?
+        };
+        let entries = decode_index(&data)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            data,
+            entries,
+        })
+    }
+
+    /// Write `values` by key into a pack file at `path` and open it
+    ///
+    /// The file is written next to `path` and renamed into place, so
+    /// processes opening the pack meanwhile never see it half written.
+    pub fn write(
+        path: impl AsRef<Path>,
+        values: impl IntoIterator<Item = (StoreKey, Bytes)>,
+    ) -> Result<Self, CacheError> {
+        let mut writer = PackWriter::create(path.as_ref())?;
+        for (key, value) in values {
+            writer.append(key.as_str(), &value)?;
+        }
+        writer.finish()?;
+        Self::open(path)
+    }
+
+    /// Download every value under `prefix` of `store` into a pack file at
+    /// `path` and open it
+    pub async fn download<S>(
+        store: &S,
+        prefix: &StorePrefix,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, CacheError>
+    where
+        S: AsyncReadableStorageTraits + AsyncListableStorageTraits + ?Sized,
+    {
+        let keys = store
+            .list_prefix(prefix)
+            .await
+            .map_err(CacheError::backend)?;
+        let mut writer = PackWriter::create(path.as_ref())?;
+        let mut values = futures::stream::iter(keys)
+            .map(|key| async move {
+                let value = store.get(&key).await.map_err(CacheError::backend)?;
+                Ok::<_, CacheError>((key, value))
+            })
+            .buffered(DOWNLOAD_CONCURRENCY);
+        while let Some((key, value)) = values.try_next().await? {
+            // Erased since it was listed
+            if let Some(value) = value {
+                writer.append(key.as_str(), &value)?;
+            }
+        }
+        writer.finish()?;
+        tracing::info!(
+            "Downloaded {} into dataset pack {}",
+            prefix,
+            path.as_ref().display()
+        );
+        Self::open(path)
+    }
+
+    /// Open the pack file at `path`, or [`download`](DatasetPack::download)
+    /// it first if it is missing or unreadable
+    pub async fn open_or_download<S>(
+        store: &S,
+        prefix: &StorePrefix,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, CacheError>
+    where
+        S: AsyncReadableStorageTraits + AsyncListableStorageTraits + ?Sized,
+    {
+        match Self::open(path.as_ref()) {
+            Ok(pack) => Ok(pack),
+            Err(CacheError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::download(store, prefix, path).await
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Downloading dataset pack {} again: {}",
+                    path.as_ref().display(),
+                    e
+                );
+                Self::download(store, prefix, path).await
+            }
+        }
+    }
+
+    /// Path of the pack file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of values in the pack
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of the values in the pack, in bytes
+    pub fn size_bytes(&self) -> u64 {
+        self.entries.values().map(|entry| entry.len as u64).sum()
+    }
+
+    /// Value of `key`, sharing the map
+    pub fn get_value(&self, key: &str) -> Option<Bytes> {
+        self.entries
+            .get(key)
+            .map(|entry| self.data.slice(entry.offset..entry.offset + entry.len))
+    }
+
+    /// Keys under `prefix`, in order
+    fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(key, _)| key.as_str())
+            .take_while(move |key| key.starts_with(prefix))
+    }
+}
+
+/// Parse and verify the index and values of a pack file
+fn decode_index(data: &[u8]) -> Result<BTreeMap<String, PackEntry>, CacheError> {
+    let corrupt = |message: &str| CacheError::Corruption(format!("dataset pack: {}", message));
+    let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+    let u64_at = |at: usize| u64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+
+    if data.len() < PACK_HEADER_LEN || &data[..4] != PACK_MAGIC {
+        return Err(corrupt("not a pack file"));
+    }
+    if data[4] != PACK_FORMAT_VERSION {
+        return Err(corrupt(&format!("unsupported format version {}", data[4])));
+    }
+    let count = u64_at(8) as usize;
+    let index_offset = u64_at(16) as usize;
+    let index_len = u64_at(24) as usize;
+    let index_end = index_offset
+        .checked_add(index_len)
+        .filter(|end| end.checked_add(4) == Some(data.len()))
+        .ok_or_else(|| corrupt("index out of bounds"))?;
+    if crc32c::crc32c(&data[index_offset..index_end]) != u32_at(index_end) {
+        return Err(corrupt("index checksum mismatch"));
+    }
+
+    let mut entries = BTreeMap::new();
+    let mut at = index_offset;
+    for _ in 0..count {
+        if at + 4 > index_end {
+            return Err(corrupt("truncated index"));
+        }
+        let key_len = u32_at(at) as usize;
+        let fields = at + 4 + key_len;
+        if fields + 20 > index_end {
+            return Err(corrupt("truncated index"));
+        }
+        let key = std::str::from_utf8(&data[at + 4..fields])
+            .map_err(|_| corrupt("key is not UTF-8"))?
+            .to_string();
+        let entry = PackEntry {
+            offset: u64_at(fields) as usize,
+            len: u64_at(fields + 8) as usize,
+        };
+        let value = entry
+            .offset
+            .checked_add(entry.len)
+            .filter(|end| entry.offset >= PACK_HEADER_LEN && *end <= index_offset)
+            .map(|end| &data[entry.offset..end])
+            .ok_or_else(|| corrupt(&format!("value of {} out of bounds", key)))?;
+        if crc32c::crc32c(value) != u32_at(fields + 16) {
+            return Err(corrupt(&format!("checksum mismatch for {}", key)));
+        }
+        entries.insert(key, entry);
+        at = fields + 20;
+    }
+    Ok(entries)
+}
+
+/// Pack file being written to a temp file beside its final path
+struct PackWriter {
+    path: PathBuf,
+    file: BufWriter<tempfile::NamedTempFile>,
+    offset: u64,
+    index: Vec<u8>,
+    count: u64,
+}
+
+impl PackWriter {
+    fn create(path: &Path) -> Result<Self, CacheError> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(dir)?;
+        let mut file = BufWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+        // Header is filled in by `finish`
+        file.write_all(&[0u8; PACK_HEADER_LEN])?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            offset: PACK_HEADER_LEN as u64,
+            index: Vec::new(),
+            count: 0,
+        })
+    }
+
+    fn append(&mut self, key: &str, value: &[u8]) -> Result<(), CacheError> {
+        let padding = self.offset.next_multiple_of(VALUE_ALIGNMENT) - self.offset;
+        self.file
+            .write_all(&[0u8; VALUE_ALIGNMENT as usize][..padding as usize])?;
+        self.offset += padding;
+
+        self.index
+            .extend_from_slice(&(key.len() as u32).to_le_bytes());
+        self.index.extend_from_slice(key.as_bytes());
+        self.index.extend_from_slice(&self.offset.to_le_bytes());
+        self.index
+            .extend_from_slice(&(value.len() as u64).to_le_bytes());
+        self.index
+            .extend_from_slice(&crc32c::crc32c(value).to_le_bytes());
+
+        self.file.write_all(value)?;
+        self.offset += value.len() as u64;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Write the index and header, and move the file into place
+    fn finish(mut self) -> Result<(), CacheError> {
+        self.file.write_all(&self.index)?;
+        self.file
+            .write_all(&crc32c::crc32c(&self.index).to_le_bytes())?;
+
+        let mut header = Vec::with_capacity(PACK_HEADER_LEN);
+        header.extend_from_slice(PACK_MAGIC);
+        header.extend_from_slice(&[PACK_FORMAT_VERSION, 0, 0, 0]);
+        header.extend_from_slice(&self.count.to_le_bytes());
+        header.extend_from_slice(&self.offset.to_le_bytes());
+        header.extend_from_slice(&(self.index.len() as u64).to_le_bytes());
+        let mut file = self.file.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&header)?;
+        file.as_file().sync_all()?;
+        file.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+impl ReadableStorageTraits for DatasetPack {
+    fn get(&self, key: &StoreKey) -> Result<MaybeBytes, StorageError> {
+        Ok(self.get_value(key.as_str()))
+    }
+
+    fn get_partial_values_key(
+        &self,
+        key: &StoreKey,
+        byte_ranges: &[ByteRange],
+    ) -> Result<Option<Vec<Bytes>>, StorageError> {
+        self.get_value(key.as_str())
+            .map(|value| slice_byte_ranges(&value, byte_ranges))
+            .transpose()
+    }
+
+    fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
+        Ok(self.entries.get(key.as_str()).map(|entry| entry.len as u64))
+    }
+}
+
+impl ListableStorageTraits for DatasetPack {
+    fn list(&self) -> Result<StoreKeys, StorageError> {
+        ListableStorageTraits::list_prefix(self, &StorePrefix::root())
+    }
+
+    fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        self.keys_with_prefix(prefix.as_str())
+            .map(|key| StoreKey::new(key).map_err(StorageError::from))
+            .collect()
+    }
+
+    fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        let mut keys = Vec::new();
+        let mut prefixes = BTreeSet::new();
+        for key in self.keys_with_prefix(prefix.as_str()) {
+            let rest = &key[prefix.as_str().len()..];
+            match rest.split_once('/') {
+                Some((child, _)) => {
+                    prefixes.insert(StorePrefix::new(format!("{}{}/", prefix.as_str(), child))?);
+                }
+                None => keys.push(StoreKey::new(key)?),
+            }
+        }
+        Ok(StoreKeysPrefixes::new(keys, prefixes.into_iter().collect()))
+    }
+
+    fn size_prefix(&self, prefix: &StorePrefix) -> Result<u64, StorageError> {
+        Ok(self
+            .keys_with_prefix(prefix.as_str())
+            .map(|key| self.entries[key].len as u64)
+            .sum())
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncReadableStorageTraits for DatasetPack {
+    async fn get(&self, key: &StoreKey) -> Result<MaybeAsyncBytes, StorageError> {
+        ReadableStorageTraits::get(self, key)
+    }
+
+    async fn get_partial_values_key(
+        &self,
+        key: &StoreKey,
+        byte_ranges: &[ByteRange],
+    ) -> Result<Option<Vec<AsyncBytes>>, StorageError> {
+        ReadableStorageTraits::get_partial_values_key(self, key, byte_ranges)
+    }
+
+    async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
+        ReadableStorageTraits::size_key(self, key)
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncListableStorageTraits for DatasetPack {
+    async fn list(&self) -> Result<StoreKeys, StorageError> {
+        ListableStorageTraits::list(self)
+    }
+
+    async fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        ListableStorageTraits::list_prefix(self, prefix)
+    }
+
+    async fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        ListableStorageTraits::list_dir(self, prefix)
+    }
+
+    async fn size_prefix(&self, prefix: &StorePrefix) -> Result<u64, StorageError> {
+        ListableStorageTraits::size_prefix(self, prefix)
+    }
+}
@@ -0,0 +1,634 @@
+//! Recording and replaying cache access traces
+//!
+//! A [`TraceWriter`] appends every access to a compact trace file: the key,
+//! the time since recording started, whether a read hit and the bytes read
+//! or written. Wrap a cache in a [`TracedCache`] to record its accesses, or
+//! feed a writer the [`EventLog`] of a [`HybridCache`] with
+//! [`TraceWriter::spawn_event_recorder`]. A [`TraceReplayer`] then drives
+//! any [`Cache`] with a recorded trace, so production access patterns can be
+//! reproduced in benchmarks and configurations tuned offline:
+//!
+//! ```rust,no_run
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use zarrs_cache::trace::{read_trace, TraceReplayer};
+//! use zarrs_cache::ArcMemoryCache;
+//!
+//! let replayer = TraceReplayer::new(read_trace("production.trace")?);
+//! let report = replayer.replay(&ArcMemoryCache::new(512 * 1024 * 1024)).await;
+//! println!(
+//!     "hit rate {:.1}% (recorded {:.1}%)",
+//!     report.hit_rate() * 100.0,
+//!     report.recorded_hit_rate() * 100.0
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Keys are written in full on their first access and as a number after
+//! that, and times and sizes as variable-length integers, so a record of a
+//! repeated key takes a few bytes. The writer remembers every key it has
+//! written for this, growing with the number of distinct keys.
+//!
+//! [`HybridCache`]: crate::cache::hybrid::HybridCache
+
+use crate::cache::{Cache, CacheStats, RemovalCause, StoreKey};
+use crate::error::CacheError;
+use crate::events::{CacheEvent, CacheEventKind, CacheEventOutcome, EventLog};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// First bytes of a trace file: a tag and the format version
+const MAGIC: &[u8; 8] = b"ZCTRACE\x01";
+
+/// Bits of a record's tag byte
+const OP_MASK: u8 = 0b11;
+const HIT_FLAG: u8 = 0b100;
+const NEW_KEY_FLAG: u8 = 0b1000;
+
+/// Longest key a trace accepts, to reject corrupt lengths before allocating
+const MAX_KEY_LEN: u64 = 64 * 1024;
+
+/// Operation of a [`TraceRecord`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceOp {
+    Get,
+    Set,
+    Remove,
+}
+
+impl TraceOp {
+    fn code(self) -> u8 {
+        match self {
+            TraceOp::Get => 0,
+            TraceOp::Set => 1,
+            TraceOp::Remove => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(TraceOp::Get),
+            1 => Some(TraceOp::Set),
+            2 => Some(TraceOp::Remove),
+            _ => None,
+        }
+    }
+}
+
+/// A single recorded access
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Time since recording started
+    pub at: Duration,
+    pub op: TraceOp,
+    pub key: StoreKey,
+    /// Whether a read found the key; false for writes and removals
+    pub hit: bool,
+    /// Bytes of a hit or a write, 0 otherwise
+    pub size: u64,
+}
+
+/// Appends accesses to a trace file
+///
+/// Records are buffered; they reach the file when the buffer fills, on
+/// [`TraceWriter::flush`] and when the writer is dropped.
+pub struct TraceWriter {
+    state: Mutex<WriterState>,
+    started: Instant,
+    started_unix_ms: u64,
+    records: AtomicU64,
+}
+
+struct WriterState {
+    out: BufWriter<File>,
+    /// Number of each key already written
+    keys: HashMap<StoreKey, u64>,
+    /// Time of the last record, so times never go backwards
+    last: Duration,
+}
+
+impl TraceWriter {
+    /// Start a trace at `path`, replacing any file there
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        let started_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&started_unix_ms.to_le_bytes())?;
+        Ok(Self {
+            state: Mutex::new(WriterState {
+                out,
+                keys: HashMap::new(),
+                last: Duration::ZERO,
+            }),
+            started: Instant::now(),
+            started_unix_ms,
+            records: AtomicU64::new(0),
+        })
+    }
+
+    /// Record an access happening now
+    pub fn record(&self, op: TraceOp, key: &str, hit: bool, size: u64) -> Result<(), CacheError> {
+        self.write(self.started.elapsed(), op, key, hit, size)
+    }
+
+    /// Record the access an event describes, at the event's time
+    ///
+    /// Events other than gets, successful writes and successful removals
+    /// are skipped.
+    pub fn record_event(&self, event: &CacheEvent) -> Result<(), CacheError> {
+        let (op, hit) = match (event.kind, event.outcome) {
+            (CacheEventKind::Get, outcome) => (TraceOp::Get, outcome == CacheEventOutcome::Hit),
+            (CacheEventKind::Set, CacheEventOutcome::Ok) => (TraceOp::Set, false),
+            (CacheEventKind::Remove, CacheEventOutcome::Ok) => (TraceOp::Remove, false),
+            _ => return Ok(()),
+        };
+        let at = Duration::from_millis(event.timestamp_ms.saturating_sub(self.started_unix_ms));
+        let size = event.size.unwrap_or(0) as u64;
+        self.write(at, op, &event.key, hit, size)
+    }
+
+    /// Spawn a task recording every event logged to `events` from now on
+    ///
+    /// The task stops once the log is dropped or a write to the trace
+    /// fails. Events a slow task falls too far behind on are lost, and
+    /// counted in a warning.
+    pub fn spawn_event_recorder(
+        self: &Arc<Self>,
+        events: &EventLog,
+    ) -> tokio::task::JoinHandle<()> {
+        let writer = self.clone();
+        let mut receiver = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = writer.record_event(&event) {
+                            tracing::warn!("Stopped recording cache trace: {}", e);
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Cache trace missed {} events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Number of records written so far
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    /// Write buffered records to the file
+    pub fn flush(&self) -> Result<(), CacheError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.out.flush()?;
+        Ok(())
+    }
+
+    fn write(
+        &self,
+        at: Duration,
+        op: TraceOp,
+        key: &str,
+        hit: bool,
+        size: u64,
+    ) -> Result<(), CacheError> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let at = at.max(state.last);
+        let delta = (at - state.last).as_micros() as u64;
+        state.last = at;
+
+        let mut record = Vec::with_capacity(16 + key.len());
+        write_varint(&mut record, delta);
+        let mut tag = op.code();
+        if hit {
+            tag |= HIT_FLAG;
+        }
+        match state.keys.get(key) {
+            Some(&id) => {
+                record.push(tag);
+                write_varint(&mut record, id);
+            }
+            None => {
+                let id = state.keys.len() as u64;
+                state.keys.insert(key.to_string(), id);
+                record.push(tag | NEW_KEY_FLAG);
+                write_varint(&mut record, key.len() as u64);
+                record.extend_from_slice(key.as_bytes());
+            }
+        }
+        write_varint(&mut record, size);
+        state.out.write_all(&record)?;
+        self.records.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads the records of a trace file in order
+pub struct TraceReader<R: Read> {
+    input: R,
+    keys: Vec<StoreKey>,
+    at: Duration,
+    started_unix_ms: u64,
+    done: bool,
+}
+
+impl TraceReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Read a trace from `input`, checking its header
+    pub fn new(mut input: R) -> Result<Self, CacheError> {
+        let mut header = [0u8; 16];
+        input.read_exact(&mut header).map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => {
+                CacheError::Corruption("trace file is missing its header".to_string())
+            }
+            _ => CacheError::Io(e),
+        })?;
+        if &header[..8] != MAGIC {
+            return Err(CacheError::Corruption(
+                "not a cache trace, or written by another version".to_string(),
+            ));
+        }
+        let mut started = [0u8; 8];
+        started.copy_from_slice(&header[8..]);
+        Ok(Self {
+            input,
+            keys: Vec::new(),
+            at: Duration::ZERO,
+            started_unix_ms: u64::from_le_bytes(started),
+            done: false,
+        })
+    }
+
+    /// Wall-clock time recording started
+    pub fn started_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.started_unix_ms)
+    }
+
+    /// The next record, or `None` at the end of the trace
+    fn read_record(&mut self) -> Result<Option<TraceRecord>, CacheError> {
+        // The trace may only end before the first byte of a record
+        let mut first = [0u8; 1];
+        if self.input.read(&mut first)? == 0 {
+            return Ok(None);
+        }
+        let delta = self.read_varint_from(first[0])?;
+        let tag = self.read_byte()?;
+        let op = TraceOp::from_code(tag & OP_MASK).ok_or_else(|| {
+            CacheError::Corruption(format!("unknown trace operation {}", tag & OP_MASK))
+        })?;
+        let key = if tag & NEW_KEY_FLAG != 0 {
+            let len = self.read_u64()?;
+            if len > MAX_KEY_LEN {
+                return Err(CacheError::Corruption(format!(
+                    "trace key of {} bytes",
+                    len
+                )));
+            }
+            let mut bytes = vec![0u8; len as usize];
+            self.input.read_exact(&mut bytes).map_err(truncated)?;
+            let key = String::from_utf8(bytes)
+                .map_err(|_| CacheError::Corruption("trace key is not UTF-8".to_string()))?;
+            self.keys.push(key.clone());
+            key
+        } else {
+            let id = self.read_u64()?;
+            self.keys
+                .get(id as usize)
+                .cloned()
+                .ok_or_else(|| CacheError::Corruption(format!("unknown trace key {}", id)))?
+        };
+        let size = self.read_u64()?;
+
+        self.at += Duration::from_micros(delta);
+        Ok(Some(TraceRecord {
+            at: self.at,
+            op,
+            key,
+            hit: tag & HIT_FLAG != 0,
+            size,
+        }))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, CacheError> {
+        let mut byte = [0u8; 1];
+        self.input.read_exact(&mut byte).map_err(truncated)?;
+        Ok(byte[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, CacheError> {
+        let first = self.read_byte()?;
+        self.read_varint_from(first)
+    }
+
+    /// A variable-length integer starting with `byte`
+    fn read_varint_from(&mut self, mut byte: u8) -> Result<u64, CacheError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            byte = self.read_byte()?;
+        }
+        Err(CacheError::Corruption(
+            "trace integer is too long".to_string(),
+        ))
+    }
+}
+
+fn truncated(error: std::io::Error) -> CacheError {
+    match error.kind() {
+        std::io::ErrorKind::UnexpectedEof => {
+            CacheError::Corruption("trace ends inside a record".to_string())
+        }
+        _ => CacheError::Io(error),
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = Result<TraceRecord, CacheError>;
+
+    /// Ends after the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if !matches!(record, Some(Ok(_))) {
+            self.done = true;
+        }
+        record
+    }
+}
+
+/// Every record of the trace at `path`
+pub fn read_trace(path: impl AsRef<Path>) -> Result<Vec<TraceRecord>, CacheError> {
+    TraceReader::open(path)?.collect()
+}
+
+/// Cache wrapper recording every get, write and removal to a trace
+///
+/// A failed write to the trace is logged and does not fail the access.
+pub struct TracedCache<C: Cache + ?Sized> {
+    inner: Arc<C>,
+    writer: Arc<TraceWriter>,
+}
+
+impl<C: Cache + ?Sized> TracedCache<C> {
+    pub fn new(inner: Arc<C>, writer: Arc<TraceWriter>) -> Self {
+        Self { inner, writer }
+    }
+
+    pub fn inner(&self) -> &Arc<C> {
+        &self.inner
+    }
+
+    pub fn writer(&self) -> &Arc<TraceWriter> {
+        &self.writer
+    }
+
+    fn record(&self, op: TraceOp, key: &str, hit: bool, size: usize) {
+        if let Err(e) = self.writer.record(op, key, hit, size as u64) {
+            tracing::warn!("Failed to record access to {} in trace: {}", key, e);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache + ?Sized> Cache for TracedCache<C> {
+    async fn get(&self, key: &str) -> Option<Bytes> {
+        let value = self.inner.get(key).await;
+        let size = value.as_ref().map_or(0, Bytes::len);
+        self.record(TraceOp::Get, key, value.is_some(), size);
+        value
+    }
+
+    async fn set(&self, key: &str, value: Bytes) -> Result<(), CacheError> {
+        let size = value.len();
+        self.inner.set(key, value).await?;
+        self.record(TraceOp::Set, key, false, size);
+        Ok(())
+    }
+
+    async fn set_many(&self, entries: Vec<(StoreKey, Bytes)>) -> Vec<Result<(), CacheError>> {
+        let written: Vec<(StoreKey, usize)> = entries
+            .iter()
+            .map(|(key, value)| (key.clone(), value.len()))
+            .collect();
+        let results = self.inner.set_many(entries).await;
+        for ((key, size), result) in written.iter().zip(&results) {
+            if result.is_ok() {
+                self.record(TraceOp::Set, key, false, *size);
+            }
+        }
+        results
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.remove_with_cause(key, RemovalCause::Removed).await
+    }
+
+    async fn remove_with_cause(&self, key: &str, cause: RemovalCause) -> Result<(), CacheError> {
+        self.inner.remove_with_cause(key, cause).await?;
+        self.record(TraceOp::Remove, key, false, 0);
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<usize, CacheError> {
+        self.inner.remove_prefix(prefix).await
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        self.inner.clear().await
+    }
+
+    async fn purge_expired(&self) -> Result<usize, CacheError> {
+        self.inner.purge_expired().await
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    fn io_operations(&self) -> Option<u64> {
+        self.inner.io_operations()
+    }
+
+    async fn resize(&self, max_size_bytes: usize) -> Result<(), CacheError> {
+        self.inner.resize(max_size_bytes).await
+    }
+
+    async fn set_ttl(&self, ttl: Option<Duration>) -> Result<(), CacheError> {
+        self.inner.set_ttl(ttl).await
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.inner.stats()
+    }
+}
+
+/// Outcome of replaying a trace against a cache
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub gets: u64,
+    pub hits: u64,
+    /// Gets that hit when the trace was recorded
+    pub recorded_hits: u64,
+    pub sets: u64,
+    /// Writes the cache rejected, such as entries too large for it
+    pub failed_sets: u64,
+    pub removes: u64,
+    /// Bytes served by hits
+    pub hit_bytes: u64,
+    /// Time the replay took
+    pub elapsed: Duration,
+}
+
+impl ReplayReport {
+    /// Share of gets that hit during the replay
+    pub fn hit_rate(&self) -> f64 {
+        if self.gets == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.gets as f64
+        }
+    }
+
+    /// Share of gets that hit when the trace was recorded
+    pub fn recorded_hit_rate(&self) -> f64 {
+        if self.gets == 0 {
+            0.0
+        } else {
+            self.recorded_hits as f64 / self.gets as f64
+        }
+    }
+}
+
+/// Drives a cache with the accesses of a recorded trace
+///
+/// Writes store zeroed values of the recorded sizes. By default a get that
+/// hit when recorded but misses in the replayed cache is followed by a
+/// write of the recorded size, standing in for the read from the store the
+/// recorded cache never needed; see [`TraceReplayer::with_read_through`].
+pub struct TraceReplayer {
+    records: Vec<TraceRecord>,
+    speed: Option<f64>,
+    read_through: bool,
+}
+
+impl TraceReplayer {
+    /// Replay `records` as fast as the cache allows
+    pub fn new(records: Vec<TraceRecord>) -> Self {
+        Self {
+            records,
+            speed: None,
+            read_through: true,
+        }
+    }
+
+    /// Keep the recorded pacing, sped up by `factor`
+    ///
+    /// A factor of 1 replays in real time and 10 ten times as fast; time
+    /// spent in the cache delays later accesses rather than adding up.
+    pub fn with_speed(mut self, factor: f64) -> Self {
+        self.speed = (factor.is_finite() && factor > 0.0).then_some(factor);
+        self
+    }
+
+    /// Whether to write the value of a recorded hit that misses
+    ///
+    /// Disable it when the trace already holds the writes that follow
+    /// every miss, such as traces of a cache in front of a store.
+    pub fn with_read_through(mut self, enabled: bool) -> Self {
+        self.read_through = enabled;
+        self
+    }
+
+    pub fn records(&self) -> &[TraceRecord] {
+        &self.records
+    }
+
+    /// Run every access of the trace against `cache`
+    pub async fn replay<C: Cache + ?Sized>(&self, cache: &C) -> ReplayReport {
+        let started = tokio::time::Instant::now();
+        let mut report = ReplayReport::default();
+        let mut zeros = Bytes::new();
+        let mut value = |size: u64| {
+            let size = size as usize;
+            if zeros.len() < size {
+                zeros = Bytes::from(vec![0u8; size]);
+            }
+            zeros.slice(..size)
+        };
+
+        for record in &self.records {
+            if let Some(speed) = self.speed {
+                tokio::time::sleep_until(started + record.at.div_f64(speed)).await;
+            }
+            match record.op {
+                TraceOp::Get => {
+                    report.gets += 1;
+                    report.recorded_hits += u64::from(record.hit);
+                    match cache.get(&record.key).await {
+                        Some(hit) => {
+                            report.hits += 1;
+                            report.hit_bytes += hit.len() as u64;
+                        }
+                        None if record.hit && self.read_through => {
+                            let stored = cache.set(&record.key, value(record.size)).await;
+                            report.failed_sets += u64::from(stored.is_err());
+                        }
+                        None => {}
+                    }
+                }
+                TraceOp::Set => {
+                    report.sets += 1;
+                    if cache.set(&record.key, value(record.size)).await.is_err() {
+                        report.failed_sets += 1;
+                    }
+                }
+                TraceOp::Remove => {
+                    report.removes += 1;
+                    // Removing a key the cache no longer holds is expected
+                    let _ = cache.remove(&record.key).await;
+                }
+            }
+        }
+
+        report.elapsed = started.elapsed();
+        report
+    }
+}
@@ -0,0 +1,281 @@
+//! Human-readable sizes and durations
+//!
+//! Configuration sizes accept `"512MB"` or `"10GiB"` besides a byte count,
+//! and durations `"5m"`, `"2h"` or `"1h30m"` besides a [`Duration`], both in
+//! serialized configurations and in config builders:
+//!
+//! ```rust
+//! use zarrs_cache::HybridCacheConfig;
+//!
+//! let config = HybridCacheConfig::builder()
+//!     .memory_size("512MiB")
+//!     .demotion_threshold("10m")
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(config.memory_size, 512 * 1024 * 1024);
+//! ```
+
+use crate::error::CacheError;
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+
+/// A size in bytes: an integer byte count or a string such as `"512MB"`
+///
+/// See [`parse_size`] for the accepted strings.
+pub trait ByteSize {
+    /// The size in bytes
+    fn to_bytes(self) -> Result<u64, CacheError>;
+
+    /// The size in bytes, if it fits a `usize`
+    fn to_usize(self) -> Result<usize, CacheError>
+    where
+        Self: Sized,
+    {
+        let bytes = self.to_bytes()?;
+        usize::try_from(bytes)
+            .map_err(|_| CacheError::InvalidConfig(format!("{} bytes is too large", bytes)))
+    }
+}
+
+impl ByteSize for u64 {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        Ok(self)
+    }
+}
+
+impl ByteSize for usize {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        Ok(self as u64)
+    }
+}
+
+impl ByteSize for u32 {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        Ok(self.into())
+    }
+}
+
+/// Unsuffixed integer literals, which default to `i32`
+impl ByteSize for i32 {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        u64::try_from(self)
+            .map_err(|_| CacheError::InvalidConfig(format!("negative size {}", self)))
+    }
+}
+
+impl ByteSize for &str {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        parse_size(self)
+    }
+}
+
+impl ByteSize for String {
+    fn to_bytes(self) -> Result<u64, CacheError> {
+        parse_size(&self)
+    }
+}
+
+/// A duration: a [`Duration`] or a string such as `"5m"`
+///
+/// See [`parse_duration`] for the accepted strings.
+pub trait HumanDuration {
+    /// The duration
+    fn to_duration(self) -> Result<Duration, CacheError>;
+}
+
+impl HumanDuration for Duration {
+    fn to_duration(self) -> Result<Duration, CacheError> {
+        Ok(self)
+    }
+}
+
+impl HumanDuration for &str {
+    fn to_duration(self) -> Result<Duration, CacheError> {
+        parse_duration(self)
+    }
+}
+
+impl HumanDuration for String {
+    fn to_duration(self) -> Result<Duration, CacheError> {
+        parse_duration(&self)
+    }
+}
+
+/// Byte count of a size such as `4096`, `512MB` or `1.5GiB`
+///
+/// `KB`, `MB`, `GB` and `TB` are powers of 1000, `KiB`, `MiB`, `GiB` and
+/// `TiB` powers of 1024. Units are case-insensitive.
+pub fn parse_size(size: &str) -> Result<u64, CacheError> {
+    let invalid = || CacheError::InvalidConfig(format!("invalid size {:?}", size));
+    let trimmed = size.trim();
+    let split = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "gb" => 1000 * 1000 * 1000,
+        "tb" => 1000 * 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+    if number.contains('.') {
+        let bytes = number.parse::<f64>().map_err(|_| invalid())? * multiplier as f64;
+        if bytes.is_finite() && bytes <= u64::MAX as f64 {
+            Ok(bytes.round() as u64)
+        } else {
+            Err(invalid())
+        }
+    } else {
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .ok_or_else(invalid)
+    }
+}
+
+/// Duration of a string such as `90`, `500ms`, `5m`, `2h` or `1h30m`
+///
+/// Units are `ns`, `us`, `ms`, `s`, `m` (or `min`), `h` and `d`; a number
+/// without a unit counts seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration, CacheError> {
+    let invalid = || CacheError::InvalidConfig(format!("invalid duration {:?}", duration));
+    let mut rest = duration.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(secs) = rest.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).map_err(|_| invalid());
+    }
+
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .ok_or_else(invalid)?;
+        let (number, tail) = rest.split_at(split);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let unit_secs = match unit.trim() {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" | "min" => 60.0,
+            "h" => 3600.0,
+            "d" => 86400.0,
+            _ => return Err(invalid()),
+        };
+        let number: f64 = number.parse().map_err(|_| invalid())?;
+        total += Duration::try_from_secs_f64(number * unit_secs).map_err(|_| invalid())?;
+        rest = tail.trim_start();
+    }
+    Ok(total)
+}
+
+/// First error met by a builder's setters, reported by its `build`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SetterError(Option<String>);
+
+impl SetterError {
+    /// The value, or `None` after recording its error
+    pub(crate) fn check<T>(&mut self, value: Result<T, CacheError>) -> Option<T> {
+        match value {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.0.get_or_insert_with(|| match error {
+                    CacheError::InvalidConfig(message) => message,
+                    error => error.to_string(),
+                });
+                None
+            }
+        }
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), CacheError> {
+        match self.0 {
+            Some(message) => Err(CacheError::InvalidConfig(message)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeRepr {
+    Bytes(u64),
+    Text(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationRepr {
+    Secs(f64),
+    Text(String),
+    Struct(Duration),
+}
+
+fn size_from<T: TryFrom<u64>, E: serde::de::Error>(repr: SizeRepr) -> Result<T, E> {
+    let bytes = match repr {
+        SizeRepr::Bytes(bytes) => bytes,
+        SizeRepr::Text(text) => parse_size(&text).map_err(E::custom)?,
+    };
+    T::try_from(bytes).map_err(|_| E::custom(format!("{} bytes is too large", bytes)))
+}
+
+fn duration_from<E: serde::de::Error>(repr: DurationRepr) -> Result<Duration, E> {
+    match repr {
+        DurationRepr::Secs(secs) => Duration::try_from_secs_f64(secs).map_err(E::custom),
+        DurationRepr::Text(text) => parse_duration(&text).map_err(E::custom),
+        DurationRepr::Struct(duration) => Ok(duration),
+    }
+}
+
+/// Deserialize a size given as a byte count or a string
+pub(crate) fn deserialize_size<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u64>,
+{
+    size_from(SizeRepr::deserialize(deserializer)?)
+}
+
+/// Deserialize an optional size given as a byte count or a string
+pub(crate) fn deserialize_optional_size<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: TryFrom<u64>,
+{
+    Option::<SizeRepr>::deserialize(deserializer)?
+        .map(size_from)
+        .transpose()
+}
+
+/// Deserialize a duration given as a `Duration`, seconds or a string
+pub(crate) fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    duration_from(DurationRepr::deserialize(deserializer)?)
+}
+
+/// Deserialize an optional duration given as a `Duration`, seconds or a
+/// string
+pub(crate) fn deserialize_optional_duration<'de, D>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<DurationRepr>::deserialize(deserializer)?
+        .map(duration_from)
+        .transpose()
+}
@@ -0,0 +1,1093 @@
+//! Guards on loads from the origin store
+//!
+//! Loaders handed to [`CachedStore::get_or_load`], the prefetch strategies
+//! and the [`CacheWarmer`] fetch from the store the cache sits in front of,
+//! such as an S3 bucket or a share behind a VPN. A [`RateLimiter`] shared
+//! by all of them caps the requests and bytes per second they fetch, so a
+//! warming run or a burst of prefetches never gets the store to throttle
+//! the application or saturates a shared link:
+//!
+//! ```rust
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use std::sync::Arc;
+//! use zarrs_cache::upstream::{RateLimitConfig, RateLimiter};
+//! use zarrs_cache::{CacheConfig, CachedStore, CacheWarmer, LruMemoryCache};
+//!
+//! let limiter = Arc::new(RateLimiter::new(
+//!     RateLimitConfig::builder()
+//!         .requests_per_second(50.0)
+//!         .bytes_per_second("20MB")
+//!         .build()?,
+//! ));
+//! let store = CachedStore::new((), LruMemoryCache::new(64 * 1024 * 1024), CacheConfig::default())
+//!     .with_rate_limiter(limiter.clone());
+//! let warmer = CacheWarmer::new(store.cache().clone()).with_rate_limiter(limiter);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Loads that can fail, such as S3 requests answered with a 503, go through
+//! a [`RetryPolicy`], which tries them again with exponential backoff.
+//! [`RetryPolicy::wrap`] turns such a loader into one every path takes,
+//! so a transient failure is neither a cache miss nor a failed warming:
+//!
+//! ```rust
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! use std::sync::Arc;
+//! use zarrs_cache::upstream::{RetryConfig, RetryPolicy};
+//! use zarrs_cache::{CacheError, CacheWarmer, LruMemoryCache};
+//!
+//! let retry = Arc::new(RetryPolicy::new(
+//!     RetryConfig::builder().max_attempts(5).build()?,
+//! ));
+//! let loader = retry.wrap(|key: String| async move {
+//!     // A request to the store, failing with `CacheError::backend` on a 503
+//!     Ok::<_, CacheError>(Some(bytes::Bytes::from(key)))
+//! });
+//! let warmer = CacheWarmer::new(Arc::new(LruMemoryCache::new(64 * 1024 * 1024)))
+//!     .with_priority_keys(["temperature/.zarray"]);
+//! warmer.warm(loader).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A [`CircuitBreaker`] around such loads stops loading from a store that
+//! keeps failing, and probes it now and then for recovery, while the
+//! [`CachedStore`] it is given serves cached copies, stale ones included:
+//!
+//! ```rust
+//! # async fn example() -> Result<(), zarrs_cache::CacheError> {
+//! # use std::sync::Arc;
+//! # use zarrs_cache::upstream::{CircuitBreaker, CircuitBreakerConfig, RetryConfig, RetryPolicy};
+//! # use zarrs_cache::{CacheConfig, CacheError, CachedStore, LruMemoryCache};
+//! # let retry = Arc::new(RetryPolicy::new(RetryConfig::default()));
+//! let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig::default()));
+//! let loader = breaker.wrap(retry.try_wrap(|key: String| async move {
+//!     Ok::<_, CacheError>(Some(bytes::Bytes::from(key)))
+//! }));
+//! let store = CachedStore::new((), LruMemoryCache::new(64 * 1024 * 1024), CacheConfig::default())
+//!     .with_circuit_breaker(breaker.clone());
+//! let chunk = store.get_or_load("temperature/0.0", &loader).await;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`CachedStore::get_or_load`]: crate::CachedStore::get_or_load
+//! [`CachedStore`]: crate::CachedStore
+//! [`CacheWarmer`]: crate::CacheWarmer
+
+use crate::config;
+use crate::error::CacheError;
+use crate::events::{CacheEvent, CacheEventKind, CacheEventOutcome, EventLog};
+use crate::units::{self, ByteSize, HumanDuration, SetterError};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Configuration of a [`RateLimiter`]
+///
+/// # Default Values
+/// - `requests_per_second`: None (unlimited)
+/// - `bytes_per_second`: None (unlimited)
+/// - `burst`: 1 second
+///
+/// Settings missing from a deserialized configuration take these defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Most loads to start per second
+    pub requests_per_second: Option<f64>,
+
+    /// Most bytes to fetch per second
+    #[serde(deserialize_with = "units::deserialize_optional_size")]
+    pub bytes_per_second: Option<u64>,
+
+    /// How long an idle limiter saves up unused allowance for, letting that
+    /// many seconds' worth of requests and bytes through at once
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub burst: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: None,
+            bytes_per_second: None,
+            burst: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> RateLimitConfigBuilder {
+        RateLimitConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if let Some(rate) = self.requests_per_second {
+            if !(rate.is_finite() && rate > 0.0) {
+                return invalid("requests_per_second must be a positive number");
+            }
+        }
+        if self.bytes_per_second == Some(0) {
+            return invalid("bytes_per_second must be greater than zero");
+        }
+        if self.burst.is_zero() {
+            return invalid("burst must be greater than zero");
+        }
+        Ok(())
+    }
+
+    /// Load the configuration from a file, as
+    /// [`CacheConfig::from_file`](crate::CacheConfig::from_file) does, with
+    /// `ZARRS_CACHE_RATE_LIMIT_<SETTING>` overrides
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        config::read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with `ZARRS_CACHE_RATE_LIMIT_<SETTING>`
+    /// overrides
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among `vars`, as
+    /// [`CacheConfig::override_from`](crate::CacheConfig::override_from) does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        config::apply_overrides(&self, config::RATE_LIMIT_ENV_PREFIX, vars)
+    }
+}
+
+/// Builder for [`RateLimitConfig`], starting from its defaults
+///
+/// Setters of optional settings take the value itself.
+/// [`build`](RateLimitConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfigBuilder {
+    config: RateLimitConfig,
+    invalid: SetterError,
+}
+
+impl RateLimitConfigBuilder {
+    pub fn requests_per_second(mut self, rate: f64) -> Self {
+        self.config.requests_per_second = Some(rate);
+        self
+    }
+
+    pub fn bytes_per_second(mut self, rate: impl ByteSize) -> Self {
+        if let Some(bytes) = self.invalid.check(rate.to_bytes()) {
+            self.config.bytes_per_second = Some(bytes);
+        }
+        self
+    }
+
+    pub fn burst(mut self, burst: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(burst.to_duration()) {
+            self.config.burst = duration;
+        }
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<RateLimitConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Loads let through a [`RateLimiter`] so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimiterStats {
+    /// Loads started
+    pub requests: u64,
+    /// Bytes the loads returned
+    pub bytes: u64,
+    /// Loads that had to wait for their turn
+    pub throttled: u64,
+    /// Time loads spent waiting, in total
+    pub throttled_time: Duration,
+}
+
+/// Allowance left in each bucket
+#[derive(Debug)]
+struct Buckets {
+    requests: f64,
+    /// Negative while fetched bytes exceed the allowance
+    bytes: f64,
+    refilled: Instant,
+}
+
+/// Token-bucket limiter of the requests and bytes loaded from the origin
+/// store
+///
+/// Each load waits in [`acquire`](Self::acquire) until a request is
+/// allowed and no bytes are owed, then reports what it fetched with
+/// [`record_bytes`](Self::record_bytes). Response sizes are only known
+/// afterwards, so a large one is let through whole and the bytes it goes
+/// over the allowance delay the loads after it. Waiting loads go in the
+/// order they arrived.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+    /// Held by the load waiting for its turn
+    queue: tokio::sync::Mutex<()>,
+    requests: AtomicU64,
+    bytes: AtomicU64,
+    throttled: AtomicU64,
+    throttled_nanos: AtomicU64,
+}
+
+impl RateLimiter {
+    /// Limiter starting with a full burst allowance
+    pub fn new(config: RateLimitConfig) -> Self {
+        let burst = config.burst.as_secs_f64();
+        let buckets = Buckets {
+            requests: config
+                .requests_per_second
+                .map_or(0.0, |rate| (rate * burst).max(1.0)),
+            bytes: config
+                .bytes_per_second
+                .map_or(0.0, |rate| rate as f64 * burst),
+            refilled: Instant::now(),
+        };
+        Self {
+            config,
+            buckets: Mutex::new(buckets),
+            queue: tokio::sync::Mutex::new(()),
+            requests: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+            throttled_nanos: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Wait until the limits allow another load, and count it
+    pub async fn acquire(&self) {
+        let _turn = self.queue.lock().await;
+        let mut waited = Duration::ZERO;
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+                self.refill(&mut buckets);
+                let wait = self.wait_time(&buckets);
+                if wait.is_zero() && self.config.requests_per_second.is_some() {
+                    buckets.requests -= 1.0;
+                }
+                wait
+            };
+            if wait.is_zero() {
+                break;
+            }
+            tokio::time::sleep(wait).await;
+            waited += wait;
+        }
+
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if !waited.is_zero() {
+            self.throttled.fetch_add(1, Ordering::Relaxed);
+            self.throttled_nanos
+                .fetch_add(waited.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Charge `bytes` fetched by a load to the byte allowance
+    pub fn record_bytes(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        if self.config.bytes_per_second.is_some() {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+            self.refill(&mut buckets);
+            buckets.bytes -= bytes as f64;
+        }
+    }
+
+    /// Load `key` with `loader` once the limits allow it, charging the
+    /// bytes it returns
+    pub async fn load<F, Fut>(&self, loader: F, key: String) -> Option<Bytes>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Option<Bytes>>,
+    {
+        self.acquire().await;
+        let data = loader(key).await;
+        if let Some(data) = &data {
+            self.record_bytes(data.len());
+        }
+        data
+    }
+
+    pub fn stats(&self) -> RateLimiterStats {
+        RateLimiterStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            throttled_time: Duration::from_nanos(self.throttled_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Add the allowance earned since the last refill, up to a burst's worth
+    fn refill(&self, buckets: &mut Buckets) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(buckets.refilled).as_secs_f64();
+        buckets.refilled = now;
+        let burst = self.config.burst.as_secs_f64();
+        if let Some(rate) = self.config.requests_per_second {
+            buckets.requests = (buckets.requests + rate * elapsed).min((rate * burst).max(1.0));
+        }
+        if let Some(rate) = self.config.bytes_per_second {
+            let rate = rate as f64;
+            buckets.bytes = (buckets.bytes + rate * elapsed).min(rate * burst);
+        }
+    }
+
+    /// How long until a request is allowed and no bytes are owed
+    fn wait_time(&self, buckets: &Buckets) -> Duration {
+        let mut wait: f64 = 0.0;
+        if let Some(rate) = self.config.requests_per_second {
+            if buckets.requests < 1.0 {
+                wait = wait.max((1.0 - buckets.requests) / rate);
+            }
+        }
+        if let Some(rate) = self.config.bytes_per_second {
+            if buckets.bytes < 0.0 {
+                wait = wait.max(-buckets.bytes / rate as f64);
+            }
+        }
+        Duration::from_secs_f64(wait)
+    }
+}
+
+/// Load `key` with `loader`, through `limiter` if there is one
+pub(crate) async fn limited_load<F, Fut>(
+    limiter: Option<&Arc<RateLimiter>>,
+    loader: &F,
+    key: String,
+) -> Option<Bytes>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Option<Bytes>>,
+{
+    match limiter {
+        Some(limiter) => limiter.load(loader, key).await,
+        None => loader(key).await,
+    }
+}
+
+/// Future of a loader made by [`RetryPolicy::wrap`] or
+/// [`CircuitBreaker::wrap`]
+pub type LoadFuture = Pin<Box<dyn Future<Output = Option<Bytes>> + Send>>;
+
+/// Future of a fallible loader made by [`RetryPolicy::try_wrap`] or
+/// [`CircuitBreaker::try_wrap`]
+pub type TryLoadFuture = Pin<Box<dyn Future<Output = Result<Option<Bytes>, CacheError>> + Send>>;
+
+/// Configuration of a [`RetryPolicy`]
+///
+/// # Default Values
+/// - `max_attempts`: 3
+/// - `initial_backoff`: 100 milliseconds
+/// - `max_backoff`: 5 seconds
+/// - `multiplier`: 2.0
+/// - `jitter`: 0.5
+///
+/// Settings missing from a deserialized configuration take these defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Most attempts at a load, the first included
+    pub max_attempts: u32,
+
+    /// Wait before the first retry
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub initial_backoff: Duration,
+
+    /// Longest wait between attempts
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub max_backoff: Duration,
+
+    /// Factor each wait grows by over the one before
+    pub multiplier: f64,
+
+    /// Share of each wait, from 0.0 to 1.0, taken off at random so loads
+    /// that failed together do not retry together
+    pub jitter: f64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> RetryConfigBuilder {
+        RetryConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable and consistent with each other
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.max_attempts == 0 {
+            return invalid("max_attempts must be greater than zero");
+        }
+        if self.initial_backoff > self.max_backoff {
+            return invalid("initial_backoff must not exceed max_backoff");
+        }
+        if !(self.multiplier.is_finite() && self.multiplier >= 1.0) {
+            return invalid("multiplier must be at least 1.0");
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return invalid("jitter must be between 0.0 and 1.0");
+        }
+        Ok(())
+    }
+
+    /// Load the configuration from a file, as
+    /// [`CacheConfig::from_file`](crate::CacheConfig::from_file) does, with
+    /// `ZARRS_CACHE_RETRY_<SETTING>` overrides
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        config::read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with `ZARRS_CACHE_RETRY_<SETTING>` overrides
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among `vars`, as
+    /// [`CacheConfig::override_from`](crate::CacheConfig::override_from) does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        config::apply_overrides(&self, config::RETRY_ENV_PREFIX, vars)
+    }
+}
+
+/// Builder for [`RetryConfig`], starting from its defaults
+///
+/// [`build`](RetryConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct RetryConfigBuilder {
+    config: RetryConfig,
+    invalid: SetterError,
+}
+
+impl RetryConfigBuilder {
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.config.max_attempts = attempts;
+        self
+    }
+
+    pub fn initial_backoff(mut self, backoff: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(backoff.to_duration()) {
+            self.config.initial_backoff = duration;
+        }
+        self
+    }
+
+    pub fn max_backoff(mut self, backoff: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(backoff.to_duration()) {
+            self.config.max_backoff = duration;
+        }
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.config.multiplier = multiplier;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.config.jitter = jitter;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<RetryConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Loads made through a [`RetryPolicy`] so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetryStats {
+    /// Loads started
+    pub loads: u64,
+    /// Attempts after the first, over all loads
+    pub retries: u64,
+    /// Loads that succeeded after failing at first
+    pub recovered: u64,
+    /// Loads that failed in the end, out of attempts or with an error not
+    /// worth retrying
+    pub failed: u64,
+}
+
+/// Retrying of failed loads from the origin store with exponential backoff
+///
+/// A failed attempt is retried while attempts remain and the error is
+/// worth retrying, by default when [`CacheError::is_retryable`]. Missing
+/// keys, loaded as `None`, are not failures.
+pub struct RetryPolicy {
+    config: RetryConfig,
+    retry_on: Box<dyn Fn(&CacheError) -> bool + Send + Sync>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    loads: AtomicU64,
+    retries: AtomicU64,
+    recovered: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl RetryPolicy {
+    pub fn new(config: RetryConfig) -> Self {
+        Self {
+            config,
+            retry_on: Box::new(CacheError::is_retryable),
+            rate_limiter: None,
+            loads: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+            recovered: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    /// Retry the failures `retry_on` accepts instead of the retryable ones
+    pub fn with_retry_on<P>(mut self, retry_on: P) -> Self
+    where
+        P: Fn(&CacheError) -> bool + Send + Sync + 'static,
+    {
+        self.retry_on = Box::new(retry_on);
+        self
+    }
+
+    /// Make each retry wait its turn with `limiter` too
+    ///
+    /// The path a load comes from only counts its first attempt against
+    /// its limiter, so give the policy the same one to keep retries of a
+    /// throttled store within the limits.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    /// Wait before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let config = &self.config;
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = config.initial_backoff.as_secs_f64() * config.multiplier.powi(exponent);
+        let backoff = backoff.min(config.max_backoff.as_secs_f64());
+        if config.jitter <= 0.0 {
+            return Duration::from_secs_f64(backoff);
+        }
+        // Uniform in [0, 1]
+        let unit = RandomState::new().hash_one(Instant::now()) as f64 / u64::MAX as f64;
+        Duration::from_secs_f64(backoff * (1.0 - config.jitter * unit))
+    }
+
+    /// Load `key` with the fallible `loader`, retrying failures
+    ///
+    /// Returns the error of the last attempt if none succeeded.
+    pub async fn load<F, Fut>(&self, loader: F, key: String) -> Result<Option<Bytes>, CacheError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>>,
+    {
+        self.loads.fetch_add(1, Ordering::Relaxed);
+        let mut attempt = 1;
+        loop {
+            match loader(key.clone()).await {
+                Ok(data) => {
+                    if attempt > 1 {
+                        self.recovered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    return Ok(data);
+                }
+                Err(error) if attempt < self.config.max_attempts && (self.retry_on)(&error) => {
+                    let backoff = self.backoff(attempt);
+                    tracing::debug!(
+                        "Retrying load of key {} in {:?} after: {}",
+                        key,
+                        backoff,
+                        error
+                    );
+                    tokio::time::sleep(backoff).await;
+                    if let Some(limiter) = &self.rate_limiter {
+                        limiter.acquire().await;
+                    }
+                    self.retries.fetch_add(1, Ordering::Relaxed);
+                    attempt += 1;
+                }
+                Err(error) => {
+                    self.failed.fetch_add(1, Ordering::Relaxed);
+                    return Err(error);
+                }
+            }
+        }
+    }
+
+    /// Fallible loader that loads with `loader` through this policy, for
+    /// wrapping in a [`CircuitBreaker`]
+    pub fn try_wrap<F, Fut>(
+        self: &Arc<Self>,
+        loader: F,
+    ) -> impl Fn(String) -> TryLoadFuture + Clone + Send + Sync + 'static
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>> + Send + 'static,
+    {
+        let policy = Arc::clone(self);
+        let loader = Arc::new(loader);
+        move |key: String| -> TryLoadFuture {
+            let policy = Arc::clone(&policy);
+            let loader = Arc::clone(&loader);
+            Box::pin(async move { policy.load(&*loader, key).await })
+        }
+    }
+
+    /// Loader for the read-through, prefetch and warming paths that loads
+    /// with the fallible `loader` through this policy
+    ///
+    /// Keys that still fail to load are logged and loaded as `None`.
+    pub fn wrap<F, Fut>(
+        self: &Arc<Self>,
+        loader: F,
+    ) -> impl Fn(String) -> LoadFuture + Clone + Send + Sync + 'static
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>> + Send + 'static,
+    {
+        infallible(self.try_wrap(loader))
+    }
+
+    pub fn stats(&self) -> RetryStats {
+        RetryStats {
+            loads: self.loads.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            recovered: self.recovered.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Loader that loads with the fallible `loader`, logging the keys that fail
+/// and loading them as `None`
+fn infallible<L>(loader: L) -> impl Fn(String) -> LoadFuture + Clone + Send + Sync + 'static
+where
+    L: Fn(String) -> TryLoadFuture + Clone + Send + Sync + 'static,
+{
+    move |key: String| -> LoadFuture {
+        let load = loader(key.clone());
+        Box::pin(async move {
+            match load.await {
+                Ok(data) => data,
+                Err(error @ CacheError::Unavailable(_)) => {
+                    tracing::debug!("Skipped load of key {}: {}", key, error);
+                    None
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to load key {}: {}", key, error);
+                    None
+                }
+            }
+        })
+    }
+}
+
+/// Configuration of a [`CircuitBreaker`]
+///
+/// # Default Values
+/// - `failure_threshold`: 5 consecutive failures
+/// - `probe_interval`: 30 seconds
+/// - `serve_stale`: true
+///
+/// Settings missing from a deserialized configuration take these defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed loads that open the breaker
+    pub failure_threshold: u32,
+
+    /// Time an open breaker waits before letting a load through to probe
+    /// whether the origin store has recovered
+    #[serde(deserialize_with = "units::deserialize_duration")]
+    pub probe_interval: Duration,
+
+    /// Answer reads from cached copies, expired ones included, while the
+    /// breaker is open
+    pub serve_stale: bool,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            probe_interval: Duration::from_secs(30),
+            serve_stale: true,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    /// Builder starting from the default configuration
+    pub fn builder() -> CircuitBreakerConfigBuilder {
+        CircuitBreakerConfigBuilder::default()
+    }
+
+    /// Check that the settings are usable
+    pub fn validate(&self) -> Result<(), CacheError> {
+        let invalid = |message: &str| Err(CacheError::InvalidConfig(message.to_string()));
+
+        if self.failure_threshold == 0 {
+            return invalid("failure_threshold must be greater than zero");
+        }
+        if self.probe_interval.is_zero() {
+            return invalid("probe_interval must be greater than zero");
+        }
+        Ok(())
+    }
+
+    /// Load the configuration from a file, as
+    /// [`CacheConfig::from_file`](crate::CacheConfig::from_file) does, with
+    /// `ZARRS_CACHE_CIRCUIT_BREAKER_<SETTING>` overrides
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CacheError> {
+        config::read_file::<Self>(path.as_ref())?.override_from(std::env::vars())
+    }
+
+    /// Default configuration with `ZARRS_CACHE_CIRCUIT_BREAKER_<SETTING>`
+    /// overrides
+    pub fn from_env() -> Result<Self, CacheError> {
+        Self::default().override_from(std::env::vars())
+    }
+
+    /// Apply the overrides among `vars`, as
+    /// [`CacheConfig::override_from`](crate::CacheConfig::override_from) does
+    pub fn override_from(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, CacheError> {
+        config::apply_overrides(&self, config::CIRCUIT_BREAKER_ENV_PREFIX, vars)
+    }
+}
+
+/// Builder for [`CircuitBreakerConfig`], starting from its defaults
+///
+/// [`build`](CircuitBreakerConfigBuilder::build) validates the result.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreakerConfigBuilder {
+    config: CircuitBreakerConfig,
+    invalid: SetterError,
+}
+
+impl CircuitBreakerConfigBuilder {
+    pub fn failure_threshold(mut self, failures: u32) -> Self {
+        self.config.failure_threshold = failures;
+        self
+    }
+
+    pub fn probe_interval(mut self, interval: impl HumanDuration) -> Self {
+        if let Some(duration) = self.invalid.check(interval.to_duration()) {
+            self.config.probe_interval = duration;
+        }
+        self
+    }
+
+    pub fn serve_stale(mut self, enabled: bool) -> Self {
+        self.config.serve_stale = enabled;
+        self
+    }
+
+    /// Validate and return the configuration
+    pub fn build(self) -> Result<CircuitBreakerConfig, CacheError> {
+        self.invalid.into_result()?;
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// State of a [`CircuitBreaker`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Loads go through to the origin store
+    #[default]
+    Closed,
+    /// Loads are refused until the probe interval has passed
+    Open,
+    /// One load is probing the origin store; the others are refused
+    HalfOpen,
+}
+
+impl std::fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half-open",
+        })
+    }
+}
+
+/// State of a [`CircuitBreaker`] and the loads it has seen so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CircuitBreakerStats {
+    pub state: BreakerState,
+    /// Failed loads since the last one that succeeded
+    pub consecutive_failures: u32,
+    /// Times the breaker opened, reopenings after failed probes included
+    pub opened: u64,
+    /// Loads refused without trying the origin store
+    pub rejected: u64,
+    /// Loads let through an open breaker to probe the origin store
+    pub probes: u64,
+    /// Reads answered from cache, expired copies included, while the
+    /// breaker was open
+    pub stale_reads: u64,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    /// When the breaker last opened or started a probe
+    since: Instant,
+}
+
+/// Circuit breaker that stops loading from an origin store that keeps
+/// failing
+///
+/// After `failure_threshold` consecutive failed loads the breaker opens and
+/// refuses loads with [`CacheError::Unavailable`], instead of adding to the
+/// load of a struggling store. Once `probe_interval` has passed it lets one
+/// load through: the breaker closes if it succeeds and opens again if it
+/// fails. A [`CachedStore`](crate::CachedStore) given the breaker answers
+/// reads from cached copies, expired ones included, while it is open.
+///
+/// Failures are the errors for which [`CacheError::is_retryable`] holds,
+/// unless [`with_failure_on`](Self::with_failure_on) says otherwise; other
+/// errors show the store is answering, and count as successes.
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<BreakerInner>,
+    failure_on: Box<dyn Fn(&CacheError) -> bool + Send + Sync>,
+    events: Option<Arc<EventLog>>,
+    opened: AtomicU64,
+    rejected: AtomicU64,
+    probes: AtomicU64,
+    stale_reads: AtomicU64,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                since: Instant::now(),
+            }),
+            failure_on: Box::new(CacheError::is_retryable),
+            events: None,
+            opened: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            probes: AtomicU64::new(0),
+            stale_reads: AtomicU64::new(0),
+        }
+    }
+
+    /// Count the errors `failure_on` accepts as failures instead of the
+    /// retryable ones
+    pub fn with_failure_on<P>(mut self, failure_on: P) -> Self
+    where
+        P: Fn(&CacheError) -> bool + Send + Sync + 'static,
+    {
+        self.failure_on = Box::new(failure_on);
+        self
+    }
+
+    /// Record each change of state in `events`, under the key of the load
+    /// that caused it
+    pub fn with_event_log(mut self, events: Arc<EventLog>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).state
+    }
+
+    /// Load `key` with the fallible `loader` if the breaker lets it through
+    pub async fn load<F, Fut>(&self, loader: F, key: String) -> Result<Option<Bytes>, CacheError>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>>,
+    {
+        self.admit(&key)?;
+        let result = loader(key.clone()).await;
+        self.record(&key, &result);
+        result
+    }
+
+    /// Fallible loader that loads with `loader` through this breaker
+    ///
+    /// Wrap a [`RetryPolicy::try_wrap`] loader, so a load that fails after
+    /// all its retries counts as one failure.
+    pub fn try_wrap<F, Fut>(
+        self: &Arc<Self>,
+        loader: F,
+    ) -> impl Fn(String) -> TryLoadFuture + Clone + Send + Sync + 'static
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>> + Send + 'static,
+    {
+        let breaker = Arc::clone(self);
+        let loader = Arc::new(loader);
+        move |key: String| -> TryLoadFuture {
+            let breaker = Arc::clone(&breaker);
+            let loader = Arc::clone(&loader);
+            Box::pin(async move { breaker.load(&*loader, key).await })
+        }
+    }
+
+    /// Loader for the read-through, prefetch and warming paths that loads
+    /// with the fallible `loader` through this breaker
+    ///
+    /// Keys that fail to load or are refused are loaded as `None`.
+    pub fn wrap<F, Fut>(
+        self: &Arc<Self>,
+        loader: F,
+    ) -> impl Fn(String) -> LoadFuture + Clone + Send + Sync + 'static
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Option<Bytes>, CacheError>> + Send + 'static,
+    {
+        infallible(self.try_wrap(loader))
+    }
+
+    pub fn stats(&self) -> CircuitBreakerStats {
+        let (state, consecutive_failures) = {
+            let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            (inner.state, inner.consecutive_failures)
+        };
+        CircuitBreakerStats {
+            state,
+            consecutive_failures,
+            opened: self.opened.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            probes: self.probes.load(Ordering::Relaxed),
+            stale_reads: self.stale_reads.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a read answered from cache while the breaker was open
+    pub(crate) fn record_stale_read(&self) {
+        self.stale_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Let a load of `key` through, or refuse it
+    ///
+    /// A probe that never reports back, such as one whose future was
+    /// dropped, is replaced by another after the probe interval.
+    fn admit(&self, key: &str) -> Result<(), CacheError> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state == BreakerState::Closed {
+            return Ok(());
+        }
+        if inner.since.elapsed() < self.config.probe_interval {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(CacheError::Unavailable(format!(
+                "circuit breaker {} after {} consecutive failures",
+                inner.state, inner.consecutive_failures
+            )));
+        }
+        inner.state = BreakerState::HalfOpen;
+        inner.since = Instant::now();
+        drop(inner);
+        self.probes.fetch_add(1, Ordering::Relaxed);
+        self.log(CacheEventKind::BreakerProbe, key, CacheEventOutcome::Ok);
+        Ok(())
+    }
+
+    /// Update the state with the result of a load of `key`
+    fn record(&self, key: &str, result: &Result<Option<Bytes>, CacheError>) {
+        let failed = matches!(result, Err(error) if (self.failure_on)(error));
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let change = if !failed {
+            inner.consecutive_failures = 0;
+            (inner.state != BreakerState::Closed).then_some(BreakerState::Closed)
+        } else {
+            inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+            let trips = match inner.state {
+                BreakerState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+                BreakerState::HalfOpen => true,
+                BreakerState::Open => false,
+            };
+            trips.then_some(BreakerState::Open)
+        };
+        let Some(state) = change else {
+            return;
+        };
+        inner.state = state;
+        inner.since = Instant::now();
+        let failures = inner.consecutive_failures;
+        drop(inner);
+
+        if state == BreakerState::Open {
+            self.opened.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                "Circuit breaker opened after {} consecutive failed loads, last of key {}",
+                failures,
+                key
+            );
+            self.log(CacheEventKind::BreakerOpen, key, CacheEventOutcome::Error);
+        } else {
+            tracing::info!(
+                "Circuit breaker closed after a successful load of key {}",
+                key
+            );
+            self.log(CacheEventKind::BreakerClose, key, CacheEventOutcome::Ok);
+        }
+    }
+
+    fn log(&self, kind: CacheEventKind, key: &str, outcome: CacheEventOutcome) {
+        if let Some(events) = &self.events {
+            events.record(CacheEvent::new(kind, key, outcome));
+        }
+    }
+}
@@ -1,53 +1,201 @@
+use crate::cache::disk::DiskCache;
 use crate::cache::Cache;
 use crate::error::CacheError;
+use crate::sketch::TopK;
+use crate::upstream::{self, RateLimiter};
 use bytes::Bytes;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
-/// Cache warming strategy enum to avoid trait object issues
-#[derive(Debug)]
-pub enum WarmingStrategy {
-    Predictive(PredictiveWarming),
-    Neighbor(NeighborWarming),
+/// Most recent accesses remembered per key
+const MAX_HISTORY_PER_KEY: usize = 1000;
+
+/// Keys whose history is remembered; the least recently accessed are
+/// forgotten first
+const MAX_HISTORY_KEYS: usize = 10_000;
+
+/// Most frequently accessed keys a [`CacheWarmer`] reports by name
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Current format of saved access history
+const HISTORY_VERSION: u32 = 1;
+
+/// Hours either side of the current one counted as its access window
+const HOUR_WINDOW: usize = 1;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+const SECS_PER_HOUR: u64 = 60 * 60;
+
+/// Most recent hours of history a forecast is fitted to
+const FORECAST_HISTORY_HOURS: u64 = 28 * 24;
+
+/// Holt-Winters smoothing factors for the level, trend and hour-of-day
+/// seasonality of a forecast
+const SMOOTHING_LEVEL: f64 = 0.2;
+const SMOOTHING_TREND: f64 = 0.05;
+const SMOOTHING_SEASON: f64 = 0.3;
+
+/// How much of the trend carries into each further hour of a forecast
+const TREND_DAMPING: f64 = 0.9;
+
+/// Access history written by [`PredictiveWarming::save_history`]
+#[derive(Serialize, Deserialize)]
+struct PersistedHistory {
+    version: u32,
+    /// Access timestamps per key, in seconds since the Unix epoch
+    accesses: HashMap<String, Vec<u64>>,
 }
 
-impl WarmingStrategy {
+/// Source of keys to warm, e.g. learned access patterns or a known
+/// forecast schedule
+///
+/// Implement it to add custom warming logic and register it with
+/// [`CacheWarmer::add_strategy`]; the warmer loads and caches the keys.
+#[async_trait::async_trait]
+pub trait WarmingStrategy: Send + Sync {
     /// Generate keys to warm based on access patterns or predictions
-    pub async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String> {
-        match self {
-            WarmingStrategy::Predictive(strategy) => strategy.generate_warming_keys(context).await,
-            WarmingStrategy::Neighbor(strategy) => strategy.generate_warming_keys(context).await,
+    async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String>;
+}
+
+/// Limits on a single [`CacheWarmer::warm`] run, so warming cannot evict
+/// the live working set
+///
+/// # Default Values
+/// - `max_bytes`: `None` (unlimited)
+/// - `max_duration`: `None` (unlimited)
+/// - `max_capacity_fraction`: `None` (unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WarmingBudget {
+    /// Most value bytes loaded into the cache per run
+    pub max_bytes: Option<usize>,
+    /// Longest a run may spend loading keys
+    pub max_duration: Option<Duration>,
+    /// Most bytes per run as a fraction (0.0 to 1.0) of the cache's
+    /// capacity; ignored for unbounded caches
+    pub max_capacity_fraction: Option<f64>,
+}
+
+/// What is left of a [`WarmingBudget`] during a run
+struct RemainingBudget {
+    bytes: Option<usize>,
+    deadline: Option<Instant>,
+}
+
+impl RemainingBudget {
+    fn unlimited() -> Self {
+        Self {
+            bytes: None,
+            deadline: None,
         }
     }
 
-    /// Execute cache warming for the given keys
-    pub async fn warm_cache<C, F, Fut>(
-        &self,
-        cache: &C,
-        keys: Vec<String>,
-        loader: F,
-    ) -> Result<usize, CacheError>
-    where
-        C: Cache,
-        F: Fn(String) -> Fut + Send + Sync,
-        Fut: std::future::Future<Output = Option<Bytes>> + Send,
-    {
-        match self {
-            WarmingStrategy::Predictive(strategy) => strategy.warm_cache(cache, keys, loader).await,
-            WarmingStrategy::Neighbor(strategy) => strategy.warm_cache(cache, keys, loader).await,
+    /// Start spending `budget` on a cache of `capacity` bytes
+    fn start(budget: &WarmingBudget, capacity: Option<usize>) -> Self {
+        let fraction_bytes = budget
+            .max_capacity_fraction
+            .zip(capacity)
+            .map(|(fraction, capacity)| (capacity as f64 * fraction.clamp(0.0, 1.0)) as usize);
+        let bytes = match (budget.max_bytes, fraction_bytes) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            bytes,
+            deadline: budget.max_duration.map(|d| Instant::now() + d),
+        }
+    }
+
+    fn timed_out(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Take `len` bytes from the budget, or `false` if they do not fit
+    fn spend(&mut self, len: usize) -> bool {
+        match &mut self.bytes {
+            Some(remaining) if *remaining < len => false,
+            Some(remaining) => {
+                *remaining -= len;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Take `len` bytes from the budget even if they do not fit
+    fn charge(&mut self, len: usize) {
+        if let Some(remaining) = &mut self.bytes {
+            *remaining = remaining.saturating_sub(len);
+        }
+    }
+
+    fn exhausted(&self) -> bool {
+        self.bytes == Some(0) || self.timed_out()
+    }
+}
+
+/// Load each of `keys` that `cache` lacks with `loader` and cache it,
+/// returning how many were warmed
+///
+/// Stops early once a loaded value does not fit the remaining `budget` or
+/// its time runs out.
+async fn warm_keys<C, F, Fut>(
+    cache: &C,
+    keys: Vec<String>,
+    loader: F,
+    budget: &mut RemainingBudget,
+    limiter: Option<&Arc<RateLimiter>>,
+) -> Result<usize, CacheError>
+where
+    C: Cache,
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: std::future::Future<Output = Option<Bytes>> + Send,
+{
+    let mut warmed_count = 0;
+
+    for key in keys {
+        if budget.exhausted() {
+            tracing::debug!("Warming budget exhausted after {} keys", warmed_count);
+            break;
+        }
+
+        // Skip if already cached
+        if cache.get(&key).await.is_some() {
+            continue;
+        }
+
+        // Load and cache the data
+        if let Some(data) = upstream::limited_load(limiter, &loader, key.clone()).await {
+            if budget.timed_out() || !budget.spend(data.len()) {
+                tracing::debug!("Warming budget exhausted at key: {}", key);
+                break;
+            }
+            cache.set(&key, data).await?;
+            warmed_count += 1;
+            tracing::debug!("Warmed cache key: {}", key);
         }
     }
+
+    Ok(warmed_count)
 }
 
 /// Context information for cache warming decisions
 #[derive(Debug, Clone)]
 pub struct WarmingContext {
-    /// Recently accessed keys with their access counts
+    /// Most frequently accessed keys with their estimated access counts
     pub recent_access: HashMap<String, u64>,
     /// Current cache hit rate
     pub hit_rate: f64,
-    /// Available cache capacity (bytes)
+    /// Bytes the cache can take before it starts evicting, `usize::MAX` if
+    /// it is unbounded
     pub available_capacity: usize,
     /// Time-based patterns (hour of day, day of week, etc.)
     pub time_context: TimeContext,
@@ -55,39 +203,200 @@ pub struct WarmingContext {
 
 #[derive(Debug, Clone)]
 pub struct TimeContext {
+    /// Seconds since the Unix epoch
+    pub unix_secs: u64,
     pub hour_of_day: u8,
     pub day_of_week: u8,
     pub is_weekend: bool,
 }
 
 impl TimeContext {
+    /// Time context of now, in UTC
     pub fn current() -> Self {
-        use chrono::{Datelike, Timelike, Utc};
-        let now = Utc::now();
+        Self::from_datetime(chrono::Utc::now())
+    }
+
+    /// Time context of `secs` seconds since the Unix epoch, in UTC
+    pub fn at_unix_secs(secs: u64) -> Self {
+        let time = chrono::DateTime::from_timestamp(secs as i64, 0).unwrap_or_default();
+        Self::from_datetime(time)
+    }
+
+    fn from_datetime(time: chrono::DateTime<chrono::Utc>) -> Self {
+        use chrono::{Datelike, Timelike};
 
         Self {
-            hour_of_day: now.hour() as u8,
-            day_of_week: now.weekday().num_days_from_monday() as u8,
-            is_weekend: now.weekday().num_days_from_monday() >= 5,
+            unix_secs: time.timestamp().max(0) as u64,
+            hour_of_day: time.hour() as u8,
+            day_of_week: time.weekday().num_days_from_monday() as u8,
+            is_weekend: time.weekday().num_days_from_monday() >= 5,
         }
     }
 }
 
+/// Hour-of-day and day-of-week access counts of one key
+struct AccessHistogram {
+    hours: [u32; 24],
+    days: [u32; 7],
+    total: u32,
+    /// Seconds between the first and last access
+    span_secs: u64,
+}
+
+impl AccessHistogram {
+    /// Histogram of access timestamps sorted oldest first
+    fn from_timestamps(timestamps: &[u64]) -> Self {
+        let mut histogram = Self {
+            hours: [0; 24],
+            days: [0; 7],
+            total: 0,
+            span_secs: match (timestamps.first(), timestamps.last()) {
+                (Some(first), Some(last)) => last.saturating_sub(*first),
+                _ => 0,
+            },
+        };
+        for &timestamp in timestamps {
+            let time = TimeContext::at_unix_secs(timestamp);
+            histogram.hours[time.hour_of_day as usize] += 1;
+            histogram.days[time.day_of_week as usize] += 1;
+            histogram.total += 1;
+        }
+        histogram
+    }
+
+    /// Whether accesses around the hour and on the day of `time_context` are
+    /// at least as dense as if they were spread evenly
+    ///
+    /// Hours are only compared once the history spans a day, and days once
+    /// it spans a week; shorter histories cannot show such a pattern.
+    fn matches(&self, time_context: &TimeContext) -> bool {
+        if self.total == 0 {
+            return false;
+        }
+        let total = self.total as f64;
+
+        if self.span_secs >= SECS_PER_DAY {
+            let hour = time_context.hour_of_day as usize;
+            let window: u32 = (hour + 24 - HOUR_WINDOW..=hour + 24 + HOUR_WINDOW)
+                .map(|h| self.hours[h % 24])
+                .sum();
+            let window_hours = (2 * HOUR_WINDOW + 1) as f64;
+            if (window as f64) / total < window_hours / 24.0 {
+                return false;
+            }
+        }
+
+        if self.span_secs >= 7 * SECS_PER_DAY {
+            let day = self.days[time_context.day_of_week as usize % 7];
+            if (day as f64) / total < 1.0 / 7.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Holt-Winters forecast of one key's hourly access counts
+///
+/// Level, damped trend and additive hour-of-day seasonality, fitted by
+/// exponential smoothing. Histories shorter than two days carry no
+/// seasonality.
+struct AccessForecast {
+    level: f64,
+    trend: f64,
+    /// Seasonal offset per UTC hour of day
+    season: [f64; 24],
+    seasonal: bool,
+    /// Last fitted hour, in hours since the Unix epoch
+    last_hour: u64,
+}
+
+impl AccessForecast {
+    /// Fit to access timestamps sorted oldest first, counting the hours up
+    /// to `now_secs` without accesses
+    fn fit(timestamps: &[u64], now_secs: u64) -> Option<Self> {
+        let first_hour = timestamps.first()? / SECS_PER_HOUR;
+        let last_hour =
+            (timestamps.last()? / SECS_PER_HOUR).max((now_secs / SECS_PER_HOUR).saturating_sub(1));
+        let start_hour = first_hour.max((last_hour + 1).saturating_sub(FORECAST_HISTORY_HOURS));
+
+        let mut counts = vec![0.0; (last_hour - start_hour + 1) as usize];
+        for &timestamp in timestamps {
+            let hour = timestamp / SECS_PER_HOUR;
+            if hour >= start_hour {
+                counts[(hour - start_hour) as usize] += 1.0;
+            }
+        }
+
+        let mut forecast = Self {
+            level: counts[0],
+            trend: 0.0,
+            season: [0.0; 24],
+            seasonal: counts.len() >= 48,
+            last_hour,
+        };
+
+        // Seed seasonality from the first day and the trend from the second
+        let mut fitted = 1;
+        if forecast.seasonal {
+            let first_day = counts[..24].iter().sum::<f64>() / 24.0;
+            let second_day = counts[24..48].iter().sum::<f64>() / 24.0;
+            forecast.level = first_day;
+            forecast.trend = (second_day - first_day) / 24.0;
+            for (i, count) in counts[..24].iter().enumerate() {
+                forecast.season[Self::slot(start_hour + i as u64)] = count - first_day;
+            }
+            fitted = 24;
+        }
+
+        for (i, &count) in counts.iter().enumerate().skip(fitted) {
+            forecast.observe(Self::slot(start_hour + i as u64), count);
+        }
+        Some(forecast)
+    }
+
+    fn slot(hour: u64) -> usize {
+        (hour % 24) as usize
+    }
+
+    fn observe(&mut self, slot: usize, count: f64) {
+        let previous = self.level;
+        self.level = SMOOTHING_LEVEL * (count - self.season[slot])
+            + (1.0 - SMOOTHING_LEVEL) * (self.level + TREND_DAMPING * self.trend);
+        self.trend = SMOOTHING_TREND * (self.level - previous)
+            + (1.0 - SMOOTHING_TREND) * TREND_DAMPING * self.trend;
+        if self.seasonal {
+            self.season[slot] = SMOOTHING_SEASON * (count - self.level)
+                + (1.0 - SMOOTHING_SEASON) * self.season[slot];
+        }
+    }
+
+    /// Expected accesses during `hour`, in hours since the Unix epoch
+    fn expected_accesses(&self, hour: u64) -> f64 {
+        let steps = hour.saturating_sub(self.last_hour).max(1) as i32;
+        let damped = TREND_DAMPING * (1.0 - TREND_DAMPING.powi(steps)) / (1.0 - TREND_DAMPING);
+        (self.level + damped * self.trend + self.season[Self::slot(hour)]).max(0.0)
+    }
+}
+
 /// Predictive warming based on access patterns
 #[derive(Debug)]
 pub struct PredictiveWarming {
-    /// Historical access patterns
-    access_history: Arc<RwLock<HashMap<String, Vec<u64>>>>,
+    /// Historical access patterns of the most recently accessed keys
+    access_history: Arc<RwLock<LruCache<String, Vec<u64>>>>,
     /// Maximum keys to warm in one operation
     max_warm_keys: usize,
-    /// Minimum access frequency to consider for warming
+    /// Minimum forecast access rate, in accesses per second, to warm a key
     min_frequency: f64,
 }
 
 impl PredictiveWarming {
     pub fn new(max_warm_keys: usize, min_frequency: f64) -> Self {
         Self {
-            access_history: Arc::new(RwLock::new(HashMap::new())),
+            access_history: Arc::new(RwLock::new(LruCache::new(
+                NonZeroUsize::new(MAX_HISTORY_KEYS).expect("non-zero capacity"),
+            ))),
             max_warm_keys,
             min_frequency,
         }
@@ -96,7 +405,7 @@ impl PredictiveWarming {
     /// Record access for pattern learning
     pub async fn record_access(&self, key: &str) {
         let mut history = self.access_history.write().await;
-        let entry = history.entry(key.to_string()).or_insert_with(Vec::new);
+        let entry = history.get_or_insert_mut(key.to_string(), Vec::new);
 
         // Record timestamp (simplified as incrementing counter)
         let timestamp = std::time::SystemTime::now()
@@ -106,59 +415,139 @@ impl PredictiveWarming {
 
         entry.push(timestamp);
 
-        // Keep only recent history (last 1000 accesses)
-        if entry.len() > 1000 {
-            entry.drain(0..entry.len() - 1000);
+        // Keep only recent history
+        if entry.len() > MAX_HISTORY_PER_KEY {
+            entry.drain(0..entry.len() - MAX_HISTORY_PER_KEY);
+        }
+    }
+
+    /// Learned access timestamps per key, in seconds since the Unix epoch
+    pub async fn export_history(&self) -> HashMap<String, Vec<u64>> {
+        self.access_history
+            .read()
+            .await
+            .iter()
+            .map(|(key, timestamps)| (key.clone(), timestamps.clone()))
+            .collect()
+    }
+
+    /// Merge access timestamps, such as those of
+    /// [`export_history`](PredictiveWarming::export_history), into the
+    /// learned history
+    pub async fn import_history(&self, accesses: HashMap<String, Vec<u64>>) {
+        let mut history = self.access_history.write().await;
+        for (key, timestamps) in accesses {
+            let entry = history.get_or_insert_mut(key, Vec::new);
+            entry.extend(timestamps);
+            entry.sort_unstable();
+            if entry.len() > MAX_HISTORY_PER_KEY {
+                entry.drain(0..entry.len() - MAX_HISTORY_PER_KEY);
+            }
+        }
+    }
+
+    /// Write the learned access history to `path` as JSON
+    ///
+    /// The file is replaced atomically, so a crash never leaves a partial
+    /// history behind.
+    pub async fn save_history(&self, path: impl AsRef<Path>) -> Result<(), CacheError> {
+        let path = path.as_ref();
+        let persisted = PersistedHistory {
+            version: HISTORY_VERSION,
+            accesses: self.export_history().await,
+        };
+        let contents =
+            serde_json::to_vec(&persisted).map_err(|e| CacheError::Serialization(e.to_string()))?;
+
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        std::fs::create_dir_all(dir)?;
+        let mut tmp = tempfile::Builder::new()
+            .prefix(".history-")
+            .suffix(".tmp")
+            .tempfile_in(dir)?;
+        tmp.write_all(&contents)?;
+        tmp.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Merge the access history saved at `path` by
+    /// [`save_history`](PredictiveWarming::save_history), returning how many
+    /// keys it held
+    ///
+    /// A missing file loads nothing, so the first start needs no special case.
+    pub async fn load_history(&self, path: impl AsRef<Path>) -> Result<usize, CacheError> {
+        let contents = match std::fs::read(path.as_ref()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let persisted: PersistedHistory = serde_json::from_slice(&contents)
+            .map_err(|e| CacheError::Serialization(e.to_string()))?;
+        if persisted.version != HISTORY_VERSION {
+            return Err(CacheError::Serialization(format!(
+                "unsupported access history version {}",
+                persisted.version
+            )));
         }
+
+        let keys = persisted.accesses.len();
+        self.import_history(persisted.accesses).await;
+        Ok(keys)
     }
 
-    /// Predict next likely accessed keys based on patterns
+    /// Predict the keys most likely accessed in the hours around now
+    ///
+    /// Each key's hourly access counts are forecast with Holt-Winters
+    /// smoothing, so keys whose use is rising or due at this hour rank above
+    /// keys that were merely popular once. Keys are ranked by their expected
+    /// accesses over the window of [`HOUR_WINDOW`] hours either side of the
+    /// current one.
     async fn predict_next_keys(&self, context: &WarmingContext) -> Vec<String> {
         let history = self.access_history.read().await;
+        let now_hour = context.time_context.unix_secs / SECS_PER_HOUR;
+        let window_hours = 2 * HOUR_WINDOW as u64 + 1;
         let mut predictions = Vec::new();
 
         for (key, accesses) in history.iter() {
             if accesses.len() < 2 {
                 continue;
             }
-
-            // Calculate access frequency
-            let time_span = accesses.last().unwrap() - accesses.first().unwrap();
-            if time_span == 0 {
+            let Some(forecast) = AccessForecast::fit(accesses, context.time_context.unix_secs)
+            else {
                 continue;
-            }
-
-            let frequency = accesses.len() as f64 / time_span as f64;
-
-            if frequency >= self.min_frequency {
-                // Check if this key fits current time patterns
-                if self.matches_time_pattern(key, &context.time_context) {
-                    predictions.push(key.clone());
-                }
+            };
+
+            let expected: f64 = (0..window_hours)
+                .map(|i| {
+                    forecast.expected_accesses((now_hour + i).saturating_sub(HOUR_WINDOW as u64))
+                })
+                .sum();
+            let rate = expected / (window_hours * SECS_PER_HOUR) as f64;
+
+            // Check if this key fits current time patterns
+            if expected > 0.0
+                && rate >= self.min_frequency
+                && self.matches_time_pattern(accesses, &context.time_context)
+            {
+                predictions.push((key.clone(), expected));
             }
         }
 
-        // Sort by recent access frequency
-        predictions.sort_by(|a, b| {
-            let a_count = context.recent_access.get(a).unwrap_or(&0);
-            let b_count = context.recent_access.get(b).unwrap_or(&0);
-            b_count.cmp(a_count)
-        });
-
+        predictions.sort_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then_with(|| a_key.cmp(b_key)));
         predictions.truncate(self.max_warm_keys);
-        predictions
-    }
-
-    /// Check if key matches time-based access patterns
-    fn matches_time_pattern(&self, _key: &str, _time_context: &TimeContext) -> bool {
-        // Simplified implementation - in practice, this would analyze historical
-        // access patterns for time-based correlations
-        true
+        predictions.into_iter().map(|(key, _)| key).collect()
     }
 
-    /// Generate warming keys based on predictions
-    pub async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String> {
-        self.predict_next_keys(context).await
+    /// Check if a key's access timestamps match the time of `time_context`
+    ///
+    /// Keys are only warmed in the hours and on the days of the week they
+    /// were historically accessed in; the forecast alone has no weekly
+    /// seasonality.
+    fn matches_time_pattern(&self, accesses: &[u64], time_context: &TimeContext) -> bool {
+        AccessHistogram::from_timestamps(accesses).matches(time_context)
     }
 
     /// Execute cache warming
@@ -173,23 +562,15 @@ impl PredictiveWarming {
         F: Fn(String) -> Fut + Send + Sync,
         Fut: std::future::Future<Output = Option<Bytes>> + Send,
     {
-        let mut warmed_count = 0;
-
-        for key in keys {
-            // Skip if already cached
-            if cache.get(&key).await.is_some() {
-                continue;
-            }
-
-            // Load and cache the data
-            if let Some(data) = loader(key.clone()).await {
-                cache.set(&key, data).await?;
-                warmed_count += 1;
-                tracing::debug!("Warmed cache key: {}", key);
-            }
-        }
+        warm_keys(cache, keys, loader, &mut RemainingBudget::unlimited(), None).await
+    }
+}
 
-        Ok(warmed_count)
+#[async_trait::async_trait]
+impl WarmingStrategy for PredictiveWarming {
+    /// Generate warming keys based on predictions
+    async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String> {
+        self.predict_next_keys(context).await
     }
 }
 
@@ -262,8 +643,26 @@ impl NeighborWarming {
 }
 
 impl NeighborWarming {
+    /// Execute cache warming for neighbor keys
+    pub async fn warm_cache<C, F, Fut>(
+        &self,
+        cache: &C,
+        keys: Vec<String>,
+        loader: F,
+    ) -> Result<usize, CacheError>
+    where
+        C: Cache,
+        F: Fn(String) -> Fut + Send + Sync,
+        Fut: std::future::Future<Output = Option<Bytes>> + Send,
+    {
+        warm_keys(cache, keys, loader, &mut RemainingBudget::unlimited(), None).await
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmingStrategy for NeighborWarming {
     /// Generate warming keys based on neighbors
-    pub async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String> {
+    async fn generate_warming_keys(&self, context: &WarmingContext) -> Vec<String> {
         let mut all_neighbors = Vec::new();
 
         // Generate neighbors for recently accessed keys
@@ -279,44 +678,193 @@ impl NeighborWarming {
 
         all_neighbors
     }
+}
 
-    /// Execute cache warming for neighbor keys
-    pub async fn warm_cache<C, F, Fut>(
-        &self,
-        cache: &C,
-        keys: Vec<String>,
-        loader: F,
-    ) -> Result<usize, CacheError>
+/// Warms exactly the keys of a manifest
+///
+/// For when the keys the next job reads are known up front: the manifest
+/// is built from explicit keys, glob patterns matched against a key listing,
+/// and ranges of chunk coordinates. Keys are warmed in the order they were
+/// added, regardless of access patterns.
+#[derive(Debug, Clone, Default)]
+pub struct ManifestWarming {
+    keys: Vec<String>,
+}
+
+impl ManifestWarming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add these exact keys
+    pub fn with_keys<I, K>(mut self, keys: I) -> Self
     where
-        C: Cache,
-        F: Fn(String) -> Fut + Send + Sync,
-        Fut: std::future::Future<Output = Option<Bytes>> + Send,
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
     {
-        let mut warmed_count = 0;
+        self.keys.extend(keys.into_iter().map(Into::into));
+        self
+    }
 
-        for key in keys {
-            // Skip if already cached
-            if cache.get(&key).await.is_some() {
-                continue;
-            }
+    /// Add the keys of `candidates`, such as a store listing, that match
+    /// `pattern`
+    ///
+    /// `*` matches any run of characters and `?` any single character,
+    /// neither crossing a `/`, so `temperature/c/0/*/*` names every chunk of
+    /// the first time step.
+    pub fn with_glob<I, K>(mut self, pattern: &str, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        self.keys.extend(
+            candidates
+                .into_iter()
+                .map(Into::into)
+                .filter(|key| glob_matches(pattern, key)),
+        );
+        self
+    }
 
-            // Load and cache the data
-            if let Some(data) = loader(key.clone()).await {
-                cache.set(&key, data).await?;
-                warmed_count += 1;
-                tracing::debug!("Warmed neighbor key: {}", key);
+    /// Add the chunk keys of every coordinate in `ranges`, one range per
+    /// dimension, as `prefix` followed by the coordinates joined by
+    /// `separator`
+    ///
+    /// `("temperature/c/", &[0..1, 0..2], '/')` names `temperature/c/0/0`
+    /// and `temperature/c/0/1`.
+    pub fn with_coordinate_range(
+        mut self,
+        prefix: &str,
+        ranges: &[std::ops::Range<u64>],
+        separator: char,
+    ) -> Self {
+        if ranges.is_empty() || ranges.iter().any(|range| range.is_empty()) {
+            return self;
+        }
+
+        let mut coords: Vec<u64> = ranges.iter().map(|range| range.start).collect();
+        loop {
+            let joined = coords
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(&separator.to_string());
+            self.keys.push(format!("{}{}", prefix, joined));
+
+            // Advance the last dimension fastest, like C-order chunk iteration
+            let mut dim = ranges.len();
+            loop {
+                if dim == 0 {
+                    return self;
+                }
+                dim -= 1;
+                coords[dim] += 1;
+                if coords[dim] < ranges[dim].end {
+                    break;
+                }
+                coords[dim] = ranges[dim].start;
             }
         }
+    }
+
+    /// Keys in the manifest, without duplicates
+    pub fn keys(&self) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        self.keys
+            .iter()
+            .filter(|key| seen.insert(key.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmingStrategy for ManifestWarming {
+    async fn generate_warming_keys(&self, _context: &WarmingContext) -> Vec<String> {
+        self.keys()
+    }
+}
+
+/// Warms the hottest keys of another worker's disk cache
+///
+/// Pair it with [`CacheWarmer::warm_from`] to copy them from that cache
+/// rather than the origin store, so a new worker starts with its peer's
+/// working set. The peer can be opened on a teammate's cache directory;
+/// it is only read.
+pub struct PeerWarming {
+    peer: Arc<DiskCache>,
+    max_warm_keys: usize,
+}
+
+impl PeerWarming {
+    pub fn new(peer: Arc<DiskCache>, max_warm_keys: usize) -> Self {
+        Self {
+            peer,
+            max_warm_keys,
+        }
+    }
+
+    pub fn peer(&self) -> &Arc<DiskCache> {
+        &self.peer
+    }
+}
+
+#[async_trait::async_trait]
+impl WarmingStrategy for PeerWarming {
+    async fn generate_warming_keys(&self, _context: &WarmingContext) -> Vec<String> {
+        self.peer
+            .hottest_keys(self.max_warm_keys)
+            .await
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+}
 
-        Ok(warmed_count)
+/// Whether `key` matches the glob `pattern`, whose `*` and `?` never match `/`
+fn glob_matches(pattern: &str, key: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let key: Vec<char> = key.chars().collect();
+
+    // Position after the last `*` and the key position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut k) = (0, 0);
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, k));
+                p += 1;
+            }
+            Some('?') if key[k] != '/' => {
+                p += 1;
+                k += 1;
+            }
+            Some(&c) if c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            _ => match star {
+                // Let the last `*` swallow one more character, within its segment
+                Some((after_star, tried)) if key[tried] != '/' => {
+                    star = Some((after_star, tried + 1));
+                    p = after_star;
+                    k = tried + 1;
+                }
+                _ => return false,
+            },
+        }
     }
+    pattern[p..].iter().all(|&c| c == '*')
 }
 
 /// Cache warmer that coordinates warming strategies
 pub struct CacheWarmer<C: Cache> {
     cache: Arc<C>,
-    strategies: Vec<WarmingStrategy>,
-    access_tracker: Arc<RwLock<HashMap<String, u64>>>,
+    strategies: Vec<Box<dyn WarmingStrategy>>,
+    priority_keys: Vec<String>,
+    access_tracker: Arc<RwLock<TopK>>,
+    budget: WarmingBudget,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl<C: Cache> CacheWarmer<C> {
@@ -324,47 +872,124 @@ impl<C: Cache> CacheWarmer<C> {
         Self {
             cache,
             strategies: Vec::new(),
-            access_tracker: Arc::new(RwLock::new(HashMap::new())),
+            priority_keys: Vec::new(),
+            access_tracker: Arc::new(RwLock::new(TopK::new(MAX_TRACKED_KEYS))),
+            budget: WarmingBudget::default(),
+            rate_limiter: None,
+        }
+    }
+
+    /// Keys to keep warm, such as metadata, coordinate arrays or the first
+    /// timestep
+    ///
+    /// Every [`warm`](Self::warm) run reloads them first, even if cached,
+    /// and whatever the budget; their bytes are taken from the budget before
+    /// the strategies spend it.
+    pub fn with_priority_keys<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        for key in keys {
+            let key = key.into();
+            if !self.priority_keys.contains(&key) {
+                self.priority_keys.push(key);
+            }
         }
+        self
+    }
+
+    /// Limit how much each [`warm`](Self::warm) run may load
+    pub fn with_budget(mut self, budget: WarmingBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Load keys, priority keys included, only as fast as `limiter` allows
+    ///
+    /// Loads from a peer in [`warm_from`](Self::warm_from) are limited too.
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
     }
 
     /// Add a warming strategy
-    pub fn add_strategy(mut self, strategy: WarmingStrategy) -> Self {
-        self.strategies.push(strategy);
+    pub fn add_strategy<S: WarmingStrategy + 'static>(mut self, strategy: S) -> Self {
+        self.strategies.push(Box::new(strategy));
         self
     }
 
     /// Record access for warming decisions
     pub async fn record_access(&self, key: &str) {
-        let mut tracker = self.access_tracker.write().await;
-        *tracker.entry(key.to_string()).or_insert(0) += 1;
+        self.access_tracker.write().await.increment(key);
     }
 
     /// Execute cache warming using all configured strategies
+    ///
+    /// Priority keys are refreshed first, then strategies run in the order
+    /// they were added, sharing the run's [`WarmingBudget`]; warming stops
+    /// once it is spent.
     pub async fn warm<F, Fut>(&self, loader: F) -> Result<usize, CacheError>
     where
         F: Fn(String) -> Fut + Send + Sync + Clone,
         Fut: std::future::Future<Output = Option<Bytes>> + Send,
     {
         let context = self.build_warming_context().await;
+        let mut budget = RemainingBudget::start(&self.budget, self.cache.capacity());
         let mut total_warmed = 0;
 
+        for key in &self.priority_keys {
+            if let Some(data) =
+                upstream::limited_load(self.rate_limiter.as_ref(), &loader, key.clone()).await
+            {
+                budget.charge(data.len());
+                self.cache.set(key, data).await?;
+                total_warmed += 1;
+                tracing::debug!("Refreshed priority key: {}", key);
+            }
+        }
+
         for strategy in &self.strategies {
+            if budget.exhausted() {
+                break;
+            }
             let keys = strategy.generate_warming_keys(&context).await;
             if !keys.is_empty() {
-                let warmed = strategy
-                    .warm_cache(&*self.cache, keys, loader.clone())
-                    .await?;
-                total_warmed += warmed;
+                total_warmed += warm_keys(
+                    &*self.cache,
+                    keys,
+                    loader.clone(),
+                    &mut budget,
+                    self.rate_limiter.as_ref(),
+                )
+                .await?;
             }
         }
 
         Ok(total_warmed)
     }
 
+    /// Execute cache warming, loading keys from `peer`, such as another
+    /// worker's cache or a remote tier, instead of the origin store
+    ///
+    /// Keys the peer lacks are skipped.
+    pub async fn warm_from<P: Cache + ?Sized>(&self, peer: &Arc<P>) -> Result<usize, CacheError> {
+        self.warm(|key| {
+            let peer = Arc::clone(peer);
+            async move { peer.get(&key).await }
+        })
+        .await
+    }
+
     /// Build warming context from current state
     async fn build_warming_context(&self) -> WarmingContext {
-        let recent_access = self.access_tracker.read().await.clone();
+        let recent_access = self
+            .access_tracker
+            .read()
+            .await
+            .iter()
+            .map(|(key, count)| (key.to_string(), count))
+            .collect();
         let stats = self.cache.stats();
 
         let hit_rate = if stats.hits + stats.misses > 0 {
@@ -373,8 +998,10 @@ impl<C: Cache> CacheWarmer<C> {
             0.0
         };
 
-        // Estimate available capacity (simplified)
-        let available_capacity = (1024_usize * 1024 * 100).saturating_sub(stats.size_bytes);
+        // Unbounded caches have room for anything
+        let available_capacity = self.cache.capacity().map_or(usize::MAX, |capacity| {
+            capacity.saturating_sub(stats.size_bytes)
+        });
 
         WarmingContext {
             recent_access,
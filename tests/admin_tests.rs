@@ -0,0 +1,140 @@
+#![cfg(feature = "admin")]
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use bytes::Bytes;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+use zarrs_cache::admin::{AdminApi, AdminCache, HotKey, InvalidateResponse, StatsResponse};
+use zarrs_cache::{Cache, HybridCache, HybridCacheConfig, LruMemoryCache};
+
+async fn request(router: &Router, method: Method, uri: &str) -> (StatusCode, serde_json::Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json = if body.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&body).unwrap()
+    };
+    (status, json)
+}
+
+#[tokio::test]
+async fn test_admin_api_reports_and_invalidates() {
+    let temp_dir = TempDir::new().unwrap();
+    let hybrid = Arc::new(
+        HybridCache::new(HybridCacheConfig {
+            disk_dir: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        })
+        .unwrap(),
+    );
+    for key in ["temp/0.0", "temp/0.1", "precip/0.0"] {
+        hybrid.set(key, Bytes::from(vec![0u8; 100])).await.unwrap();
+    }
+    for _ in 0..3 {
+        hybrid.get("precip/0.0").await;
+    }
+    let memory = Arc::new(LruMemoryCache::new(1024));
+    let router = AdminApi::new()
+        .with_cache("climate", AdminCache::hybrid(hybrid.clone()))
+        .with_cache("plain", AdminCache::new(memory))
+        .router();
+
+    let (status, names) = request(&router, Method::GET, "/caches").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names, serde_json::json!(["climate", "plain"]));
+
+    let (status, stats) = request(&router, Method::GET, "/caches/climate/stats").await;
+    assert_eq!(status, StatusCode::OK);
+    let stats: StatsResponse = serde_json::from_value(stats).unwrap();
+    assert_eq!(stats.stats.hits, 3);
+
+    let (status, tiers) = request(&router, Method::GET, "/caches/climate/tiers").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tiers["disk"]["entry_count"], 3);
+
+    let (status, hot) = request(&router, Method::GET, "/caches/climate/hot-keys?limit=1").await;
+    assert_eq!(status, StatusCode::OK);
+    let hot: Vec<HotKey> = serde_json::from_value(hot).unwrap();
+    assert_eq!(hot.len(), 1);
+    assert_eq!(hot[0].key, "precip/0.0");
+
+    let (status, removed) = request(
+        &router,
+        Method::POST,
+        "/caches/climate/invalidate?prefix=temp/",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let removed: InvalidateResponse = serde_json::from_value(removed).unwrap();
+    assert!(removed.removed >= 2);
+    assert!(hybrid.get("temp/0.0").await.is_none());
+    assert!(hybrid.get("temp/0.1").await.is_none());
+    assert!(hybrid.get("precip/0.0").await.is_some());
+
+    let (status, _) = request(&router, Method::POST, "/caches/climate/clear").await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(hybrid.get("precip/0.0").await.is_none());
+}
+
+#[tokio::test]
+async fn test_admin_api_errors() {
+    let memory = Arc::new(LruMemoryCache::new(1024));
+    let router = AdminApi::new()
+        .with_cache("plain", AdminCache::new(memory))
+        .router();
+
+    let (status, body) = request(&router, Method::GET, "/caches/missing/stats").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body["error"].as_str().unwrap().contains("missing"));
+
+    // Plain caches have no tiers, tracked keys, metrics or warming
+    for uri in [
+        "/caches/plain/tiers",
+        "/caches/plain/hot-keys",
+        "/caches/plain/report",
+    ] {
+        assert_eq!(
+            request(&router, Method::GET, uri).await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+    assert_eq!(
+        request(&router, Method::POST, "/caches/plain/warm").await.0,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn test_admin_api_triggers_warming() {
+    let cache = Arc::new(LruMemoryCache::new(1024));
+    let warmed = cache.clone();
+    let router = AdminApi::new()
+        .with_cache(
+            "plain",
+            AdminCache::new(cache.clone()).with_warming(move || {
+                let cache = warmed.clone();
+                async move {
+                    cache.set("temp/.zarray", Bytes::from("{}")).await?;
+                    Ok(1)
+                }
+            }),
+        )
+        .router();
+
+    let (status, body) = request(&router, Method::POST, "/caches/plain/warm").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["warmed"], 1);
+    assert!(cache.get("temp/.zarray").await.is_some());
+}
@@ -0,0 +1,179 @@
+use bytes::Bytes;
+use tempfile::TempDir;
+use zarrs_cache::cache::entry_footprint;
+use zarrs_cache::{
+    AdmissionCache, AdmissionPolicy, AlwaysAdmit, Cache, HybridCache, HybridCacheConfig,
+    LruMemoryCache, TinyLfu,
+};
+
+#[test]
+fn test_tinylfu_requires_repeat_access() {
+    let policy = TinyLfu::new(1024);
+
+    assert!(!policy.admit("array/0.0", None));
+    policy.record_access("array/0.0");
+    assert!(!policy.admit("array/0.0", None));
+    policy.record_access("array/0.0");
+    assert!(policy.admit("array/0.0", None));
+
+    policy.clear();
+    assert_eq!(policy.frequency("array/0.0"), 0);
+}
+
+#[test]
+fn test_tinylfu_weighs_candidate_against_victim() {
+    let policy = TinyLfu::new(1024);
+    for _ in 0..3 {
+        policy.record_access("hot");
+    }
+    for _ in 0..2 {
+        policy.record_access("warm");
+    }
+
+    // Frequent enough on its own, but not more than what it would replace
+    assert!(policy.admit("warm", None));
+    assert!(!policy.admit("warm", Some("hot")));
+    assert!(policy.admit("hot", Some("warm")));
+    assert!(!policy.admit("cold", Some("never-seen")));
+}
+
+#[tokio::test]
+async fn test_always_admit_passes_through() {
+    let cache = AdmissionCache::new(LruMemoryCache::new(1024), AlwaysAdmit);
+    let key = "key".to_string();
+
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("value")));
+}
+
+#[tokio::test]
+async fn test_scan_does_not_evict_working_set() {
+    let capacity = 10 * entry_footprint("hot/0", 10);
+    let cache = AdmissionCache::new(LruMemoryCache::new(capacity), TinyLfu::new(1024));
+
+    // Working set: requested (miss), loaded, then admitted on the second set
+    for i in 0..5 {
+        let key = format!("hot/{}", i);
+        assert!(cache.get(&key).await.is_none());
+        cache.set(&key, Bytes::from(vec![0u8; 10])).await.unwrap();
+        cache.set(&key, Bytes::from(vec![0u8; 10])).await.unwrap();
+    }
+    assert_eq!(cache.stats().entry_count, 5);
+
+    // One-touch scan is rejected
+    for i in 0..50 {
+        let key = format!("scan/{}", i);
+        cache.set(&key, Bytes::from(vec![1u8; 10])).await.unwrap();
+    }
+
+    for i in 0..5 {
+        assert!(cache.get(&format!("hot/{}", i)).await.is_some());
+    }
+    assert!(cache.inner().get("scan/0").await.is_none());
+}
+
+#[tokio::test]
+async fn test_full_cache_keeps_more_popular_victim() {
+    let capacity = 2 * entry_footprint("hot/0", 10);
+    let cache = AdmissionCache::new(LruMemoryCache::new(capacity), TinyLfu::new(1024));
+
+    for key in ["hot/0", "hot/1"] {
+        cache
+            .inner()
+            .set(key, Bytes::from(vec![0u8; 10]))
+            .await
+            .unwrap();
+        for _ in 0..4 {
+            assert!(cache.get(key).await.is_some());
+        }
+    }
+
+    // Passes min_frequency, but is less popular than the LRU entry it evicts
+    assert!(cache.get("new/0").await.is_none());
+    assert!(cache.get("new/0").await.is_none());
+    assert_eq!(
+        cache
+            .inner()
+            .eviction_candidate("new/0", 10)
+            .await
+            .as_deref(),
+        Some("hot/0")
+    );
+    cache
+        .set("new/0", Bytes::from(vec![1u8; 10]))
+        .await
+        .unwrap();
+    assert!(cache.inner().get("new/0").await.is_none());
+    assert!(cache.get("hot/0").await.is_some());
+
+    // Once more popular than the victim it is admitted in its place
+    for _ in 0..6 {
+        cache.get("new/0").await;
+    }
+    cache
+        .set("new/0", Bytes::from(vec![1u8; 10]))
+        .await
+        .unwrap();
+    assert!(cache.inner().get("new/0").await.is_some());
+    assert_eq!(cache.stats().entry_count, 2);
+}
+
+#[tokio::test]
+async fn test_w_tiny_lfu_window_holds_new_entries() {
+    let cache = AdmissionCache::w_tiny_lfu(LruMemoryCache::new(1024), 1024, 256);
+
+    cache.set("array/0.0", Bytes::from("chunk")).await.unwrap();
+    assert!(cache.inner().get("array/0.0").await.is_none());
+    assert_eq!(cache.get("array/0.0").await, Some(Bytes::from("chunk")));
+    assert_eq!(
+        cache.inner().get("array/0.0").await,
+        Some(Bytes::from("chunk"))
+    );
+}
+
+#[tokio::test]
+async fn test_window_holds_rejected_entries_until_popular() {
+    let cache =
+        AdmissionCache::new(LruMemoryCache::new(1024), TinyLfu::new(1024)).with_window(1024);
+    let key = "array/1.2".to_string();
+
+    cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert!(cache.inner().get(&key).await.is_none());
+
+    // Served from the window, and promoted once it passes the filter
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    assert_eq!(cache.inner().get(&key).await, Some(Bytes::from("chunk")));
+}
+
+#[tokio::test]
+async fn test_rejected_update_drops_stale_value() {
+    let cache = AdmissionCache::new(LruMemoryCache::new(1024), TinyLfu::new(1024));
+    let key = "key".to_string();
+
+    cache.inner().set(&key, Bytes::from("old")).await.unwrap();
+    cache.set(&key, Bytes::from("new")).await.unwrap();
+
+    assert!(cache.get(&key).await.is_none());
+}
+
+#[tokio::test]
+async fn test_hybrid_memory_tier_with_admission_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 1024,
+        disk_size: Some(1024 * 1024),
+        disk_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    let cache = HybridCache::new(config)
+        .unwrap()
+        .with_admission_policy(TinyLfu::new(1024));
+
+    let key = "array/0.0".to_string();
+    let value = Bytes::from("chunk_data");
+
+    // Rejected from memory but still served from disk
+    cache.set(&key, value.clone()).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(value));
+}
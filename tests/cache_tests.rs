@@ -1,8 +1,16 @@
 use bytes::Bytes;
+use std::sync::Arc;
 use std::time::Duration;
 use tempfile::TempDir;
 use tokio::time::sleep;
-use zarrs_cache::{Cache, DiskCache, LruMemoryCache};
+use zarrs_cache::cache::disk::JOURNAL_FILE_NAME;
+use zarrs_cache::cache::entry_footprint;
+use zarrs_cache::{
+    AdmissionCache, AlwaysAdmit, ArcMemoryCache, Cache, CacheError, ClockMemoryCache, DiskCache,
+    DiskCacheConfig, DiskEvictionPolicy, DiskGcReport, DiskIoBackend, DiskLayout, LruMemoryCache,
+    MemoryPolicy, PackedDiskCache, PackedDiskCacheConfig, RemovalCause, RemovalCounts,
+    ShardedMemoryCache,
+};
 
 #[tokio::test]
 async fn test_lru_memory_cache_basic_operations() {
@@ -39,7 +47,7 @@ async fn test_lru_memory_cache_basic_operations() {
 
 #[tokio::test]
 async fn test_lru_memory_cache_eviction() {
-    let cache = LruMemoryCache::new(20); // Very small cache
+    let cache = LruMemoryCache::new(200); // Room for a single entry including overhead
 
     let key1 = "key1".to_string();
     let key2 = "key2".to_string();
@@ -142,6 +150,291 @@ async fn test_disk_cache_with_ttl() {
     assert!(cache.get(&key).await.is_none());
 }
 
+#[tokio::test]
+async fn test_disk_cache_file_handle_pool() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        file_handle_pool_size: Some(2),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let keys: Vec<String> = (0..4).map(|i| format!("array/c/{}", i)).collect();
+    for (i, key) in keys.iter().enumerate() {
+        cache
+            .set(key, Bytes::from(vec![i as u8; 100]))
+            .await
+            .unwrap();
+    }
+    // More keys than pooled handles
+    for _ in 0..3 {
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(cache.get(key).await, Some(Bytes::from(vec![i as u8; 100])));
+        }
+    }
+
+    // A pooled handle is not reused once the entry is rewritten
+    assert!(cache.get(&keys[3]).await.is_some());
+    cache.set(&keys[3], Bytes::from("rewritten")).await.unwrap();
+    assert_eq!(cache.get(&keys[3]).await, Some(Bytes::from("rewritten")));
+    cache.remove(&keys[3]).await.unwrap();
+    assert!(cache.get(&keys[3]).await.is_none());
+    cache.set(&keys[3], Bytes::from("again")).await.unwrap();
+    assert_eq!(cache.get(&keys[3]).await, Some(Bytes::from("again")));
+
+    // Reads through pooled handles are verified too
+    for path in cache_files(temp_dir.path()) {
+        let mut contents = std::fs::read(&path).unwrap();
+        let last = contents.len() - 1;
+        contents[last] ^= 0x01;
+        std::fs::write(&path, contents).unwrap();
+    }
+    for key in &keys {
+        assert!(cache.get(key).await.is_none());
+    }
+    assert_eq!(cache.corrupted_entries(), 4);
+}
+
+#[tokio::test]
+async fn test_disk_cache_key_filter() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        // Outgrown several times below
+        key_filter_capacity: Some(100),
+        ..Default::default()
+    };
+    let cache = DiskCache::with_config(config.clone()).unwrap();
+
+    let keys: Vec<String> = (0..1000).map(|i| format!("array/c/{}/0", i)).collect();
+    for key in &keys {
+        cache.set(key, Bytes::from("chunk")).await.unwrap();
+    }
+    for key in &keys {
+        assert_eq!(cache.get(key).await, Some(Bytes::from("chunk")));
+    }
+    assert_eq!(cache.key_filter_rejections(), 0);
+
+    // Nearly all misses on never-cached keys skip the index
+    for i in 0..1000 {
+        assert!(cache.get(&format!("other/c/{}/0", i)).await.is_none());
+    }
+    assert!(cache.key_filter_rejections() > 950);
+    drop(cache);
+
+    // The filter is rebuilt from the index on startup and after a clear
+    let cache = DiskCache::with_config(config).unwrap();
+    assert_eq!(cache.get(&keys[0]).await, Some(Bytes::from("chunk")));
+    cache.clear().await.unwrap();
+    assert!(cache.get(&keys[0]).await.is_none());
+    assert_eq!(cache.key_filter_rejections(), 1);
+}
+
+#[tokio::test]
+async fn test_disk_cache_expiry_sweeper() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = Arc::new(
+        DiskCache::with_ttl(
+            temp_dir.path().to_path_buf(),
+            None,
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap(),
+    );
+
+    for i in 0..10 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![i as u8; 16]))
+            .await
+            .unwrap();
+    }
+    sleep(Duration::from_millis(60)).await;
+    // Rewritten entries expire from their latest write
+    cache.set("chunk/0", Bytes::from("fresh")).await.unwrap();
+    sleep(Duration::from_millis(60)).await;
+
+    assert_eq!(cache.purge_expired().await.unwrap(), 9);
+    assert_eq!(cache.stats().entry_count, 1);
+    assert_eq!(cache.size(), 5);
+    assert_eq!(cache_files(temp_dir.path()).len(), 1);
+
+    // The sweeper removes entries nobody reads again
+    let sweeper = cache.spawn_expiry_sweeper(Duration::from_millis(20));
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(cache.stats().entry_count, 0);
+    assert!(cache_files(temp_dir.path()).is_empty());
+    sweeper.abort();
+}
+
+/// All `.cache` files below `dir`
+fn cache_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(cache_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "cache") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[tokio::test]
+async fn test_disk_cache_persists_across_restarts() {
+    let temp_dir = TempDir::new().unwrap();
+    let key = "temperature/c/0/1".to_string();
+    let value = Bytes::from("chunk_data");
+
+    {
+        let cache = DiskCache::new(temp_dir.path().to_path_buf(), Some(1024 * 1024)).unwrap();
+        cache.set(&key, value.clone()).await.unwrap();
+        cache.set("other", Bytes::from("x")).await.unwrap();
+        cache.persist_index().await.unwrap();
+    }
+
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), Some(1024 * 1024)).unwrap();
+    assert_eq!(cache.get(&key).await, Some(value.clone()));
+    assert_eq!(cache.stats().entry_count, 2);
+    assert_eq!(cache.size(), value.len() + 1);
+}
+
+#[tokio::test]
+async fn test_disk_cache_rescans_without_journal() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        journal: false,
+        ..Default::default()
+    };
+    let key = "temperature/c/0/1".to_string();
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        cache.set(&key, Bytes::from("chunk_data")).await.unwrap();
+    }
+
+    // A file the index never saw cannot be attributed to a key
+    std::fs::write(temp_dir.path().join("stray.cache"), b"junk").unwrap();
+
+    let cache = DiskCache::with_config(config).unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk_data")));
+    assert!(!temp_dir.path().join("stray.cache").exists());
+    assert!(!temp_dir.path().join(JOURNAL_FILE_NAME).exists());
+}
+
+#[tokio::test]
+async fn test_disk_cache_journal_recovers_after_crash() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(1024),
+        ..Default::default()
+    };
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        cache.persist_index().await.unwrap();
+        for i in 0..5 {
+            cache
+                .set(&format!("chunk/{}", i), Bytes::from(vec![i as u8; 10]))
+                .await
+                .unwrap();
+        }
+        cache.remove("chunk/1").await.unwrap();
+        cache
+            .set("chunk/2", Bytes::from("rewritten"))
+            .await
+            .unwrap();
+        // Simulate a crash: nothing but the journal records the writes
+        std::mem::forget(cache);
+    }
+
+    // A record torn by the crash is ignored
+    let journal_path = temp_dir.path().join(JOURNAL_FILE_NAME);
+    let mut journal = std::fs::read(&journal_path).unwrap();
+    journal.extend_from_slice(b"{\"op\":\"remove\",\"ke");
+    std::fs::write(&journal_path, journal).unwrap();
+
+    // Files the journal knows nothing about are left for GC, not scanned
+    std::fs::write(temp_dir.path().join("stray.cache"), b"junk").unwrap();
+
+    let cache = DiskCache::with_config(config).unwrap();
+    assert_eq!(cache.stats().entry_count, 4);
+    assert_eq!(cache.size(), 3 * 10 + "rewritten".len());
+    // Startup folds the journal into a new snapshot
+    assert_eq!(std::fs::metadata(&journal_path).unwrap().len(), 0);
+    assert!(cache.get("chunk/1").await.is_none());
+    assert_eq!(cache.get("chunk/2").await, Some(Bytes::from("rewritten")));
+    assert_eq!(cache.get("chunk/4").await, Some(Bytes::from(vec![4u8; 10])));
+}
+
+#[tokio::test]
+async fn test_disk_cache_restart_drops_missing_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let key = "gone".to_string();
+
+    {
+        let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+        cache.set(&key, Bytes::from("value")).await.unwrap();
+        // Dropping persists the index
+    }
+
+    for path in cache_files(temp_dir.path()) {
+        std::fs::remove_file(path).unwrap();
+    }
+
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_disk_cache_restart_keeps_ttl() {
+    let temp_dir = TempDir::new().unwrap();
+    let ttl = Some(Duration::from_millis(100));
+    let key = "key".to_string();
+
+    {
+        let cache = DiskCache::with_ttl(temp_dir.path().to_path_buf(), None, ttl).unwrap();
+        cache.set(&key, Bytes::from("value")).await.unwrap();
+    }
+
+    sleep(Duration::from_millis(150)).await;
+
+    // Creation time survives the restart, so the entry is still expired
+    let cache = DiskCache::with_ttl(temp_dir.path().to_path_buf(), None, ttl).unwrap();
+    assert!(cache.get(&key).await.is_none());
+}
+
+#[tokio::test]
+async fn test_disk_cache_ttl_jitter_staggers_expiry() {
+    let temp_dir = TempDir::new().unwrap();
+    // Entries expire between 200ms and 600ms after being written
+    let config = DiskCacheConfig::builder()
+        .cache_dir(temp_dir.path())
+        .ttl(Duration::from_millis(400))
+        .ttl_jitter(0.5)
+        .build()
+        .unwrap();
+    let cache = DiskCache::with_config(config).unwrap();
+    for i in 0..100 {
+        cache
+            .set(&format!("key{}", i), Bytes::from("v"))
+            .await
+            .unwrap();
+    }
+
+    sleep(Duration::from_millis(400)).await;
+    let purged = cache.purge_expired().await.unwrap();
+    assert!(purged > 0 && purged < 100, "purged {} of 100", purged);
+
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(cache.purge_expired().await.unwrap(), 100 - purged);
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
 #[tokio::test]
 async fn test_memory_cache_with_ttl() {
     let ttl = Duration::from_millis(100);
@@ -160,3 +453,1501 @@ async fn test_memory_cache_with_ttl() {
     // Value should be expired
     assert!(cache.get(&key).await.is_none());
 }
+
+#[tokio::test]
+async fn test_arc_memory_cache_basic_operations() {
+    let cache = ArcMemoryCache::new(1024);
+
+    let key = "test_key".to_string();
+    let value = Bytes::from("test_value");
+
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+
+    cache.set(&key, value.clone()).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(value.clone()));
+    assert_eq!(cache.size(), value.len());
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entry_count, 1);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+
+    cache.set(&key, value.clone()).await.unwrap();
+    cache.clear().await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_arc_memory_cache_scan_resistance() {
+    let cache = ArcMemoryCache::new(10 * entry_footprint("hot/0", 10)); // Ten 10-byte entries
+
+    // Build a hot working set that is accessed repeatedly
+    for i in 0..5 {
+        let key = format!("hot/{}", i);
+        cache.set(&key, Bytes::from(vec![0u8; 10])).await.unwrap();
+        assert!(cache.get(&key).await.is_some());
+    }
+
+    // One-touch scan larger than the cache
+    for i in 0..50 {
+        let key = format!("scan/{}", i);
+        cache.set(&key, Bytes::from(vec![1u8; 10])).await.unwrap();
+    }
+
+    // The scan only displaces other scan entries
+    for i in 0..5 {
+        assert!(cache.get(&format!("hot/{}", i)).await.is_some());
+    }
+    assert!(cache.stats().accounted_bytes <= 10 * entry_footprint("hot/0", 10));
+}
+
+#[tokio::test]
+async fn test_arc_memory_cache_ghost_adaptation() {
+    let capacity = 3 * entry_footprint("a", 10);
+    let cache = ArcMemoryCache::new(capacity);
+    assert_eq!(cache.recency_target(), 0);
+
+    // Fill and overflow the recency list so "a" becomes a ghost
+    for key in ["a", "b", "c", "d"] {
+        cache.set(key, Bytes::from(vec![0u8; 10])).await.unwrap();
+    }
+    assert!(cache.get("a").await.is_none());
+
+    // Re-inserting a recency ghost grows the recency target
+    cache.set("a", Bytes::from(vec![0u8; 10])).await.unwrap();
+    assert!(cache.recency_target() > 0);
+    assert!(cache.stats().accounted_bytes <= capacity);
+}
+
+#[tokio::test]
+async fn test_arc_memory_cache_with_ttl() {
+    let cache = ArcMemoryCache::with_ttl(1024, Some(Duration::from_millis(100)));
+
+    let key = "test_key".to_string();
+    cache.set(&key, Bytes::from("test_value")).await.unwrap();
+    assert!(cache.get(&key).await.is_some());
+
+    sleep(Duration::from_millis(150)).await;
+
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_arc_memory_cache_rejects_oversized_entry() {
+    let cache = ArcMemoryCache::new(entry_footprint("big", 10));
+    let result = cache.set("big", Bytes::from(vec![0u8; 11])).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_clock_memory_cache_basic_operations() {
+    let cache = ClockMemoryCache::new(1024);
+
+    let key = "test_key".to_string();
+    let value = Bytes::from("test_value");
+
+    assert!(cache.get(&key).await.is_none());
+
+    cache.set(&key, value.clone()).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(value.clone()));
+    assert_eq!(cache.size(), value.len());
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entry_count, 1);
+
+    // Overwrite replaces the value without double counting
+    cache.set(&key, Bytes::from("new")).await.unwrap();
+    assert_eq!(cache.size(), 3);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+
+    cache.set(&key, value).await.unwrap();
+    cache.clear().await.unwrap();
+    assert_eq!(cache.size(), 0);
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_clock_memory_cache_second_chance() {
+    let capacity = 3 * entry_footprint("a", 10); // Three 10-byte entries
+    let cache = ClockMemoryCache::new(capacity);
+
+    for key in ["a", "b", "c"] {
+        cache.set(key, Bytes::from(vec![0u8; 10])).await.unwrap();
+    }
+
+    // Referenced entries survive the next sweep
+    assert!(cache.get("a").await.is_some());
+    cache.set("d", Bytes::from(vec![0u8; 10])).await.unwrap();
+
+    assert!(cache.get("a").await.is_some());
+    assert!(cache.get("b").await.is_none());
+    assert!(cache.stats().accounted_bytes <= capacity);
+}
+
+#[tokio::test]
+async fn test_clock_memory_cache_with_ttl() {
+    let cache = ClockMemoryCache::with_ttl(1024, Some(Duration::from_millis(100)));
+
+    let key = "test_key".to_string();
+    cache.set(&key, Bytes::from("test_value")).await.unwrap();
+    assert!(cache.get(&key).await.is_some());
+
+    sleep(Duration::from_millis(150)).await;
+
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_memory_cache_purge_expired() {
+    let cache = LruMemoryCache::with_ttl(1024, Some(Duration::from_millis(50)));
+
+    cache.set("a", Bytes::from("1")).await.unwrap();
+    cache.set("b", Bytes::from("2")).await.unwrap();
+    assert_eq!(cache.purge_expired().await.unwrap(), 0);
+
+    sleep(Duration::from_millis(80)).await;
+
+    // Reads no longer sweep the whole cache; purge reclaims untouched entries
+    assert_eq!(cache.purge_expired().await.unwrap(), 2);
+    assert_eq!(cache.size(), 0);
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_memory_cache_expiry_sweeper() {
+    let cache = LruMemoryCache::with_ttl(1024, Some(Duration::from_millis(50)));
+    let sweeper = cache.spawn_expiry_sweeper(Duration::from_millis(20));
+
+    cache.set("a", Bytes::from("1")).await.unwrap();
+    assert_eq!(cache.size(), 1);
+
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(cache.size(), 0);
+
+    // Sweeper exits once the cache is dropped
+    drop(cache);
+    tokio::time::timeout(Duration::from_secs(1), sweeper)
+        .await
+        .expect("sweeper should stop after the cache is dropped")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_memory_cache_accounts_entry_overhead() {
+    let key = "k".to_string();
+    let footprint = entry_footprint(&key, 1);
+
+    // A budget that fits the value bytes but not the entry overhead
+    let cache = LruMemoryCache::new(footprint - 1);
+    assert!(cache.set(&key, Bytes::from("v")).await.is_err());
+
+    let cache = LruMemoryCache::new(10 * footprint);
+    cache.set(&key, Bytes::from("v")).await.unwrap();
+
+    let stats = cache.stats();
+    assert_eq!(stats.size_bytes, 1);
+    assert_eq!(stats.accounted_bytes, footprint);
+    assert_eq!(cache.size(), 1);
+
+    // Overwrites replace rather than add to the accounted size
+    cache.set(&key, Bytes::from("v")).await.unwrap();
+    assert_eq!(cache.stats().accounted_bytes, footprint);
+
+    cache.remove(&key).await.unwrap();
+    assert_eq!(cache.stats().accounted_bytes, 0);
+}
+
+#[tokio::test]
+async fn test_many_small_entries_respect_budget() {
+    let budget = 64 * 1024;
+    let cache = LruMemoryCache::new(budget);
+
+    for i in 0..10_000 {
+        cache
+            .set(&format!("array/{}", i), Bytes::from_static(b"x"))
+            .await
+            .unwrap();
+    }
+
+    let stats = cache.stats();
+    assert!(stats.accounted_bytes <= budget);
+    assert!(stats.entry_count < 10_000);
+}
+
+#[tokio::test]
+async fn test_lru_promotion_sampling() {
+    let capacity = 3 * entry_footprint("k0", 5);
+    let cache = LruMemoryCache::new(capacity).with_promotion_sampling(1000);
+
+    for key in ["k0", "k1", "k2"] {
+        cache.set(key, Bytes::from("value")).await.unwrap();
+    }
+
+    // First hit is sampled and promotes k1; the next one only peeks
+    assert!(cache.get("k1").await.is_some());
+    assert!(cache.get("k0").await.is_some());
+
+    // k0 kept its LRU position and is evicted first
+    cache.set("k3", Bytes::from("value")).await.unwrap();
+    assert!(cache.get("k0").await.is_none());
+    assert!(cache.get("k1").await.is_some());
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 3);
+    assert_eq!(stats.misses, 1);
+}
+
+#[tokio::test]
+async fn test_lru_promotion_sampling_respects_ttl() {
+    let cache = LruMemoryCache::with_ttl(1024, Some(Duration::from_millis(50)))
+        .with_promotion_sampling(1000);
+    let key = "key".to_string();
+
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+    assert!(cache.get(&key).await.is_some());
+
+    sleep(Duration::from_millis(80)).await;
+
+    // The unsampled read path still drops expired entries
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_memory_cache_ttl_jitter_staggers_expiry() {
+    // Entries expire between 200ms and 600ms after being written
+    let ttl = Some(Duration::from_millis(400));
+    let caches: [Arc<dyn Cache>; 3] = [
+        Arc::new(LruMemoryCache::with_ttl(1024 * 1024, ttl).with_ttl_jitter(0.5)),
+        Arc::new(ArcMemoryCache::with_ttl(1024 * 1024, ttl).with_ttl_jitter(0.5)),
+        Arc::new(ClockMemoryCache::with_ttl(1024 * 1024, ttl).with_ttl_jitter(0.5)),
+    ];
+    for cache in &caches {
+        for i in 0..100 {
+            cache
+                .set(&format!("key{}", i), Bytes::from("v"))
+                .await
+                .unwrap();
+        }
+    }
+
+    sleep(Duration::from_millis(400)).await;
+    for cache in &caches {
+        let purged = cache.purge_expired().await.unwrap();
+        assert!(purged > 0 && purged < 100, "purged {} of 100", purged);
+    }
+
+    sleep(Duration::from_millis(300)).await;
+    for cache in &caches {
+        cache.purge_expired().await.unwrap();
+        assert_eq!(cache.stats().entry_count, 0);
+    }
+}
+
+#[tokio::test]
+async fn test_sharded_memory_cache_basic_operations() {
+    let cache = ShardedMemoryCache::new(4, 4096, MemoryPolicy::Lru, None);
+    assert_eq!(cache.shard_count(), 4);
+    assert_eq!(cache.capacity(), Some(4096));
+
+    let key = "array/0.0".to_string();
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("value")));
+    assert!(cache.get("missing").await.is_none());
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entry_count, 1);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+}
+
+#[tokio::test]
+async fn test_sharded_memory_cache_cross_shard_fallback() {
+    let cache = Arc::new(ShardedMemoryCache::new(
+        64,
+        64 * 1024,
+        MemoryPolicy::Lru,
+        None,
+    ));
+    // A key homed on another shard than this thread's
+    let key = (0..)
+        .map(|i| format!("array/{}.1", i))
+        .find(|key| cache.home_shard(key) != cache.local_shard())
+        .unwrap();
+    let write_from_thread = |value: &'static str| {
+        let writer = cache.clone();
+        let key = key.clone();
+        std::thread::spawn(move || {
+            futures::executor::block_on(writer.set(&key, Bytes::from(value))).unwrap();
+        })
+        .join()
+        .unwrap();
+    };
+
+    // Written from another thread, it lands in its home shard all the same
+    write_from_thread("old");
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("old")));
+    assert_eq!(cache.cross_shard_hits(), 1);
+
+    // Later reads are served by the local copy
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("old")));
+    assert_eq!(cache.cross_shard_hits(), 1);
+
+    // A write from anywhere invalidates the local copy
+    write_from_thread("new");
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("new")));
+    assert_eq!(cache.stats().entry_count, 1);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.stats().removals.removed, 1);
+}
+
+#[test]
+fn test_sharded_memory_cache_concurrent_writers_agree() {
+    let cache = Arc::new(ShardedMemoryCache::new(
+        8,
+        64 * 1024,
+        MemoryPolicy::Lru,
+        None,
+    ));
+    let barrier = Arc::new(std::sync::Barrier::new(8));
+    let writers: Vec<_> = (0..8u8)
+        .map(|i| {
+            let cache = cache.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                futures::executor::block_on(cache.set("array/0.0", Bytes::from(vec![i; 16])))
+                    .unwrap();
+                barrier.wait();
+                futures::executor::block_on(cache.get("array/0.0")).unwrap()
+            })
+        })
+        .collect();
+    let seen: Vec<Bytes> = writers.into_iter().map(|w| w.join().unwrap()).collect();
+
+    // One entry, and every worker reads the same last write
+    assert_eq!(cache.stats().entry_count, 1);
+    assert!(seen.iter().all(|value| *value == seen[0]));
+}
+
+#[tokio::test]
+async fn test_disk_cache_fan_out_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    assert_eq!(cache.layout(), DiskLayout::FanOut);
+
+    let key = "temperature/c/1/2/3".to_string();
+    cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    assert_eq!(cache_files(temp_dir.path()).len(), 1);
+
+    // No entry files at the top level
+    let top_level_files: Vec<_> = std::fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "cache"))
+        .collect();
+    assert!(top_level_files.is_empty());
+}
+
+#[tokio::test]
+async fn test_disk_cache_migrates_flat_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let flat_config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        layout: DiskLayout::Flat,
+        ..Default::default()
+    };
+    let key = "array/0.0".to_string();
+
+    {
+        let cache = DiskCache::with_config(flat_config.clone()).unwrap();
+        cache.set(&key, Bytes::from("flat")).await.unwrap();
+    }
+    let flat_files = cache_files(temp_dir.path());
+    assert_eq!(flat_files.len(), 1);
+    assert_eq!(flat_files[0].parent(), Some(temp_dir.path()));
+
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        layout: DiskLayout::FanOut,
+        ..flat_config
+    })
+    .unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("flat")));
+    assert!(!flat_files[0].exists());
+    assert_eq!(cache_files(temp_dir.path()).len(), 1);
+    assert_eq!(cache.stats().entry_count, 1);
+}
+
+#[tokio::test]
+async fn test_disk_cache_hierarchy_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        layout: DiskLayout::Hierarchy,
+        ..Default::default()
+    };
+    let cache = DiskCache::with_config(config.clone()).unwrap();
+
+    let chunk_key = "temperature/c/1/2/3".to_string();
+    let metadata_key = "temperature/zarr.json".to_string();
+    cache.set(&chunk_key, Bytes::from("chunk")).await.unwrap();
+    cache
+        .set(&metadata_key, Bytes::from("{\"zarr_format\": 3}"))
+        .await
+        .unwrap();
+
+    // The directory mirrors the store, holding the raw values
+    assert_eq!(
+        std::fs::read(temp_dir.path().join("temperature/c/1/2/3")).unwrap(),
+        b"chunk"
+    );
+    assert_eq!(
+        std::fs::read(temp_dir.path().join("temperature/zarr.json")).unwrap(),
+        b"{\"zarr_format\": 3}"
+    );
+    assert_eq!(cache.get(&chunk_key).await, Some(Bytes::from("chunk")));
+
+    for key in [
+        "../escape",
+        "/absolute",
+        "a//b",
+        "a/./b",
+        "",
+        "index.json",
+        "x.tmp",
+    ] {
+        assert!(
+            cache.set(key, Bytes::from("x")).await.is_err(),
+            "{key:?} accepted"
+        );
+    }
+    assert!(!temp_dir.path().parent().unwrap().join("escape").exists());
+    assert_eq!(cache.stats().entry_count, 2);
+
+    // A truncated value is detected by its size
+    std::fs::write(temp_dir.path().join("temperature/c/1/2/3"), b"chu").unwrap();
+    assert!(cache.get(&chunk_key).await.is_none());
+    assert_eq!(cache.corrupted_entries(), 1);
+    drop(cache);
+
+    // Without a journal, files placed by other tools are adopted on restart
+    std::fs::create_dir_all(temp_dir.path().join("temperature/c/0/0")).unwrap();
+    std::fs::write(temp_dir.path().join("temperature/c/0/0/0"), b"seeded").unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        journal: false,
+        ..config
+    })
+    .unwrap();
+    assert_eq!(
+        cache.get("temperature/c/0/0/0").await,
+        Some(Bytes::from("seeded"))
+    );
+    assert_eq!(
+        cache.get(&metadata_key).await,
+        Some(Bytes::from("{\"zarr_format\": 3}"))
+    );
+}
+
+#[tokio::test]
+async fn test_disk_cache_migrates_to_and_from_hierarchy_layout() {
+    let temp_dir = TempDir::new().unwrap();
+    let fan_out_config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let key = "array/c/0/0".to_string();
+
+    {
+        let cache = DiskCache::with_config(fan_out_config.clone()).unwrap();
+        cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    }
+
+    {
+        let cache = DiskCache::with_config(DiskCacheConfig {
+            layout: DiskLayout::Hierarchy,
+            ..fan_out_config.clone()
+        })
+        .unwrap();
+        assert!(cache_files(temp_dir.path()).is_empty());
+        assert_eq!(
+            std::fs::read(temp_dir.path().join("array/c/0/0")).unwrap(),
+            b"chunk"
+        );
+        assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    }
+
+    let cache = DiskCache::with_config(fan_out_config).unwrap();
+    assert!(!temp_dir.path().join("array/c/0/0").exists());
+    assert_eq!(cache_files(temp_dir.path()).len(), 1);
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    assert_eq!(cache.size(), 5);
+}
+
+#[tokio::test]
+async fn test_disk_cache_keys_do_not_collide() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+
+    // Both keys mapped to the same file name before hashing
+    let slash_key = "a/b".to_string();
+    let underscore_key = "a_b".to_string();
+    cache.set(&slash_key, Bytes::from("slash")).await.unwrap();
+    cache
+        .set(&underscore_key, Bytes::from("underscore"))
+        .await
+        .unwrap();
+
+    assert_eq!(cache.get(&slash_key).await, Some(Bytes::from("slash")));
+    assert_eq!(
+        cache.get(&underscore_key).await,
+        Some(Bytes::from("underscore"))
+    );
+    assert_eq!(cache_files(temp_dir.path()).len(), 2);
+}
+
+#[tokio::test]
+async fn test_disk_cache_adopts_unindexed_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let key = "written/after/persist".to_string();
+
+    {
+        let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+        cache.persist_index().await.unwrap();
+        cache.set(&key, Bytes::from("value")).await.unwrap();
+        // Simulate a crash: the index on disk predates the write
+        std::mem::forget(cache);
+    }
+
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("value")));
+    assert_eq!(cache.size(), 5);
+}
+
+#[tokio::test]
+async fn test_disk_cache_detects_corruption() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    let key = "array/0.0".to_string();
+    cache
+        .set(&key, Bytes::from("pristine chunk"))
+        .await
+        .unwrap();
+
+    // Flip one bit of the value on disk
+    let path = cache_files(temp_dir.path()).remove(0);
+    let mut contents = std::fs::read(&path).unwrap();
+    let last = contents.len() - 1;
+    contents[last] ^= 0x01;
+    std::fs::write(&path, contents).unwrap();
+
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.corrupted_entries(), 1);
+    assert!(!path.exists());
+    assert_eq!(cache.size(), 0);
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_disk_cache_lists_and_verifies_entries() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    cache.set("b/0", Bytes::from("second")).await.unwrap();
+    cache.set("a/0", Bytes::from("first")).await.unwrap();
+    cache.get("a/0").await.unwrap();
+
+    let entries = cache.entries().await;
+    let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+    assert_eq!(keys, ["a/0", "b/0"]);
+    assert_eq!(entries[0].size, 5);
+    assert_eq!(entries[0].access_count, 2);
+    assert_eq!(entries[1].access_count, 1);
+    assert!(entries
+        .iter()
+        .all(|entry| !entry.expired && entry.file_path.exists()));
+
+    // Flip one bit of the value of b/0 on disk
+    let path = entries[1].file_path.clone();
+    let mut contents = std::fs::read(&path).unwrap();
+    let last = contents.len() - 1;
+    contents[last] ^= 0x01;
+    std::fs::write(&path, contents).unwrap();
+
+    let report = cache.verify().await;
+    assert_eq!(report.checked, 2);
+    assert_eq!(report.corrupt, ["b/0"]);
+    assert!(report.unreadable.is_empty());
+    assert_eq!(cache.corrupted_entries(), 1);
+    assert!(!path.exists());
+    assert_eq!(cache.entries().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_disk_cache_streamed_writes() {
+    for layout in [DiskLayout::FanOut, DiskLayout::Hierarchy] {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DiskCacheConfig {
+            cache_dir: temp_dir.path().to_path_buf(),
+            layout,
+            ..Default::default()
+        };
+        let cache = Arc::new(DiskCache::with_config(config.clone()).unwrap());
+
+        // Written in pieces, with a size hint above the final size
+        let key = "array/c/0".to_string();
+        cache
+            .set_streaming(&key, 1 << 20, |writer| {
+                for piece in 0..100u8 {
+                    writer.write_all(&[piece; 1000])?;
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+        let expected: Vec<u8> = (0..100u8).flat_map(|piece| [piece; 1000]).collect();
+        assert_eq!(cache.get(&key).await, Some(Bytes::from(expected.clone())));
+        assert_eq!(cache.size(), 100_000);
+
+        // A failed write leaves the previous value in place
+        let failed = cache
+            .set_streaming(&key, 10, |writer| {
+                writer.write_all(b"partial")?;
+                Err(CacheError::Compression("encoder failed".to_string()))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(cache.get(&key).await, Some(Bytes::from(expected.clone())));
+        drop(cache);
+
+        // Streamed entries are checked and reloaded like any other
+        let reopened = DiskCache::with_config(config).unwrap();
+        assert_eq!(reopened.get(&key).await, Some(Bytes::from(expected)));
+        assert_eq!(reopened.corrupted_entries(), 0);
+    }
+}
+
+#[tokio::test]
+async fn test_disk_cache_atomic_writes() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        sync_writes: true,
+        journal: false,
+        ..Default::default()
+    };
+    let key = "array/0.0".to_string();
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        cache.set(&key, Bytes::from("durable")).await.unwrap();
+        cache.set(&key, Bytes::from("replaced")).await.unwrap();
+        assert_eq!(cache.get(&key).await, Some(Bytes::from("replaced")));
+    }
+
+    // A write interrupted by a crash only ever leaves a temp file behind
+    let stale = temp_dir.path().join(".entry-interrupted.tmp");
+    std::fs::write(&stale, b"partial").unwrap();
+
+    let cache = DiskCache::with_config(config).unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("replaced")));
+    assert!(!stale.exists());
+    assert_eq!(cache_files(temp_dir.path()).len(), 1);
+}
+
+#[tokio::test]
+async fn test_disk_cache_mmap_reads() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        mmap_threshold_bytes: Some(1024),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let large_key = "array/0.0".to_string();
+    let large = Bytes::from(vec![7u8; 64 * 1024]);
+    let small_key = "array/.zarray".to_string();
+    cache.set(&large_key, large.clone()).await.unwrap();
+    cache
+        .set(&small_key, Bytes::from("{\"shape\": [1]}"))
+        .await
+        .unwrap();
+
+    let mapped = cache.get(&large_key).await.unwrap();
+    assert_eq!(mapped, large);
+    assert_eq!(
+        cache.get(&small_key).await,
+        Some(Bytes::from("{\"shape\": [1]}"))
+    );
+
+    // Overwriting replaces the file; the old mapping keeps its contents
+    #[cfg(unix)]
+    {
+        cache
+            .set(&large_key, Bytes::from(vec![9u8; 64 * 1024]))
+            .await
+            .unwrap();
+        assert_eq!(mapped, large);
+        assert_eq!(
+            cache.get(&large_key).await,
+            Some(Bytes::from(vec![9u8; 64 * 1024]))
+        );
+    }
+    drop(mapped);
+
+    // Mapped reads are verified too
+    let path = cache_files(temp_dir.path())
+        .into_iter()
+        .max_by_key(|path| std::fs::metadata(path).unwrap().len())
+        .unwrap();
+    let mut contents = std::fs::read(&path).unwrap();
+    let middle = contents.len() / 2;
+    contents[middle] ^= 0x01;
+    std::fs::write(&path, contents).unwrap();
+    assert!(cache.get(&large_key).await.is_none());
+    assert_eq!(cache.corrupted_entries(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_disk_cache_concurrent_reads_and_overwrites() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap());
+    let keys: Vec<String> = (0..8).map(|i| format!("array/c/{}", i)).collect();
+    for key in &keys {
+        cache.set(key, Bytes::from(vec![0u8; 1024])).await.unwrap();
+    }
+
+    let writer = {
+        let cache = cache.clone();
+        let keys = keys.clone();
+        tokio::spawn(async move {
+            for round in 1..=20u8 {
+                for key in &keys {
+                    cache
+                        .set(key, Bytes::from(vec![round; 1024]))
+                        .await
+                        .unwrap();
+                }
+            }
+        })
+    };
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let cache = cache.clone();
+            let keys = keys.clone();
+            tokio::spawn(async move {
+                for _ in 0..50 {
+                    for key in &keys {
+                        // Every read sees one complete value
+                        let value = cache.get(key).await.unwrap();
+                        assert_eq!(value.len(), 1024);
+                        assert!(value.iter().all(|&byte| byte == value[0]));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    writer.await.unwrap();
+    for reader in readers {
+        reader.await.unwrap();
+    }
+    assert_eq!(cache.corrupted_entries(), 0);
+    assert_eq!(cache.stats().hits, 4 * 50 * 8);
+    assert_eq!(cache.stats().entry_count, 8);
+}
+
+#[tokio::test]
+async fn test_disk_cache_direct_io() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        direct_io: true,
+        sync_writes: true,
+        ..Default::default()
+    };
+    // Off Linux, or where the filesystem rejects O_DIRECT, this falls back
+    // to buffered I/O
+    let cache = DiskCache::with_config(config.clone()).unwrap();
+
+    // Sizes straddling the alignment boundaries
+    let values: Vec<(String, Bytes)> = [0usize, 1, 4095, 4096, 4097, 300_001]
+        .into_iter()
+        .map(|len| {
+            let value: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            (format!("array/c/{len}"), Bytes::from(value))
+        })
+        .collect();
+    for (key, value) in &values {
+        cache.set(key, value.clone()).await.unwrap();
+    }
+    for (key, value) in &values {
+        assert_eq!(cache.get(key).await.as_ref(), Some(value));
+    }
+    let batched = cache
+        .get_many(
+            &values
+                .iter()
+                .map(|(key, _)| key.clone())
+                .collect::<Vec<_>>(),
+        )
+        .await;
+    assert!(batched
+        .iter()
+        .zip(&values)
+        .all(|(got, (_, value))| got.as_ref() == Some(value)));
+
+    // Files hold exactly the entry, without alignment padding
+    let sizes: u64 = cache_files(temp_dir.path())
+        .iter()
+        .map(|path| std::fs::metadata(path).unwrap().len())
+        .sum();
+    let expected: usize = values
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum();
+    assert!(sizes >= expected as u64 && sizes < (expected + 4096) as u64);
+    drop(cache);
+
+    let cache = DiskCache::with_config(config).unwrap();
+    for (key, value) in &values {
+        assert_eq!(cache.get(key).await.as_ref(), Some(value));
+    }
+
+    // Direct reads are verified too
+    let path = cache_files(temp_dir.path())
+        .into_iter()
+        .max_by_key(|path| std::fs::metadata(path).unwrap().len())
+        .unwrap();
+    let mut contents = std::fs::read(&path).unwrap();
+    let middle = contents.len() / 2;
+    contents[middle] ^= 0x01;
+    std::fs::write(&path, contents).unwrap();
+    assert!(cache.get("array/c/300001").await.is_none());
+    assert_eq!(cache.corrupted_entries(), 1);
+}
+
+async fn check_disk_cache_batches(io_backend: DiskIoBackend) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        sync_writes: true,
+        io_backend,
+        ..Default::default()
+    };
+
+    let entries: Vec<(String, Bytes)> = (0..100)
+        .map(|i| (format!("chunk/{}", i), Bytes::from(vec![i as u8; 1000 + i])))
+        .collect();
+    let keys: Vec<String> = entries.iter().map(|(key, _)| key.clone()).collect();
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        let results = cache.set_many(entries.clone()).await;
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(cache.stats().entry_count, 100);
+
+        let mut lookup = keys.clone();
+        lookup.push("missing".to_string());
+        let found = cache.get_many(&lookup).await;
+        assert_eq!(found.len(), 101);
+        for ((_, value), found) in entries.iter().zip(&found) {
+            assert_eq!(found.as_ref(), Some(value));
+        }
+        assert!(found[100].is_none());
+    }
+
+    let cache = DiskCache::with_config(config).unwrap();
+    assert_eq!(cache.get(&keys[42]).await, Some(entries[42].1.clone()));
+}
+
+#[tokio::test]
+async fn test_disk_cache_batches_std() {
+    check_disk_cache_batches(DiskIoBackend::Std).await;
+}
+
+#[tokio::test]
+async fn test_disk_cache_batches_io_uring() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        io_backend: DiskIoBackend::IoUring { queue_depth: 16 },
+        ..Default::default()
+    })
+    .unwrap();
+    // Falls back to std I/O where io_uring is unavailable
+    if cfg!(not(all(target_os = "linux", feature = "io-uring"))) {
+        assert_eq!(cache.io_backend(), DiskIoBackend::Std);
+    }
+    drop(cache);
+
+    check_disk_cache_batches(DiskIoBackend::IoUring { queue_depth: 16 }).await;
+}
+
+#[tokio::test]
+async fn test_disk_cache_garbage_collection() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    for key in ["kept", "lost"] {
+        cache.set(key, Bytes::from("value")).await.unwrap();
+    }
+    let files_before = cache_files(temp_dir.path());
+
+    // Debris of crashes: an unindexed entry file, a temp file and an entry
+    // whose file vanished
+    let orphan = temp_dir.path().join("ab").join("orphan.cache");
+    std::fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+    std::fs::write(&orphan, b"0123456789").unwrap();
+    let temp_file = temp_dir.path().join(".entry-crashed.tmp");
+    std::fs::write(&temp_file, b"partial").unwrap();
+    let lost_file = files_before
+        .iter()
+        .find(|path| {
+            std::fs::read(path)
+                .unwrap()
+                .windows(4)
+                .any(|w| w == b"lost")
+        })
+        .unwrap();
+    std::fs::remove_file(lost_file).unwrap();
+
+    // Recent files may belong to in-flight writes
+    let report = cache
+        .collect_garbage(Duration::from_secs(3600))
+        .await
+        .unwrap();
+    assert_eq!(report.orphaned_files, 0);
+    assert_eq!(report.stale_temp_files, 0);
+    assert!(orphan.exists());
+    assert_eq!(report.dangling_entries, 1);
+
+    let report = cache.collect_garbage(Duration::ZERO).await.unwrap();
+    assert_eq!(
+        report,
+        DiskGcReport {
+            orphaned_files: 1,
+            orphaned_bytes: 10,
+            stale_temp_files: 1,
+            dangling_entries: 0,
+        }
+    );
+    assert!(!orphan.exists());
+    assert!(!temp_file.exists());
+    assert_eq!(cache.size(), 5);
+    assert_eq!(cache.get("kept").await, Some(Bytes::from("value")));
+}
+
+#[tokio::test]
+async fn test_disk_cache_persists_stats_and_access_order() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(30),
+        journal: false,
+        ..Default::default()
+    };
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        for key in ["a", "b", "c"] {
+            cache.set(key, Bytes::from(vec![0u8; 10])).await.unwrap();
+            sleep(Duration::from_millis(5)).await;
+        }
+        // "a" becomes the most recently used entry
+        assert!(cache.get("a").await.is_some());
+        assert!(cache.get("missing").await.is_none());
+    }
+
+    let cache = DiskCache::with_config(config).unwrap();
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+
+    // Eviction follows the access order from before the restart
+    cache.set("d", Bytes::from(vec![0u8; 10])).await.unwrap();
+    assert!(cache.get("a").await.is_some());
+    assert!(cache.get("b").await.is_none());
+}
+
+async fn disk_cache_survivors_after_scan(eviction_policy: DiskEvictionPolicy) -> usize {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(100),
+        eviction_policy,
+        ..Default::default()
+    })
+    .unwrap();
+
+    // A working set read repeatedly
+    for i in 0..5 {
+        let key = format!("hot/{}", i);
+        cache.set(&key, Bytes::from(vec![0u8; 10])).await.unwrap();
+        cache.get(&key).await.unwrap();
+    }
+    // A one-off scan larger than the cache
+    for i in 0..20 {
+        cache
+            .set(&format!("scan/{}", i), Bytes::from(vec![0u8; 10]))
+            .await
+            .unwrap();
+    }
+
+    let mut survivors = 0;
+    for i in 0..5 {
+        if cache.get(&format!("hot/{}", i)).await.is_some() {
+            survivors += 1;
+        }
+    }
+    survivors
+}
+
+#[tokio::test]
+async fn test_disk_cache_lru_k_resists_scans() {
+    assert_eq!(
+        disk_cache_survivors_after_scan(DiskEvictionPolicy::Lru).await,
+        0
+    );
+    assert_eq!(
+        disk_cache_survivors_after_scan(DiskEvictionPolicy::LruK { k: 2 }).await,
+        5
+    );
+    assert_eq!(
+        disk_cache_survivors_after_scan(DiskEvictionPolicy::Lfu).await,
+        5
+    );
+}
+
+#[tokio::test]
+async fn test_disk_cache_lfu_survives_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(20),
+        eviction_policy: DiskEvictionPolicy::Lfu,
+        ..Default::default()
+    };
+
+    {
+        let cache = DiskCache::with_config(config.clone()).unwrap();
+        for key in ["popular", "rare"] {
+            cache.set(key, Bytes::from(vec![0u8; 10])).await.unwrap();
+        }
+        for _ in 0..3 {
+            cache.get("popular").await.unwrap();
+        }
+        // Read last, so plain LRU would keep it
+        cache.get("rare").await.unwrap();
+    }
+
+    let cache = DiskCache::with_config(config).unwrap();
+    cache.set("new", Bytes::from(vec![0u8; 10])).await.unwrap();
+    assert!(cache.get("popular").await.is_some());
+    assert!(cache.get("rare").await.is_none());
+}
+
+#[tokio::test]
+async fn test_disk_cache_eviction_order_after_many_accesses() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(30),
+        ..Default::default()
+    })
+    .unwrap();
+
+    for key in ["a", "b", "c"] {
+        cache.set(key, Bytes::from(vec![0u8; 10])).await.unwrap();
+    }
+    // Enough re-rankings of one entry to rebuild the eviction queue
+    for _ in 0..3000 {
+        cache.get("b").await.unwrap();
+    }
+    cache.get("a").await.unwrap();
+    cache.get("c").await.unwrap();
+
+    cache.set("d", Bytes::from(vec![0u8; 10])).await.unwrap();
+    assert!(cache.get("b").await.is_none());
+    for key in ["a", "c", "d"] {
+        assert!(cache.get(key).await.is_some());
+    }
+
+    cache.set("e", Bytes::from(vec![0u8; 10])).await.unwrap();
+    assert!(cache.get("a").await.is_none());
+    assert_eq!(cache.stats().entry_count, 3);
+}
+
+#[tokio::test]
+async fn test_disk_cache_batched_eviction() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(100),
+        eviction_batch_fraction: 0.3,
+        ..Default::default()
+    })
+    .unwrap();
+
+    for i in 0..10 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![0u8; 10]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.size(), 100);
+
+    // One write frees 30% of the cache at once
+    cache
+        .set("chunk/10", Bytes::from(vec![0u8; 10]))
+        .await
+        .unwrap();
+    assert_eq!(cache.size(), 70);
+    assert_eq!(cache.stats().entry_count, 7);
+
+    // The following writes fit without evicting
+    for i in 11..14 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![0u8; 10]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.stats().entry_count, 10);
+    assert!(cache.get("chunk/4").await.is_some());
+    assert!(cache.get("chunk/3").await.is_none());
+
+    assert!(matches!(
+        cache.set("huge", Bytes::from(vec![0u8; 101])).await,
+        Err(zarrs_cache::CacheError::EntryTooLarge { .. })
+    ));
+    assert_eq!(cache.stats().entry_count, 10);
+}
+
+#[tokio::test]
+async fn test_disk_cache_contains_key() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        ttl: Some(Duration::from_millis(50)),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let key = "array/c/0".to_string();
+    assert!(!cache.contains_key(&key).await);
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+    assert!(cache.contains_key(&key).await);
+
+    // Membership checks are not cache accesses
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (0, 0));
+
+    sleep(Duration::from_millis(80)).await;
+    assert!(!cache.contains_key(&key).await);
+}
+
+#[tokio::test]
+async fn test_removal_causes_are_counted() {
+    let memory = LruMemoryCache::with_ttl(
+        2 * entry_footprint("key0", 100),
+        Some(Duration::from_millis(30)),
+    );
+    for i in 0..3 {
+        memory
+            .set(&format!("key{}", i), Bytes::from(vec![0u8; 100]))
+            .await
+            .unwrap();
+    }
+    memory.remove("key2").await.unwrap();
+    // Removing a missing key is not counted
+    memory.remove("key2").await.unwrap();
+    memory
+        .set("key3", Bytes::from(vec![0u8; 100]))
+        .await
+        .unwrap();
+    memory
+        .remove_with_cause("key3", RemovalCause::Demoted)
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(memory.get("key1").await, None);
+
+    assert_eq!(
+        memory.stats().removals,
+        RemovalCounts {
+            evicted: 1,
+            expired: 1,
+            removed: 1,
+            demoted: 1,
+        }
+    );
+
+    let temp_dir = TempDir::new().unwrap();
+    let disk = DiskCache::with_config(DiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(200),
+        ttl: Some(Duration::from_millis(30)),
+        ..Default::default()
+    })
+    .unwrap();
+    for i in 0..3 {
+        disk.set(&format!("key{}", i), Bytes::from(vec![0u8; 100]))
+            .await
+            .unwrap();
+    }
+    disk.remove("key2").await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(disk.purge_expired().await.unwrap(), 1);
+
+    let removals = disk.stats().removals;
+    assert_eq!(removals.evicted, 1);
+    assert_eq!(removals.expired, 1);
+    assert_eq!(removals.removed, 1);
+    assert_eq!(removals.total(), 3);
+}
+
+#[tokio::test]
+async fn test_disk_cache_config_builder_validation() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = DiskCacheConfig::builder()
+        .cache_dir(temp_dir.path())
+        .max_size_bytes(4096)
+        .eviction_policy(DiskEvictionPolicy::Lfu)
+        .build()
+        .unwrap();
+    assert_eq!(config.max_size_bytes, Some(4096));
+    DiskCache::with_config(config).unwrap();
+
+    let invalid = [
+        DiskCacheConfig::builder().max_size_bytes(0),
+        DiskCacheConfig::builder().eviction_batch_fraction(1.5),
+        DiskCacheConfig::builder().ttl_jitter(-0.1),
+        DiskCacheConfig::builder().eviction_policy(DiskEvictionPolicy::LruK { k: 0 }),
+    ];
+    for builder in invalid {
+        assert!(matches!(builder.build(), Err(CacheError::InvalidConfig(_))));
+    }
+
+    // Constructors reject what the builder would
+    let unusable = DiskCache::new(temp_dir.path().to_path_buf(), Some(0));
+    assert!(matches!(unusable, Err(CacheError::InvalidConfig(_))));
+}
+
+#[tokio::test]
+async fn test_memory_caches_reconfigure_at_runtime() {
+    for policy in [MemoryPolicy::Lru, MemoryPolicy::Arc, MemoryPolicy::Clock] {
+        let cache = policy.build(64 * 1024, None);
+        for i in 0..10 {
+            cache
+                .set(&format!("k{}", i), Bytes::from(vec![0u8; 1024]))
+                .await
+                .unwrap();
+        }
+        assert_eq!(cache.stats().entry_count, 10);
+
+        // Shrinking evicts down to the new budget
+        let budget = 3 * entry_footprint("k0", 1024);
+        cache.resize(budget).await.unwrap();
+        assert_eq!(cache.capacity(), Some(budget));
+        assert!(cache.stats().entry_count <= 3, "{:?}", policy);
+
+        // A new TTL applies to entries already cached
+        cache
+            .set_ttl(Some(Duration::from_millis(30)))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(60)).await;
+        assert_eq!(cache.get("k9").await, None, "{:?}", policy);
+        cache.set_ttl(None).await.unwrap();
+        cache.set("kept", Bytes::from("v")).await.unwrap();
+        sleep(Duration::from_millis(60)).await;
+        assert!(cache.get("kept").await.is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_disk_cache_reconfigures_at_runtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    for i in 0..10 {
+        cache
+            .set(&format!("k{}", i), Bytes::from(vec![0u8; 100]))
+            .await
+            .unwrap();
+    }
+    assert_eq!(cache.capacity(), None);
+
+    cache.resize(500).await.unwrap();
+    assert_eq!(cache.capacity(), Some(500));
+    assert!(cache.size() <= 500);
+    assert_eq!(cache.stats().entry_count, 5);
+    assert!(matches!(
+        cache.resize(0).await,
+        Err(CacheError::InvalidConfig(_))
+    ));
+
+    // Entries cached before the TTL was set expire too
+    cache
+        .set_ttl(Some(Duration::from_millis(30)))
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(60)).await;
+    assert_eq!(cache.purge_expired().await.unwrap(), 5);
+    assert_eq!(cache.size(), 0);
+
+    cache.set_ttl(None).await.unwrap();
+    cache.set("kept", Bytes::from("v")).await.unwrap();
+    sleep(Duration::from_millis(60)).await;
+    assert!(cache.get("kept").await.is_some());
+}
+
+#[tokio::test]
+async fn test_oversized_entries_are_rejected_as_too_large() {
+    let value = Bytes::from(vec![0u8; 2048]);
+    let key = "huge".to_string();
+    let lru = LruMemoryCache::new(1024);
+    let arc = ArcMemoryCache::new(1024);
+    let clock = ClockMemoryCache::new(1024);
+    let caches: [&dyn Cache; 3] = [&lru, &arc, &clock];
+    for cache in caches {
+        let error = cache.set(&key, value.clone()).await.unwrap_err();
+        let CacheError::EntryTooLarge { size, max } = error else {
+            panic!("unexpected error {:?}", error);
+        };
+        assert_eq!(size, entry_footprint(&key, 2048));
+        assert_eq!(max, 1024);
+        assert!(!error.is_retryable() && !error.is_transient());
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_overwrite_keeps_the_old_value() {
+    let key = "chunk".to_string();
+    let lru = LruMemoryCache::new(1024);
+    let arc = ArcMemoryCache::new(1024);
+    let clock = ClockMemoryCache::new(1024);
+    let caches: [&dyn Cache; 3] = [&lru, &arc, &clock];
+    for cache in caches {
+        cache.set(&key, Bytes::from("small")).await.unwrap();
+        let error = cache
+            .set(&key, Bytes::from(vec![0u8; 2048]))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, CacheError::EntryTooLarge { .. }));
+
+        assert_eq!(cache.get(&key).await, Some(Bytes::from("small")));
+        let stats = cache.stats();
+        assert_eq!(stats.entry_count, 1);
+        assert_eq!(stats.size_bytes, 5);
+    }
+}
+
+#[tokio::test]
+async fn test_get_stale_serves_expired_entries_without_evicting_them() {
+    let ttl = Some(Duration::from_millis(20));
+    let temp_dir = TempDir::new().unwrap();
+    let lru = LruMemoryCache::with_ttl(1024, ttl);
+    let arc = ArcMemoryCache::with_ttl(1024, ttl);
+    let clock = ClockMemoryCache::with_ttl(1024, ttl);
+    let sharded = ShardedMemoryCache::new(4, 4096, MemoryPolicy::Lru, ttl);
+    let admission = AdmissionCache::new(LruMemoryCache::with_ttl(1024, ttl), AlwaysAdmit);
+    let disk = DiskCache::with_ttl(temp_dir.path().join("disk"), None, ttl).unwrap();
+    let packed = PackedDiskCache::with_config(PackedDiskCacheConfig {
+        cache_dir: temp_dir.path().join("packed"),
+        ttl,
+        ..Default::default()
+    })
+    .unwrap();
+    let caches: [&dyn Cache; 7] = [&lru, &arc, &clock, &sharded, &admission, &disk, &packed];
+
+    let key = "temperature/c/0/0".to_string();
+    let value = Bytes::from("chunk");
+    for cache in caches {
+        cache.set(&key, value.clone()).await.unwrap();
+    }
+    sleep(Duration::from_millis(30)).await;
+
+    for cache in caches {
+        assert_eq!(cache.get_stale(&key).await, Some(value.clone()));
+        assert_eq!(cache.get_stale(&key).await, Some(value.clone()));
+        assert!(cache.get(&key).await.is_none());
+        assert!(cache.get_stale(&key).await.is_none());
+    }
+}
+
+#[test]
+fn test_cache_error_retryability() {
+    use std::io::{Error, ErrorKind};
+
+    let retryable = [
+        CacheError::Timeout("remote read".to_string()),
+        CacheError::backend("connection dropped"),
+        CacheError::Io(Error::from(ErrorKind::Interrupted)),
+        CacheError::Io(Error::from(ErrorKind::TimedOut)),
+    ];
+    for error in &retryable {
+        assert!(error.is_retryable(), "{:?}", error);
+        assert!(error.is_transient(), "{:?}", error);
+    }
+
+    assert!(!CacheError::CacheFull.is_retryable());
+    assert!(CacheError::CacheFull.is_transient());
+    let unavailable = CacheError::Unavailable("circuit breaker open".to_string());
+    assert!(!unavailable.is_retryable() && unavailable.is_transient());
+
+    let permanent = [
+        CacheError::Io(Error::from(ErrorKind::NotFound)),
+        CacheError::InvalidKey("".to_string()),
+        CacheError::Corruption("checksum mismatch".to_string()),
+        CacheError::EntryTooLarge { size: 2, max: 1 },
+        CacheError::Unsupported("set_ttl".to_string()),
+        CacheError::InvalidConfig("memory_size must be positive".to_string()),
+    ];
+    for error in &permanent {
+        assert!(!error.is_retryable(), "{:?}", error);
+        assert!(!error.is_transient(), "{:?}", error);
+    }
+
+    let error = CacheError::backend(Error::other("disk unplugged"));
+    assert_eq!(error.to_string(), "Storage backend error: disk unplugged");
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[tokio::test]
+async fn test_remove_prefix() {
+    let lru = LruMemoryCache::new(4096);
+    let arc = ArcMemoryCache::new(4096);
+    let clock = ClockMemoryCache::new(4096);
+    let sharded = ShardedMemoryCache::new(4, 4096, MemoryPolicy::Lru, None);
+    let temp_dir = TempDir::new().unwrap();
+    let disk = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    let caches: [&dyn Cache; 5] = [&lru, &arc, &clock, &sharded, &disk];
+    for cache in caches {
+        for key in ["temp/0.0", "temp/0.1", "precip/0.0"] {
+            cache.set(key, Bytes::from("chunk")).await.unwrap();
+        }
+
+        assert_eq!(cache.remove_prefix("temp/").await.unwrap(), 2);
+        assert!(cache.get("temp/0.0").await.is_none());
+        assert!(cache.get("temp/0.1").await.is_none());
+        assert!(cache.get("precip/0.0").await.is_some());
+        assert_eq!(cache.stats().entry_count, 1);
+        assert_eq!(cache.stats().removals.removed, 2);
+        assert_eq!(cache.remove_prefix("temp/").await.unwrap(), 0);
+    }
+}
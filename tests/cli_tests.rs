@@ -0,0 +1,69 @@
+#![cfg(feature = "cli")]
+
+use bytes::Bytes;
+use std::process::Command;
+use tempfile::TempDir;
+use zarrs_cache::{Cache, DiskCache};
+
+fn zarrs_cache(args: &[&std::ffi::OsStr]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_zarrs-cache"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_cli_lists_invalidates_and_warms() {
+    let cache_dir = TempDir::new().unwrap();
+    {
+        let cache = DiskCache::new(cache_dir.path().to_path_buf(), None).unwrap();
+        for key in ["temperature/c/0/0", "temperature/c/0/1", "pressure/c/0/0"] {
+            cache.set(key, Bytes::from("chunk")).await.unwrap();
+        }
+    }
+    let dir = cache_dir.path().as_os_str();
+
+    let (ok, listing) = zarrs_cache(&[dir, "ls".as_ref()]);
+    assert!(ok);
+    assert!(listing.contains("pressure/c/0/0"));
+    assert!(listing.contains("3 entries, 15 B"));
+
+    let (ok, output) = zarrs_cache(&[dir, "invalidate".as_ref(), "temperature/".as_ref()]);
+    assert!(ok);
+    assert_eq!(output.trim(), "Removed 2 entries");
+
+    let (ok, output) = zarrs_cache(&[dir, "verify".as_ref()]);
+    assert!(ok);
+    assert_eq!(output.trim(), "Checked 1 entries, discarded 0");
+
+    // Warm two chunks of a local copy of the store through a pattern
+    let source = TempDir::new().unwrap();
+    let chunks = source.path().join("temperature/c/1");
+    std::fs::create_dir_all(&chunks).unwrap();
+    std::fs::write(chunks.join("0"), "warm").unwrap();
+    std::fs::write(chunks.join("1"), "warm").unwrap();
+    let manifest = source.path().join("manifest.txt");
+    std::fs::write(&manifest, "# next job\ntemperature/c/1/*\n").unwrap();
+
+    let (ok, output) = zarrs_cache(&[
+        dir,
+        "warm".as_ref(),
+        "--manifest".as_ref(),
+        manifest.as_os_str(),
+        "--source".as_ref(),
+        source.path().as_os_str(),
+    ]);
+    assert!(ok);
+    assert_eq!(output.trim(), "Warmed 2 of 2 keys");
+
+    let cache = DiskCache::new(cache_dir.path().to_path_buf(), None).unwrap();
+    assert_eq!(
+        cache.get("temperature/c/1/1").await,
+        Some(Bytes::from("warm"))
+    );
+    assert!(cache.get("temperature/c/0/0").await.is_none());
+}
@@ -0,0 +1,255 @@
+use bytes::Bytes;
+use std::sync::Arc;
+use std::time::Duration;
+use zarrs_cache::{
+    Cache, CacheError, ClusterCache, ClusterConfig, HashRing, LruMemoryCache, ShardedCache,
+    ShardedCacheConfig,
+};
+
+/// A peer that never answers in time
+struct SlowPeer;
+
+#[async_trait::async_trait]
+impl Cache for SlowPeer {
+    async fn get(&self, _key: &str) -> Option<Bytes> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        None
+    }
+
+    async fn set(&self, _key: &str, _value: Bytes) -> Result<(), CacheError> {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(())
+    }
+
+    async fn remove(&self, _key: &str) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn stats(&self) -> zarrs_cache::CacheStats {
+        Default::default()
+    }
+}
+
+/// A shard whose writes always fail
+struct FullShard;
+
+#[async_trait::async_trait]
+impl Cache for FullShard {
+    async fn get(&self, _key: &str) -> Option<Bytes> {
+        None
+    }
+
+    async fn set(&self, _key: &str, _value: Bytes) -> Result<(), CacheError> {
+        Err(CacheError::CacheFull)
+    }
+
+    async fn remove(&self, _key: &str) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    fn size(&self) -> usize {
+        0
+    }
+
+    fn stats(&self) -> zarrs_cache::CacheStats {
+        Default::default()
+    }
+}
+
+fn keys() -> Vec<String> {
+    (0..1000)
+        .map(|i| format!("temperature/c/{}/0", i))
+        .collect()
+}
+
+#[test]
+fn test_hash_ring_spreads_keys_and_moves_few_on_changes() {
+    let ring = HashRing::new(["a", "b", "c"], 128);
+    assert_eq!(ring.nodes(), ["a", "b", "c"]);
+
+    let owners: Vec<&str> = keys().iter().map(|key| ring.owner(key).unwrap()).collect();
+    for node in ["a", "b", "c"] {
+        let share = owners.iter().filter(|owner| **owner == node).count();
+        assert!(share > 200, "node {} owns only {} keys", node, share);
+    }
+
+    // Rings built in another order agree, and a new node only takes keys
+    let grown = HashRing::new(["d", "c", "b", "a"], 128);
+    for (key, owner) in keys().iter().zip(&owners) {
+        let new_owner = grown.owner(key).unwrap();
+        assert!(new_owner == *owner || new_owner == "d");
+    }
+    assert!(HashRing::new(Vec::<String>::new(), 128)
+        .owner("key")
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_cluster_cache_routes_keys_to_their_owner() {
+    let share_a: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1 << 20));
+    let share_b: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1 << 20));
+    let node_a = ClusterCache::new("a", share_a.clone()).with_peer("b", share_b.clone());
+    let node_b = ClusterCache::new("b", share_b.clone()).with_peer("a", share_a.clone());
+
+    for key in keys() {
+        node_a.set(&key, Bytes::from(key.clone())).await.unwrap();
+    }
+    assert_eq!(
+        share_a.stats().entry_count + share_b.stats().entry_count,
+        1000
+    );
+    assert!(share_b.stats().entry_count > 300);
+
+    // Both nodes agree on owners, so either sees every key
+    for key in keys() {
+        assert_eq!(node_a.owner(&key), node_b.owner(&key));
+        assert_eq!(node_b.get(&key).await, Some(Bytes::from(key.clone())));
+    }
+    assert_eq!(node_b.stats().hits, 1000);
+
+    assert_eq!(node_b.remove_prefix("temperature/c/1").await.unwrap(), 111);
+    node_a.clear().await.unwrap();
+    assert_eq!(share_a.stats().entry_count, 0);
+    assert!(share_b.stats().entry_count > 0);
+
+    // Without peers, this node owns every key
+    assert!(node_a.remove_peer("b"));
+    assert_eq!(node_a.owner("temperature/c/0/0"), "a");
+}
+
+#[tokio::test]
+async fn test_cluster_cache_skips_unresponsive_peers() {
+    let config = ClusterConfig::builder()
+        .request_timeout("50ms")
+        .retry_after("1h")
+        .build()
+        .unwrap();
+    let local: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1 << 20));
+    let cluster = ClusterCache::with_config("a", local, config)
+        .unwrap()
+        .with_peer("b", Arc::new(SlowPeer));
+    let key = keys()
+        .into_iter()
+        .find(|key| cluster.owner(key) == "b")
+        .unwrap();
+
+    assert!(cluster.get(&key).await.is_none());
+    assert_eq!(cluster.peer_failures(), 1);
+
+    // The peer is skipped at once until it may be retried
+    let started = std::time::Instant::now();
+    let error = cluster.set(&key, Bytes::from("chunk")).await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_millis(50));
+    assert!(error.is_retryable());
+    assert_eq!(error.key(), Some(key.as_str()));
+    assert_eq!(cluster.peer_failures(), 1);
+
+    assert!(matches!(
+        ClusterConfig::builder().virtual_nodes(0).build(),
+        Err(CacheError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_hash_ring_places_replicas_on_distinct_nodes() {
+    let ring = HashRing::new(["a", "b", "c"], 128);
+    for key in keys() {
+        let owners = ring.owners(&key, 2);
+        assert_eq!(owners.len(), 2);
+        assert_eq!(owners[0], ring.owner(&key).unwrap());
+        assert_ne!(owners[0], owners[1]);
+    }
+    assert_eq!(ring.owners("temperature/c/0/0", 5).len(), 3);
+    assert!(HashRing::default()
+        .owners("temperature/c/0/0", 2)
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_sharded_cache_replicates_keys() {
+    let shards: Vec<Arc<LruMemoryCache>> = (0..3)
+        .map(|_| Arc::new(LruMemoryCache::new(1024 * 1024)))
+        .collect();
+    let config = ShardedCacheConfig::builder()
+        .replication(2)
+        .build()
+        .unwrap();
+    let cache = ShardedCache::with_config(config).unwrap();
+    for (i, shard) in shards.iter().enumerate() {
+        cache.add_shard(format!("shard-{}", i), shard.clone());
+    }
+    assert_eq!(cache.shard_ids(), ["shard-0", "shard-1", "shard-2"]);
+
+    let keys = &keys()[..100];
+    for key in keys {
+        cache.set(key, Bytes::from("chunk")).await.unwrap();
+    }
+    assert_eq!(cache.stats().entry_count, 200);
+    for key in keys {
+        let owners = cache.owners(key);
+        assert_eq!(owners.len(), 2);
+        for (i, shard) in shards.iter().enumerate() {
+            let owner = owners.contains(&format!("shard-{}", i));
+            assert_eq!(shard.get(key).await.is_some(), owner);
+        }
+    }
+
+    // Every key survives losing a shard
+    assert!(cache.remove_shard("shard-0"));
+    for key in keys {
+        assert!(cache.get(key).await.is_some());
+    }
+    assert_eq!(cache.stats().hits, 100);
+
+    cache.remove(&keys[0]).await.unwrap();
+    assert!(cache.get(&keys[0]).await.is_none());
+    let stored = cache.stats().entry_count;
+    assert_eq!(cache.remove_prefix("temperature/").await.unwrap(), stored);
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_sharded_cache_writes_succeed_on_any_replica() {
+    let healthy = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let cache = ShardedCache::with_config(
+        ShardedCacheConfig::builder()
+            .replication(2)
+            .build()
+            .unwrap(),
+    )
+    .unwrap()
+    .with_shard("healthy", healthy.clone())
+    .with_shard("full", Arc::new(FullShard));
+
+    let key = "temperature/c/0/0".to_string();
+    cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    assert!(healthy.get(&key).await.is_some());
+
+    // Without a replica to take it, the write fails
+    let unreplicated = ShardedCache::new().with_shard("full", Arc::new(FullShard));
+    assert!(matches!(
+        unreplicated.set(&key, Bytes::from("chunk")).await,
+        Err(CacheError::CacheFull)
+    ));
+    assert!(matches!(
+        ShardedCache::new().set(&key, Bytes::from("chunk")).await,
+        Err(CacheError::Unavailable(_))
+    ));
+    assert!(matches!(
+        ShardedCacheConfig::builder().replication(0).build(),
+        Err(CacheError::InvalidConfig(_))
+    ));
+}
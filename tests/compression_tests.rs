@@ -0,0 +1,381 @@
+use bytes::Bytes;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "snappy")]
+use zarrs_cache::SnappyCompression;
+use zarrs_cache::{
+    Cache, CacheError, CompressedCache, Compression, CompressionAlgorithm, DeflateCompression,
+    LruMemoryCache, Shuffle, ShuffleCompression,
+};
+#[cfg(feature = "zstd")]
+use zarrs_cache::{ZstdCompression, ZstdDictionaryCompression};
+
+#[test]
+fn test_deflate_round_trip() {
+    let compression = DeflateCompression::default();
+    let data = b"temperature temperature temperature temperature".repeat(64);
+
+    let compressed = compression.compress(&data).unwrap();
+    assert!(compressed.len() < data.len());
+    assert_eq!(compression.decompress(&compressed).unwrap(), data);
+    assert!(compression.decompress(b"not deflate data").is_err());
+}
+
+#[test]
+fn test_compression_algorithm_validation() {
+    assert!(CompressionAlgorithm::default().validate().is_ok());
+    assert!(CompressionAlgorithm::Deflate { level: 10 }
+        .validate()
+        .is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_round_trip() {
+    let data: Vec<u8> = (0..4096)
+        .flat_map(|i| ((i % 64) as f32 * 0.5).to_le_bytes())
+        .collect();
+    for level in [-7, 3, 19] {
+        let compression = ZstdCompression::new(level);
+        let compressed = compression.compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(compression.decompress(&compressed).unwrap(), data);
+    }
+    assert_eq!(ZstdCompression::default().level(), 3);
+    assert_eq!(ZstdCompression::new(99).level(), 22);
+    assert!(ZstdCompression::default()
+        .decompress(b"not zstd data")
+        .is_err());
+
+    assert!(CompressionAlgorithm::Zstd { level: 22 }.validate().is_ok());
+    assert!(CompressionAlgorithm::Zstd { level: 23 }.validate().is_err());
+    let built = CompressionAlgorithm::Zstd { level: 1 }.build();
+    assert_eq!(
+        built.decompress(&built.compress(&data).unwrap()).unwrap(),
+        data
+    );
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn test_snappy_round_trip() {
+    let compression = SnappyCompression;
+    let data = b"temperature temperature temperature temperature".repeat(64);
+
+    let compressed = compression.compress(&data).unwrap();
+    assert!(compressed.len() < data.len());
+    assert_eq!(compression.decompress(&compressed).unwrap(), data);
+    assert!(compression.decompress(b"\xff\xff\xff\xff").is_err());
+
+    assert!(CompressionAlgorithm::Snappy.validate().is_ok());
+    let built = CompressionAlgorithm::Snappy.build();
+    assert_eq!(
+        built.decompress(&built.compress(&data).unwrap()).unwrap(),
+        data
+    );
+}
+
+#[tokio::test]
+async fn test_compressed_cache() {
+    let cache = CompressedCache::new(LruMemoryCache::new(1024 * 1024), DeflateCompression::new(9));
+    let key = "array/c/0/0".to_string();
+    let value = Bytes::from(vec![0u8; 100_000]);
+
+    cache.set(&key, value.clone()).await.unwrap();
+    assert!(cache.size() < value.len() / 10);
+    assert_eq!(cache.get(&key).await, Some(value));
+
+    // Values the codec cannot read are dropped rather than returned
+    cache.inner().set(&key, Bytes::from("raw")).await.unwrap();
+    assert_eq!(cache.get(&key).await, None);
+    assert_eq!(cache.inner().get(&key).await, None);
+}
+
+#[tokio::test]
+async fn test_adaptive_compression_skips_incompressible_values() {
+    let cache = CompressedCache::new(LruMemoryCache::new(1024 * 1024), DeflateCompression::new(6))
+        .with_min_ratio(1.1);
+    // Already-compressed chunks look like noise
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut noise = |len: usize| -> Bytes {
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    };
+
+    let compressible = Bytes::from(vec![0u8; 100_000]);
+    let small_noise = noise(1000);
+    let large_noise = noise(200_000);
+    for (key, value) in [
+        ("zeros", &compressible),
+        ("small", &small_noise),
+        ("large", &large_noise),
+    ] {
+        cache.set(key, value.clone()).await.unwrap();
+        assert_eq!(cache.get(key).await.as_ref(), Some(value));
+    }
+    assert_eq!(cache.uncompressed_writes(), 2);
+    let mut stored_lens = Vec::new();
+    for key in ["zeros", "small", "large"] {
+        stored_lens.push(cache.inner().get(key).await.unwrap().len());
+    }
+    assert!(stored_lens[0] < 1000);
+    assert_eq!(stored_lens[1..], [1011, 200_011]);
+
+    // Entries without a header are dropped
+    let plain = DeflateCompression::new(6).compress(&compressible).unwrap();
+    cache
+        .inner()
+        .set("zeros", Bytes::from(plain))
+        .await
+        .unwrap();
+    assert_eq!(cache.get("zeros").await, None);
+}
+
+#[tokio::test]
+async fn test_compressed_entries_survive_codec_changes() {
+    let inner = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let writer =
+        CompressedCache::new(inner.clone(), DeflateCompression::new(9)).with_min_ratio(1.1);
+    let compressible = Bytes::from(vec![7u8; 10_000]);
+    let incompressible = Bytes::from((0..=255u8).collect::<Vec<_>>());
+    writer.set("packed", compressible.clone()).await.unwrap();
+    writer.set("raw", incompressible.clone()).await.unwrap();
+    assert_eq!(writer.uncompressed_writes(), 1);
+
+    // Entries name their codec, so caches configured differently read them
+    let reader = CompressedCache::new(inner.clone(), DeflateCompression::new(1));
+    assert_eq!(reader.get("packed").await, Some(compressible.clone()));
+    assert_eq!(reader.get("raw").await, Some(incompressible.clone()));
+    #[cfg(feature = "zstd")]
+    {
+        let reader = CompressedCache::new(inner.clone(), ZstdCompression::default());
+        assert_eq!(reader.get("packed").await, Some(compressible.clone()));
+    }
+
+    // A header whose length disagrees with the payload drops the entry
+    let mut stored = inner.get("packed").await.unwrap().to_vec();
+    stored[3] ^= 1;
+    inner.set("packed", Bytes::from(stored)).await.unwrap();
+    assert_eq!(reader.get("packed").await, None);
+    assert_eq!(inner.get("packed").await, None);
+}
+
+#[cfg(feature = "zstd")]
+#[tokio::test]
+async fn test_zstd_dictionary_training() {
+    // Chunks sharing most of their content, as neighbouring chunks of an
+    // array often do, but each too small to compress well alone
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let template: Vec<u8> = (0..2000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    let chunk = |i: usize| -> Bytes {
+        let offset = (i * 7) % 1000;
+        let mut chunk = template[offset..offset + 800].to_vec();
+        chunk[..8].copy_from_slice(&(i as u64).to_le_bytes());
+        Bytes::from(chunk)
+    };
+    let samples: Vec<Bytes> = (0..300).map(chunk).collect();
+
+    let plain = ZstdCompression::default();
+    let trained = ZstdDictionaryCompression::train(&samples, 4096, 3).unwrap();
+    assert!(!trained.dictionary().is_empty());
+    let unseen = chunk(1000);
+    let compressed = trained.compress(&unseen).unwrap();
+    assert!(compressed.len() * 2 < plain.compress(&unseen).unwrap().len());
+    assert_eq!(trained.decompress(&compressed).unwrap(), unseen);
+    assert!(ZstdDictionaryCompression::train(&Vec::<Bytes>::new(), 4096, 3).is_err());
+
+    let inner = Arc::new(LruMemoryCache::new(4 * 1024 * 1024));
+    let cache = CompressedCache::new(inner.clone(), plain);
+    let keys: Vec<String> = (0..300).map(|i| format!("chunk/{}", i)).collect();
+    for (key, sample) in keys.iter().zip(&samples) {
+        cache.set(key, sample.clone()).await.unwrap();
+    }
+    let plain_len = inner.get(&keys[0]).await.unwrap().len();
+
+    let dictionary = cache.train_zstd_dictionary(&keys, 4096).await.unwrap();
+    cache.set(&keys[0], samples[0].clone()).await.unwrap();
+    assert!(inner.get(&keys[0]).await.unwrap().len() * 2 < plain_len);
+    // Entries written before training remain readable
+    assert_eq!(cache.get(&keys[1]).await, Some(samples[1].clone()));
+    assert_eq!(cache.get(&keys[0]).await, Some(samples[0].clone()));
+
+    // The returned dictionary reads entries written with it after a restart
+    let restarted =
+        CompressedCache::new(inner.clone(), ZstdDictionaryCompression::new(dictionary, 3));
+    assert_eq!(restarted.get(&keys[0]).await, Some(samples[0].clone()));
+}
+
+/// Deflate recording the threads it runs on
+struct ThreadRecordingCompression(Arc<std::sync::Mutex<Vec<std::thread::ThreadId>>>);
+
+impl Compression for ThreadRecordingCompression {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        self.0.lock().unwrap().push(std::thread::current().id());
+        DeflateCompression::default().compress(data)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, CacheError> {
+        self.0.lock().unwrap().push(std::thread::current().id());
+        DeflateCompression::default().decompress(data)
+    }
+}
+
+#[tokio::test]
+async fn test_large_values_are_compressed_off_the_executor() {
+    let threads = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let cache = CompressedCache::new(
+        LruMemoryCache::new(16 * 1024 * 1024),
+        ThreadRecordingCompression(threads.clone()),
+    )
+    .with_offload_bytes(64 * 1024);
+    let executor = std::thread::current().id();
+
+    let small = Bytes::from(vec![1u8; 1024]);
+    cache.set("small", small.clone()).await.unwrap();
+    assert_eq!(cache.get("small").await, Some(small));
+    assert_eq!(*threads.lock().unwrap(), [executor, executor]);
+
+    // Entries are offloaded by their original size, however well they compress
+    threads.lock().unwrap().clear();
+    let large = Bytes::from(vec![2u8; 1024 * 1024]);
+    cache.set("large", large.clone()).await.unwrap();
+    assert_eq!(cache.get("large").await, Some(large));
+    let threads = threads.lock().unwrap();
+    assert_eq!(threads.len(), 2);
+    assert!(threads.iter().all(|thread| *thread != executor));
+}
+
+#[tokio::test]
+async fn test_compression_stats() {
+    let cache = CompressedCache::new(LruMemoryCache::new(1024 * 1024), DeflateCompression::new(6))
+        .with_min_ratio(1.1);
+    assert!(cache.stats().compression.is_empty());
+
+    let zeros = Bytes::from(vec![0u8; 10_000]);
+    let counting = Bytes::from((0..=255u8).collect::<Vec<_>>());
+    cache.set("zeros", zeros.clone()).await.unwrap();
+    cache.set("counting", counting).await.unwrap();
+    for _ in 0..3 {
+        assert_eq!(cache.get("zeros").await, Some(zeros.clone()));
+    }
+
+    let compression = cache.stats().compression;
+    let deflate = compression.algorithms["deflate"];
+    assert_eq!(deflate.values, 1);
+    assert_eq!(deflate.logical_bytes, 10_000);
+    assert_eq!(
+        deflate.stored_bytes as usize,
+        cache.inner().get("zeros").await.unwrap().len()
+    );
+    assert!(deflate.ratio() > 10.0);
+    assert!(deflate.compression_time > Duration::ZERO);
+    assert_eq!(deflate.decompressions, 3);
+
+    // Values stored as is count the time spent finding they do not compress
+    let none = compression.algorithms["none"];
+    assert_eq!(
+        (none.values, none.logical_bytes, none.stored_bytes),
+        (1, 256, 267)
+    );
+    assert!(none.compression_time > Duration::ZERO);
+    assert_eq!(none.decompressions, 0);
+
+    let total = compression.total();
+    assert_eq!(total.values, 2);
+    assert_eq!(total.logical_bytes, 10_256);
+}
+
+#[test]
+fn test_shuffle_round_trip() {
+    for shuffle in [Shuffle::Byte, Shuffle::Bit] {
+        for element_size in [1, 2, 4, 8] {
+            for len in [0, 1, 7, 33, 64, 1000, 4099] {
+                let data: Vec<u8> = (0..len).map(|i| (i * 31 % 251) as u8).collect();
+                let codec =
+                    ShuffleCompression::new(DeflateCompression::new(1), shuffle, element_size);
+                let compressed = codec.compress(&data).unwrap();
+                assert_eq!(
+                    codec.decompress(&compressed).unwrap(),
+                    data,
+                    "{:?} shuffle of {} bytes by {}",
+                    shuffle,
+                    len,
+                    element_size
+                );
+            }
+        }
+    }
+    assert_eq!(
+        ShuffleCompression::new(DeflateCompression::default(), Shuffle::Bit, 0).element_size(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_shuffle_improves_numeric_compression() {
+    // A smooth float32 field, as typical of gridded scientific data
+    let data: Vec<u8> = (0..16_384)
+        .flat_map(|i| (280.0f32 + (i as f32 * 0.01).sin() * 15.0).to_le_bytes())
+        .collect();
+    let plain = DeflateCompression::new(6).compress(&data).unwrap().len();
+    for shuffle in [Shuffle::Byte, Shuffle::Bit] {
+        let codec = ShuffleCompression::new(DeflateCompression::new(6), shuffle, 4);
+        assert!(
+            codec.compress(&data).unwrap().len() < plain * 3 / 4,
+            "{:?} shuffle",
+            shuffle
+        );
+    }
+
+    let cache = CompressedCache::new(
+        LruMemoryCache::new(1024 * 1024),
+        ShuffleCompression::new(DeflateCompression::new(6), Shuffle::Bit, 4),
+    );
+    let value = Bytes::from(data);
+    cache.set("temperature/c/0", value.clone()).await.unwrap();
+    assert_eq!(cache.get("temperature/c/0").await, Some(value));
+    assert!(cache.stats().compression.algorithms["custom"].ratio() > 1.0);
+}
+
+#[test]
+fn test_streamed_compression_matches_buffered() {
+    let data: Vec<u8> = (0..100_000)
+        .flat_map(|i| ((i % 97) as u16).to_le_bytes())
+        .collect();
+    #[cfg(feature = "zstd")]
+    let samples: Vec<&[u8]> = data.chunks(1000).collect();
+    let codecs: Vec<Box<dyn Compression>> = vec![
+        Box::new(DeflateCompression::new(6)),
+        Box::new(ShuffleCompression::new(
+            DeflateCompression::new(6),
+            Shuffle::Byte,
+            2,
+        )),
+        #[cfg(feature = "zstd")]
+        Box::new(ZstdCompression::new(3)),
+        #[cfg(feature = "zstd")]
+        Box::new(ZstdDictionaryCompression::train(&samples, 4096, 3).unwrap()),
+        #[cfg(feature = "snappy")]
+        Box::new(SnappyCompression),
+    ];
+
+    for codec in codecs {
+        let mut streamed = Vec::new();
+        codec.compress_to(&data, &mut streamed).unwrap();
+        assert!(streamed.len() < data.len());
+        assert_eq!(codec.decompress(&streamed).unwrap(), data);
+    }
+}
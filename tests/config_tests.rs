@@ -1,6 +1,11 @@
 use std::path::PathBuf;
 use std::time::Duration;
-use zarrs_cache::{CacheConfig, HybridCacheConfig, MetricsConfig, PrefetchConfig};
+use tempfile::TempDir;
+use zarrs_cache::units::{parse_duration, parse_size};
+use zarrs_cache::{
+    CacheConfig, CacheError, CircuitBreakerConfig, DiskLayout, HybridCacheConfig, MemoryPolicy,
+    MetricsConfig, PrefetchConfig, RateLimitConfig, RetryConfig, ThresholdTuning,
+};
 
 #[test]
 fn test_cache_config_default() {
@@ -11,6 +16,7 @@ fn test_cache_config_default() {
     assert_eq!(config.max_disk_size, None);
     assert_eq!(config.ttl, None);
     assert_eq!(config.prefetch_config, None);
+    assert_eq!(config.listing_ttl, Some(Duration::from_secs(30)));
 }
 
 #[test]
@@ -45,6 +51,7 @@ fn test_metrics_config_default() {
     assert_eq!(config.snapshot_interval, Duration::from_secs(60));
     assert!(config.track_access_patterns);
     assert!(config.track_efficiency);
+    assert_eq!(config.max_tracked_keys, 1000);
 }
 
 #[test]
@@ -58,6 +65,7 @@ fn test_cache_config_custom_values() {
             neighbor_chunks: 5,
             max_queue_size: 20,
         }),
+        listing_ttl: None,
     };
 
     assert_eq!(config.max_memory_size, 256 * 1024 * 1024);
@@ -81,6 +89,7 @@ fn test_hybrid_cache_config_custom_values() {
         promotion_threshold: 0.5,
         demotion_threshold: Duration::from_secs(600), // 10 minutes
         maintenance_interval: Duration::from_secs(120), // 2 minutes
+        ..Default::default()
     };
 
     assert_eq!(config.memory_size, 128 * 1024 * 1024);
@@ -115,6 +124,7 @@ fn test_metrics_config_custom_values() {
         snapshot_interval: Duration::from_secs(30),
         track_access_patterns: false,
         track_efficiency: false,
+        max_tracked_keys: 50,
     };
 
     assert_eq!(config.max_history_size, 2000);
@@ -135,6 +145,7 @@ fn test_config_serialization_compatibility() {
             neighbor_chunks: 3,
             max_queue_size: 15,
         }),
+        listing_ttl: Some(Duration::from_secs(5)),
     };
 
     // Serialize to JSON
@@ -158,6 +169,7 @@ fn test_config_serialization_compatibility() {
     );
     assert_eq!(deserialized_config.ttl, original_config.ttl);
     assert_eq!(deserialized_config.ttl, original_config.ttl);
+    assert_eq!(deserialized_config.listing_ttl, original_config.listing_ttl);
 
     let orig_prefetch = original_config.prefetch_config.unwrap();
     let deser_prefetch = deserialized_config.prefetch_config.unwrap();
@@ -167,3 +179,341 @@ fn test_config_serialization_compatibility() {
     );
     assert_eq!(deser_prefetch.max_queue_size, orig_prefetch.max_queue_size);
 }
+
+#[test]
+fn test_hybrid_cache_config_serialization() {
+    let original = HybridCacheConfig::builder()
+        .memory_size(32 * 1024 * 1024)
+        .disk_dir("/tmp/hybrid")
+        .memory_ttl(Duration::from_secs(300))
+        .memory_policy(MemoryPolicy::Arc)
+        .threshold_tuning(ThresholdTuning::default())
+        .build()
+        .unwrap();
+
+    let json = serde_json::to_string(&original).unwrap();
+    let deserialized: HybridCacheConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, original);
+
+    // Settings left out take their defaults
+    let partial: HybridCacheConfig = serde_json::from_str(
+        r#"{"memory_size": 1048576, "disk_dir": "/tmp/partial", "disk_layout": "flat"}"#,
+    )
+    .unwrap();
+    assert_eq!(partial.memory_size, 1024 * 1024);
+    assert_eq!(partial.disk_dir, PathBuf::from("/tmp/partial"));
+    assert_eq!(partial.disk_layout, DiskLayout::Flat);
+    assert_eq!(partial.promotion_threshold, 0.1);
+    assert_eq!(partial.access_tracker_capacity, 100_000);
+}
+
+#[test]
+fn test_hybrid_cache_config_builder_validation() {
+    let config = HybridCacheConfig::builder()
+        .promotion_threshold(0.5)
+        .memory_max_entry_bytes(1024)
+        .build()
+        .unwrap();
+    assert_eq!(config.promotion_threshold, 0.5);
+    assert_eq!(config.memory_max_entry_bytes, Some(1024));
+    assert_eq!(config.maintenance_interval, Duration::from_secs(60));
+
+    let invalid = [
+        HybridCacheConfig::builder().memory_size(0),
+        HybridCacheConfig::builder().promotion_threshold(f64::NAN),
+        HybridCacheConfig::builder().ttl_jitter(2.0),
+        HybridCacheConfig::builder()
+            .memory_only_max_bytes(4096)
+            .memory_max_entry_bytes(1024),
+        HybridCacheConfig::builder().threshold_tuning(ThresholdTuning {
+            adjustment_factor: 0.5,
+            ..Default::default()
+        }),
+        HybridCacheConfig::builder().threshold_tuning(ThresholdTuning {
+            hit_rate_window: Duration::from_secs(3600),
+            ..Default::default()
+        }),
+    ];
+    for builder in invalid {
+        assert!(matches!(builder.build(), Err(CacheError::InvalidConfig(_))));
+    }
+}
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_config_environment_overrides() {
+    let config = CacheConfig {
+        prefetch_config: Some(PrefetchConfig::default()),
+        ..Default::default()
+    }
+    .override_from(vars(&[
+        ("ZARRS_CACHE_MAX_MEMORY_SIZE", "512MB"),
+        ("ZARRS_CACHE_MAX_DISK_SIZE", "1.5GiB"),
+        ("ZARRS_CACHE_TTL", "90"),
+        ("ZARRS_CACHE_DISK_CACHE_DIR", "/tmp/chunks"),
+        ("ZARRS_CACHE_LISTING_TTL", "5s"),
+        ("ZARRS_CACHE_PREFETCH_NEIGHBOR_CHUNKS", "4"),
+        ("ZARRS_CACHE_UNKNOWN", "ignored"),
+        ("HOME", "/root"),
+    ]))
+    .unwrap();
+    assert_eq!(config.max_memory_size, 512_000_000);
+    assert_eq!(config.max_disk_size, Some(3 << 29));
+    assert_eq!(config.ttl, Some(Duration::from_secs(90)));
+    assert_eq!(config.disk_cache_dir, Some(PathBuf::from("/tmp/chunks")));
+    assert_eq!(config.listing_ttl, Some(Duration::from_secs(5)));
+    assert_eq!(config.prefetch_config.unwrap().neighbor_chunks, 4);
+
+    let hybrid = HybridCacheConfig::default()
+        .override_from(vars(&[
+            ("ZARRS_CACHE_MEMORY_SIZE", "64KiB"),
+            ("ZARRS_CACHE_GHOST_LIST_CAPACITY", "128"),
+            ("ZARRS_CACHE_MEMORY_TTL", "0.5"),
+        ]))
+        .unwrap();
+    assert_eq!(hybrid.memory_size, 64 * 1024);
+    assert_eq!(hybrid.ghost_list_capacity, Some(128));
+    assert_eq!(hybrid.memory_ttl, Some(Duration::from_millis(500)));
+
+    let metrics = MetricsConfig::default()
+        .override_from(vars(&[("ZARRS_CACHE_METRICS_TRACK_EFFICIENCY", "false")]))
+        .unwrap();
+    assert!(!metrics.track_efficiency);
+
+    let rate_limit = RateLimitConfig::default()
+        .override_from(vars(&[
+            ("ZARRS_CACHE_RATE_LIMIT_BYTES_PER_SECOND", "20MB"),
+            ("ZARRS_CACHE_RATE_LIMIT_BURST", "250ms"),
+        ]))
+        .unwrap();
+    assert_eq!(rate_limit.bytes_per_second, Some(20_000_000));
+    assert_eq!(rate_limit.burst, Duration::from_millis(250));
+    assert_eq!(rate_limit.requests_per_second, None);
+
+    let retry = RetryConfig::default()
+        .override_from(vars(&[
+            ("ZARRS_CACHE_RETRY_MAX_ATTEMPTS", "5"),
+            ("ZARRS_CACHE_RETRY_INITIAL_BACKOFF", "50ms"),
+        ]))
+        .unwrap();
+    assert_eq!(retry.max_attempts, 5);
+    assert_eq!(retry.initial_backoff, Duration::from_millis(50));
+
+    let breaker = CircuitBreakerConfig::default()
+        .override_from(vars(&[(
+            "ZARRS_CACHE_CIRCUIT_BREAKER_SERVE_STALE",
+            "false",
+        )]))
+        .unwrap();
+    assert!(!breaker.serve_stale);
+
+    let invalid =
+        CacheConfig::default().override_from(vars(&[("ZARRS_CACHE_MAX_MEMORY_SIZE", "lots")]));
+    assert!(matches!(invalid, Err(CacheError::InvalidConfig(_))));
+}
+
+#[test]
+fn test_config_from_file() {
+    let dir = TempDir::new().unwrap();
+
+    let json = dir.path().join("cache.json");
+    std::fs::write(
+        &json,
+        r#"{"max_memory_size": 1024, "ttl": {"secs": 5, "nanos": 0}}"#,
+    )
+    .unwrap();
+    let config = CacheConfig::from_file(&json).unwrap();
+    assert_eq!(config.max_memory_size, 1024);
+    assert_eq!(config.ttl, Some(Duration::from_secs(5)));
+    assert_eq!(config.prefetch_config, None);
+
+    let unknown = dir.path().join("cache.ini");
+    std::fs::write(&unknown, "").unwrap();
+    assert!(matches!(
+        CacheConfig::from_file(&unknown),
+        Err(CacheError::Unsupported(_))
+    ));
+    assert!(matches!(
+        CacheConfig::from_file(dir.path().join("missing.json")),
+        Err(CacheError::Io(_))
+    ));
+
+    let malformed = dir.path().join("metrics.json");
+    std::fs::write(&malformed, "{").unwrap();
+    assert!(matches!(
+        MetricsConfig::from_file(&malformed),
+        Err(CacheError::InvalidConfig(_))
+    ));
+
+    #[cfg(feature = "toml")]
+    {
+        let path = dir.path().join("cache.toml");
+        std::fs::write(
+            &path,
+            "memory_size = \"2KiB\"\npromotion_threshold = 0.25\nmemory_ttl = \"5m\"\n",
+        )
+        .unwrap();
+        let config = HybridCacheConfig::from_file(&path).unwrap();
+        assert_eq!(config.memory_size, 2048);
+        assert_eq!(config.promotion_threshold, 0.25);
+        assert_eq!(config.memory_ttl, Some(Duration::from_secs(300)));
+
+        std::fs::write(&path, "memory_size = 0\n").unwrap();
+        assert!(matches!(
+            HybridCacheConfig::from_file(&path),
+            Err(CacheError::InvalidConfig(_))
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    {
+        let path = dir.path().join("prefetch.yml");
+        std::fs::write(&path, "neighbor_chunks: 8\n").unwrap();
+        let config = PrefetchConfig::from_file(&path).unwrap();
+        assert_eq!(config.neighbor_chunks, 8);
+        assert_eq!(
+            config.max_queue_size,
+            PrefetchConfig::default().max_queue_size
+        );
+    }
+}
+
+#[test]
+fn test_config_builders_validate() {
+    let config = CacheConfig::builder()
+        .max_memory_size(1024)
+        .max_disk_size(4096)
+        .prefetch_config(
+            PrefetchConfig::builder()
+                .neighbor_chunks(4)
+                .build()
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+    assert_eq!(config.max_memory_size, 1024);
+    assert_eq!(config.max_disk_size, Some(4096));
+    assert_eq!(config.prefetch_config.unwrap().neighbor_chunks, 4);
+
+    let metrics = MetricsConfig::builder()
+        .max_tracked_keys(10)
+        .track_efficiency(false)
+        .build()
+        .unwrap();
+    assert_eq!(metrics.max_tracked_keys, 10);
+    assert!(!metrics.track_efficiency);
+
+    let invalid = [
+        CacheConfig::builder().max_memory_size(0).build().err(),
+        CacheConfig::builder()
+            .listing_ttl(Duration::ZERO)
+            .build()
+            .err(),
+        CacheConfig::builder()
+            .max_memory_size(4096)
+            .max_disk_size(1024)
+            .build()
+            .err(),
+        CacheConfig::builder()
+            .prefetch_config(PrefetchConfig {
+                max_queue_size: 0,
+                ..Default::default()
+            })
+            .build()
+            .err(),
+        HybridCacheConfig::builder()
+            .memory_size(4096)
+            .disk_size(Some(1024))
+            .build()
+            .err(),
+        MetricsConfig::builder()
+            .snapshot_interval(Duration::ZERO)
+            .build()
+            .err(),
+        RateLimitConfig::builder()
+            .requests_per_second(0.0)
+            .build()
+            .err(),
+        RateLimitConfig::builder().bytes_per_second(0).build().err(),
+        RateLimitConfig::builder().burst("0s").build().err(),
+        RetryConfig::builder().max_attempts(0).build().err(),
+        RetryConfig::builder()
+            .initial_backoff("10s")
+            .max_backoff("1s")
+            .build()
+            .err(),
+        RetryConfig::builder().multiplier(0.5).build().err(),
+        RetryConfig::builder().jitter(1.5).build().err(),
+        CircuitBreakerConfig::builder()
+            .failure_threshold(0)
+            .build()
+            .err(),
+        CircuitBreakerConfig::builder()
+            .probe_interval(Duration::ZERO)
+            .build()
+            .err(),
+    ];
+    for error in invalid {
+        assert!(matches!(error, Some(CacheError::InvalidConfig(_))));
+    }
+}
+
+#[test]
+fn test_human_readable_sizes_and_durations() {
+    assert_eq!(parse_size("512MB").unwrap(), 512_000_000);
+    assert_eq!(parse_size("10GiB").unwrap(), 10 << 30);
+    assert_eq!(parse_size("1.5 kib").unwrap(), 1536);
+    assert_eq!(parse_size("4096").unwrap(), 4096);
+    assert!(parse_size("10 parsecs").is_err());
+    assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+    assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert!(parse_duration("soon").is_err());
+
+    let config: HybridCacheConfig = serde_json::from_str(
+        r#"{
+            "memory_size": "512MiB",
+            "disk_size": "10GiB",
+            "demotion_threshold": "10m",
+            "disk_ttl": "2h",
+            "maintenance_interval": 30,
+            "memory_ttl": {"secs": 5, "nanos": 0}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.memory_size, 512 << 20);
+    assert_eq!(config.disk_size, Some(10 << 30));
+    assert_eq!(config.demotion_threshold, Duration::from_secs(600));
+    assert_eq!(config.disk_ttl, Some(Duration::from_secs(7200)));
+    assert_eq!(config.maintenance_interval, Duration::from_secs(30));
+    assert_eq!(config.memory_ttl, Some(Duration::from_secs(5)));
+    assert!(serde_json::from_str::<HybridCacheConfig>(r#"{"memory_size": "lots"}"#).is_err());
+
+    let config = HybridCacheConfig::builder()
+        .memory_size("64MiB")
+        .memory_max_entry_bytes("1MB")
+        .ttl("1d")
+        .build()
+        .unwrap();
+    assert_eq!(config.memory_size, 64 << 20);
+    assert_eq!(config.memory_max_entry_bytes, Some(1_000_000));
+    assert_eq!(config.ttl, Some(Duration::from_secs(86400)));
+
+    let invalid = CacheConfig::builder()
+        .max_memory_size("12 furlongs")
+        .build();
+    assert!(matches!(invalid, Err(CacheError::InvalidConfig(_))));
+
+    let config = CacheConfig::default()
+        .override_from(vars(&[("ZARRS_CACHE_TTL", "5m")]))
+        .unwrap();
+    assert_eq!(config.ttl, Some(Duration::from_secs(300)));
+}
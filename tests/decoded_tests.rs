@@ -0,0 +1,149 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+use zarrs::array::codec::GzipCodec;
+use zarrs::array::{ArrayBuilder, DataType, FillValue};
+use zarrs::storage::store::MemoryStore;
+use zarrs_cache::{
+    Cache, CacheError, DecodedChunkCache, DecodedChunkKey, DiskCache, LruMemoryCache,
+};
+
+#[tokio::test]
+async fn test_decoded_cache_decodes_each_chunk_once() {
+    let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024));
+    let key = DecodedChunkKey::new("/temperature/", &[0, 4, 2], r#"[{"name":"zstd"}]"#);
+    assert_eq!(key.array(), "/temperature");
+    assert_eq!(key.chunk_indices(), &[0, 4, 2]);
+
+    let decodes = Arc::new(AtomicUsize::new(0));
+    for _ in 0..3 {
+        let decodes = decodes.clone();
+        let chunk = decoded
+            .get_or_decode(&key, || async move {
+                decodes.fetch_add(1, Ordering::SeqCst);
+                Ok::<_, CacheError>(vec![7u8; 1001])
+            })
+            .await
+            .unwrap();
+        assert_eq!(chunk.len(), 1001);
+        assert!(chunk.iter().all(|&b| b == 7));
+        assert_eq!(chunk.as_ptr() as usize % 8, 0);
+    }
+    assert_eq!(decodes.load(Ordering::SeqCst), 1);
+
+    let stats = decoded.stats();
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.decodes, 1);
+    assert_eq!(stats.decoded_bytes, 1001);
+    assert_eq!(decoded.cache_stats().entry_count, 1);
+}
+
+#[tokio::test]
+async fn test_decoded_cache_keys_include_the_codec_chain() {
+    let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024));
+    let zstd = DecodedChunkKey::new("/temperature", &[0, 0], "zstd");
+    let blosc = DecodedChunkKey::new("/temperature", &[0, 0], "blosc");
+    assert_ne!(zstd.store_key(), blosc.store_key());
+    assert!(zstd.store_key().starts_with("/temperature/.decoded/"));
+    assert!(zstd.store_key().ends_with("/0.0"));
+
+    decoded.insert(&zstd, b"zstd chunk").await.unwrap();
+    assert!(decoded.get(&blosc).await.is_none());
+    assert_eq!(decoded.get(&zstd).await.unwrap(), &b"zstd chunk"[..]);
+}
+
+#[tokio::test]
+async fn test_decoded_cache_keeps_failed_decodes_out() {
+    let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024));
+    let key = DecodedChunkKey::new("/temperature", &[1], "zstd");
+
+    let result = decoded
+        .get_or_decode(&key, || async {
+            Err::<Vec<u8>, _>(CacheError::Corruption("bad zstd frame".to_string()))
+        })
+        .await;
+    assert!(matches!(result, Err(CacheError::Corruption(_))));
+    assert!(decoded.get(&key).await.is_none());
+    assert_eq!(decoded.stats().failed_decodes, 1);
+    assert_eq!(decoded.stats().decodes, 0);
+}
+
+#[tokio::test]
+async fn test_decoded_cache_invalidates_whole_arrays() {
+    let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024));
+    for chunk in 0..3 {
+        decoded
+            .insert(
+                &DecodedChunkKey::new("/temperature", &[chunk], "zstd"),
+                b"t",
+            )
+            .await
+            .unwrap();
+        decoded
+            .insert(
+                &DecodedChunkKey::new("/temperature", &[chunk], "gzip"),
+                b"t",
+            )
+            .await
+            .unwrap();
+    }
+    let pressure = DecodedChunkKey::new("/temperature_2m", &[0], "zstd");
+    decoded.insert(&pressure, b"p").await.unwrap();
+
+    assert_eq!(decoded.invalidate_array("/temperature").await.unwrap(), 6);
+    assert!(decoded.get(&pressure).await.is_some());
+    assert_eq!(decoded.cache().stats().entry_count, 1);
+}
+
+#[tokio::test]
+async fn test_decoded_cache_realigns_hits_from_disk() {
+    let temp_dir = TempDir::new().unwrap();
+    let decoded =
+        DecodedChunkCache::new(DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap());
+    let key = DecodedChunkKey::new("/temperature", &[0], "zstd");
+    decoded.insert(&key, &[1u8; 100]).await.unwrap();
+
+    let chunk = decoded.get(&key).await.unwrap();
+    assert_eq!(chunk.as_ptr() as usize % 8, 0);
+    assert_eq!(&chunk[..], &[1u8; 100][..]);
+}
+
+#[tokio::test]
+async fn test_decoded_keys_follow_zarrs_arrays() {
+    let store = Arc::new(MemoryStore::new());
+    let plain = ArrayBuilder::new(
+        vec![8, 8],
+        DataType::Float32,
+        vec![4, 4].try_into().unwrap(),
+        FillValue::from(0.0f32),
+    )
+    .build(store.clone(), "/plain")
+    .unwrap();
+    let gzip = ArrayBuilder::new(
+        vec![8, 8],
+        DataType::Float32,
+        vec![4, 4].try_into().unwrap(),
+        FillValue::from(0.0f32),
+    )
+    .bytes_to_bytes_codecs(vec![Arc::new(GzipCodec::new(5).unwrap())])
+    .build(store, "/plain")
+    .unwrap();
+
+    let key = DecodedChunkKey::for_array(&plain, &[1, 0]);
+    assert_eq!(key.array(), "/plain");
+    assert_eq!(key, DecodedChunkKey::for_array(&plain, &[1, 0]));
+    assert_ne!(key, DecodedChunkKey::for_array(&gzip, &[1, 0]));
+
+    let decoded = DecodedChunkCache::new(LruMemoryCache::new(1024 * 1024));
+    let chunk = decoded
+        .get_or_decode(&key, || async {
+            let bytes = plain.retrieve_chunk(&[1, 0]).map_err(CacheError::backend)?;
+            Ok(bytes
+                .into_fixed()
+                .map_err(CacheError::backend)?
+                .into_owned())
+        })
+        .await
+        .unwrap();
+    assert_eq!(chunk.len(), 4 * 4 * 4);
+}
@@ -0,0 +1,116 @@
+// Embedded key-value disk cache tests
+// Run with: cargo test --features redb
+
+#[cfg(feature = "redb")]
+mod embedded_tests {
+    use bytes::Bytes;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tokio::time::sleep;
+    use zarrs_cache::{Cache, EmbeddedDiskCache, EmbeddedDiskCacheConfig};
+
+    #[tokio::test]
+    async fn test_embedded_cache_basic_operations() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache =
+            EmbeddedDiskCache::new(temp_dir.path().join("cache.redb"), Some(1024 * 1024)).unwrap();
+
+        let key = "array/0.0".to_string();
+        assert!(cache.get(&key).await.is_none());
+
+        cache.set(&key, Bytes::from("chunk")).await.unwrap();
+        assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+        assert_eq!(cache.size(), 5);
+
+        cache.set(&key, Bytes::from("longer chunk")).await.unwrap();
+        assert_eq!(cache.get(&key).await, Some(Bytes::from("longer chunk")));
+        assert_eq!(cache.size(), 12);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entry_count, 1);
+
+        cache.remove(&key).await.unwrap();
+        assert!(cache.get(&key).await.is_none());
+        assert_eq!(cache.size(), 0);
+
+        cache.set(&key, Bytes::from("again")).await.unwrap();
+        cache.clear().await.unwrap();
+        assert!(cache.get(&key).await.is_none());
+        assert_eq!(cache.stats().entry_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_embedded_cache_survives_restart() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("cache.redb");
+
+        {
+            let cache = EmbeddedDiskCache::new(path.clone(), None).unwrap();
+            for i in 0..10 {
+                cache
+                    .set(&format!("chunk/{}", i), Bytes::from(vec![i as u8; 16]))
+                    .await
+                    .unwrap();
+            }
+            cache.remove("chunk/3").await.unwrap();
+        }
+
+        let cache = EmbeddedDiskCache::new(path, None).unwrap();
+        assert_eq!(cache.stats().entry_count, 9);
+        assert_eq!(cache.size(), 9 * 16);
+        assert!(cache.get("chunk/3").await.is_none());
+        assert_eq!(cache.get("chunk/7").await, Some(Bytes::from(vec![7u8; 16])));
+    }
+
+    #[tokio::test]
+    async fn test_embedded_cache_evicts_least_recently_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = EmbeddedDiskCache::new(temp_dir.path().join("cache.redb"), Some(100)).unwrap();
+
+        for i in 0..2 {
+            cache
+                .set(&format!("chunk/{}", i), Bytes::from(vec![0u8; 40]))
+                .await
+                .unwrap();
+        }
+        // Touch chunk/0 so chunk/1 is the eviction victim
+        assert!(cache.get("chunk/0").await.is_some());
+
+        cache
+            .set("chunk/2", Bytes::from(vec![0u8; 40]))
+            .await
+            .unwrap();
+        assert!(cache.size() <= 100);
+        assert!(cache.get("chunk/0").await.is_some());
+        assert!(cache.get("chunk/1").await.is_none());
+        assert!(cache.get("chunk/2").await.is_some());
+
+        assert!(matches!(
+            cache.set("huge", Bytes::from(vec![0u8; 200])).await,
+            Err(zarrs_cache::CacheError::EntryTooLarge { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_embedded_cache_ttl() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = EmbeddedDiskCache::with_config(EmbeddedDiskCacheConfig {
+            path: temp_dir.path().join("cache.redb"),
+            ttl: Some(Duration::from_millis(50)),
+            sync_writes: false,
+            ..Default::default()
+        })
+        .unwrap();
+
+        cache.set("a", Bytes::from("1")).await.unwrap();
+        cache.set("b", Bytes::from("2")).await.unwrap();
+        assert!(cache.get("a").await.is_some());
+
+        sleep(Duration::from_millis(80)).await;
+        assert!(cache.get("a").await.is_none());
+        assert_eq!(cache.purge_expired().await.unwrap(), 1);
+        assert_eq!(cache.size(), 0);
+    }
+}
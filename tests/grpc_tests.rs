@@ -0,0 +1,122 @@
+#![cfg(feature = "grpc")]
+
+use bytes::Bytes;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use zarrs_cache::grpc::{CacheService, RemoteCache};
+use zarrs_cache::{Cache, CacheError, ClusterCache, LruMemoryCache};
+
+/// Serve `cache` on a free local port, returning its endpoint
+async fn serve<C: Cache>(cache: Arc<C>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(CacheService::new(cache).into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    format!("http://{}", address)
+}
+
+#[tokio::test]
+async fn test_remote_cache_round_trip() {
+    let cache = Arc::new(LruMemoryCache::new(1024));
+    let remote = RemoteCache::connect(serve(cache.clone()).await)
+        .await
+        .unwrap();
+
+    let key = "temp/0.0".to_string();
+    assert!(remote.get(&key).await.is_none());
+    remote.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(remote.get(&key).await, Some(Bytes::from("chunk")));
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+
+    let stats = remote.stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!(remote.size(), 0);
+    let server_stats = remote.remote_stats().await.unwrap();
+    assert_eq!(server_stats.entry_count, 1);
+    assert_eq!(server_stats.size_bytes, 5);
+    assert_eq!(remote.size(), 5);
+
+    remote.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+
+    let failed = remote
+        .warm(vec![
+            ("temp/0.1".to_string(), Bytes::from("a")),
+            ("temp/huge".to_string(), Bytes::from(vec![0u8; 4096])),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(failed, vec!["temp/huge".to_string()]);
+    assert!(cache.get("temp/0.1").await.is_some());
+
+    remote.clear().await.unwrap();
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_remote_cache_errors_keep_their_kind() {
+    let cache = Arc::new(LruMemoryCache::new(1024));
+    let remote = RemoteCache::connect(serve(cache).await).await.unwrap();
+
+    let error = remote
+        .set("huge", Bytes::from(vec![0u8; 4096]))
+        .await
+        .unwrap_err();
+    let CacheError::EntryTooLarge { max, .. } = error else {
+        panic!("unexpected error {:?}", error);
+    };
+    assert_eq!(max, 1024);
+
+    // Resizing is not part of the API
+    assert!(matches!(
+        remote.resize(2048).await,
+        Err(CacheError::Unsupported(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_remote_cache_connection_failure_is_retryable() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let error = RemoteCache::connect(format!("http://{}", address))
+        .await
+        .err()
+        .unwrap();
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn test_cluster_cache_over_grpc_peers() {
+    let share_a = Arc::new(LruMemoryCache::new(1 << 20));
+    let share_b = Arc::new(LruMemoryCache::new(1 << 20));
+    let endpoint_a = serve(share_a.clone()).await;
+    let endpoint_b = serve(share_b.clone()).await;
+
+    let node_a = ClusterCache::new("a", share_a.clone())
+        .with_grpc_peer("b", endpoint_b)
+        .unwrap();
+    let node_b = ClusterCache::new("b", share_b.clone())
+        .with_grpc_peer("a", endpoint_a)
+        .unwrap();
+
+    let keys: Vec<String> = (0..50).map(|i| format!("temp/{}.0", i)).collect();
+    for key in &keys {
+        node_a.set(key, Bytes::from(key.clone())).await.unwrap();
+    }
+    assert!(share_b.stats().entry_count > 0);
+    for key in &keys {
+        assert_eq!(node_b.get(key).await, Some(Bytes::from(key.clone())));
+    }
+    assert_eq!(node_b.peer_failures(), 0);
+
+    assert!(matches!(
+        ClusterCache::new("c", share_a).with_grpc_peer("d", "not a uri"),
+        Err(CacheError::InvalidConfig(_))
+    ));
+}
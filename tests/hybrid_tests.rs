@@ -1,8 +1,13 @@
 use bytes::Bytes;
+use std::sync::Arc;
 use std::time::Duration;
 use tempfile::TempDir;
 use tokio::time::sleep;
-use zarrs_cache::{Cache, HybridCache, HybridCacheConfig};
+use zarrs_cache::{
+    Cache, CacheError, CacheEventKind, CompressionAlgorithm, DiskCache, EventLog, HybridCache,
+    HybridCacheConfig, LatencyClass, LruMemoryCache, MemoryPolicy, MetricsCollector, MetricsConfig,
+    RemoteTierConfig, ThresholdTuning,
+};
 
 #[tokio::test]
 async fn test_hybrid_cache_basic_operations() {
@@ -15,6 +20,7 @@ async fn test_hybrid_cache_basic_operations() {
         promotion_threshold: 0.5,
         demotion_threshold: Duration::from_secs(10),
         maintenance_interval: Duration::from_secs(1),
+        ..Default::default()
     };
 
     let cache = HybridCache::new(config).unwrap();
@@ -58,6 +64,7 @@ async fn test_hybrid_cache_promotion() {
         promotion_threshold: 0.1, // Very low threshold for easy testing
         demotion_threshold: Duration::from_secs(60),
         maintenance_interval: Duration::from_millis(100),
+        ..Default::default()
     };
 
     let cache = HybridCache::new(config).unwrap();
@@ -98,6 +105,7 @@ async fn test_hybrid_cache_with_ttl() {
         promotion_threshold: 0.5,
         demotion_threshold: Duration::from_secs(10),
         maintenance_interval: Duration::from_secs(1),
+        ..Default::default()
     };
 
     let cache = HybridCache::new(config).unwrap();
@@ -116,6 +124,160 @@ async fn test_hybrid_cache_with_ttl() {
     assert!(cache.get(&key).await.is_none());
 }
 
+#[tokio::test]
+async fn test_hybrid_cache_per_tier_ttl() {
+    let temp_dir = TempDir::new().unwrap();
+    let short = Duration::from_millis(50);
+    let key = "ttl_key".to_string();
+    let value = Bytes::from("ttl_value");
+
+    // Hot copies go stale in memory while disk keeps the entry
+    let cache = HybridCache::new(HybridCacheConfig {
+        disk_dir: temp_dir.path().join("memory"),
+        memory_ttl: Some(short),
+        ..Default::default()
+    })
+    .unwrap();
+    cache.set(&key, value.clone()).await.unwrap();
+    sleep(Duration::from_millis(80)).await;
+    assert_eq!(cache.get(&key).await, Some(value.clone()));
+
+    // And the other way round
+    let cache = HybridCache::new(HybridCacheConfig {
+        disk_dir: temp_dir.path().join("disk"),
+        disk_ttl: Some(short),
+        ..Default::default()
+    })
+    .unwrap();
+    cache.set(&key, value.clone()).await.unwrap();
+    sleep(Duration::from_millis(80)).await;
+    assert_eq!(cache.get(&key).await, Some(value.clone()));
+
+    // A tier TTL overrides the shared one
+    let cache = HybridCache::new(HybridCacheConfig {
+        disk_dir: temp_dir.path().join("both"),
+        ttl: Some(short),
+        disk_ttl: Some(Duration::from_secs(3600)),
+        ..Default::default()
+    })
+    .unwrap();
+    cache.set(&key, value.clone()).await.unwrap();
+    sleep(Duration::from_millis(80)).await;
+    assert_eq!(cache.get(&key).await, Some(value));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_promotion_budget() {
+    let temp_dir = TempDir::new().unwrap();
+    let budgets = [(Some(2), None), (None, Some(250))];
+
+    for (i, (max_entries, max_bytes)) in budgets.into_iter().enumerate() {
+        let config = HybridCacheConfig {
+            disk_dir: temp_dir.path().join(i.to_string()),
+            promotion_threshold: 1.0,
+            maintenance_interval: Duration::from_millis(40),
+            // Hot entries drop out of memory and wait on disk for promotion
+            memory_ttl: Some(Duration::from_millis(30)),
+            max_promotions_per_cycle: max_entries,
+            max_promotion_bytes_per_cycle: max_bytes,
+            ..Default::default()
+        };
+        let cache = HybridCache::new(config).unwrap();
+
+        let keys: Vec<String> = (0..5).map(|n| format!("array/c/{n}")).collect();
+        for key in &keys {
+            cache.set(key, Bytes::from(vec![0u8; 100])).await.unwrap();
+            for _ in 0..3 {
+                cache.get(key).await.unwrap();
+            }
+        }
+        sleep(Duration::from_millis(60)).await;
+
+        // One maintenance run promotes only what its budget allows
+        cache.get("missing").await;
+        assert_eq!(cache.size(), 5 * 100 + 2 * 100);
+    }
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_access_tracker_is_bounded() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        access_tracker_capacity: 64,
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+
+    for n in 0..1000 {
+        cache.get(&format!("array/c/{n}")).await;
+    }
+    assert!(cache.access_stats().await.len() <= 64);
+
+    // A tracked key keeps accumulating accesses
+    let key = "array/c/999".to_string();
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+    cache.get(&key).await.unwrap();
+    assert_eq!(cache.access_stats().await[&key].0, 3);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_disk_compression() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        memory_max_entry_bytes: Some(16 * 1024),
+        disk_compression: Some(CompressionAlgorithm::Deflate { level: 6 }),
+        compression_offload_bytes: 32 * 1024,
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+
+    // Memory keeps the raw value; disk holds it compressed
+    let key = "array/c/0".to_string();
+    let value = Bytes::from(vec![1u8; 10_000]);
+    cache.set(&key, value.clone()).await.unwrap();
+    assert!(cache.size() > value.len() && cache.size() < 2 * value.len());
+    assert_eq!(cache.get(&key).await, Some(value));
+
+    // Disk-only entries are decompressed on the way out, this one on the
+    // blocking thread pool
+    let large_key = "array/c/1".to_string();
+    let large = Bytes::from(vec![2u8; 64 * 1024]);
+    cache.set(&large_key, large.clone()).await.unwrap();
+    assert!(cache.stats().size_bytes < 2 * 10_000);
+    assert_eq!(cache.get(&large_key).await, Some(large));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_streams_large_entries_to_disk() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        memory_max_entry_bytes: Some(16 * 1024),
+        disk_compression: Some(CompressionAlgorithm::Deflate { level: 6 }),
+        compression_streaming_bytes: 64 * 1024,
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+
+    let small_key = "array/c/0".to_string();
+    let small = Bytes::from(vec![1u8; 32 * 1024]);
+    let large_key = "array/c/1".to_string();
+    let large: Bytes = (0..1024 * 1024).map(|i| (i / 1024) as u8).collect();
+    cache.set(&small_key, small.clone()).await.unwrap();
+    cache.set(&large_key, large.clone()).await.unwrap();
+
+    // Both are stored compressed and read back alike
+    assert!(cache.stats().size_bytes < 64 * 1024);
+    assert_eq!(cache.get(&small_key).await, Some(small));
+    assert_eq!(cache.get(&large_key).await, Some(large));
+    let deflate = cache.stats().compression.algorithms["deflate"];
+    assert_eq!(deflate.values, 2);
+    assert_eq!(deflate.logical_bytes, 32 * 1024 + 1024 * 1024);
+    assert_eq!(deflate.stored_bytes as usize, cache.stats().size_bytes);
+}
+
 #[tokio::test]
 async fn test_hybrid_cache_default_config() {
     let temp_dir = TempDir::new().unwrap();
@@ -132,6 +294,7 @@ async fn test_hybrid_cache_default_config() {
     let config = cache.config();
     assert_eq!(config.memory_size, 64 * 1024 * 1024); // 64MB default
     assert_eq!(config.disk_size, Some(1024 * 1024 * 1024)); // 1GB default
+    assert_eq!(config.compression_offload_bytes, 256 * 1024);
 }
 
 #[tokio::test]
@@ -145,6 +308,7 @@ async fn test_hybrid_cache_multi_tier_access() {
         promotion_threshold: 2.0, // High threshold to prevent automatic promotion
         demotion_threshold: Duration::from_secs(10),
         maintenance_interval: Duration::from_secs(1),
+        ..Default::default()
     };
 
     let cache = HybridCache::new(config).unwrap();
@@ -175,3 +339,508 @@ async fn test_hybrid_cache_multi_tier_access() {
     assert_eq!(stats.entry_count, 5);
     assert!(stats.size_bytes > 0);
 }
+
+#[tokio::test]
+async fn test_hybrid_cache_with_arc_memory_tier() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 1024,
+        disk_size: Some(1024 * 1024),
+        disk_dir: temp_dir.path().to_path_buf(),
+        memory_policy: MemoryPolicy::Arc,
+        ..Default::default()
+    };
+
+    let cache = HybridCache::new(config).unwrap();
+
+    let key = "array/0.0".to_string();
+    let value = Bytes::from("chunk_data");
+
+    cache.set(&key, value.clone()).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(value));
+    assert_eq!(cache.config().memory_policy, MemoryPolicy::Arc);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_size_routing() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 64 * 1024,
+        disk_size: Some(1024 * 1024),
+        disk_dir: temp_dir.path().to_path_buf(),
+        ttl: None,
+        promotion_threshold: 0.1,
+        demotion_threshold: Duration::from_millis(1),
+        maintenance_interval: Duration::from_millis(10),
+        memory_max_entry_bytes: Some(1024),
+        memory_only_max_bytes: Some(16),
+        ..Default::default()
+    };
+
+    let cache = HybridCache::new(config).unwrap();
+
+    // Tiny entries stay in memory only
+    let meta_key = "array/zarr.json".to_string();
+    let meta = Bytes::from("{}");
+    cache.set(&meta_key, meta.clone()).await.unwrap();
+    assert_eq!(cache.stats().entry_count, 0);
+    assert_eq!(cache.size(), meta.len());
+
+    // Large entries go straight to disk and are never promoted
+    let chunk_key = "array/c/0/0".to_string();
+    let chunk = Bytes::from(vec![7u8; 4096]);
+    cache.set(&chunk_key, chunk.clone()).await.unwrap();
+    assert_eq!(cache.stats().entry_count, 1);
+    for _ in 0..5 {
+        assert_eq!(cache.get(&chunk_key).await, Some(chunk.clone()));
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(cache.size(), meta.len() + chunk.len());
+
+    // Memory-only entries are not demoted to disk by maintenance
+    sleep(Duration::from_millis(20)).await;
+    cache.get("missing").await;
+    assert_eq!(cache.get(&meta_key).await, Some(meta.clone()));
+    assert_eq!(cache.stats().entry_count, 1);
+
+    // Overwriting with a small value drops the stale disk copy
+    cache.set(&chunk_key, Bytes::from("small")).await.unwrap();
+    assert_eq!(cache.stats().entry_count, 0);
+    assert_eq!(cache.get(&chunk_key).await, Some(Bytes::from("small")));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_ghost_lists_adapt_promotion_threshold() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 512,
+        disk_size: Some(1024),
+        disk_dir: temp_dir.path().to_path_buf(),
+        ttl: None,
+        promotion_threshold: 0.5,
+        memory_max_entry_bytes: Some(256),
+        ghost_list_capacity: Some(64),
+        ..Default::default()
+    };
+
+    let cache = HybridCache::new(config).unwrap();
+    assert_eq!(cache.promotion_threshold(), 0.5);
+
+    // A key evicted from memory and read again lowers the threshold
+    let key = "array/c/0".to_string();
+    cache.set(&key, Bytes::from(vec![0u8; 200])).await.unwrap();
+    cache
+        .set("array/c/1", Bytes::from(vec![1u8; 200]))
+        .await
+        .unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from(vec![0u8; 200])));
+    let lowered = cache.promotion_threshold();
+    assert!(lowered < 0.5);
+
+    // A key evicted from disk and read again raises it
+    cache.clear().await.unwrap();
+    let evicted = "array/c/2".to_string();
+    cache
+        .set(&evicted, Bytes::from(vec![2u8; 600]))
+        .await
+        .unwrap();
+    cache
+        .set("array/c/3", Bytes::from(vec![3u8; 600]))
+        .await
+        .unwrap();
+    assert!(cache.get(&evicted).await.is_none());
+    assert!(cache.promotion_threshold() > lowered);
+
+    // Without ghost lists the threshold stays fixed
+    let fixed = HybridCache::new(HybridCacheConfig {
+        memory_size: 512,
+        disk_dir: temp_dir.path().join("fixed"),
+        promotion_threshold: 0.5,
+        ..Default::default()
+    })
+    .unwrap();
+    fixed.set(&key, Bytes::from(vec![0u8; 200])).await.unwrap();
+    fixed
+        .set("array/c/1", Bytes::from(vec![1u8; 200]))
+        .await
+        .unwrap();
+    assert!(fixed.get(&key).await.is_some());
+    assert_eq!(fixed.promotion_threshold(), 0.5);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_threshold_tuning() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        promotion_threshold: 1.0,
+        demotion_threshold: Duration::from_secs(300),
+        maintenance_interval: Duration::ZERO,
+        threshold_tuning: Some(ThresholdTuning {
+            min_samples: 5,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let metrics = Arc::new(MetricsCollector::new(MetricsConfig::default()));
+    let cache = HybridCache::new(config)
+        .unwrap()
+        .with_metrics(metrics.clone());
+
+    // Memory tier misses make promotion easier and demotion later
+    for i in 0..5 {
+        assert!(cache.get(&format!("missing/{i}")).await.is_none());
+    }
+    let promotion = cache.promotion_threshold();
+    assert!(promotion < 1.0);
+    assert!(cache.demotion_threshold() > Duration::from_secs(300));
+
+    // Wasted promotions make it harder again
+    for _ in 0..4 {
+        metrics.record_promotion(false).await;
+    }
+    for i in 0..5 {
+        assert!(cache.get(&format!("missing/{i}")).await.is_none());
+    }
+    assert!(cache.promotion_threshold() > promotion);
+
+    // Tuning stays within its bounds
+    for i in 0..200 {
+        cache.get(&format!("missing/{i}")).await;
+    }
+    assert!(cache.promotion_threshold() >= 0.01);
+    assert!(cache.demotion_threshold() <= Duration::from_secs(3600));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_warm_start() {
+    let temp_dir = TempDir::new().unwrap();
+    {
+        let disk = DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+        for name in ["a", "b", "c"] {
+            disk.set(&format!("array/c/{name}"), Bytes::from(vec![0u8; 100]))
+                .await
+                .unwrap();
+        }
+        sleep(Duration::from_millis(10)).await;
+        disk.get("array/c/b").await.unwrap();
+        disk.persist_index().await.unwrap();
+    }
+
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        // Room for a single entry
+        warm_start_bytes: Some(300),
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+
+    // Only the most recently used entry was loaded into memory
+    assert_eq!(cache.size(), 300 + 100);
+    assert!(cache.get("array/c/b").await.is_some());
+    assert_eq!(cache.size(), 300 + 100);
+}
+
+#[test]
+fn test_hybrid_cache_warm_start_decodes_large_entries_outside_a_runtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        disk_compression: Some(CompressionAlgorithm::Deflate { level: 6 }),
+        warm_start_bytes: Some(1024 * 1024),
+        ..Default::default()
+    };
+    // Above the 256 KiB from which decoding moves to the blocking pool
+    let value = Bytes::from(vec![7u8; 512 * 1024]);
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime.block_on(async {
+        let cache = HybridCache::new(config.clone()).unwrap();
+        cache.set("array/c/0", value.clone()).await.unwrap();
+        cache.close().await.unwrap();
+    });
+    drop(runtime);
+
+    // Built without a runtime, so nothing can be spawned on its pool
+    let cache = HybridCache::new(config).unwrap();
+    assert_eq!(cache.stats().entry_count, 1);
+    assert_eq!(
+        futures::executor::block_on(cache.get("array/c/0")),
+        Some(value)
+    );
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_close_persists_memory_accesses() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        warm_start_bytes: Some(300),
+        ..Default::default()
+    };
+    let hot = "array/c/a".to_string();
+
+    {
+        let cache = HybridCache::new(config.clone()).unwrap();
+        cache.set(&hot, Bytes::from(vec![0u8; 100])).await.unwrap();
+        sleep(Duration::from_millis(10)).await;
+        cache
+            .set("array/c/b", Bytes::from(vec![1u8; 50]))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(10)).await;
+        // Served by memory; the disk tier only learns of it on flush
+        assert!(cache.get(&hot).await.is_some());
+        cache.close().await.unwrap();
+
+        // Still usable after closing
+        assert!(cache.get(&hot).await.is_some());
+    }
+
+    // The entry last read from memory is the one warm-started
+    let cache = HybridCache::new(config).unwrap();
+    assert_eq!(cache.size(), 150 + 100);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_remote_tier() {
+    let temp_dir = TempDir::new().unwrap();
+    let remote: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let worker = |name: &str, write_through: bool| {
+        HybridCache::new(HybridCacheConfig {
+            disk_dir: temp_dir.path().join(name),
+            maintenance_interval: Duration::ZERO,
+            // Keep values out of memory so they go idle on disk
+            memory_max_entry_bytes: Some(10),
+            ..Default::default()
+        })
+        .unwrap()
+        .with_remote_tier(
+            remote.clone(),
+            RemoteTierConfig {
+                demotion_threshold: Duration::from_millis(20),
+                write_through,
+                ..Default::default()
+            },
+        )
+    };
+    let a = worker("a", false);
+    let b = worker("b", true);
+
+    // Idle disk entries move down to the remote tier
+    let key = "array/c/0".to_string();
+    let value = Bytes::from(vec![7u8; 100]);
+    a.set(&key, value.clone()).await.unwrap();
+    assert!(remote.get(&key).await.is_none());
+    sleep(Duration::from_millis(30)).await;
+    assert!(a.get("missing").await.is_none());
+    assert_eq!(remote.get(&key).await, Some(value.clone()));
+    assert_eq!(a.stats().entry_count, 0);
+
+    // Other workers read them from there and keep a disk copy
+    assert_eq!(b.get(&key).await, Some(value.clone()));
+    assert_eq!(b.stats().entry_count, 1);
+
+    // Write-through shares new entries at once
+    let shared = "array/c/1".to_string();
+    b.set(&shared, value.clone()).await.unwrap();
+    assert_eq!(a.get(&shared).await, Some(value.clone()));
+
+    // Removals reach the remote tier
+    a.remove(&shared).await.unwrap();
+    assert!(remote.get(&shared).await.is_none());
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_tier_stats_count_demotions() {
+    let temp_dir = TempDir::new().unwrap();
+    let remote: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let cache = HybridCache::new(HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        maintenance_interval: Duration::ZERO,
+        memory_max_entry_bytes: Some(10),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_remote_tier(
+        remote,
+        RemoteTierConfig {
+            demotion_threshold: Duration::from_millis(20),
+            ..Default::default()
+        },
+    );
+
+    let key = "array/c/0".to_string();
+    cache.set(&key, Bytes::from(vec![7u8; 100])).await.unwrap();
+    cache.remove("array/c/1").await.unwrap();
+    sleep(Duration::from_millis(30)).await;
+    cache.get("missing").await;
+
+    // Moving down to the remote tier is not counted as a removal or eviction
+    let tiers = cache.tier_stats();
+    assert_eq!(tiers.disk.removals.demoted, 1);
+    assert_eq!(tiers.disk.removals.total(), 1);
+    assert_eq!(tiers.memory.removals.total(), 0);
+    assert_eq!(tiers.remote.map(|stats| stats.entry_count), Some(1));
+    assert_eq!(cache.stats().removals.demoted, 1);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_logs_demotions() {
+    let temp_dir = TempDir::new().unwrap();
+    let events = Arc::new(EventLog::new(16));
+    let remote: Arc<dyn Cache> = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let cache = HybridCache::new(HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        maintenance_interval: Duration::ZERO,
+        memory_max_entry_bytes: Some(10),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_remote_tier(
+        remote,
+        RemoteTierConfig {
+            demotion_threshold: Duration::from_millis(20),
+            ..Default::default()
+        },
+    )
+    .with_event_log(events.clone());
+
+    let key = "array/c/0".to_string();
+    cache.set(&key, Bytes::from(vec![7u8; 100])).await.unwrap();
+    sleep(Duration::from_millis(30)).await;
+    cache.get("missing").await;
+
+    let kinds: Vec<_> = events
+        .recent()
+        .into_iter()
+        .map(|event| (event.kind, event.key))
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (CacheEventKind::Set, key.clone()),
+            (CacheEventKind::Demote, key),
+            (CacheEventKind::Get, "missing".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_reconfigures_at_runtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 64 * 1024,
+        disk_size: Some(1024 * 1024),
+        disk_dir: temp_dir.path().to_path_buf(),
+        promotion_threshold: 0.5,
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+    for i in 0..20 {
+        cache
+            .set(&format!("k{}", i), Bytes::from(vec![0u8; 1024]))
+            .await
+            .unwrap();
+    }
+
+    cache.resize(8 * 1024).await.unwrap();
+    assert_eq!(cache.capacity(), Some(8 * 1024));
+    assert!(cache.tier_stats().memory.accounted_bytes <= 8 * 1024);
+    assert_eq!(cache.config().memory_size, 64 * 1024);
+
+    cache.resize_disk(10 * 1024).await.unwrap();
+    assert!(cache.tier_stats().disk.size_bytes <= 10 * 1024);
+
+    cache.set_promotion_threshold(2.0).unwrap();
+    assert_eq!(cache.promotion_threshold(), 2.0);
+    assert!(cache.set_promotion_threshold(-1.0).is_err());
+    cache.set_demotion_threshold(Duration::from_secs(5));
+    assert_eq!(cache.demotion_threshold(), Duration::from_secs(5));
+
+    cache
+        .set_ttl(Some(Duration::from_millis(30)))
+        .await
+        .unwrap();
+    sleep(Duration::from_millis(60)).await;
+    assert_eq!(cache.get("k19").await, None);
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_errors_name_key_and_tier() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        memory_size: 1024,
+        disk_size: Some(2048),
+        disk_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config).unwrap();
+
+    let key = "array/c/0/0".to_string();
+    let error = cache
+        .set(&key, Bytes::from(vec![0u8; 4096]))
+        .await
+        .unwrap_err();
+    assert_eq!(error.key(), Some(key.as_str()));
+    assert_eq!(error.tier(), Some(zarrs_cache::Tier::Disk));
+    assert_eq!(error.operation(), Some(CacheEventKind::Set));
+    assert!(matches!(error.root(), CacheError::EntryTooLarge { .. }));
+    assert!(!error.is_retryable());
+    assert!(error
+        .to_string()
+        .starts_with("Failed to set key \"array/c/0/0\" in the disk tier: "));
+
+    // Context attached closer to the failure is kept
+    let error = error.context(CacheEventKind::Promote, zarrs_cache::Tier::Memory, "other");
+    assert_eq!(error.key(), Some(key.as_str()));
+    assert_eq!(error.tier(), Some(zarrs_cache::Tier::Disk));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_retains_costly_entries_longer() {
+    let temp_dir = TempDir::new().unwrap();
+    let events = Arc::new(EventLog::new(16));
+    let config = HybridCacheConfig::builder()
+        .disk_dir(temp_dir.path())
+        .promotion_threshold(0.5)
+        .demotion_threshold("20ms")
+        .maintenance_interval(Duration::ZERO)
+        .latency_class("s3/", LatencyClass::ObjectStore)
+        .latency_class("s3/glacier/", LatencyClass::Archive)
+        .build()
+        .unwrap();
+    let cache = HybridCache::new(config)
+        .unwrap()
+        .with_event_log(events.clone());
+    assert_eq!(cache.latency_class("local/c/0"), LatencyClass::Local);
+    assert_eq!(cache.latency_class("s3/c/0"), LatencyClass::ObjectStore);
+    assert_eq!(cache.latency_class("s3/glacier/c/0"), LatencyClass::Archive);
+
+    let local = "local/c/0".to_string();
+    let archived = "s3/glacier/c/0".to_string();
+    cache.set(&local, Bytes::from("local")).await.unwrap();
+    cache.set(&archived, Bytes::from("archived")).await.unwrap();
+    assert_eq!(cache.tier_stats().memory.entry_count, 2);
+    // Neither is read often enough to be kept in memory now
+    cache.set_promotion_threshold(20.0).unwrap();
+    let demoted = || -> Vec<String> {
+        events
+            .recent()
+            .into_iter()
+            .filter(|event| event.kind == CacheEventKind::Demote)
+            .map(|event| event.key)
+            .collect()
+    };
+
+    // The archived entry may stay idle 16 times longer than the local one
+    sleep(Duration::from_millis(40)).await;
+    cache.get("missing").await;
+    assert_eq!(demoted(), vec![local.clone()]);
+    assert_eq!(cache.tier_stats().memory.entry_count, 1);
+
+    sleep(Duration::from_millis(300)).await;
+    cache.get("missing").await;
+    assert_eq!(demoted(), vec![local, archived]);
+}
@@ -0,0 +1,85 @@
+// IndexedDB browser cache tests
+// Run with: wasm-pack test --headless --firefox -- --features wasm --test indexeddb_tests
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use bytes::Bytes;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+use zarrs_cache::{Cache, CacheError, IndexedDbCache, IndexedDbCacheConfig};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+async fn open(database: &str, max_size_bytes: Option<u64>) -> IndexedDbCache {
+    let cache = IndexedDbCache::with_config(IndexedDbCacheConfig {
+        database: database.to_string(),
+        max_size_bytes,
+        memory_size: 64,
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    cache.clear().await.unwrap();
+    cache
+}
+
+#[wasm_bindgen_test]
+async fn test_indexeddb_cache_basic_operations() {
+    let cache = open("basic", None).await;
+
+    cache.set("temp/c/0", Bytes::from("celsius")).await.unwrap();
+    assert_eq!(cache.get("temp/c/0").await, Some(Bytes::from("celsius")));
+    assert_eq!(cache.size(), 7);
+
+    cache.remove("temp/c/0").await.unwrap();
+    assert_eq!(cache.get("temp/c/0").await, None);
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entry_count, 0);
+}
+
+#[wasm_bindgen_test]
+async fn test_indexeddb_cache_survives_reopen() {
+    let cache = open("reopen", None).await;
+    // Larger than the memory tier, so reads come from IndexedDB
+    let value = Bytes::from(vec![7u8; 256]);
+    cache.set("wind/c/1", value.clone()).await.unwrap();
+    cache.set("wind/c/2", Bytes::from("small")).await.unwrap();
+    drop(cache);
+
+    let reopened = IndexedDbCache::open("reopen", None).await.unwrap();
+    assert_eq!(reopened.stats().entry_count, 2);
+    assert_eq!(reopened.get("wind/c/1").await, Some(value));
+    assert_eq!(reopened.get("wind/c/2").await, Some(Bytes::from("small")));
+}
+
+#[wasm_bindgen_test]
+async fn test_indexeddb_cache_evicts_least_recently_used() {
+    let cache = open("eviction", Some(30)).await;
+
+    cache.set("a", Bytes::from(vec![0u8; 10])).await.unwrap();
+    cache.set("b", Bytes::from(vec![1u8; 10])).await.unwrap();
+    cache.set("c", Bytes::from(vec![2u8; 10])).await.unwrap();
+    assert!(cache.get("a").await.is_some());
+    cache.set("d", Bytes::from(vec![3u8; 10])).await.unwrap();
+
+    assert!(cache.get("b").await.is_none());
+    assert!(cache.get("a").await.is_some());
+    assert_eq!(cache.stats().removals.evicted, 1);
+    assert!(matches!(
+        cache.set("huge", Bytes::from(vec![0u8; 31])).await,
+        Err(CacheError::EntryTooLarge { .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn test_indexeddb_cache_remove_prefix() {
+    let cache = open("prefix", None).await;
+    for key in ["sst/c/0", "sst/c/1", "chl/c/0"] {
+        cache.set(key, Bytes::from(key)).await.unwrap();
+    }
+
+    assert_eq!(cache.remove_prefix("sst/").await.unwrap(), 2);
+    assert_eq!(cache.get("sst/c/0").await, None);
+    assert_eq!(cache.get("chl/c/0").await, Some(Bytes::from("chl/c/0")));
+}
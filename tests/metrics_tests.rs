@@ -1,5 +1,13 @@
+use bytes::Bytes;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use zarrs_cache::{MetricsCollector, MetricsConfig, PerformanceSnapshot};
+use zarrs_cache::cache::entry_footprint;
+use zarrs_cache::{
+    AlertCondition, AlertRule, AlertState, Cache, CacheAnalyticsReport, CacheEventKind,
+    CacheEventOutcome, CacheTier, CompressedCache, DeflateCompression, DiskCache, EventLog,
+    LruMemoryCache, MetricsCache, MetricsCollector, MetricsConfig, PerformanceSnapshot,
+    RegressionThresholds,
+};
 
 #[tokio::test]
 async fn test_metrics_collector_basic_operations() {
@@ -8,6 +16,7 @@ async fn test_metrics_collector_basic_operations() {
         snapshot_interval: Duration::from_secs(1),
         track_access_patterns: true,
         track_efficiency: true,
+        max_tracked_keys: 1000,
     };
 
     let collector = MetricsCollector::new(config);
@@ -55,6 +64,9 @@ async fn test_performance_snapshot_recording() {
         average_response_time_ms: 2.5,
         memory_usage_bytes: 512,
         disk_usage_bytes: 512,
+        hit_bytes: 0,
+        miss_bytes: 0,
+        byte_hit_rate: 0.0,
     };
 
     let snapshot2 = PerformanceSnapshot {
@@ -71,6 +83,9 @@ async fn test_performance_snapshot_recording() {
         average_response_time_ms: 2.0,
         memory_usage_bytes: 768,
         disk_usage_bytes: 768,
+        hit_bytes: 0,
+        miss_bytes: 0,
+        byte_hit_rate: 0.0,
     };
 
     collector.record_snapshot(snapshot1.clone()).await;
@@ -135,6 +150,9 @@ async fn test_analytics_report_generation() {
             average_response_time_ms: 2.5 - i as f64 * 0.1,
             memory_usage_bytes: (512 + i * 50) as usize,
             disk_usage_bytes: (512 + i * 50) as usize,
+            hit_bytes: 0,
+            miss_bytes: 0,
+            byte_hit_rate: 0.0,
         };
         collector.record_snapshot(snapshot).await;
     }
@@ -204,6 +222,9 @@ async fn test_recommendations_generation() {
         average_response_time_ms: 25.0, // High response time
         memory_usage_bytes: 512,
         disk_usage_bytes: 512,
+        hit_bytes: 0,
+        miss_bytes: 0,
+        byte_hit_rate: 0.0,
     };
 
     collector.record_snapshot(poor_performance_snapshot).await;
@@ -233,6 +254,7 @@ async fn test_metrics_config_customization() {
         snapshot_interval: Duration::from_secs(30),
         track_access_patterns: false,
         track_efficiency: false,
+        max_tracked_keys: 1000,
     };
 
     let collector = MetricsCollector::new(custom_config);
@@ -272,6 +294,9 @@ async fn test_history_size_limit() {
             average_response_time_ms: 1.0,
             memory_usage_bytes: 512,
             disk_usage_bytes: 512,
+            hit_bytes: 0,
+            miss_bytes: 0,
+            byte_hit_rate: 0.0,
         };
         collector.record_snapshot(snapshot).await;
     }
@@ -282,3 +307,693 @@ async fn test_history_size_limit() {
     // The report should be generated successfully even with limited history
     assert!(report.performance_summary.average_hit_rate >= 0.0);
 }
+
+#[tokio::test]
+async fn test_metrics_collector_captures_snapshots_from_caches() {
+    let memory = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let disk = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap());
+    let config = MetricsConfig {
+        snapshot_interval: Duration::from_millis(20),
+        ..Default::default()
+    };
+    let collector = Arc::new(
+        MetricsCollector::new(config)
+            .with_cache(memory.clone(), CacheTier::Memory)
+            .with_cache(disk.clone(), CacheTier::Disk),
+    );
+
+    memory.set("a", Bytes::from(vec![0u8; 100])).await.unwrap();
+    disk.set("b", Bytes::from(vec![0u8; 300])).await.unwrap();
+    memory.get("a").await;
+    memory.get("missing").await;
+    disk.get("b").await;
+    collector
+        .record_operation("a", true, Duration::from_millis(2))
+        .await;
+    collector
+        .record_operation("missing", false, Duration::from_millis(4))
+        .await;
+
+    let snapshot = collector.capture_snapshot().await;
+    assert_eq!(snapshot.hits, 2);
+    assert_eq!(snapshot.misses, 1);
+    assert!((snapshot.hit_rate - 2.0 / 3.0).abs() < 1e-9);
+    assert_eq!(snapshot.memory_usage_bytes, 100);
+    assert_eq!(snapshot.disk_usage_bytes, 300);
+    assert_eq!(snapshot.total_size_bytes, 400);
+    assert_eq!(snapshot.entry_count, 2);
+    assert!(snapshot.operations_per_second > 0.0);
+    assert!((snapshot.average_response_time_ms - 3.0).abs() < 1e-9);
+
+    // The next snapshot only covers what happened since
+    let idle = collector.capture_snapshot().await;
+    assert_eq!(idle.operations_per_second, 0.0);
+    assert_eq!(idle.average_response_time_ms, 0.0);
+
+    // Snapshots keep being captured in the background
+    let snapshotter = collector.spawn_snapshotter();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    assert!(report.performance_summary.average_hit_rate > 0.0);
+    assert!(collector.current_metrics().await.unwrap().timestamp >= idle.timestamp);
+    drop(collector);
+    tokio::time::timeout(Duration::from_secs(1), snapshotter)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_analytics_report_export() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let collector = MetricsCollector::new(MetricsConfig::default());
+    collector
+        .record_operation("temperature/<c>&0", true, Duration::from_millis(2))
+        .await;
+    for i in 0..3 {
+        collector
+            .record_snapshot(PerformanceSnapshot {
+                timestamp: 1_700_000_000 + i,
+                hits: 10 * i,
+                misses: 5,
+                hit_rate: 0.5,
+                total_size_bytes: 1024,
+                entry_count: 4,
+                operations_per_second: 12.5,
+                average_response_time_ms: 1.5,
+                memory_usage_bytes: 512,
+                disk_usage_bytes: 512,
+                hit_bytes: 0,
+                miss_bytes: 0,
+                byte_hit_rate: 0.0,
+            })
+            .await;
+    }
+
+    let csv_path = temp_dir.path().join("snapshots.csv");
+    collector.snapshots_to_csv_file(&csv_path).await.unwrap();
+    let csv = std::fs::read_to_string(&csv_path).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("timestamp,hits,misses,hit_rate,"));
+    assert_eq!(
+        lines[3],
+        "1700000002,20,5,0.5,1024,4,12.5,1.5,512,512,0,0,0"
+    );
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    let json_path = temp_dir.path().join("report.json");
+    report.to_json_file(&json_path).unwrap();
+    let reloaded: CacheAnalyticsReport =
+        serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+    assert_eq!(reloaded.generated_at, report.generated_at);
+    assert_eq!(
+        reloaded.access_patterns.most_accessed_keys,
+        report.access_patterns.most_accessed_keys
+    );
+
+    let html = report.to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("temperature/&lt;c&gt;&amp;0"));
+    assert!(!html.contains("<c>"));
+    assert!(html.contains("Average hit rate</th><td>50.0%"));
+    let html_path = temp_dir.path().join("report.html");
+    report.to_html_file(&html_path).unwrap();
+    assert_eq!(std::fs::read_to_string(&html_path).unwrap(), html);
+}
+
+#[tokio::test]
+async fn test_metrics_cache_records_operations_and_evictions() {
+    // Room for two 300-byte values with their bookkeeping
+    let config = MetricsConfig {
+        snapshot_interval: Duration::from_millis(20),
+        ..Default::default()
+    };
+    let cache = MetricsCache::new(LruMemoryCache::new(1000), config, CacheTier::Memory);
+
+    for key in ["a", "b", "c"] {
+        cache.set(key, Bytes::from(vec![0u8; 300])).await.unwrap();
+    }
+    let evictions = cache.metrics().eviction_stats().await;
+    assert_eq!(evictions.evictions, 1);
+    assert_eq!(evictions.evicted_bytes, 300);
+
+    assert!(cache.get("a").await.is_none());
+    assert!(cache.get("c").await.is_some());
+    let stats = cache.metrics().access_statistics().await;
+    assert_eq!(stats["a"], (1, 0.0));
+    assert_eq!(stats["c"], (1, 1.0));
+
+    let snapshotter = cache.spawn_snapshotter();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let snapshot = cache.metrics().current_metrics().await.unwrap();
+    assert_eq!(snapshot.memory_usage_bytes, 600);
+    assert_eq!(snapshot.hits, 1);
+    assert_eq!(snapshot.misses, 1);
+    snapshotter.abort();
+}
+
+#[tokio::test]
+async fn test_access_distribution_classification() {
+    let report_for = |accesses: Vec<(&'static str, usize)>| async move {
+        let collector = MetricsCollector::new(MetricsConfig::default());
+        for (key, count) in accesses {
+            for _ in 0..count {
+                collector
+                    .record_operation(key, true, Duration::from_millis(1))
+                    .await;
+            }
+        }
+        collector.generate_report(Duration::from_secs(60)).await
+    };
+
+    let single = report_for(vec![("temperature/chunk_0.0", 5)]).await;
+    assert_eq!(single.access_patterns.access_distribution, "unknown");
+
+    let uniform = report_for(vec![("a/x", 10), ("b/x", 10), ("c/x", 10), ("d/x", 9)]).await;
+    assert_eq!(uniform.access_patterns.access_distribution, "uniform");
+    assert!(uniform.access_patterns.access_skew < 0.05);
+    assert!(uniform
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Sizing"));
+
+    // One hot key among many cold ones, not neighbouring chunks
+    let mut hot = vec![("hot/x", 100)];
+    hot.extend([("a/x", 1), ("b/x", 1), ("c/x", 1), ("d/x", 1), ("e/x", 1)]);
+    let skewed = report_for(hot).await;
+    assert_eq!(skewed.access_patterns.access_distribution, "skewed");
+    assert!(skewed.access_patterns.access_skew > 0.7);
+
+    // Hot accesses walking back and forth between neighbouring chunks
+    let collector = MetricsCollector::new(MetricsConfig::default());
+    for key in ["t/chunk_5.5", "t/chunk_9.9"] {
+        collector
+            .record_operation(key, true, Duration::from_millis(1))
+            .await;
+    }
+    for _ in 0..20 {
+        for key in ["t/chunk_0.0", "t/chunk_0.1"] {
+            collector
+                .record_operation(key, true, Duration::from_millis(1))
+                .await;
+        }
+    }
+    let clustered = collector.generate_report(Duration::from_secs(60)).await;
+    assert_eq!(clustered.access_patterns.access_distribution, "clustered");
+}
+
+#[tokio::test]
+async fn test_report_attributes_removals_per_tier() {
+    let memory = Arc::new(LruMemoryCache::with_ttl(
+        1024 * 1024,
+        Some(Duration::from_millis(20)),
+    ));
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let disk = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), Some(250)).unwrap());
+    let collector = MetricsCollector::new(MetricsConfig::default())
+        .with_cache(memory.clone(), CacheTier::Memory)
+        .with_cache(disk.clone(), CacheTier::Disk);
+
+    for i in 0..4 {
+        let key = format!("chunk_{}", i);
+        memory.set(&key, Bytes::from(vec![0u8; 100])).await.unwrap();
+        disk.set(&key, Bytes::from(vec![0u8; 100])).await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(memory.purge_expired().await.unwrap(), 4);
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    assert_eq!(report.removals.memory.expired, 4);
+    assert_eq!(report.removals.memory.evicted, 0);
+    assert_eq!(report.removals.disk.evicted, 2);
+    assert_eq!(report.removals.disk.expired, 0);
+
+    // Memory misses point at the TTL, disk misses at its size
+    assert!(report
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Expiry" && rec.description.contains("memory tier")));
+    assert!(report
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Sizing" && rec.description.contains("disk tier")));
+    assert!(report.to_html().contains("<h2>Removals</h2>"));
+}
+
+#[tokio::test]
+async fn test_report_includes_compression_stats() {
+    let cache = Arc::new(CompressedCache::new(
+        LruMemoryCache::new(1024 * 1024),
+        DeflateCompression::new(6),
+    ));
+    let collector = MetricsCollector::new(MetricsConfig::default())
+        .with_cache(cache.clone(), CacheTier::Memory);
+
+    // Noise stands in for chunks already compressed by their zarr codec
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for i in 0..8 {
+        let noise: Bytes = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        cache.set(&format!("chunk_{}", i), noise).await.unwrap();
+    }
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    let deflate = report.compression.algorithms["deflate"];
+    assert_eq!(deflate.values, 8);
+    assert_eq!(deflate.logical_bytes, 8 * 4096);
+    assert!(deflate.ratio() < 1.0);
+    assert!(report
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Compression" && rec.description.contains("deflate")));
+    assert!(report.to_html().contains("<h2>Compression</h2>"));
+}
+
+#[tokio::test]
+async fn test_byte_weighted_hit_rate() {
+    let cache = MetricsCache::new(
+        LruMemoryCache::new(1024 * 1024),
+        MetricsConfig::default(),
+        CacheTier::Memory,
+    );
+    let small = "array/zarr.json".to_string();
+    let large = "array/c/0/0".to_string();
+
+    // Writes without a preceding miss are not upstream fetches
+    cache.set(&small, Bytes::from(vec![0u8; 10])).await.unwrap();
+    for _ in 0..9 {
+        assert!(cache.get(&small).await.is_some());
+    }
+    assert!(cache.get(&large).await.is_none());
+    cache
+        .set(&large, Bytes::from(vec![0u8; 1000]))
+        .await
+        .unwrap();
+    // Only the write that fills the miss counts
+    cache
+        .set(&large, Bytes::from(vec![0u8; 1000]))
+        .await
+        .unwrap();
+
+    let snapshot = cache.metrics().capture_snapshot().await;
+    assert_eq!(snapshot.hit_bytes, 90);
+    assert_eq!(snapshot.miss_bytes, 1000);
+    assert!((snapshot.hit_rate - 0.9).abs() < 1e-9);
+    assert!((snapshot.byte_hit_rate - 90.0 / 1090.0).abs() < 1e-9);
+
+    let report = cache
+        .metrics()
+        .generate_report(Duration::from_secs(60))
+        .await;
+    assert!(
+        (report.performance_summary.average_byte_hit_rate - snapshot.byte_hit_rate).abs() < 1e-9
+    );
+    assert!(report
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Bandwidth"));
+}
+
+#[tokio::test]
+async fn test_rolling_window_stats() {
+    let collector = MetricsCollector::new(MetricsConfig::default());
+    assert_eq!(collector.rolling_stats().await.last_1m.hit_rate, 0.0);
+
+    for i in 0..4 {
+        collector
+            .record_operation("a", i > 0, Duration::from_millis(1))
+            .await;
+    }
+
+    let rolling = collector.rolling_stats().await;
+    for window in [rolling.last_1m, rolling.last_5m, rolling.last_15m] {
+        assert_eq!((window.hits, window.misses), (3, 1));
+        assert_eq!(window.hit_rate, 0.75);
+        // Only the time since recording started counts towards throughput
+        assert!(window.operations_per_second >= 2.0);
+    }
+    assert_eq!(rolling.last_5m.window, Duration::from_secs(300));
+    assert_eq!(
+        collector.window_stats(Duration::from_secs(30)).await.hits,
+        3
+    );
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    assert_eq!(report.performance_summary.recent, rolling);
+}
+
+#[tokio::test]
+async fn test_hit_rate_vs_memory_size_simulation() {
+    let keys: Vec<String> = (0..6).map(|i| format!("t/c/{}", i)).collect();
+    let capacity = 4 * entry_footprint(&keys[0], 100);
+    let cache = MetricsCache::new(
+        LruMemoryCache::new(capacity),
+        MetricsConfig::default(),
+        CacheTier::Memory,
+    );
+    assert!(cache.metrics().hit_rate_curve().await.is_empty());
+
+    // Cycling over six entries thrashes a cache holding four
+    for _ in 0..5 {
+        for key in &keys {
+            if cache.get(key).await.is_none() {
+                cache.set(key, Bytes::from(vec![0u8; 100])).await.unwrap();
+            }
+        }
+    }
+    assert_eq!(cache.inner().stats().hits, 0);
+
+    let report = cache
+        .metrics()
+        .generate_report(Duration::from_secs(60))
+        .await;
+    let curve: Vec<(f64, usize, f64)> = report
+        .size_simulation
+        .iter()
+        .map(|point| {
+            (
+                point.size_factor,
+                point.memory_size_bytes,
+                point.estimated_hit_rate,
+            )
+        })
+        .collect();
+    assert_eq!(
+        curve,
+        vec![
+            (0.5, capacity / 2, 0.0),
+            (1.0, capacity, 0.0),
+            (2.0, capacity * 2, 0.8),
+            (4.0, capacity * 4, 0.8),
+        ]
+    );
+    assert!(report
+        .recommendations
+        .iter()
+        .any(|rec| rec.category == "Sizing" && rec.description.contains("Doubling")));
+    assert!(report.to_html().contains("Hit Rate vs Memory Size"));
+}
+
+#[tokio::test]
+async fn test_alert_rules() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let disk = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), Some(1000)).unwrap());
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let collector = MetricsCollector::new(MetricsConfig::default())
+        .with_cache(disk.clone(), CacheTier::Disk)
+        .with_alert_rule(AlertRule::new(
+            "low-hit-rate",
+            AlertCondition::HitRateBelow(0.5),
+        ))
+        .with_alert_rule(AlertRule::new(
+            "disk-full",
+            AlertCondition::DiskUtilizationAbove(0.8),
+        ))
+        .with_alert_rule(
+            AlertRule::new("sustained", AlertCondition::HitRateBelow(0.5))
+                .for_duration(Duration::from_secs(3600)),
+        )
+        .with_alert_callback({
+            let seen = seen.clone();
+            move |event| seen.lock().unwrap().push(event.rule.clone())
+        });
+    let mut alerts = collector.subscribe_alerts();
+
+    disk.set("chunk", Bytes::from(vec![0u8; 900]))
+        .await
+        .unwrap();
+    for _ in 0..4 {
+        collector
+            .record_operation("a", false, Duration::from_millis(1))
+            .await;
+    }
+    collector.capture_snapshot().await;
+
+    let mut fired = Vec::new();
+    while let Ok(event) = alerts.try_recv() {
+        assert_eq!(event.state, AlertState::Firing);
+        fired.push((event.rule, event.value));
+    }
+    fired.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        fired,
+        vec![
+            ("disk-full".to_string(), 0.9),
+            ("low-hit-rate".to_string(), 0.0)
+        ]
+    );
+    assert_eq!(seen.lock().unwrap().len(), 2);
+
+    // Still violated: no repeated events
+    collector.capture_snapshot().await;
+    assert!(alerts.try_recv().is_err());
+
+    for _ in 0..6 {
+        collector
+            .record_operation("a", true, Duration::from_millis(1))
+            .await;
+    }
+    collector.capture_snapshot().await;
+    let resolved = alerts.try_recv().unwrap();
+    assert_eq!(resolved.rule, "low-hit-rate");
+    assert_eq!(resolved.state, AlertState::Resolved);
+    assert_eq!(resolved.value, 0.6);
+    assert!(alerts.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_error_rate_alert() {
+    let cache = MetricsCache::new(
+        LruMemoryCache::new(1000),
+        MetricsConfig::default(),
+        CacheTier::Memory,
+    );
+    let collector = MetricsCollector::new(MetricsConfig::default()).with_alert_rule(
+        AlertRule::new("errors", AlertCondition::ErrorRateAbove(0.1)),
+    );
+    let mut alerts = collector.subscribe_alerts();
+
+    // Values larger than the whole cache fail to store
+    assert!(cache
+        .set("huge", Bytes::from(vec![0u8; 2000]))
+        .await
+        .is_err());
+    let window = cache.metrics().window_stats(Duration::from_secs(60)).await;
+    assert_eq!(window.errors, 1);
+    assert_eq!(window.error_rate(), 1.0);
+
+    collector
+        .record_operation("a", true, Duration::from_millis(1))
+        .await;
+    collector.capture_snapshot().await;
+    assert!(alerts.try_recv().is_err());
+    collector.record_error().await;
+    collector.capture_snapshot().await;
+    let event = alerts.try_recv().unwrap();
+    assert_eq!(event.condition, AlertCondition::ErrorRateAbove(0.1));
+    assert_eq!(event.value, 0.5);
+}
+
+#[tokio::test]
+async fn test_metrics_cache_event_log() {
+    let events = Arc::new(EventLog::new(4));
+    let mut subscriber = events.subscribe();
+    let cache = MetricsCache::new(
+        LruMemoryCache::new(1000),
+        MetricsConfig::default(),
+        CacheTier::Memory,
+    )
+    .with_event_log(events.clone());
+
+    let key = "a".to_string();
+    assert!(cache.get(&key).await.is_none());
+    cache.set(&key, Bytes::from(vec![0u8; 300])).await.unwrap();
+    assert!(cache.get(&key).await.is_some());
+    cache.remove(&key).await.unwrap();
+
+    let logged: Vec<_> = events
+        .recent()
+        .into_iter()
+        .map(|event| (event.kind, event.outcome, event.size))
+        .collect();
+    assert_eq!(
+        logged,
+        vec![
+            (CacheEventKind::Get, CacheEventOutcome::Miss, None),
+            (CacheEventKind::Set, CacheEventOutcome::Ok, Some(300)),
+            (CacheEventKind::Get, CacheEventOutcome::Hit, Some(300)),
+            (CacheEventKind::Remove, CacheEventOutcome::Ok, None),
+        ]
+    );
+    let first = subscriber.try_recv().unwrap();
+    assert_eq!(first.key, "a");
+    assert!(first.latency.is_some());
+
+    // Filling past capacity logs the eviction, and the ring keeps only the newest
+    for key in ["b", "c", "d"] {
+        cache.set(key, Bytes::from(vec![0u8; 300])).await.unwrap();
+    }
+    let recent = events.recent();
+    assert_eq!(recent.len(), 4);
+    let last = recent.last().unwrap();
+    assert_eq!((last.kind, last.key.as_str()), (CacheEventKind::Evict, "d"));
+    assert_eq!(last.size, Some(300));
+}
+
+#[tokio::test]
+async fn test_resource_utilization_is_measured() {
+    let memory_capacity = 4 * entry_footprint("a", 100);
+    let memory = Arc::new(LruMemoryCache::new(memory_capacity));
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let disk = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), Some(1000)).unwrap());
+    let collector = MetricsCollector::new(MetricsConfig::default())
+        .with_cache(memory.clone(), CacheTier::Memory)
+        .with_cache(disk.clone(), CacheTier::Disk);
+
+    memory.set("a", Bytes::from(vec![0u8; 100])).await.unwrap();
+    disk.set("b", Bytes::from(vec![0u8; 250])).await.unwrap();
+    disk.get("b").await;
+    disk.remove("b").await.unwrap();
+    disk.set("c", Bytes::from(vec![0u8; 500])).await.unwrap();
+
+    collector.capture_snapshot().await;
+    let utilization = collector.resource_utilization().await;
+    assert!((utilization.memory_utilization - 0.25).abs() < 1e-9);
+    assert!((utilization.disk_utilization - 0.5).abs() < 1e-9);
+    // Two writes, one read and one deletion of entry files
+    assert_eq!(utilization.io_operations, 4);
+    assert_eq!(disk.io_operations(), Some(4));
+    assert_eq!(memory.io_operations(), None);
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    let efficiency = report.efficiency_analysis;
+    assert!((efficiency.resource_efficiency - 0.375).abs() < 1e-9);
+    assert_eq!(efficiency.resource_utilization.io_operations, 4);
+}
+
+#[tokio::test]
+async fn test_report_diff_detects_regressions() {
+    let report_with = |hit_rate: f64, response_time_ms: f64| async move {
+        let collector = MetricsCollector::new(MetricsConfig::default());
+        collector
+            .record_snapshot(PerformanceSnapshot {
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                hits: (hit_rate * 100.0) as u64,
+                misses: 100 - (hit_rate * 100.0) as u64,
+                hit_rate,
+                total_size_bytes: 1024,
+                entry_count: 10,
+                operations_per_second: 100.0,
+                average_response_time_ms: response_time_ms,
+                memory_usage_bytes: 1024,
+                disk_usage_bytes: 0,
+                hit_bytes: 0,
+                miss_bytes: 0,
+                byte_hit_rate: 0.0,
+            })
+            .await;
+        collector.generate_report(Duration::from_secs(60)).await
+    };
+    let baseline = report_with(0.9, 2.0).await;
+
+    let unchanged = report_with(0.88, 2.2).await.diff(&baseline);
+    assert!(!unchanged.regression);
+    assert!(unchanged.regressions.is_empty());
+    assert!((unchanged.hit_rate_delta + 0.02).abs() < 1e-9);
+
+    let slower = report_with(0.7, 3.0).await;
+    let diff = slower.diff(&baseline);
+    assert!(diff.regression);
+    assert_eq!(diff.regressions.len(), 2);
+    assert!((diff.hit_rate_delta + 0.2).abs() < 1e-9);
+    assert!((diff.response_time_delta_ms - 1.0).abs() < 1e-9);
+    assert_eq!(diff.throughput_delta, 0.0);
+
+    // Looser thresholds tolerate the same change
+    let tolerant = RegressionThresholds {
+        max_hit_rate_drop: 0.5,
+        max_response_time_increase: 1.0,
+        ..Default::default()
+    };
+    assert!(!slower.diff_with(&baseline, &tolerant).regression);
+
+    // Baselines saved by an earlier run compare the same way
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("baseline.json");
+    baseline.to_json_file(&path).unwrap();
+    let loaded = CacheAnalyticsReport::from_json_file(&path).unwrap();
+    assert_eq!(slower.diff(&loaded), diff);
+}
+
+#[tokio::test]
+async fn test_access_tracking_is_bounded() {
+    let collector = MetricsCollector::new(MetricsConfig {
+        max_tracked_keys: 5,
+        ..Default::default()
+    });
+    for i in 0..1000 {
+        collector
+            .record_operation(&format!("cold/{}", i), false, Duration::from_millis(1))
+            .await;
+        if i % 4 == 0 {
+            collector
+                .record_operation("hot", true, Duration::from_millis(1))
+                .await;
+        }
+    }
+
+    let stats = collector.access_statistics().await;
+    assert_eq!(stats.len(), 5);
+    let (accesses, hit_rate) = stats["hot"];
+    assert!(accesses >= 250);
+    assert_eq!(hit_rate, 1.0);
+
+    let report = collector.generate_report(Duration::from_secs(60)).await;
+    assert_eq!(report.access_patterns.most_accessed_keys[0].0, "hot");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_recording_is_aggregated() {
+    let collector = Arc::new(MetricsCollector::new(MetricsConfig::default()));
+    let tasks: Vec<_> = (0..8)
+        .map(|task| {
+            let collector = collector.clone();
+            tokio::spawn(async move {
+                for i in 0..1000 {
+                    let key = format!("array/c/{}", task);
+                    collector
+                        .record_operation(&key, i % 4 != 0, Duration::from_millis(2))
+                        .await;
+                }
+                collector.record_error().await;
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let recent = collector.window_stats(Duration::from_secs(60)).await;
+    assert_eq!(recent.hits, 6000);
+    assert_eq!(recent.misses, 2000);
+    assert_eq!(recent.errors, 8);
+
+    // Buffered accesses are applied before they are read
+    let stats = collector.access_statistics().await;
+    assert_eq!(stats.len(), 8);
+    assert!(stats.values().all(|&(accesses, _)| accesses >= 1000));
+
+    let snapshot = collector.capture_snapshot().await;
+    assert!((snapshot.average_response_time_ms - 2.0).abs() < 1e-9);
+}
@@ -0,0 +1,170 @@
+use bytes::Bytes;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::storage::store::MemoryStore;
+use zarrs_cache::{CacheError, DatasetPack};
+use zarrs_storage::byte_range::ByteRange;
+use zarrs_storage::{
+    AsyncBytes, AsyncListableStorageTraits, AsyncReadableStorageTraits, ListableStorageTraits,
+    ReadableStorageTraits, StorageError, StoreKey, StoreKeys, StoreKeysPrefixes, StorePrefix,
+};
+
+/// Async view of a memory store that counts its reads
+struct OriginStore {
+    inner: Arc<MemoryStore>,
+    gets: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl AsyncReadableStorageTraits for OriginStore {
+    async fn get(&self, key: &StoreKey) -> Result<Option<AsyncBytes>, StorageError> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        ReadableStorageTraits::get(&*self.inner, key)
+    }
+
+    async fn get_partial_values_key(
+        &self,
+        key: &StoreKey,
+        byte_ranges: &[ByteRange],
+    ) -> Result<Option<Vec<AsyncBytes>>, StorageError> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        ReadableStorageTraits::get_partial_values_key(&*self.inner, key, byte_ranges)
+    }
+
+    async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
+        ReadableStorageTraits::size_key(&*self.inner, key)
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncListableStorageTraits for OriginStore {
+    async fn list(&self) -> Result<StoreKeys, StorageError> {
+        ListableStorageTraits::list(&*self.inner)
+    }
+
+    async fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        ListableStorageTraits::list_prefix(&*self.inner, prefix)
+    }
+
+    async fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        ListableStorageTraits::list_dir(&*self.inner, prefix)
+    }
+
+    async fn size_prefix(&self, prefix: &StorePrefix) -> Result<u64, StorageError> {
+        ListableStorageTraits::size_prefix(&*self.inner, prefix)
+    }
+}
+
+/// Origin holding a 4x4 `u8` land mask in 2x2 chunks
+fn land_mask_origin() -> OriginStore {
+    let inner = Arc::new(MemoryStore::new());
+    let array = ArrayBuilder::new(
+        vec![4, 4],
+        DataType::UInt8,
+        vec![2, 2].try_into().unwrap(),
+        FillValue::from(0u8),
+    )
+    .build(inner.clone(), "/land_mask")
+    .unwrap();
+    array.store_metadata().unwrap();
+    let mask: Vec<u8> = (0..16).map(|i| (i % 3 == 0) as u8).collect();
+    array
+        .store_array_subset_elements(&array.subset_all(), &mask)
+        .unwrap();
+    OriginStore {
+        inner,
+        gets: AtomicUsize::new(0),
+    }
+}
+
+#[tokio::test]
+async fn test_dataset_pack_serves_arrays_without_origin_traffic() {
+    let temp_dir = TempDir::new().unwrap();
+    let origin = land_mask_origin();
+    let path = temp_dir.path().join("land_mask.pack");
+
+    let pack = DatasetPack::download(&origin, &StorePrefix::root(), &path)
+        .await
+        .unwrap();
+    // Metadata and four chunks
+    assert_eq!(pack.len(), 5);
+    let downloaded = origin.gets.load(Ordering::SeqCst);
+    assert_eq!(downloaded, 5);
+
+    let array = Array::open(Arc::new(pack), "/land_mask").unwrap();
+    let mask = array
+        .retrieve_array_subset_elements::<u8>(&array.subset_all())
+        .unwrap();
+    let expected: Vec<u8> = (0..16).map(|i| (i % 3 == 0) as u8).collect();
+    assert_eq!(mask, expected);
+    assert_eq!(origin.gets.load(Ordering::SeqCst), downloaded);
+}
+
+#[tokio::test]
+async fn test_dataset_pack_is_downloaded_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let origin = land_mask_origin();
+    let path = temp_dir.path().join("packs/land_mask.pack");
+    let prefix = StorePrefix::new("land_mask/").unwrap();
+
+    DatasetPack::open_or_download(&origin, &prefix, &path)
+        .await
+        .unwrap();
+    let pack = DatasetPack::open_or_download(&origin, &prefix, &path)
+        .await
+        .unwrap();
+    assert_eq!(origin.gets.load(Ordering::SeqCst), 5);
+    assert_eq!(pack.path(), path);
+
+    let listing = ListableStorageTraits::list_dir(&pack, &prefix).unwrap();
+    assert_eq!(listing.keys().len(), 1);
+    assert_eq!(
+        listing.prefixes(),
+        &[StorePrefix::new("land_mask/c/").unwrap()]
+    );
+    assert_eq!(
+        ListableStorageTraits::size_prefix(&pack, &prefix).unwrap(),
+        pack.size_bytes()
+    );
+}
+
+#[tokio::test]
+async fn test_dataset_pack_detects_corruption() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("climatology.pack");
+    let key = StoreKey::new("climatology/c/0").unwrap();
+    let pack = DatasetPack::write(&path, [(key.clone(), Bytes::from("monthly means"))]).unwrap();
+    assert_eq!(
+        pack.get_value(key.as_str()),
+        Some(Bytes::from("monthly means"))
+    );
+    let ranges = ReadableStorageTraits::get_partial_values_key(
+        &pack,
+        &key,
+        &[ByteRange::FromStart(8, Some(5))],
+    )
+    .unwrap();
+    assert_eq!(ranges, Some(vec![Bytes::from("means")]));
+    drop(pack);
+
+    let mut data = std::fs::read(&path).unwrap();
+    let at = data
+        .windows(7)
+        .position(|window| window == b"monthly")
+        .unwrap();
+    data[at] ^= 1;
+    std::fs::write(&path, data).unwrap();
+    assert!(matches!(
+        DatasetPack::open(&path),
+        Err(CacheError::Corruption(_))
+    ));
+
+    // A corrupt pack is downloaded again
+    let origin = land_mask_origin();
+    let pack = DatasetPack::open_or_download(&origin, &StorePrefix::root(), &path)
+        .await
+        .unwrap();
+    assert_eq!(pack.len(), 5);
+}
@@ -0,0 +1,245 @@
+use bytes::Bytes;
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::time::sleep;
+use zarrs_cache::{Cache, CacheError, PackedDiskCache, PackedDiskCacheConfig};
+
+fn small_segments(dir: &TempDir) -> PackedDiskCacheConfig {
+    PackedDiskCacheConfig {
+        cache_dir: dir.path().to_path_buf(),
+        segment_size_bytes: 256,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_packed_cache_basic_operations() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = PackedDiskCache::new(temp_dir.path().to_path_buf(), Some(1024 * 1024)).unwrap();
+
+    let key = "array/0.0".to_string();
+    assert!(cache.get(&key).await.is_none());
+
+    cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+    assert_eq!(cache.size(), 5);
+
+    cache.set(&key, Bytes::from("longer chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("longer chunk")));
+    assert_eq!(cache.size(), 12);
+
+    let stats = cache.stats();
+    assert_eq!(stats.hits, 2);
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.entry_count, 1);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+
+    cache.set(&key, Bytes::from("again")).await.unwrap();
+    cache.clear().await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_packed_cache_replays_segments_on_restart() {
+    let temp_dir = TempDir::new().unwrap();
+
+    {
+        let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+        for i in 0..20 {
+            cache
+                .set(&format!("chunk/{}", i), Bytes::from(vec![i as u8; 32]))
+                .await
+                .unwrap();
+        }
+        cache.remove("chunk/3").await.unwrap();
+        cache.set("chunk/4", Bytes::from("updated")).await.unwrap();
+        assert!(cache.segment_count() > 1);
+    }
+
+    let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+    assert_eq!(cache.stats().entry_count, 19);
+    assert!(cache.get("chunk/3").await.is_none());
+    assert_eq!(cache.get("chunk/4").await, Some(Bytes::from("updated")));
+    assert_eq!(
+        cache.get("chunk/19").await,
+        Some(Bytes::from(vec![19u8; 32]))
+    );
+}
+
+#[tokio::test]
+async fn test_packed_cache_compaction_reclaims_dead_space() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+
+    for i in 0..20 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![0u8; 32]))
+            .await
+            .unwrap();
+    }
+    // Kill most of the early records
+    for i in 0..15 {
+        cache.remove(&format!("chunk/{}", i)).await.unwrap();
+    }
+
+    let before = cache.stats().accounted_bytes;
+    let reclaimed = cache.compact().await.unwrap();
+    assert!(reclaimed > 0);
+    assert!(cache.stats().accounted_bytes < before);
+
+    for i in 15..20 {
+        assert!(cache.get(&format!("chunk/{}", i)).await.is_some());
+    }
+    drop(cache);
+
+    // Removed keys stay removed after compaction and restart
+    let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+    assert_eq!(cache.stats().entry_count, 5);
+    assert!(cache.get("chunk/0").await.is_none());
+    assert!(cache.get("chunk/19").await.is_some());
+}
+
+#[tokio::test]
+async fn test_packed_cache_eviction_and_ttl() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = PackedDiskCache::with_config(PackedDiskCacheConfig {
+        cache_dir: temp_dir.path().to_path_buf(),
+        max_size_bytes: Some(100),
+        ttl: Some(Duration::from_millis(50)),
+        ..Default::default()
+    })
+    .unwrap();
+
+    for i in 0..5 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![0u8; 40]))
+            .await
+            .unwrap();
+    }
+    assert!(cache.size() <= 100);
+    assert!(cache.get("chunk/0").await.is_none());
+    assert!(cache.get("chunk/4").await.is_some());
+
+    sleep(Duration::from_millis(80)).await;
+    assert_eq!(cache.purge_expired().await.unwrap(), 2);
+    assert_eq!(cache.size(), 0);
+}
+
+#[tokio::test]
+async fn test_packed_cache_detects_corruption() {
+    let temp_dir = TempDir::new().unwrap();
+    let key = "array/0.0".to_string();
+
+    {
+        let cache = PackedDiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+        cache.set(&key, Bytes::from("pristine")).await.unwrap();
+    }
+
+    let segment = std::fs::read_dir(temp_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| std::fs::metadata(path).unwrap().len() > 0)
+        .unwrap();
+    let mut contents = std::fs::read(&segment).unwrap();
+    let last = contents.len() - 1;
+    contents[last] ^= 0x01;
+    std::fs::write(&segment, contents).unwrap();
+
+    let cache = PackedDiskCache::new(temp_dir.path().to_path_buf(), None).unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.corrupted_entries(), 1);
+    assert_eq!(cache.stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_packed_cache_evictions_survive_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = PackedDiskCacheConfig {
+        max_size_bytes: Some(160),
+        ..small_segments(&temp_dir)
+    };
+    let value = |byte: u8| Bytes::from(vec![byte; 40]);
+
+    {
+        let cache = PackedDiskCache::with_config(config.clone()).unwrap();
+        // The first version of chunk/x stays behind in the first segment
+        for key in ["chunk/x", "chunk/a", "chunk/b"] {
+            cache.set(key, value(1)).await.unwrap();
+        }
+        cache.set("chunk/x", value(2)).await.unwrap();
+        cache.set("chunk/c", value(0)).await.unwrap();
+        for key in ["chunk/a", "chunk/b", "chunk/c"] {
+            cache.get(key).await.unwrap();
+        }
+        // Evicts the second version of chunk/x
+        cache.set("chunk/d", value(0)).await.unwrap();
+        assert!(cache.get("chunk/x").await.is_none());
+        // Compacts the segment of the evicted version only
+        cache.remove("chunk/c").await.unwrap();
+        assert!(cache.compact().await.unwrap() > 0);
+    }
+
+    let cache = PackedDiskCache::with_config(config).unwrap();
+    assert!(cache.get("chunk/x").await.is_none());
+    assert_eq!(cache.stats().entry_count, 3);
+}
+
+#[tokio::test]
+async fn test_packed_cache_compaction_drops_corrupt_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+    for i in 0..4u8 {
+        cache
+            .set(&format!("chunk/{}", i), Bytes::from(vec![i; 32]))
+            .await
+            .unwrap();
+    }
+    cache.remove("chunk/0").await.unwrap();
+    cache.remove("chunk/1").await.unwrap();
+
+    // Rot the live value left in the first segment
+    let segment = temp_dir.path().join("segment-00000000.pack");
+    let mut contents = std::fs::read(&segment).unwrap();
+    let at = contents
+        .windows(32)
+        .position(|window| window == [2u8; 32])
+        .unwrap();
+    contents[at] ^= 0x01;
+    std::fs::write(&segment, contents).unwrap();
+
+    cache.compact().await.unwrap();
+    assert_eq!(cache.corrupted_entries(), 1);
+    assert!(cache.get("chunk/2").await.is_none());
+    assert!(cache.get("chunk/3").await.is_some());
+    drop(cache);
+
+    let cache = PackedDiskCache::with_config(small_segments(&temp_dir)).unwrap();
+    assert!(cache.get("chunk/2").await.is_none());
+    assert_eq!(cache.stats().entry_count, 1);
+}
+
+#[test]
+fn test_packed_config_validation() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = PackedDiskCacheConfig::builder()
+        .cache_dir(temp_dir.path())
+        .segment_size_bytes(1024)
+        .build()
+        .unwrap();
+    assert_eq!(config.segment_size_bytes, 1024);
+
+    let unusable = PackedDiskCache::with_config(PackedDiskCacheConfig {
+        segment_size_bytes: 0,
+        ..config.clone()
+    });
+    assert!(matches!(unusable, Err(CacheError::InvalidConfig(_))));
+    assert!(matches!(
+        PackedDiskCacheConfig::builder()
+            .compaction_threshold(2.0)
+            .build(),
+        Err(CacheError::InvalidConfig(_))
+    ));
+}
@@ -0,0 +1,153 @@
+use bytes::Bytes;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zarrs_cache::cache::entry_footprint;
+use zarrs_cache::pressure::shrink_on_pressure;
+use zarrs_cache::{
+    ArcMemoryCache, Cache, CacheError, ClockMemoryCache, HybridCache, HybridCacheConfig,
+    LruMemoryCache, MemoryPressureConfig, MemoryPressureMonitor, MemoryUsage, PressureLevel,
+};
+
+/// Monitor driven by a settable usage percentage
+fn test_monitor() -> (Arc<MemoryPressureMonitor>, Arc<AtomicU64>) {
+    let used = Arc::new(AtomicU64::new(0));
+    let probe_used = used.clone();
+    let monitor = MemoryPressureMonitor::with_probe(MemoryPressureConfig::default(), move || {
+        Some(MemoryUsage {
+            used_bytes: probe_used.load(Ordering::Relaxed),
+            limit_bytes: 100,
+        })
+    });
+    (Arc::new(monitor), used)
+}
+
+async fn fill(cache: &dyn Cache, count: usize) {
+    for i in 0..count {
+        cache
+            .set(&format!("key{}", i), Bytes::from(vec![0u8; 10]))
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_memory_caches_resize() {
+    let capacity = 10 * entry_footprint("key0", 10);
+    let caches: Vec<Box<dyn Cache>> = vec![
+        Box::new(LruMemoryCache::new(capacity)),
+        Box::new(ArcMemoryCache::new(capacity)),
+        Box::new(ClockMemoryCache::new(capacity)),
+    ];
+
+    for cache in caches {
+        fill(cache.as_ref(), 10).await;
+        assert_eq!(cache.capacity(), Some(capacity));
+        assert_eq!(cache.stats().entry_count, 10);
+
+        cache.resize(capacity / 2).await.unwrap();
+        assert_eq!(cache.capacity(), Some(capacity / 2));
+        assert!(cache.stats().accounted_bytes <= capacity / 2);
+        assert_eq!(cache.stats().entry_count, 5);
+
+        // Growing again only raises the limit
+        cache.resize(capacity).await.unwrap();
+        fill(cache.as_ref(), 10).await;
+        assert_eq!(cache.stats().entry_count, 10);
+    }
+}
+
+#[tokio::test]
+async fn test_monitor_levels_with_hysteresis() {
+    let (monitor, used) = test_monitor();
+    assert_eq!(monitor.check(), PressureLevel::Normal);
+
+    used.store(90, Ordering::Relaxed);
+    assert_eq!(monitor.check(), PressureLevel::Elevated);
+
+    used.store(97, Ordering::Relaxed);
+    assert_eq!(monitor.check(), PressureLevel::Critical);
+
+    // Between the low and high watermarks pressure stays raised
+    used.store(80, Ordering::Relaxed);
+    assert_eq!(monitor.check(), PressureLevel::Elevated);
+
+    used.store(50, Ordering::Relaxed);
+    assert_eq!(monitor.check(), PressureLevel::Normal);
+
+    // Rising into the band from normal does not raise pressure
+    used.store(80, Ordering::Relaxed);
+    assert_eq!(monitor.check(), PressureLevel::Normal);
+}
+
+#[tokio::test]
+async fn test_shrink_on_pressure_restores_capacity() {
+    let (monitor, used) = test_monitor();
+    let cache = Arc::new(LruMemoryCache::new(1000));
+    let _task = shrink_on_pressure(cache.clone(), monitor.signal());
+
+    used.store(90, Ordering::Relaxed);
+    monitor.check();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(cache.capacity(), Some(500));
+
+    used.store(10, Ordering::Relaxed);
+    monitor.check();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(cache.capacity(), Some(1000));
+}
+
+#[tokio::test]
+async fn test_hybrid_cache_under_pressure() {
+    let temp_dir = TempDir::new().unwrap();
+    let footprint = entry_footprint("key0", 10);
+    let config = HybridCacheConfig {
+        memory_size: 20 * footprint,
+        disk_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let (monitor, used) = test_monitor();
+    let cache = HybridCache::new(config)
+        .unwrap()
+        .with_memory_pressure(monitor.signal());
+
+    fill(&cache, 20).await;
+    let before = cache.stats();
+
+    // Critical pressure: memory shrinks to 10% and new writes go to disk only
+    used.store(99, Ordering::Relaxed);
+    monitor.check();
+    cache.set("late", Bytes::from(vec![1u8; 10])).await.unwrap();
+    let during = cache.stats();
+    assert!(during.accounted_bytes < before.accounted_bytes);
+    assert_eq!(during.entry_count, 21); // Disk still holds everything
+    assert_eq!(cache.get("late").await, Some(Bytes::from(vec![1u8; 10])));
+
+    // Pressure subsides: writes reach memory again
+    used.store(10, Ordering::Relaxed);
+    monitor.check();
+    fill(&cache, 20).await;
+    assert!(cache.stats().accounted_bytes > during.accounted_bytes + 10 * footprint);
+}
+
+#[test]
+fn test_memory_pressure_config_validation() {
+    let config = MemoryPressureConfig::builder()
+        .watermarks(0.5, 0.7, 0.9)
+        .critical_capacity_factor(0.2)
+        .build()
+        .unwrap();
+    assert_eq!(config.high_watermark, 0.7);
+    assert_eq!(config.critical_capacity_factor, 0.2);
+
+    let invalid = [
+        MemoryPressureConfig::builder().watermarks(0.8, 0.7, 0.9),
+        MemoryPressureConfig::builder().watermarks(0.5, 0.7, 1.5),
+        MemoryPressureConfig::builder().critical_capacity_factor(0.8),
+        MemoryPressureConfig::builder().check_interval(Duration::ZERO),
+    ];
+    for builder in invalid {
+        assert!(matches!(builder.build(), Err(CacheError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,73 @@
+use bytes::Bytes;
+use std::sync::Arc;
+use tempfile::TempDir;
+use zarrs_cache::{registry, Cache, CacheError, HybridCache, HybridCacheConfig, LruMemoryCache};
+
+// Tests run in one process and share the registry, so each uses its own names
+
+#[tokio::test]
+async fn test_registry_shares_caches_by_name() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    let ingest = registry::get_or_create("shared", config.clone()).unwrap();
+    let query = registry::get_or_create(
+        "shared",
+        HybridCacheConfig {
+            memory_size: 1024,
+            ..config
+        },
+    )
+    .unwrap();
+    assert!(Arc::ptr_eq(&ingest, &query));
+    assert_eq!(query.config().memory_size, 64 * 1024 * 1024);
+
+    let key = "climate/c/0".to_string();
+    ingest.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(query.get(&key).await, Some(Bytes::from("chunk")));
+
+    let found = registry::get::<HybridCache>("shared").unwrap();
+    assert!(Arc::ptr_eq(&found, &ingest));
+    assert!(registry::names().contains(&"shared".to_string()));
+
+    assert!(registry::remove("shared"));
+    assert!(registry::get::<HybridCache>("shared").is_none());
+    assert!(!registry::remove("shared"));
+}
+
+#[test]
+fn test_registry_checks_cache_types() {
+    let memory = registry::get_or_insert_with("typed", || Ok(LruMemoryCache::new(1024))).unwrap();
+    let again = registry::get_or_insert_with("typed", || -> Result<LruMemoryCache, _> {
+        panic!("the registered cache should be reused")
+    })
+    .unwrap();
+    assert!(Arc::ptr_eq(&memory, &again));
+
+    assert!(registry::get::<HybridCache>("typed").is_none());
+    assert!(matches!(
+        registry::get_or_create("typed", HybridCacheConfig::default()),
+        Err(CacheError::InvalidConfig(_))
+    ));
+
+    // Failed creation registers nothing
+    let failed = registry::get_or_create(
+        "invalid",
+        HybridCacheConfig {
+            memory_size: 0,
+            ..Default::default()
+        },
+    );
+    assert!(failed.is_err());
+    assert!(!registry::names().contains(&"invalid".to_string()));
+
+    let replaced = registry::insert("typed", Arc::new(LruMemoryCache::new(2048)));
+    assert!(replaced.is_some());
+    assert_eq!(
+        registry::get::<LruMemoryCache>("typed").unwrap().capacity(),
+        Some(2048)
+    );
+}
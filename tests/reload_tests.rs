@@ -0,0 +1,93 @@
+use std::time::Duration;
+use tempfile::TempDir;
+use zarrs_cache::reload;
+use zarrs_cache::{
+    Cache, HybridCache, HybridCacheConfig, PrefetchConfig, PrefetchStrategy, SequentialPrefetch,
+};
+
+#[tokio::test]
+async fn test_apply_reports_settings_needing_restart() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = HybridCacheConfig {
+        disk_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let cache = HybridCache::new(config.clone()).unwrap();
+
+    let changed = HybridCacheConfig {
+        memory_size: 1024 * 1024,
+        memory_ttl: Some(Duration::from_secs(60)),
+        promotion_threshold: 2.0,
+        access_tracker_capacity: 10,
+        ..config.clone()
+    };
+    let restart = reload::apply(&cache, &config, &changed).await.unwrap();
+    assert_eq!(restart, vec!["access_tracker_capacity".to_string()]);
+    assert_eq!(cache.capacity(), Some(1024 * 1024));
+    assert_eq!(cache.promotion_threshold(), 2.0);
+
+    // Nothing changed, nothing applied
+    cache.set_promotion_threshold(3.0).unwrap();
+    assert!(reload::apply(&cache, &changed, &changed)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(cache.promotion_threshold(), 3.0);
+}
+
+#[tokio::test]
+async fn test_apply_updates_prefetch_strategies() {
+    let strategy = SequentialPrefetch::new(&PrefetchConfig::default());
+    assert_eq!(strategy.generate_prefetch_keys("temp/0.0").len(), 2);
+
+    let config = PrefetchConfig {
+        neighbor_chunks: 4,
+        ..Default::default()
+    };
+    let restart = reload::apply(&strategy, &PrefetchConfig::default(), &config)
+        .await
+        .unwrap();
+    assert!(restart.is_empty());
+    assert_eq!(strategy.generate_prefetch_keys("temp/0.0").len(), 4);
+}
+
+#[cfg(feature = "hot-reload")]
+#[tokio::test]
+async fn test_config_watcher_reloads_changed_files() {
+    use std::sync::Arc;
+    use zarrs_cache::reload::ConfigWatcher;
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("cache.json");
+    let disk_dir = temp_dir.path().join("disk");
+    let write = |memory_size: &str| {
+        let json = serde_json::json!({ "memory_size": memory_size, "disk_dir": disk_dir });
+        std::fs::write(&path, json.to_string()).unwrap();
+    };
+    write("1MiB");
+
+    let config = HybridCacheConfig::from_file(&path).unwrap();
+    let cache = Arc::new(HybridCache::new(config.clone()).unwrap());
+    let watcher = ConfigWatcher::spawn(&path, cache.clone(), config).unwrap();
+
+    write("2MiB");
+    let mut reloaded = false;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if cache.capacity() == Some(2 << 20) {
+            reloaded = true;
+            break;
+        }
+    }
+    assert!(reloaded, "memory size should follow the file");
+
+    // Invalid files keep the running configuration
+    write("0");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(cache.capacity(), Some(2 << 20));
+
+    drop(watcher);
+    write("4MiB");
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(cache.capacity(), Some(2 << 20));
+}
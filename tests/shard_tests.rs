@@ -0,0 +1,216 @@
+use bytes::Bytes;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use zarrs::array::codec::{ShardingCodecBuilder, ShardingIndexLocation};
+use zarrs::array::{Array, ArrayBuilder, DataType, FillValue};
+use zarrs::storage::store::MemoryStore;
+use zarrs::storage::ReadableStorageTraits;
+use zarrs_cache::{
+    Cache, CacheError, IndexLocation, LruMemoryCache, ShardCache, ShardIndex, ShardLayout,
+};
+use zarrs_storage::byte_range::ByteRange;
+
+/// An 8x8 `u16` array in one shard of 2x2 inner chunks, holding 0..64
+fn sharded_array(location: ShardingIndexLocation) -> (Arc<MemoryStore>, Array<MemoryStore>) {
+    let store = Arc::new(MemoryStore::new());
+    let mut sharding = ShardingCodecBuilder::new(vec![4, 4].try_into().unwrap());
+    sharding.index_location(location);
+    let array = ArrayBuilder::new(
+        vec![8, 8],
+        DataType::UInt16,
+        vec![8, 8].try_into().unwrap(),
+        FillValue::from(0u16),
+    )
+    .array_to_bytes_codec(Arc::new(sharding.build()))
+    .build(store.clone(), "/temperature")
+    .unwrap();
+    let values: Vec<u16> = (0..64).collect();
+    array.store_chunk_elements(&[0, 0], &values).unwrap();
+    (store, array)
+}
+
+fn inner_chunk_values(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_shard_cache_fetches_index_once_per_shard() {
+    let (store, array) = sharded_array(ShardingIndexLocation::End);
+    let layout = ShardLayout::for_array(&array).unwrap();
+    assert_eq!(layout, ShardLayout::new(vec![2, 2]));
+    let shard_key = array.chunk_key(&[0, 0]).to_string();
+
+    let loads = Arc::new(AtomicUsize::new(0));
+    let loader = |key: String, range: ByteRange| {
+        let store = store.clone();
+        let loads = loads.clone();
+        async move {
+            loads.fetch_add(1, Ordering::SeqCst);
+            let key = zarrs::storage::StoreKey::new(key).unwrap();
+            store
+                .get_partial_values_key(&key, &[range])
+                .unwrap()
+                .map(|mut values| values.remove(0))
+        }
+    };
+
+    let shards = ShardCache::new(LruMemoryCache::new(1024 * 1024));
+    let chunk = shards
+        .get_inner_chunk(&shard_key, &layout, &[1, 1], loader)
+        .await
+        .unwrap()
+        .unwrap();
+    let expected: Vec<u16> = (4..8)
+        .flat_map(|row| (4..8).map(move |col| row * 8 + col))
+        .collect();
+    assert_eq!(inner_chunk_values(&chunk), expected);
+    // The index, then the inner chunk
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    // Cached inner chunk
+    shards
+        .get_inner_chunk(&shard_key, &layout, &[1, 1], loader)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+
+    // Neighboring inner chunk reuses the cached index
+    let neighbor = shards
+        .get_inner_chunk(&shard_key, &layout, &[1, 0], loader)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(inner_chunk_values(&neighbor)[0], 32);
+    assert_eq!(loads.load(Ordering::SeqCst), 3);
+
+    let stats = shards.stats();
+    assert_eq!(stats.index_loads, 1);
+    assert_eq!(stats.index_hits, 1);
+    assert_eq!(stats.chunk_loads, 2);
+    assert_eq!(stats.chunk_hits, 1);
+    assert_eq!(stats.bytes_loaded, layout.index_size() + 2 * 32);
+
+    assert_eq!(shards.invalidate_shard(&shard_key).await.unwrap(), 3);
+    assert_eq!(shards.cache().stats().entry_count, 0);
+}
+
+#[tokio::test]
+async fn test_shard_cache_reads_indexes_at_the_start() {
+    let (store, array) = sharded_array(ShardingIndexLocation::Start);
+    let layout = ShardLayout::for_array(&array).unwrap();
+    assert_eq!(layout.index_location, IndexLocation::Start);
+    assert_eq!(layout.index_range(), ByteRange::FromStart(0, Some(68)));
+
+    let shards = ShardCache::new(LruMemoryCache::new(1024 * 1024));
+    let chunk = shards
+        .get_inner_chunk("temperature/c/0/0", &layout, &[0, 1], |_key, range| {
+            let store = store.clone();
+            async move {
+                let key = zarrs::storage::StoreKey::new("temperature/c/0/0").unwrap();
+                store
+                    .get_partial_values_key(&key, &[range])
+                    .unwrap()
+                    .map(|mut values| values.remove(0))
+            }
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(inner_chunk_values(&chunk)[..4], [4, 5, 6, 7]);
+}
+
+#[tokio::test]
+async fn test_shard_cache_handles_missing_shards_and_chunks() {
+    let layout = ShardLayout::new(vec![2]).with_index_checksum(false);
+    let shards = ShardCache::new(LruMemoryCache::new(1024 * 1024));
+
+    let missing = shards
+        .get_inner_chunk("temperature/c/1", &layout, &[0], |_key, _range| async {
+            None
+        })
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+
+    // Only the first inner chunk is stored
+    let mut index = Vec::new();
+    index.extend_from_slice(&0u64.to_le_bytes());
+    index.extend_from_slice(&4u64.to_le_bytes());
+    index.extend_from_slice(&u64::MAX.to_le_bytes());
+    index.extend_from_slice(&u64::MAX.to_le_bytes());
+    let index = Bytes::from(index);
+    let loader = |_key: String, range: ByteRange| {
+        let index = index.clone();
+        async move {
+            match range {
+                ByteRange::Suffix(_) => Some(index),
+                _ => Some(Bytes::from("fill")),
+            }
+        }
+    };
+    let fill = shards
+        .get_inner_chunk("temperature/c/0", &layout, &[1], loader)
+        .await
+        .unwrap();
+    assert!(fill.is_none());
+    let stored = shards
+        .get_inner_chunk("temperature/c/0", &layout, &[0], loader)
+        .await
+        .unwrap();
+    assert_eq!(stored, Some(Bytes::from("fill")));
+
+    assert!(matches!(
+        shards
+            .get_inner_chunk("temperature/c/0", &layout, &[2], loader)
+            .await,
+        Err(CacheError::InvalidKey(_))
+    ));
+}
+
+#[test]
+fn test_shard_index_rejects_corrupt_indexes() {
+    let layout = ShardLayout::new(vec![1]);
+    let mut index = Vec::new();
+    index.extend_from_slice(&0u64.to_le_bytes());
+    index.extend_from_slice(&8u64.to_le_bytes());
+    let checksum = crc32c::crc32c(&index);
+    index.extend_from_slice(&checksum.to_le_bytes());
+
+    let parsed = ShardIndex::decode(&index, &layout).unwrap();
+    assert_eq!(parsed.len(), 1);
+    assert_eq!(parsed.stored_chunks(), 1);
+    assert_eq!(
+        parsed.chunk_range(0),
+        Some(ByteRange::FromStart(0, Some(8)))
+    );
+
+    index[0] ^= 1;
+    assert!(matches!(
+        ShardIndex::decode(&index, &layout),
+        Err(CacheError::Corruption(_))
+    ));
+    assert!(matches!(
+        ShardIndex::decode(&index[..10], &layout),
+        Err(CacheError::Corruption(_))
+    ));
+}
+
+#[test]
+fn test_shard_layout_requires_a_sharded_array() {
+    let array = ArrayBuilder::new(
+        vec![8, 8],
+        DataType::UInt16,
+        vec![4, 4].try_into().unwrap(),
+        FillValue::from(0u16),
+    )
+    .build(Arc::new(MemoryStore::new()), "/plain")
+    .unwrap();
+    assert!(matches!(
+        ShardLayout::for_array(&array),
+        Err(CacheError::InvalidConfig(_))
+    ));
+}
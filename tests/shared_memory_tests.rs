@@ -0,0 +1,152 @@
+use bytes::Bytes;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zarrs_cache::{Cache, CacheError, SharedMemoryCache, SharedMemoryCacheConfig};
+
+fn config(dir: &TempDir) -> SharedMemoryCacheConfig {
+    SharedMemoryCacheConfig::builder()
+        .path(dir.path().join("segment"))
+        .size_bytes(1024u64)
+        .max_entries(64)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_shared_memory_cache_basic_operations() {
+    let dir = TempDir::new().unwrap();
+    let cache = SharedMemoryCache::with_config(config(&dir)).unwrap();
+    let key = "temperature/c/0/0".to_string();
+
+    assert!(cache.get(&key).await.is_none());
+    cache.set(&key, Bytes::from("chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("chunk")));
+
+    cache.set(&key, Bytes::from("newer chunk")).await.unwrap();
+    assert_eq!(cache.get(&key).await, Some(Bytes::from("newer chunk")));
+    assert_eq!(cache.size(), 11);
+
+    let stats = cache.stats();
+    assert_eq!((stats.hits, stats.misses), (2, 1));
+    assert_eq!(stats.entry_count, 1);
+
+    cache.remove(&key).await.unwrap();
+    assert!(cache.get(&key).await.is_none());
+    assert_eq!(cache.stats().removals.removed, 1);
+
+    assert!(matches!(
+        cache.set(&key, Bytes::from(vec![0u8; 2048])).await,
+        Err(CacheError::EntryTooLarge { max: 1024, .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_shared_memory_cache_is_shared_between_instances() {
+    let dir = TempDir::new().unwrap();
+    let first = SharedMemoryCache::with_config(config(&dir)).unwrap();
+
+    // A second mapping of the segment, as another process would open it;
+    // its own size settings are ignored in favour of the segment's
+    let second = SharedMemoryCache::new(dir.path().join("segment"), 1 << 20).unwrap();
+    assert_eq!(second.capacity(), Some(1024));
+    assert_eq!(second.max_entries(), 64);
+
+    first.set("a", Bytes::from("from first")).await.unwrap();
+    second.set("b", Bytes::from("from second")).await.unwrap();
+    assert_eq!(second.get("a").await, Some(Bytes::from("from first")));
+    assert_eq!(first.get("b").await, Some(Bytes::from("from second")));
+
+    assert_eq!(second.remove_prefix("a").await.unwrap(), 1);
+    assert!(first.get("a").await.is_none());
+    first.clear().await.unwrap();
+    assert_eq!(second.stats().entry_count, 0);
+
+    // Files that are not segments are refused
+    std::fs::write(dir.path().join("other"), b"not a segment").unwrap();
+    assert!(matches!(
+        SharedMemoryCache::new(dir.path().join("other"), 1024),
+        Err(CacheError::Corruption(_))
+    ));
+
+    first.unlink().unwrap();
+    assert!(!dir.path().join("segment").exists());
+    assert!(second.get("b").await.is_none());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_shared_memory_cache_concurrent_writers() {
+    let dir = TempDir::new().unwrap();
+    let config = SharedMemoryCacheConfig::builder()
+        .path(dir.path().join("segment"))
+        .size_bytes(1024 * 1024u64)
+        .max_entries(1024)
+        .build()
+        .unwrap();
+
+    let mut tasks = Vec::new();
+    for worker in 0..4u8 {
+        let cache = Arc::new(SharedMemoryCache::with_config(config.clone()).unwrap());
+        tasks.push(tokio::spawn(async move {
+            for round in 0..50u8 {
+                for chunk in 0..8 {
+                    let key = format!("chunk/{}", chunk);
+                    cache
+                        .set(&key, Bytes::from(vec![worker ^ round; 100]))
+                        .await
+                        .unwrap();
+                    // Whatever write wins, values are never torn
+                    let value = cache.get(&key).await.unwrap();
+                    assert!(value.iter().all(|&byte| byte == value[0]));
+                }
+            }
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let cache = SharedMemoryCache::with_config(config).unwrap();
+    assert_eq!(cache.stats().entry_count, 8);
+}
+
+#[tokio::test]
+async fn test_shared_memory_cache_overwrites_oldest_entries() {
+    let dir = TempDir::new().unwrap();
+    let cache = SharedMemoryCache::with_config(config(&dir)).unwrap();
+
+    // Each record takes 104 bytes of the 1024-byte ring
+    for i in 0..20 {
+        cache
+            .set(&format!("k{:02}", i), Bytes::from(vec![i as u8; 97]))
+            .await
+            .unwrap();
+    }
+
+    assert!(cache.get("k00").await.is_none());
+    assert_eq!(cache.get("k19").await, Some(Bytes::from(vec![19u8; 97])));
+    let stats = cache.stats();
+    assert!(stats.entry_count < 20);
+    assert!(stats.size_bytes <= 1024);
+}
+
+#[tokio::test]
+async fn test_shared_memory_cache_ttl() {
+    let dir = TempDir::new().unwrap();
+    let cache = SharedMemoryCache::with_config(config(&dir)).unwrap();
+    let key = "array/0".to_string();
+    cache.set(&key, Bytes::from("value")).await.unwrap();
+
+    cache
+        .set_ttl(Some(Duration::from_millis(20)))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // The TTL is a setting of the segment, seen by every instance
+    let other = SharedMemoryCache::with_config(config(&dir)).unwrap();
+    assert!(other.get(&key).await.is_none());
+    assert_eq!(other.get_stale(&key).await, Some(Bytes::from("value")));
+    assert_eq!(cache.purge_expired().await.unwrap(), 1);
+    assert_eq!(cache.stats().entry_count, 0);
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+use zarrs_cache::cache::entry_footprint;
+use zarrs_cache::simulator::{PolicySimulator, SimulatedPolicy};
+use zarrs_cache::{TraceOp, TraceRecord};
+
+fn record(op: TraceOp, key: &str, hit: bool, size: u64) -> TraceRecord {
+    TraceRecord {
+        at: Duration::ZERO,
+        op,
+        key: key.to_string(),
+        hit,
+        size,
+    }
+}
+
+/// Ten hot keys read twice every round, each round followed by a scan of
+/// twenty keys read once, as recorded by a cache in front of a store
+fn scan_trace() -> Vec<TraceRecord> {
+    let mut records = Vec::new();
+    for round in 0..50 {
+        for hot in 0..10 {
+            let key = format!("hot/{}", hot);
+            if round == 0 {
+                records.push(record(TraceOp::Get, &key, false, 0));
+                records.push(record(TraceOp::Set, &key, false, 100));
+            } else {
+                records.push(record(TraceOp::Get, &key, true, 100));
+            }
+            records.push(record(TraceOp::Get, &key, true, 100));
+        }
+        for scan in 0..20 {
+            let key = format!("scan/{}/{}", round, scan);
+            records.push(record(TraceOp::Get, &key, false, 0));
+            records.push(record(TraceOp::Set, &key, false, 100));
+        }
+    }
+    records
+}
+
+#[tokio::test]
+async fn test_frequency_policies_resist_scans() {
+    // Room for about twelve entries
+    let size = 3000;
+    let report = PolicySimulator::new(scan_trace())
+        .with_sizes([size])
+        .run()
+        .await;
+    assert_eq!(report.results.len(), SimulatedPolicy::ALL.len());
+    assert!((report.recorded_hit_rate - 990.0 / 2000.0).abs() < 1e-9);
+
+    let hit_rate = |policy| {
+        report
+            .results
+            .iter()
+            .find(|result| result.policy == policy)
+            .unwrap()
+            .hit_rate
+    };
+    // Each scan flushes the hot keys out of an LRU cache, so only their
+    // second reads hit
+    assert_eq!(hit_rate(SimulatedPolicy::Lru), 0.25);
+    assert!(hit_rate(SimulatedPolicy::Lfu) > 0.45);
+    assert!(hit_rate(SimulatedPolicy::TinyLfu) > 0.45);
+
+    let best = report.best_at(size).unwrap();
+    assert_ne!(best.policy, SimulatedPolicy::Lru);
+    assert!(report.best_at(size + 1).is_none());
+}
+
+#[tokio::test]
+async fn test_simulator_sizes_default_to_working_set_fractions() {
+    let records = vec![
+        record(TraceOp::Get, "a", false, 0),
+        record(TraceOp::Set, "a", false, 1000),
+        record(TraceOp::Set, "b", false, 500),
+        record(TraceOp::Get, "a", true, 1000),
+        record(TraceOp::Get, "b", true, 500),
+        record(TraceOp::Remove, "b", false, 0),
+    ];
+    let simulator = PolicySimulator::new(records).with_policies([SimulatedPolicy::Lru]);
+    let working_set = entry_footprint("a", 1000) + entry_footprint("b", 500);
+    assert_eq!(simulator.working_set_bytes(), working_set);
+
+    let report = simulator.run().await;
+    assert_eq!(report.working_set_bytes, working_set);
+    let sizes: Vec<usize> = report
+        .results
+        .iter()
+        .map(|result| result.size_bytes)
+        .collect();
+    assert_eq!(sizes.len(), 5);
+    assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(*sizes.last().unwrap(), working_set);
+
+    // The whole working set fits: every read after the first write hits
+    let full = report.results.last().unwrap();
+    assert_eq!((full.gets, full.hits, full.hit_bytes), (3, 2, 1500));
+    // A twentieth of it holds nothing
+    assert_eq!(report.results[0].hits, 0);
+}
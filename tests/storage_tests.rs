@@ -0,0 +1,468 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use zarrs_cache::{
+    BreakerState, Cache, CacheConfig, CachedStore, CircuitBreaker, CircuitBreakerConfig,
+    LruMemoryCache, RateLimitConfig, RateLimiter,
+};
+use zarrs_storage::byte_range::ByteRange;
+use zarrs_storage::store::MemoryStore;
+use zarrs_storage::{
+    AsyncBytes, AsyncListableStorageTraits, AsyncReadableStorageTraits, AsyncWritableStorageTraits,
+    ListableStorageTraits, StorageError, StoreKey, StoreKeyOffsetValue, StoreKeys,
+    StoreKeysPrefixes, StorePrefix, WritableStorageTraits,
+};
+
+/// Async store in memory that counts its reads and can be made to fail
+#[derive(Default)]
+struct CountingStore {
+    values: Mutex<HashMap<String, Bytes>>,
+    gets: AtomicUsize,
+    partial_gets: AtomicUsize,
+    lists: AtomicUsize,
+    failing: AtomicBool,
+}
+
+impl CountingStore {
+    fn with(values: &[(&str, &str)]) -> Self {
+        let store = Self::default();
+        for (key, value) in values {
+            store
+                .values
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), Bytes::from(value.to_string()));
+        }
+        store
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncReadableStorageTraits for CountingStore {
+    async fn get(&self, key: &StoreKey) -> Result<Option<AsyncBytes>, StorageError> {
+        self.gets.fetch_add(1, Ordering::SeqCst);
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(StorageError::Other("503 Slow Down".to_string()));
+        }
+        Ok(self.values.lock().unwrap().get(key.as_str()).cloned())
+    }
+
+    async fn get_partial_values_key(
+        &self,
+        key: &StoreKey,
+        byte_ranges: &[ByteRange],
+    ) -> Result<Option<Vec<AsyncBytes>>, StorageError> {
+        self.partial_gets.fetch_add(1, Ordering::SeqCst);
+        let Some(value) = self.values.lock().unwrap().get(key.as_str()).cloned() else {
+            return Ok(None);
+        };
+        let size = value.len() as u64;
+        Ok(Some(
+            byte_ranges
+                .iter()
+                .map(|range| value.slice(range.to_range_usize(size)))
+                .collect(),
+        ))
+    }
+
+    async fn size_key(&self, key: &StoreKey) -> Result<Option<u64>, StorageError> {
+        Ok(self
+            .values
+            .lock()
+            .unwrap()
+            .get(key.as_str())
+            .map(|value| value.len() as u64))
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncListableStorageTraits for CountingStore {
+    async fn list(&self) -> Result<StoreKeys, StorageError> {
+        self.list_prefix(&StorePrefix::root()).await
+    }
+
+    async fn list_prefix(&self, prefix: &StorePrefix) -> Result<StoreKeys, StorageError> {
+        self.lists.fetch_add(1, Ordering::SeqCst);
+        let mut keys: StoreKeys = self
+            .values
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix.as_str()))
+            .map(|key| StoreKey::new(key.as_str()).unwrap())
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn list_dir(&self, prefix: &StorePrefix) -> Result<StoreKeysPrefixes, StorageError> {
+        Ok(StoreKeysPrefixes::new(
+            self.list_prefix(prefix).await?,
+            Vec::new(),
+        ))
+    }
+
+    async fn size_prefix(&self, _prefix: &StorePrefix) -> Result<u64, StorageError> {
+        Ok(0)
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncWritableStorageTraits for CountingStore {
+    async fn set(&self, key: &StoreKey, value: AsyncBytes) -> Result<(), StorageError> {
+        self.values.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    async fn set_partial_values(
+        &self,
+        key_offset_values: &[StoreKeyOffsetValue],
+    ) -> Result<(), StorageError> {
+        let mut values = self.values.lock().unwrap();
+        for key_offset_value in key_offset_values {
+            let mut value = values
+                .get(key_offset_value.key().as_str())
+                .map(|value| value.to_vec())
+                .unwrap_or_default();
+            let offset = key_offset_value.offset() as usize;
+            let end = offset + key_offset_value.value().len();
+            value.resize(value.len().max(end), 0);
+            value[offset..end].copy_from_slice(key_offset_value.value());
+            values.insert(key_offset_value.key().to_string(), Bytes::from(value));
+        }
+        Ok(())
+    }
+
+    async fn erase(&self, key: &StoreKey) -> Result<(), StorageError> {
+        self.values.lock().unwrap().remove(key.as_str());
+        Ok(())
+    }
+
+    async fn erase_prefix(&self, prefix: &StorePrefix) -> Result<(), StorageError> {
+        self.values
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix.as_str()));
+        Ok(())
+    }
+}
+
+fn key(key: &str) -> StoreKey {
+    StoreKey::new(key).unwrap()
+}
+
+#[tokio::test]
+async fn test_async_reads_are_cached() {
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0/0", "chunk data")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    );
+
+    for _ in 0..3 {
+        let value = AsyncReadableStorageTraits::get(&store, &key("temperature/c/0/0"))
+            .await
+            .unwrap();
+        assert_eq!(value, Some(Bytes::from("chunk data")));
+    }
+    assert_eq!(store.inner().gets.load(Ordering::SeqCst), 1);
+    assert_eq!(store.cache_stats().hits, 2);
+
+    // Missing keys are not cached
+    for _ in 0..2 {
+        let missing = AsyncReadableStorageTraits::get(&store, &key("temperature/c/9/9"))
+            .await
+            .unwrap();
+        assert!(missing.is_none());
+    }
+    assert_eq!(store.inner().gets.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_async_ranged_reads_are_sliced_from_cached_values() {
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0/0", "0123456789")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    );
+    let chunk = key("temperature/c/0/0");
+    let ranges = [ByteRange::FromStart(2, Some(3)), ByteRange::Suffix(2)];
+
+    // Uncached: the ranges come from the store and are not cached
+    let values = store
+        .get_partial_values_key(&chunk, &ranges)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(values, vec![Bytes::from("234"), Bytes::from("89")]);
+    assert_eq!(store.inner().partial_gets.load(Ordering::SeqCst), 1);
+    assert_eq!(store.cache().stats().entry_count, 0);
+
+    AsyncReadableStorageTraits::get(&store, &chunk)
+        .await
+        .unwrap();
+    let values = store
+        .get_partial_values_key(&chunk, &ranges)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(values, vec![Bytes::from("234"), Bytes::from("89")]);
+    assert_eq!(store.inner().partial_gets.load(Ordering::SeqCst), 1);
+    assert_eq!(store.size_key(&chunk).await.unwrap(), Some(10));
+
+    assert!(matches!(
+        store
+            .get_partial_values_key(&chunk, &[ByteRange::FromStart(8, Some(5))])
+            .await,
+        Err(StorageError::InvalidByteRangeError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_async_reads_go_through_the_rate_limiter() {
+    let limiter = Arc::new(RateLimiter::new(
+        RateLimitConfig::builder()
+            .requests_per_second(1000.0)
+            .build()
+            .unwrap(),
+    ));
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0/0", "chunk data")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    )
+    .with_rate_limiter(limiter.clone());
+
+    for _ in 0..2 {
+        AsyncReadableStorageTraits::get(&store, &key("temperature/c/0/0"))
+            .await
+            .unwrap();
+    }
+    let stats = limiter.stats();
+    assert_eq!(stats.requests, 1);
+    assert_eq!(stats.bytes, 10);
+}
+
+#[tokio::test]
+async fn test_async_reads_serve_stale_copies_while_the_breaker_is_open() {
+    let breaker = Arc::new(CircuitBreaker::new(
+        CircuitBreakerConfig::builder()
+            .failure_threshold(2)
+            .probe_interval("1h")
+            .build()
+            .unwrap(),
+    ));
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0/0", "chunk data")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    )
+    .with_circuit_breaker(breaker.clone());
+    AsyncReadableStorageTraits::get(&store, &key("temperature/c/0/0"))
+        .await
+        .unwrap();
+
+    store.inner().failing.store(true, Ordering::SeqCst);
+    for _ in 0..2 {
+        assert!(
+            AsyncReadableStorageTraits::get(&store, &key("temperature/c/1/0"))
+                .await
+                .is_err()
+        );
+    }
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    // Refused without reaching the store
+    let gets = store.inner().gets.load(Ordering::SeqCst);
+    assert!(
+        AsyncReadableStorageTraits::get(&store, &key("temperature/c/1/0"))
+            .await
+            .is_err()
+    );
+    assert_eq!(store.inner().gets.load(Ordering::SeqCst), gets);
+
+    let value = AsyncReadableStorageTraits::get(&store, &key("temperature/c/0/0"))
+        .await
+        .unwrap();
+    assert_eq!(value, Some(Bytes::from("chunk data")));
+    assert_eq!(breaker.stats().stale_reads, 1);
+}
+
+#[test]
+fn test_listings_are_cached_for_the_listing_ttl() {
+    let inner = Arc::new(MemoryStore::new());
+    inner
+        .set(&key("temperature/c/0"), Bytes::from("chunk"))
+        .unwrap();
+    let store = CachedStore::new(
+        inner.clone(),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::builder().listing_ttl("50ms").build().unwrap(),
+    );
+    let temperature = StorePrefix::new("temperature/").unwrap();
+
+    assert_eq!(store.list_prefix(&temperature).unwrap().len(), 1);
+    inner
+        .set(&key("temperature/c/1"), Bytes::from("chunk"))
+        .unwrap();
+    // Still the cached listing
+    assert_eq!(store.list_prefix(&temperature).unwrap().len(), 1);
+    assert_eq!(store.list_dir(&temperature).unwrap().prefixes().len(), 1);
+    let stats = store.listing_stats();
+    assert_eq!((stats.hits, stats.misses, stats.cached), (1, 2, 2));
+
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(store.list_prefix(&temperature).unwrap().len(), 2);
+
+    inner
+        .set(&key("temperature/c/2"), Bytes::from("chunk"))
+        .unwrap();
+    store.invalidate_listings();
+    assert_eq!(store.list().unwrap().len(), 3);
+    assert_eq!(store.size_prefix(&temperature).unwrap(), 15);
+}
+
+#[test]
+fn test_listings_can_skip_the_cache() {
+    let inner = Arc::new(MemoryStore::new());
+    let store = CachedStore::new(
+        inner.clone(),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig {
+            listing_ttl: None,
+            ..Default::default()
+        },
+    );
+
+    assert!(store.list().unwrap().is_empty());
+    inner
+        .set(&key("temperature/c/0"), Bytes::from("chunk"))
+        .unwrap();
+    assert_eq!(store.list().unwrap().len(), 1);
+    let stats = store.listing_stats();
+    assert_eq!((stats.hits, stats.misses, stats.cached), (0, 2, 0));
+}
+
+#[tokio::test]
+async fn test_async_listings_are_cached() {
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0", "chunk"), ("pressure/c/0", "chunk")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    );
+
+    for _ in 0..3 {
+        let keys = AsyncListableStorageTraits::list(&store).await.unwrap();
+        assert_eq!(keys.len(), 2);
+    }
+    assert_eq!(store.inner().lists.load(Ordering::SeqCst), 1);
+    assert_eq!(store.listing_stats().hits, 2);
+}
+
+#[tokio::test]
+async fn test_writes_update_cached_values() {
+    let store = CachedStore::new(
+        CountingStore::with(&[("temperature/c/0", "old"), ("temperature/c/1", "0123")]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    );
+    let chunk = key("temperature/c/0");
+    AsyncReadableStorageTraits::get(&store, &chunk)
+        .await
+        .unwrap();
+
+    AsyncWritableStorageTraits::set(&store, &chunk, Bytes::from("new"))
+        .await
+        .unwrap();
+    let value = AsyncReadableStorageTraits::get(&store, &chunk)
+        .await
+        .unwrap();
+    assert_eq!(value, Some(Bytes::from("new")));
+    // Served from the updated cache
+    assert_eq!(store.inner().gets.load(Ordering::SeqCst), 1);
+
+    // Partial writes drop the cached copy
+    let partial = key("temperature/c/1");
+    AsyncReadableStorageTraits::get(&store, &partial)
+        .await
+        .unwrap();
+    store
+        .set_partial_values(&[StoreKeyOffsetValue::new(partial.clone(), 1, b"xy")])
+        .await
+        .unwrap();
+    assert!(store.get_cached("temperature/c/1").await.is_none());
+    let value = AsyncReadableStorageTraits::get(&store, &partial)
+        .await
+        .unwrap();
+    assert_eq!(value, Some(Bytes::from("0xy3")));
+}
+
+#[tokio::test]
+async fn test_erasing_invalidates_cached_values_and_listings() {
+    let store = CachedStore::new(
+        CountingStore::with(&[
+            ("temperature/c/0", "chunk"),
+            ("temperature/c/1", "chunk"),
+            ("pressure/c/0", "chunk"),
+        ]),
+        LruMemoryCache::new(1024 * 1024),
+        CacheConfig::default(),
+    );
+    for chunk in ["temperature/c/0", "temperature/c/1", "pressure/c/0"] {
+        AsyncReadableStorageTraits::get(&store, &key(chunk))
+            .await
+            .unwrap();
+    }
+    let pressure = StorePrefix::new("pressure/").unwrap();
+    assert_eq!(
+        AsyncListableStorageTraits::list(&store)
+            .await
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(
+        AsyncListableStorageTraits::list_prefix(&store, &pressure)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    store.erase(&key("temperature/c/0")).await.unwrap();
+    assert!(
+        AsyncReadableStorageTraits::get(&store, &key("temperature/c/0"))
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        AsyncListableStorageTraits::list(&store)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    store
+        .erase_prefix(&StorePrefix::new("temperature/").unwrap())
+        .await
+        .unwrap();
+    assert!(store.get_cached("temperature/c/1").await.is_none());
+    assert!(store.get_cached("pressure/c/0").await.is_some());
+    assert_eq!(
+        AsyncListableStorageTraits::list(&store)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    // Listings of other prefixes stay cached
+    let lists = store.inner().lists.load(Ordering::SeqCst);
+    AsyncListableStorageTraits::list_prefix(&store, &pressure)
+        .await
+        .unwrap();
+    assert_eq!(store.inner().lists.load(Ordering::SeqCst), lists);
+}
@@ -0,0 +1,186 @@
+use bytes::Bytes;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use zarrs_cache::trace::{read_trace, TraceReader};
+use zarrs_cache::{
+    Cache, CacheError, CacheEvent, CacheEventKind, CacheEventOutcome, EventLog, LruMemoryCache,
+    TraceOp, TraceRecord, TraceReplayer, TraceWriter, TracedCache,
+};
+
+fn record(op: TraceOp, key: &str, hit: bool, size: u64) -> TraceRecord {
+    TraceRecord {
+        at: Duration::ZERO,
+        op,
+        key: key.to_string(),
+        hit,
+        size,
+    }
+}
+
+#[tokio::test]
+async fn test_traced_cache_records_accesses() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("accesses.trace");
+    let writer = Arc::new(TraceWriter::create(&path).unwrap());
+    let cache = TracedCache::new(Arc::new(LruMemoryCache::new(1024 * 1024)), writer.clone());
+
+    let key = "temp/0.0".to_string();
+    assert!(cache.get(&key).await.is_none());
+    cache.set(&key, Bytes::from(vec![1u8; 300])).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    assert!(cache.get(&key).await.is_some());
+    cache.remove(&key).await.unwrap();
+    assert_eq!(writer.records(), 4);
+    writer.flush().unwrap();
+
+    let records = read_trace(&path).unwrap();
+    let ops: Vec<(TraceOp, bool, u64)> = records
+        .iter()
+        .map(|record| (record.op, record.hit, record.size))
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            (TraceOp::Get, false, 0),
+            (TraceOp::Set, false, 300),
+            (TraceOp::Get, true, 300),
+            (TraceOp::Remove, false, 0),
+        ]
+    );
+    assert!(records.iter().all(|record| record.key == key));
+    assert!(records[2].at >= records[1].at + Duration::from_millis(5));
+
+    // Repeated keys are written as numbers, so the trace stays small
+    let size = std::fs::metadata(&path).unwrap().len();
+    assert!(
+        size < 16 + 4 * 8 + key.len() as u64,
+        "trace of {} bytes",
+        size
+    );
+}
+
+#[tokio::test]
+async fn test_trace_records_event_log() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("events.trace");
+    let writer = Arc::new(TraceWriter::create(&path).unwrap());
+    let events = EventLog::new(16);
+    let recorder = writer.spawn_event_recorder(&events);
+
+    events.record(CacheEvent::new(
+        CacheEventKind::Get,
+        "a",
+        CacheEventOutcome::Miss,
+    ));
+    events.record(CacheEvent::new(CacheEventKind::Set, "a", CacheEventOutcome::Ok).with_size(10));
+    // Not an access
+    events.record(CacheEvent::new(
+        CacheEventKind::Demote,
+        "a",
+        CacheEventOutcome::Ok,
+    ));
+    events.record(CacheEvent::new(CacheEventKind::Get, "a", CacheEventOutcome::Hit).with_size(10));
+    drop(events);
+    recorder.await.unwrap();
+    writer.flush().unwrap();
+
+    let reader = TraceReader::open(&path).unwrap();
+    assert!(reader.started_at() <= std::time::SystemTime::now());
+    let records: Vec<TraceRecord> = reader.collect::<Result<_, _>>().unwrap();
+    let ops: Vec<(TraceOp, bool)> = records
+        .iter()
+        .map(|record| (record.op, record.hit))
+        .collect();
+    assert_eq!(
+        ops,
+        [
+            (TraceOp::Get, false),
+            (TraceOp::Set, false),
+            (TraceOp::Get, true)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_replay_reports_hit_rates() {
+    let mut records = Vec::new();
+    for key in ["a", "b", "c"] {
+        records.push(record(TraceOp::Get, key, false, 0));
+        records.push(record(TraceOp::Set, key, false, 400));
+    }
+    for key in ["a", "b", "c"] {
+        records.push(record(TraceOp::Get, key, true, 400));
+    }
+    records.push(record(TraceOp::Remove, "a", false, 0));
+
+    // Room for every entry replays the recorded hits
+    let report = TraceReplayer::new(records.clone())
+        .replay(&LruMemoryCache::new(1024 * 1024))
+        .await;
+    assert_eq!((report.gets, report.hits, report.recorded_hits), (6, 3, 3));
+    assert_eq!((report.sets, report.removes, report.failed_sets), (3, 1, 0));
+    assert_eq!(report.hit_bytes, 1200);
+    assert_eq!(report.hit_rate(), 0.5);
+    assert_eq!(report.recorded_hit_rate(), 0.5);
+
+    // Room for one entry misses on every read, and writes the values read
+    // back in, each evicting the one before
+    let small = LruMemoryCache::new(700);
+    let report = TraceReplayer::new(records.clone()).replay(&small).await;
+    assert_eq!(report.hits, 0);
+    assert_eq!(small.stats().removals.evicted, 5);
+
+    let small = LruMemoryCache::new(700);
+    TraceReplayer::new(records)
+        .with_read_through(false)
+        .replay(&small)
+        .await;
+    assert_eq!(small.stats().removals.evicted, 2);
+}
+
+#[tokio::test]
+async fn test_replay_keeps_recorded_pace() {
+    let records = vec![
+        record(TraceOp::Set, "a", false, 1),
+        TraceRecord {
+            at: Duration::from_millis(400),
+            ..record(TraceOp::Get, "a", true, 1)
+        },
+    ];
+    let report = TraceReplayer::new(records)
+        .with_speed(4.0)
+        .replay(&LruMemoryCache::new(1024))
+        .await;
+    assert_eq!(report.hits, 1);
+    assert!(report.elapsed >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_trace_reader_rejects_damaged_traces() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("damaged.trace");
+    let writer = TraceWriter::create(&path).unwrap();
+    writer.record(TraceOp::Set, "temp/0.0", false, 100).unwrap();
+    writer.record(TraceOp::Get, "temp/0.0", true, 100).unwrap();
+    drop(writer);
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(read_trace(&path).unwrap().len(), 2);
+
+    // A record cut short
+    std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+    let mut reader = TraceReader::open(&path).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(
+        reader.next(),
+        Some(Err(CacheError::Corruption(_)))
+    ));
+    assert!(reader.next().is_none());
+
+    // Another file format
+    std::fs::write(&path, b"not a trace file").unwrap();
+    assert!(matches!(
+        TraceReader::open(&path),
+        Err(CacheError::Corruption(_))
+    ));
+}
@@ -0,0 +1,383 @@
+use bytes::Bytes;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use zarrs_cache::{
+    BreakerState, Cache, CacheConfig, CacheError, CacheEventKind, CacheWarmer, CachedStore,
+    CircuitBreaker, CircuitBreakerConfig, EventLog, HybridCache, HybridCacheConfig, LruMemoryCache,
+    PrefetchConfig, PrefetchStrategy, RateLimitConfig, RateLimiter, RetryConfig, RetryPolicy,
+    SequentialPrefetch,
+};
+
+#[tokio::test]
+async fn test_rate_limiter_spaces_out_read_through_loads() {
+    // One request every 50ms, with no burst beyond a single request
+    let limiter = Arc::new(RateLimiter::new(
+        RateLimitConfig::builder()
+            .requests_per_second(20.0)
+            .burst("50ms")
+            .build()
+            .unwrap(),
+    ));
+    let store = CachedStore::new((), LruMemoryCache::new(1024 * 1024), CacheConfig::default())
+        .with_rate_limiter(limiter.clone());
+    let loads = Arc::new(AtomicUsize::new(0));
+    let loader = |key: String| {
+        let loads = loads.clone();
+        async move {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Some(Bytes::from(key))
+        }
+    };
+
+    let start = Instant::now();
+    for chunk in 0..5 {
+        let key = format!("temp/{}", chunk);
+        assert_eq!(
+            store.get_or_load(&key, loader).await,
+            Some(Bytes::from(key))
+        );
+    }
+    assert!(start.elapsed() >= Duration::from_millis(180));
+
+    // Hits do not go upstream
+    assert!(store.get_or_load("temp/0", loader).await.is_some());
+    assert_eq!(loads.load(Ordering::SeqCst), 5);
+
+    let stats = limiter.stats();
+    assert_eq!(stats.requests, 5);
+    assert_eq!(stats.bytes, 30);
+    assert!(stats.throttled >= 3);
+    assert!(stats.throttled_time >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_rate_limiter_caps_warming_bandwidth() {
+    // 10KB/s, saving up at most 1KB: each 1KB value after the first two
+    // waits for the one before it to be paid off
+    let limiter = Arc::new(RateLimiter::new(
+        RateLimitConfig::builder()
+            .bytes_per_second("10KB")
+            .burst("100ms")
+            .build()
+            .unwrap(),
+    ));
+    let cache = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let warmer = CacheWarmer::new(cache.clone())
+        .with_priority_keys(["a/0", "a/1", "a/2", "a/3"])
+        .with_rate_limiter(limiter.clone());
+
+    let start = Instant::now();
+    let warmed = warmer
+        .warm(|_key| async move { Some(Bytes::from(vec![0u8; 1000])) })
+        .await
+        .unwrap();
+    assert_eq!(warmed, 4);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+
+    let stats = limiter.stats();
+    assert_eq!((stats.requests, stats.bytes), (4, 4000));
+    assert!(stats.throttled >= 2);
+}
+
+#[tokio::test]
+async fn test_rate_limiter_counts_prefetch_loads() {
+    let limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+    let prefetch =
+        SequentialPrefetch::new(&PrefetchConfig::default()).with_rate_limiter(limiter.clone());
+    let cache = LruMemoryCache::new(1024 * 1024);
+    cache
+        .set("temp/0.1", Bytes::from_static(b"cached"))
+        .await
+        .unwrap();
+
+    let keys = prefetch.generate_prefetch_keys("temp/0.0");
+    assert_eq!(keys, ["temp/0.1", "temp/0.2"]);
+    prefetch
+        .prefetch(&cache, keys, |key| async move { Some(Bytes::from(key)) })
+        .await
+        .unwrap();
+
+    // Without limits loads are only counted; the cached key is not loaded
+    let stats = limiter.stats();
+    assert_eq!((stats.requests, stats.bytes, stats.throttled), (1, 8, 0));
+    assert!(cache.get("temp/0.2").await.is_some());
+}
+
+type Load = std::future::Ready<Result<Option<Bytes>, CacheError>>;
+
+/// Loader failing with `error` for the first `failures` attempts, and its
+/// count of attempts
+fn flaky_loader(
+    failures: usize,
+    error: fn() -> CacheError,
+) -> (
+    Arc<AtomicUsize>,
+    impl Fn(String) -> Load + Send + Sync + 'static,
+) {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let loader = move |key: String| {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        std::future::ready(if attempt < failures {
+            Err(error())
+        } else {
+            Ok(Some(Bytes::from(key)))
+        })
+    };
+    (attempts, loader)
+}
+
+fn unavailable() -> CacheError {
+    CacheError::backend("503 Slow Down")
+}
+
+fn quick_retries() -> RetryConfig {
+    RetryConfig::builder()
+        .initial_backoff("1ms")
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_retry_policy_recovers_transient_failures() {
+    let retry = Arc::new(RetryPolicy::new(quick_retries()));
+    let (attempts, loader) = flaky_loader(2, unavailable);
+    let cache = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let warmer = CacheWarmer::new(cache.clone()).with_priority_keys(["temp/.zarray"]);
+
+    assert_eq!(warmer.warm(retry.wrap(loader)).await.unwrap(), 1);
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert!(cache.get("temp/.zarray").await.is_some());
+
+    let stats = retry.stats();
+    assert_eq!(
+        (stats.loads, stats.retries, stats.recovered, stats.failed),
+        (1, 2, 1, 0)
+    );
+}
+
+#[tokio::test]
+async fn test_retry_policy_gives_up() {
+    // Out of attempts: waits 20ms, then 40ms, before the third and last
+    let retry = Arc::new(RetryPolicy::new(
+        RetryConfig::builder()
+            .initial_backoff("20ms")
+            .jitter(0.0)
+            .build()
+            .unwrap(),
+    ));
+    let (attempts, loader) = flaky_loader(usize::MAX, unavailable);
+    let store = CachedStore::new((), LruMemoryCache::new(1024 * 1024), CacheConfig::default());
+    let start = Instant::now();
+    assert!(store
+        .get_or_load("temp/0.0", retry.wrap(loader))
+        .await
+        .is_none());
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    // Not worth retrying
+    let (attempts, loader) = flaky_loader(1, || CacheError::InvalidKey("temp/0.0".to_string()));
+    assert!(matches!(
+        retry.load(loader, "temp/0.0".to_string()).await,
+        Err(CacheError::InvalidKey(_))
+    ));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+    let stats = retry.stats();
+    assert_eq!(
+        (stats.loads, stats.retries, stats.recovered, stats.failed),
+        (2, 2, 0, 2)
+    );
+}
+
+#[tokio::test]
+async fn test_retry_policy_classification_and_backoff() {
+    let retry = Arc::new(
+        RetryPolicy::new(quick_retries())
+            .with_retry_on(|error| matches!(error.root(), CacheError::Corruption(_))),
+    );
+    let (attempts, loader) = flaky_loader(1, || CacheError::Corruption("short read".to_string()));
+    let prefetch = SequentialPrefetch::new(&PrefetchConfig {
+        neighbor_chunks: 1,
+        ..Default::default()
+    });
+    let cache = LruMemoryCache::new(1024 * 1024);
+    let keys = prefetch.generate_prefetch_keys("temp/0.0");
+    prefetch
+        .prefetch(&cache, keys, retry.wrap(loader))
+        .await
+        .unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert!(cache.get("temp/0.1").await.is_some());
+
+    // Backend failures are no longer retried
+    let (attempts, loader) = flaky_loader(1, unavailable);
+    assert!(retry.load(loader, "temp/0.2".to_string()).await.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+    let backoff = RetryPolicy::new(
+        RetryConfig::builder()
+            .initial_backoff("100ms")
+            .max_backoff("1s")
+            .multiplier(3.0)
+            .jitter(0.0)
+            .build()
+            .unwrap(),
+    );
+    let waits: Vec<Duration> = (1..=4).map(|retry| backoff.backoff(retry)).collect();
+    assert_eq!(waits, [100, 300, 900, 1000].map(Duration::from_millis));
+
+    // Jitter only shortens the waits
+    let jittered = RetryPolicy::new(RetryConfig::default());
+    for _ in 0..20 {
+        let wait = jittered.backoff(2);
+        assert!(wait >= Duration::from_millis(100) && wait <= Duration::from_millis(200));
+    }
+}
+
+fn breaker_config(serve_stale: bool) -> CircuitBreakerConfig {
+    CircuitBreakerConfig::builder()
+        .failure_threshold(3)
+        .probe_interval("50ms")
+        .serve_stale(serve_stale)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_opens_and_probes_for_recovery() {
+    let events = Arc::new(EventLog::new(16));
+    let breaker =
+        Arc::new(CircuitBreaker::new(breaker_config(true)).with_event_log(events.clone()));
+    let (attempts, failing) = flaky_loader(usize::MAX, unavailable);
+
+    // Errors the store answered with are not failures
+    let (_, invalid) = flaky_loader(1, || CacheError::InvalidKey("temp".to_string()));
+    assert!(breaker.load(invalid, "temp".to_string()).await.is_err());
+    for chunk in 0..3 {
+        let result = breaker.load(&failing, format!("temp/{}", chunk)).await;
+        assert!(matches!(result, Err(CacheError::Backend(_))));
+    }
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    // Refused without a request to the store
+    let result = breaker.load(&failing, "temp/3".to_string()).await;
+    assert!(matches!(result, Err(CacheError::Unavailable(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    // A failed probe opens the breaker again
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(breaker.load(&failing, "temp/4".to_string()).await.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    // A successful one closes it
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let (_, healthy) = flaky_loader(0, unavailable);
+    let loader = breaker.wrap(healthy);
+    assert_eq!(
+        loader("temp/5".to_string()).await,
+        Some(Bytes::from("temp/5"))
+    );
+    assert_eq!(breaker.state(), BreakerState::Closed);
+
+    let stats = breaker.stats();
+    assert_eq!(stats.state, BreakerState::Closed);
+    assert_eq!(
+        (
+            stats.consecutive_failures,
+            stats.opened,
+            stats.rejected,
+            stats.probes
+        ),
+        (0, 2, 1, 2)
+    );
+    let transitions: Vec<(CacheEventKind, String)> = events
+        .recent()
+        .into_iter()
+        .map(|event| (event.kind, event.key))
+        .collect();
+    assert_eq!(
+        transitions,
+        [
+            (CacheEventKind::BreakerOpen, "temp/2".to_string()),
+            (CacheEventKind::BreakerProbe, "temp/4".to_string()),
+            (CacheEventKind::BreakerOpen, "temp/4".to_string()),
+            (CacheEventKind::BreakerProbe, "temp/5".to_string()),
+            (CacheEventKind::BreakerClose, "temp/5".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_serves_stale_copies() {
+    for serve_stale in [true, false] {
+        let breaker = Arc::new(CircuitBreaker::new(breaker_config(serve_stale)));
+        let retry = Arc::new(RetryPolicy::new(quick_retries()));
+        let (attempts, loader) = flaky_loader(usize::MAX, unavailable);
+        let loader = breaker.wrap(retry.try_wrap(loader));
+        let cache = LruMemoryCache::with_ttl(1024 * 1024, Some(Duration::from_millis(20)));
+        let store = CachedStore::new((), cache, CacheConfig::default())
+            .with_circuit_breaker(breaker.clone());
+        store
+            .set_cached("temp/0.0", Bytes::from_static(b"old"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Each load fails after all its retries, counting once
+        for chunk in 1..=3 {
+            let key = format!("temp/0.{}", chunk);
+            assert!(store.get_or_load(&key, &loader).await.is_none());
+        }
+        assert_eq!(attempts.load(Ordering::SeqCst), 9);
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let chunk = store.get_or_load("temp/0.0", &loader).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 9);
+        if serve_stale {
+            assert_eq!(chunk, Some(Bytes::from_static(b"old")));
+            assert_eq!(breaker.stats().stale_reads, 1);
+        } else {
+            assert!(chunk.is_none());
+            assert_eq!(breaker.stats().rejected, 1);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_circuit_breaker_serves_stale_hybrid_copies() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = HybridCache::new(HybridCacheConfig {
+        memory_size: 1024,
+        disk_dir: temp_dir.path().to_path_buf(),
+        ttl: Some(Duration::from_millis(20)),
+        ..Default::default()
+    })
+    .unwrap();
+    let breaker = Arc::new(CircuitBreaker::new(breaker_config(true)));
+    let (_, loader) = flaky_loader(usize::MAX, unavailable);
+    let loader = breaker.wrap(loader);
+    let store =
+        CachedStore::new((), cache, CacheConfig::default()).with_circuit_breaker(breaker.clone());
+
+    // One copy in both tiers, one too large for the memory tier
+    let small = Bytes::from_static(b"old");
+    let large = Bytes::from(vec![1u8; 4096]);
+    store.set_cached("temp/0.0", small.clone()).await.unwrap();
+    store.set_cached("temp/0.1", large.clone()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    for chunk in 2..5 {
+        let key = format!("temp/0.{}", chunk);
+        assert!(store.get_or_load(&key, &loader).await.is_none());
+    }
+    assert_eq!(breaker.state(), BreakerState::Open);
+
+    assert_eq!(store.get_or_load("temp/0.0", &loader).await, Some(small));
+    assert_eq!(store.get_or_load("temp/0.1", &loader).await, Some(large));
+    assert_eq!(breaker.stats().stale_reads, 2);
+}
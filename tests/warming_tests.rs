@@ -0,0 +1,295 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use zarrs_cache::{
+    Cache, CacheWarmer, DiskCache, LruMemoryCache, ManifestWarming, NeighborWarming, PeerWarming,
+    PredictiveWarming, TimeContext, WarmingBudget, WarmingContext, WarmingStrategy,
+};
+
+/// Warms the chunks of the next forecast cycle
+struct NextCycleWarming;
+
+#[async_trait::async_trait]
+impl WarmingStrategy for NextCycleWarming {
+    async fn generate_warming_keys(&self, _context: &WarmingContext) -> Vec<String> {
+        (0..3).map(|n| format!("forecast/t1/c/{n}")).collect()
+    }
+}
+
+#[tokio::test]
+async fn test_cache_warmer_with_custom_strategy() {
+    let cache = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let warmer = CacheWarmer::new(Arc::clone(&cache))
+        .add_strategy(NextCycleWarming)
+        .add_strategy(NeighborWarming::new(1, 8));
+
+    warmer.record_access("temperature/1.1").await;
+    let warmed = warmer
+        .warm(|key| async move { Some(Bytes::from(key)) })
+        .await
+        .unwrap();
+
+    // Three forecast chunks and the four neighbors of the accessed chunk
+    assert_eq!(warmed, 7);
+    assert_eq!(
+        cache.get("forecast/t1/c/2").await,
+        Some(Bytes::from("forecast/t1/c/2"))
+    );
+    assert!(cache.get("temperature/1.2").await.is_some());
+}
+
+#[tokio::test]
+async fn test_predictive_warming_history_survives_restart() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("history.json");
+
+    let warming = PredictiveWarming::new(10, 0.1);
+    warming.record_access("temperature/c/0").await;
+    warming.record_access("temperature/c/0").await;
+    warming.record_access("humidity/c/3").await;
+    warming.save_history(&path).await.unwrap();
+
+    let restarted = PredictiveWarming::new(10, 0.1);
+    assert_eq!(restarted.load_history(&path).await.unwrap(), 2);
+    let history = restarted.export_history().await;
+    assert_eq!(history["temperature/c/0"].len(), 2);
+    assert_eq!(history["humidity/c/3"].len(), 1);
+
+    // Nothing saved yet is not an error
+    let missing = temp_dir.path().join("missing.json");
+    assert_eq!(restarted.load_history(&missing).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_predictive_warming_matches_time_of_day() {
+    // 2024-01-01 00:00 UTC, a Monday
+    let start = 1_704_067_200;
+    let day = 24 * 60 * 60;
+    let hour = 60 * 60;
+
+    // Two weeks of daily reads: one key every morning, one every evening,
+    // one only on Saturdays at 09:00
+    let daily = |at_hour: u64| (0..14).map(|d| start + d * day + at_hour * hour).collect();
+    let saturdays = (0..3)
+        .map(|w| start + (5 + 7 * w) * day + 9 * hour)
+        .collect();
+    let warming = PredictiveWarming::new(10, 0.0);
+    warming
+        .import_history(HashMap::from([
+            ("morning".to_string(), daily(9)),
+            ("evening".to_string(), daily(21)),
+            ("saturday".to_string(), saturdays),
+        ]))
+        .await;
+
+    let context = |time_context| WarmingContext {
+        recent_access: HashMap::new(),
+        hit_rate: 0.0,
+        available_capacity: 0,
+        time_context,
+    };
+    // Tuesday 09:00 and 22:00, Saturday 10:00
+    let tuesday_morning = context(TimeContext::at_unix_secs(start + day + 9 * hour));
+    let tuesday_night = context(TimeContext::at_unix_secs(start + day + 22 * hour));
+    let saturday_morning = context(TimeContext::at_unix_secs(start + 5 * day + 10 * hour));
+
+    assert_eq!(
+        warming.generate_warming_keys(&tuesday_morning).await,
+        vec!["morning".to_string()]
+    );
+    assert_eq!(
+        warming.generate_warming_keys(&tuesday_night).await,
+        vec!["evening".to_string()]
+    );
+    let mut keys = warming.generate_warming_keys(&saturday_morning).await;
+    keys.sort();
+    assert_eq!(keys, vec!["morning".to_string(), "saturday".to_string()]);
+}
+
+#[tokio::test]
+async fn test_manifest_warming_keys() {
+    let listing = [
+        "temperature/c/0/0/0",
+        "temperature/c/0/1/0",
+        "temperature/c/1/0/0",
+        "temperature/c/0/0/0/extra",
+        "pressure/c/0/0/0",
+    ];
+    let warming = ManifestWarming::new()
+        .with_keys(["zarr.json"])
+        .with_glob("temperature/c/0/*/*", listing)
+        .with_coordinate_range("pressure/c/", &[0..1, 0..2, 0..1], '/')
+        .with_coordinate_range("humidity/", &[3..5, 0..1], '.')
+        // Duplicates and empty ranges add nothing
+        .with_keys(["temperature/c/0/0/0"])
+        .with_coordinate_range("empty/", &[0..2, 1..1], '/');
+
+    let context = WarmingContext {
+        recent_access: HashMap::new(),
+        hit_rate: 0.0,
+        available_capacity: 0,
+        time_context: TimeContext::current(),
+    };
+    assert_eq!(
+        warming.generate_warming_keys(&context).await,
+        vec![
+            "zarr.json",
+            "temperature/c/0/0/0",
+            "temperature/c/0/1/0",
+            "pressure/c/0/0/0",
+            "pressure/c/0/1/0",
+            "humidity/3.0",
+            "humidity/4.0",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_cache_warmer_respects_budget() {
+    let keys: Vec<String> = (0..10).map(|n| format!("temperature/c/{n}")).collect();
+    let loader = |_key: String| async { Some(Bytes::from(vec![0u8; 100])) };
+    let warm = |budget: WarmingBudget| {
+        let keys = keys.clone();
+        async move {
+            let cache = Arc::new(LruMemoryCache::new(100_000));
+            CacheWarmer::new(Arc::clone(&cache))
+                .add_strategy(ManifestWarming::new().with_keys(keys))
+                .with_budget(budget)
+                .warm(loader)
+                .await
+                .unwrap()
+        }
+    };
+
+    assert_eq!(warm(WarmingBudget::default()).await, 10);
+    let bytes = WarmingBudget {
+        max_bytes: Some(450),
+        ..Default::default()
+    };
+    assert_eq!(warm(bytes).await, 4);
+    // 0.25% of 100 kB
+    let fraction = WarmingBudget {
+        max_capacity_fraction: Some(0.0025),
+        ..Default::default()
+    };
+    assert_eq!(warm(fraction).await, 2);
+    let both = WarmingBudget {
+        max_bytes: Some(150),
+        max_capacity_fraction: Some(0.5),
+        ..Default::default()
+    };
+    assert_eq!(warm(both).await, 1);
+    let no_time = WarmingBudget {
+        max_duration: Some(std::time::Duration::ZERO),
+        ..Default::default()
+    };
+    assert_eq!(warm(no_time).await, 0);
+}
+
+#[tokio::test]
+async fn test_cache_warmer_refreshes_priority_keys_first() {
+    let cache = Arc::new(LruMemoryCache::new(100_000));
+    cache
+        .set("temperature/zarr.json", Bytes::from("stale"))
+        .await
+        .unwrap();
+
+    let predicted: Vec<String> = (0..5).map(|n| format!("temperature/c/{n}")).collect();
+    let warmer = CacheWarmer::new(Arc::clone(&cache))
+        .with_priority_keys(["temperature/zarr.json", "time/c/0"])
+        .add_strategy(ManifestWarming::new().with_keys(predicted))
+        .with_budget(WarmingBudget {
+            max_bytes: Some(500),
+            ..Default::default()
+        });
+
+    // Priority keys take 200 of the 500 bytes, leaving room for three more
+    let warmed = warmer
+        .warm(|_key| async { Some(Bytes::from(vec![1u8; 100])) })
+        .await
+        .unwrap();
+    assert_eq!(warmed, 5);
+    assert_eq!(
+        cache.get("temperature/zarr.json").await,
+        Some(Bytes::from(vec![1u8; 100]))
+    );
+    assert!(cache.get("time/c/0").await.is_some());
+    assert!(cache.get("temperature/c/2").await.is_some());
+    assert!(cache.get("temperature/c/3").await.is_none());
+
+    // Priority keys are refreshed even when they exceed the budget
+    let warmer = CacheWarmer::new(Arc::clone(&cache))
+        .with_priority_keys(["temperature/zarr.json"])
+        .with_budget(WarmingBudget {
+            max_bytes: Some(10),
+            ..Default::default()
+        });
+    let warmed = warmer
+        .warm(|_key| async { Some(Bytes::from(vec![2u8; 100])) })
+        .await
+        .unwrap();
+    assert_eq!(warmed, 1);
+    assert_eq!(
+        cache.get("temperature/zarr.json").await,
+        Some(Bytes::from(vec![2u8; 100]))
+    );
+}
+
+#[tokio::test]
+async fn test_predictive_warming_forecasts_rising_keys() {
+    // 2024-01-01 00:00 UTC, a Monday
+    let start = 1_704_067_200;
+    let hour = 60 * 60;
+
+    // As many accesses each, but "fading" was read heavily on the first day
+    // only while "rising" is read steadily through the next two days
+    let fading = (0..96).map(|i| start + i * hour / 4).collect();
+    let rising = (0..96).map(|i| start + 24 * hour + i * hour / 2).collect();
+    let warming = PredictiveWarming::new(10, 0.0);
+    warming
+        .import_history(HashMap::from([
+            ("fading".to_string(), fading),
+            ("rising".to_string(), rising),
+        ]))
+        .await;
+
+    let context = WarmingContext {
+        recent_access: HashMap::new(),
+        hit_rate: 0.0,
+        available_capacity: 0,
+        time_context: TimeContext::at_unix_secs(start + 72 * hour),
+    };
+    assert_eq!(
+        warming.generate_warming_keys(&context).await,
+        vec!["rising".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_cache_warmer_clones_hot_keys_from_peer() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let peer = Arc::new(DiskCache::new(temp_dir.path().to_path_buf(), None).unwrap());
+    for n in 0..5 {
+        let key = format!("temperature/c/{n}");
+        peer.set(&key, Bytes::from(key.clone())).await.unwrap();
+    }
+    // The teammate kept reading the first two chunks
+    for _ in 0..3 {
+        peer.get("temperature/c/0").await;
+        peer.get("temperature/c/1").await;
+    }
+
+    let cache = Arc::new(LruMemoryCache::new(1024 * 1024));
+    let warmer = CacheWarmer::new(Arc::clone(&cache))
+        .with_priority_keys(["temperature/zarr.json"])
+        .add_strategy(PeerWarming::new(Arc::clone(&peer), 2));
+
+    // The peer lacks the metadata key, so only its two hot chunks are copied
+    assert_eq!(warmer.warm_from(&peer).await.unwrap(), 2);
+    assert_eq!(
+        cache.get("temperature/c/0").await,
+        Some(Bytes::from("temperature/c/0"))
+    );
+    assert!(cache.get("temperature/c/1").await.is_some());
+    assert!(cache.get("temperature/c/2").await.is_none());
+}